@@ -0,0 +1,338 @@
+// airlock.rs
+// Provides the airlocks, which run their pair of doors through a cycle from a control panel so that both sides of the
+// chamber are never open at once
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::event::ManualEventReader;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::RunState;
+use crate::engine::event::*;
+use crate::engine::event::ActionType::{CloseItem, OpenItem, UseItem};
+use crate::engine::event::GameEventType::{ActorAction, PlayerAction};
+use crate::engine::messagelog::MessageLog;
+use crate::power::PowerGrid;
+use crate::worldmap::WorldModel;
+
+// ###: CONSTANTS
+/// The name of the airlock's control panel as it's known to the ItemBuilder
+pub const AIRLOCK_PANEL_ITEM: &str = "airlock panel";
+/// How many turns the panel waits for a door to move before it decides that the door isn't going to
+pub const DOOR_PATIENCE: u64 = 4;
+
+// ###: COMPLEX TYPES
+//  ##: AirlockLink
+/// Names an airlock chamber and the two doorways that its panel runs, as the ship's plans give them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AirlockLink {
+	pub room: String,
+	pub inner: Position, // The doorway on the ship's side of the chamber
+	pub outer: Position, // The doorway that opens onto whatever's outside
+}
+
+// ###: BEVY SYSTEMS
+/// Starts an airlock cycle whenever someone uses an airlock's panel, and moves any cycles in progress along
+/// The panel works the doors with the same ActorActions that anyone else would use, so a door that's blocked, locked,
+/// or stuck stops the cycle just as it would stop a person, and the panel reports why
+pub fn airlock_system(mut events:  ResMut<Events<GameEvent>>,
+	                    mut ereader: Local<ManualEventReader<GameEvent>>,
+	                    mut msglog:  ResMut<MessageLog>,
+	                    grid:        Res<PowerGrid>,
+	                    model:       Res<WorldModel>,
+	                    run_state:   Res<RunState>,
+	                    mut a_query: Query<(Entity, &Body, &Description, &Device, &mut Airlock)>,
+	                    d_query:     Query<(&Body, &Description, &Openable)>,
+	                    o_query:     Query<&Body, Without<Airlock>>,
+	                    p_query:     Query<Entity, With<Player>>,
+) {
+	let turn = run_state.turn_count;
+	let player = p_query.get_single().unwrap_or(Entity::PLACEHOLDER);
+	let is_open = |door: Entity| d_query.get(door).map_or(false, |x| x.2.is_open);
+	let door_name = |door: Entity| d_query.get(door).map_or("door".to_string(), |x| x.1.name.clone());
+	// Start a new cycle for anyone who's just used a panel
+	let requests: Vec<GameEvent> = ereader.iter(&events).copied().collect();
+	for event in requests.iter() {
+		if !matches!(event.etype, PlayerAction(UseItem) | ActorAction(UseItem)) { continue; }
+		let econtext = if let Some(value) = event.context { value } else { continue };
+		let (a_enty, a_body, a_desc, a_device, mut a_airlock) = if let Ok(value) = a_query.get_mut(econtext.object) { value } else { continue };
+		let tell = econtext.subject == player;
+		// The operable_system has already complained about a panel that's broken down
+		if matches!(a_device.state, DeviceState::Error(_)) { continue; }
+		if !has_power(a_body, a_device, &grid, &model) {
+			if tell { msglog.tell_player(format!("The {} is dark; there's no power to this part of the ship.", a_desc.name).as_str()); }
+			continue;
+		}
+		if !a_airlock.is_linked() {
+			if tell { msglog.tell_player(format!("The {} beeps at you: [[fg:red]]NO DOORS CONNECTED.[[end]]", a_desc.name).as_str()); }
+			continue;
+		}
+		if a_airlock.cycle != AirlockCycle::Idle {
+			if tell { msglog.tell_player(format!("The {} is already cycling.", a_desc.name).as_str()); }
+			continue;
+		}
+		let (close, open) = match (is_open(a_airlock.inner), is_open(a_airlock.outer)) {
+			(true, true) => {
+				if tell { msglog.tell_player(format!("The {} refuses to cycle while both doors are open.", a_desc.name).as_str()); }
+				continue;
+			}
+			(true, false) => { (a_airlock.inner, a_airlock.outer) }
+			(false, true) => { (a_airlock.outer, a_airlock.inner) }
+			(false, false) => {
+				// With both doors shut, whoever's at the panel presumably wants the door that they aren't next to
+				let o_posn = o_query.get(econtext.subject).map_or(a_body.ref_posn, |x| x.ref_posn);
+				let reach = |door: Entity| d_query.get(door).map_or(0, |x| distance_sq(o_posn, x.0.ref_posn));
+				if reach(a_airlock.inner) > reach(a_airlock.outer) {
+					(a_airlock.outer, a_airlock.inner)
+				} else {
+					(a_airlock.inner, a_airlock.outer)
+				}
+			}
+		};
+		a_airlock.operator = econtext.subject;
+		if is_open(close) {
+			events.send(GameEvent::new(ActorAction(CloseItem), Some(a_enty), Some(close)));
+			a_airlock.cycle = AirlockCycle::Closing { door: close, then: open, since: turn };
+		} else {
+			a_airlock.cycle = AirlockCycle::Equalizing { then: open, until: turn + a_airlock.equalize_turns };
+		}
+		if tell { msglog.tell_player(format!("The {} chirps and begins to cycle.", a_desc.name).as_str()); }
+	}
+	// Move along any cycles that are already underway
+	for (a_enty, a_body, a_desc, a_device, mut a_airlock) in a_query.iter_mut() {
+		if a_airlock.cycle == AirlockCycle::Idle { continue; }
+		let tell = a_airlock.operator == player;
+		if matches!(a_device.state, DeviceState::Error(_)) || !has_power(a_body, a_device, &grid, &model) {
+			if tell { msglog.tell_player(format!("The {} goes dark partway through the cycle.", a_desc.name).as_str()); }
+			a_airlock.cycle = AirlockCycle::Idle;
+			continue;
+		}
+		match a_airlock.cycle {
+			AirlockCycle::Idle => { }
+			AirlockCycle::Closing { door, then, since } => {
+				if !is_open(door) {
+					if tell { msglog.tell_player("Air hisses through the vents as the chamber equalizes."); }
+					a_airlock.cycle = AirlockCycle::Equalizing { then, until: turn + a_airlock.equalize_turns };
+				} else if turn >= since + DOOR_PATIENCE {
+					if tell {
+						let reason = if is_doorway_occupied(door, &d_query, &model) {
+							format!("Something in the doorway is stopping the {} from closing.", door_name(door))
+						} else {
+							format!("The {} won't close.", door_name(door))
+						};
+						msglog.tell_player(format!("{} The {} aborts the cycle.", reason, a_desc.name).as_str());
+					}
+					a_airlock.cycle = AirlockCycle::Idle;
+				}
+			}
+			AirlockCycle::Equalizing { then, until } => {
+				if turn < until { continue; }
+				// Someone might have opened the other door by hand in the meantime, which spoils the seal
+				if is_open(a_airlock.other_door(then)) {
+					if tell { msglog.tell_player(format!("The {} lost its seal. The {} aborts the cycle.", door_name(a_airlock.other_door(then)), a_desc.name).as_str()); }
+					a_airlock.cycle = AirlockCycle::Idle;
+					continue;
+				}
+				events.send(GameEvent::new(ActorAction(OpenItem), Some(a_enty), Some(then)));
+				a_airlock.cycle = AirlockCycle::Opening { door: then, since: turn };
+			}
+			AirlockCycle::Opening { door, since } => {
+				if is_open(door) {
+					if tell { msglog.tell_player(format!("The {} chimes: cycle complete.", a_desc.name).as_str()); }
+					a_airlock.cycle = AirlockCycle::Idle;
+				} else if turn >= since + DOOR_PATIENCE {
+					if tell { msglog.tell_player(format!("The {} won't open. The {} aborts the cycle.", door_name(door), a_desc.name).as_str()); }
+					a_airlock.cycle = AirlockCycle::Idle;
+				}
+			}
+		}
+	}
+}
+
+// ###: METHODS
+/// Links every airlock panel to the doors that the ship's plans say its chamber has; this needs the doors to have been
+/// spawned and given their wall pockets already, since each door is found by the doorway that it shuts
+pub fn link_airlocks(world: &mut World, model: &WorldModel, links: &[AirlockLink]) {
+	let mut door_query = world.query_filtered::<(Entity, &Body, &Openable), Without<Container>>();
+	let doors: Vec<(Entity, Position)> = door_query.iter(world)
+		.map(|(d_enty, d_body, d_open)| (d_enty, d_open.doorway(d_body.ref_posn)))
+		.collect();
+	let door_at = |doorway: Position| doors.iter().find(|x| x.1 == doorway).map(|x| x.0);
+	let mut panel_query = world.query::<(&Body, &Description, &mut Airlock)>();
+	for (a_body, a_desc, mut a_airlock) in panel_query.iter_mut(world) {
+		if a_airlock.is_linked() { continue; }
+		let room = if let Some(value) = model.layout.get_room_name(a_body.ref_posn) { value } else {
+			warn!("* the {} at {} is not inside any room, so it has no doors to run", a_desc.name, a_body.ref_posn); // DEBUG: report an orphaned airlock panel
+			continue;
+		};
+		let link = if let Some(value) = links.iter().find(|x| x.room == room) { value } else {
+			warn!("* the ship's plans don't list any airlock doors for the {}", room); // DEBUG: report an airlock missing from the map data
+			continue;
+		};
+		match (door_at(link.inner), door_at(link.outer)) {
+			(Some(inner), Some(outer)) => {
+				a_airlock.inner = inner;
+				a_airlock.outer = outer;
+			}
+			(inner, outer) => {
+				warn!("* the {} is missing its doors: inner {:?} at {}, outer {:?} at {}", room, inner, link.inner, outer, link.outer); // DEBUG: report an airlock without its doors
+			}
+		}
+	}
+}
+
+// ###: UTILITIES
+/// Returns true if the given device has the power that it needs to run, whether that's its own or the mains
+fn has_power(body: &Body, device: &Device, grid: &PowerGrid, model: &WorldModel) -> bool {
+	if !device.requires_mains { return true; }
+	model.layout.get_room_name(body.ref_posn).map_or(true, |x| grid.is_powered(&x))
+}
+/// Returns true if there's anything besides the door itself in the door's doorway
+fn is_doorway_occupied(door: Entity, d_query: &Query<(&Body, &Description, &Openable)>, model: &WorldModel) -> bool {
+	let (d_body, _, d_open) = if let Ok(value) = d_query.get(door) { value } else { return false };
+	let doorway = if d_open.slide_into.is_some() { d_open.home } else { d_body.ref_posn };
+	model.get_contents_at(doorway).iter().any(|x| *x != door)
+}
+/// Gives the squared straight-line distance between two Positions, which is plenty for comparing them
+fn distance_sq(alpha: Position, beta: Position) -> i32 {
+	(alpha.x - beta.x).pow(2) + (alpha.y - beta.y).pow(2)
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::engine::messagelog::PerceptionPolicy;
+	use crate::sys::{openable_system, operable_system};
+	use crate::testmap::*;
+	use super::*;
+	//  ##: Setup
+	/// The doorways in the AIRLOCK_CHAMBER layout, and where the plans say that they go
+	fn chamber_link() -> AirlockLink {
+		AirlockLink { room: "airlock".to_string(), inner: Position::new(4, 1, 0), outer: Position::new(7, 1, 0) }
+	}
+	/// Puts a sliding door in the given doorway, open or shut, and indexes it on the map the same way a new game would
+	fn spawn_door(world: &mut World, model: &mut WorldModel, name: &str, doorway: Position, is_open: bool) -> Entity {
+		let pocket = model.find_door_pocket(doorway).expect("the test doorway should have a wall pocket");
+		let mut d_open = Openable { is_open, ..Openable::default() };
+		d_open.slides(doorway, pocket);
+		let posn = if is_open { pocket } else { doorway };
+		let mut d_enty = world.spawn((Description::new().name(name), Body::small(posn, ScreenCell::new()), d_open));
+		if !is_open { d_enty.insert(Obstructive::default()); }
+		let d_enty = d_enty.id();
+		model.add_contents(&vec![posn], 0, d_enty);
+		d_enty
+	}
+	/// Builds the airlock chamber with its panel linked up from the plans, and the player standing in the corridor by
+	/// the inner door; returns the app, the player, the panel, and the inner and outer doors
+	fn airlock_app(inner_open: bool) -> (App, Entity, Entity, Entity, Entity) {
+		let mut model = TestMap::new(AIRLOCK_CHAMBER)
+			.with_room("corridor", Position::new(1, 1, 0), Position::new(3, 1, 0))
+			.with_room("airlock", Position::new(5, 1, 0), Position::new(6, 1, 0))
+			.model;
+		let mut app = App::new();
+		let inner = spawn_door(&mut app.world, &mut model, "inner door", Position::new(4, 1, 0), inner_open);
+		let outer = spawn_door(&mut app.world, &mut model, "outer door", Position::new(7, 1, 0), false);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(3, 1, 0), ScreenCell::new()))).id();
+		let panel = app.world.spawn((Description::new().name(AIRLOCK_PANEL_ITEM), Body::small(Position::new(5, 1, 0), ScreenCell::new()), Device::new(0), Airlock::default())).id();
+		link_airlocks(&mut app.world, &model, &[chamber_link()]);
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.add_event::<DevicePowerChanged>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(model)
+		.insert_resource(RunState::new())
+		.insert_resource(PowerGrid::default())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(Position::new(3, 1, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (operable_system, airlock_system, openable_system).chain());
+		(app, player, panel, inner, outer)
+	}
+	/// Moves the turn clock on to the given turn and runs the systems once
+	fn run_turn(app: &mut App, turn: u64) {
+		app.world.resource_mut::<RunState>().turn_count = turn;
+		app.update();
+	}
+	fn is_open(app: &App, door: Entity) -> bool {
+		app.world.get::<Openable>(door).map_or(false, |x| x.is_open)
+	}
+	fn last_message(app: &App) -> String {
+		app.world.resource::<MessageLog>().last_text("world").unwrap_or_default().to_string()
+	}
+	//  ##: Linking
+	#[test]
+	fn the_panel_is_linked_from_the_plans() {
+		let (app, _, panel, inner, outer) = airlock_app(false);
+		let airlock = app.world.get::<Airlock>(panel).unwrap();
+		assert_eq!(airlock.inner, inner);
+		assert_eq!(airlock.outer, outer);
+	}
+	#[test]
+	fn a_chamber_missing_from_the_plans_stays_unlinked() {
+		let mut model = TestMap::new(AIRLOCK_CHAMBER).with_room("airlock", Position::new(5, 1, 0), Position::new(6, 1, 0)).model;
+		let mut world = World::new();
+		spawn_door(&mut world, &mut model, "inner door", Position::new(4, 1, 0), false);
+		spawn_door(&mut world, &mut model, "outer door", Position::new(7, 1, 0), false);
+		let panel = world.spawn((Description::new().name(AIRLOCK_PANEL_ITEM), Body::small(Position::new(5, 1, 0), ScreenCell::new()), Airlock::default())).id();
+		// Plans for some other chamber don't count, however the doors around this one happen to be laid out
+		let elsewhere = AirlockLink { room: "cargo lock".to_string(), ..chamber_link() };
+		link_airlocks(&mut world, &model, &[elsewhere]);
+		assert!(!world.get::<Airlock>(panel).unwrap().is_linked());
+	}
+	//  ##: Cycling
+	#[test]
+	fn a_full_cycle_opens_the_far_door() {
+		let (mut app, player, panel, inner, outer) = airlock_app(true);
+		app.world.send_event(GameEvent::new(PlayerAction(UseItem), Some(player), Some(panel)));
+		run_turn(&mut app, 0);
+		// The inner door is shut first
+		assert!(!is_open(&app, inner));
+		run_turn(&mut app, 1);
+		assert!(matches!(app.world.get::<Airlock>(panel).unwrap().cycle, AirlockCycle::Equalizing { .. }));
+		// Then the chamber equalizes, and the outer door opens
+		run_turn(&mut app, 1 + Airlock::EQUALIZE_TURNS);
+		assert!(is_open(&app, outer));
+		run_turn(&mut app, 2 + Airlock::EQUALIZE_TURNS);
+		assert_eq!(app.world.get::<Airlock>(panel).unwrap().cycle, AirlockCycle::Idle);
+		assert!(last_message(&app).contains("cycle complete"), "{}", last_message(&app));
+	}
+	#[test]
+	fn someone_in_the_doorway_aborts_the_cycle() {
+		let (mut app, player, panel, inner, _) = airlock_app(true);
+		let crate_enty = app.world.spawn((Description::new().name("crate"), Body::small(Position::new(4, 1, 0), ScreenCell::new()))).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![Position::new(4, 1, 0)], 0, crate_enty);
+		app.world.send_event(GameEvent::new(PlayerAction(UseItem), Some(player), Some(panel)));
+		run_turn(&mut app, 0);
+		assert!(is_open(&app, inner));
+		run_turn(&mut app, DOOR_PATIENCE);
+		assert_eq!(app.world.get::<Airlock>(panel).unwrap().cycle, AirlockCycle::Idle);
+		assert!(last_message(&app).contains("Something in the doorway"), "{}", last_message(&app));
+	}
+	#[test]
+	fn both_doors_open_refuses_to_cycle() {
+		let (mut app, player, panel, _, outer) = airlock_app(true);
+		app.world.send_event(GameEvent::new(ActorAction(OpenItem), Some(panel), Some(outer)));
+		run_turn(&mut app, 0);
+		assert!(is_open(&app, outer));
+		app.world.send_event(GameEvent::new(PlayerAction(UseItem), Some(player), Some(panel)));
+		run_turn(&mut app, 1);
+		assert_eq!(app.world.get::<Airlock>(panel).unwrap().cycle, AirlockCycle::Idle);
+		assert!(last_message(&app).contains("both doors are open"), "{}", last_message(&app));
+	}
+	#[test]
+	fn using_the_panel_costs_it_no_power_or_wear() {
+		let (mut app, player, panel, _, _) = airlock_app(false);
+		for turn in 0..Device::WEAR_LIMIT as u64 {
+			app.world.send_event(GameEvent::new(PlayerAction(UseItem), Some(player), Some(panel)));
+			run_turn(&mut app, turn);
+		}
+		let device = app.world.get::<Device>(panel).unwrap();
+		assert!(!device.pw_switch);
+		assert_eq!(device.wear, 0);
+		assert!(!matches!(device.state, DeviceState::Error(_)));
+	}
+}
+
+// EOF
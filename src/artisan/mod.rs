@@ -41,14 +41,21 @@
  *   Mobile
  *   Networkable
  *   Obstructive
+ *   Workbench
  * COMPLEX:
- *   Device(discharge rate in volts/turn as i32)
+ *   AnimatedRenderable(comma-separated list of glyphs to cycle through, turns per frame as u32)
+ *   Device(discharge rate in volts/turn as i32, whether it runs off the ship's mains power as bool)
  *   Key(key id as i32)
- *   Lockable(initial state as bool, matching key id as i32)
+ *   LightSource(radius of the lit area as i32, initial state as bool)
+ *   LazyLoot(comma-separated list of item names, spawned inside the container when it's first opened)
+ *   Lockable(initial state as bool, matching key id as i32, keypad code as i32, shipnet access as bool, hacking difficulty as i32)
  *   Opaque(current state as bool)
  *   Openable(initial state as bool, open/closed glyphs)
  *   Portable(carrier of item as Entity)
+ *   ReachProfile(extra tiles of reach as i32, for its owner or whoever carries it)
+ *   Unidentified(the generic name and desc to show until the item is identified)
  *   Viewshed(range in tiles as i32)
+ *   Weight(mass as i32)
  */
 
 // CLIPPY SHUT UPPPPPPPPPP
@@ -74,6 +81,7 @@ use bevy_turborand::*;
 use crate::components::*;
 use crate::planq::*;
 use crate::mason::logical_map::SpawnTemplate;
+use crate::salvage::{appraise, salvage_bin_template, SALVAGE_BIN_ITEM};
 
 //  ###: COMPLEX TYPES
 //   ##: THE ITEM BUILDER
@@ -89,10 +97,15 @@ pub struct ItemBuilder {
 	actions:  Option<ActionSet>,
 	// Optional/auxiliary components
 	access:   Option<AccessPort>,
+	airlock:  Option<Airlock>,
+	animated: Option<AnimatedRenderable>,
 	contain:  Option<Container>,
 	device:   Option<Device>,
 	is_carried: Option<IsCarried>,
 	key:      Option<Key>,
+	lazy_loot: Option<LazyLoot>,
+	light:    Option<LightSource>,
+	weight:   Option<Weight>,
 	lock:     Option<Lockable>,
 	mobile:   Option<Mobile>,
 	network:  Option<Networkable>,
@@ -101,6 +114,11 @@ pub struct ItemBuilder {
 	open:     Option<Openable>,
 	portable: Option<Portable>,
 	planq:    Option<Planq>,
+	reach:    Option<ReachProfile>,
+	salvage:  Option<Salvage>,
+	salvage_bin: Option<SalvageBin>,
+	disguise: Option<Description>, // If set, the item spawns Unidentified and wears this Description instead
+	workbench: Option<Workbench>,
 	#[reflect(ignore)]
 	item_dict:     ItemDict,
 }
@@ -138,6 +156,16 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 					match part {
 						"accessport"  => { self.access = Some(AccessPort::default()); } // tag component
 						"actionset"   => { self.actions = Some(ActionSet::default()); } // tag component
+						"airlock"     => {
+							let mut new_airlock = Airlock::default(); // the doors are linked up once the map is built
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									if key == "turns" { new_airlock.equalize_turns = value.parse().expect(&(error_msg.to_owned() + "airlock:turns")); }
+									else { warn!("* component key:value {}:{} was not recognized", key, value); }
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.airlock = Some(new_airlock);
+						}
 						"container"   => { self.contain = Some(Container::default()); } // tag component for now
 						"description" => {
 							let mut new_desc = Description::new();
@@ -160,6 +188,7 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 										"state" => { new_device.pw_switch = value.parse().expect(&(error_msg.to_owned() + "device:state")); }
 										"voltage" => { new_device.batt_voltage = value.parse().expect(&(error_msg.to_owned() + "device:voltage")); }
 										"rate" => { new_device.batt_discharge = value.parse().expect(&(error_msg.to_owned() + "device:rate")); }
+										"mains" => { new_device.requires_mains = value.parse().expect(&(error_msg.to_owned() + "device:mains")); }
 										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
 									}
 								} else { warn!("* could not split key:value on component {}", part); }
@@ -176,6 +205,66 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 							}
 							self.key = Some(new_key);
 						}
+						"lazyloot"    => {
+							let mut new_loot = LazyLoot::default();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									if key == "items" { new_loot.items = value.split(',').map(|x| x.to_string()).collect(); }
+									else { warn!("* component key:value {}:{} was not recognized", key, value); }
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.lazy_loot = Some(new_loot);
+						}
+						"animated"    => {
+							let mut new_anim = AnimatedRenderable::default();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									match key {
+										"frames" => { new_anim.frames = value.split(',').map(|x| x.to_string()).collect(); }
+										"interval" => { new_anim.interval = value.parse().expect(&(error_msg.to_owned() + "animated:interval")); }
+										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
+									}
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.animated = Some(new_anim);
+						}
+						"lightsource" => {
+							let mut new_light = LightSource::new(0);
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									match key {
+										"radius" => { new_light.radius = value.parse().expect(&(error_msg.to_owned() + "lightsource:radius")); }
+										"state" => { new_light.is_on = value.parse().expect(&(error_msg.to_owned() + "lightsource:state")); }
+										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
+									}
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.light = Some(new_light);
+						}
+						"weight"      => {
+							let mut new_weight = Weight::default();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									if key == "mass" { new_weight.mass = value.parse().expect(&(error_msg.to_owned() + "weight:mass")); }
+									else { warn!("* component key:value {}:{} was not recognized", key, value); }
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.weight = Some(new_weight);
+						}
+						"unidentified" => {
+							let mut new_disguise = Description::new();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									match key {
+										"name" => { new_disguise.name = value.to_string(); }
+										"desc" => { new_disguise.desc = value.to_string(); }
+										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
+									}
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.disguise = Some(new_disguise);
+						}
+						"workbench"   => { self.workbench = Some(Workbench::default()); } // tag component
 						"lockable"    => {
 							let mut new_lock = Lockable::default();
 							for string in details.iter() {
@@ -183,6 +272,9 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 									match key {
 										"state" => { new_lock.is_locked = value.parse().expect(&(error_msg.to_owned() + "lockable:state")); }
 										"key_id" => { new_lock.key_id = value.parse().expect(&(error_msg.to_owned() + "lockable:key_id")); }
+										"code" => { new_lock.code = value.parse().expect(&(error_msg.to_owned() + "lockable:code")); }
+										"remote" => { new_lock.remote = value.parse().expect(&(error_msg.to_owned() + "lockable:remote")); }
+										"difficulty" => { new_lock.difficulty = value.parse().expect(&(error_msg.to_owned() + "lockable:difficulty")); }
 										_ => { warn!("* component key:value {}:{} was not recognized", key, value); }
 									}
 								} else { warn!("* could not split key:value on component {}", part); }
@@ -224,10 +316,36 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 							self.open = Some(new_open);
 						}
 						"portable"    => { self.portable = Some(Portable::empty()); } // the Entity field cannot be specified before runtime
+						"reach"       => {
+							let mut new_reach = ReachProfile::default();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									if key == "extra" { new_reach.extra = value.parse().expect(&(error_msg.to_owned() + "reach:extra")); }
+									else { warn!("* component key:value {}:{} was not recognized", key, value); }
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.reach = Some(new_reach);
+						}
+						"salvage"     => {
+							let mut new_salvage = Salvage::default();
+							for string in details.iter() {
+								if let Some((key, value)) = string.split_once(':') {
+									if key == "value" { new_salvage.value = value.parse().expect(&(error_msg.to_owned() + "salvage:value")); }
+									else { warn!("* component key:value {}:{} was not recognized", key, value); }
+								} else { warn!("* could not split key:value on component {}", part); }
+							}
+							self.salvage = Some(new_salvage);
+						}
+						"salvagebin"  => { self.salvage_bin = Some(SalvageBin::default()); } // tag component
 						_ => { error!("! ERR: requested component {} was not recognized", component); }
 					}
 				}
 			}
+			// Anything that the template didn't put a price on gets appraised by what it's made of
+			if self.salvage.is_none() {
+				let value = appraise(self.portable.is_some(), self.device.is_some(), self.light.is_some());
+				if value > 0 { self.salvage = Some(Salvage { value }); }
+			}
 		}
 		/*
 		 * else if let Some(set_data) = self.item_dict.sets.iter().find(|x| x.name == new_item) {
@@ -270,24 +388,53 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 		}
 		self
 	}
+	/// Fits the item with a lock that starts out locked, and that opens with the given key_id
+	pub fn locked_with(&mut self, key_id: i32) -> &mut ItemBuilder {
+		let mut new_lock = self.lock.unwrap_or_default();
+		new_lock.is_locked = true;
+		new_lock.key_id = key_id;
+		self.lock = Some(new_lock);
+		self
+	}
 	/// Constructs the item into the specified Bevy::App, and returns the generated Entity ID as well as the full set
 	/// of Positions, aka the Body.extent, aka the item's shape, that the item occupies on the map
 	pub fn build(&'b mut self, world: &'a mut World) -> Vec<(EntityMut<'b>, Vec<Position>)> {
 		self.spawn_count += 1;
 		let mut item_shape = Vec::new();
+		let deck = self.body.as_ref().map_or(0, |x| x.ref_posn.z);
 		let mut new_item = world.spawn_empty();
+		// The real name is the template's name, even for an item that's wearing a disguise
+		if let Some(desc)     = &self.desc {
+			new_item.insert(SpawnTicket { template: desc.name.clone(), serial: self.spawn_count as u64 });
+		}
 		// Add all of the populated components to the new entity
-		if let Some(desc)     = &self.desc { new_item.insert(desc.clone()); self.desc = None; }
+		if let Some(desc)     = &self.desc {
+			// An unidentified item keeps its real Description tucked away, and shows the disguise instead
+			if let Some(disguise) = &self.disguise {
+				new_item.insert(Unidentified::new(&desc.name, desc.clone()));
+				new_item.insert(disguise.clone());
+			} else {
+				new_item.insert(desc.clone());
+			}
+			self.desc = None;
+			self.disguise = None;
+		}
 		if let Some(body)     = &self.body {
 			//debug!("* creating new item with shape {:?}", body.posns());
 			item_shape = body.posns();
 			new_item.insert(body.clone()); self.body = None;
 		}
+		if let Some(access)   = self.access { new_item.insert((access, DataStore::for_port(deck))); self.access = None; }
 		if let Some(actions)  = &self.actions { new_item.insert(actions.clone()); self.actions = None; }
+		if let Some(airlock)  = self.airlock { new_item.insert(airlock); self.airlock = None; }
+		if let Some(anim)     = &self.animated { new_item.insert(anim.clone()); self.animated = None; }
 		if let Some(contain)  = &self.contain { new_item.insert(*contain); self.contain = None; }
 		if let Some(device)   = self.device { new_item.insert(device); self.device = None; }
 		if let Some(is_carried) = self.is_carried { new_item.insert(is_carried); self.is_carried = None; }
 		if let Some(key)      = self.key { new_item.insert(key); self.key = None; }
+		if let Some(loot)     = &self.lazy_loot { new_item.insert(loot.clone()); self.lazy_loot = None; }
+		if let Some(light)    = self.light { new_item.insert(light); self.light = None; }
+		if let Some(weight)   = self.weight { new_item.insert(weight); self.weight = None; }
 		if let Some(lock)     = self.lock { new_item.insert(lock); self.lock = None; }
 		if let Some(mobile)   = self.mobile { new_item.insert(mobile); self.mobile = None; }
 		if let Some(obstruct) = self.obstruct { new_item.insert(obstruct); self.obstruct = None; }
@@ -295,9 +442,20 @@ impl<'a, 'b> ItemBuilder where 'a: 'b {
 		if let Some(open)     = &self.open { new_item.insert(open.clone()); self.open = None; }
 		if let Some(planq)    = self.planq { new_item.insert(planq); self.planq = None; }
 		if let Some(portable) = self.portable { new_item.insert(portable); self.portable = None; }
+		if let Some(reach)    = self.reach { new_item.insert(reach); self.reach = None; }
+		if let Some(salvage)  = self.salvage { new_item.insert(salvage); self.salvage = None; }
+		if let Some(bin)      = self.salvage_bin { new_item.insert(bin); self.salvage_bin = None; }
+		if let Some(bench)    = self.workbench { new_item.insert(bench); self.workbench = None; }
 		vec![(new_item, item_shape)]
 	}
 	/// Retrieves a random template from the set defined for a specified item
+	/// Lists the names of every item in the dictionary that can be picked up, ie for scattering around the ship
+	pub fn portable_item_names(&self) -> Vec<String> {
+		self.item_dict.furniture.iter()
+			.filter(|x| x.extra.iter().any(|y| y.split(' ').next() == Some("portable")))
+			.map(|x| x.name.clone())
+			.collect()
+	}
 	pub fn get_random_shape(&self, item_name: &str, rng: &mut GlobalRng) -> Option<SpawnTemplate> {
 		//debug!("* get_random_shape: {}", item_name); // DEBUG: log get_random_shape invocation
 		// If this item name was found in the ItemDict,
@@ -437,6 +595,10 @@ pub fn load_furniture_defns(items_filename: &str, sets_filename: &str) -> ItemDi
 	} else {
 		error!("! could not access the furniture sets file at {}", sets_filename);
 	}
+	// The game places a salvage bin by itself, so it needs a definition even if the file didn't provide one
+	if !new_dict.furniture.iter().any(|x| x.name == SALVAGE_BIN_ITEM) {
+		new_dict.furniture.push(salvage_bin_template());
+	}
 	// Now return the dict from this function (or put it where it needs to go)
 	new_dict
 }
@@ -0,0 +1,240 @@
+// bookmarks.rs
+// Provides the player's map bookmarks and notes, and the auto-travel that walks the player over to one of them
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::event::*;
+use crate::engine::event::ActionType::MoveTo;
+use crate::engine::event::GameEventType::*;
+use crate::engine::messagelog::*;
+use crate::robot::step_toward;
+use crate::worldmap::*;
+
+// ###: BEVY SYSTEMS
+/// Walks the player one step closer to their travel destination on each update, until they arrive or get stuck
+pub fn auto_travel_system(mut ewriter:  EventWriter<GameEvent>,
+	                        mut msglog:   ResMut<MessageLog>,
+	                        mut marks:    ResMut<Bookmarks>,
+	                        model:        Res<WorldModel>,
+	                        p_query:      Query<(Entity, &Body, Option<&NavCaps>), With<Player>>,
+) {
+	let destination = if let Some(value) = marks.destination { value } else { return };
+	let (p_enty, p_body, p_caps) = if let Ok(value) = p_query.get_single() { value } else { return };
+	let label = marks.label_at(destination).unwrap_or("your destination".to_string());
+	if p_body.ref_posn == destination {
+		msglog.tell_player(format!("You arrive at {}.", label).as_str());
+		marks.destination = None;
+		return;
+	}
+	if let Some(dir) = step_toward(&model, p_body.ref_posn, destination, p_caps.copied().unwrap_or_default()) {
+		ewriter.send(GameEvent::new(PlayerAction(MoveTo(dir)), Some(p_enty), None));
+	} else {
+		msglog.tell_player(format!("You can't find a way to {} from here.", label).as_str());
+		marks.destination = None;
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: Bookmarks
+/// Holds all of the player's bookmarks, along with the bookmark that they're currently travelling to, if any
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct Bookmarks {
+	pub marks: Vec<Bookmark>, // In the order that they were added
+	pub cursor: usize, // The index of the last bookmark that was travelled to, for cycling through them
+	pub destination: Option<Position>, // If set, the player is auto-travelling to this Position
+	pub pending: Option<Bookmark>, // If set, the waypoint whose route is being previewed, awaiting a y/n; this might be a note rather than a bookmark
+	pub preview: Vec<Position>, // The route that's being previewed, which the camera draws as a trail
+}
+impl Bookmarks {
+	pub fn new() -> Bookmarks {
+		Bookmarks::default()
+	}
+	/// Adds a new bookmark at the given Position, replacing any bookmark that was already there
+	pub fn add(&mut self, label: &str, posn: Position) {
+		self.remove_at(posn);
+		self.marks.push(Bookmark::new(label, posn));
+	}
+	/// Removes the bookmark at the given Position; returns false if there wasn't one
+	pub fn remove_at(&mut self, posn: Position) -> bool {
+		let old_len = self.marks.len();
+		self.marks.retain(|x| x.posn != posn);
+		if self.cursor >= self.marks.len() { self.cursor = 0; }
+		self.marks.len() != old_len
+	}
+	/// Returns true if there is a bookmark at the given Position
+	pub fn is_marked(&self, posn: Position) -> bool {
+		self.marks.iter().any(|x| x.posn == posn)
+	}
+	/// Returns the label of the bookmark at the given Position, if there is one
+	pub fn label_at(&self, posn: Position) -> Option<String> {
+		self.marks.iter().find(|x| x.posn == posn).map(|x| x.label.clone())
+	}
+	/// Starts travelling to the bookmark with the given index; returns its label, or None if there's no such bookmark
+	pub fn goto(&mut self, index: usize) -> Option<String> {
+		let mark = self.marks.get(index)?;
+		self.destination = Some(mark.posn);
+		self.cursor = index;
+		Some(mark.label.clone())
+	}
+	/// Starts travelling to the bookmark after the one that was last travelled to
+	pub fn goto_next(&mut self) -> Option<String> {
+		let next = self.next_index()?;
+		self.goto(next)
+	}
+	/// Returns the index of the bookmark that goto_next would pick, without setting off towards it
+	pub fn next_index(&self) -> Option<usize> {
+		if self.marks.is_empty() { return None; }
+		Some(if self.destination.is_none() && self.cursor == 0 { 0 } else { (self.cursor + 1) % self.marks.len() })
+	}
+	/// Holds the route to the given waypoint up for the player to look over before they commit to it
+	pub fn show_preview(&mut self, mark: Bookmark, route: Vec<Position>) {
+		self.pending = Some(mark);
+		self.preview = route;
+	}
+	/// Sets off along the route that was being previewed; returns its label, or None if nothing was being previewed
+	/// If the waypoint was one of the bookmarks, the next bookmark in the cycle is the one after it
+	pub fn confirm_preview(&mut self) -> Option<String> {
+		self.preview.clear();
+		let mark = self.pending.take()?;
+		if let Some(index) = self.marks.iter().position(|x| x.posn == mark.posn) {
+			self.cursor = index;
+		}
+		self.destination = Some(mark.posn);
+		Some(mark.label)
+	}
+	/// Throws away the route that was being previewed; returns false if there wasn't one
+	pub fn cancel_preview(&mut self) -> bool {
+		self.preview.clear();
+		self.pending.take().is_some()
+	}
+	/// Produces the PLANQ's listing of all the bookmarks, along with where they are from the given Position
+	pub fn report(&self, from: Position) -> Vec<String> {
+		if self.marks.is_empty() {
+			return vec!["No bookmarks.".to_string()];
+		}
+		self.marks.iter().enumerate().map(|(num, mark)| format!("{:>2}: {} ({})", num, mark.label, relative_compact(from, mark.posn))).collect()
+	}
+}
+//  ##: MapNotes
+/// Holds the notes that the player has left around the map, at most one to a tile
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct MapNotes {
+	pub notes: HashMap<Position, String>,
+}
+impl MapNotes {
+	pub fn new() -> MapNotes {
+		MapNotes::default()
+	}
+	/// Leaves a note at the given Position, replacing any note that was already there; an empty note just removes it
+	pub fn set(&mut self, posn: Position, text: &str) {
+		let text = text.trim();
+		if text.is_empty() {
+			self.notes.remove(&posn);
+		} else {
+			self.notes.insert(posn, text.to_string());
+		}
+	}
+	/// Returns the note at the given Position, if there is one
+	pub fn note_at(&self, posn: Position) -> Option<&String> {
+		self.notes.get(&posn)
+	}
+	/// Returns all of the notes sorted by deck and then by row, so that their numbering in the PLANQ's listing holds still
+	pub fn sorted(&self) -> Vec<(Position, String)> {
+		let mut output: Vec<(Position, String)> = self.notes.iter().map(|(posn, text)| (*posn, text.clone())).collect();
+		output.sort_by_key(|x| (x.0.z, x.0.y, x.0.x));
+		output
+	}
+	/// Produces the PLANQ's listing of all the notes, along with where they are from the given Position
+	pub fn report(&self, from: Position) -> Vec<String> {
+		if self.notes.is_empty() {
+			return vec!["No notes.".to_string()];
+		}
+		self.sorted().iter().enumerate().map(|(num, (posn, text))| format!("{:>2}: \"{}\" ({})", num, text, relative_compact(from, *posn))).collect()
+	}
+}
+//  ##: Bookmark
+/// A single labelled waypoint on the map
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+pub struct Bookmark {
+	pub label: String,
+	pub posn: Position,
+}
+impl Bookmark {
+	pub fn new(new_label: &str, new_posn: Position) -> Bookmark {
+		Bookmark {
+			label: new_label.to_string(),
+			posn: new_posn,
+		}
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::camera::ScreenCell;
+	use crate::engine::RunState;
+	use crate::engine::preferences::Preferences;
+	use crate::sys::movement_system;
+	use crate::testmap::{TestMap, TWO_ROOMS_WITH_DOOR};
+	use super::*;
+	//  ##: Travel
+	/// Sets up a headless App with the player in the west room of the two-room map, and with the auto-travel walking
+	/// them around through the movement_system
+	fn travel_app() -> (App, Entity) {
+		let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR);
+		let start = testmap.player_start.expect("the map should have a player start");
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ActionFailed>()
+		.add_event::<Noise>()
+		.add_event::<PlayerMoved>()
+		.insert_resource(testmap.model)
+		.insert_resource(Bookmarks::new())
+		.insert_resource(RunState::new())
+		.insert_resource(Preferences::default())
+		.insert_resource(start)
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (auto_travel_system, movement_system).chain());
+		let player = app.world.spawn((Player { }, Description::new().name("player"), Body::small(start, ScreenCell::default()))).id();
+		(app, player)
+	}
+	#[test]
+	fn goto_walks_the_player_over_to_the_bookmark() {
+		let (mut app, player) = travel_app();
+		let galley = Position::new(8, 3, 0);
+		{
+			let mut marks = app.world.resource_mut::<Bookmarks>();
+			marks.add("the galley", galley);
+			assert_eq!(marks.goto(0), Some("the galley".to_string()));
+		}
+		// The route goes out through the doorway, so it's a good deal longer than the distance between the two
+		for _ in 0..20 {
+			app.update();
+			if app.world.resource::<Bookmarks>().destination.is_none() { break; }
+		}
+		assert_eq!(app.world.get::<Body>(player).unwrap().ref_posn, galley);
+		assert_eq!(*app.world.resource::<Position>(), galley);
+		assert_eq!(app.world.resource::<Bookmarks>().destination, None);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You arrive at the galley."));
+	}
+	#[test]
+	fn an_unreachable_bookmark_calls_off_the_trip() {
+		let (mut app, player) = travel_app();
+		// The pillar in the east room can't be stood on
+		app.world.resource_mut::<Bookmarks>().add("the pillar", Position::new(7, 2, 0));
+		app.world.resource_mut::<Bookmarks>().goto(0);
+		app.update();
+		assert_eq!(app.world.get::<Body>(player).unwrap().ref_posn, Position::new(2, 2, 0));
+		assert_eq!(app.world.resource::<Bookmarks>().destination, None);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You can't find a way to the pillar from here."));
+	}
+}
+
+// EOF
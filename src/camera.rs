@@ -21,13 +21,26 @@ use bracket_geometry::prelude::*;
 use ratatui::style::Color as RatatuiColor;
 use ratatui::buffer::Cell;
 use ratatui::style::Modifier;
+use serde::{Deserialize, Serialize};
 use simplelog::*;
+use unicode_width::UnicodeWidthStr;
 
 // ###: INTERNAL LIBS
+use crate::bookmarks::{Bookmarks, MapNotes};
 use crate::components::*;
+use crate::engine::RunState;
+use crate::engine::palette::Palette;
+use crate::engine::preferences::Preferences;
+use crate::engine::viewport::WIDE_GLYPH_CUTOFF;
 use crate::worldmap::*;
 use crate::components::Color;
 
+//  ###: CONSTANTS
+/// How many turns each half of a rapid blink lasts, ie how long a flashing glyph stays lit before it goes dark again
+pub const RAPID_BLINK_TURNS: u64 = 1;
+/// How many turns each half of a slow blink lasts
+pub const SLOW_BLINK_TURNS: u64 = 4;
+
 //  ###: MAIN CLASSES
 //   ##: CameraView
 /// Represents a 'flattened' view of the Map's layers, with all entities and effects painted in,
@@ -41,6 +54,8 @@ pub struct CameraView {
 	pub height: i32,
 	pub reticle: Position,
 	pub reticle_glyphs: String,
+	pub free_origin: Option<Position>, // DEBUG: if set, the camera centers here instead of following the player
+	pub debug_reveal: DebugReveal, // DEBUG: draws more of the map than the player knows about, without touching the map
 }
 impl CameraView {
 	pub fn new(new_width: i32, new_height: i32) -> Self {
@@ -50,6 +65,8 @@ impl CameraView {
 			height: new_height,
 			reticle: Position::INVALID,
 			reticle_glyphs: "⌟⌞⌝⌜".to_string(), // Corner frame
+			free_origin: None,
+			debug_reveal: DebugReveal::Off,
 		}
 		// Other options for reticles might include: (not all tested)
 		// The reticle glyph order is UL, UR, DL, DR
@@ -74,6 +91,57 @@ impl CameraView {
 			self.output = vec![ScreenCell::default(); new_size];
 		}
 	}
+	/// Detaches the camera from the player, starting from the given Position, or reattaches it if it was already free
+	/// Returns true if the camera is now free
+	pub fn toggle_free(&mut self, anchor: Position) -> bool {
+		self.free_origin = if self.free_origin.is_some() { None } else { Some(anchor) };
+		self.free_origin.is_some()
+	}
+	/// Moves the free camera by one step in the given Direction; does nothing if the camera is following the player
+	pub fn pan(&mut self, dir: Direction) {
+		if let Some(origin) = self.free_origin {
+			self.free_origin = Some(origin + dir.to_offset());
+		}
+	}
+}
+//   ##: DebugReveal
+/// How much of the map the camera will show regardless of what the player has actually seen
+/// This is purely a rendering override: the WorldMap's revealed_tiles are never written to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum DebugReveal {
+	#[default]
+	Off,
+	Seen, // Draw every tile as if the player had explored it
+	Visible, // Draw every tile as if the player could see it right now, including the entities on it
+}
+impl DebugReveal {
+	/// Steps to the next setting, wrapping back around to Off
+	pub fn next(&self) -> DebugReveal {
+		match self {
+			DebugReveal::Off => { DebugReveal::Seen }
+			DebugReveal::Seen => { DebugReveal::Visible }
+			DebugReveal::Visible => { DebugReveal::Off }
+		}
+	}
+}
+//   ##: RememberedEntities
+/// How the camera draws the things that the player remembers seeing, but can't see right now
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum RememberedEntities {
+	Show, // Drawn just as brightly as if they were in view
+	#[default]
+	Dim, // Drawn dimmed along with the rest of the remembered map
+	Hide, // Left off entirely, so that only the remembered terrain is drawn
+}
+impl RememberedEntities {
+	/// Returns the next setting in the list, for the menu toggle
+	pub fn next(&self) -> RememberedEntities {
+		match self {
+			RememberedEntities::Show => { RememberedEntities::Dim }
+			RememberedEntities::Dim  => { RememberedEntities::Hide }
+			RememberedEntities::Hide => { RememberedEntities::Show }
+		}
+	}
 }
 //   ##: ScreenCell
 /// Compatibility type for better integration with ratatui; converts directly to a ratatui::Buffer::Cell
@@ -97,7 +165,7 @@ impl ScreenCell {
 		new_cell.glyph = str_list[0].to_string();
 		new_cell.fg = COLOR_DICT[str_list[1]] as u8;
 		new_cell.bg = COLOR_DICT[str_list[2]] as u8;
-		new_cell.modifier = MODS_DICT[str_list[3]];
+		new_cell.modifier = str_list.get(3).map_or(0, |x| parse_mods(x));
 		new_cell
 	}
 	/// Creates a ScreenCell from an input Vec of strings, such as might be obtained by collect()ing an
@@ -108,11 +176,12 @@ impl ScreenCell {
 		new_cell.glyph = input[0].to_string();
 		new_cell.fg = COLOR_DICT[input[1]] as u8;
 		new_cell.bg = COLOR_DICT[input[2]] as u8;
+		new_cell.modifier = input.get(3).map_or(0, |x| parse_mods(x));
 		new_cell
 	}
 	pub fn create(new_glyph: &str, new_fg: Color, new_bg: Color, mods: u16) -> ScreenCell {
 		ScreenCell {
-			glyph: new_glyph.to_string(),
+			glyph: checked_glyph(new_glyph),
 			fg: new_fg as u8,
 			bg: new_bg as u8,
 			modifier: mods,
@@ -122,7 +191,7 @@ impl ScreenCell {
 		ScreenCell::default()
 	}
 	pub fn glyph(mut self, new_glyph: &str) -> Self {
-		self.glyph = new_glyph.to_string();
+		self.glyph = checked_glyph(new_glyph);
 		self
 	}
 	pub fn fg(mut self, new_color: Color) -> Self {
@@ -137,6 +206,20 @@ impl ScreenCell {
 		self.modifier = new_mod;
 		self
 	}
+	/// Adds the given ratatui Modifier to whatever text modifications the cell already has
+	pub fn with_modifier(mut self, new_mod: Modifier) -> Self {
+		self.modifier |= new_mod.bits();
+		self
+	}
+	pub fn bold(self) -> Self {
+		self.with_modifier(Modifier::BOLD)
+	}
+	pub fn blink(self) -> Self {
+		self.with_modifier(Modifier::SLOW_BLINK)
+	}
+	pub fn rapid_blink(self) -> Self {
+		self.with_modifier(Modifier::RAPID_BLINK)
+	}
 	/// Creates a _visually_ empty ScreenCell, which is NOT zero values; it is occupied by nothing!
 	/// If you want an equivalent for a 'null' value, use ScreenCell::blank() instead
 	pub fn empty() -> Self {
@@ -194,7 +277,28 @@ impl ScreenCell {
 		//&& self.modifier == 0
 	}
 	pub fn set_glyph(&mut self, new_glyph: &str) {
-		self.glyph = new_glyph.to_string();
+		self.glyph = checked_glyph(new_glyph);
+	}
+	/// Returns the cell's text modifications as a ratatui Modifier; any bits that ratatui doesn't know are dropped
+	pub fn modifiers(&self) -> Modifier {
+		Modifier::from_bits_truncate(self.modifier)
+	}
+	/// Works the cell's blink out against the shared animation phase, ie the turn clock, rather than leaving it to the
+	/// terminal, so that everything on screen blinks in step and stops whenever the game does; a blinking cell is drawn
+	/// reversed for the lit half of each blink and plain for the other half
+	pub fn apply_blink_phase(&mut self, turn: u64) {
+		let mods = self.modifiers();
+		let half_period = if mods.contains(Modifier::RAPID_BLINK) {
+			RAPID_BLINK_TURNS
+		} else if mods.contains(Modifier::SLOW_BLINK) {
+			SLOW_BLINK_TURNS
+		} else {
+			return;
+		};
+		self.modifier &= !(Modifier::RAPID_BLINK | Modifier::SLOW_BLINK).bits();
+		if blink_phase(turn, half_period) {
+			self.modifier |= Modifier::REVERSED.bits();
+		}
 	}
 }
 impl From<ScreenCell> for Cell { // Used for converting my custom ScreenCell objects into ratatui::Cells for processing
@@ -204,7 +308,7 @@ impl From<ScreenCell> for Cell { // Used for converting my custom ScreenCell obj
 			fg: RatatuiColor::Indexed(input.fg),
 			bg: RatatuiColor::Indexed(input.bg),
 			underline_color: RatatuiColor::LightMagenta, // DEBUG: This is intentionally set to a trash color as I do not plan to make use of it at this time
-			modifier: Modifier::from_bits(input.modifier).unwrap_or(Modifier::empty()),
+			modifier: input.modifiers(),
 		}
 	}
 }
@@ -221,10 +325,10 @@ impl From<Vec<String>> for ScreenCell { // Input string should be formatted as "
 			COLOR_DICT[input[2].as_str()] as u8
 		};
 		ScreenCell {
-			glyph: input[0].clone(),
+			glyph: checked_glyph(&input[0]),
 			fg: fg_color,
 			bg: bg_color,
-			modifier: input[3].parse::<u16>().unwrap_or(0)
+			modifier: input.get(3).map_or(0, |x| parse_mods(x)),
 		}
 	}
 }
@@ -241,34 +345,58 @@ impl From<Vec<&str>> for ScreenCell { // Input string should be formatted as "G
 			COLOR_DICT[input[2]] as u8
 		};
 		ScreenCell {
-			glyph: input[0].to_string(),
+			glyph: checked_glyph(input[0]),
 			fg: fg_color,
 			bg: bg_color,
-			modifier: input[3].parse::<u16>().unwrap_or(0)
+			modifier: input.get(3).map_or(0, |x| parse_mods(x)),
 		}
 	}
 }
 
 // ###: BEVY SYSTEMS
+/// Moves every AnimatedRenderable along to the frame for the current turn; the turn clock only runs while the game
+/// does, so the animations hold still whenever it's paused
+pub fn animation_system(run_state:   Res<RunState>,
+	                      mut a_query: Query<&mut AnimatedRenderable>,
+) {
+	for mut anim in a_query.iter_mut() {
+		anim.advance_to(run_state.turn_count);
+	}
+}
 /// Populates and updates the CameraView's data structures so that the player can see what's going on
 pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 	                              model:       Res<WorldModel>,
 	                              p_posn:      Res<Position>,
 	                              mut p_query: Query<(Entity, &Body, &Viewshed, &Memory), With<Player>>,
-	                              e_query:     Query<(Entity, &Body), Without<Player>>,
+	                              e_query:     Query<(Entity, &Body, Option<&Mobile>, Option<&Portable>), Without<Player>>,
+	                              palette:     Res<Palette>,
+	                              marks:       Res<Bookmarks>,
+	                              notes:       Res<MapNotes>,
+	                              prefs:       Res<Preferences>,
+	                              a_query:     Query<&AnimatedRenderable>,
+	                              d_query:     Query<&Device>,
+	                              run_state:   Res<RunState>,
 ) {
 	// Bail out of the method if we're missing any of the structure we need
 	if p_query.get_single_mut().is_err() { return; }
 	let (p_enty, p_body, p_viewshed, p_memory) = p_query.get_single_mut().unwrap(); // There's probably a better way to do this but the line above guards this one so it's okay for now b(> u * )
-	let world_map = &model.levels[p_posn.z as usize];
-	assert!(!camera.output.is_empty(), "camera_update_system: camera.output has length 0!");
+	// The camera follows the player, unless it's been set loose for debugging
+	let focus = camera.free_origin.unwrap_or(*p_posn);
+	// An off-map focus would crash the indexing below; leave the last good frame up instead
+	if !model.contains(focus) {
+		error!("! camera_update_system: camera focus {} is outside the map, skipping camera update", focus); // DEBUG: report an off-map focus
+		return;
+	}
+	// A zero-area view has nothing to fill in, ie while the terminal is too small to draw the camera at all
+	if camera.width <= 0 || camera.height <= 0 || camera.output.is_empty() { return; }
+	let world_map = &model.levels[focus.z as usize];
 	assert!(!world_map.tiles.is_empty(), "camera_update_system: world_map.tiles has length 0!");
 	// Proceed with the update
 	let camera_width = camera.width as usize;
 	let screen_center = Position::new((camera_width / 2) as i32, camera.height / 2, 0);
 	// These map_frame values together define the area of the map that we'll be polling
-	let map_frame_ul = Position::new(p_posn.x - screen_center.x, p_posn.y - screen_center.y, 0);
-	let map_frame_dr = Position::new(p_posn.x + screen_center.x, p_posn.y + screen_center.y, 0);
+	let map_frame_ul = Position::new(focus.x - screen_center.x, focus.y - screen_center.y, 0);
+	let map_frame_dr = Position::new(focus.x + screen_center.x, focus.y + screen_center.y, 0);
 	// For every y-position in the map frame and its associated screen position, ...
 	for (scr_y, map_y) in (map_frame_ul.y..map_frame_dr.y).enumerate() {
 		// For every x-position in the map frame and its associated screen position, ...
@@ -276,19 +404,21 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 			trace!("- scr: {}, {}; map: {}, {}", scr_x, scr_y, map_x, map_y); // DEBUG: print the loop iteration values
 			// Get some indices for the various arrays we're going to use
 			let scr_index = xy_to_index(scr_x, scr_y, camera_width); // Indexes into the camera's map of the screen
-			let map_index = world_map.to_index(map_x, map_y); // Indexes into the worldmap's tilemap
-			let map_posn = Position::new(map_x, map_y, p_posn.z); // Shorthand container
-			// Check if the map position is currently visible or at least has been seen before
-			let is_visible = p_viewshed.visible_points.contains(&Point::new(map_x, map_y));
-			let has_seen = if map_index < world_map.revealed_tiles.len() {
-				world_map.revealed_tiles[map_index]
-			} else {
-				false
-			};
+			let map_posn = Position::new(map_x, map_y, focus.z); // Shorthand container
 			// If the map coordinates are valid, then we can go to the map to get a tile to draw on the screen
-			if map_x >= 0 && map_x < world_map.width as i32
-			&& map_y >= 0 && map_y < world_map.height as i32
-			{
+			// NOTE: the map index must not be calculated before this check; negative coords will wrap to a bogus index
+			if world_map.in_bounds(map_x, map_y) {
+				let map_index = world_map.to_index(map_x, map_y); // Indexes into the worldmap's tilemap
+				// Check if the map position is currently visible or at least has been seen before
+				// The player's viewshed only covers their own deck, but a debug reveal covers everything
+				let is_visible = camera.debug_reveal == DebugReveal::Visible
+					|| (focus.z == p_posn.z && p_viewshed.visible_points.contains(&Point::new(map_x, map_y)));
+				let has_seen = camera.debug_reveal != DebugReveal::Off || world_map.revealed_tiles[map_index];
+				// Whether there's anything drawn here besides the map itself, which the cell's neighbours must not cover up
+				let is_occupied = *p_posn == map_posn
+					|| (is_visible && top_of_stack(&world_map.get_contents_at(map_posn), &e_query).is_some())
+					|| (!is_visible && has_seen && prefs.remembered_entities != RememberedEntities::Hide
+						&& p_memory.visual.get(&map_posn).map_or(false, |x| !x.is_empty()));
 				// First, we must figure out what we're supposed to draw at this screen index:
 				camera.output[scr_index] =
 					// If this is the player's position, draw them
@@ -303,7 +433,7 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 					// Not the player, but the player can see it, get a 'live' update of what's there
 					else if is_visible {
 						// There's no System access over in the WorldMap stuff, so we have to pull the Entity ourselves
-						if let Some(enty) = world_map.get_visible_entity_at(map_posn) {
+						if let Some(enty) = top_of_stack(&world_map.get_contents_at(map_posn), &e_query) {
 							if enty == p_enty { // If it's the player after all, draw the player
 								if let Some(p_glyph) = p_body.glyph_at(&map_posn) {
 									p_glyph.into()
@@ -313,9 +443,20 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 									warn!("? Error retrieving player entity {:?} from the p_query during camera_update_system at posn {}", enty, map_posn);
 									ScreenCell::placeholder()
 								}
-							} else if let Ok((_enty, e_body)) = e_query.get(enty) { // It's a non-player entity
+							} else if let Ok((_enty, e_body, _, _)) = e_query.get(enty) { // It's a non-player entity
 								if let Some(e_glyph) = e_body.glyph_at(&map_posn) {
-									e_glyph.into()
+									let mut e_cell: ScreenCell = e_glyph.into();
+									// Anything that's animated shows its current frame instead, but only while it's in sight
+									if map_posn == e_body.ref_posn {
+										if let Some(frame) = a_query.get(enty).ok().and_then(|x| x.current()) {
+											e_cell.glyph = checked_glyph(frame);
+										}
+									}
+									// A device that's thrown an error flashes, so that the player can spot the broken one
+									if d_query.get(enty).map_or(false, |x| matches!(x.state, DeviceState::Error(_))) {
+										e_cell = e_cell.rapid_blink();
+									}
+									e_cell
 								} else {
 									warn!("? Error retrieving actor entity {:?} from the e_query during camera_update_system at posn {}", enty, map_posn);
 									ScreenCell::placeholder()
@@ -330,11 +471,15 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 						}
 					// Not the player, not visible, but has been seen by the player in the past: use the Memory component
 					} else if has_seen {
+						// The player's preferences decide whether any remembered entities are drawn here at all
+						let remembered = p_memory.visual.get(&map_posn).filter(|_| prefs.remembered_entities != RememberedEntities::Hide);
+						let mut is_entity = false;
 						let mut new_cell: ScreenCell = {
-							if let Some(enty_list) = p_memory.visual.get(&map_posn) { // Try to get an entity list for that Position
+							if let Some(enty_list) = remembered { // Try to get an entity list for that Position
 								if !enty_list.is_empty() {
-									if let Ok((_, remembered_body)) = e_query.get(enty_list[0]) {
+									if let Ok((_, remembered_body, _, _)) = e_query.get(top_of_stack(enty_list, &e_query).unwrap_or(enty_list[0])) {
 										if let Some(glyph) = remembered_body.glyph_at(&map_posn) {
+											is_entity = true;
 											glyph.into()
 										} else {
 											warn!("? Error retrieving entity's glyph from e_query during camera_update_system");
@@ -354,11 +499,47 @@ pub fn camera_update_system(mut camera:      ResMut<CameraView>,
 								world_map.get_display_tile(map_posn).cell
 							}
 						};
-						new_cell.fg = 8; // Set the foreground to dimmed
+						if !is_entity || prefs.remembered_entities != RememberedEntities::Show {
+							new_cell.fg = palette.dim(new_cell.fg); // Set the foreground to dimmed
+						}
 						new_cell
 					} else { // Player hasn't seen the tile at all, so paint some fog over it
 						ScreenCell::fog_of_war()
+					};
+				// A double-width glyph spills into the cell to its right, so that cell is left blank for the Viewport to skip
+				// If something is standing in that cell, then it stays in sight and the wide glyph is cut down instead
+				if scr_x > 0 && glyph_width(&camera.output[scr_index - 1].glyph) == 2 {
+					if is_occupied {
+						camera.output[scr_index - 1].glyph = WIDE_GLYPH_CUTOFF.to_string();
+					} else {
+						camera.output[scr_index].glyph = String::new();
+					}
+				}
+				// Underline any bookmarked spots that the player knows about
+				if (is_visible || has_seen) && *p_posn != map_posn && marks.is_marked(map_posn) {
+					camera.output[scr_index].modifier |= Modifier::UNDERLINED.bits();
+				}
+				// Tint any spots where the player has left a note, so that they can find their way back to them
+				if (is_visible || has_seen) && *p_posn != map_posn && notes.note_at(map_posn).is_some() {
+					camera.output[scr_index].fg = if is_visible { Color::Yellow as u8 } else { palette.dim(Color::Yellow as u8) };
+					camera.output[scr_index].modifier |= Modifier::ITALIC.bits();
+				}
+				// Tint any visible tile where more than one thing is piled up, so that nothing underneath goes unnoticed
+				if is_visible {
+					let stack_height = world_map.get_contents_at(map_posn).iter()
+						.filter(|x| **x == p_enty || e_query.get(**x).map_or(false, |(_, e_body, _, e_portable)| e_body.glyph_at(&map_posn).is_some() && !is_put_away(e_portable)))
+						.count();
+					if stack_height > 1 {
+						camera.output[scr_index].bg = Color::LtBlack as u8;
 					}
+				}
+				// Lay the previewed travel route down as a trail, picking out any hazards along it
+				if marks.preview.contains(&map_posn) {
+					let hazardous = model.get_tiletype_at(map_posn).properties().hazard != Hazard::None;
+					camera.output[scr_index].bg = if hazardous { Color::Red as u8 } else { Color::Blue as u8 };
+				}
+				// Anything that blinks does so in step with the turn clock
+				camera.output[scr_index].apply_blink_phase(run_state.turn_count);
 				// The map coordinates are out of bounds, display a fallback tile
 				} else {
 					camera.output[scr_index] = ScreenCell::out_of_bounds(); // Painting this blank tile helps prevent artifacting
@@ -446,12 +627,59 @@ lazy_static::lazy_static! {
 		map
 	};
 }
-/// Parses a string of Modifier types into a single Modifier object
+/// Ranks the kinds of things that can share a tile, for deciding which of them gets drawn: actors go over the fixtures,
+/// which go over any loose items
+pub fn draw_priority(mobile: bool, portable: bool) -> i32 {
+	if mobile { 2 } else if portable { 0 } else { 1 }
+}
+/// Picks out whichever Entity in the stack should be drawn on top; any ties are settled by the Entity itself, so that
+/// the same one wins on every frame instead of whichever happened to arrive last
+fn top_of_stack(stack: &[Entity], e_query: &Query<(Entity, &Body, Option<&Mobile>, Option<&Portable>), Without<Player>>) -> Option<Entity> {
+	stack.iter()
+		.filter_map(|x| e_query.get(*x).ok())
+		.filter(|(_, _, _, portable)| !is_put_away(*portable))
+		.max_by_key(|(enty, _, mobile, portable)| (draw_priority(mobile.is_some(), portable.is_some()), std::cmp::Reverse(*enty)))
+		.map(|(enty, _, _, _)| enty)
+}
+/// Returns true if the thing is being held by something else, ie it's in a crate or someone's pockets; it stays out of
+/// sight, and whatever's holding it is drawn instead
+fn is_put_away(portable: Option<&Portable>) -> bool {
+	portable.map_or(false, |x| x.carrier != Entity::PLACEHOLDER)
+}
+/// Returns the number of terminal columns that the given glyph will take up when drawn
+pub fn glyph_width(glyph: &str) -> usize {
+	UnicodeWidthStr::width(glyph)
+}
+/// Checks that a glyph will fit into one or two terminal columns; anything else is replaced by a '?' so that it can't
+/// push the rest of the row out of alignment. The empty glyph is allowed through, since it marks a blank cell
+pub fn checked_glyph(glyph: &str) -> String {
+	match glyph_width(glyph) {
+		1 | 2 => { glyph.to_string() }
+		0 if glyph.is_empty() => { String::new() }
+		width => {
+			warn!("? glyph {:?} is {} columns wide, replacing it with '?'", glyph, width); // DEBUG: report a bad glyph
+			"?".to_string()
+		}
+	}
+}
+/// Returns true if something that blinks with the given half-period should be lit up on the given turn
+pub fn blink_phase(turn: u64, half_period: u64) -> bool {
+	(turn / half_period.max(1)) % 2 == 0
+}
+/// Parses a set of text modifications into the u16 bits that a ScreenCell keeps them as, which are the same bits as
+/// ratatui's Modifier; this is the one place that the names are turned into bits, so every input format goes through it
+/// The input may be a raw number, or any of the names in the MODS_DICT joined by spaces, '+', or '|', ie "bold+blink"
 pub fn parse_mods(input: &str) -> u16 {
-	let tokens: Vec<&str> = input.split(' ').collect();
+	if let Ok(bits) = input.parse::<u16>() {
+		return Modifier::from_bits_truncate(bits).bits();
+	}
 	let mut modifier: u16 = 0;
-	for string in tokens {
-		modifier |= MODS_DICT[string];
+	for name in input.split([' ', '+', '|']).filter(|x| !x.is_empty()) {
+		if let Some(bits) = MODS_DICT.get(name.to_lowercase().as_str()) {
+			modifier |= bits;
+		} else {
+			warn!("? unknown text modifier {:?} in {:?}, ignoring it", name, input); // DEBUG: report a bad modifier name
+		}
 	}
 	modifier
 }
@@ -480,4 +708,392 @@ impl VisualEffect { // TODO: add builders to this instead of lumping it into one
 }
 */
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::engine::palette::PaletteKind;
+	use super::*;
+	/// Runs the cell's blink against each of the given turns, and notes which of them it came out lit up on
+	fn lit_turns(cell: &ScreenCell, turns: std::ops::Range<u64>) -> Vec<bool> {
+		turns.map(|turn| {
+			let mut phased = cell.clone();
+			phased.apply_blink_phase(turn);
+			let output: Cell = phased.into();
+			// The terminal never gets asked to do any blinking of its own
+			assert!(!output.modifier.intersects(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK));
+			output.modifier.contains(Modifier::REVERSED)
+		}).collect()
+	}
+	//  ##: Modifier bits
+	#[test]
+	fn modifier_names_round_trip_through_the_bits() {
+		for (name, bits) in MODS_DICT.iter() {
+			let parsed = parse_mods(name);
+			assert_eq!(parsed, *bits, "{}", name);
+			assert_eq!(Modifier::from_bits_truncate(parsed).bits(), parsed, "{}", name);
+			assert_eq!(parse_mods(&parsed.to_string()), parsed, "{}", name);
+		}
+	}
+	#[test]
+	fn modifier_names_can_be_combined() {
+		assert_eq!(parse_mods("bold+flash"), (Modifier::BOLD | Modifier::RAPID_BLINK).bits());
+		assert_eq!(parse_mods("Italic|underline dim"), (Modifier::ITALIC | Modifier::UNDERLINED | Modifier::DIM).bits());
+		// Anything that isn't a modifier is left out, and so are any bits that ratatui doesn't know about
+		assert_eq!(parse_mods("bold+sparkly"), Modifier::BOLD.bits());
+		assert_eq!(parse_mods("65535"), Modifier::all().bits());
+	}
+	#[test]
+	fn the_modifiers_reach_the_cell() {
+		let output: Cell = ScreenCell::new().glyph("@").bold().with_modifier(Modifier::ITALIC).into();
+		assert_eq!(output.modifier, Modifier::BOLD | Modifier::ITALIC);
+		let output: Cell = ScreenCell::new_from_str("& red black underline+bold").into();
+		assert_eq!(output.modifier, Modifier::UNDERLINED | Modifier::BOLD);
+		let output: Cell = ScreenCell::new_from_str_vec(vec!["&", "red", "black"]).into();
+		assert_eq!(output.modifier, Modifier::empty());
+	}
+	//  ##: Blink phase
+	#[test]
+	fn a_rapid_blink_follows_the_turn_clock() {
+		let cell = ScreenCell::new().glyph("&").bold().rapid_blink();
+		assert_eq!(lit_turns(&cell, 0..4), vec![true, false, true, false]);
+		// The rest of the cell's modifiers are kept either way
+		let mut phased = cell.clone();
+		phased.apply_blink_phase(1);
+		assert_eq!(phased.modifiers(), Modifier::BOLD);
+	}
+	#[test]
+	fn a_slow_blink_takes_longer() {
+		let cell = ScreenCell::new().glyph("&").blink();
+		assert_eq!(lit_turns(&cell, 0..8), vec![true, true, true, true, false, false, false, false]);
+	}
+	#[test]
+	fn cells_that_dont_blink_are_left_alone() {
+		let cell = ScreenCell::new().glyph("&").bold();
+		let mut phased = cell.clone();
+		phased.apply_blink_phase(3);
+		assert_eq!(phased, cell);
+	}
+	//  ##: Animation
+	/// Sets up a headless App with the animation_system and a console that cycles through three frames, two turns each
+	fn animation_app() -> (App, Entity) {
+		let mut app = App::new();
+		app.insert_resource(RunState::new())
+		.add_systems(Update, animation_system);
+		let console = app.world.spawn(AnimatedRenderable::new(vec!["a".to_string(), "b".to_string(), "c".to_string()], 2)).id();
+		(app, console)
+	}
+	/// Runs the App on the given turn and returns the frame that the console is showing
+	fn frame_on(app: &mut App, console: Entity, turn: u64) -> String {
+		app.world.resource_mut::<RunState>().turn_count = turn;
+		app.update();
+		app.world.get::<AnimatedRenderable>(console).and_then(|x| x.current()).unwrap_or_default().to_string()
+	}
+	#[test]
+	fn the_frames_advance_with_the_turns() {
+		let (mut app, console) = animation_app();
+		let frames: Vec<String> = (0..7).map(|turn| frame_on(&mut app, console, turn)).collect();
+		assert_eq!(frames, vec!["a", "a", "b", "b", "c", "c", "a"]);
+	}
+	#[test]
+	fn the_frames_hold_still_while_the_turns_do() {
+		let (mut app, console) = animation_app();
+		assert_eq!(frame_on(&mut app, console, 3), "b");
+		// However many times the App runs, and however long it takes, the frame only moves when the turn does
+		for _ in 0..5 {
+			std::thread::sleep(std::time::Duration::from_millis(2));
+			assert_eq!(frame_on(&mut app, console, 3), "b");
+		}
+	}
+	//  ##: Stacking
+	/// Sets up a headless App with the camera_update_system over a single row of floor, nine tiles long, with the player
+	/// standing in the middle of it and able to see all of it; the camera's bottom row lines up with the map's columns
+	fn stack_app() -> (App, Entity) {
+		let mut app = App::new();
+		let p_posn = Position::new(4, 0, 0);
+		app.insert_resource(CameraView::new(9, 2))
+		.insert_resource(crate::testmap::TestMap::new(".........").model)
+		.insert_resource(p_posn)
+		.insert_resource(Palette::new(crate::engine::palette::PaletteKind::Standard))
+		.insert_resource(Bookmarks::new())
+		.insert_resource(MapNotes::new())
+		.insert_resource(Preferences::default())
+		.insert_resource(RunState::new())
+		.add_systems(bevy::app::Update, camera_update_system);
+		let mut viewshed = Viewshed::new(8);
+		viewshed.visible_points = (0..9).map(|x| Point::new(x, 0)).collect();
+		let player = app.world.spawn((Player::default(), Body::small(p_posn, ScreenCell::new().glyph("@")), viewshed, Memory::new())).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![p_posn], 0, player);
+		(app, player)
+	}
+	/// Spawns something with the given glyph at the given column, without putting it on the map yet
+	fn thing(app: &mut App, x_posn: i32, glyph: &str) -> Entity {
+		app.world.spawn(Body::small(Position::new(x_posn, 0, 0), ScreenCell::new().glyph(glyph))).id()
+	}
+	/// Puts the given things down on the map at the given column, in the order given
+	fn pile_up(app: &mut App, x_posn: i32, things: &[Entity]) {
+		for enty in things.iter() {
+			app.world.resource_mut::<WorldModel>().add_contents(&vec![Position::new(x_posn, 0, 0)], 0, *enty);
+		}
+	}
+	/// Runs the camera and returns what it drew at the given column
+	fn drawn_at(app: &mut App, x_posn: i32) -> ScreenCell {
+		app.update();
+		app.world.resource::<CameraView>().output[9 + x_posn as usize].clone()
+	}
+	#[test]
+	fn equal_priorities_fall_back_to_the_entity() {
+		// The same two crates, piled up in either order, always show the one that was made first
+		for reversed in [false, true] {
+			let (mut app, _) = stack_app();
+			let first = thing(&mut app, 6, "a");
+			let second = thing(&mut app, 6, "b");
+			pile_up(&mut app, 6, if reversed { &[second, first] } else { &[first, second] });
+			for _ in 0..3 {
+				assert_eq!(drawn_at(&mut app, 6).glyph, "a", "reversed: {}", reversed);
+			}
+		}
+	}
+	#[test]
+	fn actors_go_over_fixtures_and_fixtures_go_over_items() {
+		let (mut app, _) = stack_app();
+		let item = thing(&mut app, 2, "i");
+		app.world.entity_mut(item).insert(Portable::empty());
+		let fixture = thing(&mut app, 2, "f");
+		let robot = thing(&mut app, 2, "r");
+		app.world.entity_mut(robot).insert(Mobile::default());
+		pile_up(&mut app, 2, &[item, fixture]);
+		assert_eq!(drawn_at(&mut app, 2).glyph, "f");
+		pile_up(&mut app, 2, &[robot]);
+		assert_eq!(drawn_at(&mut app, 2).glyph, "r");
+	}
+	#[test]
+	fn the_stack_indicator_comes_and_goes_with_the_pile() {
+		let (mut app, _) = stack_app();
+		let fixture = thing(&mut app, 6, "f");
+		pile_up(&mut app, 6, &[fixture]);
+		assert_ne!(drawn_at(&mut app, 6).bg, Color::LtBlack as u8);
+		let item = thing(&mut app, 6, "i");
+		app.world.entity_mut(item).insert(Portable::empty());
+		pile_up(&mut app, 6, &[item]);
+		let cell = drawn_at(&mut app, 6);
+		assert_eq!((cell.glyph.as_str(), cell.bg), ("f", Color::LtBlack as u8));
+		// Picking the item up takes it off the map, and the pile is down to one again
+		app.world.resource_mut::<WorldModel>().remove_contents(&vec![Position::new(6, 0, 0)], item);
+		assert_ne!(drawn_at(&mut app, 6).bg, Color::LtBlack as u8);
+	}
+	#[test]
+	fn whatever_is_inside_a_crate_stays_out_of_sight() {
+		let (mut app, _) = stack_app();
+		let crate_enty = thing(&mut app, 6, "c");
+		app.world.entity_mut(crate_enty).insert((Container::default(), Openable::new(false, "C", "c")));
+		let item = thing(&mut app, 6, "i");
+		app.world.entity_mut(item).insert((Portable::new(crate_enty), IsCarried::default()));
+		// Even piled up underneath the crate, the item doesn't show, and doesn't make a pile of it either
+		pile_up(&mut app, 6, &[item, crate_enty]);
+		let cell = drawn_at(&mut app, 6);
+		assert_eq!(cell.glyph, "c");
+		assert_ne!(cell.bg, Color::LtBlack as u8);
+		// An item that's been packed away on its own is just as hidden, and the floor shows through instead
+		let loose = thing(&mut app, 7, "j");
+		app.world.entity_mut(loose).insert((Portable::new(crate_enty), IsCarried::default()));
+		pile_up(&mut app, 7, &[loose]);
+		assert_ne!(drawn_at(&mut app, 7).glyph, "j");
+	}
+	#[test]
+	fn the_player_counts_towards_a_stack() {
+		let (mut app, _) = stack_app();
+		assert_ne!(drawn_at(&mut app, 4).bg, Color::LtBlack as u8);
+		let item = thing(&mut app, 4, "i");
+		app.world.entity_mut(item).insert(Portable::empty());
+		pile_up(&mut app, 4, &[item]);
+		let cell = drawn_at(&mut app, 4);
+		assert_eq!((cell.glyph.as_str(), cell.bg), ("@", Color::LtBlack as u8));
+	}
+	//  ##: Free camera
+	#[test]
+	fn the_camera_only_pans_while_its_free() {
+		let mut camera = CameraView::new(9, 2);
+		let anchor = Position::new(4, 0, 0);
+		camera.pan(Direction::E);
+		assert_eq!(camera.free_origin, None);
+		assert!(camera.toggle_free(anchor));
+		camera.pan(Direction::E);
+		camera.pan(Direction::E);
+		assert_eq!(camera.free_origin, Some(Position::new(6, 0, 0)));
+		assert!(!camera.toggle_free(anchor));
+		assert_eq!(camera.free_origin, None);
+	}
+	#[test]
+	fn a_free_camera_leaves_the_player_behind() {
+		let (mut app, _) = stack_app();
+		let crate_enty = thing(&mut app, 6, "c");
+		pile_up(&mut app, 6, &[crate_enty]);
+		{
+			let mut camera = app.world.resource_mut::<CameraView>();
+			camera.toggle_free(Position::new(4, 0, 0));
+			camera.pan(Direction::W);
+		}
+		// Everything slides one column to the right, and the near end of the row runs off the edge of the map
+		assert_eq!(drawn_at(&mut app, 7).glyph, "c");
+		assert_eq!(drawn_at(&mut app, 5).glyph, "@");
+		assert_eq!(drawn_at(&mut app, 0), ScreenCell::out_of_bounds());
+		// Snapping back puts the camera on the player again
+		app.world.resource_mut::<CameraView>().toggle_free(Position::INVALID);
+		assert_eq!(drawn_at(&mut app, 6).glyph, "c");
+		assert_eq!(drawn_at(&mut app, 4).glyph, "@");
+	}
+	//  ##: Debug reveal
+	#[test]
+	fn a_debug_reveal_draws_the_unknown_without_learning_it() {
+		let (mut app, player) = stack_app();
+		app.world.get_mut::<Viewshed>(player).expect("the player should have a viewshed").visible_points.retain(|x| x.x < 6);
+		let crate_enty = thing(&mut app, 7, "c");
+		pile_up(&mut app, 7, &[crate_enty]);
+		let before = app.world.resource::<WorldModel>().levels[0].revealed_tiles.clone();
+		assert_eq!(drawn_at(&mut app, 7), ScreenCell::fog_of_war());
+		// Seen draws the terrain as if it had been explored, and Visible draws what's standing on it as well
+		app.world.resource_mut::<CameraView>().debug_reveal = DebugReveal::Seen;
+		let seen = drawn_at(&mut app, 7);
+		assert_ne!(seen, ScreenCell::fog_of_war());
+		assert_ne!(seen.glyph, "c");
+		app.world.resource_mut::<CameraView>().debug_reveal = DebugReveal::Visible;
+		assert_eq!(drawn_at(&mut app, 7).glyph, "c");
+		// None of it sticks: switching the reveal off again leaves the map just as unexplored as it was
+		app.world.resource_mut::<CameraView>().debug_reveal = DebugReveal::Off;
+		assert_eq!(drawn_at(&mut app, 7), ScreenCell::fog_of_war());
+		assert_eq!(app.world.resource::<WorldModel>().levels[0].revealed_tiles, before);
+		assert_eq!(DebugReveal::Off.next().next().next(), DebugReveal::Off);
+	}
+	//  ##: Remembered things
+	/// Leaves a bright yellow crate at the far end of the row, out of the player's sight now but remembered from before,
+	/// and returns what the camera drew there under the given setting, along with the crate's color once dimmed
+	fn remembered_crate(setting: RememberedEntities) -> (ScreenCell, u8) {
+		let (mut app, player) = stack_app();
+		app.world.resource_mut::<Preferences>().remembered_entities = setting;
+		let crate_enty = app.world.spawn(Body::small(Position::new(7, 0, 0), ScreenCell::new().glyph("c").fg(Color::LtYellow))).id();
+		pile_up(&mut app, 7, &[crate_enty]);
+		app.world.get_mut::<Viewshed>(player).expect("the player should have a viewshed").visible_points.retain(|x| x.x != 7);
+		app.world.get_mut::<Memory>(player).expect("the player should have a memory").visual.insert(Position::new(7, 0, 0), vec![crate_enty]);
+		{
+			let mut model = app.world.resource_mut::<WorldModel>();
+			let index = model.levels[0].to_index(7, 0);
+			model.levels[0].revealed_tiles[index] = true;
+		}
+		let dimmed = app.world.resource::<Palette>().dim(Color::LtYellow as u8);
+		assert_ne!(dimmed, Color::LtYellow as u8, "the palette should dim the crate's color");
+		(drawn_at(&mut app, 7), dimmed)
+	}
+	#[test]
+	fn shown_memories_are_drawn_at_full_brightness() {
+		let (cell, _) = remembered_crate(RememberedEntities::Show);
+		assert_eq!((cell.glyph.as_str(), cell.fg), ("c", Color::LtYellow as u8));
+	}
+	#[test]
+	fn dimmed_memories_are_drawn_dimmed() {
+		let (cell, dimmed) = remembered_crate(RememberedEntities::Dim);
+		assert_eq!((cell.glyph.as_str(), cell.fg), ("c", dimmed));
+	}
+	#[test]
+	fn hidden_memories_leave_only_the_terrain() {
+		let (cell, _) = remembered_crate(RememberedEntities::Hide);
+		let floor = crate::testmap::TestMap::new(".........").model.levels[0].get_display_tile(Position::new(7, 0, 0)).cell;
+		assert_eq!(cell.glyph, floor.glyph);
+		// The remembered terrain is still dimmed, the same as under the other settings
+		assert_eq!(cell.fg, Palette::new(crate::engine::palette::PaletteKind::Standard).dim(floor.fg));
+	}
+	#[test]
+	fn the_setting_cycles_through_all_three() {
+		let start = RememberedEntities::default();
+		assert_eq!(start, RememberedEntities::Dim);
+		assert_eq!(start.next().next().next(), start);
+		assert_ne!(start.next(), start.next().next());
+	}
+	//  ##: Double-width glyphs
+	/// Runs the camera and draws its output into a headless buffer through the Viewport, the same way the game does
+	fn drawn_row(app: &mut App) -> Vec<String> {
+		app.update();
+		let camera = app.world.resource::<CameraView>();
+		let area = ratatui::layout::Rect::new(0, 0, 9, 2);
+		let mut buf = ratatui::buffer::Buffer::empty(area);
+		ratatui::widgets::Widget::render(crate::engine::viewport::Viewport::new(camera), area, &mut buf);
+		(0..9).map(|x| buf.get(x, 1).symbol.clone()).collect()
+	}
+	#[test]
+	fn a_wide_glyph_blanks_the_empty_cell_beside_it() {
+		let (mut app, _) = stack_app();
+		let robot = thing(&mut app, 1, "🤖");
+		pile_up(&mut app, 1, &[robot]);
+		assert_eq!(drawn_at(&mut app, 1).glyph, "🤖");
+		assert!(drawn_at(&mut app, 2).is_blank());
+	}
+	#[test]
+	fn a_wide_glyph_never_covers_up_its_neighbour() {
+		let (mut app, _) = stack_app();
+		let robot = thing(&mut app, 3, "🤖");
+		pile_up(&mut app, 3, &[robot]);
+		// The player is standing just to the right, so the robot gets cut down to fit
+		assert_eq!(drawn_at(&mut app, 3).glyph, WIDE_GLYPH_CUTOFF);
+		assert_eq!(drawn_at(&mut app, 4).glyph, "@");
+		let crate_enty = thing(&mut app, 6, "c");
+		let other = thing(&mut app, 5, "🤖");
+		pile_up(&mut app, 5, &[other]);
+		pile_up(&mut app, 6, &[crate_enty]);
+		assert_eq!(drawn_at(&mut app, 5).glyph, WIDE_GLYPH_CUTOFF);
+		assert_eq!(drawn_at(&mut app, 6).glyph, "c");
+	}
+	#[test]
+	fn wide_glyphs_keep_the_columns_aligned() {
+		let (mut app, _) = stack_app();
+		let robot = thing(&mut app, 1, "🤖");
+		let crate_enty = thing(&mut app, 6, "c");
+		pile_up(&mut app, 1, &[robot]);
+		pile_up(&mut app, 6, &[crate_enty]);
+		let floor = crate::testmap::TestMap::new(".........").model.levels[0].get_display_tile(Position::new(0, 0, 0)).cell.glyph;
+		let row = drawn_row(&mut app);
+		// The robot takes up its own column and the next one, and everything after it is still in its own column
+		assert_eq!(row[1], "🤖");
+		assert_eq!(row[2], " ");
+		assert_eq!(row[4], "@");
+		assert_eq!(row[6], "c");
+		for x in [0, 3, 5, 7, 8] {
+			assert_eq!(row[x], floor, "column {}", x);
+		}
+	}
+	//  ##: Palettes
+	/// Runs the camera and draws its output into a headless buffer with the given palette, returning the foreground
+	/// color that each column was drawn with
+	fn drawn_colors(app: &mut App, kind: PaletteKind) -> Vec<RatatuiColor> {
+		app.update();
+		let camera = app.world.resource::<CameraView>();
+		let palette = Palette::new(kind);
+		let area = ratatui::layout::Rect::new(0, 0, 9, 2);
+		let mut buf = ratatui::buffer::Buffer::empty(area);
+		ratatui::widgets::Widget::render(crate::engine::viewport::Viewport::new(camera).palette(&palette), area, &mut buf);
+		(0..9).map(|x| buf.get(x, 1).fg).collect()
+	}
+	#[test]
+	fn picking_a_preset_changes_the_player_and_item_colors() {
+		let (mut app, player) = stack_app();
+		app.world.entity_mut(player).insert(Body::small(Position::new(4, 0, 0), ScreenCell::new().glyph("@").fg(Color::LtGreen)));
+		let item = app.world.spawn((Body::small(Position::new(2, 0, 0), ScreenCell::new().glyph("i").fg(Color::Yellow)), Portable::empty())).id();
+		pile_up(&mut app, 2, &[item]);
+		// The standard palette leaves the colors up to the terminal
+		let standard = drawn_colors(&mut app, PaletteKind::Standard);
+		assert_eq!(standard[4], RatatuiColor::Indexed(Color::LtGreen as u8));
+		assert_eq!(standard[2], RatatuiColor::Indexed(Color::Yellow as u8));
+		// Each of the presets puts its own colors in their place
+		let deuteranopia = drawn_colors(&mut app, PaletteKind::Deuteranopia);
+		assert_eq!(deuteranopia[4], RatatuiColor::Rgb(60, 210, 170));
+		assert_eq!(deuteranopia[2], RatatuiColor::Rgb(230, 159, 0));
+		for kind in [PaletteKind::Protanopia, PaletteKind::Tritanopia] {
+			let colors = drawn_colors(&mut app, kind);
+			assert_eq!(colors[4], Palette::new(kind).color(Color::LtGreen as u8), "{}", kind);
+			assert_eq!(colors[2], Palette::new(kind).color(Color::Yellow as u8), "{}", kind);
+			assert_ne!(colors[4], deuteranopia[4], "{}", kind);
+			assert_ne!(colors[2], deuteranopia[2], "{}", kind);
+		}
+	}
+}
+
 // EOF
@@ -0,0 +1,108 @@
+// census.rs
+// Provides a headcount of the live entities in the game world, sorted into broad categories, for debugging and balancing
+
+// ###: EXTERNAL LIBS
+use std::fmt;
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+
+// ###: METHODS
+/// Counts up every live entity in the given World that falls into one of the EntityCensus categories
+pub fn take_census(world: &mut World) -> EntityCensus {
+	let mut census = EntityCensus::default();
+	let mut item_query = world.query::<&Portable>();
+	for i_portable in item_query.iter(world) {
+		if i_portable.carrier == Entity::PLACEHOLDER {
+			census.floor_items += 1;
+		} else {
+			census.carried_items += 1;
+		}
+	}
+	// Containers like lockers are Openable too, but they aren't doors
+	census.doors = world.query_filtered::<Entity, (With<Openable>, Without<Container>)>().iter(world).count();
+	census.containers = world.query_filtered::<Entity, (With<Container>, Without<Mobile>)>().iter(world).count();
+	census.devices = world.query_filtered::<Entity, With<Device>>().iter(world).count();
+	census.npcs = world.query_filtered::<Entity, (With<Mobile>, Without<Player>)>().iter(world).count();
+	census
+}
+
+// ###: COMPLEX TYPES
+//  ##: EntityCensus
+/// The number of live entities in each category, as of when take_census() was called
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntityCensus {
+	pub floor_items: usize, // Portable, and not being carried by anything
+	pub carried_items: usize, // Portable, and in someone's inventory or inside a container
+	pub doors: usize,
+	pub containers: usize, // Stationary containers only; an actor's inventory doesn't count
+	pub devices: usize,
+	pub npcs: usize, // Anything Mobile that isn't the player
+}
+impl EntityCensus {
+	/// Produces the census as a list of lines for display
+	pub fn report(&self) -> Vec<String> {
+		vec![
+			format!("Items (floor):   {}", self.floor_items),
+			format!("Items (carried): {}", self.carried_items),
+			format!("Doors:           {}", self.doors),
+			format!("Containers:      {}", self.containers),
+			format!("Devices:         {}", self.devices),
+			format!("NPCs:            {}", self.npcs),
+		]
+	}
+}
+impl fmt::Display for EntityCensus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "floor items: {}, carried items: {}, doors: {}, containers: {}, devices: {}, npcs: {}",
+			self.floor_items, self.carried_items, self.doors, self.containers, self.devices, self.npcs)
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Counting
+	#[test]
+	fn every_category_is_counted_on_its_own() {
+		let mut world = World::new();
+		let player = world.spawn((Player::default(), Mobile::default(), Container::default())).id();
+		let lmr = world.spawn((Mobile::default(), Container::with_slots(1), Device::new(-1))).id();
+		let locker = world.spawn((Container::default(), Openable::new(false, "▯", "▮"))).id();
+		world.spawn(Openable::new(false, "'", "+"));
+		world.spawn(Openable::new(true, "'", "+"));
+		world.spawn(Portable::empty());
+		world.spawn((Portable::empty(), Device::new(0)));
+		world.spawn(Portable::new(player));
+		world.spawn(Portable::new(lmr));
+		world.spawn(Portable::new(locker));
+		// Something that isn't any of the above is left out entirely
+		world.spawn(Description::new().name("dust"));
+		assert_eq!(take_census(&mut world), EntityCensus {
+			floor_items: 2,
+			carried_items: 3,
+			doors: 2,
+			containers: 1,
+			devices: 2,
+			npcs: 1,
+		});
+	}
+	#[test]
+	fn an_empty_world_counts_nothing() {
+		assert_eq!(take_census(&mut World::new()), EntityCensus::default());
+	}
+	#[test]
+	fn the_report_gives_every_count() {
+		let census = EntityCensus { floor_items: 1, carried_items: 2, doors: 3, containers: 4, devices: 5, npcs: 6 };
+		let report = census.report();
+		assert_eq!(report.len(), 6);
+		for (line, count) in report.iter().zip(1..=6) {
+			assert!(line.ends_with(&format!(" {}", count)), "{}", line);
+		}
+		assert_eq!(census.to_string(), "floor items: 1, carried items: 2, doors: 3, containers: 4, devices: 5, npcs: 6");
+	}
+}
+
+// EOF
@@ -3,6 +3,13 @@
 // ###: COMPONENTS REFERENCE LIST
 /* components.rs
  *   AccessPort - "accessport"
+ *   Airlock - "airlock turns"
+ *     inner: Entity (linked up after the map is built)
+ *     outer: Entity (linked up after the map is built)
+ *     equalize_turns: u64
+ *   AnimatedRenderable - "animated frames interval"
+ *     frames: Vec<String>
+ *     interval: u32
  *   ActionSet - "actionset"
  *     actions: HashSet<ActionType>
  *     outdated: bool
@@ -22,6 +29,8 @@
  *   Glyph - use a Body component for this instead
  *     posn: Position
  *     cell: ScreenCell
+ *   History - "history"
+ *     entries: Vec<String>
  *   IsCarried - "iscarried"
  *   Key - "key id"
  *     key_id: i32
@@ -87,6 +96,7 @@ use bevy::prelude::{
 	World,
 };
 use bevy::ecs::entity::*;
+use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::utils::hashbrown::{HashMap, HashSet};
 use bracket_pathfinding::prelude::*;
 use ratatui::layout::Rect;
@@ -96,7 +106,7 @@ use strum_macros::AsRefStr;
 
 // ###: INTERNAL LIBS
 use crate::engine::event::ActionType;
-use crate::camera::ScreenCell;
+use crate::camera::{checked_glyph, ScreenCell};
 
 // Full-length derive macro examples
 //#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -352,11 +362,34 @@ impl From<Glyph> for ScreenCell {
 		value.cell
 	}
 }
+//   ##: History
+/// Keeps a record of the notable things that have happened to an entity, such as being repaired by the LMR
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct History {
+	pub entries: Vec<String>,
+}
+impl History {
+	pub fn new() -> History {
+		History::default()
+	}
+	/// Adds a new entry to the end of the record
+	pub fn add(&mut self, entry: &str) {
+		self.entries.push(entry.to_string());
+	}
+	/// Returns the most recent entry, if there is one
+	pub fn latest(&self) -> Option<&String> {
+		self.entries.last()
+	}
+}
 //   ##: Viewshed
 /// Provides an object abstraction for the sensory range of a given entity
-//  INFO: This Viewshed type is NOT eligible for bevy_save because bracket_lib::Point doesn't impl Reflect/FromReflect
-#[derive(Component, Clone, Debug)]
+//  INFO: bracket_lib::Point doesn't impl Reflect, so the visible_points are left out of the save and get recalculated
+//      by the ResyncViewsheds event that follows a load or a deck restore
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
 pub struct Viewshed {
+	#[reflect(ignore)]
 	pub visible_points: Vec<Point>, // for bracket_lib::pathfinding::field_of_view
 	pub range: i32,
 	pub dirty: bool, // indicates whether this viewshed needs to be updated from world data
@@ -375,7 +408,7 @@ impl Viewshed {
 //    ##: Memory
 /// Provides a memory of seen entities and other things to an entity with sentience
 #[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
-#[reflect(Component)]
+#[reflect(Component, MapEntities)]
 pub struct Memory {
 	pub visual: HashMap<Position, Vec<Entity>>,
 }
@@ -395,11 +428,20 @@ impl Memory {
 		}
 	}
 }
+impl MapEntities for Memory {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for entys in self.visual.values_mut() {
+			for enty in entys.iter_mut() {
+				*enty = map_entity(entity_mapper, *enty);
+			}
+		}
+	}
+}
 //   ##: Portable
 /// Describes an entity that can be picked up and carried around
 //#[derive(Component, Clone, Copy, Debug, Default)]
 #[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
-#[reflect(Component)]
+#[reflect(Component, MapEntities)]
 pub struct Portable {
 	pub carrier: Entity
 }
@@ -409,7 +451,7 @@ impl Portable {
 }
 impl MapEntities for Portable {
 	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
-		self.carrier = entity_mapper.get_or_reserve(self.carrier);
+		self.carrier = map_entity(entity_mapper, self.carrier);
 	}
 }
 impl FromWorld for Portable {
@@ -443,16 +485,29 @@ pub struct Openable {
 	pub is_stuck: bool,
 	pub open_glyph: String,
 	pub closed_glyph: String,
+	pub slide_into: Option<Position>, // If set, the wall pocket that this door will move into when opened
+	pub home: Position, // The doorway that a sliding door occupies when closed; unused if slide_into is None
 }
 impl Openable {
 	pub fn new(state: bool, opened: &str, closed: &str) -> Openable {
 		Openable {
 			is_open: state,
 			is_stuck: false,
-			open_glyph: opened.to_string(),
-			closed_glyph: closed.to_string(),
+			open_glyph: checked_glyph(opened),
+			closed_glyph: checked_glyph(closed),
+			slide_into: None,
+			home: Position::default(),
 		}
 	}
+	/// Turns this into a sliding door that sits in the doorway when closed and in the pocket when open
+	pub fn slides(&mut self, doorway: Position, pocket: Position) {
+		self.home = doorway;
+		self.slide_into = Some(pocket);
+	}
+	/// Returns the Position of the doorway that this Openable controls, given the Position it is currently at
+	pub fn doorway(&self, current: Position) -> Position {
+		if self.slide_into.is_some() { self.home } else { current }
+	}
 }
 //   ##: Lockable
 /// Describes an Entity that can be locked and unlocked, such as a door or a locker
@@ -461,7 +516,10 @@ impl Openable {
 #[reflect(Component)]
 pub struct Lockable {
 	pub is_locked: bool,
-	pub key_id: i32
+	pub key_id: i32,
+	pub code: i32, // The keypad code, if any; 0 means there's no keypad
+	pub remote: bool, // If true, can be unlocked over the shipnet
+	pub difficulty: i32, // How hard the lock is to hack, as a percentage: 0 is trivial, 100 can't be hacked
 }
 impl Lockable {
 	// Unlocks, given the correct key value as input
@@ -494,8 +552,16 @@ pub struct Device {
 	pub batt_voltage: i32,
 	pub batt_discharge: i32,
 	pub state: DeviceState,
+	pub requires_mains: bool, // If true, the device goes offline whenever its zone of the ship loses power
+	pub wear: u32, // How worn out the device is; it breaks down once this reaches WEAR_LIMIT
 }
 impl Device {
+	/// The amount of wear that a device can take before it breaks down
+	pub const WEAR_LIMIT: u32 = 100;
+	/// The error code that a worn-out device reports
+	pub const WORN_OUT: u32 = 1;
+	/// The wear caused by running the battery completely flat
+	pub const DEEP_DISCHARGE_WEAR: u32 = 10;
 	/// Creates a new Device; set the batt_discharge param to 0 to disable battery use
 	pub fn new(discharge_rate: i32) -> Device {
 		Device {
@@ -503,10 +569,13 @@ impl Device {
 			batt_voltage: 0, // BATTERIES NOT INCLUDED LMAOOO
 			batt_discharge: discharge_rate,
 			state: DeviceState::Offline,
+			requires_mains: false,
+			wear: 0,
 		}
 	}
-	/// Turns on the device, if there's any power remaining. Returns false if no power left.
+	/// Turns on the device, if there's any power remaining and it isn't broken. Returns false if it didn't turn on.
 	pub fn power_on(&mut self) -> bool {
+		if let DeviceState::Error(_) = self.state { return false; }
 		if self.batt_voltage > 0
 		|| self.batt_discharge == 0 {
 			self.pw_switch = true;
@@ -514,9 +583,10 @@ impl Device {
 		}
 		self.pw_switch
 	}
-	/// Turns off the device.
+	/// Turns off the device; a broken device keeps reporting its error.
 	pub fn power_off(&mut self) {
 		self.pw_switch = false;
+		if let DeviceState::Error(_) = self.state { return; }
 		self.state = DeviceState::Offline;
 	}
 	/// Discharges battery power according to the specified duration, returns current power level
@@ -525,10 +595,30 @@ impl Device {
 			// This item does not need a battery/has infinite power, so no discharge can occur
 			return self.batt_voltage;
 		}
+		let was_charged = self.batt_voltage > 0;
 		self.batt_voltage -= self.batt_discharge * duration;
 		if self.batt_voltage < 0 { self.batt_voltage = 0; }
+		// Running the battery all the way down is hard on the rest of the device
+		if was_charged && self.batt_voltage == 0 { self.wear_down(Device::DEEP_DISCHARGE_WEAR); }
 		self.batt_voltage
 	}
+	/// Returns how much more wear the device can take before it breaks down
+	pub fn durability(&self) -> u32 {
+		Device::WEAR_LIMIT.saturating_sub(self.wear)
+	}
+	/// Adds the given amount of wear to the device, and puts it into an Error state if that wears it out
+	/// Returns true if the device broke down just now
+	pub fn wear_down(&mut self, amount: u32) -> bool {
+		self.wear = (self.wear + amount).min(Device::WEAR_LIMIT);
+		if self.wear < Device::WEAR_LIMIT || matches!(self.state, DeviceState::Error(_)) { return false; }
+		self.state = DeviceState::Error(Device::WORN_OUT);
+		true
+	}
+	/// Clears any wear and errors, returning the device to whatever state its power switch calls for
+	pub fn repair(&mut self) {
+		self.wear = 0;
+		self.state = if self.pw_switch { DeviceState::Idle } else { DeviceState::Offline };
+	}
 	/// Recharges the battery to the given percentage
 	pub fn recharge(&mut self, charge_level: i32) -> i32 {
 		self.batt_voltage += charge_level;
@@ -565,6 +655,11 @@ pub struct Player { }
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
 #[reflect(Component)]
 pub struct LMR { }
+//   ##: Disabled
+/// Identifies an actor that has been knocked out of action, ie a destroyed robot; it no longer moves or acts
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Disabled { }
 //   ##: IsCarried
 /// Describes an Entity that is currently located within a Container
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
@@ -574,17 +669,360 @@ pub struct IsCarried { }
 /// Describes an entity which may contain entities tagged with the Portable Component
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
 #[reflect(Component)]
-pub struct Container { } // TODO: this almost definitely needs a capacity field attached to it
+pub struct Container {
+	pub max_slots: Option<usize>, // The most items that can be held at once, regardless of their Weight; None is unlimited
+}
+impl Container {
+	/// Creates a Container that can only hold the given number of items
+	pub fn with_slots(new_max: usize) -> Container {
+		Container { max_slots: Some(new_max) }
+	}
+}
+//   ##: LazyLoot
+/// Describes a Container whose contents aren't spawned until the first time that it's opened
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct LazyLoot {
+	pub items: Vec<String>, // The names of the items to create inside it, as known to the ItemBuilder
+}
+//   ##: SpawnIndex
+/// Gives an entity a number that stays the same for the whole of its life, and across a save and a load, unlike its
+/// Entity ID; see the spawn_index_system for how they're handed out
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[reflect(Component)]
+pub struct SpawnIndex {
+	pub index: u64,
+}
+//   ##: SpawnOrder
+/// Keeps track of the next SpawnIndex to be handed out; indices are never reused, even after their entity is gone
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct SpawnOrder {
+	pub next: u64,
+}
+//   ##: SpawnTicket
+/// Records which template the ItemBuilder made an entity from, and how many things it had built by then, so that the
+/// spawn_index_system can tell apart things that turn up at the same spot; it's taken off again once it's been used
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpawnTicket {
+	pub template: String,
+	pub serial: u64,
+}
+//   ##: LootRequests
+/// Holds the items that the lazy_loot_system wants made, until the GameEngine can hand them off to the ItemBuilder
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LootRequests {
+	pub pending: Vec<(String, Entity)>, // The item name, and the Container to put it into
+}
 //   ##: AccessPort
 /// Describes an entity with a PLANQ-compatible maintenance system
 #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 pub struct AccessPort { }
+//   ##: DataStore
+/// Holds the data files that can be moved between the PLANQ and an AccessPort over the access jack
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct DataStore {
+	pub files: Vec<DataFile>,
+}
+impl DataStore {
+	/// Provides the files that a maintenance port starts out with: the plans for its own deck, and the maintenance log
+	pub fn for_port(deck: i32) -> DataStore {
+		DataStore {
+			files: vec![
+				DataFile::new(&format!("deck_{}.map", deck), DataKind::MapFragment, deck, ""),
+				DataFile::new(&format!("deck_{}.log", deck), DataKind::Log, deck, &format!("Deck {} maintenance log: no faults recorded.", deck)),
+			]
+		}
+	}
+}
+//    #: DataFile
+/// A single file of data; what the value and text are used for depends on the kind of file
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct DataFile {
+	pub name: String,
+	pub kind: DataKind,
+	pub value: i32, // The deck that a MapFragment covers, or the key_id of a Keycode
+	pub text: String, // The contents of a Log
+}
+impl DataFile {
+	pub fn new(name: &str, kind: DataKind, value: i32, text: &str) -> DataFile {
+		DataFile {
+			name: name.to_string(),
+			kind,
+			value,
+			text: text.to_string(),
+		}
+	}
+}
+//    #: DataKind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum DataKind {
+	#[default]
+	Log, // Some text for the player to read
+	MapFragment, // Reveals the layout of a deck
+	Keycode, // Unlocks the shipnet-enabled locks with a matching key_id when uploaded
+}
 //   ##: Networkable
 /// Describes an entity that can connect to and communicate with the shipnet
 #[derive(Component, Copy, Clone, Debug, Default, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 pub struct Networkable { }
+//   ##: NavCaps
+/// Describes what an actor can safely move through, for the pathfinding to take into account
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct NavCaps {
+	pub breathes: bool, // If true, the actor will not path through vacuum
+}
+impl Default for NavCaps {
+	fn default() -> NavCaps {
+		NavCaps { breathes: true }
+	}
+}
+//   ##: AnimatedRenderable
+/// Cycles an entity's glyph through a list of frames, ie for a blinking console or a flickering light
+/// Only the glyph at the entity's ref_posn is changed; the colors and modifiers still come from its Body
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct AnimatedRenderable {
+	pub frames: Vec<String>,
+	pub interval: u32, // How many turns each frame stays up
+	pub frame: usize, // The index of the frame that's currently showing
+}
+impl AnimatedRenderable {
+	pub fn new(new_frames: Vec<String>, new_interval: u32) -> AnimatedRenderable {
+		AnimatedRenderable {
+			frames: new_frames,
+			interval: new_interval,
+			..AnimatedRenderable::default()
+		}
+	}
+	/// Puts up whichever frame belongs to the given turn; every animation keeps to the same turn clock, so that two
+	/// things with the same frames and interval always show the same frame, and nothing moves while the game is paused
+	pub fn advance_to(&mut self, turn: u64) {
+		if self.frames.is_empty() || self.interval == 0 { return; }
+		self.frame = ((turn / self.interval as u64) % self.frames.len() as u64) as usize;
+	}
+	/// Returns the glyph that should be shown right now, if there's anything to show
+	pub fn current(&self) -> Option<&str> {
+		self.frames.get(self.frame).map(|x| x.as_str())
+	}
+}
+//   ##: LightSource
+/// Describes an entity that gives off light, ie a glowing console, which lets anyone nearby see by it
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct LightSource {
+	pub radius: i32,
+	pub is_on: bool,
+}
+impl LightSource {
+	pub fn new(new_radius: i32) -> LightSource {
+		LightSource {
+			radius: new_radius,
+			is_on: true,
+		}
+	}
+}
+//   ##: Vitals
+/// Describes how much punishment an actor can take before it is incapacitated
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Vitals {
+	pub hp: i32,
+	pub max_hp: i32,
+}
+impl Vitals {
+	pub fn new(new_max: i32) -> Vitals {
+		Vitals {
+			hp: new_max,
+			max_hp: new_max,
+		}
+	}
+	/// Returns true if the actor has run out of hitpoints
+	pub fn is_down(&self) -> bool {
+		self.hp <= 0
+	}
+}
+//   ##: ReachProfile
+/// Lets an entity interact with things from farther away than usual, on top of each action's base reach
+/// On an actor this applies to the actor itself; on a Portable tool, it applies to whoever is carrying it
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct ReachProfile {
+	pub extra: i32, // The number of tiles added to the base reach of every action that has one
+}
+//   ##: Weight
+/// Describes how heavy a Portable item is; items without a Weight count as 1
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Weight {
+	pub mass: i32,
+}
+impl Default for Weight {
+	fn default() -> Weight {
+		Weight { mass: 1 }
+	}
+}
+//   ##: CarryCapacity
+/// Describes how much weight an actor can haul around before it starts to slow them down and make a racket
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct CarryCapacity {
+	pub max_load: i32,
+}
+impl CarryCapacity {
+	pub fn new(new_max: i32) -> CarryCapacity {
+		CarryCapacity { max_load: new_max }
+	}
+	/// Returns the fraction of the actor's capacity that the given load takes up; may be greater than 1.0
+	pub fn load_ratio(&self, load: i32) -> f32 {
+		if self.max_load <= 0 { return 0.0; }
+		load as f32 / self.max_load as f32
+	}
+}
+//   ##: MoveStyle
+/// Describes how an actor is moving around, and how much noise they made the last time that they did
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct MoveStyle {
+	pub careful: bool, // If true, the actor makes half as much noise, but each step takes twice as long
+	pub last_volume: i32, // The volume of the most recent Noise that this actor made by moving
+	pub next_step: u64, // The turn on which the actor can take their next step, if the last one took more than a turn
+}
+impl MoveStyle {
+	/// The loudest that a single step can be
+	pub const MAX_VOLUME: i32 = 10;
+	/// Calculates the volume of a step, given the fraction of their carrying capacity that the actor is loaded with
+	pub fn volume(&self, load_ratio: f32) -> i32 {
+		let mut volume = 2.0 + 6.0 * load_ratio;
+		if self.careful { volume /= 2.0; }
+		(volume.round() as i32).clamp(0, MoveStyle::MAX_VOLUME)
+	}
+	/// Returns the number of turns that a single step takes
+	pub fn time_cost(&self) -> u64 {
+		if self.careful { 2 } else { 1 }
+	}
+	/// Returns true if the actor is still busy with their last step on the given turn
+	pub fn is_stepping(&self, turn: u64) -> bool {
+		turn < self.next_step
+	}
+	/// Returns the volume of the actor's last step as a percentage of the loudest that a step can be
+	pub fn meter_percent(&self) -> u32 {
+		(self.last_volume.clamp(0, MoveStyle::MAX_VOLUME) * 100 / MoveStyle::MAX_VOLUME) as u32
+	}
+}
+//   ##: Hears
+/// Describes an entity that can be alerted by the Noise that others make
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Hears {
+	pub last_heard: Option<Position>, // Where the most recent Noise that reached this entity came from
+}
+//   ##: Unidentified
+/// Describes an item that the player hasn't worked out yet: it wears a generic Description, ie "an unlabeled data
+/// chip", until it's used, examined at a Workbench, or analyzed by the PLANQ
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Unidentified {
+	pub true_template: String, // The item's name in the ItemBuilder's dictionary
+	pub true_desc: Description, // The Description that gets swapped in once the item is identified
+}
+impl Unidentified {
+	pub fn new(template: &str, new_desc: Description) -> Unidentified {
+		Unidentified {
+			true_template: template.to_string(),
+			true_desc: new_desc,
+		}
+	}
+}
+//   ##: Workbench
+/// Describes an entity that lets the player get a closer look at things: examining an Unidentified item while
+/// standing next to one will identify it
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Workbench { }
+//   ##: Salvage
+/// Describes an item that's worth something to the player's score if it makes it into a SalvageBin
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Salvage {
+	pub value: u32,
+}
+//   ##: SalvageBin
+/// Describes a Container that the player can drop their salvage into, to have it counted toward their score
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct SalvageBin { }
+//   ##: Airlock
+/// Describes the control panel of an airlock: the pair of doors that it runs, and how far through a cycle it's got
+/// The doors are never opened or closed directly; the panel asks for it with the same events that anyone else would
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Airlock {
+	pub inner: Entity, // The door on the ship's side of the chamber
+	pub outer: Entity, // The door that opens onto whatever's outside
+	pub cycle: AirlockCycle,
+	pub operator: Entity, // Whoever started the current cycle, so that they can be told how it went
+	pub equalize_turns: u64, // How long the chamber takes to pump up or down once both doors are shut
+}
+impl Airlock {
+	/// The default time that it takes to equalize the chamber, about three seconds at the usual tick rate
+	pub const EQUALIZE_TURNS: u64 = 12;
+	/// Returns true if both of the doors have been found and linked up to the panel
+	pub fn is_linked(&self) -> bool {
+		self.inner != Entity::PLACEHOLDER && self.outer != Entity::PLACEHOLDER
+	}
+	/// Returns the door on the other side of the chamber from the given one
+	pub fn other_door(&self, door: Entity) -> Entity {
+		if door == self.inner { self.outer } else { self.inner }
+	}
+}
+impl Default for Airlock {
+	fn default() -> Airlock {
+		Airlock {
+			inner: Entity::PLACEHOLDER,
+			outer: Entity::PLACEHOLDER,
+			cycle: AirlockCycle::Idle,
+			operator: Entity::PLACEHOLDER,
+			equalize_turns: Airlock::EQUALIZE_TURNS,
+		}
+	}
+}
+impl MapEntities for Airlock {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		self.inner = map_entity(entity_mapper, self.inner);
+		self.outer = map_entity(entity_mapper, self.outer);
+		self.operator = map_entity(entity_mapper, self.operator);
+		self.cycle.map_entities(entity_mapper);
+	}
+}
+//    #: AirlockCycle
+/// The steps of an airlock's cycle; each one that waits on a door remembers the turn it started, so that a door that
+/// never moves can be given up on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum AirlockCycle {
+	#[default]
+	Idle,
+	Closing { door: Entity, then: Entity, since: u64 }, // Waiting for one door to shut before the other is opened
+	Equalizing { then: Entity, until: u64 },
+	Opening { door: Entity, since: u64 },
+}
+impl MapEntities for AirlockCycle {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		match self {
+			AirlockCycle::Idle => { }
+			AirlockCycle::Closing { door, then, .. } => {
+				*door = map_entity(entity_mapper, *door);
+				*then = map_entity(entity_mapper, *then);
+			}
+			AirlockCycle::Equalizing { then, .. } => { *then = map_entity(entity_mapper, *then); }
+			AirlockCycle::Opening { door, .. } => { *door = map_entity(entity_mapper, *door); }
+		}
+	}
+}
 //   ##: Mobile
 /// Describes an Entity that can move around under its own power
 #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
@@ -641,6 +1079,56 @@ pub enum Direction {
 	UP,
 	DOWN
 }
+impl Direction {
+	/// Converts an offset on the x, y plane into the matching compass Direction; only the signs are considered
+	pub fn from_offset(x_diff: i32, y_diff: i32) -> Direction {
+		match (x_diff.signum(), y_diff.signum()) {
+			( 0, -1) => { Direction::N }
+			(-1, -1) => { Direction::NW }
+			(-1,  0) => { Direction::W }
+			(-1,  1) => { Direction::SW }
+			( 0,  1) => { Direction::S }
+			( 1,  1) => { Direction::SE }
+			( 1,  0) => { Direction::E }
+			( 1, -1) => { Direction::NE }
+			_        => { Direction::X }
+		}
+	}
+	/// Picks the compass octant that the given offset is heading towards; unlike from_offset(), this takes the
+	/// actual angle into account, so (5, 1) is East rather than Southeast
+	pub fn from_heading(x_diff: i32, y_diff: i32) -> Direction {
+		if x_diff == 0 && y_diff == 0 { return Direction::X; }
+		// The map's y-axis grows downwards, so flip it to get a normal compass angle
+		let angle = f32::atan2(-y_diff as f32, x_diff as f32).to_degrees();
+		let octant = ((angle + 22.5).rem_euclid(360.0) / 45.0) as i32;
+		match octant {
+			0 => { Direction::E }
+			1 => { Direction::NE }
+			2 => { Direction::N }
+			3 => { Direction::NW }
+			4 => { Direction::W }
+			5 => { Direction::SW }
+			6 => { Direction::S }
+			_ => { Direction::SE }
+		}
+	}
+	/// Converts the Direction into an (x, y, z) offset; note that the z-offset is a change of deck
+	pub fn to_offset(&self) -> (i32, i32, i32) {
+		match self {
+			Direction::X    => { ( 0,  0,  0) }
+			Direction::N    => { ( 0, -1,  0) }
+			Direction::NW   => { (-1, -1,  0) }
+			Direction::W    => { (-1,  0,  0) }
+			Direction::SW   => { (-1,  1,  0) }
+			Direction::S    => { ( 0,  1,  0) }
+			Direction::SE   => { ( 1,  1,  0) }
+			Direction::E    => { ( 1,  0,  0) }
+			Direction::NE   => { ( 1, -1,  0) }
+			Direction::UP   => { ( 0,  0,  1) }
+			Direction::DOWN => { ( 0,  0, -1) }
+		}
+	}
+}
 impl fmt::Display for Direction {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let text: String = match self {
@@ -659,6 +1147,105 @@ impl fmt::Display for Direction {
 		write!(f, "{}", text)
 	}
 }
+impl Direction {
+	/// Pairs the Direction with the set of names that it should be shown with, ie `dir.named(prefs.dir_names)`
+	pub fn named(self, names: DirNames) -> NamedDirection {
+		NamedDirection { dir: self, names }
+	}
+}
+//   ##: DirNames
+/// The sets of names that a Direction can be shown to the player with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum DirNames {
+	#[default]
+	Compass, // North, Southeast, &c
+	Ship, // Fore, Aft-starboard, &c, with the bow pointing north
+}
+impl DirNames {
+	/// Returns the other set of names, for toggling between them from the menu
+	pub fn next(&self) -> DirNames {
+		match self {
+			DirNames::Compass => DirNames::Ship,
+			DirNames::Ship => DirNames::Compass,
+		}
+	}
+}
+//   ##: NamedDirection
+/// A Direction along with the set of names to show it with; the Display impl is where the ship-frame names are kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NamedDirection {
+	pub dir: Direction,
+	pub names: DirNames,
+}
+impl fmt::Display for NamedDirection {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.names == DirNames::Compass { return write!(f, "{}", self.dir); }
+		let text = match self.dir {
+			Direction::X    => { "null_dir" }
+			Direction::N    => { "Fore" }
+			Direction::NW   => { "Fore-port" }
+			Direction::W    => { "Port" }
+			Direction::SW   => { "Aft-port" }
+			Direction::S    => { "Aft" }
+			Direction::SE   => { "Aft-starboard" }
+			Direction::E    => { "Starboard" }
+			Direction::NE   => { "Fore-starboard" }
+			Direction::UP   => { "Above decks" }
+			Direction::DOWN => { "Below decks" }
+		};
+		write!(f, "{}", text)
+	}
+}
+//   ##: DistanceBand
+/// The rough distance brackets that relative_phrase() sorts a target into, counting diagonal steps the same as
+/// orthogonal ones; this is also where the catalogue of wording for each bracket is kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DistanceBand {
+	Here,     // The very same tile
+	Adjacent, // One step away
+	Near,     // Up to NEAR_STEPS away
+	Far,      // Anything further than that on the same deck
+	Above,    // Somewhere on a higher deck
+	Below,    // Somewhere on a lower deck
+}
+impl DistanceBand {
+	/// The furthest that a target can be and still count as Near
+	pub const NEAR_STEPS: i32 = 5;
+	/// Sorts the target into its bracket, as seen by someone standing at the origin
+	pub fn between(from: Position, to: Position) -> DistanceBand {
+		if to.z > from.z { return DistanceBand::Above; }
+		if to.z < from.z { return DistanceBand::Below; }
+		match (to.x - from.x).abs().max((to.y - from.y).abs()) {
+			0 => { DistanceBand::Here }
+			1 => { DistanceBand::Adjacent }
+			x if x <= DistanceBand::NEAR_STEPS => { DistanceBand::Near }
+			_ => { DistanceBand::Far }
+		}
+	}
+	/// The wording for this bracket; any "{dir}" gets filled in with the name of the direction that the target's in
+	pub fn phrase(&self, names: DirNames) -> &'static str {
+		match (self, names) {
+			(DistanceBand::Here, _)                  => { "right here" }
+			(DistanceBand::Adjacent, _)              => { "right next to you, to the {dir}" }
+			(DistanceBand::Near, _)                  => { "nearby to the {dir}" }
+			(DistanceBand::Far, _)                   => { "far to the {dir}" }
+			(DistanceBand::Above, DirNames::Compass) => { "somewhere on a deck above" }
+			(DistanceBand::Below, DirNames::Compass) => { "somewhere on a deck below" }
+			(DistanceBand::Above, DirNames::Ship)
+			| (DistanceBand::Below, DirNames::Ship)  => { "somewhere {dir}" }
+		}
+	}
+	/// The abbreviated wording for this bracket on the PLANQ's narrow display, with "{dir}" as above and "{n}"
+	/// standing for the number of steps, or of decks
+	pub fn compact(&self) -> &'static str {
+		match self {
+			DistanceBand::Here  => { "here" }
+			DistanceBand::Above => { "UP {n}" }
+			DistanceBand::Below => { "DN {n}" }
+			_                   => { "{dir} {n}" }
+		}
+	}
+}
 //   ##: Position
 /// Represents a point on a 2D grid as an XY pair, plus a Z-coordinate to indicate what floor the entity is on
 #[derive(Component, Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
@@ -867,4 +1454,180 @@ impl std::ops::Sub<Position> for Position {
  * but that isn't useful right now since I have no physics to worry about
 */
 
+//  ###: HELPER FXNS
+/// Maps a single Entity ID for a MapEntities impl; the PLACEHOLDER stands for 'nothing', so it has to stay as it is
+/// rather than being given a freshly reserved Entity
+pub fn map_entity(entity_mapper: &mut EntityMapper, entity: Entity) -> Entity {
+	if entity == Entity::PLACEHOLDER { return entity; }
+	entity_mapper.get_or_reserve(entity)
+}
+/// Describes where the target is, as seen by someone standing at the origin: "nearby to the northeast", &c
+/// The wording itself comes from the DistanceBand catalogue
+pub fn relative_phrase(from: Position, to: Position, names: DirNames) -> String {
+	let band = DistanceBand::between(from, to);
+	band.phrase(names).replace("{dir}", &relative_heading(from, to).named(names).to_string().to_lowercase())
+}
+/// The compact form of relative_phrase() for the PLANQ's narrow display, ie "NE 12", or "UP 2" for another deck
+pub fn relative_compact(from: Position, to: Position) -> String {
+	let band = DistanceBand::between(from, to);
+	let steps = if from.z != to.z { (to.z - from.z).abs() } else { (to.x - from.x).abs().max((to.y - from.y).abs()) };
+	band.compact().replace("{dir}", relative_heading(from, to).as_ref()).replace("{n}", &steps.to_string())
+}
+/// Picks the Direction that the target lies in from the origin, going by the deck first and then by the heading
+fn relative_heading(from: Position, to: Position) -> Direction {
+	if to.z > from.z { return Direction::UP; }
+	if to.z < from.z { return Direction::DOWN; }
+	Direction::from_heading(to.x - from.x, to.y - from.y)
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Device
+	#[test]
+	fn wear_builds_up_until_the_device_breaks_down() {
+		let mut device = Device::new(0);
+		assert!(device.power_on());
+		assert!(!device.wear_down(Device::WEAR_LIMIT - 1));
+		assert_eq!(device.durability(), 1);
+		assert_eq!(device.state, DeviceState::Idle);
+		assert!(device.wear_down(1));
+		assert_eq!(device.state, DeviceState::Error(Device::WORN_OUT));
+		assert!(!device.power_on());
+		// A device that's already broken doesn't break down all over again
+		assert!(!device.wear_down(1));
+	}
+	#[test]
+	fn repair_clears_the_wear_and_the_error() {
+		let mut device = Device::new(0);
+		device.power_on();
+		device.wear_down(Device::WEAR_LIMIT);
+		device.repair();
+		assert_eq!(device.durability(), Device::WEAR_LIMIT);
+		assert_eq!(device.state, DeviceState::Idle);
+	}
+	//  ##: MoveStyle
+	#[test]
+	fn footsteps_get_louder_with_the_load() {
+		let style = MoveStyle::default();
+		assert_eq!(style.volume(0.0), 2);
+		assert_eq!(style.volume(0.5), 5);
+		assert_eq!(style.volume(1.0), 8);
+		// Overloading still gets louder, but never past the loudest a step can be
+		assert_eq!(style.volume(1.5), MoveStyle::MAX_VOLUME);
+		assert_eq!(style.volume(3.0), MoveStyle::MAX_VOLUME);
+	}
+	#[test]
+	fn careful_steps_are_half_as_loud_and_twice_as_long() {
+		let style = MoveStyle { careful: true, ..MoveStyle::default() };
+		assert_eq!(style.volume(0.0), 1);
+		assert_eq!(style.volume(1.0), 4);
+		assert_eq!(style.volume(2.0), 7);
+		assert_eq!(style.time_cost(), 2);
+		assert_eq!(MoveStyle::default().time_cost(), 1);
+	}
+	#[test]
+	fn the_meter_reads_the_last_step_as_a_percentage() {
+		let mut style = MoveStyle::default();
+		assert_eq!(style.meter_percent(), 0);
+		style.last_volume = 4;
+		assert_eq!(style.meter_percent(), 40);
+		style.last_volume = MoveStyle::MAX_VOLUME;
+		assert_eq!(style.meter_percent(), 100);
+	}
+	//  ##: DirNames
+	#[test]
+	fn the_direction_names_toggle_back_and_forth() {
+		assert_eq!(DirNames::default(), DirNames::Compass);
+		assert_eq!(DirNames::Compass.next(), DirNames::Ship);
+		assert_eq!(DirNames::Ship.next(), DirNames::Compass);
+	}
+	#[test]
+	fn each_mode_names_the_directions_its_own_way() {
+		let named = [
+			(Direction::N,    "North",     "Fore"),
+			(Direction::NE,   "Northeast", "Fore-starboard"),
+			(Direction::E,    "East",      "Starboard"),
+			(Direction::SE,   "Southeast", "Aft-starboard"),
+			(Direction::S,    "South",     "Aft"),
+			(Direction::SW,   "Southwest", "Aft-port"),
+			(Direction::W,    "West",      "Port"),
+			(Direction::NW,   "Northwest", "Fore-port"),
+			(Direction::UP,   "Up",        "Above decks"),
+			(Direction::DOWN, "Down",      "Below decks"),
+		];
+		for (dir, compass, ship) in named {
+			// The compass names are just the Direction's own
+			assert_eq!(dir.named(DirNames::Compass).to_string(), compass);
+			assert_eq!(dir.named(DirNames::Compass).to_string(), dir.to_string());
+			assert_eq!(dir.named(DirNames::Ship).to_string(), ship);
+		}
+	}
+	//  ##: DistanceBand
+	/// Each octant's unit offset, along with its compass and ship names and its compact abbreviation
+	const OCTANTS: [((i32, i32), &str, &str, &str); 8] = [
+		(( 0, -1), "north",     "fore",           "N"),
+		(( 1, -1), "northeast", "fore-starboard", "NE"),
+		(( 1,  0), "east",      "starboard",      "E"),
+		(( 1,  1), "southeast", "aft-starboard",  "SE"),
+		(( 0,  1), "south",     "aft",            "S"),
+		((-1,  1), "southwest", "aft-port",       "SW"),
+		((-1,  0), "west",      "port",           "W"),
+		((-1, -1), "northwest", "fore-port",      "NW"),
+	];
+	#[test]
+	fn every_distance_and_octant_has_its_own_phrase() {
+		let from = Position::new(50, 50, 1);
+		// The edges of each bracket, so that an off-by-one in either direction gets caught
+		let bands = [
+			(1,  DistanceBand::Adjacent, "right next to you, to the "),
+			(2,  DistanceBand::Near,     "nearby to the "),
+			(5,  DistanceBand::Near,     "nearby to the "),
+			(6,  DistanceBand::Far,      "far to the "),
+			(40, DistanceBand::Far,      "far to the "),
+		];
+		for ((x_dir, y_dir), compass, ship, short) in OCTANTS {
+			for (steps, band, lead) in bands {
+				// Exact diagonals count their steps the same as the orthogonals
+				let to = Position::new(from.x + x_dir * steps, from.y + y_dir * steps, from.z);
+				assert_eq!(DistanceBand::between(from, to), band, "{} {}", compass, steps);
+				assert_eq!(relative_phrase(from, to, DirNames::Compass), format!("{}{}", lead, compass));
+				assert_eq!(relative_phrase(from, to, DirNames::Ship), format!("{}{}", lead, ship));
+				assert_eq!(relative_compact(from, to), format!("{} {}", short, steps));
+			}
+		}
+	}
+	#[test]
+	fn the_same_tile_and_other_decks_have_no_heading() {
+		let from = Position::new(50, 50, 1);
+		assert_eq!(DistanceBand::between(from, from), DistanceBand::Here);
+		assert_eq!(relative_phrase(from, from, DirNames::Compass), "right here");
+		assert_eq!(relative_phrase(from, from, DirNames::Ship), "right here");
+		assert_eq!(relative_compact(from, from), "here");
+		// Another deck is reported the same way no matter how far across the deck the target is
+		for (x_diff, y_diff) in [(0, 0), (1, 1), (-30, 2)] {
+			let above = Position::new(from.x + x_diff, from.y + y_diff, 3);
+			let below = Position::new(from.x + x_diff, from.y + y_diff, 0);
+			assert_eq!(DistanceBand::between(from, above), DistanceBand::Above);
+			assert_eq!(DistanceBand::between(from, below), DistanceBand::Below);
+			assert_eq!(relative_phrase(from, above, DirNames::Compass), "somewhere on a deck above");
+			assert_eq!(relative_phrase(from, below, DirNames::Compass), "somewhere on a deck below");
+			assert_eq!(relative_phrase(from, above, DirNames::Ship), "somewhere above decks");
+			assert_eq!(relative_phrase(from, below, DirNames::Ship), "somewhere below decks");
+			assert_eq!(relative_compact(from, above), "UP 2");
+			assert_eq!(relative_compact(from, below), "DN 1");
+		}
+	}
+	#[test]
+	fn an_off_axis_target_gets_the_nearest_octant() {
+		let from = Position::new(0, 0, 0);
+		// Mostly east with a little south is still east, where from_offset() would have called it southeast
+		assert_eq!(relative_phrase(from, Position::new(5, 1, 0), DirNames::Compass), "nearby to the east");
+		assert_eq!(relative_phrase(from, Position::new(5, 4, 0), DirNames::Compass), "nearby to the southeast");
+		assert_eq!(relative_phrase(from, Position::new(-2, -7, 0), DirNames::Compass), "far to the north");
+		assert_eq!(relative_compact(from, Position::new(-2, -7, 0)), "N 7");
+	}
+}
+
 // EOF
@@ -0,0 +1,344 @@
+// decks.rs
+// Provides the persistence boundary between the ship's decks, so that a deck can be taken out of the World and put back
+// later without anything on it being reset, ie the doors that were left open or the items that were dropped there
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::entity::{EntityMap, MapEntities};
+use bevy::utils::{HashMap, HashSet};
+use bevy_save::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::event::ResyncViewsheds;
+use crate::engine::preferences::Preferences;
+use crate::objectives::Objectives;
+use crate::planq::PlanqData;
+use crate::power::PowerGrid;
+use crate::quickslots::QuickSlots;
+use crate::robot::TaskQueue;
+use crate::worldmap::WorldModel;
+
+// ###: BEVY SYSTEMS
+/// Keeps only the player's own deck in the World if the player has asked for that, unloading each deck as they leave
+/// it and putting it back when they return; if the preference gets switched off, every deck is put back at once
+pub fn deck_streaming_system(world: &mut World, mut last_deck: Local<Option<i32>>) {
+	let enabled = world.get_resource::<Preferences>().map_or(false, |x| x.unload_decks);
+	let mut p_query = world.query_filtered::<&Body, With<Player>>();
+	let deck = if let Ok(value) = p_query.get_single(world) { value.ref_posn.z } else { return };
+	let previous = last_deck.replace(deck);
+	if !enabled {
+		restore_all_decks(world);
+		return;
+	}
+	match previous {
+		Some(value) if value != deck => { switch_deck(world, value, deck); }
+		_ => { }
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: DeckData
+/// Everything that was on a deck when it was serialized, ready to be put back into the World
+pub struct DeckData {
+	pub deck: i32,
+	pub snapshot: Snapshot,
+	pub members: Vec<Entity>, // The IDs that the entities had when they were serialized, for remapping on their return
+	pub count: usize, // The number of entities in the snapshot, for reporting
+}
+//  ##: UnloadedDecks
+/// Holds on to any decks that have been taken out of the World; the save system puts them all back before saving, so
+/// that nothing on them is lost from the save file
+#[derive(Resource, Default)]
+pub struct UnloadedDecks {
+	pub decks: HashMap<i32, DeckData>,
+}
+impl UnloadedDecks {
+	pub fn is_unloaded(&self, deck: i32) -> bool {
+		self.decks.contains_key(&deck)
+	}
+}
+
+// ###: METHODS
+/// Returns every entity that belongs to the given deck: anything with a Body on that deck, plus anything that is being
+/// carried, at any depth, by something on that deck
+/// The player and everything they're carrying travel with them, so they never belong to a deck
+pub fn deck_members(world: &mut World, deck: i32) -> Vec<Entity> {
+	let mut b_query = world.query_filtered::<(Entity, &Body, Option<&Portable>), Without<Player>>();
+	let bodies: HashMap<Entity, (i32, Entity)> = b_query.iter(world)
+		.map(|(b_enty, b_body, b_portable)| (b_enty, (b_body.ref_posn.z, b_portable.map_or(Entity::PLACEHOLDER, |x| x.carrier))))
+		.collect();
+	bodies.iter().filter(|(b_enty, _)| {
+		// Follow the chain of carriers up to whoever is holding the whole stack; a missing link means the player has it
+		let mut current = **b_enty;
+		for _ in 0..bodies.len() {
+			match bodies.get(&current) {
+				Some((z, carrier)) if *carrier == Entity::PLACEHOLDER => { return *z == deck; }
+				Some((_, carrier)) => { current = *carrier; }
+				None => { return false; }
+			}
+		}
+		false // Somebody's holding something that's holding them; leave it where it is
+	}).map(|(b_enty, _)| *b_enty).collect()
+}
+/// Takes a copy of everything that belongs to the given deck, without changing the World
+pub fn serialize_deck(world: &mut World, deck: i32) -> DeckData {
+	let members = deck_members(world, deck);
+	let count = members.len();
+	let snapshot = Snapshot::builder(world).extract_entities(members.iter().copied()).build();
+	DeckData { deck, snapshot, members, count }
+}
+/// Serializes the given deck and then removes everything on it from the World, ie to save memory
+/// Anything outside of the deck that points at one of its entities will be left pointing at nothing until the deck is
+/// restored; the components and resources that implement MapEntities are brought up to date when it is
+pub fn unload_deck(world: &mut World, deck: i32) -> DeckData {
+	let members = deck_members(world, deck);
+	let snapshot = Snapshot::builder(world).extract_entities(members.iter().copied()).build();
+	let data = DeckData { deck, snapshot, members: members.clone(), count: members.len() };
+	let mut placed = Vec::new();
+	for enty in members.iter() {
+		if let Some(body) = world.get::<Body>(*enty) {
+			placed.push((*enty, body.posns()));
+		}
+	}
+	if let Some(mut model) = world.get_resource_mut::<WorldModel>() {
+		for (enty, posns) in placed.iter() {
+			model.remove_contents(posns, *enty);
+		}
+	}
+	for enty in members {
+		world.despawn(enty);
+	}
+	info!("* unloaded deck {}: {} entities", deck, data.count); // DEBUG: announce a deck unload
+	data
+}
+/// Puts the contents of a serialized deck back into the World; the restored entities get new IDs, but they keep all of
+/// the state that they had when the deck was serialized, and anything that pointed at their old IDs is remapped
+pub fn restore_deck(world: &mut World, data: &DeckData) {
+	let before: HashSet<Entity> = world.query::<Entity>().iter(world).collect();
+	// Everything that's already here maps to itself, so that only the deck's own references get changed
+	let mut entity_map = EntityMap::default();
+	for enty in before.iter() {
+		entity_map.insert(*enty, *enty);
+	}
+	for old_enty in data.members.iter() {
+		let new_enty = world.spawn_empty().id();
+		entity_map.insert(*old_enty, new_enty);
+	}
+	// By default the applier despawns everything that isn't in the snapshot, which would take the player and every other
+	// deck along with it
+	let applier = data.snapshot.applier(world).map(entity_map.clone()).despawn(DespawnMode::None).mapping(MappingMode::Strict);
+	if let Err(e) = applier.apply() {
+		error!("! could not restore deck {}: {}", data.deck, e); // DEBUG: report a failed deck restore
+		return;
+	}
+	// The map's occupancy lists only know about the entities that are lying out in the open
+	let mut b_query = world.query::<(Entity, &Body, Option<&Portable>)>();
+	let placed: Vec<(Entity, Vec<Position>)> = b_query.iter(world)
+		.filter(|(b_enty, _, b_portable)| !before.contains(b_enty) && b_portable.map_or(true, |x| x.carrier == Entity::PLACEHOLDER))
+		.map(|(b_enty, b_body, _)| (b_enty, b_body.posns()))
+		.collect();
+	if let Some(mut model) = world.get_resource_mut::<WorldModel>() {
+		for (enty, posns) in placed.iter() {
+			model.add_contents(posns, 0, *enty);
+		}
+	}
+	map_resource_entities(world, &mut entity_map);
+	map_component_entities(world, &entity_map);
+	if let Some(mut resync) = world.get_resource_mut::<Events<ResyncViewsheds>>() {
+		resync.send(ResyncViewsheds);
+	}
+	info!("* restored deck {}: {} entities", data.deck, data.count); // DEBUG: announce a deck restore
+}
+/// Unloads the deck that's being left and puts back the one that's being entered, if it had been unloaded before
+pub fn switch_deck(world: &mut World, from: i32, to: i32) {
+	if !world.contains_resource::<UnloadedDecks>() { return; }
+	let data = unload_deck(world, from);
+	let mut unloaded = world.resource_mut::<UnloadedDecks>();
+	unloaded.decks.insert(from, data);
+	let arriving = unloaded.decks.remove(&to);
+	if let Some(data) = arriving {
+		restore_deck(world, &data);
+	}
+}
+/// Brings the Entity IDs that are held by the saveable resources up to date after some of the entities have been given
+/// new IDs, ie when a deck is restored; the map has to cover every entity that's still around, or the resources will
+/// lose track of them
+pub fn map_resource_entities(world: &mut World, entity_map: &mut EntityMap) {
+	entity_map.world_scope(world, |world, entity_mapper| {
+		if let Some(mut planq) = world.get_resource_mut::<PlanqData>() {
+			planq.map_entities(entity_mapper);
+		}
+		if let Some(mut slots) = world.get_resource_mut::<QuickSlots>() {
+			slots.map_entities(entity_mapper);
+		}
+		if let Some(mut grid) = world.get_resource_mut::<PowerGrid>() {
+			grid.map_entities(entity_mapper);
+		}
+		if let Some(mut objectives) = world.get_resource_mut::<Objectives>() {
+			objectives.map_entities(entity_mapper);
+		}
+		if let Some(mut queue) = world.get_resource_mut::<TaskQueue>() {
+			queue.map_entities(entity_mapper);
+		}
+	});
+}
+/// Does the same for the Memory of every entity that can remember things, so that the player still recognizes what they
+/// saw on a deck after it comes back; any ID that isn't in the map is left alone, since it might belong to a deck that's
+/// still unloaded and will need to be matched up when that one comes back
+pub fn map_component_entities(world: &mut World, entity_map: &EntityMap) {
+	let mut m_query = world.query::<&mut Memory>();
+	for mut m_memory in m_query.iter_mut(world) {
+		for enty in m_memory.visual.values_mut().flatten() {
+			if let Some(new_enty) = entity_map.get(*enty) {
+				*enty = new_enty;
+			}
+		}
+	}
+}
+/// Puts every unloaded deck back into the World, ie before the game is saved
+pub fn restore_all_decks(world: &mut World) {
+	let decks = if let Some(mut unloaded) = world.get_resource_mut::<UnloadedDecks>() {
+		std::mem::take(&mut unloaded.decks)
+	} else {
+		return
+	};
+	for data in decks.values() {
+		restore_deck(world, data);
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::camera::ScreenCell;
+	use crate::engine::register_saveables;
+	use crate::robot::{Maintainer, MaintenanceJob, Task};
+	use super::*;
+	//  ##: Deck streaming
+	/// Sets up a headless App that streams the decks, with the player on deck 1
+	/// The types are registered by the engine itself, so that anything it forgets to register is lost here as well
+	fn deck_app() -> (App, Entity) {
+		let mut app = App::new();
+		app.add_plugins(MinimalPlugins).add_plugins(SavePlugins);
+		register_saveables(&mut app);
+		app.add_event::<ResyncViewsheds>()
+		.insert_resource(PlanqData::new())
+		.insert_resource(Preferences { unload_decks: true, ..Preferences::default() })
+		.insert_resource(UnloadedDecks::default())
+		.add_systems(Update, deck_streaming_system);
+		let player = app.world.spawn((Player { }, Body { ref_posn: Position::new(2, 2, 1), ..Body::default() })).id();
+		app.update();
+		(app, player)
+	}
+	/// Moves the player onto the given deck and runs an update so that the decks get swapped
+	fn move_player(app: &mut App, player: Entity, deck: i32) {
+		app.world.get_mut::<Body>(player).unwrap().move_to(Position::new(2, 2, deck));
+		app.update();
+	}
+	/// Returns the state of every door in the World
+	fn doors(app: &mut App) -> Vec<bool> {
+		app.world.query::<&Openable>().iter(&app.world).map(|x| x.is_open).collect()
+	}
+	#[test]
+	fn open_door_stays_open_across_an_unload() {
+		let (mut app, player) = deck_app();
+		let mut door = Openable::new(false, "/", "+");
+		door.is_open = true;
+		app.world.spawn((Body { ref_posn: Position::new(5, 2, 1), ..Body::default() }, Description::new().name("door"), door));
+		move_player(&mut app, player, 2);
+		assert!(app.world.resource::<UnloadedDecks>().is_unloaded(1));
+		assert!(doors(&mut app).is_empty());
+		move_player(&mut app, player, 1);
+		assert!(!app.world.resource::<UnloadedDecks>().is_unloaded(1));
+		assert_eq!(doors(&mut app), vec![true]);
+		assert!(app.world.get::<Player>(player).is_some());
+	}
+	#[test]
+	fn restoring_a_deck_leaves_the_rest_alone() {
+		let (mut app, player) = deck_app();
+		app.world.spawn((Body { ref_posn: Position::new(5, 2, 1), ..Body::default() }, Openable::new(false, "/", "+")));
+		let crate_enty = app.world.spawn((Body { ref_posn: Position::new(3, 3, 2), ..Body::default() }, Description::new().name("crate"))).id();
+		let data = serialize_deck(&mut app.world, 1);
+		restore_deck(&mut app.world, &data);
+		assert!(app.world.get::<Player>(player).is_some());
+		assert!(app.world.get::<Body>(crate_enty).is_some());
+		assert_eq!(doors(&mut app).len(), 2);
+	}
+	#[test]
+	fn restored_entities_keep_pointing_at_each_other() {
+		let (mut app, player) = deck_app();
+		let locker = app.world.spawn((Body { ref_posn: Position::new(5, 2, 1), ..Body::default() }, Description::new().name("locker"))).id();
+		app.world.spawn((Body { ref_posn: Position::new(5, 2, 1), ..Body::default() }, Description::new().name("wrench"), Portable::new(locker)));
+		app.world.resource_mut::<PlanqData>().jack_cnxn = locker;
+		move_player(&mut app, player, 2);
+		move_player(&mut app, player, 1);
+		let mut d_query = app.world.query::<(Entity, &Description, Option<&Portable>)>();
+		let restored: Vec<(Entity, String, Option<Entity>)> = d_query.iter(&app.world).map(|x| (x.0, x.1.name.clone(), x.2.map(|y| y.carrier))).collect();
+		let new_locker = restored.iter().find(|x| x.1 == "locker").map(|x| x.0).expect("the locker was not restored");
+		assert_ne!(new_locker, locker);
+		assert_eq!(restored.iter().find(|x| x.1 == "wrench").and_then(|x| x.2), Some(new_locker));
+		assert_eq!(app.world.resource::<PlanqData>().jack_cnxn, new_locker);
+	}
+	#[test]
+	fn every_component_survives_an_unload() {
+		let (mut app, player) = deck_app();
+		let locker = app.world.spawn((Body::small(Position::new(5, 2, 1), ScreenCell::new().glyph("&")), Description::new().name("locker"), Viewshed::new(3))).id();
+		app.world.spawn((Body::small(Position::new(5, 2, 1), ScreenCell::new().glyph("/")), Description::new().name("wrench"), Portable::new(locker), IsCarried::default()));
+		move_player(&mut app, player, 2);
+		move_player(&mut app, player, 1);
+		let mut l_query = app.world.query::<(&Description, &Body, &Viewshed)>();
+		let (_, l_body, l_view) = l_query.iter(&app.world).find(|x| x.0.name == "locker").expect("the locker was not restored with its Body and Viewshed");
+		assert_eq!(l_body.ref_posn, Position::new(5, 2, 1));
+		assert_eq!(l_body.extent[0].cell.glyph, "&");
+		assert_eq!(l_view.range, 3);
+		let mut w_query = app.world.query_filtered::<&Description, With<IsCarried>>();
+		assert_eq!(w_query.iter(&app.world).map(|x| x.name.clone()).collect::<Vec<String>>(), vec!["wrench".to_string()]);
+	}
+	#[test]
+	fn the_players_memory_follows_a_restored_deck() {
+		let (mut app, player) = deck_app();
+		let posn = Position::new(5, 2, 1);
+		let wrench = app.world.spawn((Body::small(posn, ScreenCell::new()), Description::new().name("wrench"))).id();
+		app.world.entity_mut(player).insert(Memory::new());
+		app.world.get_mut::<Memory>(player).unwrap().update(vec![(posn, Some(vec![wrench]))]);
+		move_player(&mut app, player, 2);
+		move_player(&mut app, player, 1);
+		let mut d_query = app.world.query::<(Entity, &Description)>();
+		let new_wrench = d_query.iter(&app.world).find(|x| x.1.name == "wrench").map(|x| x.0).expect("the wrench was not restored");
+		assert_ne!(new_wrench, wrench);
+		assert_eq!(app.world.get::<Memory>(player).unwrap().visual.get(&posn), Some(&vec![new_wrench]));
+	}
+	#[test]
+	fn a_robots_job_follows_a_restored_deck() {
+		let (mut app, player) = deck_app();
+		let posn = Position::new(5, 2, 1);
+		let pump = app.world.spawn((Body::small(posn, ScreenCell::new()), Description::new().name("pump"))).id();
+		let job = MaintenanceJob::Repair(pump);
+		app.world.spawn((Body::small(Position::new(4, 2, 1), ScreenCell::new()), Description::new().name("robot"), Maintainer { task: Some(Task::new(job, posn)), ..Maintainer::default() }));
+		app.world.insert_resource(TaskQueue::new());
+		app.world.resource_mut::<TaskQueue>().post(job, posn);
+		move_player(&mut app, player, 2);
+		move_player(&mut app, player, 1);
+		let mut d_query = app.world.query::<(Entity, &Description)>();
+		let new_pump = d_query.iter(&app.world).find(|x| x.1.name == "pump").map(|x| x.0).expect("the pump was not restored");
+		let mut w_query = app.world.query::<&Maintainer>();
+		let tasks: Vec<Option<MaintenanceJob>> = w_query.iter(&app.world).map(|x| x.task.map(|y| y.job)).collect();
+		assert_eq!(tasks, vec![Some(MaintenanceJob::Repair(new_pump))]);
+		assert_eq!(app.world.resource::<TaskQueue>().jobs[0].job, MaintenanceJob::Repair(new_pump));
+	}
+	#[test]
+	fn switching_the_preference_off_restores_every_deck() {
+		let (mut app, player) = deck_app();
+		app.world.spawn((Body { ref_posn: Position::new(5, 2, 1), ..Body::default() }, Openable::new(false, "/", "+")));
+		move_player(&mut app, player, 2);
+		assert!(doors(&mut app).is_empty());
+		app.world.resource_mut::<Preferences>().unload_decks = false;
+		app.update();
+		assert!(app.world.resource::<UnloadedDecks>().decks.is_empty());
+		assert_eq!(doors(&mut app), vec![false]);
+	}
+}
+
+// EOF
@@ -9,8 +9,15 @@ use std::fmt::{Display, Formatter, Result};
 use std::borrow::Cow;
 
 //  ###: INTERNAL LIBS
-use crate::components::Direction;
+use crate::components::{Body, Direction, Openable, Portable, Position, ReachProfile};
 use crate::engine::EngineMode;
+use crate::worldmap::{has_line_of_sight, LevelEnvironment, WorldModel};
+
+//  ###: CONSTANTS
+/// How close an actor needs to be to something to handle it, ie open it, use it, or pick it up
+pub const DEFAULT_REACH: i32 = 1;
+/// How close an actor needs to be to something to get a good look at it
+pub const EXAMINE_REACH: i32 = 2;
 
 //  ###: COMPLEX TYPES
 //   ##: GameEvent
@@ -31,12 +38,34 @@ impl GameEvent {
 				None => { Entity::PLACEHOLDER }
 				Some(enty) => { enty }
 			},
+			location: None,
+			destination: None,
 		};
 		GameEvent {
 			etype: new_type,
 			context: if new_context.is_blank() { None } else { Some(new_context) },
 		}
 	}
+	/// Creates an event for handing an item directly from one carrier to another, ie the player passing a tool to the LMR
+	/// The context is filled in as well, so that anything watching for the subject or object still sees it
+	pub fn give(from: Entity, item: Entity, to: Entity) -> GameEvent {
+		GameEvent::new(GameEventType::ItemGive { from, item, to }, Some(from), Some(item)).into_container(to)
+	}
+	/// Points the event at a map Position instead of (or as well as) an Entity, ie to examine a tile of terrain
+	pub fn at(mut self, target: Position) -> GameEvent {
+		let mut context = self.context.unwrap_or_default();
+		context.location = Some(target);
+		self.context = Some(context);
+		self
+	}
+	/// Names the Container that a MoveItem should put the object into, if it's not meant for the subject's own inventory,
+	/// or the salvage bin that a DropItem should put it into instead of the floor
+	pub fn into_container(mut self, target: Entity) -> GameEvent {
+		let mut context = self.context.unwrap_or_default();
+		context.destination = Some(target);
+		self.context = Some(context);
+		self
+	}
 	/// Checks the Event's context to make sure there are non-PLACEHOLDER values in the right places for the Action
 	pub fn is_valid(&self) -> bool {
 		match self.etype {
@@ -53,14 +82,24 @@ impl GameEvent {
 								context.subject != Entity::PLACEHOLDER
 							} else { false }
 						}
-						// Requires both a subject and an object
+						// Requires a subject, and either an object or a location
 						ActionType::Examine
-						| ActionType::UseItem
+						=> {
+							context.subject != Entity::PLACEHOLDER
+							&& (context.object != Entity::PLACEHOLDER || context.location.is_some())
+						}
+						// Requires both a subject and an object
+						ActionType::UseItem
 						| ActionType::MoveItem
 						| ActionType::DropItem
 						| ActionType::KillItem
 						| ActionType::OpenItem
 						| ActionType::CloseItem
+						| ActionType::LockItem
+						| ActionType::UnlockItem
+						| ActionType::HackItem
+						| ActionType::EmptyItem
+						| ActionType::AssignSlot
 						=> {
 							context.subject != Entity::PLACEHOLDER && context.object != Entity::PLACEHOLDER
 						}
@@ -75,6 +114,7 @@ impl GameEvent {
 				}
 			}
 			GameEventType::PlanqConnect(target) => { target != Entity::PLACEHOLDER && if let Some(context) = self.context { !context.is_blank() } else { false } }
+			GameEventType::ItemGive { from, item, to } => { ![from, item, to].contains(&Entity::PLACEHOLDER) && from != to }
 		}
 	}
 }
@@ -83,6 +123,140 @@ impl Display for GameEvent {
 		write!(f, "{}", self.etype)
 	}
 }
+//   ##: ActionFailed
+/// Tells the AI that one of its actor's actions couldn't go ahead, so that it can decide what to do about it
+/// A transient failure is one that ought to clear up by itself, ie a door that's still opening, or another actor that's
+/// standing in the way for the moment
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ActionFailed {
+	pub actor: Entity,
+	pub action: ActionType,
+	pub transient: bool,
+}
+//   ##: ContainerOpened
+/// Announces that a Container has just been opened, so that anything waiting on that can react, ie to stock it with
+/// loot or to spring a trap on whoever opened it
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ContainerOpened {
+	pub container: Entity,
+	pub opener: Entity,
+}
+//   ##: Noise
+/// Describes a sound made somewhere in the world; anything that Hears within the volume's range of it can notice
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Noise {
+	pub source: Position,
+	pub volume: i32, // How far the sound carries, in tiles
+	pub maker: Entity,
+}
+//   ##: PlayerMoved
+/// Announces that the player has moved to a new Position, so that anything tracking them doesn't need to poll
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerMoved {
+	pub posn: Position,
+}
+//   ##: AmbientCue
+/// Marks a moment that deserves some atmosphere, ie arriving on a new deck; for now these become lines in the message
+/// log, but an audio backend can listen for them just as well
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmbientCue {
+	pub cue: CueKind,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CueKind {
+	EnterDeck(LevelEnvironment), // The player has arrived on a deck in the given condition
+	HullBreach, // The player has just stepped out into vacuum
+}
+impl CueKind {
+	/// The line of ambiance to show in place of the cue's sound
+	pub fn line(&self) -> &'static str {
+		match self {
+			CueKind::EnterDeck(environment) => { environment.line() }
+			CueKind::HullBreach => { "The air roars past you and out into the void." }
+		}
+	}
+}
+//   ##: EntityKilled
+/// Announces that a gameplay entity has been despawned, so that anything still holding a reference to it can let go
+/// Send these through despawn_gameplay_entity() rather than by hand, so that the despawn and the event always match up
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct EntityKilled {
+	pub entity: Entity,
+	pub posns: Vec<Position>, // Everywhere that the entity was occupying on the map
+	pub policy: DespawnPolicy,
+}
+/// Decides what happens to the things that a despawned entity was carrying
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DespawnPolicy {
+	#[default]
+	DropContents, // Everything it held falls to the floor where it was
+	DestroyContents, // Everything it held is despawned along with it, all the way down
+}
+//   ##: DevicePowerChanged
+/// Announces that a Device has been switched on or off
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DevicePowerChanged {
+	pub device: Entity,
+	pub is_on: bool,
+}
+//   ##: InventoryChanged
+/// Announces that something was put into or taken out of the given carrier
+#[derive(Event, Clone, Copy, Debug)]
+pub struct InventoryChanged {
+	pub carrier: Entity,
+	pub item: Entity, // The thing that came or went, or Entity::PLACEHOLDER if several things went at once
+}
+//   ##: MissedEvents
+/// Keeps a note of every change that a polling fallback caught without the event that should have announced it, so
+/// that a missing emitter can be tracked down; the fallbacks only run in debug builds
+#[derive(Resource, Clone, Debug, Default)]
+pub struct MissedEvents {
+	pub missed: Vec<String>, // The name of each event that was missed, in the order that they were caught
+}
+impl MissedEvents {
+	/// Notes that the named event should have been sent, but wasn't
+	pub fn flag(&mut self, event_name: &str) {
+		self.missed.push(event_name.to_string());
+	}
+}
+//   ##: IdentifyItem
+/// Asks the identification_system to reveal an Unidentified item's true Description
+#[derive(Event, Clone, Copy, Debug)]
+pub struct IdentifyItem {
+	pub item: Entity,
+	pub identifier: Entity, // Whoever worked it out
+}
+//   ##: ObjectiveComplete
+/// Announces that the named objective has been met; each objective only sends this once
+#[derive(Event, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjectiveComplete {
+	pub name: String,
+}
+//   ##: PowerOutage
+/// Takes a generator off the grid for the given number of turns, or every generator if none is named
+/// This is how scripted blackouts are started; the power_grid_system brings the generator back once the time is up
+#[derive(Event, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PowerOutage {
+	pub generator: Option<String>,
+	pub duration: u64,
+}
+//   ##: ShipLogEvent
+/// Records something notable in the ship's own log, whether or not the player was in a position to notice it
+#[derive(Event, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShipLogEvent {
+	pub text: String,
+}
+impl ShipLogEvent {
+	pub fn new(text: &str) -> ShipLogEvent {
+		ShipLogEvent { text: text.to_string() }
+	}
+}
+//   ##: ResyncViewsheds
+/// Instructs the viewshed_resync_system to mark every Viewshed in the world as dirty, so that they all get recalculated
+/// Viewsheds cannot be saved (see the Viewshed defn), so this gets sent after a game load to rebuild them from the
+/// saved WorldMap.revealed_tiles and the current entity positions before the first frame is drawn
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct ResyncViewsheds;
 //   ##: GameEventType
 /// Provides the descriptors for GameEvents
 /// Unless otherwise noted, any relevant event info will be included as a GameEventContext
@@ -95,6 +269,7 @@ pub enum GameEventType {
 	PlayerAction(ActionType),
 	ActorAction(ActionType),
 	PlanqConnect(Entity),
+	ItemGive { from: Entity, item: Entity, to: Entity }, // hands an item straight from one carrier to another Container
 }
 impl Display for GameEventType {
 	fn fmt(&self, f: &mut Formatter<'_>) -> Result {
@@ -105,6 +280,7 @@ impl Display for GameEventType {
 			GameEventType::PlayerAction(action)  => { format!("{}", action) }
 			GameEventType::ActorAction(action)   => { format!("{}", action) }
 			GameEventType::PlanqConnect(target)  => { format!("{:?}", target) } // NOTE: just for debugging right now
+			GameEventType::ItemGive { .. }       => { "Give".to_string() }
 		};
 		let prim = output.as_str();
 		write!(f, "{}", prim)
@@ -127,6 +303,9 @@ pub enum ActionType {
 	CloseItem,          // Openable
 	LockItem,           // Lockable
 	UnlockItem,         // Lockable
+	HackItem,           // Lockable
+	EmptyItem,          // Container
+	AssignSlot,         // PLAYER: binds a carried item to a quick-slot, or unbinds it if it already has one
 }
 impl Display for ActionType {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -151,6 +330,9 @@ impl Display for ActionType {
 			ActionType::CloseItem    => { "Close".to_string() }
 			ActionType::LockItem     => { "Lock".to_string() }
 			ActionType::UnlockItem   => { "Unlock".to_string() }
+			ActionType::HackItem     => { "Hack".to_string() }
+			ActionType::EmptyItem    => { "Empty".to_string() }
+			ActionType::AssignSlot   => { "Quick-slot".to_string() }
 		};
 		// Trying to write the output var directly causes major borrow issues
 		// Using the output var as an interstitial allows us to use format! to build the string dynamically
@@ -158,6 +340,52 @@ impl Display for ActionType {
 		write!(f, "{}", prim)
 	}
 }
+impl ActionType {
+	/// Returns a short explanation of what the action does, for the context menu's help line
+	pub fn describe(&self) -> &'static str {
+		match self {
+			ActionType::NoAction     => { "" }
+			ActionType::Examine      => { "Take a closer look at it." }
+			ActionType::MoveTo(_)    => { "Walk one step in that direction." }
+			ActionType::Inventory    => { "Rummage through your belongings." }
+			ActionType::MoveItem     => { "Pick it up and carry it with you." }
+			ActionType::DropItem     => { "Put it down and leave it behind." }
+			ActionType::UseItem      => { "Switch it on or off, or otherwise operate it." }
+			ActionType::KillItem     => { "" }
+			ActionType::OpenItem     => { "Open it up." }
+			ActionType::CloseItem    => { "Shut it." }
+			ActionType::LockItem     => { "Lock it, if you have something that fits." }
+			ActionType::UnlockItem   => { "Unlock it, if you have something that fits." }
+			ActionType::HackItem     => { "Try to get past its lock without a key." }
+			ActionType::EmptyItem    => { "Tip out everything that's inside it." }
+			ActionType::AssignSlot   => { "Bind it to a quick-slot key, or free up the key it's bound to." }
+		}
+	}
+	/// Returns how close anyone must be to the action's object for the action to go ahead, or None if there's no limit
+	/// This is the base reach before any ReachProfiles are counted; see reach_for()
+	pub fn reach(&self) -> Option<i32> {
+		match self {
+			ActionType::NoAction
+			| ActionType::MoveTo(_)
+			| ActionType::Inventory
+			| ActionType::KillItem => { None }
+			ActionType::Examine => { Some(EXAMINE_REACH) }
+			_ => { Some(DEFAULT_REACH) }
+		}
+	}
+	/// Returns how close the given subject must be to the action's object, counting the subject's own ReachProfile and
+	/// the best one among the tools that it's carrying; the handler and the target recheck both use this
+	pub fn reach_for(&self, world: &World, subject: Entity) -> Option<i32> {
+		let base = self.reach()?;
+		let own = world.get::<ReachProfile>(subject).map_or(0, |x| x.extra);
+		let tool = world.iter_entities()
+			.filter(|x| x.get::<Portable>().map_or(false, |y| y.carrier == subject))
+			.filter_map(|x| x.get::<ReachProfile>().map(|y| y.extra))
+			.max()
+			.unwrap_or(0);
+		Some(base + own + tool.max(0))
+	}
+}
 impl From<ActionType> for Cow<'_, str> {
 	fn from(a_type: ActionType) -> Self {
 		let pack = Cow::Owned(format!("{}", a_type).clone());
@@ -173,21 +401,25 @@ impl From<ActionType> for Cow<'_, str> {
 pub struct GameEventContext {
 	pub subject: Entity, // the entity performing the action; by defn, only one
 	pub object: Entity, // the entity upon which the subject will perform the action
+	pub location: Option<Position>, // the map position upon which the action is performed, if it targets terrain
+	pub destination: Option<Entity>, // the carried Container that a moved item should go into, if not the subject itself
 }
 impl GameEventContext {
 	pub fn new(actor: Entity, target: Entity) -> GameEventContext {
 		GameEventContext {
 			subject: actor,
 			object: target,
+			location: None,
+			destination: None,
 		}
 	}
 	/// Returns true if either of the context elements are set to the Placeholder
 	pub fn is_partial(&self) -> bool {
 		self.subject == Entity::PLACEHOLDER || self.object == Entity::PLACEHOLDER
 	}
-	/// Returns true IFF both of the context elements are set to the Placeholder
+	/// Returns true IFF both of the context elements are set to the Placeholder and there's no location
 	pub fn is_blank(&self) -> bool {
-		self.subject == Entity::PLACEHOLDER && self.object == Entity::PLACEHOLDER
+		self.subject == Entity::PLACEHOLDER && self.object == Entity::PLACEHOLDER && self.location.is_none()
 	}
 }
 impl Default for GameEventContext {
@@ -195,6 +427,8 @@ impl Default for GameEventContext {
 		GameEventContext {
 			subject: Entity::PLACEHOLDER,
 			object: Entity::PLACEHOLDER,
+			location: None,
+			destination: None,
 		}
 	}
 }
@@ -202,15 +436,147 @@ impl MapEntities for GameEventContext { // Maintain Entity references wrt bevy_s
 	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
 		self.subject = entity_mapper.get_or_reserve(self.subject);
 		self.object = entity_mapper.get_or_reserve(self.object);
+		if let Some(target) = self.destination {
+			self.destination = Some(entity_mapper.get_or_reserve(target));
+		}
 	}
 }
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Returns true if the actor can affect the target from where it stands: some part of the target has to be within the
+/// given reach and in the actor's line of sight; anything that the actor is carrying can always be affected, and
+/// anything shut away inside of a closed container never can be
+pub fn can_affect(actor: Entity, target: Entity, reach: i32, world: &World) -> bool {
+	let carrier = world.get::<Portable>(target).map_or(Entity::PLACEHOLDER, |x| x.carrier);
+	if carrier == actor { return true; }
+	if carrier != Entity::PLACEHOLDER && world.get::<Openable>(carrier).map_or(false, |x| !x.is_open) { return false; }
+	let a_posn = if let Some(value) = world.get::<Body>(actor) { value.ref_posn } else { return false };
+	let t_body = if let Some(value) = world.get::<Body>(target) { value } else { return false };
+	let model = if let Some(value) = world.get_resource::<WorldModel>() { value } else { return false };
+	t_body.extent.iter().any(|part| part.posn.in_range_of(&a_posn, reach) && has_line_of_sight(model, a_posn, part.posn))
+}
 /// Allows comparison of two variant enums without regard to their type, ie
 ///   `ModeSwitch(Paused) == ModeSwitch(Running)`
 /// should return TRUE where Rust would return FALSE
 pub fn same_enum_variant<T>(a: &T, b: &T) -> bool {
 	std::mem::discriminant(a) == std::mem::discriminant(b)
 }
+/// Picks out the events for the given actions, and settles any that would fight over the same object in one frame, ie
+/// an open and a close on the same door; the order that the events arrive in depends on which systems happened to run
+/// first, so the winner is picked by something that doesn't: the player's actions win over anyone else's, then the
+/// actor with the lowest Entity index, and if it's the same actor twice then their last word on it stands
+/// Events that agree with the winner are kept, the rest are dropped and logged; the survivors keep their original order
+pub fn resolve_conflicts<'a>(events: impl Iterator<Item = &'a GameEvent>, actions: &[ActionType]) -> Vec<GameEvent> {
+	let action_of = |x: &GameEvent| match x.etype {
+		GameEventType::PlayerAction(action) | GameEventType::ActorAction(action) => { Some(action) }
+		_ => { None }
+	};
+	let candidates: Vec<GameEvent> = events.filter(|x| action_of(x).map_or(false, |y| actions.contains(&y))).copied().collect();
+	let rank = |x: &GameEvent| {
+		let subject = x.context.map_or(Entity::PLACEHOLDER, |y| y.subject);
+		(matches!(x.etype, GameEventType::PlayerAction(_)), std::cmp::Reverse(subject.index()))
+	};
+	let mut survivors = Vec::new();
+	for event in candidates.iter() {
+		let object = if let Some(context) = event.context { context.object } else { continue };
+		let rivals = candidates.iter().filter(|x| x.context.map_or(false, |y| y.object == object));
+		let winner = rivals.max_by_key(|x| rank(*x)).expect("the event should be one of its own rivals");
+		if action_of(event) == action_of(winner) {
+			survivors.push(*event);
+		} else {
+			info!("* event conflict on {:?}: dropped {} in favour of {}", object, event, winner); // DEBUG: log the losing side of an event conflict
+		}
+	}
+	survivors
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use GameEventType::*;
+	/// The actor and item that the events are passed between
+	const ACTOR: Entity = Entity::from_raw(1);
+	const ITEM: Entity = Entity::from_raw(2);
+	//  ##: Reach
+	#[test]
+	fn reach_counts_the_actor_and_its_best_tool() {
+		let mut world = World::new();
+		let actor = world.spawn_empty().id();
+		assert_eq!(ActionType::OpenItem.reach_for(&world, actor), Some(DEFAULT_REACH));
+		assert_eq!(ActionType::Examine.reach_for(&world, actor), Some(EXAMINE_REACH));
+		world.entity_mut(actor).insert(ReachProfile { extra: 1 });
+		world.spawn((ReachProfile { extra: 3 }, Portable::new(actor)));
+		world.spawn((ReachProfile { extra: 2 }, Portable::new(actor)));
+		// A tool that nobody's holding doesn't help anyone
+		world.spawn((ReachProfile { extra: 5 }, Portable::empty()));
+		assert_eq!(ActionType::OpenItem.reach_for(&world, actor), Some(DEFAULT_REACH + 1 + 3));
+		// Actions without a limit stay that way
+		assert_eq!(ActionType::KillItem.reach_for(&world, actor), None);
+	}
+	//  ##: Conflicts
+	/// Settles the given open/close events, and lists the surviving events as (action, subject)
+	fn settle(events: &[GameEvent]) -> Vec<(GameEventType, Entity)> {
+		resolve_conflicts(events.iter(), &[ActionType::OpenItem, ActionType::CloseItem]).iter()
+			.map(|x| (x.etype, x.context.map_or(Entity::PLACEHOLDER, |y| y.subject)))
+			.collect()
+	}
+	#[test]
+	fn the_same_conflict_settles_the_same_way_in_any_order() {
+		let (first, second) = (Entity::from_raw(3), Entity::from_raw(5));
+		let open = GameEvent::new(ActorAction(ActionType::OpenItem), Some(second), Some(ITEM));
+		let close = GameEvent::new(ActorAction(ActionType::CloseItem), Some(first), Some(ITEM));
+		// The actor with the lower index wins, whichever event went out first
+		let expected = vec![(ActorAction(ActionType::CloseItem), first)];
+		assert_eq!(settle(&[open, close]), expected);
+		assert_eq!(settle(&[close, open]), expected);
+		// But the player beats everybody
+		let player_open = GameEvent::new(PlayerAction(ActionType::OpenItem), Some(second), Some(ITEM));
+		let expected = vec![(PlayerAction(ActionType::OpenItem), second)];
+		assert_eq!(settle(&[player_open, close]), expected);
+		assert_eq!(settle(&[close, player_open]), expected);
+	}
+	#[test]
+	fn only_events_on_the_same_object_conflict() {
+		let other = Entity::from_raw(7);
+		let open = GameEvent::new(ActorAction(ActionType::OpenItem), Some(ACTOR), Some(ITEM));
+		let close = GameEvent::new(ActorAction(ActionType::CloseItem), Some(ACTOR), Some(other));
+		let examine = GameEvent::new(ActorAction(ActionType::Examine), Some(ACTOR), Some(ITEM));
+		assert_eq!(settle(&[open, examine, close]), vec![(ActorAction(ActionType::OpenItem), ACTOR), (ActorAction(ActionType::CloseItem), ACTOR)]);
+		// An actor that changes its mind within the frame gets its last word
+		let shut = GameEvent::new(ActorAction(ActionType::CloseItem), Some(ACTOR), Some(ITEM));
+		assert_eq!(settle(&[open, shut]), vec![(ActorAction(ActionType::CloseItem), ACTOR)]);
+	}
+	//  ##: Position targets
+	#[test]
+	fn an_event_can_point_at_a_tile() {
+		let spot = Position::new(4, 2, 1);
+		let event = GameEvent::new(PlayerAction(ActionType::Examine), Some(ACTOR), None).at(spot);
+		let context = event.context.expect("the event should have a context");
+		assert_eq!((context.subject, context.object, context.location), (ACTOR, Entity::PLACEHOLDER, Some(spot)));
+		assert!(event.is_valid());
+		// Without the tile, there's nothing to examine
+		assert!(!GameEvent::new(PlayerAction(ActionType::Examine), Some(ACTOR), None).is_valid());
+	}
+	#[test]
+	fn a_tile_alone_is_still_a_context() {
+		let spot = Position::new(4, 2, 1);
+		let event = GameEvent::new(PlayerAction(ActionType::Examine), None, None);
+		assert!(event.context.is_none());
+		let event = event.at(spot);
+		assert!(!event.context.expect("the tile should make a context").is_blank());
+		// But nobody is doing the examining
+		assert!(!event.is_valid());
+	}
+	#[test]
+	fn only_examining_can_make_do_with_a_tile() {
+		// Anything that handles an object still needs the object, wherever it's pointed
+		let event = GameEvent::new(PlayerAction(ActionType::OpenItem), Some(ACTOR), None).at(Position::new(1, 1, 0));
+		assert!(!event.is_valid());
+		let event = GameEvent::new(PlayerAction(ActionType::OpenItem), Some(ACTOR), Some(ITEM)).at(Position::new(1, 1, 0));
+		assert!(event.is_valid());
+		assert_eq!(event.context.map(|x| x.object), Some(ITEM));
+	}
+}
 
 // EOF
@@ -3,10 +3,11 @@
 
 //  ###: EXTERNAL LIBRARIES
 use bevy::ecs::event::Events;
+use bevy::utils::Duration;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 // crossterm::KeyEvent: https://docs.rs/crossterm/latest/crossterm/event/struct.KeyEvent.html
 // bevy::KeyboardInput: https://docs.rs/bevy/latest/bevy/input/keyboard/struct.KeyboardInput.html
-use tui_textarea::{Key, Input};
+use tui_textarea::{Key, Input, TextArea};
 
 //  ###: INTERNAL LIBRARIES
 use crate::components::*;
@@ -14,8 +15,11 @@ use crate::components::Direction;
 use crate::engine::*;
 use crate::engine::handler::ActionType::*;
 use crate::engine::event::*;
+use crate::engine::snapshot::{InputSnapshot, NearbyEntity};
 use crate::engine::event::GameEventType::*;
 use crate::planq::*;
+use crate::planq::dashboard::PlanqView;
+use crate::camera::draw_priority;
 //use crate::engine::planq::PlanqEventType::*;
 
 /// Parses the player inputs coming from ratatui and turns them into game logic
@@ -24,6 +28,7 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 	// Either you meant to send a control command somewhere else,
 	//  you forgot to defer/delegate the data query to a Bevy system,
 	//  or if you're trying to control the GameEngine, consider abstracting up to the GameEngine
+	// If the handler just needs to know what's around the player, add it to the InputSnapshot in engine/snapshot.rs
 	/* Because it is implemented in crossterm via ratatui, making it into a Bevy system
 	 * has so far been too difficult to finish, if not outright impossible
 	 * The game_events object below will monopolize the mutable ref to the game world
@@ -35,14 +40,68 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 		// Always allow the program to be closed via Ctrl-C
 		eng.quit();
 	}
-	// Extract entity ids for the player and the player's planq
-	let mut player_query = eng.bevy.world.query_filtered::<Entity, With<Player>>();
-	let player_ref = player_query.get_single(&eng.bevy.world);
-	let player = player_ref.unwrap_or(Entity::PLACEHOLDER);
+	// The loading screen only listens for the cancel key
+	if eng.loading.is_some() {
+		if key_event.code == KeyCode::Esc {
+			eng.cancel_loading();
+		}
+		return Ok(())
+	}
+	// Take a snapshot of what the handler needs to know about the player's surroundings, once, before anything changes
+	let snapshot = InputSnapshot::take(&mut eng.bevy.world);
+	let player = snapshot.player;
 	// ###: GAME CONTROL HANDLING
 	if eng.mode == EngineMode::Running {
 		let mut new_game_event = GameEvent::new(GameEventType::NullEvent, Some(player), None);
 		let mut new_planq_event = PlanqEvent::new(PlanqEventType::NullEvent);
+		// Any keypress will interrupt an auto-travel that's in progress, except for the key that cycles the destination
+		if key_event.code != KeyCode::Char('\'') {
+			let was_travelling = eng.bevy.world.get_resource_mut::<Bookmarks>().map_or(false, |mut x| x.destination.take().is_some());
+			if was_travelling {
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player("You stop travelling.");
+				}
+				return Ok(())
+			}
+		}
+		//  ##: MAP NOTE INPUT MODE
+		if eng.note_input.is_some() {
+			match key_event.code {
+				KeyCode::Esc => { // Throw the note away, leaving whatever was there before
+					eng.note_input = None;
+				}
+				KeyCode::Enter => { // Leave the note on its tile; an empty note scrubs out the old one
+					let (posn, input) = if let Some(value) = eng.note_input.take() { value } else { return Ok(()) };
+					let text = input.lines().join(" ");
+					let report = if let Some(mut notes) = eng.bevy.world.get_resource_mut::<MapNotes>() {
+						let had_note = notes.note_at(posn).is_some();
+						notes.set(posn, &text);
+						match (had_note, notes.note_at(posn).is_some()) {
+							(_, true) => { "You leave a note here." }
+							(true, false) => { "You scrub out the note here." }
+							(false, false) => { "You decide not to leave a note after all." }
+						}
+					} else {
+						return Ok(())
+					};
+					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+						msglog.tell_player(report);
+					}
+				}
+				the_input => {
+					if let Some((_, input)) = eng.note_input.as_mut() {
+						input.input(
+							Input {
+								key: keycode_to_input_key(the_input),
+								ctrl: false,
+								alt: false,
+							}
+						);
+					}
+				}
+			}
+			return Ok(())
+		}
 		// FIXME: once the show_cli_input flag is moved to the GameEngine, this get_resource_mut and unwrap() call can be moved
 		// into the conditional block below
 		let planq = &mut eng.bevy.world.get_resource_mut::<PlanqData>().expect("The PlanqData resource should have been loaded into Bevy");
@@ -52,22 +111,21 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				// close the CLI, do not run anything
 				KeyCode::Esc => { // Close and clear the input buffer
 					planq.show_cli_input = false; // Need to force it closed immediately, the system updates don't seem to work for this
+					eng.focus = PanelFocus::World;
+					eng.planq_stdin.input.move_cursor(tui_textarea::CursorMove::Head);
+					eng.planq_stdin.input.delete_line_by_end();
 					new_planq_event.etype = PlanqEventType::CliClose; // Still going to generate the event in case I use it for a hook later
 				}
 				KeyCode::Enter => { // Dispatch the input buffer to the parser
 					planq.show_cli_input = false;
+					eng.focus = PanelFocus::World; // Any app that the command opens will take the focus back
 					eng.planq_stdin.input.move_cursor(tui_textarea::CursorMove::Head);
 					eng.planq_stdin.input.delete_line_by_end();
 					let input_text = eng.planq_stdin.input.yank_text().to_string();
 					let echo_text = "[[fg:green]]>[[end]] ".to_string() + &*input_text;
+					// Hide the idle graphic straight away, rather than waiting for the monitor to notice the new output
+					planq.since_output = Duration::ZERO;
 					// WARN: We must finish working with the PLANQ reference before we can get the msglog
-					/*
-					// If there's an idle graphic enabled, we'll want to overwrite it instead of adding another line
-					if planq.cpu_mode == PlanqCPUMode::Idle {
-						let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap(); // Must keep these here to satisfy borrow checker
-						msglog.replace(echo_text.clone(), "planq".to_string(), 0, 0);
-					} else {
-					*/
 					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() { // See above ^^^
 						msglog.tell_planq(&echo_text);
 					}
@@ -89,6 +147,98 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			}
 			return Ok(()) // WARN: do not disable this, lest key inputs be parsed twice (ie again below) by mistake!
 		}
+		//  ##: PANEL FOCUS
+		// If the PLANQ's terminal has gone away (ie it was dropped or lost power), it can't keep the focus
+		if eng.focus == PanelFocus::Planq && !(planq.is_carried && planq.show_terminal) {
+			eng.focus = PanelFocus::World;
+		}
+		if key_event.code == KeyCode::Tab {
+			// The PLANQ can only take the focus if there's a terminal showing on it
+			let mut next_focus = eng.focus.next();
+			if next_focus == PanelFocus::Planq && !(planq.is_carried && planq.show_terminal) {
+				next_focus = next_focus.next();
+			}
+			eng.focus = next_focus;
+			return Ok(())
+		}
+		if eng.focus != PanelFocus::World && planq.current_view() == PlanqView::Terminal {
+			// The terminal and the log panels only scroll; anything else falls through to the usual game inputs
+			let scroll = if eng.focus == PanelFocus::Planq { &mut eng.planq_scroll } else { &mut eng.log_scroll };
+			match key_event.code {
+				KeyCode::Up       => { *scroll = scroll.saturating_add(1); return Ok(()) }
+				KeyCode::Down     => { *scroll = scroll.saturating_sub(1); return Ok(()) }
+				KeyCode::PageUp   => { *scroll = scroll.saturating_add(PAGE_SCROLL_LINES); return Ok(()) }
+				KeyCode::PageDown => { *scroll = scroll.saturating_sub(PAGE_SCROLL_LINES); return Ok(()) }
+				KeyCode::Home     => { *scroll = usize::MAX; return Ok(()) } // Clamped to the top of the backlog when drawn
+				KeyCode::End      => { *scroll = 0; return Ok(()) }
+				KeyCode::Esc      => { // Snap back to the latest lines and hand the keys back to the world
+					*scroll = 0;
+					eng.focus = PanelFocus::World;
+					return Ok(())
+				}
+				_ => { }
+			}
+		}
+		//  ##: PLANQ DASHBOARD
+		if eng.focus == PanelFocus::Planq && planq.current_view() == PlanqView::Dashboard {
+			match key_event.code {
+				KeyCode::Up    => { planq.dashboard.select_prev(); }
+				KeyCode::Down  => { planq.dashboard.select_next(); }
+				KeyCode::Enter => { planq.dashboard.toggle_drill(); }
+				KeyCode::Esc   => {
+					if planq.dashboard.drilled {
+						planq.dashboard.toggle_drill();
+					} else {
+						planq.pop_view();
+						eng.focus = PanelFocus::World;
+					}
+				}
+				_ => { }
+			}
+			return Ok(())
+		}
+		//  ##: PLANQ MINIMAP
+		if eng.focus == PanelFocus::Planq && planq.current_view() == PlanqView::Minimap {
+			if key_event.code == KeyCode::Esc {
+				planq.pop_view();
+				eng.focus = PanelFocus::World;
+			}
+			return Ok(())
+		}
+		//  ##: HAZARD CONFIRMATION
+		// A step into a hazard is held back until the player answers; anything other than 'y' calls it off
+		if let Some(dir) = eng.pending_move.take() {
+			if key_event.code == KeyCode::Char('y') || key_event.code == KeyCode::Char('Y') {
+				if let Some((hazard, target)) = hazard_ahead(eng, dir) {
+					eng.hazards_confirmed.push((hazard, target));
+				}
+				if let Some(mut game_events) = eng.bevy.world.get_resource_mut::<Events<GameEvent>>() {
+					game_events.send(GameEvent::new(PlayerAction(MoveTo(dir)), Some(player), None));
+				}
+			} else if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+				msglog.tell_player("You stay where you are.");
+			}
+			return Ok(())
+		}
+		//  ##: TRAVEL CONFIRMATION
+		// The previewed route stays on the camera until the player answers; anything other than 'y' clears it away
+		if eng.bevy.world.get_resource::<Bookmarks>().map_or(false, |x| x.pending.is_some()) {
+			let confirmed = key_event.code == KeyCode::Char('y') || key_event.code == KeyCode::Char('Y');
+			let report = if let Some(mut marks) = eng.bevy.world.get_resource_mut::<Bookmarks>() {
+				if confirmed {
+					marks.confirm_preview().map(|label| format!("You set off towards {}.", label))
+				} else {
+					marks.cancel_preview();
+					Some("You decide to stay put.".to_string())
+				}
+			} else {
+				None
+			};
+			if let (Some(report), Some(mut msglog)) = (report, eng.bevy.world.get_resource_mut::<MessageLog>()) {
+				msglog.tell_player(&report);
+			}
+			return Ok(())
+		}
 		//  ##: STANDARD GAME INPUTS
 		match key_event.code {
 			//   #: Meta/menu controls
@@ -97,6 +247,70 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				eng.pause_game();
 				return Ok(())
 			}
+			KeyCode::Char('H') => { // Toggle the mode/turn/clock HUD
+				eng.prefs.show_hud = !eng.prefs.show_hud;
+				eng.apply_prefs();
+				return Ok(())
+			}
+			KeyCode::Char('m') => { // MARK the player's current position, or remove the bookmark if there's one already
+				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
+					*value
+				} else {
+					return Ok(())
+				};
+				let room_name = eng.bevy.world.get_resource::<WorldModel>().and_then(|x| x.layout.get_room_name(p_posn));
+				let report = if let Some(mut marks) = eng.bevy.world.get_resource_mut::<Bookmarks>() {
+					if marks.remove_at(p_posn) {
+						"You remove the bookmark here.".to_string()
+					} else {
+						let label = room_name.unwrap_or(format!("Mark {}", marks.marks.len()));
+						marks.add(&label, p_posn);
+						format!("You bookmark this spot as {}.", label)
+					}
+				} else {
+					return Ok(())
+				};
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(&report);
+				}
+				return Ok(())
+			}
+			KeyCode::Char('N') => { // NOTE: write a note to leave on the player's current tile, or rewrite the one that's there
+				let p_posn = if let Some(value) = player_posn(eng) { value } else { return Ok(()) };
+				let old_note = eng.bevy.world.get_resource::<MapNotes>().and_then(|x| x.note_at(p_posn).cloned()).unwrap_or_default();
+				let mut input = TextArea::new(vec![old_note]);
+				input.move_cursor(tui_textarea::CursorMove::End);
+				eng.note_input = Some((p_posn, input));
+				return Ok(())
+			}
+			KeyCode::Char('M') => { // MOVE carefully: toggles between quiet-but-slow and normal movement
+				let report = if let Some(mut style) = eng.bevy.world.get_mut::<MoveStyle>(player) {
+					style.careful = !style.careful;
+					if style.careful { "You begin moving carefully." } else { "You stop moving carefully." }
+				} else {
+					return Ok(())
+				};
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(report);
+				}
+				return Ok(())
+			}
+			KeyCode::Char('\'') => { // TRAVEL to the next bookmark in the list, showing the route first if the player wants to see it
+				let report = if eng.prefs.preview_travel {
+					if let Some(value) = preview_next_bookmark(eng) { value } else { return Ok(()) }
+				} else if let Some(mut marks) = eng.bevy.world.get_resource_mut::<Bookmarks>() {
+					match marks.goto_next() {
+						Some(label) => { format!("You set off towards {}.", label) }
+						None => { "You haven't bookmarked anywhere yet.".to_string() }
+					}
+				} else {
+					return Ok(())
+				};
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(&report);
+				}
+				return Ok(())
+			}
 			KeyCode::Esc | KeyCode::Char('Q') => { // Close any open menus, or if none are open, open the main menu
 				eng.menu_context.reset();
 				if eng.visible_menu != MenuType::None {
@@ -155,300 +369,194 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			KeyCode::Char('n') => { new_game_event.etype = PlayerAction(MoveTo(Direction::SE));}
 			KeyCode::Char('>') => { new_game_event.etype = PlayerAction(MoveTo(Direction::DOWN));}
 			KeyCode::Char('<') => { new_game_event.etype = PlayerAction(MoveTo(Direction::UP));}
+			KeyCode::Char(num @ '1'..='9') => { // Use whatever's on the matching quick-slot
+				let index = num.to_digit(10).unwrap_or(1) as usize - 1;
+				match quick_slot_event(&eng.bevy.world, index, player) {
+					Ok(event) => { new_game_event = event; }
+					Err(reason) => {
+						if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+							msglog.tell_player(reason.as_str());
+						}
+						return Ok(())
+					}
+				}
+			}
 			//   #: Compound actions, context required: may require secondary inputs from player
 			KeyCode::Char('i') => { // INVENTORY the player's possessions and allow selection
 				let mut item_names = Vec::new();
-				// Get every Entity that has a Description, is Portable, and is currently being carried by someone
-				let mut backpack_query = eng.bevy.world.query::<(Entity, &Description, &Portable, &ActionSet)>();
-				for (i_enty, i_desc, i_portable, i_actions) in backpack_query.iter(&eng.bevy.world) {
-					debug!("* found item {}", i_desc.name.clone()); // DEBUG: report the item being worked on
-					if i_portable.carrier == player {
-						let mut menu_entries = Vec::new();
-						for action in i_actions.actions.iter() {
-							menu_entries.push(GameEvent::new(PlayerAction(*action), Some(player), Some(i_enty)));
+				// Get every Entity that has a Description, is Portable, and is currently being carried by the player
+				for item in snapshot.carried_by(player) {
+					debug!("* found item {}", item.name.clone()); // DEBUG: report the item being worked on
+					let actions = if let Some(value) = &item.actions { value } else { continue };
+					let mut menu_entries = Vec::new();
+					for action in actions.iter() {
+						// Only offer the lock actions that make sense right now; a locked thing has to be unlocked before it opens
+						if let Some(is_locked) = item.is_locked {
+							if is_locked && (*action == OpenItem || *action == LockItem) { continue; }
+							if !is_locked && *action == UnlockItem { continue; }
 						}
-						let submenu = make_new_submenu(menu_entries);
-						//debug!("* Made submenu of size {} from {} actions", submenu.len(), item.3.actions.len()); // DEBUG: report submenu creation
-						item_names.push(MenuItem::group(i_desc.name.clone(), submenu));
-					}
-				}
-				if item_names.is_empty() {
-					debug!("* Nothing in inventory to display"); // DEBUG: announce feedback
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("You are not carrying anything.");
+						menu_entries.push(GameEvent::new(PlayerAction(*action), Some(player), Some(item.enty)));
 					}
-					return Ok(());
-				} else {
-					//debug!("* Attempting to show_chooser()"); // DEBUG: announce attempt to show the context menu
-					eng.menu_context = MenuState::new(item_names);
-					eng.set_menu(MenuType::Context, (15, 5));
+					menu_entries.push(GameEvent::new(PlayerAction(AssignSlot), Some(player), Some(item.enty)));
+					let submenu = make_new_submenu(menu_entries);
+					//debug!("* Made submenu of size {} from {} actions", submenu.len(), actions.len()); // DEBUG: report submenu creation
+					item_names.push(MenuItem::group(item.name.clone(), submenu));
 				}
+				show_menu_or_report(eng, item_names, "You are not carrying anything.");
+				return Ok(())
 			}
 			KeyCode::Char('d') => { // DROP an item from player's inventory
-				let mut item_names = Vec::new();
-				let mut backpack_query = eng.bevy.world.query_filtered::<(Entity, &Description, &Portable), With<IsCarried>>();
-				for (i_enty, i_desc, i_portable) in backpack_query.iter(&eng.bevy.world) {
-					if i_portable.carrier == player {
-						item_names.push(MenuItem::item(
-							i_desc.name.clone(),
-							GameEvent::new(PlayerAction(DropItem), Some(player), Some(i_enty)),
-							None,
-							)
-						);
+				player_action_menu(eng, player, DropItem, snapshot.inventory_candidates(), "You have nothing to drop.");
+				return Ok(())
+			}
+			KeyCode::Char('t') => { // TRANSFER items between the player's inventory and a nearby container
+				transfer_menu(eng, &snapshot);
+				return Ok(())
+			}
+			KeyCode::Char('g') | KeyCode::Char('G') => { // GET an item from the ground or a nearby open container; 'G' to pick which bag it goes in
+				let mut candidates = Vec::new();
+				let p_posn = if let Some(value) = snapshot.p_posn { value } else { return Ok(()) };
+				let reach = MoveItem.reach_for(&eng.bevy.world, player).unwrap_or(DEFAULT_REACH);
+				for target in snapshot.items.iter() {
+					//debug!("* found item {}", target.name.clone()); // DEBUG: announce found targets for GET
+					if target.carrier == Entity::PLACEHOLDER {
+						// It's lying on the ground
+						if snapshot.nearby_entity(target.enty).map_or(false, |x| x.body.contains(&p_posn)) {
+							candidates.push((target.enty, target.name.clone(), None));
+						}
+					} else if let Some(cont) = snapshot.nearby_entity(target.carrier).filter(|x| x.is_fixture()) {
+						// It's inside a container, which needs to stay put (no picking the LMR's pockets), be nearby, and be open (if it has a lid at all)
+						if cont.body.in_range_of(&p_posn, reach) && cont.is_accessible() {
+							candidates.push((target.enty, format!("{} (in the {})", target.name, cont.name), Some(cont.body.ref_posn)));
+						}
 					}
 				}
-				if item_names.is_empty() {
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("You have nothing to drop.");
-					}
-					return Ok(())
+				if key_event.code == KeyCode::Char('G') {
+					pick_up_into_menu(eng, &snapshot, candidates);
 				} else {
-					eng.menu_context = MenuState::new(item_names);
-					eng.set_menu(MenuType::Context, (15, 5));
+					player_action_menu(eng, player, MoveItem, candidates, "There's nothing here to pick up.");
 				}
+				return Ok(())
 			}
-			KeyCode::Char('g') => { // GET an item from the ground
-				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Portable)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
+			KeyCode::Char('E') => { // EMPTY a nearby container out onto the floor
+				let reach = EmptyItem.reach_for(&eng.bevy.world, player).unwrap_or(DEFAULT_REACH);
+				let candidates = snapshot.within(reach)
+					.filter(|x| x.is_fixture())
+					.map(|x| (x.enty, x.name.clone(), Some(x.body.ref_posn)))
+					.collect();
+				player_action_menu(eng, player, EmptyItem, candidates, "There's nothing nearby to empty out.");
+				return Ok(())
+			}
+			KeyCode::Char('o') => { // OPEN an Openable item, or every one of them nearby if ALT is held
+				let candidates = adjacent_openables(eng, &snapshot, false);
+				if key_event.modifiers.contains(KeyModifiers::ALT) && !candidates.is_empty() {
+					send_to_all(eng, player, OpenItem, &candidates);
 					return Ok(())
-				};
-				for (t_enty, t_desc, t_body, _portable) in item_query.iter(&eng.bevy.world) {
-					//debug!("* found item {}", target.1.name.clone()); // DEBUG: announce found targets for GET
-					if t_body.contains(p_posn) {
-						item_names.push(MenuItem::item(
-							t_desc.name.clone(),
-							GameEvent::new(PlayerAction(MoveItem), Some(player), Some(t_enty)),
-							None,
-						));
-					}
 				}
-				if item_names.is_empty() {
-					//debug!("* Nothing to pick up at player's position"); // DEBUG: announce feedback
-					if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
-						msglog.tell_player("There's nothing here to pick up.");
+				// If the only things in reach are locked, give one of them a try anyway so that the player finds out why
+				if candidates.is_empty() {
+					let locked = adjacent_locked_openables(eng, &snapshot);
+					if !locked.is_empty() {
+						send_to_all(eng, player, OpenItem, &locked[..1]);
+						return Ok(())
 					}
-					return Ok(())
-				} else {
-					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
-					eng.menu_context = MenuState::new(item_names);
-					eng.set_menu(MenuType::Context, (15, 5));
 				}
+				player_action_menu(eng, player, OpenItem, candidates, "There's nothing nearby to open.");
+				return Ok(())
 			}
-			KeyCode::Char('o') => { // OPEN an Openable item
-				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Openable)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
+			KeyCode::Char('c') => { // CLOSE an Openable nearby, or every one of them if ALT is held
+				let candidates = adjacent_openables(eng, &snapshot, true);
+				if key_event.modifiers.contains(KeyModifiers::ALT) && !candidates.is_empty() {
+					send_to_all(eng, player, CloseItem, &candidates);
 					return Ok(())
-				};
-				for (t_enty, t_desc, t_body, t_open) in item_query.iter(&eng.bevy.world) {
-					//debug!("* found item {}", target.1.name.clone()); // DEBUG: report found OPENABLE items
-					if t_body.is_adjacent_to(p_posn) && !t_open.is_open {
-						item_names.push(MenuItem::item(
-								t_desc.name.clone(),
-								GameEvent::new(PlayerAction(OpenItem), Some(player), Some(t_enty)),
-								Some(t_body.ref_posn)
-							)
-						);
-					}
-				}
-				if item_names.is_empty() {
-					//debug!("* Nothing to open nearby"); // DEBUG: announce feedback
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to open.");
-					return Ok(())
-				} else {
-					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
-					eng.menu_context = MenuState::new(item_names);
-					eng.set_menu(MenuType::Context, (15, 5));
 				}
+				player_action_menu(eng, player, CloseItem, candidates, "There's nothing nearby to close.");
+				return Ok(())
 			}
-			KeyCode::Char('c') => { // CLOSE an Openable nearby
-				let mut item_names = Vec::new();
-				let mut item_query = eng.bevy.world.query::<(Entity, &Description, &Body, &Openable)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
-				};
-				for (t_enty, t_desc, t_body, t_open) in item_query.iter(&eng.bevy.world) {
-					//debug!("* found item {}", target.1.name.clone()); // DEBUG: report found closed OPENABLE items
-					if t_body.is_adjacent_to(p_posn) && t_open.is_open {
-						item_names.push(MenuItem::item(
-								t_desc.name.clone(),
-								GameEvent::new(PlayerAction(CloseItem), Some(player), Some(t_enty)),
-								Some(t_body.ref_posn)
-							)
-						);
-					}
-				}
-				if item_names.is_empty() {
-					//debug!("* Nothing to close nearby"); // DEBUG: announce feedback
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to close.");
-					return Ok(())
-				} else {
-					//debug!("* Attempting to set the entity menu"); // DEBUG: announce entity menu use
-					eng.menu_context = MenuState::new(item_names);
-					eng.set_menu(MenuType::Context, (15, 5));
+			KeyCode::Char(',') => { // LOOK at everything that's piled up on the player's own tile
+				let report = if let Some(value) = look_here(eng, player) { value } else { return Ok(()) };
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(&report);
 				}
+				return Ok(())
 			}
 			KeyCode::Char('x') => { // EXAMINE a nearby Entity
 				let mut enty_names = Vec::new();
-				let mut enty_query = eng.bevy.world.query::<(Entity, &Description, &Body)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
-				};
-				for (t_enty, t_desc, t_body) in enty_query.iter(&eng.bevy.world) {
-					//debug!("* Found target {}", target.1.name.clone()); // DEBUG: announce EXAMINE target
-					if t_body.in_range_of(p_posn, 2) {
+				let reach = Examine.reach_for(&eng.bevy.world, player).unwrap_or(EXAMINE_REACH);
+				let p_posn = if let Some(value) = snapshot.p_posn { value } else { return Ok(()) };
+				// The ground underfoot can always be examined
+				if eng.bevy.world.get_resource::<WorldModel>().map_or(false, |x| x.contains(p_posn)) {
+					enty_names.push(MenuItem::item(
+						format!("Terrain: {}", eng.tile_summary(p_posn)),
+						GameEvent::new(PlayerAction(Examine), Some(player), None).at(p_posn),
+						Some(p_posn),
+					));
+				}
+				// So can any terrain in reach that's more than bare floor and walls, ie a stairway or open vacuum
+				for t_posn in notable_terrain(eng, p_posn, reach) {
+					enty_names.push(MenuItem::item(
+						format!("Terrain: {}", eng.tile_summary(t_posn)),
+						GameEvent::new(PlayerAction(Examine), Some(player), None).at(t_posn),
+						Some(t_posn),
+					));
+				}
+				for target in snapshot.within(reach) {
+					//debug!("* Found target {}", target.name.clone()); // DEBUG: announce EXAMINE target
+					if can_affect(player, target.enty, reach, &eng.bevy.world) {
 						enty_names.push(MenuItem::item(
-							t_desc.name.clone(),
-							GameEvent::new(PlayerAction(Examine), Some(player), Some(t_enty)),
-							Some(t_body.ref_posn),
+							target.name.clone(),
+							GameEvent::new(PlayerAction(Examine), Some(player), Some(target.enty)),
+							Some(target.body.ref_posn),
 						));
 					}
 				}
-				if enty_names.is_empty() {
-					//debug!("* Nothing close enough to examine"); // DEBUG: report EXAMINE failure
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to examine.");
-					return Ok(());
-				} else {
-					//debug!("* Attempting to set the entity menu with targets");// DEBUG: announce examine menu use
-					eng.menu_context = MenuState::new(enty_names);
-					eng.set_menu(MenuType::Context, (15, 5));
-				}
+				show_menu_or_report(eng, enty_names, "There's nothing nearby to examine.");
+				return Ok(())
 			}
 			KeyCode::Char('a') => { // APPLY (use) an Operable item
-				// Get a list of all Operable items in the player's vicinity
-				let mut device_names = Vec::new();
-				let mut device_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, Option<&Portable>, &Device)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
-				};
-				//eng.item_chooser.list.clear();
-				// Drop them into one of the choosers
-				for (d_enty, d_body, d_desc, d_portable, _device) in device_query.iter(&eng.bevy.world) {
-					if let Some(is_portable) = d_portable {
-						if is_portable.carrier == player {
-							device_names.push(MenuItem::item(
-								d_desc.name.clone(),
-								GameEvent::new(PlayerAction(UseItem), Some(player), Some(d_enty)),
-								None,
-							));
-						}
-					//} else if device.1.is_some() { // Is the player near it?
-					} else if let Some(has_body) = d_body {
-						if p_posn.in_range_of(&has_body.ref_posn, 1) {
-							device_names.push(MenuItem::item(
-								d_desc.name.clone(),
-								GameEvent::new(PlayerAction(UseItem), Some(player), Some(d_enty)),
-								None,
-							));
-						}
-					}
-				}
-				if device_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing nearby to use.");
-					return Ok(())
-				} else {
-					eng.menu_context = MenuState::new(device_names);
-					eng.set_menu(MenuType::Context, (15, 5));
-				}
-			}
-			KeyCode::Char('L') => { // LOCK a Lockable item
-				let mut lock_names = Vec::new();
-				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
-				};
-				for (l_enty, l_body, l_desc, l_lock) in lock_query.iter(&eng.bevy.world) {
-					if let Some(l_posn) = l_body {
-						if l_posn.in_range_of(p_posn, 1)
-						&& l_lock.is_locked {
-							lock_names.push(MenuItem::item(
-								l_desc.name.clone(),
-								GameEvent::new(PlayerAction(LockItem), Some(player), Some(l_enty)),
-								None,
-							));
-						}
+				// Get a list of all Operable items that the player is carrying or standing next to
+				let reach = UseItem.reach_for(&eng.bevy.world, player).unwrap_or(DEFAULT_REACH);
+				let mut candidates: Vec<(Entity, String, Option<Position>)> = snapshot.carried_by(player)
+					.filter(|x| x.is_device)
+					.map(|x| (x.enty, x.name.clone(), None))
+					.collect();
+				for target in snapshot.within(reach) {
+					if target.is_device && target.carrier.is_none() {
+						candidates.push((target.enty, target.name.clone(), None));
 					}
 				}
-				if lock_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing to lock nearby.");
-					return Ok(())
-				} else {
-					eng.menu_context = MenuState::new(lock_names);
-					eng.set_menu(MenuType::Context, (15, 5));
-				}
+				player_action_menu(eng, player, UseItem, candidates, "There's nothing nearby to use.");
+				return Ok(())
 			}
-			KeyCode::Char('U') => { // UNLOCK a Lockable item
-				let mut lock_names = Vec::new();
-				let mut lock_query = eng.bevy.world.query::<(Entity, Option<&Body>, &Description, &Lockable)>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
+			KeyCode::Char('L') | KeyCode::Char('K') | KeyCode::Char('U') => { // LOCK, HACK, or UNLOCK a nearby Lockable
+				// Only the unlocked things can be locked, and only the locked ones can be hacked or unlocked
+				let (verb, want_locked, empty_msg) = match key_event.code {
+					KeyCode::Char('L') => { (LockItem, false, "There's nothing to lock nearby.") }
+					KeyCode::Char('K') => { (HackItem, true, "There's nothing to hack nearby.") }
+					_                  => { (UnlockItem, true, "There's nothing to unlock nearby.") }
 				};
-				for (l_enty, l_body, l_desc, l_lock) in lock_query.iter(&eng.bevy.world) {
-					if let Some(l_posn) = l_body {
-						if !l_lock.is_locked
-						&& l_posn.in_range_of(p_posn, 1) {
-							lock_names.push(MenuItem::item(
-								l_desc.name.clone(),
-								GameEvent::new(PlayerAction(UnlockItem), Some(player), Some(l_enty)),
-								None,
-							));
-						}
-					}
-				}
-				if lock_names.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There's nothing to unlock nearby.");
-					return Ok(())
-				} else {
-					eng.menu_context = MenuState::new(lock_names);
-					eng.set_menu(MenuType::Context, (15, 5));
-				}
+				let reach = verb.reach_for(&eng.bevy.world, player).unwrap_or(DEFAULT_REACH);
+				let candidates = snapshot.within(reach)
+					.filter(|x| x.is_locked == Some(want_locked))
+					.map(|x| (x.enty, x.name.clone(), None))
+					.collect();
+				player_action_menu(eng, player, verb, candidates, empty_msg);
+				return Ok(())
 			}
 			KeyCode::Char('C') => { // CONNECT the PLANQ to a nearby AccessPort
 				let mut access_ports = Vec::new();
-				let mut port_query = eng.bevy.world.query_filtered::<(Entity, &Body, &Description), With<AccessPort>>();
-				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() {
-					value
-				} else {
-					return Ok(())
-				};
-				for (p_enty, p_body, p_desc) in port_query.iter(&eng.bevy.world) {
-					if p_body.is_adjacent_to(p_posn) {
+				let p_posn = if let Some(value) = snapshot.p_posn { value } else { return Ok(()) };
+				for port in snapshot.nearby.iter().filter(|x| x.is_access_port) {
+					if port.body.is_adjacent_to(&p_posn) {
 						access_ports.push(MenuItem::item(
-							p_desc.name.clone(),
-							GameEvent::new(PlanqConnect(p_enty), Some(player), Some(p_enty)), // NOTE: might want to swap player for planq here?
+							port.name.clone(),
+							GameEvent::new(PlanqConnect(port.enty), Some(player), Some(port.enty)), // NOTE: might want to swap player for planq here?
 							None,
 						));
 					}
 				}
-				if access_ports.is_empty() {
-					let mut msglog = eng.bevy.world.get_resource_mut::<MessageLog>().unwrap();
-					msglog.tell_player("There are no access ports nearby.");
-					return Ok(())
-				} else {
-					eng.menu_context = MenuState::new(access_ports);
-					eng.set_menu(MenuType::Context, (15, 5));
-				}
+				show_menu_or_report(eng, access_ports, "There are no access ports nearby.");
+				return Ok(())
 			}
 			KeyCode::Char('D') => { // DISCONNECT the PLANQ from a connected AccessPort, if set
 				if planq.jack_cnxn == Entity::PLACEHOLDER {
@@ -458,14 +566,39 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 				} else {
 					// disconnect the PLANQ
 					new_game_event.etype = PlanqConnect(Entity::PLACEHOLDER);
-					new_game_event.context = Some(GameEventContext{ subject: player, object: planq.jack_cnxn });
+					new_game_event.context = Some(GameEventContext::new(player, planq.jack_cnxn));
 				}
 			}
 			//   #: PLANQ 'sidebar'/ambient controls
 			KeyCode::Char('P') | KeyCode::Char(':') => {
-				if planq.cpu_mode == PlanqCPUMode::Idle || planq.cpu_mode == PlanqCPUMode::Working {
+				if planq.is_carried && (planq.cpu_mode == PlanqCPUMode::Idle || planq.cpu_mode == PlanqCPUMode::Working) {
+					// The CLI is drawn on the terminal, so flip the screen back up rather than have the player type blind
+					if !planq.show_terminal {
+						planq.show_terminal = true;
+						eng.layout_changed = true;
+					}
 					new_planq_event.etype = PlanqEventType::CliOpen;
+					eng.focus = PanelFocus::Planq;
+				}
+			}
+			KeyCode::Char('T') => { // Show or hide the PLANQ's TERMINAL; this never touches its power switch
+				let report = if !planq.is_carried {
+					"You don't have a PLANQ to look at."
+				} else {
+					planq.show_terminal = !planq.show_terminal;
+					eng.layout_changed = true;
+					if planq.show_terminal {
+						"You flip the PLANQ's screen up."
+					} else {
+						planq.show_cli_input = false;
+						eng.focus = PanelFocus::World;
+						"You fold the PLANQ's screen away."
+					}
+				};
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(report);
 				}
+				return Ok(())
 			}
 			//   #: Debug keys and other tools
 			/* Disabled these since I deprecated the make_item function
@@ -478,12 +611,53 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			 *	eng.give_item(ItemType::Snack, player);
 			 *}
 			 */
+			KeyCode::F(2) => { // DEBUG: detach the camera from the player, or snap it back
+				let p_posn = if let Some(value) = eng.bevy.world.get_resource::<Position>() { *value } else { return Ok(()) };
+				if let Some(mut camera) = eng.bevy.world.get_resource_mut::<CameraView>() {
+					let is_free = camera.toggle_free(p_posn);
+					debug!("* free camera: {}", is_free); // DEBUG: announce the free camera toggle
+				}
+				return Ok(())
+			}
+			KeyCode::F(3) => { // DEBUG: cycle through the full-map reveal settings
+				if let Some(mut camera) = eng.bevy.world.get_resource_mut::<CameraView>() {
+					camera.debug_reveal = camera.debug_reveal.next();
+					debug!("* map reveal: {:?}", camera.debug_reveal); // DEBUG: announce the map reveal setting
+				}
+				return Ok(())
+			}
 			_ => {
 				error!("* Unhandled key: {:?}", key_event.code); // DEBUG: report an unhandled key from this method
 			}
 		}
-		// If an event was generated, send it off for processing
-		if new_game_event.etype != GameEventType::NullEvent {
+		// While the camera is free, the movement keys steer it instead of the player, so the game state is left alone
+		if let PlayerAction(MoveTo(dir)) = new_game_event.etype {
+			if let Some(mut camera) = eng.bevy.world.get_resource_mut::<CameraView>() {
+				if camera.free_origin.is_some() {
+					camera.pan(dir);
+					return Ok(())
+				}
+			}
+		}
+		// A step into a hazard may need the player's say-so first, depending on their settings
+		if let PlayerAction(MoveTo(dir)) = new_game_event.etype {
+			if let Some(warning) = hazard_prompt_for(eng, dir) {
+				eng.pending_move = Some(dir);
+				if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+					msglog.tell_player(format!("{} (y/n)", warning).as_str());
+				}
+				return Ok(())
+			}
+		}
+		// The PLANQ might be keeping the player's hands busy, depending on their settings
+		if let Some(reason) = eng.planq_refuses(&new_game_event) {
+			if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+				msglog.tell_player(&reason);
+			}
+			return Ok(())
+		}
+		// If an event was generated, send it off for processing; without a player, there's nobody to carry it out
+		if new_game_event.etype != GameEventType::NullEvent && snapshot.has_player() {
 			// Get a linkage to the game event distribution system
 			if let Some(mut game_events) = eng.bevy.world.get_resource_mut::<Events<GameEvent>>() {
 				game_events.send(new_game_event);
@@ -495,10 +669,31 @@ pub fn key_parser(key_event: KeyEvent, eng: &mut GameEngine) -> AppResult<()> {
 			}
 		}
 	} else { // ###: ALL OTHER SITUATIONS: Paused, Standby, etc
+		//  ##: NAME ENTRY MODE
+		if eng.naming {
+			match key_event.code {
+				KeyCode::Char(letter) => { if eng.player_name.len() < MAX_NAME_LEN { eng.player_name.push(letter); } }
+				KeyCode::Backspace => { eng.player_name.pop(); }
+				KeyCode::Enter | KeyCode::Esc => {
+					eng.naming = false;
+					if eng.player_name.trim().is_empty() { eng.player_name = "Pleyeur".to_string(); }
+				}
+				_ => { }
+			}
+			// Rebuild the menu so that the name being typed is shown
+			eng.set_menu(MenuType::Main, eng.menu_posn);
+			return Ok(())
+		}
 		match key_event.code {
 			// Only handle these keys if the game's actually in-progress
 			// Close open menus/unpause on Esc or Q
 			KeyCode::Esc | KeyCode::Char('Q') => {
+				// The end screen goes back to the main menu, there's no game left to unpause
+				if eng.mode == EngineMode::BadEnd || eng.mode == EngineMode::GoodEnd {
+					eng.halt_game();
+					eng.set_menu(MenuType::Main, eng.menu_posn);
+					return Ok(())
+				}
 				//eng.menu_context.target = None; // Reset the targeting reticle
 				eng.visible_menu = MenuType::None;
 				eng.menu_main.reset();
@@ -534,8 +729,80 @@ pub fn planq_parser(input: &str) -> PlanqCmd {
 		"help" => { PlanqCmd::Help }
 		"shutdown" => { PlanqCmd::Shutdown }
 		"reboot" => { PlanqCmd::Reboot }
-		"connect" => { PlanqCmd::Connect(input_vec[1].to_string()) }
+		"connect" => { PlanqCmd::Connect(input_vec[1..].join(" ").trim().to_string()) }
 		"disconnect" => { PlanqCmd::Disconnect }
+		"msgs" => {
+			if input_vec.len() < 2 || input_vec[1].is_empty() { return PlanqCmd::Msgs(None); }
+			match input_vec[1].parse::<usize>() {
+				Ok(index) => { PlanqCmd::Msgs(Some(index)) }
+				Err(_) => { PlanqCmd::Error(format!("Not a message number: {}", input_vec[1])) }
+			}
+		}
+		"mark" => { PlanqCmd::Mark(input_vec[1..].join(" ").trim().to_string()) }
+		"marks" => { PlanqCmd::Marks }
+		"notes" => {
+			if input_vec.len() < 2 || input_vec[1].is_empty() { return PlanqCmd::Notes(None); }
+			match input_vec[1].parse::<usize>() {
+				Ok(index) => { PlanqCmd::Notes(Some(index)) }
+				Err(_) => { PlanqCmd::Error(format!("Not a note number: {}", input_vec[1])) }
+			}
+		}
+		#[cfg(debug_assertions)]
+		"census" => { PlanqCmd::Census }
+		#[cfg(debug_assertions)]
+		"reveal" => { PlanqCmd::Reveal }
+		#[cfg(debug_assertions)]
+		"diffsaves" => {
+			if input_vec.len() < 3 || input_vec[2].is_empty() { return PlanqCmd::Error("Usage: diffsaves <slot> <slot>".to_string()); }
+			PlanqCmd::DiffSaves(input_vec[1].to_string(), input_vec[2].to_string())
+		}
+		"power" => {
+			match input_vec.get(1).copied().unwrap_or("") {
+				"" | "status" => { PlanqCmd::Power }
+				#[cfg(debug_assertions)]
+				"blackout" => {
+					match input_vec.get(2).copied().unwrap_or("10").parse::<u64>() {
+						Ok(duration) => { PlanqCmd::Blackout(duration) }
+						Err(_) => { PlanqCmd::Error(format!("Not a number of turns: {}", input_vec[2])) }
+					}
+				}
+				_ => { PlanqCmd::Error("Usage: power [status]".to_string()) }
+			}
+		}
+		"dash" => { PlanqCmd::Dash }
+		"map" => { PlanqCmd::Map }
+		"export" => { PlanqCmd::Export }
+		"download" => { PlanqCmd::Download }
+		"upload" => { PlanqCmd::Upload }
+		"transcript" => {
+			match input_vec.get(1).copied().unwrap_or("") {
+				"on" => { PlanqCmd::Transcript(true) }
+				"off" => { PlanqCmd::Transcript(false) }
+				_ => { PlanqCmd::Error("Usage: transcript on|off".to_string()) }
+			}
+		}
+		"analyze" => { PlanqCmd::Analyze(input_vec[1..].join(" ").trim().to_string()) }
+		"log" => {
+			if input_vec.len() < 2 || input_vec[1].is_empty() { return PlanqCmd::ShipLog(1); }
+			match input_vec[1].parse::<usize>() {
+				Ok(page) => { PlanqCmd::ShipLog(page) }
+				Err(_) => { PlanqCmd::Error(format!("Not a page number: {}", input_vec[1])) }
+			}
+		}
+		"scan" => {
+			match input_vec.get(1).copied().unwrap_or("") {
+				"" => { PlanqCmd::Scan }
+				"log" | "history" => { PlanqCmd::ScanLog }
+				_ => { PlanqCmd::Error("Usage: scan [log]".to_string()) }
+			}
+		}
+		"goto" => {
+			if input_vec.len() < 2 || input_vec[1].is_empty() { return PlanqCmd::Error("Usage: goto <bookmark number>".to_string()); }
+			match input_vec[1].parse::<usize>() {
+				Ok(index) => { PlanqCmd::Goto(index) }
+				Err(_) => { PlanqCmd::Error(format!("Not a bookmark number: {}", input_vec[1])) }
+			}
+		}
 		input => { PlanqCmd::Error(format!("Unknown command: {}", input)) } // No matching command was found!
 	}
 }
@@ -571,6 +838,147 @@ pub fn keycode_to_input_key(key_code: KeyCode) -> Key {
 		KeyCode::Null        => { Key::Null }
 	}
 }
+/// Offers the player a context menu of the given action against each of the candidates, which are given as
+/// (target, menu label, reticle position); if there are no candidates, the player is told so instead
+pub fn player_action_menu(eng: &mut GameEngine, player: Entity, verb: ActionType, candidates: Vec<(Entity, String, Option<Position>)>, empty_msg: &str) {
+	let menu_items = candidates.into_iter().map(|(target, label, posn)| {
+		MenuItem::item(label, GameEvent::new(PlayerAction(verb), Some(player), Some(target)), posn)
+	}).collect();
+	show_menu_or_report(eng, menu_items, empty_msg);
+}
+/// Offers each of the items that can be picked up, with a submenu of the places in the player's inventory that it can go:
+/// either the player's own pockets, or any of the Containers that they're carrying
+pub fn pick_up_into_menu(eng: &mut GameEngine, snapshot: &InputSnapshot, candidates: Vec<(Entity, String, Option<Position>)>) {
+	let player = snapshot.player;
+	let mut bags: Vec<(Entity, String)> = snapshot.carried_by(player)
+		.filter(|x| x.is_container)
+		.map(|x| (x.enty, x.name.clone()))
+		.collect();
+	bags.sort_by(|a, b| a.1.cmp(&b.1));
+	let menu_items = candidates.into_iter().map(|(target, label, posn)| {
+		let mut submenu = vec![MenuItem::item("your pockets", GameEvent::new(PlayerAction(MoveItem), Some(player), Some(target)), posn)];
+		for (b_enty, b_name) in bags.iter() {
+			let event = GameEvent::new(PlayerAction(MoveItem), Some(player), Some(target)).into_container(*b_enty);
+			submenu.push(MenuItem::item(format!("the {}", b_name), event, posn));
+		}
+		MenuItem::group(label, submenu)
+	}).collect();
+	show_menu_or_report(eng, menu_items, "There's nothing here to pick up.");
+}
+/// Lists every container within reach, each with a submenu for moving things between it and the player's inventory
+/// Putting something in is an ItemGive, or a deposit if the container is a salvage bin; taking it out is a MoveItem
+pub fn transfer_menu(eng: &mut GameEngine, snapshot: &InputSnapshot) {
+	let player = snapshot.player;
+	let reach = MoveItem.reach_for(&eng.bevy.world, player).unwrap_or(DEFAULT_REACH);
+	// Only the stationary containers count, and only the ones that are open, if they have a lid at all
+	let containers: Vec<&NearbyEntity> = snapshot.within(reach)
+		.filter(|x| x.is_fixture() && x.carrier.is_none() && x.is_accessible())
+		.collect();
+	let menu_items = containers.into_iter().filter_map(|cont| {
+		let c_posn = Some(cont.body.ref_posn);
+		let mut submenu = Vec::new();
+		for item in snapshot.carried_by(player) {
+			let event = if cont.is_salvage_bin {
+				GameEvent::new(PlayerAction(DropItem), Some(player), Some(item.enty)).into_container(cont.enty)
+			} else {
+				GameEvent::give(player, item.enty, cont.enty)
+			};
+			submenu.push(MenuItem::item(format!("put in: {}", item.name), event, c_posn));
+		}
+		for item in snapshot.carried_by(cont.enty) {
+			let event = GameEvent::new(PlayerAction(MoveItem), Some(player), Some(item.enty));
+			submenu.push(MenuItem::item(format!("take out: {}", item.name), event, c_posn));
+		}
+		if submenu.is_empty() { None } else { Some(MenuItem::group(cont.name.clone(), submenu)) }
+	}).collect();
+	show_menu_or_report(eng, menu_items, "There's nothing nearby to move things in or out of.");
+}
+/// Shows the given items in the context menu, or if there aren't any, sends the empty message to the player instead
+pub fn show_menu_or_report(eng: &mut GameEngine, menu_items: Vec<MenuItem<GameEvent>>, empty_msg: &str) {
+	if menu_items.is_empty() {
+		if let Some(mut msglog) = eng.bevy.world.get_resource_mut::<MessageLog>() {
+			msglog.tell_player(empty_msg);
+		}
+	} else {
+		eng.menu_context = MenuState::new(menu_items);
+		eng.set_menu(MenuType::Context, (15, 5));
+	}
+}
+/// Returns the player's current position, if the game has placed the player yet
+fn player_posn(eng: &GameEngine) -> Option<Position> {
+	eng.bevy.world.get_resource::<Position>().copied()
+}
+/// Returns the hazard in the tile that the player would step into by moving in the given direction, if there is one
+/// Nothing is reported if the player is already standing in the same kind of hazard, since they're in it either way
+fn hazard_ahead(eng: &GameEngine, dir: Direction) -> Option<(Hazard, Position)> {
+	let origin = player_posn(eng)?;
+	let target = origin + dir.to_offset();
+	let model = eng.bevy.world.get_resource::<WorldModel>()?;
+	if !model.contains(origin) || !model.contains(target) { return None; }
+	let hazard = model.get_tiletype_at(target).properties().hazard;
+	if hazard == Hazard::None || model.get_tiletype_at(origin).properties().hazard == hazard { return None; }
+	Some((hazard, target))
+}
+/// Returns the question to ask before the player steps in the given direction, or None if the step can go ahead
+fn hazard_prompt_for(eng: &GameEngine, dir: Direction) -> Option<&'static str> {
+	let prompt = eng.prefs.hazard_prompt;
+	if prompt == HazardPrompt::Never { return None; }
+	let (hazard, target) = hazard_ahead(eng, dir)?;
+	if prompt == HazardPrompt::FirstTime && eng.hazards_confirmed.contains(&(hazard, target)) { return None; }
+	hazard.warning()
+}
+/// Works out the route to the next bookmark and holds it up on the camera for the player to look over
+/// Returns the question to put to the player, or the reason why there's nothing to travel to
+fn preview_next_bookmark(eng: &mut GameEngine) -> Option<String> {
+	let origin = player_posn(eng)?;
+	let marks = eng.bevy.world.get_resource::<Bookmarks>()?;
+	let index = if let Some(value) = marks.next_index() { value } else {
+		return Some("You haven't bookmarked anywhere yet.".to_string());
+	};
+	let mark = marks.marks[index].clone();
+	if mark.posn == origin {
+		eng.bevy.world.get_resource_mut::<Bookmarks>()?.cursor = index;
+	}
+	Some(match eng.preview_route(mark) { Ok(value) | Err(value) => value })
+}
+/// Lists everything that's sharing the player's tile, in the same order that the camera stacks them up
+fn look_here(eng: &GameEngine, player: Entity) -> Option<String> {
+	let p_posn = player_posn(eng)?;
+	let model = eng.bevy.world.get_resource::<WorldModel>()?;
+	if !model.contains(p_posn) { return None; }
+	let mut stack: Vec<(i32, Entity, String)> = model.get_contents_at(p_posn).into_iter()
+		.filter(|x| *x != player)
+		.filter_map(|x| {
+			let name = eng.bevy.world.get::<Description>(x)?.name.clone();
+			let priority = draw_priority(eng.bevy.world.get::<Mobile>(x).is_some(), eng.bevy.world.get::<Portable>(x).is_some());
+			Some((priority, x, name))
+		})
+		.collect();
+	let note = eng.bevy.world.get_resource::<MapNotes>().and_then(|x| x.note_at(p_posn))
+		.map(|x| format!(" A note here reads: \"{}\"", x)).unwrap_or_default();
+	let terrain = model.describe_terrain(p_posn);
+	if stack.is_empty() {
+		return Some(format!("There's nothing here but you, on the {}.{}", terrain, note));
+	}
+	stack.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+	let names: Vec<String> = stack.into_iter().map(|x| x.2).collect();
+	Some(format!("On the {}, you find: {}.{}", terrain, names.join(", "), note))
+}
+/// Lists the tiles around the player, but not under them, whose terrain is worth a closer look: anything besides bare
+/// floor and walls that's within the given reach and in plain sight
+fn notable_terrain(eng: &GameEngine, p_posn: Position, reach: i32) -> Vec<Position> {
+	let model = if let Some(value) = eng.bevy.world.get_resource::<WorldModel>() { value } else { return Vec::new() };
+	let mut found = Vec::new();
+	for y_diff in -reach..=reach {
+		for x_diff in -reach..=reach {
+			let t_posn = p_posn + (x_diff, y_diff, 0);
+			if t_posn == p_posn || !model.contains(t_posn) || !t_posn.in_range_of(&p_posn, reach) { continue; }
+			if matches!(model.get_tiletype_at(t_posn), TileType::Floor | TileType::Wall) { continue; }
+			if has_line_of_sight(model, p_posn, t_posn) { found.push(t_posn); }
+		}
+	}
+	found
+}
 /// Creates a new submenu given a Vec of the entries to put in it; note that only strings, Actions, and Entities are supported
 pub fn make_new_submenu<T: std::fmt::Display>(entries: Vec<T>) -> Vec<MenuItem<T>> {
 	let mut submenu = Vec::new();
@@ -580,5 +988,32 @@ pub fn make_new_submenu<T: std::fmt::Display>(entries: Vec<T>) -> Vec<MenuItem<T
 	submenu.sort_by(|a, b| a.partial_cmp(b).unwrap());
 	submenu
 }
+/// Lists the Openables within the player's reach that are in the given state, in the same form as player_action_menu()
+/// takes; anything that's still locked is left off the list of things to open, since it belongs on the Unlock menu instead
+pub fn adjacent_openables(eng: &GameEngine, snapshot: &InputSnapshot, is_open: bool) -> Vec<(Entity, String, Option<Position>)> {
+	let action = if is_open { CloseItem } else { OpenItem };
+	let reach = action.reach_for(&eng.bevy.world, snapshot.player).unwrap_or(DEFAULT_REACH);
+	snapshot.within(reach)
+		.filter(|x| x.is_open == Some(is_open))
+		.filter(|x| is_open || x.is_locked != Some(true))
+		.map(|x| (x.enty, x.name.clone(), Some(x.body.ref_posn)))
+		.collect()
+}
+/// Retrieves the list of closed Openables in reach of the player that are locked, which adjacent_openables leaves out
+pub fn adjacent_locked_openables(eng: &GameEngine, snapshot: &InputSnapshot) -> Vec<(Entity, String, Option<Position>)> {
+	let reach = OpenItem.reach_for(&eng.bevy.world, snapshot.player).unwrap_or(DEFAULT_REACH);
+	snapshot.within(reach)
+		.filter(|x| x.is_locked == Some(true) && x.is_open == Some(false))
+		.map(|x| (x.enty, x.name.clone(), Some(x.body.ref_posn)))
+		.collect()
+}
+/// Sends the same player action at each of the given targets at once, ie to shut every door in the room in one go
+pub fn send_to_all(eng: &mut GameEngine, player: Entity, action: ActionType, targets: &[(Entity, String, Option<Position>)]) {
+	if let Some(mut game_events) = eng.bevy.world.get_resource_mut::<Events<GameEvent>>() {
+		for (t_enty, _, _) in targets.iter() {
+			game_events.send(GameEvent::new(PlayerAction(action), Some(player), Some(*t_enty)));
+		}
+	}
+}
 
 // EOF
@@ -0,0 +1,270 @@
+// engine/legacy.rs
+// Keeps a small record of how previous runs of the game turned out, which persists between sessions
+
+// ###: EXTERNAL LIBRARIES
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use serde::{Deserialize, Serialize};
+use simplelog::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::components::Position;
+use crate::salvage::salvage_rank;
+
+// ###: CONSTANTS
+/// The file that the legacy record is kept in; one JSON object per line, oldest first
+pub const LEGACY_FILENAME: &str = "legacy.jsonl";
+/// The legacy file will be trimmed down to this many of the most recent entries whenever it is written
+pub const MAX_LEGACY_ENTRIES: usize = 50;
+/// The number of the player's final messages that are repeated on the death screen
+pub const LAST_WORDS_COUNT: usize = 3;
+
+// ###: COMPLEX TYPES
+//  ##: LegacyRecord
+/// The full set of legacy entries that were read from (or will be written to) the legacy file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LegacyRecord {
+	pub entries: Vec<LegacyEntry>,
+}
+impl LegacyRecord {
+	pub fn new() -> LegacyRecord {
+		LegacyRecord::default()
+	}
+	/// Reads the legacy record from the given file; any lines that can't be parsed are skipped, and a missing or
+	/// unreadable file just produces an empty record
+	pub fn load(filename: &str) -> LegacyRecord {
+		let mut record = LegacyRecord::new();
+		let file = if let Ok(value) = File::open(filename) { value } else { return record };
+		for (num, line) in BufReader::new(file).lines().enumerate() {
+			let text = if let Ok(value) = line { value } else { continue };
+			if text.trim().is_empty() { continue; }
+			match serde_json::from_str::<LegacyEntry>(&text) {
+				Ok(entry) => { record.entries.push(entry); }
+				Err(e) => { warn!("* skipping corrupt legacy entry on line {}: {}", num + 1, e); } // DEBUG: report a bad legacy line
+			}
+		}
+		record
+	}
+	/// Writes the legacy record out to the given file, replacing whatever was there
+	pub fn save(&self, filename: &str) -> std::io::Result<()> {
+		let mut file = File::create(filename)?;
+		for entry in self.entries.iter() {
+			if let Ok(text) = serde_json::to_string(entry) {
+				writeln!(file, "{}", text)?;
+			}
+		}
+		Ok(())
+	}
+	/// Adds a new entry to the end of the record, dropping the oldest entries if the record is too long
+	pub fn append(&mut self, entry: LegacyEntry) {
+		self.entries.push(entry);
+		if self.entries.len() > MAX_LEGACY_ENTRIES {
+			let excess = self.entries.len() - MAX_LEGACY_ENTRIES;
+			self.entries.drain(..excess);
+		}
+	}
+	/// Returns up to the given number of the most recent entries, newest first
+	pub fn latest(&self, count: usize) -> Vec<&LegacyEntry> {
+		self.entries.iter().rev().take(count).collect()
+	}
+	/// Finds the most recent run on the given seed that ended badly, if there was one
+	pub fn last_bad_end_on(&self, seed: u64) -> Option<&LegacyEntry> {
+		self.entries.iter().rev().find(|x| x.seed == seed && x.outcome == "BadEnd")
+	}
+}
+//  ##: LegacyEntry
+/// A compact summary of a single run of the game
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LegacyEntry {
+	pub seed: u64,
+	pub outcome: String, // The EngineMode that the game ended in, ie GoodEnd or BadEnd
+	pub turns: u64,
+	pub final_posn: (i32, i32, i32),
+	pub cause: Option<String>, // The cause of death, if any
+	#[serde(default)] // Entries from before the salvage bin have none
+	pub salvage: u64,
+}
+impl LegacyEntry {
+	pub fn new(seed: u64, outcome: &str, turns: u64, final_posn: Position, cause: Option<String>) -> LegacyEntry {
+		LegacyEntry {
+			seed,
+			outcome: outcome.to_string(),
+			turns,
+			final_posn: (final_posn.x, final_posn.y, final_posn.z),
+			cause,
+			salvage: 0,
+		}
+	}
+	/// Records how much salvage the run brought in
+	pub fn with_salvage(mut self, salvage: u64) -> LegacyEntry {
+		self.salvage = salvage;
+		self
+	}
+	/// Produces the one-line summary that is shown in the main menu's footer
+	pub fn summary(&self) -> String {
+		// Escapes are ranked by how much they managed to bring along with them
+		let ending = if self.outcome == "GoodEnd" { format!("ESCAPED {}", salvage_rank(self.salvage)) } else { "LOST".to_string() };
+		let cause = self.cause.as_ref().map(|x| format!(" ({})", x)).unwrap_or_default();
+		format!("#{:016x} {}{} after {} turns", self.seed, ending, cause, self.turns)
+	}
+	/// Produces a 'corrupted' log fragment describing this run, for the PLANQ to stumble across while booting
+	pub fn ghost_fragment(&self) -> String {
+		let cause = self.cause.clone().unwrap_or("unknown".to_string());
+		format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]RECOVERED:[[end]] ..d3ck {} ▒▒ {}, {} ▒ T+{} ▒▒ {}▒▒",
+			self.final_posn.2, self.final_posn.0, self.final_posn.1, self.turns, cause)
+	}
+}
+//  ##: RunSummary
+/// The details of a run that has ended, as shown on the end screen
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunSummary {
+	pub escaped: bool, // True if the player got away, rather than dying
+	pub cause: String,
+	pub turns: u64,
+	pub items_collected: u64,
+	pub salvage: u64, // The value of everything that made it into the salvage bin
+	pub decks_explored: usize,
+	pub last_words: Vec<String>, // The last few messages that the player saw
+}
+impl RunSummary {
+	/// Produces the lines of text that make up the end screen; an escape is ranked by the salvage that came along
+	pub fn report(&self) -> Vec<String> {
+		let outcome = if self.escaped {
+			format!("ESCAPED {}", salvage_rank(self.salvage))
+		} else {
+			format!("Cause of death: {}", self.cause)
+		};
+		let mut lines = vec![
+			outcome,
+			format!("Turns survived: {}", self.turns),
+			format!("Items collected: {}", self.items_collected),
+			format!("Salvage recovered: {}", self.salvage),
+			format!("Decks explored: {}", self.decks_explored),
+		];
+		if !self.last_words.is_empty() {
+			lines.push("".to_string());
+			lines.extend(self.last_words.iter().map(|x| format!("  \"{}\"", strip_markup(x))));
+		}
+		lines
+	}
+}
+
+// ###: UTILITIES
+/// Removes the [[...]] style tags from a message, so that it can be shown as plain text
+pub(crate) fn strip_markup(input: &str) -> String {
+	let mut output = String::new();
+	let mut rest = input;
+	while let Some(start) = rest.find("[[") {
+		output.push_str(&rest[..start]);
+		rest = match rest[start..].find("]]") {
+			Some(end) => { &rest[start + end + 2..] }
+			None => { "" }
+		};
+	}
+	output.push_str(rest);
+	output
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Setup
+	/// Makes an entry for a run on the given seed that lasted the given number of turns
+	fn run(seed: u64, outcome: &str, turns: u64) -> LegacyEntry {
+		let cause = if outcome == "BadEnd" { Some("asphyxiation".to_string()) } else { None };
+		LegacyEntry::new(seed, outcome, turns, Position::new(3, 4, 1), cause)
+	}
+	/// Picks out a path in the temp dir for a test's legacy file, with nothing left over in it from an earlier run
+	fn legacy_path(name: &str) -> String {
+		let path = std::env::temp_dir().join(name);
+		let _ = std::fs::remove_file(&path);
+		path.to_string_lossy().to_string()
+	}
+	//  ##: Reading and writing
+	#[test]
+	fn each_finished_run_is_added_to_the_end_of_the_file() {
+		let path = legacy_path("test_legacy_append.jsonl");
+		// There's no file yet, so there are no earlier runs
+		let mut record = LegacyRecord::load(&path);
+		assert!(record.entries.is_empty());
+		record.append(run(1, "BadEnd", 10));
+		record.save(&path).unwrap();
+		// The next session picks up where the last one left off
+		let mut record = LegacyRecord::load(&path);
+		record.append(run(2, "GoodEnd", 20));
+		record.save(&path).unwrap();
+		let loaded = LegacyRecord::load(&path);
+		let _ = std::fs::remove_file(&path);
+		assert_eq!(loaded.entries, vec![run(1, "BadEnd", 10), run(2, "GoodEnd", 20)]);
+		assert_eq!(loaded.latest(5), vec![&run(2, "GoodEnd", 20), &run(1, "BadEnd", 10)]);
+	}
+	#[test]
+	fn a_corrupt_line_is_skipped_over() {
+		let path = legacy_path("test_legacy_corrupt.jsonl");
+		let good = serde_json::to_string(&run(1, "BadEnd", 10)).unwrap();
+		std::fs::write(&path, format!("{}\n{{\"seed\": 2, \"outc\n\n{}\n", good, good)).unwrap();
+		let loaded = LegacyRecord::load(&path);
+		let _ = std::fs::remove_file(&path);
+		assert_eq!(loaded.entries, vec![run(1, "BadEnd", 10), run(1, "BadEnd", 10)]);
+	}
+	#[test]
+	fn only_the_newest_runs_are_kept() {
+		let path = legacy_path("test_legacy_trim.jsonl");
+		let mut record = LegacyRecord::new();
+		for turns in 0..(MAX_LEGACY_ENTRIES as u64 + 5) {
+			record.append(run(turns, "GoodEnd", turns));
+		}
+		record.save(&path).unwrap();
+		let loaded = LegacyRecord::load(&path);
+		let _ = std::fs::remove_file(&path);
+		assert_eq!(loaded.entries.len(), MAX_LEGACY_ENTRIES);
+		assert_eq!(loaded.entries.first().map(|x| x.turns), Some(5));
+		assert_eq!(loaded.entries.last().map(|x| x.turns), Some(MAX_LEGACY_ENTRIES as u64 + 4));
+	}
+	//  ##: Runs on the same seed
+	#[test]
+	fn a_second_run_on_the_same_seed_finds_the_first_ones_bad_end() {
+		let mut record = LegacyRecord::new();
+		record.append(run(7, "BadEnd", 10));
+		record.append(run(8, "BadEnd", 99));
+		record.append(run(7, "GoodEnd", 30));
+		// The escape doesn't count, and neither does the death on the other seed
+		assert_eq!(record.last_bad_end_on(7), Some(&run(7, "BadEnd", 10)));
+		assert_eq!(record.last_bad_end_on(9), None);
+		// The most recent death on the seed is the one that gets remembered
+		record.append(run(7, "BadEnd", 40));
+		let echo = record.last_bad_end_on(7).expect("the seed should have a bad end");
+		assert_eq!(echo.turns, 40);
+		assert!(echo.ghost_fragment().contains("T+40"));
+		assert!(echo.ghost_fragment().contains("asphyxiation"));
+	}
+	//  ##: Run summaries
+	#[test]
+	fn a_death_is_summed_up_with_its_cause_and_turn_count() {
+		let summary = RunSummary {
+			cause: "asphyxiation".to_string(),
+			turns: 412,
+			items_collected: 3,
+			decks_explored: 2,
+			last_words: vec!["You feel [[fg:red]]lightheaded[[end]].".to_string()],
+			..RunSummary::default()
+		};
+		let report = summary.report();
+		assert_eq!(report[0], "Cause of death: asphyxiation");
+		assert_eq!(report[1], "Turns survived: 412");
+		// The last words come after a gap, with their markup taken out
+		assert_eq!(report.last().map(|x| x.as_str()), Some("  \"You feel lightheaded.\""));
+		assert_eq!(report[report.len() - 2], "");
+	}
+	#[test]
+	fn an_escape_is_summed_up_without_a_cause() {
+		let summary = RunSummary { escaped: true, cause: "asphyxiation".to_string(), turns: 90, ..RunSummary::default() };
+		let report = summary.report();
+		assert!(report[0].starts_with("ESCAPED"), "{}", report[0]);
+		assert!(!report.iter().any(|x| x.contains("asphyxiation")), "{:?}", report);
+		assert_eq!(report[1], "Turns survived: 90");
+	}
+}
+
+// EOF
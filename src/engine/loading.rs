@@ -0,0 +1,278 @@
+// engine/loading.rs
+// Provides the loading phase of a new game, where the ship is built on a thread of its own so that the terminal can
+// keep showing the player how it's getting on
+
+// ###: EXTERNAL LIBRARIES
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use bevy_turborand::prelude::*;
+use simplelog::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::airlock::AIRLOCK_PANEL_ITEM;
+use crate::artisan::ItemBuilder;
+use crate::mason::{get_world_builder, BuildProgress, WorldBuilder, WorldPlan};
+use crate::mason::seeding::{seed_items, SpawnDensity};
+use crate::salvage::{salvage_bin_room, SALVAGE_BIN_ITEM};
+
+// ###: COMPLEX TYPES
+//  ##: LoadingJob
+/// Keeps track of a WorldBuilder that's running in the background; dropping the job cancels it, since the builder's
+/// result has nowhere to go once the receiver is gone
+pub struct LoadingJob {
+	pub stage: String, // The name of the stage that the builder last reported
+	pub percent: u8,
+	receiver: Receiver<BuildProgress>,
+	cancelled: Arc<AtomicBool>, // Shared with the worker, which gives up at the next stage once this is set
+}
+impl LoadingJob {
+	/// Starts building a new ship on a background thread
+	pub fn start(artisan: ItemBuilder, run_seed: u64) -> LoadingJob {
+		LoadingJob::start_with(get_world_builder(), artisan, run_seed)
+	}
+	/// Starts building a new ship with the given builder; the ItemBuilder is a copy, since it's only used for working
+	/// out where things go, and the spawning itself happens back in the World once the job is done
+	pub fn start_with(mut builder: Box<dyn WorldBuilder>, artisan: ItemBuilder, run_seed: u64) -> LoadingJob {
+		let (sender, receiver) = channel();
+		let cancelled = Arc::new(AtomicBool::new(false));
+		let is_cancelled = cancelled.clone();
+		thread::spawn(move || {
+			builder.build_world(Some(&sender));
+			if is_cancelled.load(Ordering::Relaxed) { return; }
+			let plan = plan_world(builder.as_ref(), &artisan, run_seed, Some(&sender));
+			if is_cancelled.load(Ordering::Relaxed) { return; }
+			// If the job was dropped in the meantime, this send fails and the plan is thrown away with the thread
+			let _ = sender.send(BuildProgress::Done(Box::new(plan)));
+		});
+		LoadingJob {
+			stage: "Starting up".to_string(),
+			percent: 0,
+			receiver,
+			cancelled,
+		}
+	}
+	/// Tells the worker to stop at the next stage; the job reports that it was cancelled once the worker has gone
+	pub fn cancel(&mut self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+	/// Picks up any progress reports that have come in since the last poll
+	pub fn poll(&mut self) -> LoadStatus {
+		loop {
+			match self.receiver.try_recv() {
+				Ok(BuildProgress::Stage(stage, percent)) => {
+					self.stage = stage;
+					self.percent = percent;
+				}
+				Ok(BuildProgress::Done(plan)) => {
+					if self.cancelled.load(Ordering::Relaxed) { return LoadStatus::Cancelled; }
+					return LoadStatus::Done(plan);
+				}
+				Err(TryRecvError::Empty) => { return LoadStatus::Working; }
+				Err(TryRecvError::Disconnected) => {
+					if self.cancelled.load(Ordering::Relaxed) { return LoadStatus::Cancelled; }
+					error!("! the world builder thread stopped without finishing"); // DEBUG: report a crashed builder thread
+					return LoadStatus::Failed;
+				}
+			}
+		}
+	}
+}
+impl Drop for LoadingJob {
+	fn drop(&mut self) {
+		self.cancel();
+	}
+}
+//  ##: LoadStatus
+/// Describes where a LoadingJob has got to
+pub enum LoadStatus {
+	Working,
+	Done(Box<WorldPlan>),
+	Cancelled, // The job was called off, and the worker has stopped without handing anything back
+	Failed, // The builder's thread went away without handing anything back, ie it panicked
+}
+
+// ###: METHODS
+/// Works out where everything goes on a freshly-built ship: the items that the ship's plans ask for, the salvage bin,
+/// the airlock panels, and a scattering of loose items, all seeded from the run seed so that the same seed always
+/// lays out the same ship; nothing is spawned here, so this is safe to run away from the World
+pub fn plan_world(builder: &dyn WorldBuilder, artisan: &ItemBuilder, run_seed: u64, progress: Option<&Sender<BuildProgress>>) -> WorldPlan {
+	if let Some(sender) = progress {
+		let _ = sender.send(BuildProgress::Stage("Stocking the rooms".to_string(), 90));
+	}
+	let mut rng = GlobalRng::with_seed(run_seed);
+	// Get a copy of the freshly-constructed world model
+	let mut model = builder.get_model();
+	// Get the list of items that we know for sure need to be generated at specific positions
+	let mut spawns = builder.get_essential_item_requests(); // list of (name, posn)
+	// Next, get the list of requested items, find spawnpoints for them, and add them to the list of spawns
+	for (room_name, item_name) in builder.get_additional_item_requests().iter() {
+		if let Some(item_shape) = artisan.get_random_shape(item_name, &mut rng) {
+			if let Some(mut item_spawns) = model.find_spawnpoint_in(room_name, item_shape, &mut rng) {
+				spawns.append(&mut item_spawns);
+			}
+		}
+	}
+	// The salvage bin goes wherever the player will be leaving from, so that they can cash in on their way out
+	if let Some(room_name) = salvage_bin_room(&model) {
+		if let Some(mut bin_spawns) = artisan.get_random_shape(SALVAGE_BIN_ITEM, &mut rng).and_then(|x| model.find_spawnpoint_in(&room_name, x, &mut rng)) {
+			spawns.append(&mut bin_spawns);
+		}
+	}
+	// Each airlock chamber in the ship's plans gets a panel to cycle its doors with; the doors are linked up once
+	// they've been spawned
+	let airlocks = builder.get_airlocks();
+	for link in airlocks.iter() {
+		if let Some(mut panel_spawns) = artisan.get_random_shape(AIRLOCK_PANEL_ITEM, &mut rng).and_then(|x| model.find_spawnpoint_in(&link.room, x, &mut rng)) {
+			spawns.append(&mut panel_spawns);
+		}
+	}
+	// Then scatter some loose items around the rooms; this is seeded separately so that it comes out the same
+	// for the same run seed, no matter how many numbers the placements above drew from the RNG
+	let mut scattered = seed_items(&mut model, artisan, &SpawnDensity::default(), run_seed, &spawns);
+	spawns.append(&mut scattered);
+	WorldPlan {
+		model,
+		spawns,
+		power_grid: builder.get_power_grid(),
+		airlocks,
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use std::sync::mpsc::SyncSender;
+	use std::sync::Mutex;
+	use std::time::{Duration, Instant};
+	use crate::airlock::AirlockLink;
+	use crate::components::Position;
+	use crate::power::PowerGrid;
+	use crate::testmap::*;
+	use crate::worldmap::{TileType, WorldModel};
+	use super::*;
+	//  ##: Setup
+	/// Stands in for the JSON builder with a small test map; if it's given a gate, it waits there until the test
+	/// opens it, so that the test can be sure of where the worker has got to
+	struct TestBuilder {
+		model: WorldModel,
+		doors: Vec<Position>,
+		gate: Option<Mutex<Receiver<()>>>,
+	}
+	impl TestBuilder {
+		fn new(gate: Option<Receiver<()>>) -> TestBuilder {
+			let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR)
+				.with_room("west room", Position::new(1, 1, 0), Position::new(3, 3, 0))
+				.with_room("east room", Position::new(5, 1, 0), Position::new(9, 3, 0));
+			TestBuilder { model: testmap.model, doors: testmap.doors, gate: gate.map(Mutex::new) }
+		}
+	}
+	impl WorldBuilder for TestBuilder {
+		fn build_world(&mut self, progress: Option<&Sender<BuildProgress>>) {
+			if let Some(sender) = progress {
+				let _ = sender.send(BuildProgress::Stage("Waiting at the gate".to_string(), 50));
+			}
+			if let Some(gate) = self.gate.as_ref() {
+				let _ = gate.lock().map(|x| x.recv_timeout(Duration::from_secs(10)));
+			}
+		}
+		fn get_model(&self) -> WorldModel { self.model.clone() }
+		fn get_essential_item_requests(&self) -> Vec<(String, Position)> {
+			self.doors.iter().map(|x| ("door".to_string(), *x)).collect()
+		}
+		fn get_additional_item_requests(&self) -> Vec<(String, String)> { Vec::new() }
+		fn get_power_grid(&self) -> PowerGrid { PowerGrid::default() }
+		fn get_airlocks(&self) -> Vec<AirlockLink> { Vec::new() }
+	}
+	/// Polls the job until it's no longer working, or gives up after a while so that a stuck worker fails the test
+	fn wait_for(job: &mut LoadingJob) -> LoadStatus {
+		let started = Instant::now();
+		loop {
+			match job.poll() {
+				LoadStatus::Working => {
+					assert!(started.elapsed() < Duration::from_secs(10), "the loading job never finished");
+					thread::sleep(Duration::from_millis(5));
+				}
+				other => { return other; }
+			}
+		}
+	}
+	//  ##: Planning
+	#[test]
+	fn the_same_seed_plans_the_same_world() {
+		let artisan = ItemBuilder::new();
+		let first = plan_world(&TestBuilder::new(None), &artisan, 42, None);
+		let second = plan_world(&TestBuilder::new(None), &artisan, 42, None);
+		assert_eq!(first.spawns, second.spawns);
+		assert!(first.spawns.contains(&("door".to_string(), Position::new(4, 2, 0))));
+	}
+	#[test]
+	fn the_whole_plan_comes_back_from_the_worker() {
+		let artisan = ItemBuilder::new();
+		let expected = plan_world(&TestBuilder::new(None), &artisan, 7, None);
+		let mut job = LoadingJob::start_with(Box::new(TestBuilder::new(None)), artisan, 7);
+		match wait_for(&mut job) {
+			LoadStatus::Done(plan) => { assert_eq!(plan.spawns, expected.spawns); }
+			_ => { panic!("the job should have finished"); }
+		}
+		assert_eq!(job.percent, 90);
+	}
+	//  ##: Seeding
+	/// A density thick enough that the small test map gets a scattering at all, since the default one would leave rooms
+	/// this size empty
+	fn dense() -> SpawnDensity {
+		SpawnDensity { tiles_per_item: 1, max_per_room: 20, ..SpawnDensity::default() }
+	}
+	#[test]
+	fn the_same_seed_scatters_the_same_items() {
+		let artisan = ItemBuilder::new();
+		let builder = TestBuilder::new(None);
+		let reserved = builder.get_essential_item_requests();
+		let first = seed_items(&mut builder.get_model(), &artisan, &dense(), 42, &reserved);
+		let second = seed_items(&mut builder.get_model(), &artisan, &dense(), 42, &reserved);
+		assert!(!first.is_empty());
+		assert_eq!(first, second);
+	}
+	#[test]
+	fn nothing_is_scattered_onto_walls_or_doorways() {
+		let artisan = ItemBuilder::new();
+		let builder = TestBuilder::new(None);
+		let reserved = builder.get_essential_item_requests();
+		let door = Position::new(4, 2, 0);
+		for seed in 0..20 {
+			let mut model = builder.get_model();
+			let scattered = seed_items(&mut model, &artisan, &dense(), seed, &reserved);
+			let mut seen: Vec<Position> = Vec::new();
+			for (name, posn) in scattered.iter() {
+				assert!(!model.is_blocked_at(*posn), "seed {}: {} at {} is on a blocked tile", seed, name, posn);
+				assert_eq!(model.get_tiletype_at(*posn), TileType::Floor, "seed {}: {} at {}", seed, name, posn);
+				// Neither in the doorway itself, nor on either side of it where it'd be in the way
+				assert!((door.x - posn.x).abs() + (door.y - posn.y).abs() > 1, "seed {}: {} at {} blocks the door", seed, name, posn);
+				assert!(!seen.contains(posn), "seed {}: two things at {}", seed, posn);
+				seen.push(*posn);
+			}
+		}
+	}
+	//  ##: Cancelling
+	#[test]
+	fn a_cancelled_job_hands_nothing_back() {
+		let (open_gate, gate): (SyncSender<()>, Receiver<()>) = std::sync::mpsc::sync_channel(1);
+		let mut job = LoadingJob::start_with(Box::new(TestBuilder::new(Some(gate))), ItemBuilder::default(), 7);
+		job.cancel();
+		let _ = open_gate.send(());
+		assert!(matches!(wait_for(&mut job), LoadStatus::Cancelled));
+	}
+	#[test]
+	fn dropping_a_job_lets_the_worker_go() {
+		let (open_gate, gate): (SyncSender<()>, Receiver<()>) = std::sync::mpsc::sync_channel(1);
+		let job = LoadingJob::start_with(Box::new(TestBuilder::new(Some(gate))), ItemBuilder::default(), 7);
+		let cancelled = job.cancelled.clone();
+		drop(job);
+		assert!(cancelled.load(Ordering::Relaxed));
+		// The worker can still finish its stage, but it has nowhere to send anything, and nobody waits on it
+		assert!(open_gate.send(()).is_ok());
+	}
+}
+
+// EOF
@@ -11,7 +11,7 @@ use std::cmp::Ordering;
 use ratatui::{
 	buffer::Buffer,
 	layout::Rect,
-	style::{Color, Style},
+	style::{Color, Modifier, Style},
 	text::Span,
 	widgets::{
 		Block,
@@ -256,7 +256,11 @@ impl<T> MenuItem<T> {
 	pub fn is_group(&self) -> bool {
 		!self.children.is_empty()
 	}
-	fn name(&self) -> &str {
+	/// Returns the data that best describes this entry: its own, or if it's a submenu group, its first child's
+	pub fn help_data(&self) -> Option<&T> {
+		self.data.as_ref().or_else(|| self.children.first().and_then(|x| x.help_data()))
+	}
+	pub fn name(&self) -> &str {
 		&self.name
 	}
 	fn highlight_first_child(&mut self) -> Option<Position> {
@@ -363,6 +367,8 @@ pub struct Menu<'a, T> {
 	block: Option<Block<'a>>,
 	default_style: Style,
 	highlight_style: Style,
+	high_vis: bool, // If true, the selection is drawn with the high_vis_style and a marker, for low-vision players
+	high_vis_style: Style,
 	drop_width: u16,
 	drop_style: Style,
 	shadow_style: Style,
@@ -379,6 +385,8 @@ impl<'a, T> Menu<'a, T> {
 			block: None,
 			default_style: Style::default().fg(Color::Black).bg(Color::Gray),
 			highlight_style: Style::default().fg(Color::Black).bg(Color::White),
+			high_vis: false,
+			high_vis_style: Style::default().fg(Color::Yellow).bg(Color::Black).add_modifier(Modifier::BOLD),
 			drop_width: 20,
 			drop_style: Style::default().fg(Color::Black).bg(Color::Gray),
 			shadow_style: Style::default().fg(Color::Red).bg(Color::DarkGray),
@@ -397,6 +405,10 @@ impl<'a, T> Menu<'a, T> {
 		self.highlight_style = style;
 		self
 	}
+	pub fn high_visibility(mut self, enabled: bool) -> Self {
+		self.high_vis = enabled;
+		self
+	}
 	pub fn dropdown_width(mut self, width: u16) -> Self {
 		self.drop_width = width;
 		self
@@ -418,9 +430,9 @@ impl<'a, T> Menu<'a, T> {
 				x,
 				item_y,
 				&Span::styled(
-					item.name(),
+					self.label(item),
 					if is_active {
-						self.highlight_style
+						if self.high_vis { self.high_vis_style } else { self.highlight_style }
 					} else {
 						self.default_style
 					},
@@ -441,6 +453,14 @@ impl<'a, T> Menu<'a, T> {
 			}
 		}
 	}
+	/// Produces the text to draw for a menu item; in high-visibility mode, every item gets a gutter for the selection marker
+	fn label(&self, item: &MenuItem<T>) -> String {
+		if !self.high_vis {
+			return item.name().to_string();
+		}
+		let marker = if item.is_highlighted { "▶ " } else { "  " };
+		format!("{}{}", marker, item.name())
+	}
 	/// Draws the drop-shadow underneath a menu, given the area it will occupy
 	/// Note that this does NOT clear the menu's area after drawing into it; the caller must do so before drawing the menu
 	/// This helps ensure that nothing is removed that shouldn't be
@@ -515,6 +535,9 @@ impl MenuHelperGameEvent {
 			| ActionType::CloseItem
 			| ActionType::LockItem
 			| ActionType::UnlockItem
+			| ActionType::HackItem
+			| ActionType::EmptyItem
+			| ActionType::AssignSlot
 			=> {
 				self.subject != Entity::PLACEHOLDER && self.object != Entity::PLACEHOLDER
 			},
@@ -552,4 +575,44 @@ pub enum MenuEvent<T> {
 	Selected(T),
 }
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Selection cursor
+	/// Draws a two-item menu with its first item selected into a headless buffer, and returns that buffer
+	fn drawn_menu(high_vis: bool) -> Buffer {
+		let mut state = MenuState::new(vec![MenuItem::item("Save", "save", None), MenuItem::item("Quit", "quit", None)]);
+		state.activate();
+		let mut buf = Buffer::empty(Rect::new(0, 0, 24, 4));
+		Menu::new().high_visibility(high_vis).render(Rect::new(0, 0, 20, 1), &mut buf, &mut state);
+		buf
+	}
+	/// Returns the text on the given row of the buffer, without any trailing spaces
+	fn row_text(buf: &Buffer, row: u16) -> String {
+		(0..buf.area.width).map(|x| buf.get(x, row).symbol.as_str()).collect::<String>().trim_end().to_string()
+	}
+	#[test]
+	fn the_high_visibility_cursor_marks_and_recolors_the_selection() {
+		let buf = drawn_menu(true);
+		assert_eq!(row_text(&buf, 0), "▶ Save");
+		let cell = buf.get(2, 0);
+		assert_eq!((cell.fg, cell.bg), (Color::Yellow, Color::Black));
+		assert!(cell.modifier.contains(Modifier::BOLD));
+		// The other items are lined up with the selection, but otherwise drawn as usual
+		assert_eq!(row_text(&buf, 1), "  Quit");
+		let cell = buf.get(2, 1);
+		assert_eq!((cell.fg, cell.bg), (Color::Black, Color::Gray));
+	}
+	#[test]
+	fn the_standard_cursor_only_lightens_the_selection() {
+		let buf = drawn_menu(false);
+		assert_eq!(row_text(&buf, 0), "Save");
+		let cell = buf.get(0, 0);
+		assert_eq!((cell.fg, cell.bg), (Color::Black, Color::White));
+		assert!(!cell.modifier.contains(Modifier::BOLD));
+		assert_eq!(row_text(&buf, 1), "Quit");
+	}
+}
+
 // EOF
@@ -5,6 +5,11 @@
 use bevy::prelude::*;
 use ratatui::text::{Line, Span};
 use ratatui::style::{Style, Color, Modifier};
+use unicode_width::UnicodeWidthChar;
+
+//  ###: INTERNAL LIBRARIES
+use crate::components::{Position, Viewshed};
+use crate::planq::PlanqBootText;
 
 //  ###: COMPLEX TYPES
 //   ##: MessageLog
@@ -12,7 +17,8 @@ use ratatui::style::{Style, Color, Modifier};
 #[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
 #[reflect(Resource)]
 pub struct MessageLog {
-	pub logs: Vec<MessageChannel>
+	pub logs: Vec<MessageChannel>,
+	pub last_block: u32, // The id that was handed out to the most recent block of messages; 0 means there hasn't been one
 }
 impl MessageLog {
 	/// Creates a new MessageLog with the preset channels
@@ -21,7 +27,7 @@ impl MessageLog {
 		for name in channels {
 			new_logs.push(MessageChannel::new(&name));
 		}
-		MessageLog{ logs: new_logs }
+		MessageLog{ logs: new_logs, last_block: 0 }
 	}
 	//  * TOOLS
 	/// Adds a new message to the given channel; if the channel does not exist it will be made
@@ -45,6 +51,28 @@ impl MessageLog {
 		new_channel.add(Message::new(msg_time, msg_prio, msg_chan, msg_text));
 		self.logs.push(new_channel);
 	}
+	/// Adds a whole set of lines to the given channel in one go, so that nothing else can end up in between them;
+	/// the lines all share a block id, which the displays use to keep them together. Returns the new block's id
+	/// # Arguments
+	/// * `msg_chan`  - The msg channel's name, ie "planq"
+	/// * `msg_lines` - The text of each message, in order
+	/// * `msg_prio`  - Higher -> more important
+	pub fn add_block(&mut self, msg_chan: &str, msg_lines: Vec<String>, msg_prio: i32) -> u32 {
+		self.last_block += 1;
+		let block_id = self.last_block;
+		let chan_index = if let Some(index) = self.logs.iter().position(|x| x.name == msg_chan) {
+			index
+		} else {
+			self.logs.push(MessageChannel::new(msg_chan));
+			self.logs.len() - 1
+		};
+		for text in msg_lines.iter() {
+			let mut new_msg = Message::new(0, msg_prio, msg_chan, text);
+			new_msg.block = block_id;
+			self.logs[chan_index].add(new_msg);
+		}
+		block_id
+	}
 	/// Replaces the last message in the given channel with the new message; does nothing if channel does not exist
 	pub fn replace(&mut self, msg_text: &str, msg_chan: &str, msg_prio: i32, msg_time: i32) {
 		// Check for an existing channel to add the new message to
@@ -65,47 +93,19 @@ impl MessageLog {
 		}
 		0
 	}
-	/// Sends a boot message associated with the given boot_stage to the PLANQ's channel
-	pub fn boot_message(&mut self, boot_stage: u32) {
-		if boot_stage > 4 {
-			return;
+	/// Returns the text of the most recent message in the specified channel, if there is one
+	pub fn last_text(&self, req_channel: &str) -> Option<&str> {
+		self.logs.iter().find(|x| x.name == req_channel).and_then(|x| x.contents.last()).map(|x| x.text.as_str())
+	}
+	/// Sends the boot message for the given boot_stage to the PLANQ's channel, as the given PlanqBootText words it
+	/// A stage with several lines goes up as a single block; a stage without any lines is passed over quietly
+	pub fn boot_message(&mut self, boot_stage: u32, text: &PlanqBootText) {
+		let lines = if let Some(value) = text.stages.get(boot_stage as usize) { value } else { return };
+		match lines.len() {
+			0 => { }
+			1 => { self.tell_planq(&lines[0]); }
+			_ => { self.add_block("planq", lines.clone(), 0); }
 		}
-		match boot_stage {
-			// This version of the OS logo doesn't have the extra \s, which are required as escapes by Rust
-			//                     ▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄
-			//                     ▌ __         __  __     __   ▐
-			//                     ▌/   _||   |/  \(_     /_    ▐
-			//                     ▌\__(-|||_||\__/__)  \/__)/) ▐
-			//                     ▌────────<-──────────<-─<{ (<▐
-			//                     ▌         \           \   \) ▐
-			//                     ▙▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▟
-			//                     _123456789_12356789_123456789_
-			0 => {
-				//│─
-				self.tell_planq("[[fg:gray]]╃────────────────────────────╄");
-				self.tell_planq("[[fg:gray]]│[[fg:ltcyan]] __         __  __     __   [[fg:gray]]│");
-				self.tell_planq("[[fg:gray]]│[[fg:ltcyan]]/   _||   |/  \\(_     /_    [[fg:gray]]│");
-				self.tell_planq("[[fg:gray]]│[[fg:ltcyan]]\\__(-|||_||\\__/__)  [[fg:green]]\\/[[fg:ltcyan]]__)[[fg:red]]/) [[fg:gray]]│");
-				self.tell_planq("[[fg:gray]]│[[fg:green]]────────<-──────────<-─<[[fg:red]]{ (<[[fg:gray]]│");
-				self.tell_planq("[[fg:gray]]│[[fg:green]]         \\           \\   [[fg:red]]\\) [[fg:gray]]│");
-				self.tell_planq("[[fg:gray]]┽────────────────────────────╆");
-				self.tell_planq(" ");
-				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]BIOS:  GRAIN v17.6.8, [[mod:+italic]]Cedar[[end]]");
-			}
-			1 => {
-				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Hardware Status ..... [ [[fg:green]]OK[[end]] ]");
-			}
-			2 => {
-				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Firmware Status ..... [ [[fg:green]]OK[[end]] ]");
-			}
-			3 => {
-				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Bootloader Status ... [ [[fg:green]]OK[[end]] ]");
-			}
-			4 => {
-				self.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Ready for input!");
-			}
-			_ => { }
-		};
 	}
 	/// Clears a message channel's backscroll: WARN: irreversible!
 	/// Returns false if the specified channel was not found
@@ -160,6 +160,13 @@ impl MessageLog {
 	pub fn tell_planq(&mut self, msg_text: &str) {
 		self.add(msg_text, "planq", 0, 0);
 	}
+	/// Helper method: adds a command's output to the "planq" channel as a single block, with the usual line prefix and
+	/// the blank line after it
+	pub fn tell_planq_block(&mut self, lines: Vec<String>) {
+		let mut output: Vec<String> = lines.iter().map(|x| format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", x)).collect();
+		output.push(" ".to_string());
+		self.add_block("planq", output, 0);
+	}
 
 }
 /// Implements the Default trait for the reference type
@@ -167,10 +174,51 @@ impl<'a> Default for &'a MessageLog {
 	fn default() -> &'a MessageLog {
 		static VALUE: MessageLog = MessageLog {
 			logs: Vec::new(),
+			last_block: 0,
 		};
 		&VALUE
 	}
 }
+//   ##: PerceptionPolicy
+/// Decides whether the player gets to hear about something that another actor did, based on where it happened
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct PerceptionPolicy {
+	pub mode: PerceptionMode,
+	pub earshot: i32, // The max distance on the same deck at which the player can hear something they can't see
+}
+impl Default for PerceptionPolicy {
+	fn default() -> PerceptionPolicy {
+		PerceptionPolicy {
+			mode: PerceptionMode::SightOrEarshot,
+			earshot: 6,
+		}
+	}
+}
+impl PerceptionPolicy {
+	pub fn new() -> PerceptionPolicy {
+		PerceptionPolicy::default()
+	}
+	/// Returns true if a listener at the given Position, with the given view, could perceive something that
+	/// happened at the source Position
+	pub fn can_perceive(&self, listener: Position, view: Option<&Viewshed>, source: Position) -> bool {
+		if self.mode == PerceptionMode::Omniscient { return true; }
+		if listener.z != source.z { return false; } // Nobody can see or hear through the decks
+		let in_sight = view.map_or(false, |x| x.visible_points.iter().any(|pt| pt.x == source.x && pt.y == source.y));
+		match self.mode {
+			PerceptionMode::SightOnly => in_sight,
+			_ => in_sight || listener.in_range_of(&source, self.earshot),
+		}
+	}
+}
+/// Defines how strictly the PerceptionPolicy filters messages about other actors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum PerceptionMode {
+	Omniscient, // Report everything, no matter where it happened
+	SightOnly,
+	#[default]
+	SightOrEarshot,
+}
 //   ##: MessageChannel
 /// Holds a series of Messages, which all share the same channel name
 #[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
@@ -205,6 +253,7 @@ pub struct Message {
 	pub priority: i32,
 	pub channel: String,
 	pub text: String,
+	pub block: u32, // The id of the block that this message was added in, or 0 if it was added on its own
 }
 impl Message {
 	pub fn new(time: i32, level: i32, chan: &str, msg: &str) -> Message {
@@ -213,6 +262,7 @@ impl Message {
 			priority: level,
 			channel: chan.to_string(),
 			text: msg.to_string(),
+			block: 0,
 		}
 	}
 }
@@ -356,4 +406,160 @@ impl From<Message> for Line<'_> {
 	}
 }
 
+//  ###: UTILITIES
+/// Breaks a Line up into rows that are no wider than the given number of terminal columns, keeping each span's style
+/// Double-width chars count as two columns, and are never split across rows
+pub fn wrap_line(line: Line<'_>, width: usize) -> Vec<Line<'_>> {
+	let mut rows: Vec<Line> = Vec::new();
+	let mut row: Vec<Span> = Vec::new();
+	let mut row_width = 0;
+	for span in line.spans.iter() {
+		let mut chunk = String::new();
+		for letter in span.content.chars() {
+			let letter_width = letter.width().unwrap_or(0);
+			if row_width > 0 && row_width + letter_width > width {
+				if !chunk.is_empty() { row.push(Span::styled(std::mem::take(&mut chunk), span.style)); }
+				rows.push(Line::from(std::mem::take(&mut row)));
+				row_width = 0;
+			}
+			chunk.push(letter);
+			row_width += letter_width;
+		}
+		if !chunk.is_empty() { row.push(Span::styled(chunk, span.style)); }
+	}
+	rows.push(Line::from(row));
+	rows
+}
+/// Works out which rows of a backlog to show, given the block id of each row, how many rows fit, and how far back the
+/// view has been scrolled; returns the start and end of the visible range
+/// When scrolled back, a block that would be cut in half at the top edge is shown from its first row instead, as long
+/// as the whole block fits; the scroll is clamped and updated to match what's shown
+pub fn block_window(blocks: &[u32], visible: usize, scroll: &mut usize) -> (usize, usize) {
+	*scroll = (*scroll).min(blocks.len().saturating_sub(visible));
+	let end = blocks.len() - *scroll;
+	let start = end.saturating_sub(visible);
+	if *scroll == 0 || start == 0 || blocks[start] == 0 || blocks[start - 1] != blocks[start] {
+		return (start, end);
+	}
+	let block_id = blocks[start];
+	let first = blocks[..start].iter().rposition(|x| *x != block_id).map_or(0, |x| x + 1);
+	let last = start + blocks[start..].iter().position(|x| *x != block_id).unwrap_or(blocks.len() - start);
+	if last - first > visible { return (start, end); }
+	let end = (first + visible).min(blocks.len());
+	*scroll = blocks.len() - end;
+	(first, end)
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	/// Returns the text of every message in the PLANQ's channel, along with the block each one went up in
+	fn planq_lines(msglog: &MessageLog) -> Vec<(String, u32)> {
+		msglog.logs.iter().find(|x| x.name == "planq").map_or(Vec::new(), |x| x.contents.iter().map(|y| (y.text.clone(), y.block)).collect())
+	}
+	//  ##: Boot messages
+	#[test]
+	fn the_default_boot_text_is_the_grain_bios() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		let text = PlanqBootText::default();
+		msglog.boot_message(0, &text);
+		assert_eq!(planq_lines(&msglog).len(), text.stages[0].len());
+		assert!(planq_lines(&msglog).iter().all(|x| x.1 == 1));
+		msglog.boot_message(4, &text);
+		assert_eq!(msglog.last_text("planq"), Some("[[fg:yellow]]¶[[fg:gray]]│[[end]]Ready for input!"));
+	}
+	#[test]
+	fn overridden_boot_text_is_what_gets_sent() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		let text = PlanqBootText::new(vec![
+			vec!["MINCE-OS".to_string(), "(c) Mince Heavy Industries".to_string()],
+			vec!["Checking the cutlery".to_string()],
+		]);
+		msglog.boot_message(1, &text);
+		assert_eq!(planq_lines(&msglog), vec![("Checking the cutlery".to_string(), 0)]);
+		// A stage with several lines goes up together as one block
+		msglog.boot_message(0, &text);
+		assert_eq!(planq_lines(&msglog)[1..].to_vec(), vec![("MINCE-OS".to_string(), 1), ("(c) Mince Heavy Industries".to_string(), 1)]);
+	}
+	#[test]
+	fn missing_and_empty_stages_are_passed_over() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		let mut text = PlanqBootText::new(Vec::new());
+		text.set_stage(2, vec!["Third time lucky".to_string()]);
+		assert_eq!(text.stages.len(), 3);
+		for stage in 0..5 {
+			msglog.boot_message(stage, &text);
+		}
+		assert_eq!(planq_lines(&msglog), vec![("Third time lucky".to_string(), 0)]);
+	}
+	//  ##: Blocks
+	#[test]
+	fn a_block_goes_up_in_one_piece() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		msglog.tell_planq("before");
+		let first = msglog.add_block("planq", vec!["one".to_string(), "two".to_string()], 0);
+		msglog.tell_planq("between");
+		msglog.tell_planq_block(vec!["three".to_string()]);
+		assert_eq!(first, 1);
+		assert_eq!(planq_lines(&msglog), vec![
+			("before".to_string(), 0),
+			("one".to_string(), 1),
+			("two".to_string(), 1),
+			("between".to_string(), 0),
+			("[[fg:yellow]]¶[[fg:gray]]│[[end]]three".to_string(), 2),
+			(" ".to_string(), 2),
+		]);
+		assert_eq!(msglog.last_block, 2);
+	}
+	#[test]
+	fn scrolling_back_never_cuts_the_top_off_a_block() {
+		let blocks = [0, 0, 1, 1, 1, 0, 0];
+		let mut scroll = 0;
+		assert_eq!(block_window(&blocks, 3, &mut scroll), (4, 7));
+		// One row back would show only the tail of the block, so the view moves up to its first row instead
+		scroll = 1;
+		assert_eq!(block_window(&blocks, 3, &mut scroll), (2, 5));
+		assert_eq!(scroll, 2);
+		// Lines that aren't part of any block can be cut anywhere
+		scroll = 3;
+		assert_eq!(block_window(&blocks, 3, &mut scroll), (1, 4));
+		scroll = 99;
+		assert_eq!(block_window(&blocks, 3, &mut scroll), (0, 3));
+		assert_eq!(scroll, 4);
+	}
+	#[test]
+	fn a_block_too_big_to_fit_is_cut_anyway() {
+		let blocks = [0, 1, 1, 1, 1, 0];
+		let mut scroll = 1;
+		assert_eq!(block_window(&blocks, 2, &mut scroll), (3, 5));
+		assert_eq!(scroll, 1);
+	}
+	//  ##: Wrapping
+	/// Wraps the text to the given width, and returns the text of each row along with how many columns it takes up
+	fn wrapped(text: &str, width: usize) -> Vec<(String, usize)> {
+		wrap_line(Line::from(text.to_string()), width).iter()
+			.map(|x| (x.spans.iter().map(|y| y.content.to_string()).collect::<String>(), x.width()))
+			.collect()
+	}
+	#[test]
+	fn wide_chars_are_never_split_across_rows() {
+		// Each of these takes up two columns, so only two of them fit into a row of five
+		assert_eq!(wrapped("日本語です", 5), vec![("日本".to_string(), 4), ("語で".to_string(), 4), ("す".to_string(), 2)]);
+		assert_eq!(wrapped("ab日cd", 3), vec![("ab".to_string(), 2), ("日c".to_string(), 3), ("d".to_string(), 1)]);
+	}
+	#[test]
+	fn combining_marks_stay_with_their_letter() {
+		// The accent doesn't take up a column of its own, so it can't be pushed onto the next row by itself
+		assert_eq!(wrapped("cafe\u{301}s", 4), vec![("cafe\u{301}".to_string(), 4), ("s".to_string(), 1)]);
+	}
+	#[test]
+	fn wrapped_rows_keep_their_styles() {
+		let line = Line::from(vec![Span::raw("abc"), Span::styled("de", Style::default().fg(Color::Red))]);
+		let rows = wrap_line(line, 4);
+		assert_eq!(rows.len(), 2);
+		assert_eq!(rows[1].spans, vec![Span::styled("e", Style::default().fg(Color::Red))]);
+	}
+}
+
 // EOF
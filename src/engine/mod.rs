@@ -4,6 +4,7 @@
 // ###: EXTERNAL LIBS
 use std::borrow::Cow;
 use std::error;
+use std::path::{Path, PathBuf};
 use bevy::{
 	prelude::*,
 	utils::*,
@@ -11,6 +12,7 @@ use bevy::{
 use bevy_save::prelude::*;
 use bevy_turborand::prelude::*;
 use bracket_rex::prelude::*;
+use bracket_geometry::prelude::Point;
 use ratatui::{
 	prelude::*,
 	Frame,
@@ -23,38 +25,74 @@ use ratatui::{
 	widgets::*,
 };
 use strum::IntoEnumIterator;
+use tui_textarea::TextArea;
 
 // ###: INTERNAL LIBS
 pub mod event;
 pub mod handler;
+pub mod legacy;
+pub mod loading;
 pub mod menu;
 pub mod messagelog;
+pub mod palette;
+pub mod preferences;
+pub mod saveslot;
+pub mod snapshot;
 pub mod tui;
 pub mod viewport;
 use crate::{
+	airlock::*,
 	artisan::*,
+	bookmarks::*,
 	camera::*,
 	components::*,
+	decks::*,
 	engine::{
 		event::*,
+		legacy::*,
+		loading::*,
 		menu::*,
 		messagelog::*,
+		palette::*,
+		preferences::*,
+		saveslot::*,
 		tui::*,
 		viewport::Viewport,
 	},
+	fov::*,
 	mason::{
 		get_world_builder,
 		rexpaint_loader::load_rex_pgraph,
 		WorldBuilder,
+		WorldPlan,
 	},
+	objectives::*,
 	planq::*,
+	planq::comms::*,
+	planq::dashboard::*,
+	planq::minimap::*,
 	planq::monitor::*,
+	planq::transcript::*,
 	planq::tui::*,
+	power::*,
+	quickslots::*,
 	rex_assets::*,
+	robot::*,
+	salvage::*,
+	scenarios::{Scenario, ScenarioKind},
+	shiplog::*,
 	sys::*,
+	tutorial::*,
 	worldmap::*,
 };
 
+// ###: CONSTANTS
+/// The longest name that the player can give their character
+pub const MAX_NAME_LEN: usize = 20;
+/// The smallest terminal that the game will draw itself into; anything smaller gets a notice instead
+pub const MIN_TERM_WIDTH: u16 = 60;
+pub const MIN_TERM_HEIGHT: u16 = 20;
+
 // ###: COMPLEX TYPES
 //  ##: GameEngine
 pub struct GameEngine<'a> {
@@ -72,13 +110,37 @@ pub struct GameEngine<'a> {
 	pub layout_changed: bool,
 	pub default_block:  Block<'a>,
 	pub default_style:  Style,
-	pub savegame_filename: String,
+	pub savegame_filename: Option<String>, // The slot that this run was loaded from or last saved to; None until then
+	pub player_name:    String, // The name that the next new game's player character will be given
+	pub naming:         bool, // If true, the player is typing a new name into the main menu
 	pub term_dims:      Rect,
 	pub planq_stdin:    PlanqInput<'a>,
+	pub run_seed:       u64, // The RNG seed that the current game was started with
+	pub files:          EngineFiles, // Where the preferences, the legacy record, and the save slots are kept
+	pub legacy:         LegacyRecord, // The outcomes of previous runs, as loaded from the legacy file
+	pub palette:        Palette, // The colors that the camera is drawn with; mirrored into Bevy for the camera system
+	pub prefs:          Preferences, // The player's own preferences, which persist between games; see apply_prefs()
+	pub run_summary:    Option<RunSummary>, // How the last run went, for the end screen; only set once the game ends
+	pub focus:          PanelFocus, // The panel that receives the scrolling and navigation keys
+	pub log_scroll:     usize, // How many lines the message log has been scrolled back from the latest message
+	pub planq_scroll:   usize, // How many lines the PLANQ's terminal has been scrolled back from the latest output
+	pub pending_move:   Option<crate::components::Direction>, // A step into a hazard that's waiting on the player to confirm it
+	pub hazards_confirmed: Vec<(Hazard, Position)>, // The hazardous spots that the player has already agreed to enter
+	pub scenario_kind:  ScenarioKind, // The scenario that the next new game will be played under
+	pub scenario:       Box<dyn Scenario>, // The scenario that the current game is being played under
+	pub scenario_reader: bevy::ecs::event::ManualEventReader<GameEvent>, // Feeds the GameEvents to the scenario
+	pub tooltip:        Option<(Position, String)>, // The summary of the tile under the look or targeting cursor, and where it is
+	pub loading:        Option<LoadingJob>, // If set, a new ship is being built in the background and the loading screen is up
+	pub note_input:     Option<(Position, TextArea<'a>)>, // If set, the player is writing a note to leave at this Position
 }
 impl GameEngine<'_> {
 	/// Constructs a new instance of [`GameEngine`].
 	pub fn new(max_area: Rect) -> Self {
+		GameEngine::with_files(max_area, EngineFiles::default())
+	}
+	/// Constructs a new [`GameEngine`] that keeps its preferences, legacy record, and save slots in the given places
+	pub fn with_files(max_area: Rect, files: EngineFiles) -> Self {
+		let prefs = Preferences::load(&files.prefs);
 		let mut new_eng = GameEngine {
 			running: false,
 			standby: true,
@@ -95,9 +157,28 @@ impl GameEngine<'_> {
 			layout_changed: true,
 			default_block: Block::default().borders(Borders::ALL).border_type(BorderType::Plain),
 			default_style: Style::default().fg(Color::White).bg(Color::Black),
-			savegame_filename: "demo_game".to_string(),
+			savegame_filename: None,
+			player_name: "Pleyeur".to_string(),
+			naming: false,
 			term_dims: max_area,
 			planq_stdin: PlanqInput::new(),
+			run_seed: 0,
+			legacy: LegacyRecord::load(&files.legacy),
+			files,
+			palette: Palette::new(prefs.palette),
+			prefs,
+			run_summary: None,
+			focus: PanelFocus::World,
+			log_scroll: 0,
+			planq_scroll: 0,
+			pending_move: None,
+			hazards_confirmed: Vec::new(),
+			scenario_kind: ScenarioKind::default(),
+			scenario: ScenarioKind::default().build(),
+			scenario_reader: Default::default(),
+			tooltip: None,
+			loading: None,
+			note_input: None,
 		};
 		new_eng.planq_stdin.input.set_cursor_line_style(Style::default().fg(Color::Yellow).bg(Color::Black));
 		new_eng.bevy.add_plugins(MinimalPlugins).add_plugins(SavePlugins);
@@ -122,23 +203,106 @@ impl GameEngine<'_> {
 	 *		}
 	 *	}
 	 */
-		// This is where I'd pull any mode changes that might have happened during the last Bevy update and apply them
-		//if settings.mode_changed { ... }
+		// Nothing else happens while the ship is still being built
+		if self.loading.is_some() {
+			self.poll_loading();
+			return;
+		}
+		// Pull any mode changes that might have been requested during the last Bevy update and apply them
+		let mode_request = self.bevy.world.get_resource::<RunState>().filter(|x| x.mode_changed).map(|x| x.mode);
+		if let Some(new_mode) = mode_request {
+			self.set_mode(new_mode);
+		}
 		// If there are any menu events, handle them
 		for event in self.menu_main.drain_events() {
 			// NOTE: if the user selects a submenu heading as their choice, *nothing* will be generated; the menu will just close
 			//       not sure yet if there's a way to trap that outcome
 			match event {
-				MenuEvent::Selected(item) => match item.as_ref() {
-					"main.new_game"  => { self.new_game(); }
-					"main.load_game" => { self.load_game(&self.savegame_filename.clone()); }
-					"main.save_game" => { self.save_game(&self.savegame_filename.clone()); }
-					"main.abandon_game" => {
-						info!("* Deleting savegame at {} and shutting down...", self.savegame_filename.clone()); // DEBUG: announce game abandon
-						let _ = self.delete_game(&self.savegame_filename.clone()); // WARN: may want to trap this error?
+				// The save slot options carry the name of their slot after a colon, ie "main.load_game:slot_1"
+				MenuEvent::Selected(item) => match item.split_once(':').unwrap_or((item.as_ref(), "")) {
+					("main.new_game", _)  => { self.new_game(); }
+					("main.scenario", _)  => {
+						self.scenario_kind = self.scenario_kind.next();
+						self.set_menu(MenuType::Main, self.menu_posn);
+					}
+					("main.name", _)      => {
+						self.naming = true;
+						self.set_menu(MenuType::Main, self.menu_posn);
+					}
+					("main.load_game", slot) => { self.load_game(slot); }
+					("main.save_game", slot) => { self.save_game(slot); }
+					("main.delete_game", slot) => {
+						if let Err(e) = delete_slot(&self.files.slot_key(slot)) {
+							error!("! could not delete save slot '{}': {}", slot, e); // DEBUG: report a failed slot deletion
+						}
+						// Show the browser again without the slot that was just deleted
+						self.set_menu(MenuType::Main, self.menu_posn);
+					}
+					("main.palette", _)   => { self.set_palette(self.palette.kind.next()); }
+					("main.cursor", _)    => {
+						self.prefs.hivis_cursor = !self.prefs.hivis_cursor;
+						self.apply_prefs();
+						if self.visible_menu == MenuType::Main {
+							self.set_menu(MenuType::Main, self.menu_posn);
+						}
+					}
+					("main.travel_preview", _) => {
+						self.prefs.preview_travel = !self.prefs.preview_travel;
+						self.apply_prefs();
+					}
+					("main.busy_hands", _) => {
+						self.prefs.planq_busy_hands = !self.prefs.planq_busy_hands;
+						self.apply_prefs();
+					}
+					("main.autopower", _) => {
+						self.prefs.planq_autopower = !self.prefs.planq_autopower;
+						self.apply_prefs();
+					}
+					("main.unload_decks", _) => {
+						self.prefs.unload_decks = !self.prefs.unload_decks;
+						self.apply_prefs();
+					}
+					("main.sightings", _) => {
+						self.prefs.announce_sightings = !self.prefs.announce_sightings;
+						self.apply_prefs();
+					}
+					("main.hints", _) => {
+						self.prefs.show_hints = !self.prefs.show_hints;
+						self.apply_prefs();
+					}
+					("main.fov", _) => {
+						self.prefs.fov_algorithm = self.prefs.fov_algorithm.next();
+						self.apply_prefs();
+						// Everyone's view has to be redone with the new algorithm straight away
+						if let Some(mut resync) = self.bevy.world.get_resource_mut::<Events<ResyncViewsheds>>() {
+							resync.send(ResyncViewsheds);
+						}
+					}
+					("main.permadeath", _) => {
+						if let Some(mut run_state) = self.bevy.world.get_resource_mut::<RunState>() {
+							run_state.permadeath = !run_state.permadeath;
+						}
+					}
+					("main.remembered", _) => {
+						self.prefs.remembered_entities = self.prefs.remembered_entities.next();
+						self.apply_prefs();
+					}
+					("main.hazards", _) => {
+						self.prefs.hazard_prompt = self.prefs.hazard_prompt.next();
+						self.apply_prefs();
+					}
+					("main.dir_names", _) => {
+						self.prefs.dir_names = self.prefs.dir_names.next();
+						self.apply_prefs();
+					}
+					("main.abandon_game", _) => {
+						info!("* Deleting savegame at {:?} and shutting down...", self.savegame_filename); // DEBUG: announce game abandon
+						if let Some(slot) = self.savegame_filename.take() {
+							let _ = self.delete_game(&slot); // WARN: may want to trap this error?
+						}
 						self.set_mode(EngineMode::Offline);
 					}
-					"main.quit"      => {
+					("main.quit", _)      => {
 						info!("* Engine is shutting down..."); // DEBUG: announce engine shutdown
 						self.set_mode(EngineMode::Offline);
 					}
@@ -153,6 +317,19 @@ impl GameEngine<'_> {
 				MenuEvent::Selected(event) => {
 					trace!("* tick(): menu event: {:?}", event); // DEBUG: announce the context event that got matched
 					if event.is_valid() {
+						// The menu was built a while ago, so make sure that its target is still there to be acted upon
+						if let Err(reason) = self.recheck_target(&event) {
+							if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+								msglog.tell_player(reason);
+							}
+							continue;
+						}
+						if let Some(reason) = self.planq_refuses(&event) {
+							if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+								msglog.tell_player(&reason);
+							}
+							continue;
+						}
 						if let Some(event_handler) = &mut self.bevy.world.get_resource_mut::<Events<GameEvent>>() {
 							event_handler.send(event);
 						}
@@ -187,7 +364,19 @@ impl GameEngine<'_> {
 			}
 			EngineMode::Running => {
 				/* the main running mode of the game */
+				if let Some(mut run_state) = self.bevy.world.get_resource_mut::<RunState>() {
+					run_state.turn_count += 1;
+				}
 				self.bevy.update();
+				self.stock_lazy_loot();
+				self.run_scenario();
+				// Most of the systems silently stop working without exactly one player, so call it out and stop here
+				if let Err(count) = self.verify_single_player() {
+					error!("! tick(): found {} Player entities, expected exactly 1", count); // DEBUG: report the broken player count
+					if let Some(mut run_state) = self.bevy.world.get_resource_mut::<RunState>() {
+						run_state.end_game(EngineMode::BadEnd, &format!("engine fault: {} player entities in the world", count));
+					}
+				}
 			}
 			EngineMode::Paused  => {
 				/* halts the execution/processing of the game state vs Running */
@@ -202,9 +391,13 @@ impl GameEngine<'_> {
 	}
 	/// Master render method, invoking this will redraw the entire screen
 	pub fn render<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		// A terminal that's too small would squash the layout down to nothing, so hold off until it's been enlarged
+		// The simulation carries on regardless, since it doesn't depend on anything being drawn
+		if is_too_small(frame.size()) { self.render_too_small(frame); return; }
 		// If the layout is dirty, recalculate it
 		if self.layout_changed { self.solve_layout(frame.size()); }
-		let default_block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White).bg(Color::Black));
+		if self.loading.is_some() { self.render_loading_screen(frame); return; }
+		let default_block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(self.focus.border_color(PanelFocus::World, Color::White)).bg(Color::Black));
 		// If the engine is in standby mode, defer immediately
 		if self.standby { self.render_main_menu(frame); return; }
 		// Try to get the player's position out of Bevy
@@ -220,10 +413,18 @@ impl GameEngine<'_> {
 			} else if view.reticle != Position::INVALID {
 				view.reticle = Position::INVALID;
 			}
-			frame.render_widget(Viewport::new(&view).block(default_block), self.ui_grid.camera_main);
+			frame.render_widget(Viewport::new(&view).palette(&self.palette).block(default_block), self.ui_grid.camera_main);
 		} else {
 			frame.render_widget(Block::default().title("[no CameraView initialized]"), self.ui_grid.camera_main);
 		}
+		// Draw the HUD on top of the camera's border, if it's enabled
+		self.render_hud(frame);
+		self.update_tooltip();
+		self.render_tooltip(frame);
+		self.render_context_help(frame);
+		self.render_quickslots(frame);
+		self.render_reveal_tag(frame);
+		self.render_note_input(frame);
 		// If there's a visible menu, render that too
 		if self.visible_menu != MenuType::None {
 			match self.visible_menu {
@@ -245,12 +446,169 @@ impl GameEngine<'_> {
 				frame.render_widget(Clear, banner_area);
 				frame.render_widget(banner_img, banner_area);
 			}
-		} else if self.mode == EngineMode::GoodEnd {
-			info!("*************************");
-			info!("*** Victory detected! ***");
-			info!("*************************");
-			self.quit();
+		} else if self.mode == EngineMode::BadEnd || self.mode == EngineMode::GoodEnd {
+			self.render_end_screen(frame);
+		}
+	}
+	/// Renders the notice that stands in for everything else while the terminal is below the minimum size
+	pub fn render_too_small<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let area = frame.size();
+		let notice = format!("Terminal too small: need {}x{}, have {}x{}", MIN_TERM_WIDTH, MIN_TERM_HEIGHT, area.width, area.height);
+		frame.render_widget(Clear, area);
+		frame.render_widget(Paragraph::new(notice).wrap(Wrap { trim: true }).style(Style::default().fg(Color::White).bg(Color::Black)), area);
+	}
+	/// Renders the loading screen, with the builder's current stage and a bar showing how far along it is
+	pub fn render_loading_screen<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let job = if let Some(value) = self.loading.as_ref() { value } else { return };
+		let area = frame.size();
+		let width = area.width.min(60);
+		let panel = Rect::new(area.x + (area.width - width) / 2, area.y + area.height / 2 - 3, width, 6);
+		let block = Block::default().borders(Borders::ALL).title("BUILDING SHIP").style(Style::default().fg(Color::White).bg(Color::Black));
+		let inner = block.inner(panel);
+		frame.render_widget(Clear, panel);
+		frame.render_widget(block, panel);
+		frame.render_widget(Paragraph::new(format!("{}...", job.stage)), Rect::new(inner.x, inner.y, inner.width, 1));
+		let bar = Gauge::default().gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray)).percent(job.percent.min(100) as u16);
+		frame.render_widget(bar, Rect::new(inner.x, inner.y + 1, inner.width, 1));
+		frame.render_widget(Paragraph::new("Esc: cancel").style(Style::default().fg(Color::DarkGray)), Rect::new(inner.x, inner.y + 3, inner.width, 1));
+	}
+	/// Renders the HUD line showing the engine mode, the turn count, the ship clock, and the deck indicator
+	pub fn render_hud<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		if !self.prefs.show_hud { return; }
+		let run_state = if let Some(value) = self.bevy.world.get_resource::<RunState>() { value } else { return };
+		let clock = self.bevy.world.get_resource::<Time>().map(ship_clock).unwrap_or_default();
+		let mut hud_text = run_state.hud_text(clock);
+		if let Some(deck) = self.bevy.world.get_resource::<DeckIndicator>().filter(|x| !x.text.is_empty()) {
+			hud_text = format!("{}| {} ", hud_text, deck.text);
+		}
+		let max_width = self.ui_grid.camera_main.width.saturating_sub(2);
+		let area = Rect::new(self.ui_grid.camera_main.x + 1, self.ui_grid.camera_main.y, (hud_text.len() as u16).min(max_width), 1);
+		frame.render_widget(Paragraph::new(hud_text).style(Style::default().fg(Color::Black).bg(Color::Gray)), area);
+	}
+	/// Returns the map Position that the player is pointing at, if any: the free camera's focus while looking around,
+	/// or the target of the context menu's selection while picking something out
+	pub fn cursor_posn(&self) -> Option<Position> {
+		if self.visible_menu == MenuType::Context {
+			if let Some(target) = self.menu_context.target.filter(|x| *x != Position::INVALID) { return Some(target); }
+		}
+		self.bevy.world.get_resource::<CameraView>().and_then(|x| x.free_origin)
+	}
+	/// Recalculates the tooltip if the cursor has moved to a new tile, or drops it if there's no cursor any more
+	pub fn update_tooltip(&mut self) {
+		let posn = if let Some(value) = self.cursor_posn() { value } else { self.tooltip = None; return };
+		if self.tooltip.as_ref().map_or(false, |x| x.0 == posn) { return; }
+		let summary = self.tile_summary(posn);
+		self.tooltip = Some((posn, summary));
+	}
+	/// Renders the tooltip along the bottom of the camera, where it stands in for the quick-slot bindings
+	pub fn render_tooltip<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let tip_text = if let Some((_, text)) = &self.tooltip { format!(" {} ", text) } else { return };
+		let camera_area = self.ui_grid.camera_main;
+		let max_width = camera_area.width.saturating_sub(2);
+		let area = Rect::new(camera_area.x + 1, camera_area.y + camera_area.height.saturating_sub(1), (tip_text.chars().count() as u16).min(max_width), 1);
+		frame.render_widget(Paragraph::new(tip_text).style(Style::default().fg(Color::Black).bg(Color::Yellow)), area);
+	}
+	/// Renders a line of help about the highlighted context menu item, just above the tooltip
+	pub fn render_context_help<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		if self.visible_menu != MenuType::Context { return; }
+		let help_text = if let Some(value) = self.context_help() { format!(" {} ", value) } else { return };
+		let camera_area = self.ui_grid.camera_main;
+		let max_width = camera_area.width.saturating_sub(2);
+		let area = Rect::new(camera_area.x + 1, camera_area.y + camera_area.height.saturating_sub(2), (help_text.chars().count() as u16).min(max_width), 1);
+		frame.render_widget(Paragraph::new(help_text).style(Style::default().fg(Color::White).bg(Color::DarkGray)), area);
+	}
+	/// Describes the highlighted context menu item: an entry that's named for its verb gets the verb's explanation,
+	/// and anything else gets the long description of whatever it targets, falling back to the verb if there isn't one
+	pub fn context_help(&mut self) -> Option<String> {
+		let item = self.menu_context.highlight()?;
+		let event = *item.help_data()?;
+		let is_verb_entry = !item.is_group() && item.name() == event.to_string();
+		let action = match event.etype {
+			GameEventType::PlayerAction(action) => { action }
+			_ => { return None; }
+		};
+		let target_desc = event.context.filter(|_| !is_verb_entry)
+			.and_then(|x| self.bevy.world.get_entity(x.object))
+			.and_then(|x| x.get::<Description>())
+			.map(|x| x.desc.clone())
+			.filter(|x| !x.is_empty());
+		target_desc.or_else(|| Some(action.describe().to_string()).filter(|x| !x.is_empty()))
+	}
+	/// Produces a one-line summary of the given map tile as far as the player knows it: the terrain, the topmost thing
+	/// on it along with its state, and whether it's in view right now or only remembered
+	/// Nothing is given away about a tile that the player has never seen
+	pub fn tile_summary(&mut self, posn: Position) -> String {
+		let mut p_query = self.bevy.world.query_filtered::<(Entity, &Body, &Viewshed, &Memory), With<Player>>();
+		let (p_enty, is_visible, memory) = if let Ok((p_enty, p_body, p_view, p_memory)) = p_query.get_single(&self.bevy.world) {
+			let is_visible = posn.z == p_body.ref_posn.z && p_view.visible_points.contains(&Point::new(posn.x, posn.y));
+			(p_enty, is_visible, p_memory.visual.get(&posn).cloned().unwrap_or_default())
+		} else {
+			return "???".to_string();
+		};
+		let model = if let Some(value) = self.bevy.world.get_resource::<WorldModel>() { value } else { return "???".to_string() };
+		if !model.contains(posn) { return "???".to_string(); }
+		let level = &model.levels[posn.z as usize];
+		let has_seen = level.revealed_tiles[level.to_index(posn.x, posn.y)];
+		if !is_visible && !has_seen { return "??? (unknown)".to_string(); }
+		// A visible tile shows what's there now; a remembered one shows what was there when the player last saw it
+		let contents = if is_visible { model.get_contents_at(posn) } else { memory };
+		let mut summary = model.get_tiletype_at(posn).to_string();
+		if let Some((t_enty, t_desc)) = contents.iter().filter(|x| **x != p_enty).find_map(|x| self.bevy.world.get::<Description>(*x).map(|y| (*x, y))) {
+			let mut parts = vec![t_desc.name.clone()];
+			// The player can't know whether a remembered door has been opened or locked since they last saw it
+			if is_visible {
+				parts.extend(state_qualifiers(self.bevy.world.get::<Openable>(t_enty), self.bevy.world.get::<Lockable>(t_enty)));
+			}
+			summary = format!("{}: {}", summary, parts.join(", "));
 		}
+		format!("{} ({})", summary, if is_visible { "visible" } else { "remembered" })
+	}
+	/// Renders the current quick-slot bindings along the bottom of the camera, if the HUD is enabled and anything's bound
+	pub fn render_quickslots<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		if !self.prefs.show_hud || self.tooltip.is_some() { return; }
+		let world = &self.bevy.world;
+		let slots = if let Some(value) = world.get_resource::<QuickSlots>() { value } else { return };
+		let hint_text = if let Some(value) = slots.hint_text(|x| world.get::<Description>(x).map(|y| y.name.clone())) { value } else { return };
+		let camera_area = self.ui_grid.camera_main;
+		let max_width = camera_area.width.saturating_sub(2);
+		let area = Rect::new(camera_area.x + 1, camera_area.y + camera_area.height.saturating_sub(1), (hint_text.len() as u16).min(max_width), 1);
+		frame.render_widget(Paragraph::new(hint_text).style(Style::default().fg(Color::Black).bg(Color::Gray)), area);
+	}
+	/// Renders a warning tag in the corner of the camera while the debug map reveal is active, so that it can't be
+	/// mistaken for what the player actually knows
+	pub fn render_reveal_tag<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let reveal = if let Some(value) = self.bevy.world.get_resource::<CameraView>() { value.debug_reveal } else { return };
+		if reveal == DebugReveal::Off { return; }
+		let tag_text = format!(" REVEAL: {:?} ", reveal);
+		let camera_area = self.ui_grid.camera_main;
+		let width = (tag_text.len() as u16).min(camera_area.width.saturating_sub(2));
+		let area = Rect::new(camera_area.x + camera_area.width.saturating_sub(width + 1), camera_area.y, width, 1);
+		frame.render_widget(Paragraph::new(tag_text).style(Style::default().fg(Color::Black).bg(Color::Red)), area);
+	}
+	/// Renders the box that the player types a new map note into, along the bottom of the camera
+	pub fn render_note_input<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let camera_area = self.ui_grid.camera_main;
+		let input = if let Some((_, value)) = self.note_input.as_mut() { value } else { return };
+		input.set_block(Block::default().borders(Borders::ALL).title("NOTE (Enter: leave it, Esc: cancel)"));
+		let width = 60.min(camera_area.width);
+		let height = 3.min(camera_area.height);
+		let area = Rect::new(camera_area.x + (camera_area.width - width) / 2, camera_area.y + camera_area.height - height, width, height);
+		frame.render_widget(Clear, area);
+		frame.render_widget(input.widget(), area);
+	}
+	/// Renders the summary of the run that just ended, over the top of the camera
+	pub fn render_end_screen<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
+		let summary = if let Some(value) = &self.run_summary { value } else { return };
+		let mut text: Vec<Line> = summary.report().into_iter().map(Line::from).collect();
+		text.push(Line::from(""));
+		text.push(Line::from("Press Esc for the main menu."));
+		let camera_area = self.ui_grid.camera_main;
+		let width = 50.min(camera_area.width);
+		let height = (text.len() as u16 + 2).min(camera_area.height);
+		let area = Rect::new(camera_area.x + (camera_area.width - width) / 2, camera_area.y + (camera_area.height - height) / 2, width, height);
+		frame.render_widget(Clear, area);
+		let title = if summary.escaped { "ESCAPE" } else { "LAST WORDS" };
+		frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).block(Block::default().borders(Borders::ALL).title(title)), area);
 	}
 	/// Renders the main menu, using the main menu object
 	pub fn render_main_menu<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
@@ -258,16 +616,35 @@ impl GameEngine<'_> {
 		let menu = Menu::new().block(Block::default()
 			                           .borders(Borders::TOP | Borders::RIGHT)
 			                           .border_style(Style::default().fg(Color::White).bg(Color::DarkGray))
-			                           .title("MAIN".to_string()));
+			                           .title("MAIN".to_string()))
+			.high_visibility(self.prefs.hivis_cursor);
 		let area = Rect::new(self.menu_posn.0, self.menu_posn.1, self.menu_main.width as u16, 1);
 		frame.render_stateful_widget(menu, area, &mut self.menu_main);
+		// Remind the player of how their last few runs went
+		let history: Vec<Line> = self.legacy.latest(3).iter().map(|x| Line::from(x.summary())).collect();
+		if !history.is_empty() {
+			let height = history.len() as u16;
+			let footer = Rect::new(0, self.term_dims.height.saturating_sub(height), self.term_dims.width, height);
+			frame.render_widget(Clear, footer);
+			frame.render_widget(Paragraph::new(history).style(Style::default().fg(Color::DarkGray).bg(Color::Black)), footer);
+		}
+		// Show a snapshot of whichever save slot is highlighted in the Load Game menu
+		let slot = self.menu_main.highlight().and_then(|x| x.data.clone()).and_then(|x| x.strip_prefix("main.load_game:").map(|y| y.to_string()));
+		if let Some(meta) = slot.and_then(|x| SlotMeta::read(&meta_path(&self.files.slot_key(&x)))) {
+			if meta.thumbnail.is_empty() { return; }
+			let thumb: Vec<Line> = meta.thumbnail.iter().map(|x| Line::from(x.clone())).collect();
+			let area = Rect::new(self.menu_posn.0, self.menu_posn.1.saturating_sub(THUMB_HEIGHT as u16 + 2), THUMB_WIDTH as u16 + 2, THUMB_HEIGHT as u16 + 2);
+			frame.render_widget(Clear, area);
+			frame.render_widget(Paragraph::new(thumb).block(Block::default().borders(Borders::ALL).title(meta.header.name)), area);
+		}
 	}
 	/// Renders the context menu, using the common context menu object
 	pub fn render_context_menu<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
 		let menu = Menu::new().block(Block::default()
 			                           .borders(Borders::TOP | Borders::RIGHT)
 			                           .border_style(Style::default().fg(Color::White).bg(Color::DarkGray))
-			                           .title("CONTEXT".to_string()));
+			                           .title("CONTEXT".to_string()))
+			.high_visibility(self.prefs.hivis_cursor);
 		let area = Rect::new(self.menu_posn.0, self.menu_posn.1, self.menu_context.width as u16, 1);
 		frame.render_stateful_widget(menu, area, &mut self.menu_context)
 	}
@@ -276,8 +653,10 @@ impl GameEngine<'_> {
 		if let Some(monitor) = self.bevy.world.get_resource::<PlanqMonitor>() {
 			self.ui_grid.p_status_height = monitor.status_bars.len();
 		}
+		self.ui_grid.calc_planq_layout(self.ui_grid.planq_sidebar);
+		// The minimap is drawn from the world's resources, so it has to be worked out before the PLANQ is borrowed below
+		let minimap = self.minimap_lines(self.ui_grid.planq_stdout);
 		if let Some(mut planq) = self.bevy.world.get_resource_mut::<PlanqData>() {
-			self.ui_grid.calc_planq_layout(self.ui_grid.planq_sidebar);
 			// Display some kind of 'planq offline' state if not carried
 			if !planq.is_carried { // Player is not carrying a planq
 				frame.render_widget(
@@ -288,9 +667,22 @@ impl GameEngine<'_> {
 				);
 				return;
 			}
-			// Display the terminal window if it's been set to visible
-			if planq.show_terminal {
-				planq.render_terminal(frame, self.ui_grid.planq_stdout);
+			// Any app that's been opened takes over the terminal's space until it's closed
+			let border_color = self.focus.border_color(PanelFocus::Planq, Color::Blue);
+			// The screen can be up while the PLANQ is switched off, in which case there's nothing on it
+			if planq.show_terminal && !planq.power_is_on {
+				frame.render_widget(
+					Paragraph::new("[ PLANQ OFF ]").style(Style::default().fg(Color::DarkGray)).block(
+						Block::default().borders(Borders::ALL).border_style(Style::default().fg(border_color))
+					),
+					self.ui_grid.planq_stdout,
+				);
+			} else if planq.show_terminal && planq.current_view() == PlanqView::Dashboard {
+				planq.dashboard.render(frame, self.ui_grid.planq_stdout, border_color);
+			} else if let (true, Some((rows, legend))) = (planq.show_terminal, minimap.as_ref()) {
+				render_minimap(frame, self.ui_grid.planq_stdout, border_color, rows, legend);
+			} else if planq.show_terminal {
+				planq.render_terminal(frame, self.ui_grid.planq_stdout, &mut self.planq_scroll, border_color);
 				// Only display the CLI if there's a terminal visible to contain it
 				if planq.show_cli_input {
 					planq.render_cli(frame, self.ui_grid.planq_stdin, &mut self.planq_stdin);
@@ -303,29 +695,45 @@ impl GameEngine<'_> {
 			monitor.render(frame, self.ui_grid.planq_status);
 		}
 	}
+	/// Works out the minimap's rows and legend to fit the given area, if the minimap is the PLANQ's current view
+	fn minimap_lines(&self, area: Rect) -> Option<(Vec<String>, Vec<String>)> {
+		let planq = self.bevy.world.get_resource::<PlanqData>()?;
+		if planq.current_view() != PlanqView::Minimap { return None; }
+		let p_posn = self.bevy.world.get_resource::<Position>().copied()?;
+		let model = self.bevy.world.get_resource::<WorldModel>()?;
+		let notes = self.bevy.world.get_resource::<MapNotes>()?;
+		let legend = minimap_legend(notes, p_posn.z);
+		// The legend goes underneath the map after a blank line, and both go inside the borders
+		let width = (area.width as usize).saturating_sub(2);
+		let height = (area.height as usize).saturating_sub(3 + legend.len()).max(1);
+		Some((minimap_rows(model, notes, p_posn, width, height), legend))
+	}
 	/// Renders the message log pane at the bottom
 	pub fn render_message_log<B: Backend>(&mut self, frame: &mut Frame<'_, B>) {
 		// Obtain a slice of the message log here and feed to the next widget
 		let msglog_ref = self.bevy.world.get_resource::<MessageLog>();
 		let msglog = msglog_ref.unwrap_or_default(); // get a handle on the msglog service
 		if msglog_ref.is_some() {
-			let worldmsg = msglog.get_log_as_lines("world", 0); // get the full backlog
+			// Get the full backlog, wrapped to fit inside the borders so that wide chars don't run off the edge
+			let inner_width = (self.ui_grid.msg_world.width as usize).saturating_sub(2);
+			let worldmsg: Vec<Line> = msglog.get_log_as_lines("world", 0).into_iter().flat_map(|x| wrap_line(x, inner_width)).collect();
 			/* WARN: magic number offset for window borders
 			 * NOTE: it would be possible to 'reserve' space here by setting the magic num offset
 			 *       greater than is strictly required to cause scrollback
 			 */
 			// Strict attention to typing required here lest we cause subtraction overflow errs
-			let backlog_start_offset = (worldmsg.len() as i32) - self.ui_grid.msg_world.height as i32 + 2;
-			let mut backlog_start: usize = 0;
-			if backlog_start_offset > 0 { backlog_start = backlog_start_offset as usize; }
-			let backlog = worldmsg[backlog_start..].to_vec(); // get a slice of the latest msgs
+			let visible = (self.ui_grid.msg_world.height as usize).saturating_sub(2);
+			self.log_scroll = self.log_scroll.min(worldmsg.len().saturating_sub(visible));
+			let backlog_end = worldmsg.len() - self.log_scroll;
+			let backlog_start = backlog_end.saturating_sub(visible);
+			let backlog = worldmsg[backlog_start..backlog_end].to_vec(); // get a slice of the latest msgs, less any scrollback
 			// Draw the message log pane
 			frame.render_widget(
 				Paragraph::new(Text::from(backlog)) // requires a Vec<Line<'a>> for group insert on creation
 				.block(
 					Block::default()
 					.borders(Borders::ALL)
-					.border_style(Style::default().fg(Color::White))
+					.border_style(Style::default().fg(self.focus.border_color(PanelFocus::Log, Color::White)))
 				),
 				self.ui_grid.msg_world,
 			);
@@ -338,16 +746,53 @@ impl GameEngine<'_> {
 		if m_type == MenuType::Main {
 			let mut menu_items: Vec<MenuItem<Cow<'_, str>>> = Vec::new();
 			menu_items.push(MenuItem::item("New Game", "main.new_game".into(), None));
-			let filepath = bevy_save::get_save_file(&self.savegame_filename);
+			let name_label = if self.naming { format!("Name: {}_", self.player_name) } else { format!("Name: {}", self.player_name) };
+			menu_items.push(MenuItem::item(name_label, "main.name".into(), None));
+			menu_items.push(MenuItem::item(format!("Scenario: {}", self.scenario_kind), "main.scenario".into(), None));
+			let saved_slots = self.files.list_slots();
 			if !self.standby {
-				menu_items.push(MenuItem::item("Save Game", "main.save_game".into(), None));
+				let slot_items = SAVE_SLOTS.iter().map(|slot| {
+					let label = saved_slots.iter().find(|x| x.slot == *slot).map_or(format!("{}: (empty)", slot), |x| x.summary());
+					MenuItem::item(label, format!("main.save_game:{}", slot).into(), None)
+				}).collect();
+				menu_items.push(MenuItem::group("Save Game", slot_items));
 			}
-			if std::fs::metadata(filepath).is_ok() {
-				menu_items.push(MenuItem::item("Load Game", "main.load_game".into(), None));
+			if !saved_slots.is_empty() {
+				let load_items = saved_slots.iter().map(|x| MenuItem::item(x.summary(), format!("main.load_game:{}", x.slot).into(), None)).collect();
+				menu_items.push(MenuItem::group("Load Game", load_items));
+				// Deleting a slot has to be confirmed by going one level deeper
+				let delete_items = saved_slots.iter().map(|x| {
+					MenuItem::group(x.summary(), vec![MenuItem::item(format!("Yes, delete {}", x.slot), format!("main.delete_game:{}", x.slot).into(), None)])
+				}).collect();
+				menu_items.push(MenuItem::group("Delete Save", delete_items));
 			}
 			if !self.standby {
 				menu_items.push(MenuItem::item("Abandon Game", "main.abandon_game".into(), None));
 			}
+			menu_items.push(MenuItem::item(format!("Palette: {}", self.palette.kind), "main.palette".into(), None));
+			let sightings = if self.prefs.announce_sightings { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("Sightings: {}", sightings), "main.sightings".into(), None));
+			menu_items.push(MenuItem::item(format!("Directions: {:?}", self.prefs.dir_names), "main.dir_names".into(), None));
+			let hints = if self.prefs.show_hints { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("Hints: {}", hints), "main.hints".into(), None));
+			menu_items.push(MenuItem::item(format!("FOV: {:?}", self.prefs.fov_algorithm), "main.fov".into(), None));
+			menu_items.push(MenuItem::item(format!("Hazard prompts: {:?}", self.prefs.hazard_prompt), "main.hazards".into(), None));
+			menu_items.push(MenuItem::item(format!("Remembered things: {:?}", self.prefs.remembered_entities), "main.remembered".into(), None));
+			let travel_preview = if self.prefs.preview_travel { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("Travel preview: {}", travel_preview), "main.travel_preview".into(), None));
+			let busy_hands = if self.prefs.planq_busy_hands { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("PLANQ busy hands: {}", busy_hands), "main.busy_hands".into(), None));
+			let autopower = if self.prefs.planq_autopower { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("PLANQ auto power: {}", autopower), "main.autopower".into(), None));
+			let unload_decks = if self.prefs.unload_decks { "On" } else { "Off" };
+			menu_items.push(MenuItem::item(format!("Unload other decks: {}", unload_decks), "main.unload_decks".into(), None));
+			// Permadeath belongs to the run rather than to the player, so it's only offered while there's a game loaded
+			if let Some(run_state) = self.bevy.world.get_resource::<RunState>() {
+				let permadeath = if run_state.permadeath { "On (save is deleted on death)" } else { "Off" };
+				menu_items.push(MenuItem::item(format!("Permadeath: {}", permadeath), "main.permadeath".into(), None));
+			}
+			let cursor_name = if self.prefs.hivis_cursor { "High-vis" } else { "Standard" };
+			menu_items.push(MenuItem::item(format!("Cursor: {}", cursor_name), "main.cursor".into(), None));
 			menu_items.push(MenuItem::item("Quit", "main.quit".into(), None));
 			self.menu_main = MenuState::new(menu_items);
 		}
@@ -357,10 +802,335 @@ impl GameEngine<'_> {
 	/// Helper for changing the current mode of the GameEngine
 	pub fn set_mode(&mut self, new_mode: EngineMode) {
 		//debug!("* eng.mode set to {new_mode:?}"); // DEBUG: announce engine mode switch
+		if (new_mode == EngineMode::GoodEnd || new_mode == EngineMode::BadEnd) && self.mode != new_mode {
+			self.record_legacy(new_mode);
+			self.run_summary = Some(self.summarize_run(new_mode == EngineMode::GoodEnd));
+			if new_mode == EngineMode::BadEnd {
+				self.enforce_permadeath();
+			}
+		}
 		self.mode = new_mode;
+		// Keep the Bevy-side copy of the mode in sync so that the systems can see it
+		if let Some(mut run_state) = self.bevy.world.get_resource_mut::<RunState>() {
+			run_state.mode = new_mode;
+			run_state.mode_changed = false;
+		}
+	}
+	/// Switches the camera over to one of the built-in palettes
+	pub fn set_palette(&mut self, kind: PaletteKind) {
+		self.palette = Palette::new(kind);
+		self.prefs.palette = kind;
+		self.apply_prefs();
+		if let Some(mut palette) = self.bevy.world.get_resource_mut::<Palette>() {
+			*palette = self.palette.clone();
+		}
+		if cfg!(debug_assertions) { // DEBUG: flag any colors in this palette that are hard to read
+			for failure in self.palette.contrast_failures(MIN_CONTRAST) {
+				warn!("* low contrast in the {} palette: {}", kind, failure);
+			}
+		}
+		// Rebuild the main menu so that it shows the new palette's name
+		if self.visible_menu == MenuType::Main {
+			self.set_menu(MenuType::Main, self.menu_posn);
+		}
+	}
+	/// Writes the player's preferences out to the preferences file, and mirrors them into Bevy for the systems to see
+	/// Call this after every change to GameEngine.prefs; Bevy's copy is read-only and is overwritten here
+	pub fn apply_prefs(&mut self) {
+		if let Err(e) = self.prefs.save(&self.files.prefs) {
+			error!("! could not write the preferences file at {}: {}", self.files.prefs, e); // DEBUG: report a preferences write failure
+		}
+		if let Some(mut prefs) = self.bevy.world.get_resource_mut::<Preferences>() {
+			*prefs = self.prefs.clone();
+		}
+	}
+	/// Spawns the contents of any LazyLoot containers that were opened during the last update
+	pub fn stock_lazy_loot(&mut self) {
+		let requests = if let Some(mut loot) = self.bevy.world.get_resource_mut::<LootRequests>() {
+			std::mem::take(&mut loot.pending)
+		} else {
+			return
+		};
+		for (i_name, container) in requests.iter() {
+			self.artisan.create(i_name).give_to(*container).build(&mut self.bevy.world);
+		}
+	}
+	/// Works out the player's route to the given waypoint and holds it up on the camera for them to look over
+	/// Returns the question to put to the player, or the reason why there's no route to show them
+	pub fn preview_route(&mut self, mark: Bookmark) -> Result<String, String> {
+		let mut player_query = self.bevy.world.query_filtered::<Entity, With<Player>>();
+		let player = player_query.get_single(&self.bevy.world).map_err(|_| "No user found.".to_string())?;
+		let origin = self.bevy.world.get_resource::<Position>().copied().ok_or("No user found.".to_string())?;
+		if mark.posn == origin { return Err(format!("You're already at {}.", mark.label)); }
+		let caps = self.bevy.world.get::<NavCaps>(player).copied().unwrap_or_default();
+		let model = self.bevy.world.get_resource::<WorldModel>().ok_or("No map found.".to_string())?;
+		let route = path_to(model, origin, mark.posn, caps).ok_or(format!("You can't find a way to {} from here.", mark.label))?;
+		let hazards = route.iter().filter(|x| model.get_tiletype_at(**x).properties().hazard != Hazard::None).count();
+		let mut prompt = format!("Travel to {}, {} steps away?", mark.label, route.len());
+		if hazards > 0 {
+			prompt.push_str(&format!(" The route crosses {} hazardous tiles!", hazards));
+		}
+		prompt.push_str(" (y/n)");
+		self.bevy.world.get_resource_mut::<Bookmarks>().ok_or("No bookmarks found.".to_string())?.show_preview(mark, route);
+		Ok(prompt)
+	}
+	/// Starts a PLANQ process that will identify an Unidentified item that the player is carrying; the target may be
+	/// part of the item's name, or blank to take the first one found. Returns the PLANQ's response
+	pub fn start_analysis(&mut self, target: &str) -> PlanqCmdResult {
+		let mut player_query = self.bevy.world.query_filtered::<Entity, With<Player>>();
+		let player = if let Ok(value) = player_query.get_single(&self.bevy.world) { value } else { return PlanqCmdResult::fail(vec!["No user found.".to_string()]) };
+		let mut i_query = self.bevy.world.query_filtered::<(Entity, &Description, &Portable), With<Unidentified>>();
+		let item = i_query.iter(&self.bevy.world)
+			.find(|(_, i_desc, i_portable)| i_portable.carrier == player && (target.is_empty() || i_desc.name.contains(target)))
+			.map(|(i_enty, i_desc, _)| (i_enty, i_desc.name.clone()));
+		let (i_enty, i_name) = if let Some(value) = item { value } else { return PlanqCmdResult::fail(vec!["No unidentified items to analyze.".to_string()]) };
+		let process = self.bevy.world.spawn(
+			PlanqProcess::new()
+			.time(ANALYSIS_DURATION)
+			.event(PlanqEvent::new(PlanqEventType::Analyze(i_enty)))
+		).id();
+		if let Some(mut planq) = self.bevy.world.get_resource_mut::<PlanqData>() {
+			planq.proc_table.push(process);
+		}
+		PlanqCmdResult::ok(vec![format!("Analyzing the {}...", i_name)])
+	}
+	/// Scans everything that the player can currently see, and adds it to the PLANQ's scan log so that it can be looked
+	/// up again after it's gone out of range. Returns the PLANQ's response
+	pub fn run_scan(&mut self) -> PlanqCmdResult {
+		if !self.bevy.world.get_resource::<PlanqData>().map_or(false, |x| x.power_is_on) {
+			return PlanqCmdResult::fail(vec!["The PLANQ has no power.".to_string()]);
+		}
+		let mut p_query = self.bevy.world.query_filtered::<(Entity, &Body, &Viewshed, &Memory), With<Player>>();
+		let (player, p_z, targets) = if let Ok((p_enty, p_body, p_view, p_memory)) = p_query.get_single(&self.bevy.world) {
+			let targets: Vec<(Entity, Position)> = p_view.visible_points.iter()
+				.map(|x| Position::new(x.x, x.y, p_body.ref_posn.z))
+				.flat_map(|posn| p_memory.visual.get(&posn).cloned().unwrap_or_default().into_iter().map(move |x| (x, posn)))
+				.collect();
+			(p_enty, p_body.ref_posn.z, targets)
+		} else {
+			return PlanqCmdResult::fail(vec!["No user found.".to_string()]);
+		};
+		let turn = self.bevy.world.get_resource::<RunState>().map_or(0, |x| x.turn_count);
+		let mut found: Vec<(Entity, String, Position)> = Vec::new();
+		for (t_enty, t_posn) in targets.iter() {
+			if *t_enty == player || found.iter().any(|x| x.0 == *t_enty) { continue; }
+			if let Some(t_desc) = self.bevy.world.get::<Description>(*t_enty) {
+				found.push((*t_enty, t_desc.name.clone(), *t_posn));
+			}
+		}
+		if found.is_empty() { return PlanqCmdResult::ok(vec![format!("Scan complete: nothing of note on deck {}.", p_z)]); }
+		let mut report = vec![format!("Scan complete: {} contact(s) logged", found.len())];
+		if let Some(mut planq) = self.bevy.world.get_resource_mut::<PlanqData>() {
+			for (t_enty, t_name, t_posn) in found.iter() {
+				planq.record_scan(*t_enty, t_name, *t_posn, turn);
+				report.push(format!("  {} at {}", t_name, t_posn));
+			}
+		}
+		PlanqCmdResult::ok(report)
+	}
+	/// Reads out the given page of the ship log, which is only reachable through an AccessPort
+	pub fn read_ship_log(&mut self, page: usize) -> PlanqCmdResult {
+		let (power_is_on, port) = if let Some(planq) = self.bevy.world.get_resource::<PlanqData>() {
+			(planq.power_is_on, planq.jack_cnxn)
+		} else {
+			return PlanqCmdResult::fail(vec!["No PLANQ found.".to_string()]);
+		};
+		if !power_is_on { return PlanqCmdResult::fail(vec!["The PLANQ has no power.".to_string()]); }
+		if port == Entity::PLACEHOLDER || self.bevy.world.get::<AccessPort>(port).is_none() {
+			return PlanqCmdResult::fail(vec!["The ship log needs a connection to an access port.".to_string()]);
+		}
+		if !self.bevy.world.get::<Device>(port).map_or(true, |x| x.pw_switch) {
+			return PlanqCmdResult::fail(vec!["The access port is not responding.".to_string()]);
+		}
+		match self.bevy.world.get_resource::<ShipLog>() {
+			Some(ship_log) if page == 0 || page > ship_log.page_count() => {
+				PlanqCmdResult::fail(vec![format!("No such page: {} (the log has {})", page, ship_log.page_count())])
+			}
+			Some(ship_log) => { PlanqCmdResult::ok(ship_log.page(page)) }
+			None => { PlanqCmdResult::fail(vec!["Unable to reach the ship log.".to_string()]) }
+		}
+	}
+	/// Moves data files over the PLANQ's access jack: a download copies the connected port's files into the PLANQ's
+	/// storage and puts them to use, and an upload copies the PLANQ's files onto the port
+	pub fn transfer_data(&mut self, upload: bool) -> PlanqCmdResult {
+		let (power_is_on, port) = if let Some(planq) = self.bevy.world.get_resource::<PlanqData>() {
+			(planq.power_is_on, planq.jack_cnxn)
+		} else {
+			return PlanqCmdResult::fail(vec!["No PLANQ found.".to_string()]);
+		};
+		if !power_is_on { return PlanqCmdResult::fail(vec!["The PLANQ has no power.".to_string()]); }
+		if port == Entity::PLACEHOLDER || self.bevy.world.get::<AccessPort>(port).is_none() {
+			return PlanqCmdResult::fail(vec!["Transfers need a connection to an access port.".to_string()]);
+		}
+		if !self.bevy.world.get::<Device>(port).map_or(true, |x| x.pw_switch) {
+			return PlanqCmdResult::fail(vec!["The access port is not responding.".to_string()]);
+		}
+		let port_deck = self.bevy.world.get::<Body>(port).map_or(0, |x| x.ref_posn.z);
+		let mut report = Vec::new();
+		if upload {
+			let files = self.bevy.world.get_resource::<PlanqData>().map(|x| x.storage.clone()).unwrap_or_default();
+			if files.is_empty() { return PlanqCmdResult::fail(vec!["No data to upload.".to_string()]); }
+			// Any keycodes are tried against the shipnet-enabled locks on the port's deck
+			let mut l_query = self.bevy.world.query::<(&Description, &Body, &mut Lockable)>();
+			for (l_desc, l_body, mut l_lock) in l_query.iter_mut(&mut self.bevy.world) {
+				if !l_lock.remote || !l_lock.is_locked || l_body.ref_posn.z != port_deck { continue; }
+				let key_id = l_lock.key_id;
+				if files.iter().any(|x| x.kind == DataKind::Keycode && x.value == key_id) && l_lock.unlock(key_id) {
+					report.push(format!("The {} unlocks.", l_desc.name));
+				}
+			}
+			if let Some(mut store) = self.bevy.world.get_mut::<DataStore>(port) {
+				for file in files.iter() {
+					if !store.files.contains(file) { store.files.push(file.clone()); }
+				}
+			}
+			report.insert(0, format!("Uploaded {} file(s)", files.len()));
+			return PlanqCmdResult::ok(report);
+		}
+		// The port also hands out the keycodes for the shipnet-enabled locks on its own deck
+		let mut files = self.bevy.world.get::<DataStore>(port).map(|x| x.files.clone()).unwrap_or_default();
+		let mut l_query = self.bevy.world.query::<(&Description, &Body, &Lockable)>();
+		for (l_desc, l_body, l_lock) in l_query.iter(&self.bevy.world) {
+			if l_lock.remote && l_body.ref_posn.z == port_deck {
+				files.push(DataFile::new(&format!("{}.key", l_desc.name.to_lowercase().replace(' ', "_")), DataKind::Keycode, l_lock.key_id, ""));
+			}
+		}
+		let new_files: Vec<DataFile> = if let Some(mut planq) = self.bevy.world.get_resource_mut::<PlanqData>() {
+			let new_files: Vec<DataFile> = files.into_iter().filter(|x| !planq.storage.contains(x)).collect();
+			planq.storage.extend(new_files.iter().cloned());
+			new_files
+		} else {
+			Vec::new()
+		};
+		if new_files.is_empty() { return PlanqCmdResult::ok(vec!["No new data on this port.".to_string()]); }
+		for file in new_files.iter() {
+			report.push(format!("Downloaded {}", file.name));
+			match file.kind {
+				DataKind::Log => { report.push(format!("  {}", file.text)); }
+				DataKind::MapFragment => {
+					let revealed = self.bevy.world.get_resource_mut::<WorldModel>().map_or(false, |mut x| x.reveal_deck(file.value));
+					if revealed { report.push(format!("  Deck {} plans added to the map", file.value)); }
+				}
+				DataKind::Keycode => { report.push("  Keycode stored; upload it to use it".to_string()); }
+			}
+		}
+		PlanqCmdResult::ok(report)
+	}
+	/// Returns the reason that the player can't do what the event asks for, if their settings let the PLANQ keep their
+	/// hands busy while it's booting up or shutting down
+	pub fn planq_refuses(&self, event: &GameEvent) -> Option<String> {
+		if !self.prefs.planq_busy_hands { return None; }
+		let action = if let GameEventType::PlayerAction(value) = event.etype { value } else { return None };
+		self.bevy.world.get_resource::<PlanqData>()?.refuses(action)
+	}
+	/// Checks that the object of an event still exists, still has the Components that its action needs, and is still
+	/// within reach of the subject; the context menus capture their targets when they're built, which may be stale
+	pub fn recheck_target(&self, event: &GameEvent) -> Result<(), &'static str> {
+		let context = if let Some(value) = event.context { value } else { return Ok(()) };
+		if context.object == Entity::PLACEHOLDER { return Ok(()); }
+		let world = &self.bevy.world;
+		let gone = "It's no longer there.";
+		if world.get_entity(context.object).is_none() { return Err(gone); }
+		let action = match event.etype {
+			GameEventType::PlayerAction(action) | GameEventType::ActorAction(action) => { action }
+			_ => { return Ok(()) }
+		};
+		let has_parts = match action {
+			ActionType::Examine => { world.get::<Description>(context.object).is_some() }
+			ActionType::MoveItem
+			| ActionType::DropItem => { world.get::<Portable>(context.object).is_some() }
+			ActionType::UseItem => { world.get::<Device>(context.object).is_some() }
+			ActionType::OpenItem
+			| ActionType::CloseItem => { world.get::<Openable>(context.object).is_some() }
+			ActionType::LockItem
+			| ActionType::UnlockItem
+			| ActionType::HackItem => { world.get::<Lockable>(context.object).is_some() }
+			ActionType::EmptyItem => { world.get::<Container>(context.object).is_some() }
+			_ => { true }
+		};
+		if !has_parts { return Err(gone); }
+		let range = if let Some(value) = action.reach_for(world, context.subject) { value } else { return Ok(()) };
+		// Anything that the subject is carrying is always within reach
+		if world.get::<Portable>(context.object).map_or(false, |x| x.carrier == context.subject) { return Ok(()); }
+		let s_posn = if let Some(value) = world.get::<Body>(context.subject) { value.ref_posn } else { return Ok(()) };
+		// Looking at something takes a clear view of it as well, since it can be done from across the room
+		if action == ActionType::Examine {
+			return if can_affect(context.subject, context.object, range, world) { Ok(()) } else { Err(gone) };
+		}
+		match world.get::<Body>(context.object) {
+			Some(o_body) if o_body.in_range_of(&s_posn, range) => { Ok(()) }
+			_ => { Err(gone) }
+		}
+	}
+	/// Confirms that there is exactly one Player in the world, as the rest of the game assumes; returns the player's
+	/// Entity if so, or else the number of Players that were actually found
+	pub fn verify_single_player(&mut self) -> Result<Entity, usize> {
+		let mut player_query = self.bevy.world.query_filtered::<Entity, With<Player>>();
+		let players: Vec<Entity> = player_query.iter(&self.bevy.world).collect();
+		if players.len() == 1 { Ok(players[0]) } else { Err(players.len()) }
+	}
+	/// Returns true if the PLANQ's CLI is open and taking keyboard input
+	pub fn is_cli_open(&self) -> bool {
+		self.bevy.world.get_resource::<PlanqData>().map_or(false, |x| x.show_cli_input)
+	}
+	/// Writes the outcome of the current game to the legacy file
+	pub fn record_legacy(&mut self, outcome: EngineMode) {
+		let (turns, cause, salvage) = self.bevy.world.get_resource::<RunState>()
+			.map(|x| (x.turn_count, x.end_cause.clone(), x.salvage))
+			.unwrap_or_default();
+		let final_posn = *self.bevy.world.get_resource::<Position>().unwrap_or(&Position::INVALID);
+		self.legacy.append(LegacyEntry::new(self.run_seed, &format!("{:?}", outcome), turns, final_posn, cause).with_salvage(salvage));
+		if let Err(e) = self.legacy.save(&self.files.legacy) {
+			error!("! could not write the legacy file at {}: {}", self.files.legacy, e); // DEBUG: report legacy write failure
+		}
+	}
+	/// Collects the details of how the current run went, for the end screen
+	pub fn summarize_run(&mut self, escaped: bool) -> RunSummary {
+		let (cause, turns, items, salvage) = self.bevy.world.get_resource::<RunState>()
+			.map(|x| (x.end_cause.clone(), x.turn_count, x.items_collected, x.salvage))
+			.unwrap_or_default();
+		let mut m_query = self.bevy.world.query_filtered::<&Memory, With<Player>>();
+		let mut decks: Vec<i32> = m_query.get_single(&self.bevy.world).map_or(Vec::new(), |x| x.visual.keys().map(|posn| posn.z).collect());
+		decks.sort();
+		decks.dedup();
+		let last_words = self.bevy.world.get_resource::<MessageLog>().map_or(Vec::new(), |x| {
+			let log = x.get_log_as_messages("world", 0);
+			log.iter().rev().take(LAST_WORDS_COUNT).rev().map(|msg| msg.text.clone()).collect()
+		});
+		RunSummary {
+			escaped,
+			cause: cause.unwrap_or("unknown causes".to_string()),
+			turns,
+			items_collected: items,
+			salvage,
+			decks_explored: decks.len().max(1),
+			last_words,
+		}
+	}
+	/// Hands the GameEvents from the last tick to the current scenario, and ends the game if the scenario says so
+	pub fn run_scenario(&mut self) {
+		let events: Vec<GameEvent> = if let Some(events) = self.bevy.world.get_resource::<Events<GameEvent>>() {
+			self.scenario_reader.iter(events).copied().collect()
+		} else {
+			Vec::new()
+		};
+		for event in events.iter() {
+			self.scenario.on_event(&mut self.bevy.world, event);
+		}
+		if let Some(outcome) = self.scenario.victory(&mut self.bevy.world) {
+			if let Some(mut run_state) = self.bevy.world.get_resource_mut::<RunState>() {
+				if !run_state.mode_changed {
+					run_state.end_game(outcome.mode, &outcome.cause);
+				}
+			}
+		}
 	}
 	/// Causes the GameEngine to halt and quit
 	pub fn quit(&mut self) {
+		// Anything that the PLANQ said since the last update still makes it into the transcript
+		if self.bevy.world.get_resource::<PlanqTranscript>().map_or(false, |x| x.is_recording()) {
+			self.run_planq_cmd(PlanqCmd::Transcript(false));
+		}
 		self.running = false;
 	}
 	/// Starts a new game from scratch
@@ -370,11 +1140,47 @@ impl GameEngine<'_> {
 			warn!("* ! game is in progress!"); // DEBUG: warn about running game
 			self.halt_game();
 			self.standby = true;
-			self.running = false;
 		}
+		self.run_seed = new_run_seed();
+		self.savegame_filename = None; // This run hasn't been saved anywhere yet, so there's nothing of its own to delete
 		self.init_bevy();
-		self.build_new_worldmap();
+		// If this seed has already claimed someone, the PLANQ might still remember a little of it
+		if let Some(prev_run) = self.legacy.last_bad_end_on(self.run_seed) {
+			let fragment = prev_run.ghost_fragment();
+			if let Some(mut planq) = self.bevy.world.get_resource_mut::<PlanqData>() {
+				planq.ghost_log = Some(fragment);
+			}
+		}
+		// The ship itself is built in the background; see poll_loading() for the rest of the setup
+		self.visible_menu = MenuType::None;
+		self.loading = Some(LoadingJob::start(self.artisan.clone(), self.run_seed));
+	}
+	/// Checks in on the ship that's being built for a new game, and finishes setting the game up once it's ready
+	pub fn poll_loading(&mut self) {
+		let status = if let Some(job) = self.loading.as_mut() { job.poll() } else { return };
+		match status {
+			LoadStatus::Working => { }
+			LoadStatus::Done(plan) => {
+				self.loading = None;
+				self.finish_new_game(*plan);
+			}
+			LoadStatus::Cancelled | LoadStatus::Failed => { self.cancel_loading(); }
+		}
+	}
+	/// Abandons the ship that's being built and goes back to the main menu; dropping the job tells the worker to stop at
+	/// its next stage, and whatever it was working on is thrown away, since nobody is listening for it anymore
+	pub fn cancel_loading(&mut self) {
+		if self.loading.take().is_none() { return; }
+		self.halt_game();
+		self.set_menu(MenuType::Main, self.menu_posn);
+	}
+	/// Puts the newly-built ship into the World and starts the game on it
+	pub fn finish_new_game(&mut self, plan: WorldPlan) {
+		self.build_new_worldmap(plan);
 		self.bevy.update();
+		// The scenario's setup goes last, so that it can build on top of the player and the intro
+		self.scenario = self.scenario_kind.build();
+		self.scenario.setup(&mut self.bevy.world);
 		self.standby = false;
 		self.running = true;
 		self.set_mode(EngineMode::Running);
@@ -391,12 +1197,32 @@ impl GameEngine<'_> {
 	//      ~/.local/share/spacegame/saves/FILENAME.sav
 	pub fn save_game(&mut self, filename: &str) {
 		//debug!("* save_game() called on {}", filename); // DEBUG: alert when save_game is called
-		if let Err(e) = self.bevy.world.save(filename) {
+		// Any decks that were taken out of the World have to be put back, or everything on them would be missing
+		restore_all_decks(&mut self.bevy.world);
+		if let Err(e) = self.bevy.world.save(&self.files.slot_key(filename)) {
 			error!("! ! save_game() failed on '{}', error: {}", filename, e); // DEBUG: warn about save game error
 			return;
 		}
+		self.savegame_filename = Some(filename.to_string());
+		// The metadata is only a convenience for the Load Game menu, so a failure here doesn't spoil the save
+		let meta_file = meta_path(&self.files.slot_key(filename));
+		if let Err(e) = self.slot_meta(filename).write(&meta_file) {
+			warn!("* could not write the metadata for save slot '{}': {}", filename, e); // DEBUG: report a metadata write failure
+		}
 		self.quit();
 	}
+	/// Collects the metadata that describes the current game, for storing alongside its save file
+	pub fn slot_meta(&mut self, slot: &str) -> SlotMeta {
+		let (name, turns) = self.bevy.world.get_resource::<RunState>().map_or((self.player_name.clone(), 0), |x| (x.player_name.clone(), x.turn_count));
+		let ship_time = self.bevy.world.get_resource::<Time>().map(ship_clock).unwrap_or_default().get_as_string();
+		let mut p_query = self.bevy.world.query_filtered::<&Body, With<Player>>();
+		let deck = p_query.get_single(&self.bevy.world).map_or(0, |x| x.ref_posn.z);
+		let thumbnail = self.bevy.world.get_resource::<CameraView>().map_or(Vec::new(), |x| {
+			let glyphs: Vec<String> = x.output.iter().map(|cell| cell.glyph.clone()).collect();
+			make_thumbnail(&glyphs, x.width as usize, x.height as usize)
+		});
+		SlotMeta::new(SlotHeader { slot: slot.to_string(), name, ship_time, deck, turns, saved_at: 0 }, thumbnail)
+	}
 	/// Loads a saved game from the given external file
 	pub fn load_game(&mut self, filename: &str) {
 		//debug!("* load_game() called on {} ({})", filename, self.standby); // DEBUG: alert when load_game is called
@@ -407,7 +1233,7 @@ impl GameEngine<'_> {
 			self.running = false;
 		}
 		self.init_bevy();
-		match self.bevy.world.load_applier(filename) {
+		match self.bevy.world.load_applier(&self.files.slot_key(filename)) {
 			Ok(applier) => {
 				if let Err(f) = applier.despawn(DespawnMode::Unmapped).apply() {
 					error!( "! ERR: load_game() failed to apply the EntityMap, error: {}", f); // DEBUG: warn about loading error
@@ -417,17 +1243,79 @@ impl GameEngine<'_> {
 				error!("! ERR: load_game() failed on '{}', error: {}", filename, e); // DEBUG: warn about loading error
 			}
 		}
+		self.savegame_filename = Some(filename.to_string());
+		self.migrate_legacy_settings();
+		if let Some(run_state) = self.bevy.world.get_resource::<RunState>() {
+			self.run_seed = run_state.seed;
+			self.scenario_kind = run_state.scenario;
+		}
+		self.scenario = self.scenario_kind.build();
+		// The Viewsheds were not saved, so they need to be rebuilt before the first frame gets drawn
+		if let Some(mut resync) = self.bevy.world.get_resource_mut::<Events<ResyncViewsheds>>() {
+			resync.send(ResyncViewsheds);
+		}
 		self.bevy.update();
 		self.standby = false;
 		self.running = true;
 		self.set_mode(EngineMode::Running);
 		//debug!("* load_game() finished successfully"); // DEBUG: alert when load_game finishes
 	}
+	/// Converts the GameSettings from a save made before the split into the RunState that replaced it
+	/// The old display settings are dropped rather than copied over, since a load must never change the Preferences
+	pub fn migrate_legacy_settings(&mut self) {
+		let legacy = if let Some(value) = self.bevy.world.remove_resource::<GameSettings>() { value } else { return };
+		info!("* migrating the GameSettings from an older save"); // DEBUG: announce a legacy save migration
+		self.bevy.world.insert_resource(RunState {
+			mode: legacy.mode,
+			mode_changed: legacy.mode_changed,
+			turn_count: legacy.turn_count,
+			seed: legacy.seed,
+			end_cause: legacy.end_cause,
+			player_name: legacy.player_name,
+			items_collected: legacy.items_collected,
+			permadeath: legacy.permadeath,
+			..RunState::default()
+		});
+	}
+	/// DEBUG: Loads each of the given save slots into a scratch engine and lists what changed from the first to the
+	/// second; the game that's in progress is left untouched
+	#[cfg(debug_assertions)]
+	pub fn diff_saves(&self, before: &str, after: &str) -> PlanqCmdResult {
+		let mut snapshots = Vec::new();
+		for slot in [before, after] {
+			let mut scratch = GameEngine::with_files(self.term_dims, self.files.clone());
+			scratch.init_bevy();
+			let applier = match scratch.bevy.world.load_applier(&self.files.slot_key(slot)) {
+				Ok(value) => { value }
+				Err(e) => { return PlanqCmdResult::fail(vec![format!("Could not load {}: {}", slot, e)]); }
+			};
+			if let Err(e) = applier.despawn(DespawnMode::Unmapped).apply() {
+				return PlanqCmdResult::fail(vec![format!("Could not apply {}: {}", slot, e)]);
+			}
+			snapshots.push(crate::savediff::take_snapshot(&mut scratch.bevy.world));
+		}
+		let lines = crate::savediff::diff_snapshots(&snapshots[0], &snapshots[1]);
+		info!("* diffsaves {} {}: {:?}", before, after, lines); // DEBUG: log the comparison for later reference
+		PlanqCmdResult::ok(lines)
+	}
+	/// Deletes the save that this game was loaded from or last saved to, so that a permadeath run can't be reloaded
+	/// A run that was never saved leaves every slot alone, since none of them belong to it
+	pub fn enforce_permadeath(&mut self) {
+		let permadeath = self.bevy.world.get_resource::<RunState>().map_or(false, |x| x.permadeath);
+		let slot = if let Some(value) = permadeath_slot(self.savegame_filename.as_deref(), permadeath) { value.to_string() } else { return };
+		info!("* permadeath: deleting savegame at {}", slot); // DEBUG: announce the permadeath deletion
+		match self.delete_game(&slot) {
+			Ok(()) => { self.savegame_filename = None; }
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => { self.savegame_filename = None; } // Already gone
+			Err(e) => {
+				error!("! could not delete the savegame at {} for permadeath: {}", slot, e); // DEBUG: report a failed permadeath deletion
+			}
+		}
+	}
 	/// Deletes the game save, ie after dying or abandoning the game
 	pub fn delete_game(&mut self, filename: &str) -> std::io::Result<()> {
 		//debug!("* delete_game() called on {}", filename); // DEBUG: alert when delete_game is called
-		let filepath = bevy_save::get_save_file(filename);
-		std::fs::remove_file(filepath)
+		delete_slot(&self.files.slot_key(filename))
 	}
 	/// Puts the game into a PAUSED state
 	pub fn pause_game(&mut self) {
@@ -451,135 +1339,116 @@ impl GameEngine<'_> {
 		let chanlist = vec!["world".to_string(),
 			                  "planq".to_string(),
 			                  "debug".to_string()];
+		self.scenario_reader = Default::default(); // The new World starts its events from scratch
 		self.bevy
-		.add_plugins(RngPlugin::default()) // Non-deterministic RNG
-		//.add_plugins(RngPlugin::new().with_rng_seed(69420)) // Forces the RNG to be deterministic
+		//.add_plugins(RngPlugin::default()) // Non-deterministic RNG
+		.add_plugins(RngPlugin::new().with_rng_seed(self.run_seed)) // Seeded per run, see new_run_seed()
 		.add_systems(Startup, (new_player_spawn,
 			                     new_lmr_spawn,
 			                     ))
 		.add_systems(Update, (action_referee_system,
+			                    auto_travel_system.before(movement_system),
 			                    camera_update_system,
 			                    examination_system,
+			                    hearing_system.after(movement_system),
+			                    identification_system.after(examination_system),
 			                    item_collection_system,
+			                    lazy_loot_system.after(openable_system),
+			                    lighting_system.before(visibility_system),
 			                    lockable_system,
-			                    map_indexing_system,
+			                    maintenance_ai_system.after(movement_system),
+			                    map_indexing_system.before(visibility_system),
 			                    movement_system,
 			                    openable_system,
 			                    operable_system,
-			                    planq_update_system,
+			                    planq_comms_system,
+			                    planq_update_system.after(item_collection_system),
 			                    planq_monitor_system,
-			                    visibility_system,
-			                    ))
-		.register_type::<(i32, i32, i32)>()
-		.register_type::<DeviceState>()
-		.register_type::<PlanqDataType>()
-		.register_type::<PlanqEvent>()
-		.register_type::<PlanqEventType>()
-		.register_type::<Portal>()
-		.register_type::<Position>()
-		.register_type::<TimerMode>()
-		.register_type::<Vec<bool>>()
-		.register_type::<Vec<Entity>>()
-		.register_type::<Vec<WorldMap>>()
-		.register_type::<Vec<Message>>()
-		.register_type::<Vec<MessageChannel>>()
-		.register_type::<Vec<Portal>>()
-		.register_type::<Vec<String>>()
-		.register_type::<Vec<TileType>>()
-		.register_type::<Vec<Tile>>()
-		.register_type::<HashMap<(i32, i32, i32), (i32, i32, i32)>>()
-		.register_type::<HashMap<Entity, Position>>() // planned to be superceded by the below type
-		.register_type::<HashMap<Position, Vec<Entity>>>()
-		.register_type::<HashMap<String, PlanqDataType>>()
-		.register_type::<HashMap<Position, ScreenCell>>()
-		.register_type::<bevy::utils::HashSet<ActionType>>()
-		.register_saveable::<AccessPort>()
-		.register_saveable::<ActionSet>()
-		.register_saveable::<CameraView>()
-		.register_saveable::<Container>()
-		.register_saveable::<DataSampleTimer>()
-		.register_saveable::<Description>()
-		.register_saveable::<Device>()
-		.register_saveable::<GameEvent>()
-		.register_saveable::<GameEventContext>()
-		.register_saveable::<GameEventType>()
-		.register_saveable::<GlobalRng>()
-		.register_saveable::<Key>()
-		.register_saveable::<LMR>()
-		.register_saveable::<Lockable>()
-		.register_saveable::<WorldMap>()
-		.register_saveable::<Memory>()
-		.register_saveable::<Message>()
-		.register_saveable::<MessageChannel>()
-		.register_saveable::<MessageLog>()
-		.register_saveable::<Mobile>()
-		.register_saveable::<WorldModel>()
-		.register_saveable::<Networkable>()
-		.register_saveable::<Obstructive>()
-		.register_saveable::<Opaque>()
-		.register_saveable::<Openable>()
-		.register_saveable::<Planq>()
-		.register_saveable::<PlanqActionMode>()
-		.register_saveable::<PlanqCPUMode>()
-		.register_saveable::<PlanqData>()
-		.register_saveable::<PlanqMonitor>()
-		.register_saveable::<PlanqProcess>()
-		.register_saveable::<Player>()
-		.register_saveable::<Portable>()
-		.register_saveable::<Position>()
-		.register_saveable::<RngComponent>()
-		.register_saveable::<Tile>()
-		.register_saveable::<TileType>()
-		.register_saveable::<bevy::utils::hashbrown::HashMap<Position, Position>>()
-		.register_saveable::<bevy::utils::hashbrown::HashSet<ActionType>>()
+			                    task_generation_system.before(maintenance_ai_system),
+			                    visibility_system.before(camera_update_system),
+			                    ));
+		register_saveables(&mut self.bevy);
+		self.bevy
+		.insert_resource(Bookmarks::new())
+		.insert_resource(DeckIndicator::default())
+		.insert_resource(Events::<ActionFailed>::default())
+		.insert_resource(Events::<AmbientCue>::default())
+		.insert_resource(Events::<ContainerOpened>::default())
+		.insert_resource(Events::<DevicePowerChanged>::default())
+		.insert_resource(Events::<EntityKilled>::default())
 		.insert_resource(Events::<GameEvent>::default())
+		.insert_resource(Events::<IdentifyItem>::default())
+		.insert_resource(Events::<InventoryChanged>::default())
+		.insert_resource(Events::<Noise>::default())
+		.insert_resource(Events::<ObjectiveComplete>::default())
 		.insert_resource(Events::<PlanqEvent>::default())
+		.insert_resource(Events::<PlayerMoved>::default())
+		.insert_resource(Events::<PowerOutage>::default())
+		.insert_resource(Events::<ResyncViewsheds>::default())
+		.insert_resource(Events::<ShipLogEvent>::default())
+		.insert_resource(MissedEvents::default())
+		.insert_resource(RunState { seed: self.run_seed, player_name: self.player_name.clone(), scenario: self.scenario_kind, ..RunState::new() })
+		.insert_resource(LootRequests::default())
+		.insert_resource(MapNotes::new())
 		.insert_resource(MessageLog::new(chanlist))
+		.insert_resource(Objectives::new())
+		.insert_resource(PerceptionPolicy::new())
+		.insert_resource(self.palette.clone())
+		.insert_resource(PlanqComms::new())
+		.insert_resource(PlanqBootText::default())
 		.insert_resource(PlanqData::new())
 		.insert_resource(PlanqMonitor::new())
+		.insert_resource(PlanqTranscript::default())
 		.insert_resource(Position::new(4, 14, 1)) // DEBUG: arbitrary player spawnpoint
+		.insert_resource(PowerGrid::default())
+		.insert_resource(self.prefs.clone())
+		.insert_resource(QuickSlots::new())
 		.insert_resource(RexAssets::new())
+		.insert_resource(TaskQueue::new())
+		.insert_resource(TutorialState::new())
+		.insert_resource(UnloadedDecks::default())
+		.insert_resource(ShipLog::default())
+		.insert_resource(SpawnOrder::default())
 		;
+		// The Update tuple above is full, so any further systems have to be added separately
+		self.bevy.add_systems(Update, (vitals_system.after(movement_system),
+			                             planq_dashboard_system,
+			                             planq_autopower_system.after(item_collection_system),
+			                             sighting_system.after(visibility_system),
+			                             quickslot_system.after(item_collection_system),
+			                             tutorial_system.after(movement_system),
+			                             ambient_cue_system.after(movement_system),
+			                             ambience_system.after(ambient_cue_system),
+			                             deck_indicator_system.after(movement_system),
+			                             entity_reaper_system.after(item_collection_system),
+			                             power_grid_system.before(lighting_system),
+			                             seal_objective_system.after(openable_system).after(lockable_system),
+			                             device_wear_system,
+			                             ship_log_system,
+			                             animation_system.before(camera_update_system),
+			                             planq_transcript_system,
+			                             salvage_system.after(item_collection_system),
+			                             spawn_index_system,
+			                             airlock_system.after(operable_system),
+			                             ));
+		self.bevy.add_systems(Update, deck_streaming_system.after(movement_system));
+		self.bevy.add_systems(Update, viewshed_resync_system.before(visibility_system));
+		self.bevy.add_systems(Update, comms_trigger_system.after(movement_system).before(planq_comms_system));
+		#[cfg(debug_assertions)]
+		self.bevy.add_systems(Update, carrier_check_system);
 		self.mode = EngineMode::Startup;
 		self.solve_layout(self.term_dims);
 		self.build_camera();
 	}
-	/// Creates the initial worldmap from scratch
-	pub fn build_new_worldmap(&mut self) {
-		// Loads the generated JSON layout file and parses it out into the game's data structures:
-		// - Creates the 'physical' tilemaps of ScreenCells that represent the game's terrain
-		// - Creates the 'logical' topology map of GraphRooms/GraphPortals that provide pathfinding and placement
-		// - Generates the baseline list of doors required to connect all of the rooms in the map
-		// - Generates the list of 'ladders' that connect rooms across z-levels and allow movement
-		let mut rng = self.bevy.world.get_resource_mut::<GlobalRng>().expect("RNG should always be an available Bevy resource");
-		self.mason.build_world(); // <- remove the RNG from here for starters, insert it closer to where it's needed
-		// Get a copy of the freshly-constructed world model
-		let mut model = self.mason.get_model();
-		let mut new_item_list = Vec::new();
-
-		// Get the list of items that we know for sure need to be generated at specific positions
-		let mut item_spawns = self.mason.get_essential_item_requests(); // list of (name, posn)
-		//eprintln!("* DEBUG: build_new_worldmap: essential: {:?}", item_spawns);
-		new_item_list.append(&mut item_spawns);
-		// Next, get the list of requested items, find spawnpoints for them, and add them to the list of spawns
-		let item_reqs = self.mason.get_additional_item_requests();
-		//eprintln!("* DEBUG: build_new_worldmap: additional: {:?}", item_reqs); // DEBUG:
-		for (room_name, item_name) in item_reqs.iter() {
-			//eprintln!("* DEBUG: Attempting to spawn {} in {}", item_name, room_name); // DEBUG:
-			// get the item shape from artisan (returns a SpawnTemplate)
-			//eprintln!("** DEBUG: looking to get a shape for {}", item_name);
-			if let Some(item_shape) = self.artisan.get_random_shape(item_name, &mut rng) {
-				// try to get a spawnpoint from mason using the ItemTemplate (returns a Option<Vec<(name: String, ref_posn: Position)>>)
-				//eprintln!("*** DEBUG: looking to get a spawnpoint for {}", item_name);
-				if let Some(mut item_spawns) = model.find_spawnpoint_in(room_name, item_shape, &mut rng) {
-					//eprintln!("**** DEBUG: found a place to spawn {}: {:?}", item_name, item_spawns);
-					new_item_list.append(&mut item_spawns);
-				}
-			}
-		}
-		// Spawn all of the items we need for the game
-		// This CANNOT be executed in the loop above or Rust will complain about a double borrow
-		// WARN: Need to have *all* positions decided on by this point
+	/// Spawns everything on a newly-planned ship and puts its model into the World
+	pub fn build_new_worldmap(&mut self, plan: WorldPlan) {
+		// Everything that takes any time was worked out by the LoadingJob, see loading::plan_world():
+		// - The 'physical' tilemaps of ScreenCells that represent the game's terrain
+		// - The 'logical' topology map of GraphRooms/GraphPortals that provide pathfinding and placement
+		// - The doors required to connect all of the rooms in the map, and the 'ladders' that connect the decks
+		// - Where each of the items on the ship goes, including the scattering of loose ones
+		let WorldPlan { mut model, spawns: new_item_list, power_grid, airlocks } = plan;
+		// Spawn all of the items we need for the game, in the order that they were planned
 		//eprintln!("* DEBUG: Sending the following list for spawn:\n{:#?}", new_item_list); // DEBUG:
 		for (i_name, i_posn) in new_item_list.iter() {
 			let item_list = self.artisan.create(i_name).at(*i_posn).build(&mut self.bevy.world);
@@ -589,8 +1458,24 @@ impl GameEngine<'_> {
 				//eprintln!("DEBUG: * added new item '{}' at posn {:?}", i_name, i_posn);
 			}
 		}
-		// Add the fully-constructed world model to Bevy
+		// Any door that sits in a straight wall gets a pocket in that wall to slide into when it opens
+		let mut door_query = self.bevy.world.query_filtered::<(Entity, &mut Body, &mut Openable), Without<Container>>();
+		for (d_enty, mut d_body, mut d_open) in door_query.iter_mut(&mut self.bevy.world) {
+			if d_body.extent.len() > 1 { continue; }
+			if let Some(pocket) = model.find_door_pocket(d_body.ref_posn) {
+				let doorway = d_body.ref_posn;
+				d_open.slides(doorway, pocket);
+				if d_open.is_open { // Doors that start out open need to be put away first
+					model.remove_contents(&d_body.posns(), d_enty);
+					d_body.move_to(pocket);
+					model.add_contents(&d_body.posns(), 0, d_enty);
+				}
+			}
+		}
+		link_airlocks(&mut self.bevy.world, &model, &airlocks);
+		// Add the fully-constructed world model to Bevy, along with the power grid that runs through it
 		self.bevy.insert_resource(model);
+		self.bevy.insert_resource(power_grid);
 	}
 	/// DEBUG: Creates a fallback dev map for testing purposes
 	pub fn build_dev_worldmap(&mut self) {
@@ -625,41 +1510,209 @@ impl GameEngine<'_> {
 		let main_camera = CameraView::new(self.ui_grid.camera_main.width as i32, self.ui_grid.camera_main.height as i32);
 		self.bevy.insert_resource(main_camera);
 	}
+	/// Takes note of the terminal's new size; the layout itself is solved again on the next render
+	pub fn resize(&mut self, width: u16, height: u16) {
+		self.term_dims = Rect::new(0, 0, width, height);
+		self.layout_changed = true;
+	}
 	/// Solves the layout configuration given a set of layout constraints and an area to cover
+	/// The PLANQ's terminal only gets room set aside for it while it's being carried and its screen is up
 	pub fn solve_layout(&mut self, area: Rect) {
-		self.ui_grid.calc_layout(area);
+		let show_terminal = self.bevy.world.get_resource::<PlanqData>().map_or(true, |x| x.is_carried && x.show_terminal);
+		self.ui_grid.calc_layout(area, show_terminal);
 		if let Some(mut camera) = self.bevy.world.get_resource_mut::<CameraView>() {
 			camera.set_dims(self.ui_grid.camera_main.width as i32, self.ui_grid.camera_main.height as i32);
 		}
 	}
-	/// Executes a command on the PLANQ, generally from the CLI; DEBUG: always returns false
+	/// Executes a command on the PLANQ, generally from the CLI, and puts whatever it had to say up on the PLANQ's
+	/// terminal; returns true if the command succeeded
 	pub fn exec(&mut self, cmd: PlanqCmd) -> bool {
-		// FIXME: this unwrap() cannot be replaced in situ, because regardless of whether or not there's a MessageLog,
-		// the PLANQ's commands should still be executed!
-		// Therefore, it would be better to pull all of these msglog-unwrap-tell_planq chains out to their own
-		// dedicated method, as self.tell_planq(), which itself handles these parts and can safely handle
-		// the unwrapping logic
-		let mut msglog = self.bevy.world.get_resource_mut::<MessageLog>().expect("MessageLog should be in Bevy");
+		let result = self.run_planq_cmd(cmd);
+		if let Some(mut msglog) = self.bevy.world.get_resource_mut::<MessageLog>() {
+			result.report(&mut msglog);
+		}
+		result.success
+	}
+	/// Runs a command on the PLANQ without reporting anything, and hands back its output for the caller to show
+	pub fn run_planq_cmd(&mut self, cmd: PlanqCmd) -> PlanqCmdResult {
 		match cmd {
-			PlanqCmd::Error(msg) => {
-				msglog.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:");
-				msglog.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", msg).as_str());
-				msglog.tell_planq(" ");
-			}
+			PlanqCmd::NoOperation => { PlanqCmdResult::ok(Vec::new()) }
+			PlanqCmd::Error(msg) => { PlanqCmdResult::fail(vec![msg]) }
 			PlanqCmd::Help => {
-				msglog.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Available commands:");
-				for command in PlanqCmd::iter() {
-					msglog.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]  {}", command).as_str());
+				let mut help_output = vec!["Available commands:".to_string()];
+				help_output.extend(PlanqCmd::iter().map(|x| format!("  {}", x)));
+				PlanqCmdResult::ok(help_output)
+			}
+			// TODO: these still need their subroutines; until then they point the player at the hardware that does the job
+			PlanqCmd::Shutdown | PlanqCmd::Reboot => {
+				PlanqCmdResult::fail(vec!["Not supported yet; use the PLANQ's power switch instead.".to_string()])
+			}
+			PlanqCmd::Connect(_) | PlanqCmd::Disconnect => {
+				PlanqCmdResult::fail(vec!["Not supported yet; use the PLANQ's access jack instead.".to_string()])
+			}
+			PlanqCmd::Msgs(index) => {
+				match self.bevy.world.get_resource_mut::<PlanqComms>() {
+					Some(mut comms) => { PlanqCmdResult::ok(comms.report(index)) }
+					None => { PlanqCmdResult::fail(vec!["Unable to reach the message store.".to_string()]) }
+				}
+			}
+			PlanqCmd::Mark(label) => {
+				let p_posn = self.bevy.world.get_resource::<Position>().copied();
+				let room_name = p_posn.and_then(|posn| self.bevy.world.get_resource::<WorldModel>().and_then(|x| x.layout.get_room_name(posn)));
+				match (p_posn, self.bevy.world.get_resource_mut::<Bookmarks>()) {
+					(Some(posn), Some(mut marks)) => {
+						let label = if !label.is_empty() { label } else { room_name.unwrap_or(format!("Mark {}", marks.marks.len())) };
+						marks.add(&label, posn);
+						PlanqCmdResult::ok(vec![format!("Bookmarked {} as '{}'", posn, label)])
+					}
+					_ => { PlanqCmdResult::fail(vec!["Unable to set a bookmark.".to_string()]) }
+				}
+			}
+			PlanqCmd::Marks => {
+				let p_posn = self.bevy.world.get_resource::<Position>().copied().unwrap_or_default();
+				PlanqCmdResult::ok(self.bevy.world.get_resource::<Bookmarks>().map(|x| x.report(p_posn)).unwrap_or_default())
+			}
+			PlanqCmd::Goto(index) => {
+				match self.bevy.world.get_resource_mut::<Bookmarks>().and_then(|mut x| x.goto(index)) {
+					Some(label) => { PlanqCmdResult::ok(vec![format!("Travelling to '{}'", label)]) }
+					None => { PlanqCmdResult::fail(vec![format!("No such bookmark: {}", index)]) }
+				}
+			}
+			PlanqCmd::Notes(None) => {
+				let p_posn = self.bevy.world.get_resource::<Position>().copied().unwrap_or_default();
+				PlanqCmdResult::ok(self.bevy.world.get_resource::<MapNotes>().map(|x| x.report(p_posn)).unwrap_or_default())
+			}
+			PlanqCmd::Notes(Some(index)) => {
+				let target = self.bevy.world.get_resource::<MapNotes>().and_then(|x| x.sorted().get(index).cloned());
+				let (posn, text) = if let Some(value) = target { value } else {
+					return PlanqCmdResult::fail(vec![format!("No such note: {}", index)]);
+				};
+				let mark = Bookmark::new(&format!("the note \"{}\"", text), posn);
+				// Notes go through the same route preview as the bookmarks, if the player wants to see it
+				if self.prefs.preview_travel {
+					return match self.preview_route(mark) {
+						Ok(prompt) => { PlanqCmdResult::ok(vec![prompt]) }
+						Err(reason) => { PlanqCmdResult::fail(vec![reason]) }
+					};
+				}
+				match self.bevy.world.get_resource_mut::<Bookmarks>() {
+					Some(mut marks) => {
+						marks.destination = Some(posn);
+						PlanqCmdResult::ok(vec![format!("Travelling to {}", mark.label)])
+					}
+					None => { PlanqCmdResult::fail(vec!["Unable to reach the bookmarks.".to_string()]) }
+				}
+			}
+			PlanqCmd::Analyze(target) => { self.start_analysis(&target) }
+			PlanqCmd::Scan => { self.run_scan() }
+			PlanqCmd::ScanLog => {
+				PlanqCmdResult::ok(self.bevy.world.get_resource::<PlanqData>().map(|x| x.scan_report()).unwrap_or_default())
+			}
+			PlanqCmd::ShipLog(page) => { self.read_ship_log(page) }
+			PlanqCmd::Dash => {
+				match self.bevy.world.get_resource_mut::<PlanqData>() {
+					Some(mut planq) if planq.jack_cnxn != Entity::PLACEHOLDER => {
+						planq.push_view(PlanqView::Dashboard);
+						self.focus = PanelFocus::Planq;
+						PlanqCmdResult::ok(Vec::new())
+					}
+					_ => { PlanqCmdResult::fail(vec!["The dashboard needs a connection to an access port.".to_string()]) }
+				}
+			}
+			PlanqCmd::Map => {
+				match self.bevy.world.get_resource_mut::<PlanqData>() {
+					Some(mut planq) => {
+						planq.push_view(PlanqView::Minimap);
+						self.focus = PanelFocus::Planq;
+						PlanqCmdResult::ok(Vec::new())
+					}
+					None => { PlanqCmdResult::fail(vec!["No PLANQ found.".to_string()]) }
+				}
+			}
+			PlanqCmd::Export => {
+				match self.bevy.world.get_resource::<PlanqMonitor>().and_then(|x| x.telemetry_csv()) {
+					Some(text) => {
+						match std::fs::write(TELEMETRY_FILENAME, text) {
+							Ok(()) => { PlanqCmdResult::ok(vec![format!("Telemetry exported to {}", TELEMETRY_FILENAME)]) }
+							Err(e) => {
+								error!("! could not write the telemetry file at {}: {}", TELEMETRY_FILENAME, e); // DEBUG: report a failed export
+								PlanqCmdResult::fail(vec!["Telemetry export failed.".to_string()])
+							}
+						}
+					}
+					None => { PlanqCmdResult::fail(vec!["No telemetry has been recorded yet.".to_string()]) }
+				}
+			}
+			PlanqCmd::Download => { self.transfer_data(false) }
+			PlanqCmd::Upload => { self.transfer_data(true) }
+			PlanqCmd::Transcript(true) => {
+				let world = &mut self.bevy.world;
+				if !world.contains_resource::<PlanqTranscript>() || !world.contains_resource::<MessageLog>() {
+					return PlanqCmdResult::fail(vec!["Unable to reach the terminal's output.".to_string()]);
+				}
+				let result = world.resource_scope(|world, mut transcript: Mut<PlanqTranscript>| {
+					if transcript.is_recording() { return None; }
+					Some(transcript.start(world.resource::<MessageLog>(), &transcript_path()))
+				});
+				match result {
+					Some(Ok(path)) => { PlanqCmdResult::ok(vec![format!("Recording a transcript to {}", path.display())]) }
+					Some(Err(e)) => {
+						error!("! could not start the transcript: {}", e); // DEBUG: report a failed transcript
+						PlanqCmdResult::fail(vec!["Unable to start a transcript.".to_string()])
+					}
+					None => { PlanqCmdResult::fail(vec!["A transcript is already being recorded.".to_string()]) }
+				}
+			}
+			PlanqCmd::Transcript(false) => {
+				let stamp = self.bevy.world.get_resource::<Time>().map(ship_clock).unwrap_or_default().get_as_string();
+				let world = &mut self.bevy.world;
+				let path = if world.contains_resource::<PlanqTranscript>() {
+					world.resource_scope(|world, mut transcript: Mut<PlanqTranscript>| {
+						world.get_resource::<MessageLog>().and_then(|x| transcript.stop(x, &stamp))
+					})
+				} else {
+					None
+				};
+				match path {
+					Some(path) => { PlanqCmdResult::ok(vec![format!("Transcript saved to {}", path.display())]) }
+					None => { PlanqCmdResult::fail(vec!["No transcript is being recorded.".to_string()]) }
+				}
+			}
+			#[cfg(debug_assertions)]
+			PlanqCmd::Census => {
+				let census = crate::census::take_census(&mut self.bevy.world);
+				info!("* census: {}", census); // DEBUG: log the census for later reference
+				PlanqCmdResult::ok(census.report())
+			}
+			#[cfg(debug_assertions)]
+			PlanqCmd::Reveal => {
+				match self.bevy.world.get_resource_mut::<CameraView>() {
+					Some(mut camera) => {
+						camera.debug_reveal = camera.debug_reveal.next();
+						PlanqCmdResult::ok(vec![format!("Map reveal: {:?}", camera.debug_reveal)])
+					}
+					None => { PlanqCmdResult::fail(vec!["There's no camera to reveal the map on.".to_string()]) }
+				}
+			}
+			#[cfg(debug_assertions)]
+			PlanqCmd::DiffSaves(before, after) => { self.diff_saves(&before, &after) }
+			PlanqCmd::Power => {
+				match self.bevy.world.get_resource::<PowerGrid>() {
+					Some(grid) => { PlanqCmdResult::ok(grid.status_report()) }
+					None => { PlanqCmdResult::fail(vec!["Unable to reach the power grid.".to_string()]) }
+				}
+			}
+			#[cfg(debug_assertions)]
+			PlanqCmd::Blackout(duration) => {
+				match self.bevy.world.get_resource_mut::<Events<PowerOutage>>() {
+					Some(mut outages) => {
+						outages.send(PowerOutage { generator: None, duration });
+						PlanqCmdResult::ok(vec![format!("Cutting the mains for {} turns", duration)])
+					}
+					None => { PlanqCmdResult::fail(vec!["Unable to reach the power grid.".to_string()]) }
 				}
-				msglog.tell_planq(" ");
 			}
-			PlanqCmd::Shutdown => { todo!(); /* trigger a shutdown */ }
-			PlanqCmd::Reboot => { todo!(); /* execute a reboot */ }
-			PlanqCmd::Connect(_target) => { todo!(); /* run the planq.connect subroutine */ }
-			PlanqCmd::Disconnect => { todo!(); /* run the planq.disconnect subroutine */ }
-			_ => { /* NoOperation */ }
 		}
-		false
 	}
 }
 
@@ -678,8 +1731,1684 @@ pub enum EngineMode {
 	GoodEnd,
 	BadEnd,     // TODO: set up variants for both this and GoodEnd? maybe just a GameOver mode?
 }
+//   ##: RunState
+/// Contains the state flags of the game in progress that need to be visible to both the GameEngine and to Bevy
+/// This is saved along with the rest of the game; the player's own preferences are kept apart, see Preferences
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct RunState {
+	pub mode:         EngineMode, // Mirrors GameEngine.mode, do not set this directly; see mode_changed
+	pub mode_changed: bool, // If true, the GameEngine will switch to the mode given above on its next tick
+	pub turn_count:   u64, // The number of ticks that the game has been Running for
+	pub seed:         u64, // The RNG seed that this game was started with
+	pub end_cause:    Option<String>, // Why the game ended, if it has; ie the cause of death
+	pub player_name:  String, // The name that the player character was given at the start of the game
+	pub items_collected: u64, // The number of items that the player has picked up over the course of the game
+	pub salvage:      u64, // The total value of everything that's been put into the salvage bin
+	pub permadeath:   bool, // If true, the save file is deleted when the player dies, so there's no going back
+	pub scenario:     ScenarioKind, // The scenario that this game is being played under
+}
+impl RunState {
+	pub fn new() -> RunState {
+		RunState::default()
+	}
+	/// Allows a Bevy system to request a change of the GameEngine's mode
+	pub fn request_mode(&mut self, new_mode: EngineMode) {
+		self.mode = new_mode;
+		self.mode_changed = true;
+	}
+	/// Ends the game with the given outcome, recording the reason why for posterity
+	pub fn end_game(&mut self, outcome: EngineMode, cause: &str) {
+		self.end_cause = Some(cause.to_string());
+		self.request_mode(outcome);
+	}
+	/// Produces the text of the HUD line given the current ship time
+	pub fn hud_text(&self, clock: Duration) -> String {
+		format!(" {:?} | T:{} | {} ", self.mode, self.turn_count, clock.get_as_string())
+	}
+}
+//   ##: GameSettings
+/// The settings and run state that were saved together before being split into RunState and Preferences
+/// Nothing uses this any more; it's only kept so that older saves can be read in, see migrate_legacy_settings()
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct GameSettings {
+	pub mode:         EngineMode,
+	pub mode_changed: bool,
+	pub turn_count:   u64,
+	pub show_hud:     bool,
+	pub seed:         u64,
+	pub end_cause:    Option<String>,
+	pub player_name:  String,
+	pub items_collected: u64,
+	pub announce_sightings: bool,
+	pub dir_names:    DirNames,
+	pub show_hints:   bool,
+	pub fov_algorithm: FovAlgorithm,
+	pub hazard_prompt: HazardPrompt,
+	pub permadeath:   bool,
+}
+//   ##: EngineFiles
+/// Where the GameEngine keeps everything that outlasts a single game: the player's preferences, the legacy record,
+/// and the save slots; a real game uses the defaults, and the tests point them all into a scratch dir of their own
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineFiles {
+	pub prefs:    String, // The path to the preferences file
+	pub legacy:   String, // The path to the legacy file
+	pub save_dir: Option<PathBuf>, // If set, the save slots are kept in here instead of in bevy_save's own dir
+}
+impl Default for EngineFiles {
+	fn default() -> EngineFiles {
+		EngineFiles {
+			prefs: PREFS_FILENAME.to_string(),
+			legacy: LEGACY_FILENAME.to_string(),
+			save_dir: None,
+		}
+	}
+}
+impl EngineFiles {
+	/// Keeps all of the files together in the given dir, which is made if it isn't there yet
+	pub fn in_dir(dir: &Path) -> EngineFiles {
+		if let Err(e) = std::fs::create_dir_all(dir) {
+			error!("! could not make the dir at {}: {}", dir.display(), e); // DEBUG: report a failure to make the files dir
+		}
+		EngineFiles {
+			prefs: dir.join(PREFS_FILENAME).to_string_lossy().to_string(),
+			legacy: dir.join(LEGACY_FILENAME).to_string_lossy().to_string(),
+			save_dir: Some(dir.to_path_buf()),
+		}
+	}
+	/// Returns the name that bevy_save should keep the given slot under; bevy_save joins the name onto its own dir, so
+	/// a full path into the save_dir takes the slot out of there entirely
+	pub fn slot_key(&self, slot: &str) -> String {
+		self.save_dir.as_ref().map_or(slot.to_string(), |x| x.join(slot).to_string_lossy().to_string())
+	}
+	/// Lists the headers of every slot that has a save in it, newest first, as list_slots() does for bevy_save's dir
+	pub fn list_slots(&self) -> Vec<SlotHeader> {
+		list_slots_keyed(&SAVE_SLOTS, |x| self.slot_key(x))
+	}
+}
+//   ##: state_qualifiers
+/// Lists the state words that describe an openable or lockable thing, in the order they're read out, ie "closed, locked"
+pub fn state_qualifiers(openable: Option<&Openable>, lockable: Option<&Lockable>) -> Vec<String> {
+	let mut output = Vec::new();
+	if let Some(door) = openable {
+		output.push(if door.is_open { "open" } else { "closed" }.to_string());
+		if door.is_stuck { output.push("stuck".to_string()); }
+	}
+	if let Some(lock) = lockable {
+		if lock.is_locked { output.push("locked".to_string()); }
+	}
+	output
+}
+//   ##: permadeath_slot
+/// Picks out the save slot that a death should delete: only the current run's own save, and only under permadeath
+pub fn permadeath_slot(current_slot: Option<&str>, permadeath: bool) -> Option<&str> {
+	if !permadeath { return None; }
+	current_slot
+}
+//   ##: is_too_small
+/// Returns true if the given terminal area is below the minimum size that the game will draw itself into
+pub fn is_too_small(area: Rect) -> bool {
+	area.width < MIN_TERM_WIDTH || area.height < MIN_TERM_HEIGHT
+}
+//   ##: new_run_seed
+/// Picks the RNG seed for a new game: uses the SPACEGAME_SEED environment variable if it's set to a number,
+/// otherwise makes one up from the system clock
+pub fn new_run_seed() -> u64 {
+	if let Some(seed) = std::env::var("SPACEGAME_SEED").ok().and_then(|x| x.parse::<u64>().ok()) {
+		return seed;
+	}
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map(|x| x.as_nanos() as u64)
+		.unwrap_or(69420)
+}
+//   ##: register_saveables
+/// Registers every type that goes into a save file, along with the types that they're built from; the deck streaming
+/// uses the same registry, so anything that's missing here will also be lost when a deck gets unloaded
+pub fn register_saveables(app: &mut App) {
+	app
+	.register_type::<(i32, i32, i32)>()
+	.register_type::<AirlockCycle>()
+	.register_type::<DataFile>()
+	.register_type::<DataKind>()
+	.register_type::<DebugReveal>()
+	.register_type::<DirNames>()
+	.register_type::<EngineMode>()
+	.register_type::<FovAlgorithm>()
+	.register_type::<HazardPrompt>()
+	.register_type::<LevelEnvironment>()
+	.register_type::<Generator>()
+	.register_type::<SealObjective>()
+	.register_type::<Vec<SealObjective>>()
+	.register_type::<PowerFeed>()
+	.register_type::<ScenarioKind>()
+	.register_type::<Vec<Generator>>()
+	.register_type::<Vec<PowerFeed>>()
+	.register_type::<Vec<(String, u64)>>()
+	.register_type::<(String, u64)>()
+	.register_type::<JobStage>()
+	.register_type::<MaintenanceJob>()
+	.register_type::<Task>()
+	.register_type::<Vec<Task>>()
+	.register_type::<DeviceState>()
+	.register_type::<crate::components::Direction>()
+	.register_type::<Dashboard>()
+	.register_type::<DeviceEntry>()
+	.register_type::<PlanqView>()
+	.register_type::<ZoneStatus>()
+	.register_type::<Vec<DeviceEntry>>()
+	.register_type::<Vec<PlanqView>>()
+	.register_type::<Vec<ZoneStatus>>()
+	.register_type::<CommsMessage>()
+	.register_type::<PlanqDataType>()
+	.register_type::<ScanRecord>()
+	.register_type::<Vec<ScanRecord>>()
+	.register_type::<ShipLogEntry>()
+	.register_type::<Vec<ShipLogEntry>>()
+	.register_type::<PlanqEvent>()
+	.register_type::<PlanqEventType>()
+	.register_type::<Option<Position>>()
+	.register_type::<Option<u64>>()
+	.register_type::<Option<usize>>()
+	.register_type::<Option<Entity>>()
+	.register_type::<Option<crate::components::Direction>>()
+	.register_type::<Option<Task>>()
+	.register_type::<Vec<Option<Entity>>>()
+	.register_type::<Portal>()
+	.register_type::<Position>()
+	.register_type::<TimerMode>()
+	.register_type::<Vec<bool>>()
+	.register_type::<Vec<Bookmark>>()
+	.register_type::<Option<Bookmark>>()
+	.register_type::<Vec<CommsMessage>>()
+	.register_type::<Vec<DataFile>>()
+	.register_type::<Vec<TutorialHint>>()
+	.register_type::<Vec<Entity>>()
+	.register_type::<Vec<Glyph>>()
+	.register_type::<Vec<WorldMap>>()
+	.register_type::<Vec<Message>>()
+	.register_type::<Vec<MessageChannel>>()
+	.register_type::<Vec<Portal>>()
+	.register_type::<Vec<Position>>()
+	.register_type::<Vec<String>>()
+	.register_type::<Vec<Vec<String>>>()
+	.register_type::<Vec<TileType>>()
+	.register_type::<Vec<Tile>>()
+	.register_type::<HashMap<(i32, i32, i32), (i32, i32, i32)>>()
+	.register_type::<HashMap<Entity, Position>>() // planned to be superceded by the below type
+	.register_type::<HashMap<Position, Vec<Entity>>>()
+	.register_type::<HashMap<String, PlanqDataType>>()
+	.register_type::<HashMap<Position, ScreenCell>>()
+	.register_type::<bevy::utils::HashSet<ActionType>>()
+	.register_saveable::<AccessPort>()
+	.register_saveable::<ActionSet>()
+	.register_saveable::<Airlock>()
+	.register_saveable::<AnimatedRenderable>()
+	.register_saveable::<Bookmark>()
+	.register_saveable::<Bookmarks>()
+	.register_saveable::<Body>()
+	.register_saveable::<CameraView>()
+	.register_saveable::<CarryCapacity>()
+	.register_saveable::<CommsTrigger>()
+	.register_saveable::<Container>()
+	.register_saveable::<DataSampleTimer>()
+	.register_saveable::<DeckIndicator>()
+	.register_saveable::<DataStore>()
+	.register_saveable::<Disabled>()
+	.register_saveable::<Description>()
+	.register_saveable::<Device>()
+	.register_saveable::<GameEvent>()
+	.register_saveable::<GameEventContext>()
+	.register_saveable::<GameEventType>()
+	.register_saveable::<GameSettings>() // Only for loading older saves, see migrate_legacy_settings()
+	.register_saveable::<GlobalRng>()
+	.register_saveable::<Glyph>()
+	.register_saveable::<Hears>()
+	.register_saveable::<History>()
+	.register_saveable::<IsCarried>()
+	.register_saveable::<Key>()
+	.register_saveable::<LazyLoot>()
+	.register_saveable::<LightSource>()
+	.register_saveable::<LMR>()
+	.register_saveable::<Lockable>()
+	.register_saveable::<Maintainer>()
+	.register_saveable::<MapNotes>()
+	.register_saveable::<WorldMap>()
+	.register_saveable::<Memory>()
+	.register_saveable::<Message>()
+	.register_saveable::<MessageChannel>()
+	.register_saveable::<MessageLog>()
+	.register_saveable::<Mobile>()
+	.register_saveable::<MoveStyle>()
+	.register_saveable::<NavCaps>()
+	.register_saveable::<WorldModel>()
+	.register_saveable::<Networkable>()
+	.register_saveable::<Objectives>()
+	.register_saveable::<Obstructive>()
+	.register_saveable::<Opaque>()
+	.register_saveable::<Openable>()
+	.register_saveable::<PerceptionMode>()
+	.register_saveable::<PerceptionPolicy>()
+	.register_saveable::<Planq>()
+	.register_saveable::<PlanqActionMode>()
+	.register_saveable::<PlanqBootText>()
+	.register_saveable::<PlanqComms>()
+	.register_saveable::<PlanqCPUMode>()
+	.register_saveable::<PlanqData>()
+	.register_saveable::<PlanqMonitor>()
+	.register_saveable::<PlanqProcess>()
+	.register_saveable::<Player>()
+	.register_saveable::<Portable>()
+	.register_saveable::<Position>()
+	.register_saveable::<PowerGrid>()
+	.register_saveable::<QuickSlots>()
+	.register_saveable::<ReachProfile>()
+	.register_saveable::<RngComponent>()
+	.register_saveable::<RunState>()
+	.register_saveable::<Salvage>()
+	.register_saveable::<SalvageBin>()
+	.register_saveable::<ScreenCell>()
+	.register_saveable::<ShipLog>()
+	.register_saveable::<SpawnIndex>()
+	.register_saveable::<SpawnOrder>()
+	.register_saveable::<TaskQueue>()
+	.register_saveable::<Tile>()
+	.register_saveable::<TileType>()
+	.register_saveable::<TutorialHint>()
+	.register_saveable::<TutorialState>()
+	.register_saveable::<Unidentified>()
+	.register_saveable::<Viewshed>()
+	.register_saveable::<Vitals>()
+	.register_saveable::<Weight>()
+	.register_saveable::<Workbench>()
+	.register_saveable::<bevy::utils::hashbrown::HashMap<Position, Position>>()
+	.register_saveable::<bevy::utils::hashbrown::HashMap<Position, String>>()
+	.register_saveable::<bevy::utils::hashbrown::HashSet<ActionType>>()
+	;
+}
 //   ##: AppResult
 /// Application result type, provides some nice handling if the game crashes
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+	use ratatui::{backend::TestBackend, Terminal};
+	use crate::engine::handler::key_parser;
+	use crate::testmap::*;
+	use super::*;
+	//  ##: Setup
+	/// Sets up an engine with a fresh World, registered the same way that a new game's is
+	/// Each one keeps its files in a scratch dir of its own, so that no test reads or writes the player's real
+	/// preferences, legacy record, or save slots, and tests running side by side can't trip over each other's
+	fn test_engine() -> GameEngine<'static> {
+		static ENGINE_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let number = ENGINE_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+		let dir = std::env::temp_dir().join(format!("test_engine_{}_{}", std::process::id(), number));
+		let mut eng = GameEngine::with_files(Rect::new(0, 0, 80, 24), EngineFiles::in_dir(&dir));
+		eng.init_bevy();
+		eng
+	}
+	/// Puts the player into the engine along with any extra Components that the test calls for, and mirrors their spot
+	/// into the World the same way that a running game does; if a test map is given, it's laid out first, and the
+	/// player stands at the map's own start unless they're given a spot of their own
+	fn player_setup(eng: &mut GameEngine<'_>, map: Option<TestMap>, posn: Option<Position>, extras: impl Bundle) -> Entity {
+		let start = posn.or(map.as_ref().and_then(|x| x.player_start)).expect("the player needs somewhere to stand");
+		if let Some(testmap) = map {
+			eng.bevy.world.insert_resource(testmap.model);
+		}
+		eng.bevy.world.insert_resource(start);
+		eng.bevy.world.spawn((Player { }, Body::small(start, ScreenCell::default()), extras)).id()
+	}
+	//  ##: Save round trips
+	/// Saves the engine's World into the given slot and loads it back into a fresh engine, the same way that the Load
+	/// Game menu would, so that any reflected type that was never registered makes the test fail
+	fn round_trip(eng: &mut GameEngine<'_>, slot: &str) -> GameEngine<'static> {
+		let key = eng.files.slot_key(slot);
+		eng.bevy.world.save(&key).expect("could not save the test game");
+		let mut loaded = test_engine();
+		let applier = loaded.bevy.world.load_applier(&key).expect("could not read back the test game");
+		let result = applier.despawn(DespawnMode::Unmapped).apply();
+		let _ = delete_slot(&key);
+		result.expect("could not apply the test game");
+		loaded
+	}
+	#[test]
+	fn an_engine_under_test_keeps_its_files_to_itself() {
+		let mut eng = test_engine();
+		let dir = eng.files.save_dir.clone().expect("the test engine should have a save dir");
+		assert!(Path::new(&eng.files.prefs).starts_with(&dir));
+		assert!(Path::new(&eng.files.legacy).starts_with(&dir));
+		assert_eq!(bevy_save::get_save_file(&eng.files.slot_key("slot_1")).parent(), Some(dir.as_path()));
+		eng.prefs.show_hud = false;
+		eng.apply_prefs();
+		assert!(!Preferences::load(&eng.files.prefs).show_hud);
+		// Nothing has been saved into this engine's slots, whatever might be in the real ones
+		assert!(eng.files.list_slots().is_empty());
+		let _ = std::fs::remove_dir_all(dir);
+	}
+	#[test]
+	fn container_slot_limit_survives_a_save() {
+		let mut eng = test_engine();
+		eng.bevy.world.spawn((Description::new().name("satchel"), Container::with_slots(3)));
+		let mut loaded = round_trip(&mut eng, "test_container_slots");
+		let mut c_query = loaded.bevy.world.query::<(&Description, &Container)>();
+		let slots: Vec<Option<usize>> = c_query.iter(&loaded.bevy.world).filter(|x| x.0.name == "satchel").map(|x| x.1.max_slots).collect();
+		assert_eq!(slots, vec![Some(3)]);
+	}
+	/// Builds a schedule that runs the maintenance jobs, without anything else in the game getting in the way
+	fn maintenance_schedule() -> Schedule {
+		let mut schedule = Schedule::default();
+		schedule.add_systems((task_generation_system, maintenance_ai_system, openable_system, item_collection_system).chain());
+		schedule
+	}
+	#[test]
+	fn the_lmr_keeps_working_after_a_load() {
+		let mut eng = test_engine();
+		let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR).with_room("main hallway", Position::new(1, 1, 0), Position::new(3, 3, 0));
+		eng.bevy.world.insert_resource(testmap.model);
+		eng.bevy.world.spawn((LMR { }, Description::new().name("LMR"), Body::small(Position::new(1, 1, 0), ScreenCell::default()), Container::default(), Mobile::default(), Maintainer::default()));
+		eng.bevy.world.spawn((Description::new().name("wrench"), Body::small(Position::new(2, 1, 0), ScreenCell::default()), Portable::empty()));
+		eng.bevy.world.spawn((Description::new().name("locker"), Body::small(Position::new(1, 2, 0), ScreenCell::default()), Container::default(), Openable::new(true, "▯", "▮")));
+		maintenance_schedule().run(&mut eng.bevy.world);
+		let mut loaded = round_trip(&mut eng, "test_lmr_job");
+		let mut w_query = loaded.bevy.world.query::<&Maintainer>();
+		let tasks: Vec<Option<Task>> = w_query.iter(&loaded.bevy.world).map(|x| x.task).collect();
+		assert_eq!(tasks.len(), 1);
+		assert!(matches!(tasks[0].map(|x| x.job), Some(MaintenanceJob::Tidy(_))), "the LMR forgot its job: {:?}", tasks);
+		let mut schedule = maintenance_schedule();
+		for _ in 0..10 { schedule.run(&mut loaded.bevy.world); }
+		let mut d_query = loaded.bevy.world.query::<(Entity, &Description, Option<&Portable>)>();
+		let found: Vec<(Entity, String, Option<Entity>)> = d_query.iter(&loaded.bevy.world).map(|x| (x.0, x.1.name.clone(), x.2.map(|y| y.carrier))).collect();
+		let locker = found.iter().find(|x| x.1 == "locker").map(|x| x.0).expect("the locker was not loaded");
+		assert_eq!(found.iter().find(|x| x.1 == "wrench").and_then(|x| x.2), Some(locker));
+		assert!(loaded.bevy.world.resource::<TaskQueue>().jobs.is_empty());
+	}
+	#[test]
+	fn a_loaded_game_keeps_its_own_lmr() {
+		let mut eng = test_engine();
+		eng.bevy.world.insert_resource(TestMap::new(CORRIDOR_WITH_DOOR).model);
+		eng.bevy.world.spawn((LMR { }, Body::small(Position::new(3, 1, 0), ScreenCell::default()), Maintainer::default()));
+		let mut loaded = round_trip(&mut eng, "test_lmr_spawn");
+		let mut schedule = Schedule::default();
+		schedule.add_systems(new_lmr_spawn);
+		schedule.run(&mut loaded.bevy.world);
+		let mut l_query = loaded.bevy.world.query_filtered::<&Body, (With<LMR>, With<Maintainer>)>();
+		let bodies: Vec<Position> = l_query.iter(&loaded.bevy.world).map(|x| x.ref_posn).collect();
+		assert_eq!(bodies, vec![Position::new(3, 1, 0)]);
+	}
+	#[test]
+	fn the_first_frame_after_a_load_sees_the_right_tiles() {
+		let mut eng = test_engine();
+		eng.bevy.world.insert_resource(TestMap::new(TWO_ROOMS_WITH_DOOR).model);
+		eng.bevy.world.spawn((Player::default(), Body::small(Position::new(2, 2, 0), ScreenCell::new().glyph("@")), Viewshed::new(8), Memory::new()));
+		let mut loaded = round_trip(&mut eng, "test_first_frame");
+		// The same as load_game does once the save has been applied, and then the systems that the first frame runs
+		loaded.bevy.world.resource_mut::<Events<ResyncViewsheds>>().send(ResyncViewsheds);
+		let mut schedule = Schedule::default();
+		schedule.add_systems((viewshed_resync_system, visibility_system).chain());
+		schedule.run(&mut loaded.bevy.world);
+		let mut p_query = loaded.bevy.world.query_filtered::<&Viewshed, With<Player>>();
+		let view = p_query.single(&loaded.bevy.world);
+		// The whole of the first room is in sight, along with the row through the doorway up to the pillar
+		assert!(view.visible_points.contains(&Point::new(1, 1)));
+		assert!(view.visible_points.contains(&Point::new(3, 3)));
+		assert!(view.visible_points.contains(&Point::new(6, 2)));
+		assert!(!view.visible_points.contains(&Point::new(9, 2)));
+		assert!(!view.dirty);
+		let map = &loaded.bevy.world.resource::<WorldModel>().levels[0];
+		assert!(map.revealed_tiles[map.to_index(1, 1)]);
+	}
+	#[test]
+	fn run_state_survives_a_save() {
+		let mut eng = test_engine();
+		{
+			let mut run_state = eng.bevy.world.resource_mut::<RunState>();
+			run_state.turn_count = 412;
+			run_state.seed = 99;
+			run_state.player_name = "Tess".to_string();
+			run_state.end_cause = Some("fell down a ladder".to_string());
+			run_state.mode = EngineMode::Paused;
+		}
+		let loaded = round_trip(&mut eng, "test_run_state");
+		let run_state = loaded.bevy.world.resource::<RunState>();
+		assert_eq!((run_state.turn_count, run_state.seed, run_state.mode), (412, 99, EngineMode::Paused));
+		assert_eq!(run_state.player_name, "Tess");
+		assert_eq!(run_state.end_cause, Some("fell down a ladder".to_string()));
+	}
+	#[test]
+	fn a_load_never_changes_the_preferences() {
+		let mut eng = test_engine();
+		let mut saved_with = Preferences::default();
+		saved_with.show_hud = false;
+		saved_with.palette = saved_with.palette.next();
+		saved_with.hazard_prompt = saved_with.hazard_prompt.next();
+		eng.prefs = saved_with.clone();
+		eng.bevy.world.insert_resource(saved_with.clone());
+		let key = eng.files.slot_key("test_prefs_untouched");
+		eng.bevy.world.save(&key).expect("could not save the test game");
+		// The player has changed their mind since the game was saved
+		let mut loaded = test_engine();
+		let current = Preferences { show_hints: false, ..Preferences::default() };
+		loaded.prefs = current.clone();
+		loaded.bevy.world.insert_resource(current.clone());
+		let applier = loaded.bevy.world.load_applier(&key).expect("could not read back the test game");
+		let result = applier.despawn(DespawnMode::Unmapped).apply();
+		let _ = delete_slot(&key);
+		result.expect("could not apply the test game");
+		loaded.migrate_legacy_settings();
+		assert_eq!(loaded.prefs, current);
+		assert_eq!(*loaded.bevy.world.resource::<Preferences>(), current);
+		assert_ne!(current, saved_with);
+	}
+	#[test]
+	fn an_older_save_has_its_settings_migrated() {
+		let mut eng = test_engine();
+		eng.bevy.world.remove_resource::<RunState>();
+		eng.bevy.world.insert_resource(GameSettings {
+			turn_count: 30,
+			seed: 1234,
+			player_name: "Pleyeur".to_string(),
+			permadeath: true,
+			show_hud: false,
+			..GameSettings::default()
+		});
+		let mut loaded = round_trip(&mut eng, "test_legacy_settings");
+		let prefs_before = loaded.prefs.clone();
+		loaded.migrate_legacy_settings();
+		assert!(loaded.bevy.world.get_resource::<GameSettings>().is_none());
+		let run_state = loaded.bevy.world.resource::<RunState>();
+		assert_eq!((run_state.turn_count, run_state.seed, run_state.permadeath), (30, 1234, true));
+		assert_eq!(run_state.player_name, "Pleyeur");
+		// The old HUD setting is not carried over
+		assert_eq!(loaded.prefs, prefs_before);
+	}
+	#[test]
+	fn bookmark_preview_survives_a_save() {
+		let mut eng = test_engine();
+		let route = vec![Position::new(1, 1, 0), Position::new(2, 1, 0), Position::new(3, 2, 0)];
+		{
+			let mut bookmarks = eng.bevy.world.resource_mut::<Bookmarks>();
+			bookmarks.marks.push(Bookmark::new("galley", Position::new(3, 2, 0)));
+			bookmarks.pending = Some(Bookmark::new("galley", Position::new(3, 2, 0)));
+			bookmarks.preview = route.clone();
+		}
+		let loaded = round_trip(&mut eng, "test_bookmark_preview");
+		let bookmarks = loaded.bevy.world.resource::<Bookmarks>();
+		assert_eq!(bookmarks.marks, vec![Bookmark::new("galley", Position::new(3, 2, 0))]);
+		assert_eq!(bookmarks.pending, Some(Bookmark::new("galley", Position::new(3, 2, 0))));
+		assert_eq!(bookmarks.preview, route);
+	}
+	#[test]
+	fn planq_dashboard_survives_a_save() {
+		let mut eng = test_engine();
+		let device = DeviceEntry { name: "light panel".to_string(), address: Entity::PLACEHOLDER, state: DeviceState::Working };
+		{
+			let mut planq = eng.bevy.world.resource_mut::<PlanqData>();
+			planq.push_view(PlanqView::Dashboard);
+			planq.dashboard.refresh(&[("Galley".to_string(), device.clone())]);
+		}
+		let loaded = round_trip(&mut eng, "test_planq_dashboard");
+		let planq = loaded.bevy.world.resource::<PlanqData>();
+		assert_eq!(planq.current_view(), PlanqView::Dashboard);
+		assert_eq!(planq.dashboard.zones.len(), 1);
+		assert_eq!(planq.dashboard.zones[0].working, 1);
+		assert_eq!(planq.dashboard.zones[0].devices[0].name, device.name);
+	}
+	#[test]
+	fn quick_slots_survive_a_save() {
+		let mut eng = test_engine();
+		let item = eng.bevy.world.spawn((Description::new().name("flashlight"), Portable::empty())).id();
+		eng.bevy.world.resource_mut::<QuickSlots>().assign(item);
+		let loaded = round_trip(&mut eng, "test_quick_slots");
+		let bound = loaded.bevy.world.resource::<QuickSlots>().get(0).expect("the quick-slot was not saved");
+		assert_eq!(loaded.bevy.world.get::<Description>(bound).map(|x| x.name.clone()), Some("flashlight".to_string()));
+		assert_eq!(loaded.bevy.world.resource::<QuickSlots>().get(1), None);
+	}
+	#[test]
+	fn tutorial_progress_survives_a_save() {
+		let mut eng = test_engine();
+		{
+			let mut tutorial = eng.bevy.world.resource_mut::<TutorialState>();
+			tutorial.fired.push(TutorialHint::GetItem);
+			tutorial.last_turn = Some(42);
+		}
+		let loaded = round_trip(&mut eng, "test_tutorial_progress");
+		let tutorial = loaded.bevy.world.resource::<TutorialState>();
+		assert_eq!(tutorial.fired, vec![TutorialHint::GetItem]);
+		assert_eq!(tutorial.last_turn, Some(42));
+	}
+	#[test]
+	fn tripped_devices_survive_a_save() {
+		let mut eng = test_engine();
+		let lamp = eng.bevy.world.spawn((Description::new().name("lamp"), Device::new(0))).id();
+		{
+			let mut grid = eng.bevy.world.resource_mut::<PowerGrid>();
+			*grid = PowerGrid::new(vec!["reactor".to_string()]);
+			grid.connect("Galley", "reactor");
+			grid.tripped.push(lamp);
+		}
+		let loaded = round_trip(&mut eng, "test_tripped_devices");
+		let grid = loaded.bevy.world.resource::<PowerGrid>();
+		assert_eq!(grid.zones(), vec!["Galley"]);
+		assert_eq!(grid.tripped.len(), 1);
+		assert_eq!(loaded.bevy.world.get::<Description>(grid.tripped[0]).map(|x| x.name.clone()), Some("lamp".to_string()));
+	}
+	#[test]
+	fn seal_objectives_survive_a_save() {
+		let mut eng = test_engine();
+		let hatch = eng.bevy.world.spawn((Description::new().name("hatch"), Openable::new(true, "/", "+"))).id();
+		eng.bevy.world.resource_mut::<Objectives>().add_seal("Seal the breach", vec![hatch]);
+		let loaded = round_trip(&mut eng, "test_seal_objectives");
+		let objectives = loaded.bevy.world.resource::<Objectives>();
+		assert_eq!(objectives.seals.len(), 1);
+		let door = objectives.seals[0].doors[0];
+		assert_eq!(loaded.bevy.world.get::<Description>(door).map(|x| x.name.clone()), Some("hatch".to_string()));
+	}
+	#[test]
+	fn ship_log_survives_a_save() {
+		let mut eng = test_engine();
+		eng.bevy.world.resource_mut::<ShipLog>().record("01:02", "Door forced: Cargo Hold");
+		let loaded = round_trip(&mut eng, "test_ship_log");
+		let log = loaded.bevy.world.resource::<ShipLog>();
+		assert_eq!(log.entries, vec![ShipLogEntry { stamp: "01:02".to_string(), text: "Door forced: Cargo Hold".to_string() }]);
+	}
+	#[test]
+	fn salvage_survives_a_save() {
+		let mut eng = test_engine();
+		eng.bevy.world.resource_mut::<RunState>().salvage = 120;
+		eng.bevy.world.spawn((Description::new().name("circuit board"), Portable::empty(), Salvage { value: 30 }));
+		let mut loaded = round_trip(&mut eng, "test_salvage");
+		assert_eq!(loaded.bevy.world.resource::<RunState>().salvage, 120);
+		let mut s_query = loaded.bevy.world.query::<(&Description, &Salvage)>();
+		let values: Vec<(String, u32)> = s_query.iter(&loaded.bevy.world).map(|x| (x.0.name.clone(), x.1.value)).collect();
+		assert_eq!(values, vec![("circuit board".to_string(), 30)]);
+	}
+	//  ##: Scan history
+	/// Puts a powered PLANQ and a player into the engine, with the player able to see a cargo crate on the next tile
+	fn scan_setup(eng: &mut GameEngine<'_>) -> Entity {
+		eng.bevy.world.resource_mut::<PlanqData>().power_is_on = true;
+		let crate_posn = Position::new(5, 4, 0);
+		let cargo = eng.bevy.world.spawn((Description::new().name("cargo crate"), Body::small(crate_posn, ScreenCell::default()))).id();
+		let mut p_view = Viewshed::new(8);
+		p_view.visible_points.push(Point::new(crate_posn.x, crate_posn.y));
+		let mut p_memory = Memory::new();
+		p_memory.visual.insert(crate_posn, vec![cargo]);
+		player_setup(eng, None, Some(Position::new(4, 4, 0)), (p_view, p_memory));
+		cargo
+	}
+	#[test]
+	fn scanning_something_adds_it_to_the_history() {
+		let mut eng = test_engine();
+		let cargo = scan_setup(&mut eng);
+		let report = eng.run_scan();
+		assert!(report.success);
+		assert!(report.output.iter().any(|x| x.contains("cargo crate")), "{:?}", report);
+		let planq = eng.bevy.world.resource::<PlanqData>();
+		assert_eq!(planq.scan_log.len(), 1);
+		assert_eq!(planq.scan_log[0].entity, cargo);
+		assert_eq!(planq.scan_log[0].posn, Position::new(5, 4, 0));
+	}
+	#[test]
+	fn the_history_outlasts_the_scanned_thing() {
+		let mut eng = test_engine();
+		let cargo = scan_setup(&mut eng);
+		eng.run_scan();
+		let mut p_query = eng.bevy.world.query_filtered::<&mut Viewshed, With<Player>>();
+		p_query.single_mut(&mut eng.bevy.world).visible_points.clear();
+		eng.bevy.world.despawn(cargo);
+		let report = eng.run_scan();
+		assert!(!report.output.iter().any(|x| x.contains("cargo crate")));
+		let history = eng.bevy.world.resource::<PlanqData>().scan_report();
+		assert!(history.iter().any(|x| x.contains("cargo crate") && x.contains(&Position::new(5, 4, 0).to_string())), "{:?}", history);
+	}
+	#[test]
+	fn the_history_survives_a_save() {
+		let mut eng = test_engine();
+		scan_setup(&mut eng);
+		eng.run_scan();
+		let loaded = round_trip(&mut eng, "test_scan_history");
+		let planq = loaded.bevy.world.resource::<PlanqData>();
+		assert_eq!(planq.scan_log.len(), 1);
+		assert_eq!(loaded.bevy.world.get::<Description>(planq.scan_log[0].entity).map(|x| x.name.clone()), Some("cargo crate".to_string()));
+	}
+	//  ##: Terminal size
+	/// Resizes the engine and draws it once into a headless terminal of that size, and returns the text on the screen
+	fn draw_at(eng: &mut GameEngine<'_>, width: u16, height: u16) -> String {
+		eng.resize(width, height);
+		let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("could not make a test terminal");
+		terminal.draw(|frame| eng.render(frame)).expect("could not draw to the test terminal");
+		terminal.backend().buffer().content.iter().map(|x| x.symbol.as_str()).collect()
+	}
+	#[test]
+	fn a_tiny_terminal_only_gets_the_size_notice() {
+		let mut eng = test_engine();
+		let screen = draw_at(&mut eng, 10, 5);
+		assert!(screen.starts_with("Terminal"), "{}", screen);
+		// The layout isn't solved for an area that it can't fit into
+		assert_eq!(eng.ui_grid.camera_main, Rect::default());
+	}
+	#[test]
+	fn the_game_comes_back_once_the_terminal_is_big_enough() {
+		let mut eng = test_engine();
+		draw_at(&mut eng, 10, 5);
+		let screen = draw_at(&mut eng, 80, 24);
+		assert!(!screen.contains("Terminal"), "{}", screen);
+		assert_eq!(eng.term_dims, Rect::new(0, 0, 80, 24));
+		assert!(eng.ui_grid.camera_main.width > 0 && eng.ui_grid.camera_main.height > 0);
+	}
+	//  ##: HUD
+	#[test]
+	fn the_hud_follows_the_engine_into_a_pause() {
+		let mut eng = test_engine();
+		eng.standby = false;
+		eng.prefs.show_hud = true;
+		eng.set_mode(EngineMode::Running);
+		eng.bevy.world.resource_mut::<RunState>().turn_count = 7;
+		let screen = draw_at(&mut eng, 80, 24);
+		assert!(screen.contains(" Running | T:7 "), "{}", screen);
+		eng.pause_toggle();
+		let screen = draw_at(&mut eng, 80, 24);
+		assert!(screen.contains(" Paused | T:7 "), "{}", screen);
+		assert!(!screen.contains("Running"), "{}", screen);
+		// And it's gone altogether once it's been switched off
+		eng.prefs.show_hud = false;
+		let screen = draw_at(&mut eng, 80, 24);
+		assert!(!screen.contains("T:7"), "{}", screen);
+	}
+	//  ##: Menus
+	/// Draws just the given menu into a headless terminal, and returns the text on the screen
+	fn draw_menu(eng: &mut GameEngine<'_>, m_type: MenuType) -> String {
+		let mut terminal = Terminal::new(TestBackend::new(80, 24)).expect("could not make a test terminal");
+		terminal.draw(|frame| {
+			match m_type {
+				MenuType::Main => { eng.render_main_menu(frame); }
+				_ => { eng.render_context_menu(frame); }
+			}
+		}).expect("could not draw to the test terminal");
+		terminal.backend().buffer().content.iter().map(|x| x.symbol.as_str()).collect()
+	}
+	#[test]
+	fn both_menus_follow_the_cursor_preference() {
+		let mut eng = test_engine();
+		eng.menu_main = MenuState::new(vec![MenuItem::item("New Game", "main.new_game".into(), None)]);
+		eng.menu_main.activate();
+		eng.menu_context = MenuState::new(vec![MenuItem::item("Examine", GameEvent::new(GameEventType::PlayerAction(ActionType::Examine), None, None), None)]);
+		eng.menu_context.activate();
+		eng.prefs.hivis_cursor = true;
+		assert!(draw_menu(&mut eng, MenuType::Main).contains("▶ New Game"));
+		assert!(draw_menu(&mut eng, MenuType::Context).contains("▶ Examine"));
+		eng.prefs.hivis_cursor = false;
+		for m_type in [MenuType::Main, MenuType::Context] {
+			assert!(!draw_menu(&mut eng, m_type).contains('▶'), "{:?}", m_type);
+		}
+	}
+	//  ##: Loading
+	#[test]
+	fn cancelling_a_new_game_goes_back_to_the_main_menu() {
+		let mut eng = test_engine();
+		eng.new_game();
+		assert!(eng.loading.is_some());
+		eng.cancel_loading();
+		assert!(eng.loading.is_none());
+		assert!(eng.standby);
+		assert_eq!(eng.mode, EngineMode::Standby);
+		assert_eq!(eng.visible_menu, MenuType::Main);
+		// Nothing from the abandoned build turns up later on
+		eng.poll_loading();
+		assert!(eng.bevy.world.get_resource::<WorldModel>().is_none());
+	}
+	//  ##: PLANQ terminal
+	/// Hands the engine a PLANQ that's in the player's hand and idling, with its screen up or folded away
+	fn terminal_setup(eng: &mut GameEngine<'_>, show_terminal: bool) {
+		eng.set_mode(EngineMode::Running);
+		let mut planq = eng.bevy.world.resource_mut::<PlanqData>();
+		planq.is_carried = true;
+		planq.power_is_on = true;
+		planq.cpu_mode = PlanqCPUMode::Idle;
+		planq.show_terminal = show_terminal;
+	}
+	#[test]
+	fn folding_the_terminal_away_gives_the_camera_its_space() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, true);
+		eng.solve_layout(Rect::new(0, 0, 80, 24));
+		let with_terminal = eng.ui_grid.camera_main;
+		terminal_setup(&mut eng, false);
+		eng.solve_layout(Rect::new(0, 0, 80, 24));
+		assert!(eng.ui_grid.camera_main.width > with_terminal.width, "{:?} vs {:?}", eng.ui_grid.camera_main, with_terminal);
+		assert_eq!(eng.ui_grid.camera_main.width, 80);
+		// The status bars still have somewhere to go, down beside the message log
+		assert_eq!(eng.ui_grid.planq_sidebar.y, eng.ui_grid.msg_world.y);
+		assert!(eng.ui_grid.planq_sidebar.width > 0);
+	}
+	#[test]
+	fn the_t_key_changes_the_layout() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, true);
+		draw_at(&mut eng, 80, 24);
+		let with_terminal = eng.ui_grid.camera_main.width;
+		key_parser(KeyEvent::new(KeyCode::Char('T'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		assert!(!eng.bevy.world.resource::<PlanqData>().show_terminal);
+		draw_at(&mut eng, 80, 24);
+		assert!(eng.ui_grid.camera_main.width > with_terminal);
+	}
+	#[test]
+	fn opening_the_cli_brings_the_terminal_back() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, false);
+		key_parser(KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		assert!(eng.bevy.world.resource::<PlanqData>().show_terminal);
+		assert_eq!(eng.focus, PanelFocus::Planq);
+		eng.solve_layout(Rect::new(0, 0, 80, 24));
+		assert!(eng.ui_grid.camera_main.width < 80);
+	}
+	#[test]
+	fn the_cli_stays_shut_without_a_planq_in_hand() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, false);
+		eng.bevy.world.resource_mut::<PlanqData>().is_carried = false;
+		key_parser(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		assert!(!eng.bevy.world.resource::<PlanqData>().show_terminal);
+		assert_eq!(eng.focus, PanelFocus::World);
+	}
+	//  ##: Panel focus
+	/// Presses the given key with no modifiers, and returns the panel that has the focus afterwards
+	fn press(eng: &mut GameEngine<'_>, code: KeyCode) -> PanelFocus {
+		key_parser(KeyEvent::new(code, KeyModifiers::NONE), eng).expect("the key should be handled");
+		eng.focus
+	}
+	#[test]
+	fn tab_cycles_the_focus_through_every_panel() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, true);
+		assert_eq!(eng.focus, PanelFocus::World);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::Planq);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::Log);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::World);
+	}
+	#[test]
+	fn tab_skips_the_planq_without_a_terminal() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, false);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::Log);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::World);
+		// Nor does the PLANQ get to keep the focus once its terminal has been folded away
+		terminal_setup(&mut eng, true);
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::Planq);
+		eng.bevy.world.resource_mut::<PlanqData>().show_terminal = false;
+		assert_eq!(press(&mut eng, KeyCode::Tab), PanelFocus::Log);
+	}
+	#[test]
+	fn the_focused_panel_takes_the_scrolling_keys() {
+		let mut eng = test_engine();
+		terminal_setup(&mut eng, true);
+		press(&mut eng, KeyCode::Tab);
+		press(&mut eng, KeyCode::Up);
+		press(&mut eng, KeyCode::Up);
+		assert_eq!((eng.planq_scroll, eng.log_scroll), (2, 0));
+		press(&mut eng, KeyCode::Tab);
+		press(&mut eng, KeyCode::PageUp);
+		assert_eq!((eng.planq_scroll, eng.log_scroll), (2, PAGE_SCROLL_LINES));
+		// Esc snaps the panel back to its latest lines, and hands the keys back to the world
+		assert_eq!(press(&mut eng, KeyCode::Esc), PanelFocus::World);
+		assert_eq!(eng.log_scroll, 0);
+	}
+	//  ##: PLANQ commands
+	/// Powers up the PLANQ and plugs its access jack into a new access port, which holds the given files
+	fn jack_setup(eng: &mut GameEngine<'_>, files: Vec<DataFile>) -> Entity {
+		let mut p_device = Device::new(0);
+		p_device.power_on();
+		let port = eng.bevy.world.spawn((
+			Description::new().name("access port"),
+			Body::small(Position::new(3, 4, 0), ScreenCell::default()),
+			AccessPort { },
+			DataStore { files },
+			p_device,
+		)).id();
+		let mut planq = eng.bevy.world.resource_mut::<PlanqData>();
+		planq.power_is_on = true;
+		planq.jack_cnxn = port;
+		port
+	}
+	#[test]
+	fn help_lists_every_command() {
+		let mut eng = test_engine();
+		let result = eng.run_planq_cmd(PlanqCmd::Help);
+		assert!(result.success);
+		assert_eq!(result.output.len(), PlanqCmd::iter().count() + 1);
+	}
+	#[test]
+	fn no_operation_succeeds_quietly() {
+		let mut eng = test_engine();
+		assert_eq!(eng.run_planq_cmd(PlanqCmd::NoOperation), PlanqCmdResult::ok(Vec::new()));
+	}
+	#[test]
+	fn parse_errors_are_failures() {
+		let mut eng = test_engine();
+		let result = eng.run_planq_cmd(handler::planq_parser("msgs banana"));
+		assert_eq!(result, PlanqCmdResult::fail(vec!["Not a message number: banana".to_string()]));
+	}
+	#[test]
+	fn unfinished_commands_fail_instead_of_panicking() {
+		let mut eng = test_engine();
+		for cmd in [PlanqCmd::Shutdown, PlanqCmd::Reboot, PlanqCmd::Connect("port".to_string()), PlanqCmd::Disconnect] {
+			let result = eng.run_planq_cmd(cmd);
+			assert!(!result.success);
+			assert!(!result.output.is_empty());
+		}
+	}
+	#[test]
+	fn marks_and_goto_report_their_bookmarks() {
+		let mut eng = test_engine();
+		let result = eng.run_planq_cmd(PlanqCmd::Mark("galley".to_string()));
+		assert!(result.success, "{:?}", result);
+		let result = eng.run_planq_cmd(PlanqCmd::Marks);
+		assert!(result.success);
+		assert!(result.output.iter().any(|x| x.contains("galley")), "{:?}", result);
+		assert!(eng.run_planq_cmd(PlanqCmd::Goto(0)).success);
+		assert!(!eng.run_planq_cmd(PlanqCmd::Goto(7)).success);
+	}
+	/// Lays out the corridor map with the player standing at its west end, and a note left further along
+	fn notes_setup(eng: &mut GameEngine<'_>) {
+		eng.set_mode(EngineMode::Running);
+		player_setup(eng, Some(TestMap::new(CORRIDOR_WITH_DOOR)), None, ());
+		eng.bevy.world.resource_mut::<MapNotes>().set(Position::new(6, 1, 0), "loose panel");
+	}
+	#[test]
+	fn travelling_to_a_note_shows_the_route_first() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		eng.prefs.preview_travel = true;
+		let result = eng.run_planq_cmd(PlanqCmd::Notes(Some(0)));
+		assert!(result.success, "{:?}", result);
+		assert!(result.output[0].starts_with("Travel to the note \"loose panel\""), "{:?}", result);
+		let marks = eng.bevy.world.resource::<Bookmarks>();
+		assert_eq!(marks.destination, None);
+		assert!(marks.pending.is_some());
+		assert!(!marks.preview.is_empty());
+		key_parser(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		let marks = eng.bevy.world.resource::<Bookmarks>();
+		assert_eq!(marks.destination, Some(Position::new(6, 1, 0)));
+		assert!(marks.preview.is_empty());
+	}
+	#[test]
+	fn a_note_route_can_be_turned_down() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		eng.prefs.preview_travel = true;
+		assert!(eng.run_planq_cmd(PlanqCmd::Notes(Some(0))).success);
+		key_parser(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		let marks = eng.bevy.world.resource::<Bookmarks>();
+		assert_eq!(marks.destination, None);
+		assert_eq!(marks.pending, None);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("You decide to stay put."));
+	}
+	#[test]
+	fn without_the_preview_a_note_is_travelled_to_at_once() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		eng.prefs.preview_travel = false;
+		let result = eng.run_planq_cmd(PlanqCmd::Notes(Some(0)));
+		assert_eq!(result, PlanqCmdResult::ok(vec!["Travelling to the note \"loose panel\"".to_string()]));
+		assert_eq!(eng.bevy.world.resource::<Bookmarks>().destination, Some(Position::new(6, 1, 0)));
+		assert!(!eng.run_planq_cmd(PlanqCmd::Notes(Some(3))).success);
+	}
+	#[test]
+	fn the_minimap_shows_the_notes_legend() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		terminal_setup(&mut eng, true);
+		assert!(eng.run_planq_cmd(PlanqCmd::Map).success);
+		assert_eq!(eng.bevy.world.resource::<PlanqData>().current_view(), PlanqView::Minimap);
+		let screen = draw_at(&mut eng, 80, 24);
+		assert!(screen.contains("MAP"), "{}", screen);
+		assert!(screen.contains("0  0: loose panel"), "{}", screen);
+		// Escape closes the map and hands the keys back to the world
+		key_parser(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		assert_eq!(eng.bevy.world.resource::<PlanqData>().current_view(), PlanqView::Terminal);
+		assert_eq!(eng.focus, PanelFocus::World);
+	}
+	#[test]
+	fn analyze_fails_without_anything_to_analyze() {
+		let mut eng = test_engine();
+		player_setup(&mut eng, None, Some(Position::new(4, 4, 0)), ());
+		let result = eng.run_planq_cmd(PlanqCmd::Analyze(String::new()));
+		assert_eq!(result, PlanqCmdResult::fail(vec!["No unidentified items to analyze.".to_string()]));
+	}
+	/// Gives the player an unlabeled data chip that's really a security chip
+	fn chip_setup(eng: &mut GameEngine<'_>) -> Entity {
+		let player = player_setup(eng, None, Some(Position::new(4, 4, 0)), ());
+		eng.bevy.world.spawn((
+			Description::new().name("unlabeled data chip"),
+			Unidentified::new("security chip", Description::new().name("security chip")),
+			Portable::new(player),
+		)).id()
+	}
+	#[test]
+	fn analysis_runs_as_a_planq_process() {
+		let mut eng = test_engine();
+		let chip = chip_setup(&mut eng);
+		let result = eng.run_planq_cmd(PlanqCmd::Analyze("chip".to_string()));
+		assert_eq!(result, PlanqCmdResult::ok(vec!["Analyzing the unlabeled data chip...".to_string()]));
+		let process = *eng.bevy.world.resource::<PlanqData>().proc_table.last().expect("the analysis should be on the process table");
+		let proc = eng.bevy.world.get::<PlanqProcess>(process).expect("the analysis should be a PlanqProcess");
+		assert_eq!(proc.outcome.etype, PlanqEventType::Analyze(chip));
+		assert_eq!(proc.timer.duration(), std::time::Duration::from_secs(ANALYSIS_DURATION));
+	}
+	#[test]
+	fn identification_survives_a_save() {
+		let mut eng = test_engine();
+		let chip = chip_setup(&mut eng);
+		eng.bevy.world.spawn((Description::new().name("wrench"), Body::small(Position::new(1, 1, 0), ScreenCell::default())));
+		eng.bevy.world.spawn(PlanqProcess::new().time(ANALYSIS_DURATION).event(PlanqEvent::new(PlanqEventType::Analyze(chip))));
+		let mut loaded = round_trip(&mut eng, "test_identification");
+		let mut i_query = loaded.bevy.world.query::<(Entity, &Description, &Unidentified)>();
+		let found: Vec<(Entity, String, String)> = i_query.iter(&loaded.bevy.world).map(|x| (x.0, x.1.name.clone(), x.2.true_desc.name.clone())).collect();
+		assert_eq!(found.len(), 1);
+		assert_eq!((found[0].1.as_str(), found[0].2.as_str()), ("unlabeled data chip", "security chip"));
+		// The analysis that was under way still points at the same chip
+		let mut t_query = loaded.bevy.world.query::<&PlanqProcess>();
+		let targets: Vec<PlanqEventType> = t_query.iter(&loaded.bevy.world).map(|x| x.outcome.etype).collect();
+		assert!(targets.contains(&PlanqEventType::Analyze(found[0].0)), "{:?}", targets);
+	}
+	#[test]
+	fn scan_fails_without_power() {
+		let mut eng = test_engine();
+		scan_setup(&mut eng);
+		eng.bevy.world.resource_mut::<PlanqData>().power_is_on = false;
+		let result = eng.run_planq_cmd(PlanqCmd::Scan);
+		assert_eq!(result, PlanqCmdResult::fail(vec!["The PLANQ has no power.".to_string()]));
+	}
+	#[test]
+	fn transfers_fail_without_a_connection() {
+		let mut eng = test_engine();
+		eng.bevy.world.resource_mut::<PlanqData>().power_is_on = true;
+		for cmd in [PlanqCmd::Download, PlanqCmd::Upload] {
+			let result = eng.run_planq_cmd(cmd);
+			assert_eq!(result, PlanqCmdResult::fail(vec!["Transfers need a connection to an access port.".to_string()]));
+		}
+	}
+	#[test]
+	fn transfers_work_over_a_connection() {
+		let mut eng = test_engine();
+		jack_setup(&mut eng, vec![DataFile::new("crew.log", DataKind::Log, 0, "All hands to the galley.")]);
+		let result = eng.run_planq_cmd(PlanqCmd::Download);
+		assert!(result.success, "{:?}", result);
+		assert_eq!(result.output[0], "Downloaded crew.log");
+		let result = eng.run_planq_cmd(PlanqCmd::Upload);
+		assert!(result.success, "{:?}", result);
+		assert_eq!(result.output[0], "Uploaded 1 file(s)");
+	}
+	#[test]
+	fn upload_fails_with_nothing_to_send() {
+		let mut eng = test_engine();
+		jack_setup(&mut eng, Vec::new());
+		assert_eq!(eng.run_planq_cmd(PlanqCmd::Upload), PlanqCmdResult::fail(vec!["No data to upload.".to_string()]));
+	}
+	#[test]
+	fn downloading_a_map_fragment_reveals_its_deck() {
+		let mut eng = test_engine();
+		eng.bevy.world.insert_resource(TestMap::new(CORRIDOR_WITH_DOOR).model);
+		assert!(eng.bevy.world.resource::<WorldModel>().levels[0].revealed_tiles.iter().all(|x| !x));
+		jack_setup(&mut eng, vec![DataFile::new("deck_0.map", DataKind::MapFragment, 0, "")]);
+		let result = eng.run_planq_cmd(PlanqCmd::Download);
+		assert_eq!(result, PlanqCmdResult::ok(vec!["Downloaded deck_0.map".to_string(), "  Deck 0 plans added to the map".to_string()]));
+		assert!(eng.bevy.world.resource::<WorldModel>().levels[0].revealed_tiles.iter().all(|x| *x));
+		assert!(eng.bevy.world.resource::<PlanqData>().storage.iter().any(|x| x.kind == DataKind::MapFragment));
+		// The same files aren't downloaded twice
+		assert_eq!(eng.run_planq_cmd(PlanqCmd::Download), PlanqCmdResult::ok(vec!["No new data on this port.".to_string()]));
+	}
+	#[test]
+	fn an_uploaded_keycode_opens_the_shipnet_locks() {
+		let mut eng = test_engine();
+		let lock = Lockable { is_locked: true, key_id: 77, remote: true, ..Lockable::default() };
+		let door = eng.bevy.world.spawn((Description::new().name("Brig Door"), Body::small(Position::new(5, 4, 0), ScreenCell::default()), lock)).id();
+		jack_setup(&mut eng, Vec::new());
+		let result = eng.run_planq_cmd(PlanqCmd::Download);
+		assert!(result.output.contains(&"Downloaded brig_door.key".to_string()), "{:?}", result);
+		assert!(eng.bevy.world.get::<Lockable>(door).unwrap().is_locked);
+		let result = eng.run_planq_cmd(PlanqCmd::Upload);
+		assert!(result.output.contains(&"The Brig Door unlocks.".to_string()), "{:?}", result);
+		assert!(!eng.bevy.world.get::<Lockable>(door).unwrap().is_locked);
+	}
+	#[test]
+	fn a_switched_off_port_refuses_transfers() {
+		let mut eng = test_engine();
+		let port = jack_setup(&mut eng, vec![DataFile::new("crew.log", DataKind::Log, 0, "All hands to the galley.")]);
+		eng.bevy.world.get_mut::<Device>(port).unwrap().power_off();
+		let result = eng.run_planq_cmd(PlanqCmd::Download);
+		assert_eq!(result, PlanqCmdResult::fail(vec!["The access port is not responding.".to_string()]));
+		assert!(eng.bevy.world.resource::<PlanqData>().storage.is_empty());
+	}
+	#[test]
+	fn the_ship_log_needs_a_working_port() {
+		let mut eng = test_engine();
+		eng.bevy.world.resource_mut::<PlanqData>().power_is_on = true;
+		assert!(!eng.run_planq_cmd(PlanqCmd::ShipLog(1)).success);
+		let port = jack_setup(&mut eng, Vec::new());
+		eng.bevy.world.resource_mut::<ShipLog>().record("01:02", "Door forced: Cargo Hold");
+		let result = eng.run_planq_cmd(PlanqCmd::ShipLog(1));
+		assert!(result.success, "{:?}", result);
+		assert!(!eng.run_planq_cmd(PlanqCmd::ShipLog(9)).success);
+		eng.bevy.world.get_mut::<Device>(port).unwrap().power_off();
+		let result = eng.run_planq_cmd(PlanqCmd::ShipLog(1));
+		assert_eq!(result, PlanqCmdResult::fail(vec!["The access port is not responding.".to_string()]));
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn diffsaves_fails_on_a_missing_slot() {
+		let eng = test_engine();
+		let result = eng.diff_saves("test_no_such_slot_a", "test_no_such_slot_b");
+		assert!(!result.success);
+		assert!(result.output[0].starts_with("Could not load test_no_such_slot_a"), "{:?}", result);
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn diffsaves_reports_a_single_move() {
+		let mut eng = test_engine();
+		let player = eng.bevy.world.spawn((Description::new().name("player"), Body::small(Position::new(1, 1, 0), ScreenCell::default()))).id();
+		eng.bevy.world.spawn((Description::new().name("wrench"), Body::small(Position::new(3, 1, 0), ScreenCell::default())));
+		let (before, after) = (eng.files.slot_key("test_diff_before"), eng.files.slot_key("test_diff_after"));
+		eng.bevy.world.save(&before).expect("could not save the first test game");
+		eng.bevy.world.get_mut::<Body>(player).unwrap().move_to(Position::new(2, 1, 0));
+		eng.bevy.world.save(&after).expect("could not save the second test game");
+		let result = eng.run_planq_cmd(PlanqCmd::DiffSaves("test_diff_before".to_string(), "test_diff_after".to_string()));
+		let _ = delete_slot(&before);
+		let _ = delete_slot(&after);
+		assert_eq!(result, PlanqCmdResult::ok(vec!["player: 1, 1, 0 -> 2, 1, 0".to_string()]));
+	}
+	#[test]
+	fn power_reports_the_grid() {
+		let mut eng = test_engine();
+		assert!(eng.run_planq_cmd(PlanqCmd::Power).success);
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn blackout_is_a_debug_command() {
+		let mut eng = test_engine();
+		assert!(eng.run_planq_cmd(PlanqCmd::Blackout(5)).success);
+		assert_eq!(handler::planq_parser("power blackout 5"), PlanqCmd::Blackout(5));
+		assert_eq!(handler::planq_parser("census"), PlanqCmd::Census);
+	}
+	#[cfg(not(debug_assertions))]
+	#[test]
+	fn debug_commands_are_left_out_of_release_builds() {
+		assert!(matches!(handler::planq_parser("census"), PlanqCmd::Error(_)));
+		assert!(matches!(handler::planq_parser("power blackout 5"), PlanqCmd::Error(_)));
+	}
+	//  ##: Tile summaries
+	/// Lays out the corridor with the player at its west end, seeing only the first few tiles, and a locked door
+	/// partway along; returns the player and the door
+	fn summary_setup(eng: &mut GameEngine<'_>) -> (Entity, Entity) {
+		let mut viewshed = Viewshed::new(8);
+		viewshed.visible_points = (0..5).map(|x| Point::new(x, 1)).collect();
+		let player = player_setup(eng, Some(TestMap::new(CORRIDOR_WITH_DOOR)), None, (viewshed, Memory::new()));
+		let door_posn = Position::new(4, 1, 0);
+		let door = eng.bevy.world.spawn((
+			Description::new().name("door"),
+			Body::small(door_posn, ScreenCell::default()),
+			Openable::default(),
+			Lockable { is_locked: true, ..Lockable::default() },
+		)).id();
+		eng.bevy.world.resource_mut::<WorldModel>().add_contents(&vec![door_posn], 0, door);
+		(player, door)
+	}
+	#[test]
+	fn state_words_are_read_out_in_order() {
+		let stuck_door = Openable { is_stuck: true, ..Openable::default() };
+		let lock = Lockable { is_locked: true, ..Lockable::default() };
+		assert_eq!(state_qualifiers(Some(&stuck_door), Some(&lock)), vec!["closed", "stuck", "locked"]);
+		let open_door = Openable { is_open: true, ..Openable::default() };
+		assert_eq!(state_qualifiers(Some(&open_door), Some(&Lockable::default())), vec!["open"]);
+		assert_eq!(state_qualifiers(None, Some(&lock)), vec!["locked"]);
+		assert!(state_qualifiers(None, None).is_empty());
+	}
+	#[test]
+	fn a_visible_tile_names_its_topmost_thing() {
+		let mut eng = test_engine();
+		summary_setup(&mut eng);
+		assert_eq!(eng.tile_summary(Position::new(4, 1, 0)), "floor: door, closed, locked (visible)");
+		assert_eq!(eng.tile_summary(Position::new(2, 1, 0)), "floor (visible)");
+	}
+	#[test]
+	fn an_unknown_tile_gives_nothing_away() {
+		let mut eng = test_engine();
+		let (_, door) = summary_setup(&mut eng);
+		// The door is far along enough to be out of sight, and the player has never been there
+		let door_posn = Position::new(6, 1, 0);
+		eng.bevy.world.get_mut::<Body>(door).unwrap().move_to(door_posn);
+		eng.bevy.world.resource_mut::<WorldModel>().add_contents(&vec![door_posn], 0, door);
+		assert_eq!(eng.tile_summary(door_posn), "??? (unknown)");
+		assert_eq!(eng.tile_summary(Position::new(40, 40, 0)), "???");
+	}
+	#[test]
+	fn a_remembered_tile_shows_what_was_seen_without_its_state() {
+		let mut eng = test_engine();
+		let (player, door) = summary_setup(&mut eng);
+		let door_posn = Position::new(4, 1, 0);
+		{
+			let mut model = eng.bevy.world.resource_mut::<WorldModel>();
+			let index = model.levels[0].to_index(door_posn.x, door_posn.y);
+			model.levels[0].revealed_tiles[index] = true;
+		}
+		eng.bevy.world.get_mut::<Memory>(player).unwrap().visual.insert(door_posn, vec![door]);
+		eng.bevy.world.get_mut::<Viewshed>(player).unwrap().visible_points.clear();
+		// The door gets unlocked behind the player's back, which they have no way of knowing
+		eng.bevy.world.get_mut::<Lockable>(door).unwrap().is_locked = false;
+		assert_eq!(eng.tile_summary(door_posn), "floor: door (remembered)");
+	}
+	//  ##: Context help
+	/// Spawns a player, a wrench with a long description, and a locker without one, and returns them in that order
+	fn help_setup(eng: &mut GameEngine<'_>) -> (Entity, Entity, Entity) {
+		let player = player_setup(eng, None, Some(Position::new(4, 4, 0)), Description::new().name("player"));
+		let world = &mut eng.bevy.world;
+		let wrench = world.spawn(Description::new().name("wrench").desc("A heavy adjustable wrench, scuffed from use.")).id();
+		let locker = world.spawn(Description::new().name("locker")).id();
+		(player, wrench, locker)
+	}
+	#[test]
+	fn the_help_line_follows_the_highlight() {
+		let mut eng = test_engine();
+		let (player, wrench, locker) = help_setup(&mut eng);
+		eng.visible_menu = MenuType::Context;
+		eng.menu_context = MenuState::new(vec![
+			MenuItem::item("wrench", GameEvent::new(GameEventType::PlayerAction(ActionType::MoveItem), Some(player), Some(wrench)), None),
+			MenuItem::item("Open", GameEvent::new(GameEventType::PlayerAction(ActionType::OpenItem), Some(player), Some(locker)), None),
+		]);
+		// An entry that names its target is described by the target
+		eng.menu_context.activate();
+		assert_eq!(eng.context_help(), Some("A heavy adjustable wrench, scuffed from use.".to_string()));
+		// An entry that names its verb is described by the verb
+		eng.menu_context.down();
+		assert_eq!(eng.context_help(), Some(ActionType::OpenItem.describe().to_string()));
+	}
+	#[test]
+	fn a_group_is_described_by_its_first_entry() {
+		let mut eng = test_engine();
+		let (player, wrench, _) = help_setup(&mut eng);
+		eng.menu_context = MenuState::new(vec![
+			MenuItem::group("wrench", vec![
+				MenuItem::item("Drop", GameEvent::new(GameEventType::PlayerAction(ActionType::DropItem), Some(player), Some(wrench)), None),
+				MenuItem::item("Examine", GameEvent::new(GameEventType::PlayerAction(ActionType::Examine), Some(player), Some(wrench)), None),
+			]),
+		]);
+		eng.menu_context.activate();
+		assert_eq!(eng.context_help(), Some("A heavy adjustable wrench, scuffed from use.".to_string()));
+		// Inside the group, the entries are named for their verbs
+		eng.menu_context.push();
+		assert_eq!(eng.context_help(), Some(ActionType::DropItem.describe().to_string()));
+	}
+	#[test]
+	fn a_target_without_a_description_falls_back_to_the_verb() {
+		let mut eng = test_engine();
+		let (player, _, locker) = help_setup(&mut eng);
+		eng.menu_context = MenuState::new(vec![
+			MenuItem::item("locker", GameEvent::new(GameEventType::PlayerAction(ActionType::OpenItem), Some(player), Some(locker)), None),
+		]);
+		eng.menu_context.activate();
+		assert_eq!(eng.context_help(), Some("Open it up.".to_string()));
+	}
+	//  ##: Line of sight
+	/// Walks down the open context menu from the top, and returns the names of its entries in order
+	fn context_names(eng: &mut GameEngine<'_>) -> Vec<String> {
+		let mut names = Vec::new();
+		eng.menu_context.activate();
+		while let Some(name) = eng.menu_context.highlight().map(|x| x.name().to_string()) {
+			// The highlight stays put on the last entry
+			if names.contains(&name) { break; }
+			names.push(name);
+			eng.menu_context.down();
+		}
+		names
+	}
+	/// Puts the player at 3, 1 in the west room of the two-room map, with a box beside them, a crate on the far side
+	/// of the wall, and a wrench in their pocket; returns the player, the box, the crate, and the wrench
+	fn sight_setup(eng: &mut GameEngine<'_>) -> (Entity, Entity, Entity, Entity) {
+		eng.set_mode(EngineMode::Running);
+		let player = player_setup(eng, Some(TestMap::new(TWO_ROOMS_WITH_DOOR)), Some(Position::new(3, 1, 0)), ());
+		let near_box = eng.bevy.world.spawn((Description::new().name("box"), Body::small(Position::new(2, 2, 0), ScreenCell::default()))).id();
+		let crate_enty = eng.bevy.world.spawn((Description::new().name("crate"), Body::small(Position::new(5, 1, 0), ScreenCell::default()))).id();
+		let wrench = eng.bevy.world.spawn((Description::new().name("wrench"), Portable::new(player))).id();
+		(player, near_box, crate_enty, wrench)
+	}
+	#[test]
+	fn nothing_can_be_affected_through_a_wall() {
+		let mut eng = test_engine();
+		let (player, near_box, crate_enty, wrench) = sight_setup(&mut eng);
+		let world = &eng.bevy.world;
+		assert!(can_affect(player, near_box, EXAMINE_REACH, world));
+		// The crate is close enough, but the wall is in the way
+		assert!(!can_affect(player, crate_enty, EXAMINE_REACH, world));
+		// Whatever the player is holding can't be blocked by anything
+		assert!(can_affect(player, wrench, EXAMINE_REACH, world));
+	}
+	#[test]
+	fn the_examine_menu_leaves_out_what_is_behind_a_wall() {
+		let mut eng = test_engine();
+		sight_setup(&mut eng);
+		key_parser(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		assert_eq!(eng.visible_menu, MenuType::Context);
+		let names = context_names(&mut eng);
+		assert!(names.contains(&"box".to_string()), "{:?}", names);
+		assert!(!names.contains(&"crate".to_string()), "{:?}", names);
+	}
+	#[test]
+	fn examining_through_a_wall_is_rechecked() {
+		let mut eng = test_engine();
+		let (player, near_box, crate_enty, _) = sight_setup(&mut eng);
+		let looking_at = |target| GameEvent::new(GameEventType::PlayerAction(ActionType::Examine), Some(player), Some(target));
+		assert_eq!(eng.recheck_target(&looking_at(near_box)), Ok(()));
+		assert!(eng.recheck_target(&looking_at(crate_enty)).is_err());
+	}
+	#[test]
+	fn a_closed_locker_hides_what_is_inside_it() {
+		let mut eng = test_engine();
+		let (player, _, _, _) = sight_setup(&mut eng);
+		let locker = eng.bevy.world.spawn((Description::new().name("locker"), Body::small(Position::new(3, 2, 0), ScreenCell::default()), Container::default(), Openable::new(false, "▯", "▮"))).id();
+		let spanner = eng.bevy.world.spawn((Description::new().name("spanner"), Body::small(Position::new(3, 2, 0), ScreenCell::default()), Portable::new(locker), IsCarried::default())).id();
+		let examine_names = |eng: &mut GameEngine<'_>| {
+			eng.visible_menu = MenuType::None;
+			key_parser(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE), eng).expect("the key should be handled");
+			context_names(eng)
+		};
+		let names = examine_names(&mut eng);
+		assert!(names.contains(&"locker".to_string()), "{:?}", names);
+		assert!(!names.contains(&"spanner".to_string()), "{:?}", names);
+		// A menu that was built before the locker was shut doesn't get to look inside it either
+		let looking_at = GameEvent::new(GameEventType::PlayerAction(ActionType::Examine), Some(player), Some(spanner));
+		assert!(eng.recheck_target(&looking_at).is_err());
+		eng.bevy.world.get_mut::<Openable>(locker).unwrap().is_open = true;
+		let names = examine_names(&mut eng);
+		assert!(names.contains(&"spanner".to_string()), "{:?}", names);
+		assert_eq!(eng.recheck_target(&looking_at), Ok(()));
+	}
+	#[test]
+	fn lazy_loot_is_made_inside_its_container() {
+		let mut eng = test_engine();
+		let locker = eng.bevy.world.spawn((Description::new().name("locker"), Body::small(Position::new(3, 2, 0), ScreenCell::default()), Container::default())).id();
+		// The salvage bin is the one template that the ItemBuilder always has on hand
+		eng.bevy.world.resource_mut::<LootRequests>().pending.push((SALVAGE_BIN_ITEM.to_string(), locker));
+		eng.stock_lazy_loot();
+		assert!(eng.bevy.world.resource::<LootRequests>().pending.is_empty());
+		let mut query = eng.bevy.world.query::<(&Description, &Portable)>();
+		let stocked: Vec<String> = query.iter(&eng.bevy.world).filter(|x| x.1.carrier == locker).map(|x| x.0.name.clone()).collect();
+		assert_eq!(stocked, vec![SALVAGE_BIN_ITEM.to_string()]);
+	}
+	//  ##: Examining terrain
+	#[test]
+	fn a_nearby_stairway_can_be_examined() {
+		let mut eng = test_engine();
+		eng.set_mode(EngineMode::Running);
+		let testmap = TestMap::from_decks(&TWO_DECKS_WITH_STAIRS).with_room("engine room", Position::new(1, 1, 0), Position::new(5, 1, 0));
+		let mut viewshed = Viewshed::new(8);
+		viewshed.visible_points = (0..7).map(|x| Point::new(x, 1)).collect();
+		player_setup(&mut eng, Some(testmap), Some(Position::new(3, 1, 0)), (viewshed, Memory::new()));
+		key_parser(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		// The bare floor around the player isn't worth offering, but the stairway two tiles off is
+		let names = context_names(&mut eng);
+		assert_eq!(names, vec!["Terrain: floor (visible)".to_string(), "Terrain: stairway (visible)".to_string()]);
+		assert_eq!(eng.bevy.world.resource::<WorldModel>().describe_terrain(Position::new(5, 1, 0)), "stairway in engine room");
+	}
+	//  ##: Busy hands
+	/// Presses the right arrow key, and returns the moves that it sent
+	fn step_right(eng: &mut GameEngine<'_>) -> usize {
+		key_parser(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE), eng).expect("the key should be handled");
+		let mut events = eng.bevy.world.resource_mut::<Events<GameEvent>>();
+		let moves = events.get_reader().iter(&events).filter(|x| x.etype == GameEventType::PlayerAction(ActionType::MoveTo(Direction::E))).count();
+		events.clear();
+		moves
+	}
+	#[test]
+	fn a_booting_planq_keeps_the_player_from_moving() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		eng.prefs.planq_busy_hands = true;
+		{
+			let mut planq = eng.bevy.world.resource_mut::<PlanqData>();
+			planq.is_carried = true;
+			planq.cpu_mode = PlanqCPUMode::Startup;
+		}
+		assert_eq!(step_right(&mut eng), 0);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("You need both hands to steady the PLANQ while it boots up."));
+		// Once it's booted, the player's hands are free again
+		eng.bevy.world.resource_mut::<PlanqData>().cpu_mode = PlanqCPUMode::Idle;
+		assert_eq!(step_right(&mut eng), 1);
+	}
+	#[test]
+	fn busy_hands_are_off_unless_asked_for() {
+		let mut eng = test_engine();
+		notes_setup(&mut eng);
+		{
+			let mut planq = eng.bevy.world.resource_mut::<PlanqData>();
+			planq.is_carried = true;
+			planq.cpu_mode = PlanqCPUMode::Startup;
+		}
+		assert_eq!(step_right(&mut eng), 1);
+	}
+	//  ##: Locked doors
+	/// Lays out the corridor with the player at its west end, and a locked door right beside them
+	fn locked_door_setup(eng: &mut GameEngine<'_>) -> Entity {
+		eng.set_mode(EngineMode::Running);
+		player_setup(eng, Some(TestMap::new(CORRIDOR_WITH_DOOR)), None, ());
+		let start = *eng.bevy.world.resource::<Position>();
+		eng.bevy.world.spawn((
+			Description::new().name("door"),
+			Body::small(start + (1, 0, 0), ScreenCell::default()),
+			Openable::new(false, "'", "+"),
+			Lockable { is_locked: true, ..Lockable::default() },
+			Obstructive::default(),
+		)).id()
+	}
+	#[test]
+	fn o_tries_a_locked_door_instead_of_finding_nothing() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		key_parser(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		// The attempt goes through to the openable_system, which is what tells the player that the door is locked
+		let events = eng.bevy.world.resource::<Events<GameEvent>>();
+		let attempts: Vec<Option<Entity>> = events.get_reader().iter(events)
+			.filter(|x| x.etype == GameEventType::PlayerAction(ActionType::OpenItem))
+			.map(|x| x.context.map(|c| c.object))
+			.collect();
+		assert_eq!(attempts, vec![Some(door)]);
+		assert_ne!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("There's nothing nearby to open."));
+	}
+	#[test]
+	fn o_finds_nothing_when_there_is_nothing_to_open() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		eng.bevy.world.despawn(door);
+		key_parser(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE), &mut eng).expect("the key should be handled");
+		let events = eng.bevy.world.resource::<Events<GameEvent>>();
+		assert_eq!(events.get_reader().iter(events).filter(|x| x.etype == GameEventType::PlayerAction(ActionType::OpenItem)).count(), 0);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("There's nothing nearby to open."));
+	}
+	//  ##: Target rechecks
+	#[test]
+	fn a_target_still_in_reach_passes_the_recheck() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		let player = eng.verify_single_player().expect("there should be one player");
+		let unlocking = GameEvent::new(GameEventType::PlayerAction(ActionType::UnlockItem), Some(player), Some(door));
+		assert_eq!(eng.recheck_target(&unlocking), Ok(()));
+		// Anything that the player is holding is always in reach, wherever its Body was left
+		let key = eng.bevy.world.spawn((Description::new().name("key"), Body::small(Position::new(7, 1, 0), ScreenCell::default()), Portable::new(player))).id();
+		assert_eq!(eng.recheck_target(&GameEvent::new(GameEventType::PlayerAction(ActionType::DropItem), Some(player), Some(key))), Ok(()));
+	}
+	#[test]
+	fn a_target_that_moved_away_fails_the_recheck() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		let player = eng.verify_single_player().expect("there should be one player");
+		let unlocking = GameEvent::new(GameEventType::PlayerAction(ActionType::UnlockItem), Some(player), Some(door));
+		let start = *eng.bevy.world.resource::<Position>();
+		eng.bevy.world.get_mut::<Body>(door).unwrap().move_to(start + (4, 0, 0));
+		assert_eq!(eng.recheck_target(&unlocking), Err("It's no longer there."));
+	}
+	#[test]
+	fn a_stale_target_fails_the_recheck() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		let player = eng.verify_single_player().expect("there should be one player");
+		let unlocking = GameEvent::new(GameEventType::PlayerAction(ActionType::UnlockItem), Some(player), Some(door));
+		// Losing the part that the action works on is as good as being gone
+		eng.bevy.world.entity_mut(door).remove::<Lockable>();
+		assert_eq!(eng.recheck_target(&unlocking), Err("It's no longer there."));
+		eng.bevy.world.despawn(door);
+		assert_eq!(eng.recheck_target(&unlocking), Err("It's no longer there."));
+	}
+	//  ##: Closing everything
+	/// Spawns a door with the given name and state at the given offset from the player in the locked_door_setup
+	fn door_near(eng: &mut GameEngine<'_>, name: &str, offset: (i32, i32, i32), is_open: bool) -> Entity {
+		let start = *eng.bevy.world.resource::<Position>();
+		eng.bevy.world.spawn((Description::new().name(name), Body::small(start + offset, ScreenCell::default()), Openable::new(is_open, "'", "+"))).id()
+	}
+	#[test]
+	fn alt_c_closes_every_open_door_in_reach() {
+		let mut eng = test_engine();
+		// The locked door beside the player is shut already, so it has to be left alone
+		locked_door_setup(&mut eng);
+		let south = door_near(&mut eng, "south door", (0, 1, 0), true);
+		let corner = door_near(&mut eng, "corner door", (1, 1, 0), true);
+		// Nor does a door that's down the hall get an event
+		door_near(&mut eng, "far door", (4, 0, 0), true);
+		key_parser(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT), &mut eng).expect("the key should be handled");
+		let events = eng.bevy.world.resource::<Events<GameEvent>>();
+		let mut closing: Vec<Entity> = events.get_reader().iter(events)
+			.filter(|x| x.etype == GameEventType::PlayerAction(ActionType::CloseItem))
+			.filter_map(|x| x.context.map(|c| c.object))
+			.collect();
+		closing.sort();
+		let mut expected = vec![south, corner];
+		expected.sort();
+		assert_eq!(closing, expected);
+	}
+	//  ##: Targeted menus
+	/// Presses the given key with a fresh menu slate, and lists what the context menu offered as (label, action, target)
+	fn menu_for(eng: &mut GameEngine<'_>, key: char) -> Vec<(String, GameEventType, Option<Entity>)> {
+		eng.visible_menu = MenuType::None;
+		eng.menu_context = MenuState::new(Vec::new());
+		key_parser(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE), eng).expect("the key should be handled");
+		let mut entries: Vec<(String, GameEventType, Option<Entity>)> = Vec::new();
+		eng.menu_context.activate();
+		while let Some(item) = eng.menu_context.highlight() {
+			let event = item.help_data().copied().unwrap_or_default();
+			let entry = (item.name().to_string(), event.etype, event.context.map(|x| x.object));
+			// The highlight stays put on the last entry
+			if entries.contains(&entry) { break; }
+			entries.push(entry);
+			eng.menu_context.down();
+		}
+		entries
+	}
+	#[test]
+	fn each_targeted_key_offers_its_own_action() {
+		let mut eng = test_engine();
+		let door = locked_door_setup(&mut eng);
+		let hatch = door_near(&mut eng, "hatch", (0, 1, 0), true);
+		let verb = |action| GameEventType::PlayerAction(action);
+		assert_eq!(menu_for(&mut eng, 'U'), vec![("door".to_string(), verb(ActionType::UnlockItem), Some(door))]);
+		assert_eq!(menu_for(&mut eng, 'K'), vec![("door".to_string(), verb(ActionType::HackItem), Some(door))]);
+		assert_eq!(menu_for(&mut eng, 'c'), vec![("hatch".to_string(), verb(ActionType::CloseItem), Some(hatch))]);
+		// Once the door's unlocked, it moves over to the other menus
+		eng.bevy.world.get_mut::<Lockable>(door).unwrap().is_locked = false;
+		assert_eq!(menu_for(&mut eng, 'L'), vec![("door".to_string(), verb(ActionType::LockItem), Some(door))]);
+		assert_eq!(menu_for(&mut eng, 'o'), vec![("door".to_string(), verb(ActionType::OpenItem), Some(door))]);
+	}
+	#[test]
+	fn an_empty_menu_is_reported_instead_of_shown() {
+		let mut eng = test_engine();
+		locked_door_setup(&mut eng);
+		assert!(menu_for(&mut eng, 'L').is_empty());
+		assert_eq!(eng.visible_menu, MenuType::None);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("There's nothing to lock nearby."));
+		assert!(menu_for(&mut eng, 'c').is_empty());
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("There's nothing nearby to close."));
+	}
+	//  ##: Extended reach
+	#[test]
+	fn a_carried_tool_lets_the_player_reach_farther() {
+		let mut eng = test_engine();
+		locked_door_setup(&mut eng);
+		let player = eng.verify_single_player().expect("there should be one player");
+		let far = door_near(&mut eng, "far hatch", (3, 0, 0), true);
+		let closing = GameEvent::new(GameEventType::PlayerAction(ActionType::CloseItem), Some(player), Some(far));
+		assert!(menu_for(&mut eng, 'c').is_empty());
+		assert_eq!(eng.recheck_target(&closing), Err("It's no longer there."));
+		let hook = eng.bevy.world.spawn((Description::new().name("boathook"), ReachProfile { extra: 2 }, Portable::new(player))).id();
+		assert_eq!(menu_for(&mut eng, 'c'), vec![("far hatch".to_string(), GameEventType::PlayerAction(ActionType::CloseItem), Some(far))]);
+		assert_eq!(eng.recheck_target(&closing), Ok(()));
+		// Putting the tool down takes the extra reach away with it
+		eng.bevy.world.get_mut::<Portable>(hook).unwrap().carrier = Entity::PLACEHOLDER;
+		assert_eq!(eng.recheck_target(&closing), Err("It's no longer there."));
+	}
+	//  ##: Hazard prompts
+	/// Turns the tile just east of the player in the locked_door_setup into vacuum, with the door taken out of the way
+	/// Returns the spot that was opened up to space
+	fn vacuum_setup(eng: &mut GameEngine<'_>, prompt: HazardPrompt) -> Position {
+		let door = locked_door_setup(eng);
+		eng.bevy.world.despawn(door);
+		eng.prefs.hazard_prompt = prompt;
+		let target = *eng.bevy.world.resource::<Position>() + (1, 0, 0);
+		let mut model = eng.bevy.world.resource_mut::<WorldModel>();
+		let index = model.levels[0].to_index(target.x, target.y);
+		model.levels[0].tiles[index] = Tile::new_vacuum();
+		target
+	}
+	/// Presses the given key, and returns how many eastward steps the player has sent off so far
+	fn step_key(eng: &mut GameEngine<'_>, key: char) -> usize {
+		key_parser(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE), eng).expect("the key should be handled");
+		let events = eng.bevy.world.resource::<Events<GameEvent>>();
+		events.get_reader().iter(events).filter(|x| x.etype == GameEventType::PlayerAction(ActionType::MoveTo(Direction::E))).count()
+	}
+	#[test]
+	fn a_step_into_vacuum_waits_for_a_yes() {
+		let mut eng = test_engine();
+		let target = vacuum_setup(&mut eng, HazardPrompt::FirstTime);
+		assert_eq!(step_key(&mut eng, 'l'), 0);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("Really step out into the vacuum? (y/n)"));
+		// Anything but a yes calls the step off
+		assert_eq!(step_key(&mut eng, 'n'), 0);
+		assert_eq!(eng.bevy.world.resource::<MessageLog>().last_text("world"), Some("You stay where you are."));
+		assert_eq!(eng.pending_move, None);
+		assert_eq!(step_key(&mut eng, 'l'), 0);
+		assert_eq!(step_key(&mut eng, 'y'), 1);
+		assert_eq!(eng.hazards_confirmed, vec![(Hazard::Vacuum, target)]);
+		// Having agreed to it once, the player isn't asked about the same spot again
+		assert_eq!(step_key(&mut eng, 'l'), 2);
+	}
+	#[test]
+	fn the_prompt_setting_decides_how_often_to_ask() {
+		let mut eng = test_engine();
+		vacuum_setup(&mut eng, HazardPrompt::Always);
+		step_key(&mut eng, 'l');
+		assert_eq!(step_key(&mut eng, 'y'), 1);
+		assert_eq!(step_key(&mut eng, 'l'), 1);
+		assert!(eng.pending_move.is_some());
+		step_key(&mut eng, 'n');
+		eng.prefs.hazard_prompt = HazardPrompt::Never;
+		assert_eq!(step_key(&mut eng, 'l'), 2);
+		assert_eq!(eng.pending_move, None);
+	}
+	//  ##: Death summary
+	#[test]
+	fn the_death_summary_reports_the_cause_and_the_turns() {
+		let mut eng = test_engine();
+		{
+			let mut run_state = eng.bevy.world.resource_mut::<RunState>();
+			run_state.turn_count = 57;
+			run_state.end_game(EngineMode::BadEnd, "crushed by a bulkhead");
+		}
+		eng.bevy.world.resource_mut::<MessageLog>().tell_player("The bulkhead slams shut.");
+		let summary = eng.summarize_run(false);
+		assert_eq!((summary.cause.as_str(), summary.turns), ("crushed by a bulkhead", 57));
+		assert_eq!(summary.last_words.last().map(|x| x.as_str()), Some("The bulkhead slams shut."));
+		let report = summary.report();
+		assert!(report.contains(&"Cause of death: crushed by a bulkhead".to_string()), "{:?}", report);
+		assert!(report.contains(&"Turns survived: 57".to_string()), "{:?}", report);
+	}
+	//  ##: Player count
+	#[test]
+	fn anything_but_one_player_is_a_fault() {
+		let mut eng = test_engine();
+		assert_eq!(eng.verify_single_player(), Err(0));
+		let player = eng.bevy.world.spawn((Player { }, Body::small(Position::new(1, 1, 0), ScreenCell::default()))).id();
+		assert_eq!(eng.verify_single_player(), Ok(player));
+		// A second player, ie from a bad load or a double spawn, is caught just the same as a missing one
+		eng.bevy.world.spawn((Player { }, Body::small(Position::new(2, 1, 0), ScreenCell::default())));
+		assert_eq!(eng.verify_single_player(), Err(2));
+	}
+	//  ##: Scenarios
+	/// Lays out the two rooms with the player in the west one, names the east one, and starts the given scenario
+	fn scenario_setup(eng: &mut GameEngine<'_>, east_room: &str, kind: ScenarioKind) -> Entity {
+		let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR)
+			.with_room("west room", Position::new(1, 1, 0), Position::new(3, 3, 0))
+			.with_room(east_room, Position::new(5, 1, 0), Position::new(9, 3, 0));
+		let player = player_setup(eng, Some(testmap), None, ());
+		eng.scenario_kind = kind;
+		eng.scenario = kind.build();
+		eng.scenario.setup(&mut eng.bevy.world);
+		player
+	}
+	/// Returns how the game ended, if the scenario has ended it
+	fn outcome(eng: &GameEngine<'_>) -> Option<(EngineMode, String)> {
+		let run_state = eng.bevy.world.resource::<RunState>();
+		if !run_state.mode_changed { return None; }
+		Some((run_state.mode, run_state.end_cause.clone().unwrap_or_default()))
+	}
+	#[test]
+	fn the_escape_is_won_by_carrying_the_planq_out() {
+		let mut eng = test_engine();
+		let player = scenario_setup(&mut eng, "port airlock", ScenarioKind::Escape);
+		let planq = eng.bevy.world.spawn((Description::new().name("PLANQ"), Body::small(Position::new(3, 1, 0), ScreenCell::default()), Planq::new(), Portable::empty())).id();
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), None);
+		// Reaching the airlock without the PLANQ isn't enough
+		eng.bevy.world.get_mut::<Body>(player).unwrap().move_to(Position::new(6, 2, 0));
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), None);
+		eng.bevy.world.get_mut::<Body>(player).unwrap().move_to(Position::new(3, 2, 0));
+		eng.bevy.world.get_mut::<Portable>(planq).unwrap().carrier = player;
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), None);
+		eng.bevy.world.get_mut::<Body>(player).unwrap().move_to(Position::new(6, 2, 0));
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), Some((EngineMode::GoodEnd, "escaped the ship with the PLANQ".to_string())));
+	}
+	#[test]
+	fn restoring_power_is_won_by_throwing_the_breaker_and_reaching_the_elevator() {
+		let mut eng = test_engine();
+		let mut grid = PowerGrid::new(vec!["main".to_string()]);
+		grid.connect("cargo elevator", "main");
+		grid.powered = grid.resolve();
+		eng.bevy.world.insert_resource(grid);
+		let player = scenario_setup(&mut eng, "cargo elevator", ScenarioKind::RestorePower);
+		assert!(eng.bevy.world.resource::<PowerGrid>().generators.iter().all(|x| !x.is_online));
+		let mut b_query = eng.bevy.world.query::<(Entity, &Description, &Body)>();
+		let (breaker, b_posn) = b_query.iter(&eng.bevy.world)
+			.find(|x| x.1.name == crate::scenarios::restore_power::BREAKER_NAME)
+			.map(|x| (x.0, x.2.ref_posn))
+			.expect("the breaker panel should have been spawned");
+		assert!(b_posn.in_range_of(&Position::new(2, 2, 0), 1));
+		// The power system hasn't caught up with the blackout yet, so do that by hand
+		let mut grid = eng.bevy.world.resource_mut::<PowerGrid>();
+		grid.powered = grid.resolve();
+		eng.bevy.world.get_mut::<Body>(player).unwrap().move_to(Position::new(6, 2, 0));
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), None);
+		eng.bevy.world.send_event(GameEvent::new(GameEventType::PlayerAction(ActionType::UseItem), Some(player), Some(breaker)));
+		eng.run_scenario();
+		assert!(eng.bevy.world.resource::<PowerGrid>().generators.iter().all(|x| x.is_online));
+		assert_eq!(outcome(&eng), None);
+		let mut grid = eng.bevy.world.resource_mut::<PowerGrid>();
+		grid.powered = grid.resolve();
+		eng.run_scenario();
+		assert_eq!(outcome(&eng), Some((EngineMode::GoodEnd, "restored the power and rode the elevator out".to_string())));
+	}
+	#[test]
+	fn a_loaded_game_keeps_its_scenario() {
+		let mut eng = test_engine();
+		eng.bevy.world.resource_mut::<RunState>().scenario = ScenarioKind::RestorePower;
+		let loaded = round_trip(&mut eng, "test_scenario_kind");
+		assert_eq!(loaded.bevy.world.resource::<RunState>().scenario, ScenarioKind::RestorePower);
+	}
+	//  ##: permadeath
+	#[test]
+	fn permadeath_deletes_the_runs_own_save_on_death() {
+		assert_eq!(permadeath_slot(Some("slot_2"), true), Some("slot_2"));
+	}
+	#[test]
+	fn no_permadeath_preserves_the_save_on_death() {
+		assert_eq!(permadeath_slot(Some("slot_2"), false), None);
+	}
+	#[test]
+	fn permadeath_leaves_other_slots_alone_if_the_run_was_never_saved() {
+		assert_eq!(permadeath_slot(None, true), None);
+	}
+}
+
 // EOF
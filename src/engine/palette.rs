@@ -0,0 +1,224 @@
+// engine/palette.rs
+// Provides the color palettes that the camera is drawn with, including the colorblind-safe alternatives
+
+// ###: EXTERNAL LIBRARIES
+use std::fmt;
+use bevy::prelude::Resource;
+use ratatui::style::Color as RatatuiColor;
+use serde::{Deserialize, Serialize};
+
+// ###: INTERNAL LIBRARIES
+use crate::components::Color;
+
+// ###: CONSTANTS
+/// The lowest acceptable contrast ratio between a foreground role and its background, per the WCAG guideline
+pub const MIN_CONTRAST: f32 = 4.5;
+/// The foreground colors that the camera draws on the black background, and what they're used for
+pub const CAMERA_ROLES: [(&str, Color); 8] = [
+	("floor",     Color::LtBlack),
+	("wall",      Color::White),
+	("robot",     Color::Cyan),
+	("device",    Color::LtCyan),
+	("alert",     Color::LtRed),
+	("ok",        Color::LtGreen),
+	("highlight", Color::LtYellow),
+	("item",      Color::Yellow),
+];
+
+// ###: COMPLEX TYPES
+//  ##: Palette
+/// Maps the 16 ANSI colors used throughout the game onto the colors that will actually be put on the screen
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+	pub kind: PaletteKind,
+	rgb: [(u8, u8, u8); 16], // The actual color for each ANSI index
+	dim: [u8; 16], // The ANSI index to use for each color when it's remembered but not currently visible
+}
+impl Default for Palette {
+	fn default() -> Palette {
+		Palette::new(PaletteKind::Standard)
+	}
+}
+impl Palette {
+	pub fn new(kind: PaletteKind) -> Palette {
+		// The standard set is the stock xterm palette; the terminal is free to substitute its own theme for it
+		const STANDARD: [(u8, u8, u8); 16] = [
+			(0, 0, 0),       (205, 0, 0),     (0, 205, 0),     (205, 205, 0),
+			(0, 0, 238),     (205, 0, 205),   (0, 205, 205),   (229, 229, 229),
+			(127, 127, 127), (255, 0, 0),     (0, 255, 0),     (255, 255, 0),
+			(92, 92, 255),   (255, 0, 255),   (0, 255, 255),   (255, 255, 255),
+		];
+		// Reds and greens are pushed apart in brightness and towards orange and teal
+		const DEUTERANOPIA: [(u8, u8, u8); 16] = [
+			(0, 0, 0),       (213, 94, 0),    (0, 158, 115),   (230, 159, 0),
+			(86, 150, 233),  (204, 121, 167), (0, 170, 200),   (220, 220, 220),
+			(130, 130, 130), (255, 140, 60),  (60, 210, 170),  (255, 210, 80),
+			(150, 200, 255), (240, 160, 210), (120, 220, 240), (255, 255, 255),
+		];
+		// Reds look dark to protanopes, so they get brightened towards orange instead
+		const PROTANOPIA: [(u8, u8, u8); 16] = [
+			(0, 0, 0),       (230, 120, 0),   (0, 150, 160),   (240, 200, 30),
+			(90, 140, 240),  (180, 130, 220), (60, 180, 230),  (220, 220, 220),
+			(130, 130, 130), (255, 170, 50),  (40, 200, 210),  (255, 235, 100),
+			(140, 180, 255), (215, 170, 255), (140, 215, 255), (255, 255, 255),
+		];
+		// Blues and yellows are the ones that run together for tritanopes, so yellow leans to pink and blue to teal
+		const TRITANOPIA: [(u8, u8, u8); 16] = [
+			(0, 0, 0),       (220, 50, 50),   (0, 160, 140),   (230, 120, 150),
+			(0, 120, 140),   (170, 90, 200),  (60, 190, 200),  (220, 220, 220),
+			(130, 130, 130), (255, 100, 100), (60, 220, 190),  (255, 170, 200),
+			(70, 200, 220),  (215, 150, 255), (150, 235, 240), (255, 255, 255),
+		];
+		// The colorblind palettes keep the hue of a remembered tile and just drop it to the darker shade, so that
+		// the difference between eg a red and a green door survives going out of view
+		const HUE_DIM: [u8; 16] = [0, 8, 8, 8, 8, 8, 8, 8, 8, 1, 2, 3, 4, 5, 6, 7];
+		match kind {
+			PaletteKind::Standard => Palette { kind, rgb: STANDARD, dim: [8; 16] },
+			PaletteKind::Deuteranopia => Palette { kind, rgb: DEUTERANOPIA, dim: HUE_DIM },
+			PaletteKind::Protanopia => Palette { kind, rgb: PROTANOPIA, dim: HUE_DIM },
+			PaletteKind::Tritanopia => Palette { kind, rgb: TRITANOPIA, dim: HUE_DIM },
+		}
+	}
+	/// Converts an ANSI color index into the color that ratatui should draw
+	pub fn color(&self, index: u8) -> RatatuiColor {
+		if self.kind == PaletteKind::Standard || index > 15 {
+			return RatatuiColor::Indexed(index);
+		}
+		let (r, g, b) = self.rgb[index as usize];
+		RatatuiColor::Rgb(r, g, b)
+	}
+	/// Returns the ANSI index that the given color should be dimmed to when drawn from memory
+	pub fn dim(&self, index: u8) -> u8 {
+		if index > 15 { return index; }
+		self.dim[index as usize]
+	}
+	/// Calculates the WCAG contrast ratio between two of this palette's colors, from 1.0 (none) to 21.0
+	pub fn contrast(&self, fg: Color, bg: Color) -> f32 {
+		let fg_lum = relative_luminance(self.rgb[fg as usize]);
+		let bg_lum = relative_luminance(self.rgb[bg as usize]);
+		(fg_lum.max(bg_lum) + 0.05) / (fg_lum.min(bg_lum) + 0.05)
+	}
+	/// DEBUG: Checks every camera role against the background, both as-is and dimmed, and returns a description of
+	/// every pairing that falls below the given contrast ratio
+	pub fn contrast_failures(&self, min_ratio: f32) -> Vec<String> {
+		let mut failures = Vec::new();
+		for (role, fg) in CAMERA_ROLES.iter() {
+			let dimmed = COLOR_LIST[self.dim(*fg as u8) as usize];
+			for (label, color) in [("", *fg), (" (dimmed)", dimmed)] {
+				let ratio = self.contrast(color, Color::Black);
+				if ratio < min_ratio {
+					failures.push(format!("{}{}: {:?} on Black is {:.2}:1", role, label, color, ratio));
+				}
+			}
+		}
+		failures
+	}
+}
+//  ##: PaletteKind
+/// The set of built-in palettes that can be picked from the settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteKind {
+	#[default]
+	Standard,
+	Deuteranopia,
+	Protanopia,
+	Tritanopia,
+}
+impl PaletteKind {
+	/// Returns the next palette in the list, for cycling through them from the menu
+	pub fn next(&self) -> PaletteKind {
+		match self {
+			PaletteKind::Standard => PaletteKind::Deuteranopia,
+			PaletteKind::Deuteranopia => PaletteKind::Protanopia,
+			PaletteKind::Protanopia => PaletteKind::Tritanopia,
+			PaletteKind::Tritanopia => PaletteKind::Standard,
+		}
+	}
+}
+impl fmt::Display for PaletteKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let output = match self {
+			PaletteKind::Standard => "Standard",
+			PaletteKind::Deuteranopia => "Deuteranopia",
+			PaletteKind::Protanopia => "Protanopia",
+			PaletteKind::Tritanopia => "Tritanopia",
+		};
+		write!(f, "{}", output)
+	}
+}
+
+// ###: SIMPLE TYPES AND HELPERS
+/// The Colors in order of their ANSI index, for going back from an index to a Color
+const COLOR_LIST: [Color; 16] = [
+	Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Pink, Color::Cyan, Color::White,
+	Color::LtBlack, Color::LtRed, Color::LtGreen, Color::LtYellow, Color::LtBlue, Color::LtPink, Color::LtCyan, Color::LtWhite,
+];
+/// Calculates the relative luminance of an sRGB color, as defined by WCAG 2
+fn relative_luminance(rgb: (u8, u8, u8)) -> f32 {
+	let channel = |value: u8| {
+		let c = value as f32 / 255.0;
+		if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+	};
+	0.2126 * channel(rgb.0) + 0.7152 * channel(rgb.1) + 0.0722 * channel(rgb.2)
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	/// Every built-in palette, in the order that the menu cycles through them
+	const KINDS: [PaletteKind; 4] = [PaletteKind::Standard, PaletteKind::Deuteranopia, PaletteKind::Protanopia, PaletteKind::Tritanopia];
+	//  ##: Palettes
+	#[test]
+	fn the_menu_cycles_through_every_palette() {
+		for (index, kind) in KINDS.iter().enumerate() {
+			assert_eq!(kind.next(), KINDS[(index + 1) % KINDS.len()]);
+		}
+	}
+	#[test]
+	fn only_the_standard_palette_leaves_the_colors_to_the_terminal() {
+		let red = Color::LtRed as u8;
+		assert_eq!(Palette::new(PaletteKind::Standard).color(red), RatatuiColor::Indexed(red));
+		assert_eq!(Palette::new(PaletteKind::Deuteranopia).color(red), RatatuiColor::Rgb(255, 140, 60));
+		assert_eq!(Palette::new(PaletteKind::Tritanopia).color(red), RatatuiColor::Rgb(255, 100, 100));
+		// Anything past the 16 ANSI colors is passed along as it is
+		for kind in KINDS {
+			assert_eq!(Palette::new(kind).color(200), RatatuiColor::Indexed(200));
+		}
+	}
+	#[test]
+	fn a_colorblind_palette_keeps_the_hue_of_a_remembered_tile() {
+		let (alert, ok) = (Color::LtRed as u8, Color::LtGreen as u8);
+		let standard = Palette::new(PaletteKind::Standard);
+		assert_eq!((standard.dim(alert), standard.dim(ok)), (Color::LtBlack as u8, Color::LtBlack as u8));
+		for kind in KINDS.iter().skip(1) {
+			let palette = Palette::new(*kind);
+			assert_eq!((palette.dim(alert), palette.dim(ok)), (Color::Red as u8, Color::Green as u8), "{}", kind);
+		}
+	}
+	//  ##: Contrast
+	#[test]
+	fn contrast_runs_from_none_to_black_on_white() {
+		let palette = Palette::default();
+		assert!((palette.contrast(Color::Black, Color::Black) - 1.0).abs() < 0.01);
+		assert!((palette.contrast(Color::LtWhite, Color::Black) - 21.0).abs() < 0.01);
+		// It doesn't matter which way around the colors are
+		assert_eq!(palette.contrast(Color::LtRed, Color::Black), palette.contrast(Color::Black, Color::LtRed));
+	}
+	#[test]
+	fn every_palette_is_readable_on_the_camera() {
+		for kind in KINDS {
+			assert_eq!(Palette::new(kind).contrast_failures(MIN_CONTRAST), Vec::<String>::new(), "{}", kind);
+		}
+	}
+	#[test]
+	fn the_checker_reports_each_role_that_falls_short() {
+		// Nothing is quite as bright as white on black, so at that ratio every role fails, dimmed or not
+		let failures = Palette::default().contrast_failures(21.0);
+		assert_eq!(failures.len(), CAMERA_ROLES.len() * 2);
+		assert_eq!(failures[0], "floor: LtBlack on Black is 5.24:1");
+		assert_eq!(failures[1], "floor (dimmed): LtBlack on Black is 5.24:1");
+	}
+}
+
+// EOF
@@ -0,0 +1,86 @@
+// engine/preferences.rs
+// Keeps the player's own preferences, which persist between sessions and are never part of a saved game
+
+// ###: EXTERNAL LIBRARIES
+use std::fs::File;
+use std::io::{BufReader, Write};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use simplelog::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::camera::RememberedEntities;
+use crate::components::DirNames;
+use crate::engine::palette::PaletteKind;
+use crate::fov::FovAlgorithm;
+use crate::worldmap::HazardPrompt;
+
+// ###: CONSTANTS
+/// The file that the player's preferences are kept in, as a single JSON object
+pub const PREFS_FILENAME: &str = "preferences.json";
+
+// ###: COMPLEX TYPES
+//  ##: Preferences
+/// The player's choices about how the game looks and behaves, loaded before any game starts
+/// The GameEngine owns the master copy and mirrors it into Bevy, where it's read-only; it is deliberately not
+/// registered as saveable, so that loading a game can never change it and changing it can never leak into a save
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)] // Any fields that are missing from an older file are filled in from the defaults
+pub struct Preferences {
+	pub show_hud:     bool, // If true, the mode/turn/clock HUD will be drawn over the camera
+	pub announce_sightings: bool, // If true, the player is told whenever an actor comes into view
+	pub dir_names:    DirNames, // Whether directions are given as compass points or in the ship's own frame
+	pub show_hints:   bool, // If true, the tutorial's one-time hints will be shown
+	pub fov_algorithm: FovAlgorithm, // Which field of view calculation to use; bracket's is only kept for comparison
+	pub hazard_prompt: HazardPrompt, // When the player has to confirm a step into a hazardous tile
+	pub palette:      PaletteKind, // The colors that the camera is drawn with
+	pub hivis_cursor: bool, // If true, the menus draw their selection with high-contrast colors and a marker
+	pub preview_travel: bool, // If true, the route to a bookmark is shown for confirmation before travelling along it
+	pub planq_busy_hands: bool, // If true, the player can't move or handle things while the PLANQ boots up or shuts down
+	pub planq_autopower: bool, // If true, the PLANQ is switched on for the player the first time that they pick it up
+	pub remembered_entities: RememberedEntities, // How the things that the player remembers but can't see are drawn
+	pub unload_decks: bool, // If true, only the player's own deck is kept in the World; the others are stored until revisited
+}
+impl Default for Preferences {
+	fn default() -> Preferences {
+		Preferences {
+			show_hud: true,
+			announce_sightings: true,
+			dir_names: DirNames::default(),
+			show_hints: true,
+			fov_algorithm: FovAlgorithm::default(),
+			hazard_prompt: HazardPrompt::default(),
+			palette: PaletteKind::default(),
+			hivis_cursor: false,
+			preview_travel: true,
+			planq_busy_hands: false,
+			planq_autopower: false,
+			remembered_entities: RememberedEntities::default(),
+			unload_decks: false,
+		}
+	}
+}
+impl Preferences {
+	pub fn new() -> Preferences {
+		Preferences::default()
+	}
+	/// Reads the preferences from the given file; a missing or unreadable file just produces the defaults
+	pub fn load(filename: &str) -> Preferences {
+		let file = if let Ok(value) = File::open(filename) { value } else { return Preferences::default() };
+		match serde_json::from_reader(BufReader::new(file)) {
+			Ok(prefs) => { prefs }
+			Err(e) => {
+				warn!("* could not read the preferences at {}, using the defaults: {}", filename, e); // DEBUG: report a bad preferences file
+				Preferences::default()
+			}
+		}
+	}
+	/// Writes the preferences out to the given file, replacing whatever was there
+	pub fn save(&self, filename: &str) -> std::io::Result<()> {
+		let mut file = File::create(filename)?;
+		let text = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+		writeln!(file, "{}", text)
+	}
+}
+
+// EOF
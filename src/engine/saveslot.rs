@@ -0,0 +1,228 @@
+// engine/saveslot.rs
+// Keeps a small metadata file next to each save slot, so that the slots can be listed without loading the saves
+
+// ###: EXTERNAL LIBRARIES
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use simplelog::*;
+
+// ###: CONSTANTS
+/// The names of the save slots that the player can choose from, as they are given to bevy_save
+pub const SAVE_SLOTS: [&str; 3] = ["slot_1", "slot_2", "slot_3"];
+/// The size of the text thumbnail that is taken from the camera when the game is saved
+pub const THUMB_WIDTH: usize = 24;
+pub const THUMB_HEIGHT: usize = 8;
+
+// ###: COMPLEX TYPES
+//  ##: SlotHeader
+/// The part of a slot's metadata that the Load Game menu needs; this is always the first line of the metadata file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotHeader {
+	pub slot: String,
+	pub name: String, // The player character's name
+	pub ship_time: String, // The ship's clock when the game was saved, ie "12:34:56"
+	pub deck: i32,
+	pub turns: u64, // How long the game has been played for
+	pub saved_at: u64, // Seconds since the epoch, only used to sort the slots
+}
+impl SlotHeader {
+	/// Reads just the header line from the given metadata file, without touching the thumbnail or the save itself
+	pub fn read(path: &PathBuf) -> Option<SlotHeader> {
+		let file = File::open(path).ok()?;
+		let mut line = String::new();
+		BufReader::new(file).read_line(&mut line).ok()?;
+		match serde_json::from_str::<SlotHeader>(line.trim()) {
+			Ok(header) => { Some(header) }
+			Err(e) => {
+				warn!("* could not read the save slot header at {}: {}", path.display(), e); // DEBUG: report a bad slot header
+				None
+			}
+		}
+	}
+	/// Produces the one-line summary that is shown in the Load Game menu
+	pub fn summary(&self) -> String {
+		format!("{}: {}, deck {}, {} T:{}", self.slot, self.name, self.deck, self.ship_time, self.turns)
+	}
+}
+//  ##: SlotMeta
+/// The full metadata for a save slot: the header, followed by a text snapshot of the camera
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotMeta {
+	pub header: SlotHeader,
+	pub thumbnail: Vec<String>,
+}
+impl SlotMeta {
+	pub fn new(header: SlotHeader, thumbnail: Vec<String>) -> SlotMeta {
+		SlotMeta {
+			header: SlotHeader { saved_at: now_secs(), ..header },
+			thumbnail,
+		}
+	}
+	/// Reads the header and the thumbnail from the given metadata file
+	pub fn read(path: &PathBuf) -> Option<SlotMeta> {
+		let header = SlotHeader::read(path)?;
+		let file = File::open(path).ok()?;
+		let thumbnail = BufReader::new(file).lines().skip(1).map_while(Result::ok).collect();
+		Some(SlotMeta { header, thumbnail })
+	}
+	/// Writes the metadata out to the given file, replacing whatever was there
+	pub fn write(&self, path: &PathBuf) -> std::io::Result<()> {
+		let mut file = File::create(path)?;
+		writeln!(file, "{}", serde_json::to_string(&self.header)?)?;
+		for line in self.thumbnail.iter() {
+			writeln!(file, "{}", line)?;
+		}
+		Ok(())
+	}
+}
+
+// ###: UTILITIES
+/// Returns the path to the metadata file that sits alongside the given slot's save file
+pub fn meta_path(slot: &str) -> PathBuf {
+	bevy_save::get_save_file(slot).with_extension("meta")
+}
+/// Lists the headers of every slot that has a save in it, newest first
+pub fn list_slots() -> Vec<SlotHeader> {
+	list_slots_from(&SAVE_SLOTS)
+}
+/// Lists the headers of whichever of the given slots have a save in them, newest first
+pub fn list_slots_from(names: &[&str]) -> Vec<SlotHeader> {
+	list_slots_keyed(names, |x| x.to_string())
+}
+/// As list_slots_from, but each slot's files are looked for under the name that the given function gives it, while
+/// the headers still go by the slot's own name
+pub fn list_slots_keyed(names: &[&str], key: impl Fn(&str) -> String) -> Vec<SlotHeader> {
+	let mut slots: Vec<SlotHeader> = names.iter()
+		.filter(|x| std::fs::metadata(bevy_save::get_save_file(&key(x))).is_ok())
+		.map(|x| SlotHeader::read(&meta_path(&key(x))).unwrap_or(SlotHeader { slot: x.to_string(), name: "(no details)".to_string(), ..SlotHeader::default() }))
+		.collect();
+	slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+	slots
+}
+/// Deletes a slot's save file along with its metadata; a missing metadata file is not an error
+pub fn delete_slot(slot: &str) -> std::io::Result<()> {
+	std::fs::remove_file(bevy_save::get_save_file(slot))?;
+	match std::fs::remove_file(meta_path(slot)) {
+		Err(e) if e.kind() != std::io::ErrorKind::NotFound => { Err(e) }
+		_ => { Ok(()) }
+	}
+}
+/// Samples the camera's glyphs down into a small block of text, for the Load Game menu to show
+pub fn make_thumbnail(glyphs: &[String], width: usize, height: usize) -> Vec<String> {
+	if width == 0 || height == 0 || glyphs.len() < width * height { return Vec::new(); }
+	let step_x = (width / THUMB_WIDTH).max(1);
+	let step_y = (height / THUMB_HEIGHT).max(1);
+	(0..height).step_by(step_y).take(THUMB_HEIGHT).map(|y| {
+		(0..width).step_by(step_x).take(THUMB_WIDTH).map(|x| {
+			glyphs[y * width + x].chars().next().unwrap_or(' ')
+		}).collect()
+	}).collect()
+}
+fn now_secs() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_secs())
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Setup
+	/// Makes a header for the given slot, saved at the given time
+	fn header(slot: &str, saved_at: u64) -> SlotHeader {
+		SlotHeader { slot: slot.to_string(), name: "Tester".to_string(), ship_time: "01:02:03".to_string(), deck: 1, turns: 40, saved_at }
+	}
+	/// Puts a stand-in save file into the given slot, along with its metadata if there is any
+	fn fill_slot(slot: &str, meta: Option<SlotMeta>) {
+		let save_path = bevy_save::get_save_file(slot);
+		if let Some(parent) = save_path.parent() {
+			std::fs::create_dir_all(parent).expect("could not make the save directory");
+		}
+		std::fs::write(&save_path, b"not really a save").expect("could not write the test save");
+		if let Some(meta) = meta {
+			meta.write(&meta_path(slot)).expect("could not write the test metadata");
+		}
+	}
+	//  ##: Reading the metadata
+	#[test]
+	fn the_header_is_read_without_the_rest_of_the_file() {
+		let path = std::env::temp_dir().join("test_slot_header_only.meta");
+		// The thumbnail is cut off partway through and isn't even text, but the header comes before it
+		let mut contents = serde_json::to_string(&header("slot_1", 5)).unwrap().into_bytes();
+		contents.extend_from_slice(b"\n#..@\xff\xfe");
+		std::fs::write(&path, contents).unwrap();
+		assert_eq!(SlotHeader::read(&path), Some(header("slot_1", 5)));
+		let _ = std::fs::remove_file(&path);
+	}
+	#[test]
+	fn a_header_on_its_own_has_no_thumbnail() {
+		let path = std::env::temp_dir().join("test_slot_no_thumbnail.meta");
+		SlotMeta { header: header("slot_2", 5), thumbnail: Vec::new() }.write(&path).unwrap();
+		let meta = SlotMeta::read(&path).expect("the header should still be read");
+		assert_eq!(meta.header.summary(), "slot_2: Tester, deck 1, 01:02:03 T:40".to_string());
+		assert!(meta.thumbnail.is_empty());
+		let _ = std::fs::remove_file(&path);
+	}
+	#[test]
+	fn a_header_that_was_cut_short_is_refused() {
+		let path = std::env::temp_dir().join("test_slot_cut_short.meta");
+		let full = serde_json::to_string(&header("slot_3", 5)).unwrap();
+		std::fs::write(&path, &full[..full.len() / 2]).unwrap();
+		assert_eq!(SlotHeader::read(&path), None);
+		assert_eq!(SlotHeader::read(&std::env::temp_dir().join("test_slot_not_there.meta")), None);
+		let _ = std::fs::remove_file(&path);
+	}
+	#[test]
+	fn the_metadata_survives_a_write_and_read() {
+		let path = std::env::temp_dir().join("test_slot_round_trip.meta");
+		let meta = SlotMeta { header: header("slot_1", 9), thumbnail: vec!["#####".to_string(), "#.@.#".to_string()] };
+		meta.write(&path).unwrap();
+		assert_eq!(SlotMeta::read(&path), Some(meta));
+		let _ = std::fs::remove_file(&path);
+	}
+	//  ##: Listing and deleting
+	#[test]
+	fn the_slots_are_listed_newest_first() {
+		let names = ["test_list_older", "test_list_newer", "test_list_bare", "test_list_empty"];
+		fill_slot(names[0], Some(SlotMeta { header: header(names[0], 100), thumbnail: Vec::new() }));
+		fill_slot(names[1], Some(SlotMeta { header: header(names[1], 200), thumbnail: Vec::new() }));
+		fill_slot(names[2], None);
+		let listed: Vec<String> = list_slots_from(&names).into_iter().map(|x| x.slot).collect();
+		for name in names.iter().take(3) {
+			let _ = delete_slot(name);
+		}
+		// A save without its metadata is still listed, just without any details; an empty slot isn't listed at all
+		assert_eq!(listed, vec![names[1].to_string(), names[0].to_string(), names[2].to_string()]);
+	}
+	#[test]
+	fn deleting_a_slot_takes_its_metadata_with_it() {
+		let slot = "test_delete_slot";
+		fill_slot(slot, Some(SlotMeta { header: header(slot, 100), thumbnail: Vec::new() }));
+		assert!(delete_slot(slot).is_ok());
+		assert!(std::fs::metadata(bevy_save::get_save_file(slot)).is_err());
+		assert!(std::fs::metadata(meta_path(slot)).is_err());
+		assert!(list_slots_from(&[slot]).is_empty());
+		// There's nothing left to delete the second time around
+		assert!(delete_slot(slot).is_err());
+	}
+	#[test]
+	fn a_slot_without_metadata_can_still_be_deleted() {
+		let slot = "test_delete_bare_slot";
+		fill_slot(slot, None);
+		assert!(delete_slot(slot).is_ok());
+		assert!(std::fs::metadata(bevy_save::get_save_file(slot)).is_err());
+	}
+	//  ##: Thumbnails
+	#[test]
+	fn a_thumbnail_samples_the_camera_down() {
+		let glyphs: Vec<String> = (0..THUMB_WIDTH * 2 * THUMB_HEIGHT).map(|x| if x % 2 == 0 { "#" } else { "." }.to_string()).collect();
+		let thumbnail = make_thumbnail(&glyphs, THUMB_WIDTH * 2, THUMB_HEIGHT);
+		assert_eq!(thumbnail.len(), THUMB_HEIGHT);
+		assert!(thumbnail.iter().all(|x| *x == "#".repeat(THUMB_WIDTH)));
+		assert!(make_thumbnail(&glyphs, 1000, 1000).is_empty());
+	}
+}
+
+// EOF
@@ -0,0 +1,225 @@
+// engine/snapshot.rs
+// Provides the read-only snapshot of the world that the key handler works from, so that it doesn't need to run Bevy
+// queries of its own in the middle of handling an input
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::event::ActionType;
+
+// ###: CONSTANTS
+/// How far from the player the snapshot looks for things; this is comfortably more than any reach in the game
+pub const SNAPSHOT_RADIUS: i32 = 10;
+
+// ###: COMPLEX TYPES
+//  ##: InputSnapshot
+/// Holds copies of the things that the key handler most often needs to know about, taken once per keypress
+/// Nothing in here borrows from the World, so the handler is free to send events or change resources while using it
+#[derive(Clone, Debug)]
+pub struct InputSnapshot {
+	pub player: Entity, // Entity::PLACEHOLDER if there isn't exactly one Player
+	pub p_posn: Option<Position>,
+	pub nearby: Vec<NearbyEntity>, // Everything with a Description and a Body near the player and on the same deck, the player included
+	pub items: Vec<PortableItem>, // Everything Portable with a Description, wherever it is and whoever has it
+}
+impl InputSnapshot {
+	/// Takes a new snapshot of the world from the player's point of view
+	pub fn take(world: &mut World) -> InputSnapshot {
+		let mut player_query = world.query_filtered::<Entity, With<Player>>();
+		let player = player_query.get_single(world).unwrap_or(Entity::PLACEHOLDER);
+		let p_posn = world.get_resource::<Position>().copied();
+		let mut nearby = Vec::new();
+		if let Some(posn) = p_posn {
+			let mut enty_query = world.query::<(Entity, &Description, &Body, Option<&Portable>, Option<&Openable>, Option<&Lockable>)>();
+			for (t_enty, t_desc, t_body, t_portable, t_open, t_lock) in enty_query.iter(world) {
+				if !t_body.in_range_of(&posn, SNAPSHOT_RADIUS) { continue; }
+				nearby.push(NearbyEntity {
+					enty: t_enty,
+					name: t_desc.name.clone(),
+					body: t_body.clone(),
+					carrier: t_portable.map(|x| x.carrier),
+					is_open: t_open.map(|x| x.is_open),
+					is_locked: t_lock.map(|x| x.is_locked),
+					is_container: world.get::<Container>(t_enty).is_some(),
+					is_mobile: world.get::<Mobile>(t_enty).is_some(),
+					is_salvage_bin: world.get::<SalvageBin>(t_enty).is_some(),
+					is_device: world.get::<Device>(t_enty).is_some(),
+					is_access_port: world.get::<AccessPort>(t_enty).is_some(),
+				});
+			}
+		}
+		let mut items = Vec::new();
+		let mut item_query = world.query::<(Entity, &Description, &Portable, Option<&ActionSet>, Option<&Lockable>)>();
+		for (i_enty, i_desc, i_portable, i_actions, i_lock) in item_query.iter(world) {
+			items.push(PortableItem {
+				enty: i_enty,
+				name: i_desc.name.clone(),
+				carrier: i_portable.carrier,
+				actions: i_actions.map(|x| x.actions.iter().copied().collect()),
+				is_locked: i_lock.map(|x| x.is_locked),
+				is_container: world.get::<Container>(i_enty).is_some(),
+				is_device: world.get::<Device>(i_enty).is_some(),
+			});
+		}
+		InputSnapshot {
+			player,
+			p_posn,
+			nearby,
+			items,
+		}
+	}
+	/// Returns true if the snapshot found a player to work from
+	pub fn has_player(&self) -> bool {
+		self.player != Entity::PLACEHOLDER
+	}
+	/// Lists the nearby things that have any part within the given range of the player
+	pub fn within(&self, range: i32) -> impl Iterator<Item = &NearbyEntity> + '_ {
+		let p_posn = self.p_posn.unwrap_or(Position::INVALID);
+		self.nearby.iter().filter(move |x| x.body.in_range_of(&p_posn, range))
+	}
+	/// Finds the given Entity among the nearby things, if it's close enough to have been noticed
+	pub fn nearby_entity(&self, target: Entity) -> Option<&NearbyEntity> {
+		self.nearby.iter().find(|x| x.enty == target)
+	}
+	/// Lists the Portable things that the given carrier is holding directly
+	pub fn carried_by(&self, carrier: Entity) -> impl Iterator<Item = &PortableItem> + '_ {
+		self.items.iter().filter(move |x| x.carrier == carrier)
+	}
+	/// Lists the player's inventory in the same form as player_action_menu() takes
+	pub fn inventory_candidates(&self) -> Vec<(Entity, String, Option<Position>)> {
+		self.carried_by(self.player).map(|x| (x.enty, x.name.clone(), None)).collect()
+	}
+}
+//  ##: NearbyEntity
+/// A copy of the few details about something near the player that the key handler needs to offer it on a menu
+#[derive(Clone, Debug)]
+pub struct NearbyEntity {
+	pub enty: Entity,
+	pub name: String,
+	pub body: Body,
+	pub carrier: Option<Entity>, // None if it isn't Portable at all
+	pub is_open: Option<bool>, // None if it can't be opened or closed
+	pub is_locked: Option<bool>, // None if it doesn't have a lock
+	pub is_container: bool,
+	pub is_mobile: bool,
+	pub is_salvage_bin: bool,
+	pub is_device: bool,
+	pub is_access_port: bool,
+}
+impl NearbyEntity {
+	/// Returns true if this is a Container that stays put, ie a locker rather than the LMR's pockets
+	pub fn is_fixture(&self) -> bool {
+		self.is_container && !self.is_mobile
+	}
+	/// Returns true if this can be reached into: anything without a lid, or with its lid open
+	pub fn is_accessible(&self) -> bool {
+		self.is_open.unwrap_or(true)
+	}
+}
+//  ##: PortableItem
+/// A copy of the details about something Portable that the key handler needs, no matter where the thing is
+#[derive(Clone, Debug)]
+pub struct PortableItem {
+	pub enty: Entity,
+	pub name: String,
+	pub carrier: Entity, // Entity::PLACEHOLDER if it's lying on the ground
+	pub actions: Option<Vec<ActionType>>, // None if it has no ActionSet
+	pub is_locked: Option<bool>, // None if it doesn't have a lock
+	pub is_container: bool,
+	pub is_device: bool,
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: Setup
+	/// Builds a small World with a player at 5, 5, 0 and a few things around them, some near and some far
+	fn snapshot_world() -> (World, Entity) {
+		let mut world = World::new();
+		let p_posn = Position::new(5, 5, 0);
+		world.insert_resource(p_posn);
+		let player = world.spawn((Player::default(), Description::new().name("player"), Body::small(p_posn, ScreenCell::new()))).id();
+		let locker = world.spawn((Description::new().name("locker"), Body::small(Position::new(6, 5, 0), ScreenCell::new()),
+			Container::default(), Openable::default(), Lockable { is_locked: true, ..Lockable::default() })).id();
+		world.spawn((Description::new().name("terminal"), Body::small(Position::new(5, 6, 0), ScreenCell::new()), Device::default(), AccessPort::default()));
+		world.spawn((Description::new().name("faraway crate"), Body::small(Position::new(40, 40, 0), ScreenCell::new()), Container::default()));
+		world.spawn((Description::new().name("wrench"), Body::small(p_posn, ScreenCell::new()), Portable::new(player), IsCarried::default(), ActionSet::new()));
+		world.spawn((Description::new().name("snack"), Body::small(Position::new(6, 5, 0), ScreenCell::new()), Portable::new(locker)));
+		world.spawn((Description::new().name("pebble"), Body::small(p_posn, ScreenCell::new()), Portable::empty()));
+		(world, player)
+	}
+	/// Sorts the names so that the query order doesn't matter to the comparisons
+	fn sorted(mut names: Vec<String>) -> Vec<String> {
+		names.sort();
+		names
+	}
+	//  ##: Equivalence with the queries that the handler used to run
+	#[test]
+	fn the_snapshot_finds_the_player() {
+		let (mut world, player) = snapshot_world();
+		let snapshot = InputSnapshot::take(&mut world);
+		assert!(snapshot.has_player());
+		assert_eq!(snapshot.player, player);
+		assert_eq!(snapshot.p_posn, Some(Position::new(5, 5, 0)));
+	}
+	#[test]
+	fn without_a_player_the_snapshot_says_so() {
+		let mut world = World::new();
+		let snapshot = InputSnapshot::take(&mut world);
+		assert!(!snapshot.has_player());
+		assert!(snapshot.nearby.is_empty());
+		assert_eq!(snapshot.within(1).count(), 0);
+	}
+	#[test]
+	fn nearby_matches_a_range_query() {
+		let (mut world, _) = snapshot_world();
+		let snapshot = InputSnapshot::take(&mut world);
+		let p_posn = Position::new(5, 5, 0);
+		for reach in [0, 1, 2] {
+			let mut enty_query = world.query::<(&Description, &Body)>();
+			let expected: Vec<String> = enty_query.iter(&world).filter(|x| x.1.in_range_of(&p_posn, reach)).map(|x| x.0.name.clone()).collect();
+			let found: Vec<String> = snapshot.within(reach).map(|x| x.name.clone()).collect();
+			assert_eq!(sorted(found), sorted(expected), "reach {}", reach);
+		}
+		assert!(snapshot.within(SNAPSHOT_RADIUS).all(|x| x.name != "faraway crate"));
+	}
+	#[test]
+	fn nearby_copies_the_component_states() {
+		let (mut world, _) = snapshot_world();
+		let snapshot = InputSnapshot::take(&mut world);
+		let locker = snapshot.nearby.iter().find(|x| x.name == "locker").expect("the locker should be nearby");
+		assert!(locker.is_fixture());
+		assert_eq!(locker.is_open, Some(false));
+		assert_eq!(locker.is_locked, Some(true));
+		assert!(!locker.is_accessible());
+		let terminal = snapshot.nearby.iter().find(|x| x.name == "terminal").expect("the terminal should be nearby");
+		assert!(terminal.is_device && terminal.is_access_port);
+		assert_eq!(terminal.carrier, None);
+		assert!(terminal.is_accessible());
+	}
+	#[test]
+	fn the_inventory_matches_a_carrier_query() {
+		let (mut world, player) = snapshot_world();
+		let snapshot = InputSnapshot::take(&mut world);
+		let mut backpack_query = world.query_filtered::<(&Description, &Portable), With<IsCarried>>();
+		let expected: Vec<String> = backpack_query.iter(&world).filter(|x| x.1.carrier == player).map(|x| x.0.name.clone()).collect();
+		let found: Vec<String> = snapshot.inventory_candidates().into_iter().map(|x| x.1).collect();
+		assert_eq!(found, expected);
+		assert_eq!(snapshot.carried_by(player).next().and_then(|x| x.actions.clone()), Some(Vec::new()));
+	}
+	#[test]
+	fn items_inside_containers_know_their_carrier() {
+		let (mut world, _) = snapshot_world();
+		let snapshot = InputSnapshot::take(&mut world);
+		let locker = snapshot.nearby.iter().find(|x| x.name == "locker").map(|x| x.enty).expect("the locker should be nearby");
+		let inside: Vec<String> = snapshot.carried_by(locker).map(|x| x.name.clone()).collect();
+		assert_eq!(inside, vec!["snack".to_string()]);
+		let on_floor: Vec<String> = snapshot.carried_by(Entity::PLACEHOLDER).map(|x| x.name.clone()).collect();
+		assert_eq!(on_floor, vec!["pebble".to_string()]);
+	}
+}
+
+// EOF
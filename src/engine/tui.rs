@@ -10,6 +10,7 @@ use std::time::{Duration, Instant};
 use crossterm::event::{
 	self,
 	Event as CrosstermEvent,
+	KeyCode,
 	KeyEvent,
 	MouseEvent,
 	DisableMouseCapture,
@@ -28,6 +29,7 @@ use ratatui::{
 		Layout,
 		Rect
 	},
+	style::Color,
 	Terminal,
 };
 
@@ -116,7 +118,9 @@ impl UIGrid {
 		self.planq_stdin = second_split[1];
 	}
 	/// Recalculates the UI layout based on the given size, to be invoked if the screen is resized
-	pub fn calc_layout(&mut self, max_area: Rect) {
+	/// If the PLANQ's terminal isn't showing, the camera takes the sidebar's column and the PLANQ's status bars move
+	/// down beside the message log instead
+	pub fn calc_layout(&mut self, max_area: Rect, show_terminal: bool) {
 		/* Use the layout to build up the UI and its contents
 		 * - iterate through the layout stack
 		 * - if the object indexed to the layout Rect is active, then draw it
@@ -141,6 +145,23 @@ impl UIGrid {
 		 * Cogmind uses a minimum 'grid' size of 80 wide by 60 high, seems legit
 		 */
 		// Recalculate everything given the new area
+		if !show_terminal {
+			// Split the entire window between [1](0) and [2/3](1) vertically
+			let camera_bottom_split = Layout::default()
+				.direction(Direction::Vertical)
+				.constraints([Constraint::Min(30), Constraint::Length(12)].as_ref())
+				.split(max_area).to_vec();
+			// Split [2](0) and [3](1) horizontally
+			let worldmsg_status_split = Layout::default()
+				.direction(Direction::Horizontal)
+				.constraints([Constraint::Min(30), Constraint::Length(32)].as_ref())
+				.split(camera_bottom_split[1]).to_vec();
+			self.camera_main = camera_bottom_split[0];
+			self.msg_world = worldmsg_status_split[0];
+			self.planq_sidebar = worldmsg_status_split[1];
+			self.calc_planq_layout(self.planq_sidebar);
+			return;
+		}
 		// Split the entire window between [1/2](0) and [3](1) horizontally
 		let main_horiz_split = Layout::default()
 			.direction(Direction::Horizontal)
@@ -163,6 +184,31 @@ impl Default for UIGrid {
 		UIGrid::new()
 	}
 }
+//  ###: PanelFocus
+/// The number of lines that PageUp and PageDown scroll a focused panel by
+pub const PAGE_SCROLL_LINES: usize = 5;
+/// Names the UI panel that the scrolling and navigation keys are currently directed to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanelFocus {
+	#[default]
+	World, // The camera: the navigation keys move the player
+	Planq, // The PLANQ's terminal, or whichever app is open on it
+	Log,   // The message log underneath the camera
+}
+impl PanelFocus {
+	/// Returns the panel that follows this one when cycling with Tab
+	pub fn next(&self) -> PanelFocus {
+		match self {
+			PanelFocus::World => PanelFocus::Planq,
+			PanelFocus::Planq => PanelFocus::Log,
+			PanelFocus::Log   => PanelFocus::World,
+		}
+	}
+	/// Returns the border color for the given panel: the focused panel is picked out in yellow
+	pub fn border_color(&self, panel: PanelFocus, normal: Color) -> Color {
+		if *self == panel { Color::Yellow } else { normal }
+	}
+}
 
 //  ###: Tui
 /// Provides the representation of the TUI, sets up the terminal and interface, handles drawing events
@@ -261,6 +307,49 @@ impl TuiEventHandler {
 		Ok(self.receiver.recv()?)
 	}
 }
+//  ###: InputThrottle
+/// Keeps held-down movement keys from flooding the game with moves: the first press of a key always goes through,
+/// but repeats of that same key are only allowed once per interval
+#[derive(Clone, Debug)]
+pub struct InputThrottle {
+	/// The minimum time between two accepted repeats of the same key
+	pub interval: Duration,
+	last_key: Option<KeyCode>,
+	last_time: Option<Instant>,
+}
+impl InputThrottle {
+	pub fn new(interval: Duration) -> InputThrottle {
+		InputThrottle {
+			interval,
+			last_key: None,
+			last_time: None,
+		}
+	}
+	/// Decides whether the given key event, arriving at the given time, should be passed on to the key parser
+	pub fn admit(&mut self, key_event: &KeyEvent, now: Instant) -> bool {
+		// Only the movement keys are throttled, everything else goes straight through
+		if !InputThrottle::is_throttled(key_event.code) {
+			self.last_key = None;
+			return true;
+		}
+		if self.last_key == Some(key_event.code) {
+			if let Some(then) = self.last_time {
+				if now.saturating_duration_since(then) < self.interval { return false; }
+			}
+		}
+		self.last_key = Some(key_event.code);
+		self.last_time = Some(now);
+		true
+	}
+	/// Returns true if this key is one of the ones that moves the player around
+	fn is_throttled(code: KeyCode) -> bool {
+		matches!(code,
+			KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+			| KeyCode::Char('h') | KeyCode::Char('j') | KeyCode::Char('k') | KeyCode::Char('l')
+			| KeyCode::Char('y') | KeyCode::Char('u') | KeyCode::Char('b') | KeyCode::Char('n')
+			| KeyCode::Char('<') | KeyCode::Char('>'))
+	}
+}
 //  ###: TuiEvent
 /// Defines the set of interface events in the TUI
 #[derive(Clone, Copy, Debug)]
@@ -275,4 +364,39 @@ pub enum TuiEvent {
 	Resize(u16, u16)
 }
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use crossterm::event::KeyModifiers;
+	use super::*;
+	//  ##: Key repeats
+	/// Feeds the given keys to a fresh throttle with a 100ms interval, each at its offset in ms from the first, and
+	/// returns which of them were let through
+	fn admitted(keys: &[(KeyCode, u64)]) -> Vec<bool> {
+		let mut throttle = InputThrottle::new(Duration::from_millis(100));
+		let start = Instant::now();
+		keys.iter()
+			.map(|(code, msecs)| throttle.admit(&KeyEvent::new(*code, KeyModifiers::NONE), start + Duration::from_millis(*msecs)))
+			.collect()
+	}
+	#[test]
+	fn a_held_movement_key_only_repeats_once_per_interval() {
+		let left = KeyCode::Char('h');
+		assert_eq!(admitted(&[(left, 0), (left, 30), (left, 60), (left, 100), (left, 150), (left, 210)]),
+		           vec![true, false, false, true, false, true]);
+	}
+	#[test]
+	fn a_different_movement_key_goes_straight_through() {
+		assert_eq!(admitted(&[(KeyCode::Left, 0), (KeyCode::Up, 10), (KeyCode::Left, 20), (KeyCode::Left, 40)]),
+		           vec![true, true, true, false]);
+	}
+	#[test]
+	fn other_keys_are_never_throttled() {
+		let examine = KeyCode::Char('x');
+		assert_eq!(admitted(&[(examine, 0), (examine, 1), (KeyCode::Enter, 2), (KeyCode::Enter, 3)]), vec![true; 4]);
+		// And they break up a held movement key, so the next press of it counts as a new one
+		assert_eq!(admitted(&[(KeyCode::Down, 0), (KeyCode::Esc, 10), (KeyCode::Down, 20)]), vec![true; 3]);
+	}
+}
+
 // EOF
@@ -6,16 +6,21 @@ use ratatui::{
 	buffer::Buffer,
 	widgets::{Block, Widget},
 	layout::{Alignment, Rect},
-	style::{Color::Indexed, Style},
+	style::Style,
 };
 
 //  ###: INTERNAL LIBRARIES
 use crate::worldmap::xy_to_index;
-use crate::camera::CameraView;
+use crate::camera::{glyph_width, CameraView};
+use crate::engine::palette::Palette;
+
+/// Drawn in place of a double-width glyph that doesn't have room for its second column
+pub const WIDE_GLYPH_CUTOFF: &str = "›";
 
 //  ###: Viewport
 pub struct Viewport<'a> {
 	source: &'a CameraView,
+	palette: Option<&'a Palette>,
 	// these are the tui-rs attributes
 	block: Option<Block<'a>>,
 	style: Style,
@@ -41,11 +46,21 @@ impl<'a> Widget for Viewport<'a> {
 			return;
 		}
 		// We are certain of a valid drawing area, so let's gooooo
+		let palette = self.palette.cloned().unwrap_or_default();
 		for map_y in area.top()..area.bottom() {        // Hooray
 			for map_x in area.left()..area.right() {      // for 1:1 mapping!
 				let index = xy_to_index(map_x.into(), map_y.into(), self.source.width as usize);
-				let tilestyle = Style::default().fg(Indexed(self.source.output[index].fg)).bg(Indexed(self.source.output[index].bg));
-				buf.set_string(map_x, map_y, &self.source.output[index].glyph, tilestyle);
+				let cell = &self.source.output[index];
+				// A blank cell is the right half of a double-width glyph, which ratatui has already accounted for
+				if cell.is_blank() { continue; }
+				let tilestyle = Style::default().fg(palette.color(cell.fg)).bg(palette.color(cell.bg))
+					.add_modifier(cell.modifiers());
+				// A double-width glyph in the last column would spill over the border, so it gets cut down to a marker
+				if glyph_width(&cell.glyph) > (area.right() - map_x) as usize {
+					buf.set_string(map_x, map_y, WIDE_GLYPH_CUTOFF, tilestyle);
+				} else {
+					buf.set_string(map_x, map_y, &cell.glyph, tilestyle);
+				}
 			}
 		}
 	}
@@ -54,6 +69,7 @@ impl <'a> Viewport<'a> {
 	pub fn new(new_source: &'a CameraView) -> Viewport<'a> {
 		Viewport {
 			source: new_source,
+			palette: None,
 			block: None,
 			style: Style::default(),
 			align: Alignment::Left,
@@ -64,6 +80,10 @@ impl <'a> Viewport<'a> {
 		self.source = new_source;
 		self
 	}
+	pub fn palette(mut self, palette: &'a Palette) -> Viewport<'a> {
+		self.palette = Some(palette);
+		self
+	}
 	pub fn block(mut self, block: Block<'a>) -> Viewport<'a> {
 		self.block = Some(block);
 		self
@@ -0,0 +1,234 @@
+// fov.rs
+// Provides the field of view calculations, using symmetric shadowcasting so that seeing is always mutual
+
+// ###: EXTERNAL LIBS
+use bevy::reflect::Reflect;
+use bracket_pathfinding::prelude::{field_of_view, Point};
+use serde::{Deserialize, Serialize};
+
+// ###: INTERNAL LIBS
+use crate::worldmap::WorldMap;
+
+// ###: METHODS
+/// Works out which tiles can be seen from the origin within the given range, using the chosen algorithm
+/// The result is always deduplicated and always inside the map's bounds, whichever algorithm is used
+pub fn visible_points(algorithm: FovAlgorithm, origin: Point, range: i32, map: &WorldMap) -> Vec<Point> {
+	let mut points = match algorithm {
+		FovAlgorithm::Symmetric => { symmetric_fov(origin, range, map) }
+		FovAlgorithm::Bracket   => { field_of_view(origin, range, map) }
+	};
+	points.retain(|p| map.in_bounds(p.x, p.y));
+	points.sort_by_key(|p| (p.y, p.x));
+	points.dedup();
+	points
+}
+/// Symmetric shadowcasting on the map's opacity grid: if A can see B, then B can see A from the same range
+/// Distances are compared as whole-number squares, so the same inputs always give the same circle
+/// Based on Albert Ford's description of the algorithm, https://www.albertford.com/shadowcasting/
+pub fn symmetric_fov(origin: Point, range: i32, map: &WorldMap) -> Vec<Point> {
+	let mut points = Vec::new();
+	if !map.in_bounds(origin.x, origin.y) { return points; }
+	points.push(origin);
+	for quadrant in Quadrant::ALL {
+		let mut caster = Caster { origin, range, quadrant, map, points: &mut points };
+		caster.scan(Row { depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1) });
+	}
+	points
+}
+
+// ###: COMPLEX TYPES
+//  ##: FovAlgorithm
+/// The field of view calculations that are available; bracket's is only kept around for comparison
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum FovAlgorithm {
+	#[default]
+	Symmetric,
+	Bracket,
+}
+impl FovAlgorithm {
+	/// Returns the other algorithm, for the menu toggle
+	pub fn next(&self) -> FovAlgorithm {
+		match self {
+			FovAlgorithm::Symmetric => { FovAlgorithm::Bracket }
+			FovAlgorithm::Bracket   => { FovAlgorithm::Symmetric }
+		}
+	}
+}
+//  ##: Quadrant
+/// One of the four 90-degree wedges that the shadowcaster works through; depth runs away from the origin and col runs
+/// across the wedge
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Quadrant {
+	North,
+	East,
+	South,
+	West,
+}
+impl Quadrant {
+	const ALL: [Quadrant; 4] = [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West];
+	fn transform(&self, origin: Point, depth: i32, col: i32) -> Point {
+		match self {
+			Quadrant::North => { Point::new(origin.x + col, origin.y - depth) }
+			Quadrant::South => { Point::new(origin.x + col, origin.y + depth) }
+			Quadrant::East  => { Point::new(origin.x + depth, origin.y + col) }
+			Quadrant::West  => { Point::new(origin.x - depth, origin.y + col) }
+		}
+	}
+}
+//  ##: Slope
+/// An exact fraction, so that no floating point rounding can creep into the shadow edges; den is always positive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Slope {
+	num: i32,
+	den: i32,
+}
+impl Slope {
+	fn new(num: i32, den: i32) -> Slope {
+		Slope { num, den }
+	}
+	/// The slope of the given tile's near edge, as seen from the origin
+	fn of_tile(depth: i32, col: i32) -> Slope {
+		Slope::new(2 * col - 1, 2 * depth)
+	}
+}
+//  ##: Row
+/// A single row of tiles within a quadrant, bounded by the start and end slopes of the light that reaches it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Row {
+	depth: i32,
+	start: Slope,
+	end: Slope,
+}
+impl Row {
+	/// The first and last columns that the row's slopes cover; ties at the start round up and ties at the end round down
+	fn cols(&self) -> std::ops::RangeInclusive<i32> {
+		let min_col = (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den);
+		let max_col = -((self.end.den - 2 * self.depth * self.end.num).div_euclid(2 * self.end.den));
+		min_col..=max_col
+	}
+	fn next(&self) -> Row {
+		Row { depth: self.depth + 1, ..*self }
+	}
+	/// A floor tile is only visible if its center lies inside the row's slopes, which is what keeps the result symmetric
+	fn is_symmetric(&self, col: i32) -> bool {
+		col * self.start.den >= self.depth * self.start.num
+		&& col * self.end.den <= self.depth * self.end.num
+	}
+}
+//  ##: Caster
+/// Carries the shadowcaster's state through its scan of a single quadrant
+struct Caster<'a> {
+	origin: Point,
+	range: i32,
+	quadrant: Quadrant,
+	map: &'a WorldMap,
+	points: &'a mut Vec<Point>,
+}
+impl Caster<'_> {
+	fn scan(&mut self, mut row: Row) {
+		if row.depth > self.range { return; }
+		let mut prev_wall: Option<bool> = None;
+		for col in row.cols() {
+			let point = self.quadrant.transform(self.origin, row.depth, col);
+			let is_wall = self.is_wall(point);
+			if (is_wall || row.is_symmetric(col)) && self.in_range(row.depth, col) && self.map.in_bounds(point.x, point.y) {
+				self.points.push(point);
+			}
+			if prev_wall == Some(true) && !is_wall {
+				row.start = Slope::of_tile(row.depth, col);
+			}
+			if prev_wall == Some(false) && is_wall {
+				let mut next_row = row.next();
+				next_row.end = Slope::of_tile(row.depth, col);
+				self.scan(next_row);
+			}
+			prev_wall = Some(is_wall);
+		}
+		if prev_wall == Some(false) {
+			self.scan(row.next());
+		}
+	}
+	/// Anything off the edge of the map blocks sight just like a wall does
+	fn is_wall(&self, point: Point) -> bool {
+		if !self.map.in_bounds(point.x, point.y) { return true; }
+		self.map.opaque_tiles[self.map.to_index(point.x, point.y)]
+	}
+	fn in_range(&self, depth: i32, col: i32) -> bool {
+		depth * depth + col * col <= self.range * self.range
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy_turborand::{DelegatedRng, GlobalRng};
+	use crate::worldmap::Tile;
+	use super::*;
+	/// Builds a square map with walls scattered over about a third of it, the same walls for the same seed
+	fn random_map(seed: u64, size: usize) -> WorldMap {
+		let mut rng = GlobalRng::with_seed(seed);
+		let mut map = WorldMap::new(size, size);
+		for tile in map.tiles.iter_mut() {
+			*tile = if rng.chance(0.3) { Tile::new_wall() } else { Tile::new_floor() };
+		}
+		map.update_tilemaps();
+		map
+	}
+	//  ##: Symmetry
+	#[test]
+	fn seeing_is_mutual_on_random_maps() {
+		let (size, range) = (12, 6);
+		for seed in 0..40 {
+			let map = random_map(seed, size);
+			let floors: Vec<Point> = (0..size as i32).flat_map(|y| (0..size as i32).map(move |x| Point::new(x, y)))
+				.filter(|p| !map.opaque_tiles[map.to_index(p.x, p.y)])
+				.collect();
+			let views: Vec<Vec<Point>> = floors.iter().map(|x| visible_points(FovAlgorithm::Symmetric, *x, range, &map)).collect();
+			for (a_index, a_posn) in floors.iter().enumerate() {
+				for (b_index, b_posn) in floors.iter().enumerate() {
+					assert_eq!(views[a_index].contains(b_posn), views[b_index].contains(a_posn),
+						"seed {}: {:?} and {:?} don't see each other alike", seed, a_posn, b_posn);
+				}
+			}
+		}
+	}
+	//  ##: Bounds and duplicates
+	#[test]
+	fn every_point_is_unique_and_on_the_map() {
+		let map = random_map(7, 8);
+		for algorithm in [FovAlgorithm::Symmetric, FovAlgorithm::Bracket] {
+			// Standing in a corner with a long range makes both algorithms reach past the edges
+			let points = visible_points(algorithm, Point::new(0, 0), 20, &map);
+			assert!(points.iter().all(|p| map.in_bounds(p.x, p.y)), "{:?}", algorithm);
+			let mut deduped = points.clone();
+			deduped.dedup();
+			assert_eq!(deduped, points, "{:?}", algorithm);
+		}
+		assert!(symmetric_fov(Point::new(-1, 3), 5, &map).is_empty());
+	}
+	#[test]
+	fn walls_block_sight_but_can_be_seen() {
+		let mut map = WorldMap::new(7, 1);
+		for tile in map.tiles.iter_mut() {
+			*tile = Tile::new_floor();
+		}
+		map.tiles[3] = Tile::new_wall();
+		map.update_tilemaps();
+		let points = visible_points(FovAlgorithm::Symmetric, Point::new(0, 0), 10, &map);
+		assert_eq!(points, vec![Point::new(0, 0), Point::new(1, 0), Point::new(2, 0), Point::new(3, 0)]);
+	}
+	#[test]
+	fn the_range_is_a_circle() {
+		let mut map = WorldMap::new(11, 11);
+		for tile in map.tiles.iter_mut() {
+			*tile = Tile::new_floor();
+		}
+		map.update_tilemaps();
+		let points = visible_points(FovAlgorithm::Symmetric, Point::new(5, 5), 3, &map);
+		assert!(points.contains(&Point::new(8, 5)));
+		assert!(points.contains(&Point::new(7, 7)));
+		assert!(!points.contains(&Point::new(8, 7)));
+	}
+}
+
+// EOF
@@ -1,22 +1,55 @@
 // lib.rs
 // July 12 2023
 
+// Provides the airlocks and the control panels that cycle them
+pub mod airlock;
 // Provides the item builder
 pub mod artisan;
+// Provides the player's map bookmarks and notes, and the auto-travel that takes them there
+pub mod bookmarks;
 // Provides the abstraction onto the game world for rendering onto a display
 pub mod camera;
+// Provides the entity headcount used for debugging and balancing
+pub mod census;
+// Provides the boundary between the ship's decks, for taking a deck out of the World and putting it back
+pub mod decks;
 // Collection of smaller Components for Bevy that aren't directly associated with a particular type
 pub mod components;
 // Provides the GameEngine with an internal Bevy instance and related methods
 pub mod engine;
+// Provides the field of view calculations
+pub mod fov;
 // Provides the prototypes and logic for the GameWorld world model object
 pub mod worldmap;
 // Provides the map builder
 pub mod mason;
+// Provides the reusable mission objectives
+pub mod objectives;
+// Provides the ship's power grid
+pub mod power;
+// Provides the inventory quick-slots on the number keys
+pub mod quickslots;
 // Provides the REXpaint assets and handlers
 pub mod rex_assets;
+// Provides the maintenance task queue and the AI for the ship's robots
+pub mod robot;
+// Provides the saved-game comparison used for debugging save and load
+pub mod savediff;
+// Provides the scripted starting scenarios and the missions that can be picked for a new game
+pub mod scenarios;
+// Provides the ship's own log of what's been happening aboard, readable from the AccessPorts
+pub mod shiplog;
+// Provides the text-art map builder for trying things out on small maps
+#[cfg(any(test, feature = "testkit"))]
+pub mod testmap;
+// Provides the salvage bin and the score that the player earns by filling it
+pub mod salvage;
+// Provides the authorization checks that decide who can get through a lock
+pub mod security;
 // Collection of Systems for Bevy that aren't directly associated with a particular type
 pub mod sys;
+// Provides the one-time hints for new players
+pub mod tutorial;
 // Provide's the player's PLANQ
 pub mod planq;
 
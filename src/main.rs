@@ -3,6 +3,7 @@
 
 // ###: EXTERNAL LIBS
 use std::io;
+use std::time::{Duration, Instant};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 extern crate simplelog;
@@ -18,6 +19,10 @@ use spacegame::engine::{
 	tui::TuiEvent, // this line is required for disambiguiation vs Bevy
 };
 
+// ###: CONSTANTS
+/// Holding down a movement key will move the player at most once per this many milliseconds
+const KEY_REPEAT_INTERVAL: u64 = 120;
+
 // ###: MAIN METHOD
 fn main() -> AppResult<()> {
 	// HINT: Set the LevelFilter below to change how much logging you wish to see
@@ -46,6 +51,7 @@ fn main() -> AppResult<()> {
 	let events = TuiEventHandler::new(250);
 	let mut tui = Tui::new(terminal, events);
 	tui.init()?;
+	let mut throttle = InputThrottle::new(Duration::from_millis(KEY_REPEAT_INTERVAL));
 	//  ##: Set up the game engine
 	let mut eng = GameEngine::new(tsize);
 	//  ##: Start the game loop
@@ -57,9 +63,14 @@ fn main() -> AppResult<()> {
 		// Handle input events
 		match tui.events.next()? {
 			TuiEvent::Tick           => eng.tick(),
-			TuiEvent::Key(key_event) => key_parser(key_event, &mut eng)?,
+			TuiEvent::Key(key_event) => {
+				// Typing into the PLANQ's CLI is never throttled, the letters there aren't movement commands
+				if eng.is_cli_open() || throttle.admit(&key_event, Instant::now()) {
+					key_parser(key_event, &mut eng)?
+				}
+			}
 			TuiEvent::Mouse(_)       => { }
-			TuiEvent::Resize(_, _)   => { }
+			TuiEvent::Resize(w, h)   => { eng.resize(w, h) }
 		}
 	}
 	//  ##: The game loop has stopped, so exit the program
@@ -13,6 +13,7 @@ use crate::mason::*;
  *      width    - integer, width of entire tilemap
  *      height   - integer, height of entire tilemap
  *      tilemap  - array of strings, where each string.length == width, and tilemap.length == height
+ *      environment (optional) - string, one of failing_life_support, no_atmosphere or unpowered; nominal if absent
  *    level_Y:
  *      ...
  *    graph:
@@ -25,6 +26,15 @@ use crate::mason::*;
  *        name   - string
  *        points - array of 2 arrays of 3 integers (-> 2 Positions)
  *        twoway - bool, determines if the portal can be used in the reverse direction
+ *    generators (optional):
+ *      name     - string
+ *    power (optional):
+ *      zone     - string, the name of a room
+ *      source   - string, the name of the generator or the other room that this room draws its power from
+ *    airlocks (optional):
+ *      room     - string, the name of the airlock chamber
+ *      inner    - array of 3 integers (-> 1 Position), the doorway on the ship's side of the chamber
+ *      outer    - array of 3 integers (-> 1 Position), the doorway that opens onto whatever's outside
  *  }
  */
 /* The hierarchy of the Model object:
@@ -50,6 +60,12 @@ pub struct JsonBucket {
 	pub map_list: Vec<JsonMap>,
 	pub room_list: Vec<JsonRoom>,
 	pub ladder_list: Vec<JsonPortal>,
+	#[serde(default)]
+	pub generator_list: Vec<String>,
+	#[serde(default)]
+	pub power_list: Vec<JsonPowerFeed>,
+	#[serde(default)]
+	pub airlock_list: Vec<JsonAirlock>,
 }
 //   ##: JsonRoom
 /// A JSON-formatted representation of a room
@@ -115,6 +131,32 @@ impl Default for JsonPortal {
 		}
 	}
 }
+//   ##: JsonPowerFeed
+/// A JSON-formatted representation of one link in the ship's power grid
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct JsonPowerFeed {
+	pub zone: String,
+	pub source: String,
+}
+//   ##: JsonAirlock
+/// A JSON-formatted representation of an airlock chamber and the two doorways that its panel runs
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct JsonAirlock {
+	pub room: String,
+	pub inner: Vec<usize>,
+	pub outer: Vec<usize>,
+}
+impl JsonAirlock {
+	/// Converts the chamber's doorways into the AirlockLink that the panel is linked up with
+	pub fn to_link(&self) -> AirlockLink {
+		let to_posn = |x: &Vec<usize>| Position::new(x[0] as i32, x[1] as i32, x[2] as i32);
+		AirlockLink {
+			room: self.room.clone(),
+			inner: to_posn(&self.inner),
+			outer: to_posn(&self.outer),
+		}
+	}
+}
 //   ##: JsonMap
 /// A JSON-formatted representation of a 'raw' tilemap
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -122,6 +164,8 @@ pub struct JsonMap {
 	pub tilemap: Vec<String>,
 	pub width: usize,
 	pub height: usize,
+	#[serde(default)]
+	pub environment: String,
 }
 impl From<JsonMap> for WorldMap {
 	fn from(input: JsonMap) -> Self {
@@ -360,9 +360,10 @@ impl GraphRoom {
 		// if at any point there is a failure to match, try a new point
 		// repeat until either a valid starting point is found,
 		// or all starting points are exhausted
-		let possible_starts: Vec<Position> = self.new_interior.iter() // All points in the interior of the room...
+		let mut possible_starts: Vec<Position> = self.new_interior.iter() // All points in the interior of the room...
 			.filter(|x| *x.1 == template.shape[0].1 || *x.1 == CellType::Open) // ...which have the same CellType as the template's first point, or are Open...
 			.map(|x| *x.0).collect(); // ...mapped into a Vec<Position> and gathered up
+		possible_starts.sort(); // The HashMap's order changes from run to run, which would spoil a seeded placement
 		if possible_starts.is_empty() { return None; } // Early return if there were no candidate points at all
 		// start with a list of all points that match the type of the template's ref_point
 		// choose a point in the list randomly
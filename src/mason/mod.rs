@@ -5,24 +5,32 @@
 use simplelog::*;
 use std::fs::File;
 use std::io::BufReader;
+use std::sync::mpsc::Sender;
 //use bevy_turborand::*;
 
 //  ###: INTERNAL LIBRARIES
+use crate::airlock::AirlockLink;
 use crate::components::Position;
+use crate::power::PowerGrid;
 use crate::worldmap::*;
 pub mod rexpaint_loader;
 pub mod json_map;
 use json_map::*;
 pub mod logical_map;
 use logical_map::*;
+pub mod seeding;
 
 //  ###: TRAITS
 //   ##: WorldBuilder
-pub trait WorldBuilder {
-	fn build_world(&mut self);
+// The builder has to be Send so that it can be run on a thread of its own, see engine::loading
+pub trait WorldBuilder: Send {
+	/// Builds the world, reporting each stage over the given channel as it starts, if there is one
+	fn build_world(&mut self, progress: Option<&Sender<BuildProgress>>);
 	fn get_model(&self) -> WorldModel;
 	fn get_essential_item_requests(&self) -> Vec<(String, Position)>;
 	fn get_additional_item_requests(&self) -> Vec<(String, String)>;
+	fn get_power_grid(&self) -> PowerGrid;
+	fn get_airlocks(&self) -> Vec<AirlockLink>;
 }
 /// Loads a worldmodel from a pregenerated JSON file and sets it up for gameplay
 pub fn get_world_builder() -> Box<dyn WorldBuilder> {
@@ -30,16 +38,35 @@ pub fn get_world_builder() -> Box<dyn WorldBuilder> {
 }
 
 //  ###: COMPLEX TYPES
+//   ##: BuildProgress
+/// A progress report from a WorldBuilder, for showing on the loading screen
+pub enum BuildProgress {
+	Stage(String, u8), // The name of the stage that's just started, and how far along the whole build is, in percent
+	Done(Box<WorldPlan>), // The finished ship, with everything on it already placed and waiting to be spawned
+}
+//   ##: WorldPlan
+/// Everything that a new game needs to set up its World, worked out ahead of time so that none of it has to be done
+/// while the World is waiting: the model with every spawn already placed in it, the power grid that runs through it,
+/// and the airlocks that need their panels linked up once the doors are in
+pub struct WorldPlan {
+	pub model: WorldModel,
+	pub spawns: Vec<(String, Position)>, // The item name and where to put it, in the order that they should be built
+	pub power_grid: PowerGrid,
+	pub airlocks: Vec<AirlockLink>,
+}
 //   ##: JsonWorldBuilder
 #[derive(Default)]
 pub struct JsonWorldBuilder {
 	model: WorldModel,
 	enty_list: Vec<(String, Position)>,
-	addtl_items: Vec<(String, String)>
+	addtl_items: Vec<(String, String)>,
+	grid: PowerGrid,
+	airlocks: Vec<AirlockLink>,
 }
 impl JsonWorldBuilder {
 	/// Extracts, parses, and stores the furniture files in local data storage
-	pub fn load_json_file(&mut self, file_path: &str) {
+	pub fn load_json_file(&mut self, file_path: &str, progress: Option<&Sender<BuildProgress>>) {
+		report_stage(progress, "Reading the ship's plans", 0);
 		//debug!("* opening input file at {}", file_path);
 		let input_data = if let Ok(file) = File::open(file_path) {
 			let reader = BufReader::new(file);
@@ -52,11 +79,13 @@ impl JsonWorldBuilder {
 			JsonBucket::default()
 		};
 		// 1: Use the map lists to create the map stack and put it into the model
+		report_stage(progress, "Laying out the decks", 20);
 		let mut hallway_tiles: Vec<Vec<Position>> = Vec::new();
 		let mut logical_door_list: Vec<Position> = Vec::new();
 		let mut _furniture_requests: Vec<(String, String)> = Vec::new();
 		for (z_posn, input_map) in input_data.map_list.iter().enumerate() {
 			let mut new_map = WorldMap::new(input_map.width, input_map.height);
+			new_map.environment = LevelEnvironment::from_name(&input_map.environment);
 			let mut current_hallway = Vec::new();
 			for (y_posn, line) in input_map.tilemap.iter().enumerate() {
 				for (x_posn, tile) in line.chars().enumerate() {
@@ -83,6 +112,7 @@ impl JsonWorldBuilder {
 			hallway_tiles.push(current_hallway);
 		}
 		// 2: Use the room list to create the topo graph of the layout
+		report_stage(progress, "Connecting the rooms", 40);
 		// Iterate on all the rooms in the input list
 		for cur_room in input_data.room_list.iter() {
 			let room_index: usize;
@@ -125,6 +155,7 @@ impl JsonWorldBuilder {
 			}
 		}
 		// 2.5: Use the logical door list to populate those tiles in the logical maps of each room
+		report_stage(progress, "Hanging the doors", 60);
 		for posn in logical_door_list.iter() {
 			// FIXME: NEED to add Margin tiles around the door
 			// Get the room which contains the given position
@@ -137,6 +168,7 @@ impl JsonWorldBuilder {
 			self.model.layout.add_door_to_map_at(*posn);
 		}
 		// 3: use the portal list to create the list of ladders that need to be spawned
+		report_stage(progress, "Fitting the ladders", 70);
 		for portal in input_data.ladder_list.iter() {
 			// The tiles at the target positions need to be set to TileType::Stairway
 			let left_side = Position::new(portal.points[0][0] as i32, portal.points[0][1] as i32, portal.points[0][2] as i32);
@@ -151,6 +183,14 @@ impl JsonWorldBuilder {
 			// Add the graph connection between the two rooms using the manual method
 			self.model.add_portal(left_side, right_side, true);
 		}
+		// 4: use the generator and power lists to lay out the power grid
+		report_stage(progress, "Wiring the power grid", 80);
+		self.grid = PowerGrid::new(input_data.generator_list.clone());
+		for feed in input_data.power_list.iter() {
+			self.grid.connect(&feed.zone, &feed.source);
+		}
+		// 5: the airlock list says which doorways each airlock chamber's panel runs
+		self.airlocks = input_data.airlock_list.iter().map(|x| x.to_link()).collect();
 		// DEBUG: a bunch of different output formats for mapgen feedback
 		//for room in self.model.layout.rooms.iter() {
 		//	debug!("* new room: {}", room.name);
@@ -161,8 +201,8 @@ impl JsonWorldBuilder {
 	}
 }
 impl WorldBuilder for JsonWorldBuilder {
-	fn build_world(&mut self) {
-		JsonWorldBuilder::load_json_file(self, "resources/test_ship_v3.json");
+	fn build_world(&mut self, progress: Option<&Sender<BuildProgress>>) {
+		JsonWorldBuilder::load_json_file(self, "resources/test_ship_v3.json", progress);
 	}
 	fn get_model(&self) -> WorldModel {
 		self.model.clone()
@@ -173,9 +213,22 @@ impl WorldBuilder for JsonWorldBuilder {
 	fn get_additional_item_requests(&self) -> Vec<(String, String)> {
 		self.addtl_items.clone()
 	}
+	fn get_power_grid(&self) -> PowerGrid {
+		self.grid.clone()
+	}
+	fn get_airlocks(&self) -> Vec<AirlockLink> {
+		self.airlocks.clone()
+	}
 }
 
 //  ###: SIMPLE TYPES AND HELPERS
+/// Sends a progress report if anyone's listening; if the listener has gone away, ie the load was cancelled, the build
+/// just carries on quietly and its result is thrown away at the end
+fn report_stage(progress: Option<&Sender<BuildProgress>>, stage: &str, percent: u8) {
+	if let Some(sender) = progress {
+		let _ = sender.send(BuildProgress::Stage(stage.to_string(), percent));
+	}
+}
 //   ##: Floating-point (for fractional values) vector math functions
 /// Returns a vector of Positions that describe a direct line/path between the two inputs
 fn get_line(first: &Position, second: &Position) -> Vec<Position> {
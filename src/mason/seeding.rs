@@ -0,0 +1,79 @@
+// mason/seeding.rs
+// Provides the procedural seeding pass that scatters loose items around the ship's rooms, so that every new game isn't
+// laid out exactly the same as the last one
+
+//  ###: EXTERNAL LIBRARIES
+use bevy_turborand::{DelegatedRng, GlobalRng};
+
+//  ###: INTERNAL LIBRARIES
+use crate::artisan::ItemBuilder;
+use crate::components::Position;
+use crate::mason::logical_map::CellType;
+use crate::worldmap::*;
+
+//  ###: COMPLEX TYPES
+//   ##: SpawnDensity
+/// Controls how thickly the seeding pass scatters items around the ship
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpawnDensity {
+	pub tiles_per_item: usize, // One item is placed for every this many open tiles in a room
+	pub max_per_room: usize,
+	pub pool: Vec<String>, // The item names to pick from; if empty, every Portable item that the ItemBuilder knows about
+	pub skip_rooms: Vec<String>, // Any room with one of these in its name is left alone
+}
+impl Default for SpawnDensity {
+	fn default() -> SpawnDensity {
+		SpawnDensity {
+			tiles_per_item: 40,
+			max_per_room: 3,
+			pool: Vec::new(),
+			// The maintenance robots tidy up anything lying around in the hallways, so there's no point
+			skip_rooms: vec!["hallway".to_string()],
+		}
+	}
+}
+
+//  ###: METHODS
+/// Picks out spawnpoints for a scattering of loose items across every room in the model, at the given density
+/// The same seed always produces the same placements for the same map; nothing is placed on anything but open floor,
+/// on top of any of the reserved spawns, or in front of a door
+/// Returns the list of (item name, position) pairs, ready to be handed to the ItemBuilder
+pub fn seed_items(model:    &mut WorldModel,
+	                artisan:  &ItemBuilder,
+	                density:  &SpawnDensity,
+	                seed:     u64,
+	                reserved: &[(String, Position)],
+) -> Vec<(String, Position)> {
+	let mut rng = GlobalRng::with_seed(seed);
+	let pool = if density.pool.is_empty() { artisan.portable_item_names() } else { density.pool.clone() };
+	if pool.is_empty() || density.tiles_per_item == 0 { return Vec::new(); }
+	let doorways: Vec<Position> = reserved.iter().filter(|x| x.0 == "door").map(|x| x.1).collect();
+	let mut taken: Vec<Position> = reserved.iter().map(|x| x.1).collect();
+	let mut output = Vec::new();
+	for room_index in 0..model.layout.rooms.len() {
+		let room_name = model.layout.rooms[room_index].name.clone();
+		if density.skip_rooms.iter().any(|x| room_name.contains(x.as_str())) { continue; }
+		let open_tiles = model.layout.rooms[room_index].new_interior.values().filter(|x| **x == CellType::Open).count();
+		let count = (open_tiles / density.tiles_per_item).min(density.max_per_room);
+		for _ in 0..count {
+			let item_name = if let Some(value) = rng.sample(&pool) { value.clone() } else { continue };
+			let shape = if let Some(value) = artisan.get_random_shape(&item_name, &mut rng) { value } else { continue };
+			let spawns = if let Some(value) = model.find_spawnpoint_in(&room_name, shape, &mut rng) { value } else { continue };
+			if spawns.iter().all(|(_, posn)| is_clear_for_seeding(model, *posn, &taken, &doorways)) {
+				taken.extend(spawns.iter().map(|x| x.1));
+				output.extend(spawns);
+			}
+		}
+	}
+	output
+}
+
+//  ###: UTILITIES
+/// Returns true if the seeding pass may put something at the given Position: it has to be open floor that nothing
+/// else has claimed, and it can't be in a doorway or on the tiles directly in front of one
+fn is_clear_for_seeding(model: &WorldModel, posn: Position, taken: &[Position], doorways: &[Position]) -> bool {
+	if !model.contains(posn) || model.get_tiletype_at(posn) != TileType::Floor || taken.contains(&posn) { return false; }
+	!doorways.iter().any(|door| door.z == posn.z && (door.x - posn.x).abs() + (door.y - posn.y).abs() <= 1)
+}
+
+// EOF
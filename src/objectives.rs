@@ -0,0 +1,141 @@
+// objectives.rs
+// Provides the reusable mission objectives, which watch the state of the world and announce when they've been met
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::event::*;
+use crate::engine::messagelog::*;
+
+// ###: BEVY SYSTEMS
+/// Checks each of the unfinished seal objectives, and marks any whose doors are now all closed and locked as complete
+pub fn seal_objective_system(mut owriter:    EventWriter<ObjectiveComplete>,
+	                         mut msglog:     ResMut<MessageLog>,
+	                         mut objectives: ResMut<Objectives>,
+	                         d_query:        Query<(&Openable, &Lockable)>,
+) {
+	if objectives.seals.iter().all(|x| x.is_complete) { return; }
+	for seal in objectives.seals.iter_mut().filter(|x| !x.is_complete) {
+		if !seal.is_sealed(|door| d_query.get(door).ok().map(|(d_open, d_lock)| (d_open.is_open, d_lock.is_locked))) { continue; }
+		seal.is_complete = true;
+		msglog.tell_player(format!("[[fg:green]]OBJECTIVE COMPLETE:[[end]] {}", seal.name).as_str());
+		owriter.send(ObjectiveComplete { name: seal.name.clone() });
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: Objectives
+/// Holds every objective that's been set up for the current game, finished or not
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct Objectives {
+	pub seals: Vec<SealObjective>,
+}
+impl MapEntities for Objectives {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for door in self.seals.iter_mut().flat_map(|x| x.doors.iter_mut()) {
+			*door = map_entity(entity_mapper, *door);
+		}
+	}
+}
+impl Objectives {
+	pub fn new() -> Objectives {
+		Objectives::default()
+	}
+	/// Sets up a new objective to close and lock all of the given doors
+	pub fn add_seal(&mut self, name: &str, doors: Vec<Entity>) {
+		self.seals.push(SealObjective::new(name, doors));
+	}
+	/// Returns true if the named objective has been completed
+	pub fn is_complete(&self, name: &str) -> bool {
+		self.seals.iter().any(|x| x.name == name && x.is_complete)
+	}
+}
+//  ##: SealObjective
+/// An objective that's met once every one of its doors is closed and locked, ie "seal the breach"
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct SealObjective {
+	pub name: String,
+	pub doors: Vec<Entity>,
+	pub is_complete: bool,
+}
+impl SealObjective {
+	pub fn new(name: &str, doors: Vec<Entity>) -> SealObjective {
+		SealObjective {
+			name: name.to_string(),
+			doors,
+			is_complete: false,
+		}
+	}
+	/// Returns true if every door is closed and locked, given a way to look up each door's (is_open, is_locked) state
+	/// A door that can't be found doesn't count as sealed, and neither does an objective with no doors at all
+	pub fn is_sealed(&self, state_of: impl Fn(Entity) -> Option<(bool, bool)>) -> bool {
+		!self.doors.is_empty() && self.doors.iter().all(|door| state_of(*door).map_or(false, |(is_open, is_locked)| !is_open && is_locked))
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use super::*;
+	/// Sets up a headless App with the seal_objective_system, and a seal objective on two open, unlocked doors
+	fn seal_app() -> (App, Vec<Entity>) {
+		let mut app = App::new();
+		app.add_event::<ObjectiveComplete>()
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(Objectives::new())
+		.add_systems(Update, seal_objective_system);
+		let doors: Vec<Entity> = (0..2).map(|_| app.world.spawn((Openable::new(true, "/", "+"), Lockable::default())).id()).collect();
+		app.world.resource_mut::<Objectives>().add_seal("Seal the breach", doors.clone());
+		(app, doors)
+	}
+	fn seal_door(app: &mut App, door: Entity) {
+		app.world.get_mut::<Openable>(door).unwrap().is_open = false;
+		app.world.get_mut::<Lockable>(door).unwrap().is_locked = true;
+	}
+	fn completions(app: &App) -> Vec<String> {
+		let events = app.world.resource::<Events<ObjectiveComplete>>();
+		events.get_reader().iter(events).map(|x| x.name.clone()).collect()
+	}
+	#[test]
+	fn sealing_the_last_door_completes_the_objective() {
+		let (mut app, doors) = seal_app();
+		seal_door(&mut app, doors[0]);
+		app.update();
+		assert!(!app.world.resource::<Objectives>().is_complete("Seal the breach"));
+		assert!(completions(&app).is_empty());
+		seal_door(&mut app, doors[1]);
+		app.update();
+		assert!(app.world.resource::<Objectives>().is_complete("Seal the breach"));
+		assert_eq!(completions(&app), vec!["Seal the breach".to_string()]);
+	}
+	#[test]
+	fn a_closed_but_unlocked_door_does_not_count() {
+		let (mut app, doors) = seal_app();
+		seal_door(&mut app, doors[0]);
+		app.world.get_mut::<Openable>(doors[1]).unwrap().is_open = false;
+		app.update();
+		assert!(!app.world.resource::<Objectives>().is_complete("Seal the breach"));
+	}
+	#[test]
+	fn the_objective_only_completes_once() {
+		let (mut app, doors) = seal_app();
+		seal_door(&mut app, doors[0]);
+		seal_door(&mut app, doors[1]);
+		app.update();
+		app.update();
+		assert_eq!(completions(&app).len(), 1);
+	}
+	#[test]
+	fn a_missing_door_is_never_sealed() {
+		let seal = SealObjective::new("Seal the hold", vec![Entity::PLACEHOLDER]);
+		assert!(!seal.is_sealed(|_| None));
+		assert!(!SealObjective::new("Seal nothing", Vec::new()).is_sealed(|_| Some((false, true))));
+	}
+}
+
+// EOF
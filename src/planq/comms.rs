@@ -0,0 +1,235 @@
+// planq/comms.rs
+// Provides the PLANQ's radio link, which carries scripted messages addressed to the player
+
+// ###: EXTERNAL LIBRARIES
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+// ###: INTERNAL LIBRARIES
+use crate::engine::event::PlayerMoved;
+use crate::engine::messagelog::*;
+use crate::planq::*;
+use crate::worldmap::WorldModel;
+
+// ###: BEVY SYSTEMS
+/// Counts down the delivery delays on any queued comms, and delivers them to the PLANQ once it's able to receive them
+pub fn planq_comms_system(time:       Res<Time>,
+	                        mut msglog: ResMut<MessageLog>,
+	                        mut comms:  ResMut<PlanqComms>,
+	                        planq:      Res<PlanqData>,
+) {
+	for msg in comms.outbox.iter_mut() {
+		msg.delay.tick(time.delta());
+	}
+	// The PLANQ can't receive anything unless it's switched on, finished booting, and on the player's person
+	let is_receiving = planq.power_is_on
+		&& planq.is_carried
+		&& (planq.cpu_mode == PlanqCPUMode::Idle || planq.cpu_mode == PlanqCPUMode::Working);
+	if !is_receiving { return; }
+	// Messages are delivered strictly in the order they were sent, so a later message with a shorter delay will wait
+	while comms.outbox.first().map_or(false, |x| x.delay.finished()) {
+		let msg = comms.outbox.remove(0);
+		msglog.tell_planq(format!("[[fg:yellow]]¶[[fg:gray]]│[[fg:ltcyan]]*chirp*[[end]] New message from {}", msg.sender).as_str());
+		msglog.tell_player("Your PLANQ chirps.");
+		comms.inbox.push(msg);
+	}
+}
+
+/// Sends the message from any CommsTrigger whose room the player has just walked into; each one only goes off once
+pub fn comms_trigger_system(mut commands: Commands,
+	                          mut preader:  EventReader<PlayerMoved>,
+	                          mut comms:    ResMut<PlanqComms>,
+	                          model:        Res<WorldModel>,
+	                          t_query:      Query<(Entity, &CommsTrigger)>,
+) {
+	let mut fired = Vec::new();
+	for moved in preader.iter() {
+		let room = if let Some(value) = model.layout.get_room_name(moved.posn) { value } else { continue };
+		for (t_enty, trigger) in t_query.iter() {
+			if trigger.room != room || fired.contains(&t_enty) { continue; }
+			comms.send(&trigger.sender, &trigger.text, trigger.delay);
+			commands.entity(t_enty).despawn();
+			fired.push(t_enty);
+		}
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: PlanqComms
+/// Holds all of the PLANQ's comms traffic: the messages still in transit, and the ones that have been received
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct PlanqComms {
+	pub outbox: Vec<CommsMessage>, // Sent but not yet delivered, in order of sending
+	pub inbox: Vec<CommsMessage>, // Delivered to the PLANQ, in order of arrival
+}
+impl PlanqComms {
+	pub fn new() -> PlanqComms {
+		PlanqComms::default()
+	}
+	/// Queues a new message for the player, to be delivered after the given delay in seconds
+	pub fn send(&mut self, sender: &str, text: &str, delay: u64) {
+		self.outbox.push(CommsMessage::new(sender, text, delay));
+	}
+	/// Returns the number of delivered messages that the player hasn't read yet
+	pub fn unread_count(&self) -> usize {
+		self.inbox.iter().filter(|x| !x.is_read).count()
+	}
+	/// Produces the PLANQ's output for the `msgs` command: a summary of the inbox if no index was given, or else the
+	/// text of the specified message, which will be marked as read
+	pub fn report(&mut self, index: Option<usize>) -> Vec<String> {
+		let mut output = Vec::new();
+		match index {
+			None => {
+				if self.inbox.is_empty() {
+					output.push("No messages.".to_string());
+				}
+				for (num, msg) in self.inbox.iter().enumerate() {
+					let flag = if msg.is_read { " " } else { "*" };
+					output.push(format!("{}{:>2}: {}", flag, num, msg.sender));
+				}
+			}
+			Some(num) => {
+				if let Some(msg) = self.inbox.get_mut(num) {
+					msg.is_read = true;
+					output.push(format!("From: {}", msg.sender));
+					output.push(msg.text.clone());
+				} else {
+					output.push(format!("No message at index {}.", num));
+				}
+			}
+		}
+		output
+	}
+}
+//  ##: CommsTrigger
+/// Queues up a comms message for the player the first time that they walk into the given room, ie a call from the
+/// bridge as they arrive in engineering; each trigger gets an entity of its own, which is removed once it goes off
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct CommsTrigger {
+	pub room: String,
+	pub sender: String,
+	pub text: String,
+	pub delay: u64, // The delivery delay in seconds, counted from when the trigger goes off
+}
+impl CommsTrigger {
+	pub fn new(room: &str, sender: &str, text: &str, delay: u64) -> CommsTrigger {
+		CommsTrigger {
+			room: room.to_string(),
+			sender: sender.to_string(),
+			text: text.to_string(),
+			delay,
+		}
+	}
+}
+//  ##: CommsMessage
+/// A single message sent over the comms channel
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct CommsMessage {
+	pub sender: String,
+	pub text: String,
+	pub delay: Timer, // Counts down from the time of sending until the message is eligible for delivery
+	pub is_read: bool,
+}
+impl CommsMessage {
+	pub fn new(new_sender: &str, new_text: &str, delay: u64) -> CommsMessage {
+		CommsMessage {
+			sender: new_sender.to_string(),
+			text: new_text.to_string(),
+			delay: Timer::new(Duration::from_secs(delay), TimerMode::Once),
+			is_read: false,
+		}
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::components::Position;
+	use crate::testmap::TestMap;
+	use super::*;
+	//  ##: Delivery
+	/// Sets up a headless App with the planq_comms_system and a PLANQ in the given state, with nothing in transit
+	fn comms_app(power_is_on: bool, is_carried: bool, cpu_mode: PlanqCPUMode) -> App {
+		let mut app = App::new();
+		app.insert_resource(Time::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(PlanqComms::new())
+		.insert_resource(PlanqData { power_is_on, is_carried, cpu_mode, ..PlanqData::new() })
+		.add_systems(Update, planq_comms_system);
+		app
+	}
+	/// Returns the senders of every message in the inbox, in the order they arrived
+	fn senders(app: &App) -> Vec<String> {
+		app.world.resource::<PlanqComms>().inbox.iter().map(|x| x.sender.clone()).collect()
+	}
+	#[test]
+	fn nothing_arrives_until_the_planq_can_receive_it() {
+		for (power_is_on, is_carried, cpu_mode) in [
+			(false, true, PlanqCPUMode::Offline),
+			(true, true, PlanqCPUMode::Startup),
+			(true, true, PlanqCPUMode::Shutdown),
+			(true, false, PlanqCPUMode::Idle),
+		] {
+			let mut app = comms_app(power_is_on, is_carried, cpu_mode);
+			app.world.resource_mut::<PlanqComms>().send("Steward", "Hello", 0);
+			app.update();
+			assert!(senders(&app).is_empty(), "{} {} {:?}", power_is_on, is_carried, cpu_mode);
+			// The message waits in the outbox until the PLANQ is ready for it
+			*app.world.resource_mut::<PlanqData>() = PlanqData { power_is_on: true, is_carried: true, cpu_mode: PlanqCPUMode::Idle, ..PlanqData::new() };
+			app.update();
+			assert_eq!(senders(&app), vec!["Steward".to_string()]);
+			assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("Your PLANQ chirps."));
+		}
+	}
+	#[test]
+	fn messages_arrive_in_the_order_they_were_sent() {
+		let mut app = comms_app(true, true, PlanqCPUMode::Idle);
+		app.world.resource_mut::<PlanqComms>().send("First", "one", 5);
+		app.world.resource_mut::<PlanqComms>().send("Second", "two", 0);
+		app.update();
+		// The second one is ready to go, but it has to wait for the first
+		assert!(senders(&app).is_empty());
+		app.world.resource_mut::<PlanqComms>().outbox[0].delay.tick(Duration::from_secs(5));
+		app.update();
+		assert_eq!(senders(&app), vec!["First".to_string(), "Second".to_string()]);
+		assert!(app.world.resource::<PlanqComms>().outbox.is_empty());
+	}
+	#[test]
+	fn reading_a_message_marks_it_as_read() {
+		let mut comms = PlanqComms::new();
+		comms.inbox.push(CommsMessage::new("Steward", "Hello", 0));
+		comms.inbox.push(CommsMessage::new("Bosun", "Ahoy", 0));
+		assert_eq!(comms.unread_count(), 2);
+		assert_eq!(comms.report(None), vec!["* 0: Steward".to_string(), "* 1: Bosun".to_string()]);
+		assert_eq!(comms.report(Some(1)), vec!["From: Bosun".to_string(), "Ahoy".to_string()]);
+		assert_eq!(comms.unread_count(), 1);
+		assert_eq!(comms.report(None), vec!["* 0: Steward".to_string(), "  1: Bosun".to_string()]);
+		assert_eq!(comms.report(Some(7)), vec!["No message at index 7.".to_string()]);
+	}
+	//  ##: Triggers
+	#[test]
+	fn a_trigger_goes_off_once_when_the_player_walks_in() {
+		let mut app = App::new();
+		app.add_event::<PlayerMoved>()
+		.insert_resource(TestMap::new("#######\n#.....#\n#######").with_room("galley", Position::new(4, 1, 0), Position::new(5, 1, 0)).model)
+		.insert_resource(PlanqComms::new())
+		.add_systems(Update, comms_trigger_system);
+		app.world.spawn(CommsTrigger::new("galley", "Cook", "Mind the soup.", 3));
+		app.world.send_event(PlayerMoved { posn: Position::new(2, 1, 0) });
+		app.update();
+		assert!(app.world.resource::<PlanqComms>().outbox.is_empty());
+		for x_posn in [4, 5] {
+			app.world.send_event(PlayerMoved { posn: Position::new(x_posn, 1, 0) });
+			app.update();
+		}
+		let outbox: Vec<String> = app.world.resource::<PlanqComms>().outbox.iter().map(|x| x.text.clone()).collect();
+		assert_eq!(outbox, vec!["Mind the soup.".to_string()]);
+		assert_eq!(app.world.resource::<PlanqComms>().outbox[0].delay.duration(), Duration::from_secs(3));
+		assert!(app.world.query::<&CommsTrigger>().iter(&app.world).next().is_none());
+	}
+}
+
+// EOF
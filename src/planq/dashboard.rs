@@ -0,0 +1,225 @@
+// planq/dashboard.rs
+// Provides the PLANQ's ship systems dashboard, which sums up the state of the devices in each zone of the ship
+
+// ###: EXTERNAL LIBRARIES
+use bevy::prelude::*;
+use ratatui::prelude::*;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::Line;
+use ratatui::widgets::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::components::*;
+use crate::engine::RunState;
+use crate::planq::*;
+use crate::worldmap::WorldModel;
+
+// ###: CONSTANTS
+/// The dashboard's figures are only recalculated once every this many turns
+pub const DASH_REFRESH_TURNS: u64 = 10;
+
+// ###: BEVY SYSTEMS
+/// Keeps the dashboard's figures up to date while the dashboard is open
+pub fn planq_dashboard_system(mut planq: ResMut<PlanqData>,
+	                            run_state: Res<RunState>,
+	                            model:     Res<WorldModel>,
+	                            d_query:   Query<(Entity, &Device, &Body, &Description)>,
+) {
+	if planq.current_view() != PlanqView::Dashboard { return; }
+	if planq.dashboard.is_fresh && run_state.turn_count % DASH_REFRESH_TURNS != 0 { return; }
+	let devices: Vec<(String, DeviceEntry)> = d_query.iter().map(|(d_enty, d_device, d_body, d_desc)| {
+		let zone = model.layout.get_room_name(d_body.ref_posn).unwrap_or("(unmapped)".to_string());
+		(zone, DeviceEntry { name: d_desc.name.clone(), address: d_enty, state: d_device.state })
+	}).collect();
+	planq.dashboard.refresh(&devices);
+}
+
+// ###: COMPLEX TYPES
+//  ##: PlanqView
+/// The screens that can be shown in the PLANQ's terminal area; the terminal itself is always at the bottom of the stack
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum PlanqView {
+	#[default]
+	Terminal,
+	Dashboard,
+	Minimap,
+}
+//  ##: Dashboard
+/// The state of the dashboard app: the per-zone figures, and which zone the player has picked out
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct Dashboard {
+	pub zones: Vec<ZoneStatus>,
+	pub selected: usize,
+	pub drilled: bool, // If true, the selected zone's device list is shown instead of the zone summary
+	pub is_fresh: bool, // If false, the figures will be recalculated on the next update
+}
+impl Dashboard {
+	/// Regroups the given (zone name, device) pairs into the per-zone figures
+	pub fn refresh(&mut self, devices: &[(String, DeviceEntry)]) {
+		self.zones = aggregate_devices(devices);
+		self.selected = self.selected.min(self.zones.len().saturating_sub(1));
+		self.is_fresh = true;
+	}
+	pub fn select_prev(&mut self) {
+		if self.drilled { return; }
+		self.selected = self.selected.saturating_sub(1);
+	}
+	pub fn select_next(&mut self) {
+		if self.drilled { return; }
+		if self.selected + 1 < self.zones.len() { self.selected += 1; }
+	}
+	/// Switches between the zone summary and the selected zone's device list
+	pub fn toggle_drill(&mut self) {
+		self.drilled = !self.drilled && !self.zones.is_empty();
+	}
+	/// Produces the lines of text that the dashboard shows, depending on whether a zone has been drilled into
+	pub fn report(&self) -> Vec<Line> {
+		if self.zones.is_empty() { return vec![Line::from("No devices reporting.")]; }
+		if self.drilled {
+			let zone = &self.zones[self.selected];
+			let mut lines = vec![Line::from(format!("{}:", zone.name))];
+			for device in zone.devices.iter() {
+				lines.push(Line::from(format!(" {:>5} {} {}", device.address.index(), device.name, state_name(device.state))));
+			}
+			return lines;
+		}
+		let mut lines = vec![Line::from("ZONE         IDL WRK ERR OFF")];
+		for (index, zone) in self.zones.iter().enumerate() {
+			let style = if index == self.selected { Style::default().fg(Color::Black).bg(Color::Blue) } else { Style::default() };
+			let alert_color = match zone.alert_level() {
+				"ALERT" => { Color::Red }
+				"WARN"  => { Color::Yellow }
+				_       => { Color::Green }
+			};
+			lines.push(Line::from(vec![
+				Span::styled(format!("{:<12.12} {:>3} {:>3} {:>3} {:>3} ", zone.name, zone.idle, zone.working, zone.error, zone.offline), style),
+				Span::styled(zone.alert_level(), Style::default().fg(alert_color)),
+			]));
+		}
+		lines.push(Line::from("ATMO: n/a"));
+		lines
+	}
+	/// Renders the dashboard into the PLANQ's terminal area
+	pub fn render<B: Backend>(&self, frame: &mut Frame<'_, B>, area: Rect, border_color: Color) {
+		frame.render_widget(
+			Paragraph::new(Text::from(self.report()))
+			.block(Block::default()
+			       .borders(Borders::ALL)
+			       .border_type(BorderType::Plain)
+			       .border_style(Style::default().fg(border_color))
+			       .title("DASH")),
+			area,
+		);
+	}
+}
+//  ##: ZoneStatus
+/// The number of devices in each state within a single zone of the ship
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct ZoneStatus {
+	pub name: String,
+	pub idle: usize,
+	pub working: usize,
+	pub error: usize,
+	pub offline: usize,
+	pub devices: Vec<DeviceEntry>,
+}
+impl ZoneStatus {
+	/// Any device in an error state is an alert; a zone that's entirely offline is a warning
+	pub fn alert_level(&self) -> &'static str {
+		if self.error > 0 { return "ALERT"; }
+		if self.offline > 0 && self.idle + self.working == 0 { return "WARN"; }
+		"OK"
+	}
+}
+//  ##: DeviceEntry
+/// A single device as listed on the dashboard; the address is the ID that the diagnostic commands expect
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct DeviceEntry {
+	pub name: String,
+	pub address: Entity,
+	pub state: DeviceState,
+}
+
+// ###: UTILITIES
+/// Sorts the given (zone name, device) pairs into per-zone counts, ordered by zone name
+pub fn aggregate_devices(devices: &[(String, DeviceEntry)]) -> Vec<ZoneStatus> {
+	let mut zones: Vec<ZoneStatus> = Vec::new();
+	for (zone_name, device) in devices.iter() {
+		let index = if let Some(value) = zones.iter().position(|x| x.name == *zone_name) {
+			value
+		} else {
+			zones.push(ZoneStatus { name: zone_name.clone(), ..ZoneStatus::default() });
+			zones.len() - 1
+		};
+		let zone = &mut zones[index];
+		match device.state {
+			DeviceState::Idle     => { zone.idle += 1; }
+			DeviceState::Working  => { zone.working += 1; }
+			DeviceState::Error(_) => { zone.error += 1; }
+			DeviceState::Offline  => { zone.offline += 1; }
+		}
+		zone.devices.push(device.clone());
+	}
+	zones.sort_by(|a, b| a.name.cmp(&b.name));
+	zones
+}
+fn state_name(state: DeviceState) -> String {
+	match state {
+		DeviceState::Idle        => { "idle".to_string() }
+		DeviceState::Working     => { "working".to_string() }
+		DeviceState::Error(code) => { format!("ERROR {}", code) }
+		DeviceState::Offline     => { "offline".to_string() }
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	fn entry(name: &str, state: DeviceState) -> DeviceEntry {
+		DeviceEntry { name: name.to_string(), address: Entity::PLACEHOLDER, state }
+	}
+	//  ##: aggregate_devices
+	#[test]
+	fn devices_are_counted_by_zone_and_state() {
+		let devices = vec![
+			("Galley".to_string(), entry("light", DeviceState::Idle)),
+			("Bridge".to_string(), entry("console", DeviceState::Working)),
+			("Galley".to_string(), entry("oven", DeviceState::Error(3))),
+			("Galley".to_string(), entry("fridge", DeviceState::Offline)),
+		];
+		let zones = aggregate_devices(&devices);
+		assert_eq!(zones.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>(), vec!["Bridge", "Galley"]);
+		assert_eq!((zones[0].idle, zones[0].working, zones[0].error, zones[0].offline), (0, 1, 0, 0));
+		assert_eq!((zones[1].idle, zones[1].working, zones[1].error, zones[1].offline), (1, 0, 1, 1));
+		assert_eq!(zones[1].devices.len(), 3);
+		assert_eq!(zones[1].alert_level(), "ALERT");
+	}
+	#[test]
+	fn an_entirely_offline_zone_is_a_warning() {
+		let zones = aggregate_devices(&[("Hold".to_string(), entry("lamp", DeviceState::Offline))]);
+		assert_eq!(zones[0].alert_level(), "WARN");
+	}
+	//  ##: View stack
+	#[test]
+	fn closing_the_dashboard_returns_to_the_terminal() {
+		let mut planq = PlanqData::default();
+		planq.push_view(PlanqView::Dashboard);
+		assert_eq!(planq.current_view(), PlanqView::Dashboard);
+		assert_eq!(planq.pop_view(), Some(PlanqView::Dashboard));
+		assert_eq!(planq.current_view(), PlanqView::Terminal);
+		assert_eq!(planq.pop_view(), None);
+	}
+	#[test]
+	fn shutting_down_closes_every_app() {
+		let mut planq = PlanqData::default();
+		planq.push_view(PlanqView::Dashboard);
+		planq.dashboard.refresh(&[("Hold".to_string(), entry("lamp", DeviceState::Idle))]);
+		planq.reset_views();
+		assert_eq!(planq.current_view(), PlanqView::Terminal);
+		assert!(planq.dashboard.zones.is_empty());
+	}
+}
+
+// EOF
@@ -0,0 +1,174 @@
+// planq/minimap.rs
+// Provides the PLANQ's minimap app, which shrinks the player's deck down to fit on the terminal, with a legend of the
+// notes that the player has left on it
+
+// ###: EXTERNAL LIBRARIES
+use ratatui::prelude::*;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::Line;
+use ratatui::widgets::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::bookmarks::MapNotes;
+use crate::components::Position;
+use crate::worldmap::WorldModel;
+
+// ###: METHODS
+/// Draws the player's deck shrunk down to fit the given area, from what the player has seen of it so far
+/// Each cell stands for a square block of tiles: the player's block gets an '@', a block with a note in it gets the
+/// note's marker from the legend, and anything else shows as floor or wall if any of it has been seen
+pub fn minimap_rows(model: &WorldModel, notes: &MapNotes, p_posn: Position, width: usize, height: usize) -> Vec<String> {
+	if !model.contains(p_posn) || width == 0 || height == 0 { return Vec::new(); }
+	let level = &model.levels[p_posn.z as usize];
+	let scale = blocks(level.width, width).max(blocks(level.height, height)).max(1);
+	let deck_notes: Vec<(usize, Position)> = notes.sorted().into_iter().enumerate()
+		.filter(|(_, (posn, _))| posn.z == p_posn.z)
+		.map(|(index, (posn, _))| (index, posn))
+		.collect();
+	let mut rows = Vec::new();
+	for block_y in 0..blocks(level.height, scale) {
+		let mut row = String::new();
+		for block_x in 0..blocks(level.width, scale) {
+			let (x_range, y_range) = (block_x * scale..((block_x + 1) * scale).min(level.width), block_y * scale..((block_y + 1) * scale).min(level.height));
+			let in_block = |posn: Position| x_range.contains(&(posn.x as usize)) && y_range.contains(&(posn.y as usize));
+			let mut has_floor = false;
+			let mut has_wall = false;
+			for y_posn in y_range.clone() {
+				for x_posn in x_range.clone() {
+					let index = level.to_index(x_posn as i32, y_posn as i32);
+					if !level.revealed_tiles[index] { continue; }
+					if level.tiles[index].ttype.properties().passable { has_floor = true; } else { has_wall = true; }
+				}
+			}
+			// Notes are only marked where the player has seen, the same as on the camera
+			let note = deck_notes.iter().find(|(_, posn)| in_block(*posn) && level.revealed_tiles[level.to_index(posn.x, posn.y)]);
+			row.push(if in_block(p_posn) {
+				'@'
+			} else if let Some((index, _)) = note {
+				note_marker(*index)
+			} else if has_floor {
+				'.'
+			} else if has_wall {
+				'#'
+			} else {
+				' '
+			});
+		}
+		rows.push(row);
+	}
+	rows
+}
+/// Lists the notes on the given deck beside their markers, numbered the same as in the 'notes' listing so that the
+/// player can travel to one with 'notes N'
+pub fn minimap_legend(notes: &MapNotes, deck: i32) -> Vec<String> {
+	let legend: Vec<String> = notes.sorted().into_iter().enumerate()
+		.filter(|(_, (posn, _))| posn.z == deck)
+		.map(|(index, (_, text))| format!("{} {:>2}: {}", note_marker(index), index, text))
+		.collect();
+	if legend.is_empty() { return vec!["No notes on this deck.".to_string()]; }
+	legend
+}
+/// Renders the minimap into the PLANQ's terminal area, with as much of the legend underneath as will fit
+pub fn render_minimap<B: Backend>(frame: &mut Frame<'_, B>, area: Rect, border_color: Color, rows: &[String], legend: &[String]) {
+	let mut lines: Vec<Line> = rows.iter().map(|x| Line::from(x.clone())).collect();
+	lines.push(Line::from(""));
+	lines.extend(legend.iter().map(|x| Line::from(Span::styled(x.clone(), Style::default().fg(Color::Yellow)))));
+	frame.render_widget(
+		Paragraph::new(Text::from(lines))
+		.block(Block::default()
+		       .borders(Borders::ALL)
+		       .border_type(BorderType::Plain)
+		       .border_style(Style::default().fg(border_color))
+		       .title("MAP")),
+		area,
+	);
+}
+
+// ###: UTILITIES
+/// Returns the mark that stands for the note with the given number: the first ten get their digit, and the rest share
+/// an asterisk, since there's only one cell to put it in
+pub fn note_marker(index: usize) -> char {
+	char::from_digit(index as u32, 10).unwrap_or('*')
+}
+/// Returns how many blocks of the given size it takes to cover the given length, counting any partial block
+fn blocks(length: usize, size: usize) -> usize {
+	(length + size - 1) / size
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use crate::testmap::*;
+	use super::*;
+	/// Builds the corridor map with every tile on it already seen
+	fn seen_corridor() -> WorldModel {
+		let mut model = TestMap::new(CORRIDOR_WITH_DOOR).model;
+		model.levels[0].revealed_tiles.iter_mut().for_each(|x| *x = true);
+		model
+	}
+	//  ##: Drawing
+	#[test]
+	fn a_deck_that_fits_is_drawn_tile_for_tile() {
+		let model = seen_corridor();
+		let rows = minimap_rows(&model, &MapNotes::new(), Position::new(1, 1, 0), 20, 10);
+		assert_eq!(rows, vec!["#########".to_string(), "#@......#".to_string(), "#########".to_string()]);
+	}
+	#[test]
+	fn a_deck_that_doesnt_fit_is_shrunk() {
+		let model = seen_corridor();
+		// Three tiles to a cell: the corridor's nine columns come down to three, and its three rows come down to one
+		let rows = minimap_rows(&model, &MapNotes::new(), Position::new(7, 1, 0), 3, 3);
+		assert_eq!(rows, vec!["..@".to_string()]);
+	}
+	#[test]
+	fn unseen_tiles_are_left_blank() {
+		let mut model = TestMap::new(CORRIDOR_WITH_DOOR).model;
+		for x_posn in 0..3 {
+			for y_posn in 0..3 {
+				let index = model.levels[0].to_index(x_posn, y_posn);
+				model.levels[0].revealed_tiles[index] = true;
+			}
+		}
+		let rows = minimap_rows(&model, &MapNotes::new(), Position::new(1, 1, 0), 20, 10);
+		assert_eq!(rows[1], "#@.      ".to_string());
+	}
+	//  ##: Notes and the legend
+	#[test]
+	fn notes_are_marked_with_their_numbers() {
+		let model = seen_corridor();
+		let mut notes = MapNotes::new();
+		notes.set(Position::new(6, 1, 0), "loose panel");
+		notes.set(Position::new(3, 1, 0), "good spot");
+		let rows = minimap_rows(&model, &notes, Position::new(1, 1, 0), 20, 10);
+		assert_eq!(rows[1], "#@.0..1.#".to_string());
+		assert_eq!(minimap_legend(&notes, 0), vec!["0  0: good spot".to_string(), "1  1: loose panel".to_string()]);
+	}
+	#[test]
+	fn notes_on_unseen_tiles_are_not_marked() {
+		let mut model = seen_corridor();
+		let index = model.levels[0].to_index(6, 1);
+		model.levels[0].revealed_tiles[index] = false;
+		let mut notes = MapNotes::new();
+		notes.set(Position::new(6, 1, 0), "loose panel");
+		let rows = minimap_rows(&model, &notes, Position::new(1, 1, 0), 20, 10);
+		assert_eq!(rows[1], "#@.... .#".to_string());
+	}
+	#[test]
+	fn the_legend_keeps_to_the_players_deck() {
+		let mut notes = MapNotes::new();
+		notes.set(Position::new(2, 1, 0), "downstairs");
+		notes.set(Position::new(2, 1, 1), "upstairs");
+		// The numbering still counts the notes on the other decks, so that 'notes N' finds the same one
+		assert_eq!(minimap_legend(&notes, 1), vec!["1  1: upstairs".to_string()]);
+		assert_eq!(minimap_legend(&notes, 2), vec!["No notes on this deck.".to_string()]);
+	}
+	#[test]
+	fn markers_run_out_after_ten_notes() {
+		assert_eq!(note_marker(0), '0');
+		assert_eq!(note_marker(9), '9');
+		assert_eq!(note_marker(10), '*');
+	}
+}
+
+// EOF
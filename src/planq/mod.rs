@@ -6,6 +6,8 @@
 //  ###: EXTERNAL LIBRARIES
 use bevy::{
 	prelude::*,
+	ecs::entity::{EntityMapper, MapEntities},
+	ecs::reflect::ReflectMapEntities,
 	ecs::query::*,
 	utils::*,
 };
@@ -21,28 +23,47 @@ use crate::{
 	components::*,
 	engine::event::*,
 	engine::messagelog::*,
+	engine::preferences::Preferences,
 	planq::{
+		dashboard::*,
 		tui::*,
 		PlanqEventType::*,
 	},
 };
+pub mod comms;
+pub mod dashboard;
+pub mod minimap;
 pub mod monitor;
+pub mod transcript;
 pub mod tui;
 
-//  ###: COMPLEX TYPES
+//  ###: CONSTANTS
+/// The number of seconds that the PLANQ's output has to be left alone before the idle graphic shows up again
+pub const IDLE_QUIET_DURATION: u64 = 5;
+/// The number of seconds that it takes the PLANQ to analyze an unidentified item
+pub const ANALYSIS_DURATION: u64 = 5;
+/// The block id that the idle graphic's row is shown with; the MessageLog's own ids count up from 1, so it can't clash
+pub const IDLE_BLOCK: u32 = u32::MAX;
 
+//  ###: COMPLEX TYPES
 
 //  ###: BEVY SYSTEMS
 /// Allows us to run PLANQ updates and methods in their own thread, just like a real computer~
 pub fn planq_update_system(mut commands: Commands,
 	                         mut ereader:  EventReader<GameEvent>,
 	                         mut preader:  EventReader<PlanqEvent>,
+	                         mut iwriter:  EventWriter<IdentifyItem>,
+	                         mut creader:  EventReader<InventoryChanged>,
+	                         mut pwreader: EventReader<DevicePowerChanged>,
+	                         mut missed:   ResMut<MissedEvents>,
 	                         mut msglog:   ResMut<MessageLog>,
 	                         time:         Res<Time>,
+	                         boot_text:    Res<PlanqBootText>,
 	                         mut planq:    ResMut<PlanqData>, // contains the PLANQ's settings and data storage
 	                         p_query:      Query<(Entity, &Body), With<Player>>, // provides interface to player data
 	                         mut q_query:  Query<(Entity, &Device, &Portable), With<Planq>>, // contains the PLANQ's component data
 	                         mut t_query:  Query<(Entity, &mut PlanqProcess)>, // contains the set of all PlanqTimers
+	                         i_query:      Query<(Entity, &Portable), Without<Planq>>, // everything that might be in the player's inventory
 ) {
 	if p_query.is_empty() { return; }
 	if q_query.is_empty() { return; }
@@ -59,16 +80,6 @@ pub fn planq_update_system(mut commands: Commands,
 			}
 			match atype {
 				// Player interaction events that need to be monitored
-				ActionType::MoveItem => { // The player (g)ot the PLANQ from somewhere external
-					if let Some(econtext) = event.context.as_ref() {
-						planq.is_carried = econtext.subject == p_enty && econtext.object == q_enty;
-					}
-				}
-				ActionType::DropItem => { // The player (d)ropped the PLANQ
-					if let Some(econtext) = event.context.as_ref() {
-						if econtext.object == q_enty { planq.is_carried = false; }
-					}
-				}
 				ActionType::UseItem => { // The player (a)pplied the PLANQ
 					if let Some(econtext) = event.context.as_ref() {
 						if econtext.subject == p_enty
@@ -77,7 +88,8 @@ pub fn planq_update_system(mut commands: Commands,
 							// PLANQ: it allows the player to operate the power switch
 							// This seems likely to change in the future to allow some better service
 							// commands, like battery swaps or peripheral attachment
-							msglog.tell_player("There is a faint 'click' as you press the PLANQ's power button.");
+							// The power state hasn't been updated for this press yet, so this describes what it's about to do
+							msglog.tell_player(planq.power_button_message());
 						}
 					}
 				}
@@ -101,7 +113,7 @@ pub fn planq_update_system(mut commands: Commands,
 					planq.action_mode = PlanqActionMode::CliInput;
 				}
 				PlanqEventType::CliClose => {
-					// FIXME: need to clear the CLI's input buffer! might need to do this at the time of key input?
+					// The CLI's input buffer gets cleared by the key handler, which owns it
 					planq.show_cli_input = false;
 					planq.action_mode = PlanqActionMode::Default; // FIXME: this might be a bad choice
 				}
@@ -129,19 +141,36 @@ pub fn planq_update_system(mut commands: Commands,
 					// "P: (idle)"
 					todo!(">>> planq.rs:planq_update_system(), l125 - implement PlanqEventType::AccessUnlink");
 				}
+				PlanqEventType::Analyze(_) => { /* handled when the analysis process finishes, see below */ }
 			}
 		}
 	}
 	// Update the PLANQData resources:
+	// - Keep track of what the player's carrying, and whether the PLANQ is among it
+	for event in creader.iter() {
+		if event.carrier != p_enty { continue; }
+		planq.inventory_list = i_query.iter().filter(|x| x.1.carrier == p_enty).map(|x| x.0).collect();
+		planq.is_carried = q_portable.carrier == p_enty;
+	}
 	// - Get the device hardware info
-	if !planq.power_is_on && q_device.pw_switch {
-		planq.power_is_on = q_device.pw_switch; // Update the power switch setting
-		planq.show_terminal = true;
+	let mut power_switch = planq.power_is_on;
+	for event in pwreader.iter() {
+		if event.device == q_enty { power_switch = event.is_on; }
+	}
+	if cfg!(debug_assertions) && power_switch != q_device.pw_switch { // DEBUG: catch any power change that went unannounced
+		error!("! planq_update_system: the PLANQ's power switch changed without a DevicePowerChanged event");
+		missed.flag("DevicePowerChanged");
+		power_switch = q_device.pw_switch;
+	}
+	if !planq.power_is_on && power_switch {
+		planq.power_is_on = power_switch; // Update the power switch setting
+		planq.ever_powered = true;
 		planq.cpu_mode = PlanqCPUMode::Startup; // Begin booting the PLANQ's OS
 	}
-	if planq.power_is_on && !q_device.pw_switch {
-		planq.power_is_on = q_device.pw_switch; // Update the power switch setting
+	if planq.power_is_on && !power_switch {
+		planq.power_is_on = power_switch; // Update the power switch setting
 		planq.cpu_mode = PlanqCPUMode::Shutdown; // Initiate a shutdown
+		planq.reset_views(); // None of the apps survive losing power
 	}
 	// - Handle the Planq's CPU mode logic
 	// CRASH CHECK:
@@ -182,7 +211,7 @@ pub fn planq_update_system(mut commands: Commands,
 				0 => {
 					if planq.proc_table.is_empty() {
 						//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
-						msglog.boot_message(planq.boot_stage);
+						msglog.boot_message(planq.boot_stage, &boot_text);
 						// kick off boot stage 1
 						planq.proc_table.push(commands.spawn(
 								PlanqProcess::new()
@@ -196,7 +225,7 @@ pub fn planq_update_system(mut commands: Commands,
 					if let Ok((_enty, mut proc)) = proc_ref {
 						if proc.timer.just_finished() {
 							//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
-							msglog.boot_message(planq.boot_stage);
+							msglog.boot_message(planq.boot_stage, &boot_text);
 							// set its duration, if needed
 							//proc.1.timer.set_duration(Duration::from_secs(5));
 							// reset it
@@ -209,7 +238,10 @@ pub fn planq_update_system(mut commands: Commands,
 					if let Ok((_enty, mut proc)) = proc_ref {
 						if proc.timer.just_finished() {
 							//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
-							msglog.boot_message(planq.boot_stage);
+							msglog.boot_message(planq.boot_stage, &boot_text);
+							if let Some(fragment) = planq.ghost_log.take() {
+								msglog.tell_planq(&fragment);
+							}
 							// set its duration, if needed
 							//proc.1.timer.set_duration(Duration::from_secs(5));
 							// reset it and start it
@@ -222,7 +254,7 @@ pub fn planq_update_system(mut commands: Commands,
 					if let Ok((_enty, mut proc)) = proc_ref {
 						if proc.timer.just_finished() {
 							//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
-							msglog.boot_message(planq.boot_stage);
+							msglog.boot_message(planq.boot_stage, &boot_text);
 							// set its duration, if needed
 							//proc.1.timer.set_duration(Duration::from_secs(5));
 							// reset it and start it
@@ -235,7 +267,7 @@ pub fn planq_update_system(mut commands: Commands,
 					if let Ok((_enty, mut proc)) = proc_ref {
 						if proc.timer.just_finished() {
 							//debug!("¶ running boot stage {}", planq.boot_stage); // DEBUG: announce the current PLANQ boot stage
-							msglog.boot_message(planq.boot_stage);
+							msglog.boot_message(planq.boot_stage, &boot_text);
 							proc.outcome = PlanqEvent::new(PlanqEventType::NullEvent);
 							planq.idle_mode(&mut msglog);
 						}
@@ -251,7 +283,6 @@ pub fn planq_update_system(mut commands: Commands,
 			todo!(">>> planq.rs:planq_update_system(), l258 - implement PlanqCPUMode::Shutdown");
 		}
 		PlanqCPUMode::Idle     => {
-			/*
 			// IDLE GRAPHIC: Bouncing Box
 			// Given a sequence of integers 0-9, produce a smoothly scaled integer 1-21:
 			let smooth_input = (time.elapsed().as_secs() % 10) as f64;
@@ -260,7 +291,6 @@ pub fn planq_update_system(mut commands: Commands,
 			let output = (4.4 * smooth_input - 23.0).abs() as usize;
 			// Creates the new idle image by prepending with a variable number of spaces, so that the graphic 'moves'
 			let idle_message = format!("{:width$}", "", width=output) + "-=[ ]=-";
-			*/
 			/*
 			// IDLE GRAPHIC: Bizarre Data
 			let sample = vec!['▖', '▗', '▘', '▝', '▀', '▄', '▌', '▐', '▚', '▞', '▙', '▛', '▜', '▟', '█'];
@@ -272,14 +302,27 @@ pub fn planq_update_system(mut commands: Commands,
 			}
 			*/
 			// Update the idle message if there's nothing waiting for processing
+			// The idle graphic has its own line below the backlog, so it can never overwrite a command's output
 			if planq.proc_table.len() == 1 {
-				//msglog.replace(idle_message, "planq".to_string(), 0, 0); // continue idling
+				planq.idle_line = Some(idle_message); // continue idling
 			} else {
+				planq.idle_line = None;
 				planq.cpu_mode = PlanqCPUMode::Working;
 			}
 		}
 		PlanqCPUMode::Working  => {
 			// Display the outputs from the workloads
+			// Proc 0 is the boot process, the rest are jobs; any that have finished get their results handed off
+			for id in planq.proc_table.clone().into_iter().skip(1) {
+				let (_proc_enty, proc) = if let Ok(value) = t_query.get(id) { value } else { continue };
+				if !proc.timer.finished() { continue; }
+				if let PlanqEventType::Analyze(item) = proc.outcome.etype {
+					msglog.tell_planq("[[fg:yellow]]¶[[fg:gray]]│[[end]]Analysis complete.");
+					iwriter.send(IdentifyItem { item, identifier: p_enty });
+				}
+				planq.proc_table.retain(|x| *x != id);
+				commands.entity(id).despawn();
+			}
 			// If all workloads are done, shift back to Idle mode
 			if planq.proc_table.len() == 1 { planq.idle_mode(&mut msglog); }
 		}
@@ -291,8 +334,32 @@ pub fn planq_update_system(mut commands: Commands,
 		}
 	}
 	// - Check for some edge cases and other things that we'd like to avoid
-	if planq.is_carried && q_portable.carrier != p_enty { planq.is_carried = false; }
-	if !planq.is_carried && q_portable.carrier == p_enty { planq.is_carried = true; }
+	if cfg!(debug_assertions) && planq.is_carried != (q_portable.carrier == p_enty) { // DEBUG: catch any unannounced pickup or drop
+		error!("! planq_update_system: the PLANQ's carrier changed without an InventoryChanged event");
+		missed.flag("InventoryChanged");
+		planq.is_carried = q_portable.carrier == p_enty;
+	}
+}
+
+/// Presses the PLANQ's power button on the player's behalf the first time that they pick it up, if their settings ask
+/// for it; any later pickups, or a PLANQ that's already on, are left for the player to deal with
+pub fn planq_autopower_system(mut creader: EventReader<InventoryChanged>,
+	                            mut ewriter: EventWriter<GameEvent>,
+	                            prefs:       Res<Preferences>,
+	                            mut planq:   ResMut<PlanqData>,
+	                            p_query:     Query<Entity, With<Player>>,
+	                            q_query:     Query<(Entity, &Device, &Portable), With<Planq>>,
+) {
+	let p_enty = if let Ok(value) = p_query.get_single() { value } else { return };
+	let (q_enty, q_device, q_portable) = if let Ok(value) = q_query.get_single() { value } else { return };
+	// Picking up anything else doesn't count, even if the PLANQ happens to be in the inventory already
+	let picked_up = creader.iter().any(|x| x.carrier == p_enty && x.item == q_enty);
+	if !picked_up || !prefs.planq_autopower || planq.ever_powered { return; }
+	if q_portable.carrier != p_enty { return; }
+	// Only the first pickup gets this treatment, whether or not the button actually needed pressing
+	planq.ever_powered = true;
+	if q_device.pw_switch { return; }
+	ewriter.send(GameEvent::new(GameEventType::PlayerAction(ActionType::UseItem), Some(p_enty), Some(q_enty)));
 }
 
 /// BEVY: Defines the Planq settings/controls (interface bwn my GameEngine class & Bevy)
@@ -304,7 +371,7 @@ pub struct PlanqData {
 	pub is_carried: bool, // true if the planq is in the player's inventory
 	pub cpu_mode: PlanqCPUMode,
 	pub action_mode: PlanqActionMode, // Provides player action context for disambiguation
-	pub show_terminal: bool,
+	pub show_terminal: bool, // true if the terminal is on screen; the player sets this, it doesn't follow the power switch
 	pub show_inventory: bool,
 	pub inventory_list: Vec<Entity>,
 	pub player_loc: Position,
@@ -312,6 +379,14 @@ pub struct PlanqData {
 	pub stdout: Vec<Message>, // Local copy of the PLANQ's message backlog, as copied from the MessageLog "planq" channel
 	pub proc_table: Vec<Entity>, // The list of PlanqProcesses running in the Planq
 	pub jack_cnxn: Entity, // ID of the object that the PLANQ's access jack is connected to
+	pub ghost_log: Option<String>, // A log fragment left over from a previous run, shown once during boot
+	pub idle_line: Option<String>, // The current frame of the idle graphic, which is kept out of the stdout backlog
+	pub since_output: Duration, // How long it's been since anything was added to the stdout backlog
+	pub view_stack: Vec<PlanqView>, // The apps that have been opened over the terminal, most recent last
+	pub dashboard: Dashboard,
+	pub storage: Vec<DataFile>, // The data files that have been downloaded from AccessPorts
+	pub scan_log: Vec<ScanRecord>, // Everything that the scanner has picked up, most recently scanned last
+	pub ever_powered: bool, // true once the PLANQ has been switched on, or its first pickup has been dealt with
 }
 impl Default for PlanqData {
 	fn default() -> PlanqData {
@@ -321,7 +396,7 @@ impl Default for PlanqData {
 			is_carried: false, // true if the planq is in the player's inventory
 			cpu_mode: PlanqCPUMode::Offline,
 			action_mode: PlanqActionMode::Default, // Provides player action context for disambiguation
-			show_terminal: false,
+			show_terminal: true, // Shows the dark screen until the PLANQ is switched on
 			show_inventory: false,
 			inventory_list: Vec::new(),
 			player_loc: Position::default(), // player's current coordinates (TODO: replace with a room-based system)
@@ -329,6 +404,25 @@ impl Default for PlanqData {
 			stdout: Vec::new(), // Contains the PLANQ's message backlog
 			proc_table: Vec::new(), // The list of PlanqProcesses running in the Planq
 			jack_cnxn: Entity::PLACEHOLDER, // ID of the object that the PLANQ's access jack is connected to
+			ghost_log: None,
+			idle_line: None,
+			since_output: Duration::ZERO,
+			view_stack: Vec::new(),
+			dashboard: Dashboard::default(),
+			storage: Vec::new(),
+			scan_log: Vec::new(),
+			ever_powered: false,
+		}
+	}
+}
+impl MapEntities for PlanqData {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for enty in self.inventory_list.iter_mut().chain(self.proc_table.iter_mut()) {
+			*enty = map_entity(entity_mapper, *enty);
+		}
+		self.jack_cnxn = map_entity(entity_mapper, self.jack_cnxn);
+		for record in self.scan_log.iter_mut() {
+			record.entity = map_entity(entity_mapper, record.entity);
 		}
 	}
 }
@@ -336,6 +430,54 @@ impl PlanqData {
 	pub fn new() -> PlanqData {
 		PlanqData::default()
 	}
+	/// Returns the reason that the player can't perform the given action right now, if the PLANQ is keeping their hands
+	/// busy; only a PLANQ that's being carried can get in the way
+	pub fn refuses(&self, action: ActionType) -> Option<String> {
+		if !self.is_carried || !self.cpu_mode.restricts(action) { return None; }
+		let activity = if self.cpu_mode == PlanqCPUMode::Startup { "boots up" } else { "shuts down" };
+		Some(format!("You need both hands to steady the PLANQ while it {}.", activity))
+	}
+	/// Returns whichever view is on top of the stack; the terminal is showing if nothing else is
+	pub fn current_view(&self) -> PlanqView {
+		self.view_stack.last().copied().unwrap_or(PlanqView::Terminal)
+	}
+	/// Opens the given app over whatever is showing now
+	pub fn push_view(&mut self, view: PlanqView) {
+		if view == PlanqView::Dashboard { self.dashboard.is_fresh = false; }
+		self.view_stack.push(view);
+	}
+	/// Closes the topmost app, returning to whatever was under it
+	pub fn pop_view(&mut self) -> Option<PlanqView> {
+		self.view_stack.pop()
+	}
+	/// Describes what happens when the player presses the power button, given the state that the PLANQ was in beforehand
+	/// The Operable system only ever switches a Device on, so pressing the button while it's running doesn't stop it
+	pub fn power_button_message(&self) -> &'static str {
+		if !self.power_is_on {
+			"There is a faint 'click' as you press the PLANQ's power button, and its screen flickers to life."
+		} else if self.cpu_mode == PlanqCPUMode::Startup {
+			"You press the PLANQ's power button, but it's still booting up and doesn't respond."
+		} else {
+			"You press the PLANQ's power button; it's already on, and nothing happens."
+		}
+	}
+	/// Adds a scanned entity to the scan log, or moves its existing entry to the end with its new position and time
+	pub fn record_scan(&mut self, entity: Entity, name: &str, posn: Position, turn: u64) {
+		self.scan_log.retain(|x| x.entity != entity);
+		self.scan_log.push(ScanRecord { entity, name: name.to_string(), posn, turn });
+	}
+	/// Lists the contents of the scan log, most recently scanned first
+	pub fn scan_report(&self) -> Vec<String> {
+		if self.scan_log.is_empty() { return vec!["Scan history is empty.".to_string()]; }
+		let mut report = vec![format!("Scan history ({} entries):", self.scan_log.len())];
+		report.extend(self.scan_log.iter().rev().map(|x| format!("  {}: last seen at {}, turn {}", x.name, x.posn, x.turn)));
+		report
+	}
+	/// Closes every app at once, ie when the PLANQ loses power
+	pub fn reset_views(&mut self) {
+		self.view_stack.clear();
+		self.dashboard = Dashboard::default();
+	}
 	/// Renders the CLI input box
 	pub fn render_cli<B: Backend>(&mut self, frame: &mut Frame<'_, B>, area: Rect, stdin: &mut PlanqInput) {
 		//let mut cli = TextArea::default();
@@ -348,31 +490,70 @@ impl PlanqData {
 		frame.render_widget(stdin.input.widget(), area);
 	}
 	/// Renders the whole terminal window, including the backlog, leaving room for the CLI
-	pub fn render_terminal<B: Backend>(&mut self, frame: &mut Frame<'_, B>, area: Rect) {
-		let stdout = self.get_stdout_as_lines();
-		let start_offset = (stdout.len() as i32) - area.height as i32 + 2;
-		let mut start: usize = 0;
-		if start_offset > 0 { start = start_offset as usize; }
-		let backscroll = stdout[start..].to_vec();
+	/// The scroll is how many lines back from the latest output to show, and is clamped to the length of the backlog;
+	/// when scrolled back, a block of output that would be cut off at the top is shown whole instead
+	pub fn render_terminal<B: Backend>(&mut self, frame: &mut Frame<'_, B>, area: Rect, scroll: &mut usize, border_color: Color) {
+		let inner_width = (area.width as usize).saturating_sub(2).max(1);
+		let (stdout, blocks): (Vec<Line>, Vec<u32>) = self.get_stdout_as_rows(inner_width).into_iter().unzip();
+		let visible = (area.height as usize).saturating_sub(2);
+		let (start, end) = block_window(&blocks, visible, scroll);
+		let backscroll = stdout[start..end].to_vec();
 		frame.render_widget(
 			Paragraph::new(Text::from(backscroll))
 			.block(Block::default()
 			       .borders(Borders::ALL)
 			       .border_type(BorderType::Plain)
-			       .border_style(Style::default().fg(Color::Blue)),
+			       .border_style(Style::default().fg(border_color)),
 			),
 			area,
 		);
 	}
-	/// Provides the contents of the PLANQ's stdout as a set of formatted Line for ratatui
-	pub fn get_stdout_as_lines(&self) -> Vec<Line> {
-		let mut output: Vec<Line> = Vec::new();
-		if self.stdout.is_empty() { return output; }
+	/// Provides the contents of the PLANQ's stdout as formatted rows for ratatui, wrapped to the given width and paired
+	/// with the id of the block that each came from
+	pub fn get_stdout_as_rows(&self, width: usize) -> Vec<(Line, u32)> {
+		let mut output: Vec<(Line, u32)> = Vec::new();
+		for block in self.output_blocks() {
+			match block {
+				OutputBlock::Stdout(id, lines) => {
+					for msg in lines {
+						output.extend(wrap_line(msg.into(), width).into_iter().map(|x| (x, id)));
+					}
+				}
+				OutputBlock::Idle(frame) => { output.push((Line::from(frame), IDLE_BLOCK)); }
+			}
+		}
+		output
+	}
+	/// Sorts the stdout into the blocks that each part of it went up in, in the order that they went up
+	/// The idle graphic gets a block of its own at the very end, but only once the output has been left alone for a while
+	pub fn output_blocks(&self) -> Vec<OutputBlock> {
+		let mut output: Vec<OutputBlock> = Vec::new();
 		for msg in self.stdout.iter() {
-			output.push(msg.clone().into());
+			match output.last_mut() {
+				// A message that went up on its own, with block id 0, is never grouped with its neighbours
+				Some(OutputBlock::Stdout(id, lines)) if msg.block != 0 && *id == msg.block => { lines.push(msg.clone()); }
+				_ => { output.push(OutputBlock::Stdout(msg.block, vec![msg.clone()])); }
+			}
+		}
+		if let Some(idle) = self.idle_line.as_ref().filter(|_| self.is_quiet()) {
+			output.push(OutputBlock::Idle(idle.clone()));
 		}
 		output
 	}
+	/// Brings the local copy of the stdout up to date with the MessageLog, and keeps track of how long it's been since
+	/// anything new was written to it
+	pub fn refresh_stdout(&mut self, new_stdout: Vec<Message>, delta: Duration) {
+		if new_stdout != self.stdout {
+			self.since_output = Duration::ZERO;
+			self.stdout = new_stdout;
+		} else {
+			self.since_output += delta;
+		}
+	}
+	/// Returns true if nothing new has been written to the stdout for long enough that the idle graphic can show
+	pub fn is_quiet(&self) -> bool {
+		self.since_output >= Duration::from_secs(IDLE_QUIET_DURATION)
+	}
 	/// Handler for executing the shift into Idle mode; does a little bit of cleanup as part of the process
 	pub fn idle_mode(&mut self, msglog: &mut MessageLog) {
 		//self.stdout.push(Message::new(0, 0, "planq".to_string(), "".to_string()));
@@ -385,11 +566,18 @@ impl PlanqData {
 /// BEVY: Provides the Bevy-backed tools for doing things on the PLANQ involving time intervals
 /// That is, this represents a 'process' or task within the PLANQ that needs processing time to complete
 #[derive(Component, Clone, Debug, Default, Reflect)]
-#[reflect(Component)]
+#[reflect(Component, MapEntities)]
 pub struct PlanqProcess {
 	pub timer: Timer,
 	pub outcome: PlanqEvent,
 }
+impl MapEntities for PlanqProcess {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		if let PlanqEventType::Analyze(item) = self.outcome.etype {
+			self.outcome.etype = PlanqEventType::Analyze(map_entity(entity_mapper, item));
+		}
+	}
+}
 impl PlanqProcess {
 	pub fn new() -> PlanqProcess {
 		PlanqProcess {
@@ -431,6 +619,30 @@ impl std::fmt::Display for PlanqCPUMode {
 		write!(f, "{}", output)
 	}
 }
+impl PlanqCPUMode {
+	/// Returns true if the PLANQ occupies the player's hands in this mode, such that they can't perform the given action
+	/// Looking around and checking their own pockets are always allowed
+	pub fn restricts(&self, action: ActionType) -> bool {
+		if !matches!(self, PlanqCPUMode::Startup | PlanqCPUMode::Shutdown) { return false; }
+		!matches!(action, ActionType::NoAction | ActionType::Examine | ActionType::Inventory | ActionType::AssignSlot)
+	}
+}
+//  ##: ScanRecord
+/// One entry in the PLANQ's scan log: what was scanned, and where and when it was last seen
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct ScanRecord {
+	pub entity: Entity,
+	pub name: String,
+	pub posn: Position,
+	pub turn: u64, // The RunState's turn_count at the time of the scan
+}
+//  ##: OutputBlock
+/// One block of the PLANQ's terminal output, as sorted out by PlanqData::output_blocks()
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputBlock {
+	Stdout(u32, Vec<Message>), // The lines that went up together, with their block id from the MessageLog
+	Idle(String), // The current frame of the idle graphic, which never shares its block with anything else
+}
 /// Defines the full set of user commands that can actually be executed on the PLANQ
 #[derive(Clone, Debug, Default, PartialEq, Eq, Reflect, EnumIter)]
 pub enum PlanqCmd {
@@ -441,7 +653,31 @@ pub enum PlanqCmd {
 	Shutdown,
 	Reboot,
 	Connect(String),
-	Disconnect
+	Disconnect,
+	Msgs(Option<usize>),
+	Mark(String),
+	Marks,
+	Goto(usize),
+	Notes(Option<usize>),
+	Analyze(String),
+	Scan,
+	ScanLog,
+	ShipLog(usize),
+	Dash,
+	Map,
+	Export,
+	Download,
+	Upload,
+	Transcript(bool),
+	#[cfg(debug_assertions)]
+	Census, // DEBUG: counts up the live entities by category
+	#[cfg(debug_assertions)]
+	Reveal, // DEBUG: cycles the camera's full-map reveal
+	#[cfg(debug_assertions)]
+	DiffSaves(String, String), // DEBUG: lists what changed between two save slots
+	Power,
+	#[cfg(debug_assertions)]
+	Blackout(u64), // DEBUG: cuts every generator for the given number of turns
 }
 impl std::fmt::Display for PlanqCmd {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -453,7 +689,115 @@ impl std::fmt::Display for PlanqCmd {
 			PlanqCmd::Reboot => { write!(f, "reboot") }
 			PlanqCmd::Connect(_) => { write!(f, "connect") }
 			PlanqCmd::Disconnect => { write!(f, "disconnect") }
+			PlanqCmd::Msgs(_) => { write!(f, "msgs") }
+			PlanqCmd::Mark(_) => { write!(f, "mark") }
+			PlanqCmd::Marks => { write!(f, "marks") }
+			PlanqCmd::Goto(_) => { write!(f, "goto") }
+			PlanqCmd::Notes(_) => { write!(f, "notes") }
+			PlanqCmd::Analyze(_) => { write!(f, "analyze") }
+			PlanqCmd::Scan => { write!(f, "scan") }
+			PlanqCmd::ScanLog => { write!(f, "scan log") }
+			PlanqCmd::ShipLog(_) => { write!(f, "log") }
+			#[cfg(debug_assertions)]
+			PlanqCmd::Census => { write!(f, "census") }
+			#[cfg(debug_assertions)]
+			PlanqCmd::Reveal => { write!(f, "reveal") }
+			#[cfg(debug_assertions)]
+			PlanqCmd::DiffSaves(_, _) => { write!(f, "diffsaves") }
+			PlanqCmd::Power => { write!(f, "power") }
+			#[cfg(debug_assertions)]
+			PlanqCmd::Blackout(_) => { write!(f, "power blackout") }
+			PlanqCmd::Dash => { write!(f, "dash") }
+			PlanqCmd::Map => { write!(f, "map") }
+			PlanqCmd::Export => { write!(f, "export") }
+			PlanqCmd::Download => { write!(f, "download") }
+			PlanqCmd::Upload => { write!(f, "upload") }
+			PlanqCmd::Transcript(_) => { write!(f, "transcript") }
+		}
+	}
+}
+
+//  ##: PlanqCmdResult
+/// The outcome of running a PlanqCmd: whatever it had to say, and whether it actually worked
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlanqCmdResult {
+	pub output: Vec<String>,
+	pub success: bool,
+}
+impl PlanqCmdResult {
+	pub fn ok(lines: Vec<String>) -> PlanqCmdResult {
+		PlanqCmdResult { output: lines, success: true }
+	}
+	pub fn fail(lines: Vec<String>) -> PlanqCmdResult {
+		PlanqCmdResult { output: lines, success: false }
+	}
+	/// Puts the output up on the PLANQ's terminal: a success is shown as-is, and a failure gets the ERROR header
+	/// A success with nothing to say doesn't print anything at all, ie when the command opened an app
+	pub fn report(&self, msglog: &mut MessageLog) {
+		if self.success {
+			if !self.output.is_empty() {
+				msglog.tell_planq_block(self.output.clone());
+			}
+		} else {
+			let mut block = vec!["[[fg:yellow]]¶[[fg:gray]]│[[fg:red]]ERROR:".to_string()];
+			block.extend(self.output.iter().map(|x| format!("[[fg:yellow]]¶[[fg:gray]]│[[end]]{}", x)));
+			block.push(" ".to_string());
+			msglog.add_block("planq", block, 0);
+		}
+	}
+}
+
+//  ##: PlanqBootText
+/// The lines that the PLANQ prints at each stage of its boot sequence, in stage order; a scenario or a mod can swap
+/// these out to give the PLANQ a different look without touching the boot logic
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct PlanqBootText {
+	pub stages: Vec<Vec<String>>,
+}
+impl PlanqBootText {
+	/// Creates a new PlanqBootText from the lines for each stage, in order
+	pub fn new(stages: Vec<Vec<String>>) -> PlanqBootText {
+		PlanqBootText { stages }
+	}
+	/// Replaces the lines for a single stage, adding any empty stages that are needed to get there
+	pub fn set_stage(&mut self, boot_stage: u32, lines: Vec<String>) {
+		let index = boot_stage as usize;
+		if self.stages.len() <= index {
+			self.stages.resize(index + 1, Vec::new());
 		}
+		self.stages[index] = lines;
+	}
+}
+impl Default for PlanqBootText {
+	fn default() -> PlanqBootText {
+		// This version of the OS logo doesn't have the extra \s, which are required as escapes by Rust
+		//                     ▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄
+		//                     ▌ __         __  __     __   ▐
+		//                     ▌/   _||   |/  \(_     /_    ▐
+		//                     ▌\__(-|||_||\__/__)  \/__)/) ▐
+		//                     ▌────────<-──────────<-─<{ (<▐
+		//                     ▌         \           \   \) ▐
+		//                     ▙▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▟
+		//                     _123456789_12356789_123456789_
+		let logo = [
+			"[[fg:gray]]╃────────────────────────────╄",
+			"[[fg:gray]]│[[fg:ltcyan]] __         __  __     __   [[fg:gray]]│",
+			"[[fg:gray]]│[[fg:ltcyan]]/   _||   |/  \\(_     /_    [[fg:gray]]│",
+			"[[fg:gray]]│[[fg:ltcyan]]\\__(-|||_||\\__/__)  [[fg:green]]\\/[[fg:ltcyan]]__)[[fg:red]]/) [[fg:gray]]│",
+			"[[fg:gray]]│[[fg:green]]────────<-──────────<-─<[[fg:red]]{ (<[[fg:gray]]│",
+			"[[fg:gray]]│[[fg:green]]         \\           \\   [[fg:red]]\\) [[fg:gray]]│",
+			"[[fg:gray]]┽────────────────────────────╆",
+			" ",
+			"[[fg:yellow]]¶[[fg:gray]]│[[end]]BIOS:  GRAIN v17.6.8, [[mod:+italic]]Cedar[[end]]",
+		];
+		PlanqBootText::new(vec![
+			logo.iter().map(|x| x.to_string()).collect(),
+			vec!["[[fg:yellow]]¶[[fg:gray]]│[[end]]Hardware Status ..... [ [[fg:green]]OK[[end]] ]".to_string()],
+			vec!["[[fg:yellow]]¶[[fg:gray]]│[[end]]Firmware Status ..... [ [[fg:green]]OK[[end]] ]".to_string()],
+			vec!["[[fg:yellow]]¶[[fg:gray]]│[[end]]Bootloader Status ... [ [[fg:green]]OK[[end]] ]".to_string()],
+			vec!["[[fg:yellow]]¶[[fg:gray]]│[[end]]Ready for input!".to_string()],
+		])
 	}
 }
 
@@ -487,6 +831,7 @@ pub enum PlanqEventType {
 	CliClose,
 	AccessLink,
 	AccessUnlink,
+	Analyze(Entity), // Finishes an analysis of the given unidentified item
 }
 
 //  ###: UTILITIES and COMPONENTS
@@ -500,4 +845,243 @@ impl Planq {
 	}
 }
 
+//  ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use super::*;
+	//  ##: Auto-power
+	/// Sets up a headless App with the planq_autopower_system, a player carrying a PLANQ that's switched off, and a
+	/// wrench lying on the floor; the player's preferences ask for the PLANQ to be switched on
+	fn autopower_app() -> (App, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<InventoryChanged>()
+		.insert_resource(Preferences { planq_autopower: true, ..Preferences::default() })
+		.insert_resource(PlanqData::new())
+		.add_systems(Update, planq_autopower_system);
+		let player = app.world.spawn(Player { }).id();
+		let planq = app.world.spawn((Planq::new(), Device::new(1), Portable::new(player))).id();
+		let wrench = app.world.spawn(Portable::new(player)).id();
+		(app, player, planq, wrench)
+	}
+	/// Runs the App once and returns how many times the power button was pressed on the player's behalf
+	fn presses(app: &mut App, planq: Entity) -> usize {
+		app.update();
+		let mut events = app.world.resource_mut::<Events<GameEvent>>();
+		let count = events.get_reader().iter(&events).filter(|x| x.etype == GameEventType::PlayerAction(ActionType::UseItem) && x.context.map_or(false, |c| c.object == planq)).count();
+		// Clear them out so that the next run only counts its own presses
+		events.clear();
+		count
+	}
+	#[test]
+	fn picking_up_the_planq_switches_it_on() {
+		let (mut app, player, planq, _) = autopower_app();
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		assert_eq!(presses(&mut app, planq), 1);
+		assert!(app.world.resource::<PlanqData>().ever_powered);
+	}
+	#[test]
+	fn picking_up_something_else_leaves_the_planq_alone() {
+		let (mut app, player, planq, wrench) = autopower_app();
+		app.world.send_event(InventoryChanged { carrier: player, item: wrench });
+		assert_eq!(presses(&mut app, planq), 0);
+		// The first real pickup of the PLANQ is still to come, so it still gets switched on then
+		assert!(!app.world.resource::<PlanqData>().ever_powered);
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		assert_eq!(presses(&mut app, planq), 1);
+	}
+	#[test]
+	fn only_the_first_pickup_counts() {
+		let (mut app, player, planq, _) = autopower_app();
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		assert_eq!(presses(&mut app, planq), 1);
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		assert_eq!(presses(&mut app, planq), 0);
+	}
+	#[test]
+	fn the_preference_can_turn_it_off() {
+		let (mut app, player, planq, _) = autopower_app();
+		app.world.resource_mut::<Preferences>().planq_autopower = false;
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		assert_eq!(presses(&mut app, planq), 0);
+	}
+	//  ##: Power button
+	#[test]
+	fn the_power_button_describes_the_state_it_found() {
+		let mut planq = PlanqData::new();
+		assert_eq!(planq.power_button_message(), "There is a faint 'click' as you press the PLANQ's power button, and its screen flickers to life.");
+		planq.power_is_on = true;
+		planq.cpu_mode = PlanqCPUMode::Startup;
+		assert_eq!(planq.power_button_message(), "You press the PLANQ's power button, but it's still booting up and doesn't respond.");
+		for mode in [PlanqCPUMode::Idle, PlanqCPUMode::Working] {
+			planq.cpu_mode = mode;
+			assert_eq!(planq.power_button_message(), "You press the PLANQ's power button; it's already on, and nothing happens.", "{:?}", mode);
+		}
+		// Whatever state the CPU was left in, a PLANQ without power only ever clicks on
+		planq.power_is_on = false;
+		assert!(planq.power_button_message().starts_with("There is a faint 'click'"));
+	}
+	//  ##: Busy hands
+	#[test]
+	fn booting_and_shutting_down_tie_up_the_players_hands() {
+		for mode in [PlanqCPUMode::Startup, PlanqCPUMode::Shutdown] {
+			assert!(mode.restricts(ActionType::MoveTo(Direction::N)), "{:?}", mode);
+			assert!(mode.restricts(ActionType::OpenItem), "{:?}", mode);
+			// Looking around and checking the inventory don't need any hands
+			assert!(!mode.restricts(ActionType::Examine), "{:?}", mode);
+			assert!(!mode.restricts(ActionType::Inventory), "{:?}", mode);
+		}
+		for mode in [PlanqCPUMode::Offline, PlanqCPUMode::Idle, PlanqCPUMode::Working] {
+			assert!(!mode.restricts(ActionType::MoveTo(Direction::N)), "{:?}", mode);
+		}
+	}
+	#[test]
+	fn only_a_carried_planq_refuses() {
+		let mut planq = PlanqData::new();
+		planq.cpu_mode = PlanqCPUMode::Startup;
+		assert_eq!(planq.refuses(ActionType::MoveTo(Direction::N)), None);
+		planq.is_carried = true;
+		assert_eq!(planq.refuses(ActionType::MoveTo(Direction::N)), Some("You need both hands to steady the PLANQ while it boots up.".to_string()));
+		planq.cpu_mode = PlanqCPUMode::Shutdown;
+		assert_eq!(planq.refuses(ActionType::OpenItem), Some("You need both hands to steady the PLANQ while it shuts down.".to_string()));
+		planq.cpu_mode = PlanqCPUMode::Idle;
+		assert_eq!(planq.refuses(ActionType::MoveTo(Direction::N)), None);
+	}
+	//  ##: Missed events
+	/// Sets up a headless App with the planq_update_system, a player, and a PLANQ that's lying switched off on the floor
+	fn update_app() -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<PlanqEvent>()
+		.add_event::<IdentifyItem>()
+		.add_event::<InventoryChanged>()
+		.add_event::<DevicePowerChanged>()
+		.insert_resource(MissedEvents::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(Time::default())
+		.insert_resource(PlanqBootText::default())
+		.insert_resource(PlanqData::new())
+		.add_systems(Update, planq_update_system);
+		let player = app.world.spawn((Player { }, Body::small(Position::new(1, 1, 0), ScreenCell::new()))).id();
+		let planq = app.world.spawn((Planq::new(), Device::new(1), Portable::empty())).id();
+		(app, player, planq)
+	}
+	#[test]
+	fn an_announced_pickup_updates_the_planq() {
+		let (mut app, player, planq) = update_app();
+		let wrench = app.world.spawn(Portable::new(player)).id();
+		app.world.get_mut::<Portable>(planq).unwrap().carrier = player;
+		app.world.send_event(InventoryChanged { carrier: player, item: planq });
+		app.update();
+		let data = app.world.resource::<PlanqData>();
+		assert!(data.is_carried);
+		assert_eq!(data.inventory_list, vec![wrench]);
+		assert!(app.world.resource::<MissedEvents>().missed.is_empty());
+	}
+	#[test]
+	fn an_announced_power_switch_boots_the_planq() {
+		let (mut app, _, planq) = update_app();
+		app.world.get_mut::<Device>(planq).unwrap().pw_switch = true;
+		app.world.send_event(DevicePowerChanged { device: planq, is_on: true });
+		app.update();
+		assert!(app.world.resource::<PlanqData>().power_is_on);
+		assert_eq!(app.world.resource::<PlanqData>().cpu_mode, PlanqCPUMode::Startup);
+		assert!(app.world.resource::<MissedEvents>().missed.is_empty());
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn a_pickup_without_its_event_is_caught() {
+		let (mut app, player, planq) = update_app();
+		app.world.get_mut::<Portable>(planq).unwrap().carrier = player;
+		app.update();
+		assert_eq!(app.world.resource::<MissedEvents>().missed, vec!["InventoryChanged".to_string()]);
+		// The fallback puts things right, so the same desync isn't caught twice
+		assert!(app.world.resource::<PlanqData>().is_carried);
+		app.update();
+		assert_eq!(app.world.resource::<MissedEvents>().missed.len(), 1);
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn a_power_switch_without_its_event_is_caught() {
+		let (mut app, _, planq) = update_app();
+		app.world.get_mut::<Device>(planq).unwrap().pw_switch = true;
+		app.update();
+		assert_eq!(app.world.resource::<MissedEvents>().missed, vec!["DevicePowerChanged".to_string()]);
+		assert!(app.world.resource::<PlanqData>().power_is_on);
+	}
+	#[cfg(not(debug_assertions))]
+	#[test]
+	fn release_builds_only_follow_the_events() {
+		let (mut app, player, planq) = update_app();
+		app.world.get_mut::<Portable>(planq).unwrap().carrier = player;
+		app.world.get_mut::<Device>(planq).unwrap().pw_switch = true;
+		app.update();
+		assert!(app.world.resource::<MissedEvents>().missed.is_empty());
+		assert!(!app.world.resource::<PlanqData>().is_carried);
+		assert!(!app.world.resource::<PlanqData>().power_is_on);
+	}
+	//  ##: Output blocks
+	/// Returns the texts of the lines in each of the PLANQ's output blocks, with the idle graphic as a lone "IDLE"
+	fn block_texts(planq: &PlanqData) -> Vec<Vec<String>> {
+		planq.output_blocks().into_iter().map(|x| match x {
+			OutputBlock::Stdout(_, lines) => { lines.into_iter().map(|y| y.text).collect() }
+			OutputBlock::Idle(_) => { vec!["IDLE".to_string()] }
+		}).collect()
+	}
+	#[test]
+	fn lines_that_went_up_together_share_a_block() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		msglog.tell_planq("> help");
+		msglog.add_block("planq", vec!["one".to_string(), "two".to_string()], 0);
+		msglog.tell_planq("> ps");
+		msglog.tell_planq("> ls");
+		msglog.add_block("planq", vec!["three".to_string()], 0);
+		let mut planq = PlanqData::new();
+		planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), Duration::ZERO);
+		// The lines that went up on their own stay apart, even when they're right next to each other
+		assert_eq!(block_texts(&planq), vec![
+			vec!["> help".to_string()],
+			vec!["one".to_string(), "two".to_string()],
+			vec!["> ps".to_string()],
+			vec!["> ls".to_string()],
+			vec!["three".to_string()],
+		]);
+	}
+	#[test]
+	fn a_commands_output_stays_on_screen_while_idling() {
+		let mut msglog = MessageLog::new(vec!["planq".to_string()]);
+		let mut planq = PlanqData::new();
+		planq.cpu_mode = PlanqCPUMode::Idle;
+		planq.idle_line = Some("-=[ ]=-".to_string());
+		// With nothing written for a good long while, there's only the idle graphic to show
+		planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), Duration::from_secs(60));
+		assert_eq!(block_texts(&planq), vec![vec!["IDLE".to_string()]]);
+		msglog.tell_planq_block(vec!["help".to_string(), "ps".to_string()]);
+		let frame = Duration::from_millis(250);
+		let frames = IDLE_QUIET_DURATION * 4;
+		for count in 0..frames {
+			planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), frame);
+			// The idle graphic keeps animating the whole time, but never gets shown over the top of the output
+			planq.idle_line = Some(format!("{:width$}-=[ ]=-", "", width = count as usize % 10));
+			let blocks = block_texts(&planq);
+			assert_eq!(blocks.len(), 1, "after {} frames", count);
+			assert!(blocks[0][0].ends_with("help") && blocks[0][1].ends_with("ps"));
+			assert!(planq.get_stdout_as_rows(40).iter().all(|x| x.1 != IDLE_BLOCK));
+		}
+		// Once it's been quiet for long enough, the idle graphic goes up underneath the output instead
+		planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), frame);
+		assert_eq!(planq.since_output, Duration::from_secs(IDLE_QUIET_DURATION));
+		let blocks = block_texts(&planq);
+		assert_eq!(blocks.len(), 2);
+		assert!(blocks[0][0].ends_with("help"));
+		assert_eq!(blocks[1], vec!["IDLE".to_string()]);
+		assert_eq!(planq.get_stdout_as_rows(40).last().map(|x| x.1), Some(IDLE_BLOCK));
+		// And it gets out of the way again as soon as anything new is written
+		msglog.tell_planq("> ls");
+		planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), frame);
+		assert_eq!(block_texts(&planq).last(), Some(&vec!["> ls".to_string()]));
+	}
+}
+
 // EOF
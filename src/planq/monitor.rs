@@ -11,24 +11,38 @@ use ratatui::widgets::*;
 
 // ###: INTERNAL LIBRARIES
 use crate::planq::*;
-use crate::sys::DurationFmtExt;
+use crate::planq::comms::PlanqComms;
+use crate::sys::{DurationFmtExt, ship_clock};
+use crate::worldmap::DeckIndicator;
+
+// ###: CONSTANTS
+/// The most samples that any of the telemetry histories will keep before dropping the oldest
+pub const MAX_HISTORY: usize = 500;
+/// The file that the telemetry gets exported to
+pub const TELEMETRY_FILENAME: &str = "telemetry.csv";
 
 // ###: BEVY SYSTEMS
 /// Handles the PLANQ's output status bars and other such things
 pub fn planq_monitor_system(time:        Res<Time>,
 	                          mut rng:     ResMut<GlobalRng>,
 	                          msglog:      ResMut<MessageLog>,
+	                          comms:       Res<PlanqComms>,
+	                          deck:        Res<DeckIndicator>,
 	                          mut planq:   ResMut<PlanqData>,
 	                          mut monitor: ResMut<PlanqMonitor>,
-	                          p_query:     Query<(Entity, &Body, &Description), With<Player>>,
+	                          p_query:     Query<(Entity, &Body, &Description, Option<&MoveStyle>), With<Player>>,
 	                          //mut q_query: Query<(Entity, &Device, &mut RngComponent), With<Planq>>,
 	                          mut q_query: Query<(Entity, &Device), With<Planq>>,
 	                          mut s_query: Query<(Entity, &mut DataSampleTimer)>,
+	                          mut mreader: EventReader<PlayerMoved>,
+	                          mut missed:  ResMut<MissedEvents>,
 ) {
 	if p_query.is_empty() { return; }
 	if q_query.is_empty() { return; }
-	let (_enty, p_body, p_desc) = if let Ok(value) = p_query.get_single() { value } else { return };
+	let (_enty, p_body, p_desc, p_style) = if let Ok(value) = p_query.get_single() { value } else { return };
 	let (_enty, q_device) = if let Ok(value) = q_query.get_single_mut() { value } else { return };
+	let last_move = mreader.iter().last().copied();
+	let has_moved = last_move.is_some();
 	// Iterate any active PlanqProcesses
 	// These should be iterated locally here so that they are consistent from frame to frame; this is because
 	//   Bevy's Systems implement a multithreading model that does NOT guarantee anything about consistent concurrency
@@ -50,15 +64,39 @@ pub fn planq_monitor_system(time:        Res<Time>,
 					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(planq.cpu_mode.to_string()));
 				}
 				"player_location" => {
+					// The location only needs redoing when the player has actually gone somewhere
+					if !has_moved && monitor.visited.last() == Some(&p_desc.locn) { continue; }
 					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(p_desc.locn.clone()));
+					if !p_desc.locn.is_empty() && monitor.visited.last() != Some(&p_desc.locn) {
+						monitor.visited.push(p_desc.locn.clone());
+					}
+				}
+				"deck_indicator"  => {
+					// The indicator itself is only redone when the player moves, so this just copies over the latest one
+					if monitor.raw_data.get(&source_name) == Some(&PlanqDataType::Text(deck.text.clone())) { continue; }
+					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(deck.text.clone()));
 				}
 				"current_time"    => { // FIXME: this shows as a stopwatch instead of an actual clock
-					let start_time_offset = Duration::new(2096, 789); // 12:34:56.789
-					let current_time = time.elapsed() + start_time_offset;
+					let current_time = ship_clock(&time);
 					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(current_time.get_as_string()));
 				}
+				"comms_unread"    => {
+					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Text(comms.unread_count().to_string()));
+				}
 				"planq_battery"   => {
 					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Percent(q_device.batt_voltage as u32));
+					// Only the changes are worth keeping, otherwise the history would fill up with one sample per frame
+					if let Some(PlanqDataType::Series(ref mut arr)) = monitor.raw_data.get_mut("battery_history") {
+						let level = q_device.batt_voltage.max(0) as u64;
+						if arr.back() != Some(&level) {
+							arr.push_back(level);
+							if arr.len() > MAX_HISTORY { arr.pop_front(); }
+						}
+					}
+				}
+				"noise_meter"     => {
+					let pct = p_style.map_or(0, |x| x.meter_percent());
+					monitor.raw_data.entry(source_name).and_modify(|x| *x = PlanqDataType::Percent(pct));
 				}
 				"test_line"       => {
 					monitor.raw_data.entry(source_name)
@@ -91,9 +129,16 @@ pub fn planq_monitor_system(time:        Res<Time>,
 	// -- SIMPLE DATA
 	// Refresh the planq's scrollback
 	// TODO: optimize this to avoid doing a full copy of the log every single time
-	planq.stdout = msglog.get_log_as_messages("planq", 0);
+	planq.refresh_stdout(msglog.get_log_as_messages("planq", 0), time.delta());
 	// Get the player's location
-	planq.player_loc = p_body.ref_posn;
+	if let Some(moved) = last_move {
+		planq.player_loc = moved.posn;
+	}
+	if cfg!(debug_assertions) && planq.player_loc != p_body.ref_posn { // DEBUG: catch any player movement that went unannounced
+		error!("! planq_monitor_system: the player moved to {} without a PlayerMoved event", p_body.ref_posn);
+		missed.flag("PlayerMoved");
+		planq.player_loc = p_body.ref_posn;
+	}
 }
 
 //  ###: MAJOR FXNS
@@ -103,6 +148,7 @@ pub fn planq_monitor_system(time:        Res<Time>,
 pub struct PlanqMonitor {
 	pub status_bars: Vec<String>, // The list of active statusbar modules
 	pub raw_data: HashMap<String, PlanqDataType>, // Contains the live monitoring data
+	pub visited: Vec<String>, // The names of the places that the player has been, in the order they got there
 }
 impl PlanqMonitor {
 	// Builders
@@ -123,6 +169,17 @@ impl PlanqMonitor {
 		}
 		false
 	}
+	/// Produces the telemetry that the monitor has recorded as CSV text, with one row per sample;
+	/// returns None if nothing has been recorded yet
+	pub fn telemetry_csv(&self) -> Option<String> {
+		let mut rows: Vec<String> = Vec::new();
+		if let Some(PlanqDataType::Series(arr)) = self.raw_data.get("battery_history") {
+			rows.extend(arr.iter().enumerate().map(|(index, value)| format!("battery_history,{},{}", index, value)));
+		}
+		rows.extend(self.visited.iter().enumerate().map(|(index, place)| format!("location,{},\"{}\"", index, place.replace('"', "\"\""))));
+		if rows.is_empty() { return None; }
+		Some(format!("series,index,value\n{}\n", rows.join("\n")))
+	}
 	/// Describes how the PLANQ's monitor will render to the screen
 	/// Note that the area parameter should be just the sidebar area, not including the terminal
 	pub fn render<B: Backend>(&mut self, frame: &mut Frame<'_, B>, mut area: Rect) {
@@ -147,7 +204,9 @@ impl PlanqMonitor {
 						let prefix = match source.as_str() {
 							"planq_mode" => { "MODE: ".to_string() }
 							"player_location" => { "LOCN: ".to_string() }
+							"deck_indicator" => { "DECK: ".to_string() }
 							"current_time" => { "TIME: ".to_string() }
+							"comms_unread" => { "MSGS: ".to_string() }
 							_ => { "".to_string() }
 						};
 						let remainder = area.width as usize - prefix.len() - 2;
@@ -169,6 +228,14 @@ impl PlanqMonitor {
 							frame.render_widget(Gauge::default().percent(*pct as u16).label(format!("{:width$}", output, width = area.width as usize))
 							                    .gauge_style(Style::default().fg(Color::White).bg(Color::Black))
 							                    .block(default_block.clone()), area)
+						} else if source == "noise_meter" {
+							let prefix = "NOIS: ".to_string();
+							let remainder = area.width as usize - prefix.len() - 2;
+							let line = PlanqMonitor::right_align(format!("{}{}", pct, "%").as_str(), remainder);
+							let output = prefix + &line;
+							frame.render_widget(Gauge::default().percent(*pct as u16).label(format!("{:width$}", output, width = area.width as usize))
+							                    .gauge_style(Style::default().fg(Color::Yellow).bg(Color::Black))
+							                    .block(default_block.clone()), area)
 						} else {
 							frame.render_widget(Gauge::default().percent(*pct as u16)
 							                    .gauge_style(Style::default().fg(Color::White).bg(Color::Black))
@@ -210,12 +277,17 @@ impl PlanqMonitor {
 impl Default for PlanqMonitor {
 	fn default() -> PlanqMonitor {
 		PlanqMonitor {
-			status_bars: vec!["planq_battery".to_string(), "planq_mode".to_string(), "current_time".to_string(), "player_location".to_string()],
+			status_bars: vec!["planq_battery".to_string(), "planq_mode".to_string(), "current_time".to_string(), "player_location".to_string(), "deck_indicator".to_string(), "comms_unread".to_string(), "noise_meter".to_string()],
 			raw_data: HashMap::from([("current_time".to_string(), PlanqDataType::Text("Initializing...".to_string())),
+				                       ("comms_unread".to_string(), PlanqDataType::Text("0".to_string())),
 				                       ("planq_battery".to_string(), PlanqDataType::Percent(0)),
+				                       ("noise_meter".to_string(), PlanqDataType::Percent(0)),
 				                       ("planq_mode".to_string(), PlanqDataType::Text("Initializing...".to_string())),
 				                       ("player_location".to_string(), PlanqDataType::Text("Initializing...".to_string())),
+				                       ("deck_indicator".to_string(), PlanqDataType::Text("Initializing...".to_string())),
+				                       ("battery_history".to_string(), PlanqDataType::Series(VecDeque::new())),
 			]),
+			visited: Vec::new(),
 		}
 	}
 }
@@ -254,4 +326,78 @@ pub enum PlanqDataType {
 	Series(VecDeque<u64>),
 }
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use super::*;
+	//  ##: Player location
+	/// Sets up a headless App with the planq_monitor_system, a player, and a PLANQ; the player's arrival is announced
+	fn monitor_app() -> (App, Entity) {
+		let mut app = App::new();
+		app.add_event::<PlayerMoved>()
+		.insert_resource(MissedEvents::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(Time::default())
+		.insert_resource(GlobalRng::with_seed(1))
+		.insert_resource(PlanqComms::default())
+		.insert_resource(DeckIndicator::default())
+		.insert_resource(PlanqData::new())
+		.insert_resource(PlanqMonitor::new())
+		.add_systems(Update, planq_monitor_system);
+		let start = Position::new(3, 3, 0);
+		let player = app.world.spawn((Player { }, Body::small(start, ScreenCell::new()), Description::new().name("player"))).id();
+		app.world.spawn((Planq::new(), Device::new(1)));
+		app.world.send_event(PlayerMoved { posn: start });
+		app.update();
+		(app, player)
+	}
+	#[test]
+	fn an_announced_move_updates_the_location() {
+		let (mut app, player) = monitor_app();
+		assert_eq!(app.world.resource::<PlanqData>().player_loc, Position::new(3, 3, 0));
+		app.world.get_mut::<Body>(player).unwrap().move_to(Position::new(4, 3, 0));
+		app.world.send_event(PlayerMoved { posn: Position::new(4, 3, 0) });
+		app.update();
+		assert_eq!(app.world.resource::<PlanqData>().player_loc, Position::new(4, 3, 0));
+		assert!(app.world.resource::<MissedEvents>().missed.is_empty());
+	}
+	#[cfg(debug_assertions)]
+	#[test]
+	fn a_move_without_its_event_is_caught() {
+		let (mut app, player) = monitor_app();
+		app.world.get_mut::<Body>(player).unwrap().move_to(Position::new(4, 3, 0));
+		app.update();
+		assert_eq!(app.world.resource::<MissedEvents>().missed, vec!["PlayerMoved".to_string()]);
+		assert_eq!(app.world.resource::<PlanqData>().player_loc, Position::new(4, 3, 0));
+	}
+	#[cfg(not(debug_assertions))]
+	#[test]
+	fn release_builds_only_follow_the_moves_they_hear_about() {
+		let (mut app, player) = monitor_app();
+		app.world.get_mut::<Body>(player).unwrap().move_to(Position::new(4, 3, 0));
+		app.update();
+		assert!(app.world.resource::<MissedEvents>().missed.is_empty());
+		assert_eq!(app.world.resource::<PlanqData>().player_loc, Position::new(3, 3, 0));
+	}
+	//  ##: Telemetry export
+	#[test]
+	fn the_telemetry_is_exported_in_three_columns() {
+		let mut monitor = PlanqMonitor::new();
+		assert_eq!(monitor.telemetry_csv(), None);
+		monitor.raw_data.insert("battery_history".to_string(), PlanqDataType::Series(VecDeque::from(vec![100, 97])));
+		monitor.visited = vec!["galley".to_string(), "the \"pit\"".to_string()];
+		let csv = monitor.telemetry_csv().expect("there should be something to export");
+		let rows: Vec<&str> = csv.lines().collect();
+		assert_eq!(rows, vec![
+			"series,index,value",
+			"battery_history,0,100",
+			"battery_history,1,97",
+			"location,0,\"galley\"",
+			"location,1,\"the \"\"pit\"\"\"",
+		]);
+		assert!(csv.ends_with('\n'));
+	}
+}
+
 // EOF
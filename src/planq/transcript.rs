@@ -0,0 +1,142 @@
+// planq/transcript.rs
+// Provides the PLANQ's transcript recorder, which copies everything that crosses the PLANQ's terminal out to a text
+// file, ie for attaching to a bug report
+
+// ###: EXTERNAL LIBRARIES
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBRARIES
+use crate::engine::legacy::strip_markup;
+use crate::engine::messagelog::MessageLog;
+use crate::sys::{ship_clock, DurationFmtExt};
+
+// ###: CONSTANTS
+/// The file that the transcript is written to, in the working directory; each new recording replaces the last one
+pub const TRANSCRIPT_FILENAME: &str = "planq_transcript.txt";
+
+// ###: BEVY SYSTEMS
+/// Copies any new lines on the PLANQ's terminal into the transcript, stamped with the ship time
+pub fn planq_transcript_system(mut transcript: ResMut<PlanqTranscript>,
+	                             msglog:         Res<MessageLog>,
+	                             time:           Res<Time>,
+) {
+	if !transcript.is_recording() { return; }
+	transcript.capture(&msglog, &ship_clock(&time).get_as_string());
+}
+
+// ###: METHODS
+/// Returns where a new transcript gets written to, which is in the working directory if there is one
+pub fn transcript_path() -> PathBuf {
+	std::env::current_dir().map(|x| x.join(TRANSCRIPT_FILENAME)).unwrap_or(PathBuf::from(TRANSCRIPT_FILENAME))
+}
+
+// ###: COMPLEX TYPES
+//  ##: PlanqTranscript
+/// Holds the transcript that's being recorded, if there is one; it isn't saved along with the game, since the file that
+/// it's writing to belongs to this session only
+#[derive(Resource, Debug, Default)]
+pub struct PlanqTranscript {
+	recording: Option<Transcript>,
+}
+impl PlanqTranscript {
+	/// Starts recording a new transcript at the given path, beginning from whatever is next added to the PLANQ's
+	/// terminal; returns the path, or the reason that the file couldn't be made
+	pub fn start(&mut self, msglog: &MessageLog, path: &Path) -> std::io::Result<PathBuf> {
+		let writer = BufWriter::new(File::create(path)?);
+		self.recording = Some(Transcript { writer, path: path.to_path_buf(), seen: msglog.channel_len("planq") });
+		Ok(path.to_path_buf())
+	}
+	/// Writes out anything that's still waiting and closes the transcript; returns its path, or None if there wasn't one
+	pub fn stop(&mut self, msglog: &MessageLog, stamp: &str) -> Option<PathBuf> {
+		self.capture(msglog, stamp);
+		self.recording.take().map(|x| x.path)
+	}
+	/// Returns true if a transcript is being recorded
+	pub fn is_recording(&self) -> bool {
+		self.recording.is_some()
+	}
+	/// Writes out any messages that have arrived in the PLANQ's channel since the last capture
+	/// Each capture goes straight out to the disk, so that a crash still leaves everything up to the last update behind,
+	/// since that's usually when the transcript is wanted most
+	pub fn capture(&mut self, msglog: &MessageLog, stamp: &str) {
+		let transcript = if let Some(value) = self.recording.as_mut() { value } else { return };
+		let channel = if let Some(value) = msglog.logs.iter().find(|x| x.name == "planq") { value } else { return };
+		if channel.contents.len() <= transcript.seen { return; }
+		for msg in channel.contents.iter().skip(transcript.seen) {
+			if let Err(e) = writeln!(transcript.writer, "[{}] {}", stamp, strip_markup(&msg.text)) {
+				error!("! could not write to the transcript at {}: {}", transcript.path.display(), e); // DEBUG: report a failed transcript write
+				self.recording = None;
+				return;
+			}
+		}
+		transcript.seen = channel.contents.len();
+		if let Err(e) = transcript.writer.flush() {
+			error!("! could not flush the transcript at {}: {}", transcript.path.display(), e); // DEBUG: report a failed transcript flush
+			self.recording = None;
+		}
+	}
+}
+//   #: Transcript
+/// An open transcript file, and how far through the PLANQ's channel it has got
+#[derive(Debug)]
+struct Transcript {
+	writer: BufWriter<File>,
+	path: PathBuf,
+	seen: usize, // The number of messages in the PLANQ's channel that have already been written out
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use super::*;
+	/// Sets up a headless App with the transcript system, recording into a scratch file with the given name
+	fn transcript_app(filename: &str) -> (App, PathBuf) {
+		let path = std::env::temp_dir().join(filename);
+		let mut msglog = MessageLog::new(vec!["world".to_string(), "planq".to_string()]);
+		msglog.tell_planq("this was said before the recording started");
+		let mut transcript = PlanqTranscript::default();
+		transcript.start(&msglog, &path).expect("could not start the test transcript");
+		let mut app = App::new();
+		app.insert_resource(msglog)
+		.insert_resource(transcript)
+		.insert_resource(Time::default())
+		.add_systems(Update, planq_transcript_system);
+		(app, path)
+	}
+	#[test]
+	fn new_planq_lines_are_written_out_without_markup() {
+		let (mut app, path) = transcript_app("test_transcript_capture.txt");
+		app.world.resource_mut::<MessageLog>().tell_planq("[[fg:red]]ERROR:[[end]] no signal");
+		app.world.resource_mut::<MessageLog>().tell_player("this belongs to the world channel");
+		app.update();
+		// Each update goes straight out to the disk, so the file is complete even before the recording stops
+		let text = std::fs::read_to_string(&path).expect("could not read back the test transcript");
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines.len(), 1, "{:?}", lines);
+		assert!(lines[0].starts_with('['));
+		assert!(lines[0].ends_with("] ERROR: no signal"), "{:?}", lines);
+		let _ = std::fs::remove_file(&path);
+	}
+	#[test]
+	fn stopping_catches_the_last_lines_and_ends_the_recording() {
+		let (mut app, path) = transcript_app("test_transcript_stop.txt");
+		app.update();
+		app.world.resource_mut::<MessageLog>().tell_planq("goodbye");
+		let stopped = app.world.resource_scope(|world, mut transcript: Mut<PlanqTranscript>| {
+			transcript.stop(world.resource::<MessageLog>(), "12:34:56")
+		});
+		assert_eq!(stopped, Some(path.clone()));
+		assert!(!app.world.resource::<PlanqTranscript>().is_recording());
+		app.world.resource_mut::<MessageLog>().tell_planq("nobody is listening");
+		app.update();
+		let text = std::fs::read_to_string(&path).expect("could not read back the test transcript");
+		assert_eq!(text, "[12:34:56] goodbye\n");
+		let _ = std::fs::remove_file(&path);
+	}
+}
+
+// EOF
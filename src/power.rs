@@ -0,0 +1,237 @@
+// power.rs
+// Provides the ship's power grid, which decides which zones have mains power and which devices go dark without it
+
+// ###: EXTERNAL LIBS
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::RunState;
+use crate::engine::event::*;
+use crate::engine::messagelog::*;
+use crate::worldmap::WorldModel;
+
+// ###: BEVY SYSTEMS
+/// Runs any blackouts, takes mains-powered devices offline in the zones that have lost power, and brings them back up
+/// as their zones are reconnected, nearest to the generator first
+pub fn power_grid_system(mut oreader: EventReader<PowerOutage>,
+	                     mut pwriter: EventWriter<DevicePowerChanged>,
+	                     mut swriter: EventWriter<ShipLogEvent>,
+	                     mut msglog:  ResMut<MessageLog>,
+	                     mut grid:    ResMut<PowerGrid>,
+	                     run_state:   Res<RunState>,
+	                     model:       Res<WorldModel>,
+	                     mut d_query: Query<(Entity, &Body, &mut Device)>,
+) {
+	// Start any new outages, and end any of the old ones whose time is up
+	for outage in oreader.iter() {
+		let names: Vec<String> = grid.generators.iter()
+			.filter(|x| x.is_online && outage.generator.as_ref().map_or(true, |name| *name == x.name))
+			.map(|x| x.name.clone())
+			.collect();
+		if names.is_empty() { continue; }
+		for name in names {
+			swriter.send(ShipLogEvent::new(&format!("ALERT: {} offline", name)));
+			grid.set_online(&name, false);
+			grid.restore_at.push((name, run_state.turn_count + outage.duration));
+		}
+		msglog.tell_player("The lights flicker and die as the power goes out.");
+	}
+	let restored: Vec<String> = grid.restore_at.iter()
+		.filter(|x| x.1 <= run_state.turn_count)
+		.map(|x| x.0.clone())
+		.collect();
+	if !restored.is_empty() {
+		grid.restore_at.retain(|x| x.1 > run_state.turn_count);
+		for name in restored.iter() {
+			swriter.send(ShipLogEvent::new(&format!("{} back online", name)));
+			grid.set_online(name, true);
+		}
+		msglog.tell_player("Somewhere deep in the ship, a generator hums back to life.");
+	}
+	let powered = grid.resolve();
+	let dark_zones: Vec<String> = grid.zones().into_iter().filter(|x| !powered.contains(x)).collect();
+	// Anything that needs the mains and is switched on in a dark zone gets shut off, and remembered for later
+	for (d_enty, d_body, mut d_device) in d_query.iter_mut() {
+		if !d_device.requires_mains || !d_device.pw_switch { continue; }
+		let zone = if let Some(value) = model.layout.get_room_name(d_body.ref_posn) { value } else { continue };
+		if !dark_zones.contains(&zone) { continue; }
+		d_device.power_off();
+		if !grid.tripped.contains(&d_enty) { grid.tripped.push(d_enty); }
+		pwriter.send(DevicePowerChanged { device: d_enty, is_on: false });
+	}
+	// The tripped devices come back in the same order as the power does
+	let mut tripped = std::mem::take(&mut grid.tripped);
+	for zone in powered.iter() {
+		tripped.retain(|d_enty| {
+			let (_, d_body, mut d_device) = if let Ok(value) = d_query.get_mut(*d_enty) { value } else { return false };
+			if model.layout.get_room_name(d_body.ref_posn).as_ref() != Some(zone) { return true; }
+			if d_device.power_on() {
+				pwriter.send(DevicePowerChanged { device: *d_enty, is_on: true });
+			}
+			false
+		});
+	}
+	// Anything that was despawned while it was tripped can be forgotten about
+	tripped.retain(|d_enty| d_query.contains(*d_enty));
+	grid.tripped = tripped;
+	grid.powered = powered;
+}
+
+// ###: COMPLEX TYPES
+//  ##: PowerGrid
+/// Describes which generators the ship has, and which zones draw their power from which generator or other zone
+/// Any zone that isn't listed in the feeds is off the grid entirely, and always counts as having power
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct PowerGrid {
+	pub generators: Vec<Generator>,
+	pub feeds: Vec<PowerFeed>,
+	pub powered: Vec<String>, // The zones that had power as of the last update, in the order that they receive it
+	pub tripped: Vec<Entity>, // The devices that were shut off by a loss of power, and will come back with it
+	pub restore_at: Vec<(String, u64)>, // The generators that are out, and the turn on which they'll come back
+}
+impl MapEntities for PowerGrid {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for d_enty in self.tripped.iter_mut() {
+			*d_enty = map_entity(entity_mapper, *d_enty);
+		}
+	}
+}
+impl PowerGrid {
+	/// Creates a new grid with the given generators, all of them online and none of them feeding anything yet
+	pub fn new(generators: Vec<String>) -> PowerGrid {
+		PowerGrid {
+			generators: generators.into_iter().map(|name| Generator { name, is_online: true }).collect(),
+			..PowerGrid::default()
+		}
+	}
+	/// Has the given zone draw its power from the given source, which can be a generator or another zone
+	pub fn connect(&mut self, zone: &str, source: &str) {
+		self.feeds.push(PowerFeed { zone: zone.to_string(), source: source.to_string() });
+	}
+	/// Switches the named generator on or off; returns false if there's no generator by that name
+	pub fn set_online(&mut self, name: &str, state: bool) -> bool {
+		if let Some(generator) = self.generators.iter_mut().find(|x| x.name == name) {
+			generator.is_online = state;
+			return true;
+		}
+		false
+	}
+	/// Returns every zone that's on the grid, in the order that they're listed in the feeds
+	pub fn zones(&self) -> Vec<String> {
+		let mut zones: Vec<String> = Vec::new();
+		for feed in self.feeds.iter() {
+			if !zones.contains(&feed.zone) { zones.push(feed.zone.clone()); }
+		}
+		zones
+	}
+	/// Works out which zones have power, by following the feeds outward from each of the online generators
+	/// The zones are returned in the order that the power reaches them, which is also the order that they recover in
+	pub fn resolve(&self) -> Vec<String> {
+		let mut powered: Vec<String> = Vec::new();
+		let mut queue: VecDeque<String> = self.generators.iter().filter(|x| x.is_online).map(|x| x.name.clone()).collect();
+		while let Some(source) = queue.pop_front() {
+			for feed in self.feeds.iter().filter(|x| x.source == source) {
+				if powered.contains(&feed.zone) { continue; }
+				powered.push(feed.zone.clone());
+				queue.push_back(feed.zone.clone());
+			}
+		}
+		powered
+	}
+	/// Returns true if the given zone has power, which includes any zone that isn't on the grid at all
+	pub fn is_powered(&self, zone: &str) -> bool {
+		self.powered.iter().any(|x| x == zone) || !self.feeds.iter().any(|x| x.zone == zone)
+	}
+	/// Describes the state of the grid, as lines of text for display
+	pub fn status_report(&self) -> Vec<String> {
+		let mut lines = Vec::new();
+		if self.generators.is_empty() && self.feeds.is_empty() {
+			lines.push("No power grid found.".to_string());
+			return lines;
+		}
+		for generator in self.generators.iter() {
+			lines.push(format!("{}: {}", generator.name, if generator.is_online { "ONLINE" } else { "OFFLINE" }));
+		}
+		for zone in self.zones() {
+			lines.push(format!("  {}: {}", zone, if self.is_powered(&zone) { "powered" } else { "NO POWER" }));
+		}
+		if !self.tripped.is_empty() {
+			lines.push(format!("{} device(s) waiting on power", self.tripped.len()));
+		}
+		lines
+	}
+}
+//  ##: Generator
+/// A source of mains power, which feeds every zone that's connected to it while it's online
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct Generator {
+	pub name: String,
+	pub is_online: bool,
+}
+//  ##: PowerFeed
+/// A single link in the power grid: the zone draws its power from the source, which is a generator or another zone
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct PowerFeed {
+	pub zone: String,
+	pub source: String,
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	/// A generator feeding a chain of three zones, with a fourth zone hanging off of the first
+	fn chain_grid() -> PowerGrid {
+		let mut grid = PowerGrid::new(vec!["reactor".to_string()]);
+		grid.connect("Engineering", "reactor");
+		grid.connect("Corridor", "Engineering");
+		grid.connect("Bridge", "Corridor");
+		grid.connect("Galley", "Engineering");
+		grid
+	}
+	//  ##: Outages
+	#[test]
+	fn an_outage_cascades_down_the_whole_feed() {
+		let mut grid = chain_grid();
+		grid.set_online("reactor", false);
+		grid.powered = grid.resolve();
+		for zone in ["Engineering", "Corridor", "Bridge", "Galley"] {
+			assert!(!grid.is_powered(zone), "{} still has power", zone);
+		}
+	}
+	#[test]
+	fn zones_off_the_grid_always_have_power() {
+		let mut grid = chain_grid();
+		grid.set_online("reactor", false);
+		grid.powered = grid.resolve();
+		assert!(grid.is_powered("Cargo Hold"));
+	}
+	#[test]
+	fn a_second_generator_keeps_its_own_zones_lit() {
+		let mut grid = chain_grid();
+		grid.generators.push(Generator { name: "backup".to_string(), is_online: true });
+		grid.connect("Medbay", "backup");
+		grid.set_online("reactor", false);
+		grid.powered = grid.resolve();
+		assert!(grid.is_powered("Medbay"));
+		assert!(!grid.is_powered("Bridge"));
+	}
+	//  ##: Recovery
+	#[test]
+	fn zones_recover_nearest_to_the_generator_first() {
+		let grid = chain_grid();
+		assert_eq!(grid.resolve(), vec!["Engineering", "Corridor", "Galley", "Bridge"]);
+	}
+	#[test]
+	fn a_feed_loop_does_not_hang_the_resolver() {
+		let mut grid = chain_grid();
+		grid.connect("Engineering", "Bridge");
+		assert_eq!(grid.resolve().len(), 4);
+	}
+}
+
+// EOF
@@ -0,0 +1,192 @@
+// quickslots.rs
+// Provides the player's inventory quick-slots, which put a carried item's usual action on one of the number keys
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::event::*;
+use crate::engine::event::GameEventType::*;
+use crate::engine::messagelog::*;
+
+// ###: CONSTANTS
+/// The number of quick-slots, one for each of the number keys 1 through 9
+pub const QUICK_SLOT_COUNT: usize = 9;
+
+// ###: BEVY SYSTEMS
+/// Binds items to quick-slots when the player asks for it, and drops the bindings of items that have left the player's
+/// inventory
+pub fn quickslot_system(mut ereader: EventReader<GameEvent>,
+	                      mut creader: EventReader<InventoryChanged>,
+	                      mut msglog:  ResMut<MessageLog>,
+	                      mut slots:   ResMut<QuickSlots>,
+	                      p_query:     Query<Entity, With<Player>>,
+	                      i_query:     Query<(&Description, &Portable)>,
+) {
+	let player = if let Ok(value) = p_query.get_single() { value } else { return };
+	for event in ereader.iter() {
+		if event.etype != PlayerAction(ActionType::AssignSlot) { continue; }
+		let econtext = if let Some(value) = event.context { value } else { continue };
+		let i_name = i_query.get(econtext.object).map_or("item".to_string(), |(i_desc, _)| i_desc.name.clone());
+		if let Some(index) = slots.slot_of(econtext.object) {
+			slots.clear(index);
+			msglog.tell_player(format!("You take the {} off quick-slot {}.", i_name, index + 1).as_str());
+		} else if let Some(index) = slots.assign(econtext.object) {
+			msglog.tell_player(format!("You put the {} on quick-slot {}.", i_name, index + 1).as_str());
+		} else {
+			msglog.tell_player("All of your quick-slots are already in use.");
+		}
+	}
+	if creader.is_empty() { return; }
+	creader.clear();
+	// Anything that's no longer in the player's hands can't be used from a quick-slot
+	slots.retain(|item| i_query.get(item).map_or(false, |(_, i_portable)| i_portable.carrier == player));
+}
+
+// ###: COMPLEX TYPES
+//  ##: QuickSlots
+/// Holds the item that's bound to each of the quick-slots, if any; slot 0 is on the 1 key, and so on
+#[derive(Resource, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct QuickSlots {
+	pub slots: Vec<Option<Entity>>,
+}
+impl Default for QuickSlots {
+	fn default() -> QuickSlots {
+		QuickSlots {
+			slots: vec![None; QUICK_SLOT_COUNT],
+		}
+	}
+}
+impl MapEntities for QuickSlots {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for slot in self.slots.iter_mut() {
+			if let Some(item) = slot {
+				*item = map_entity(entity_mapper, *item);
+			}
+		}
+	}
+}
+impl QuickSlots {
+	pub fn new() -> QuickSlots {
+		QuickSlots::default()
+	}
+	/// Returns the item that's bound to the given slot, if there is one
+	pub fn get(&self, index: usize) -> Option<Entity> {
+		self.slots.get(index).copied().flatten()
+	}
+	/// Returns the slot that the given item is bound to, if it has one
+	pub fn slot_of(&self, item: Entity) -> Option<usize> {
+		self.slots.iter().position(|x| *x == Some(item))
+	}
+	/// Binds the given item to the first free slot; returns that slot, or None if they're all taken
+	pub fn assign(&mut self, item: Entity) -> Option<usize> {
+		let index = self.slots.iter().position(|x| x.is_none())?;
+		self.slots[index] = Some(item);
+		Some(index)
+	}
+	pub fn clear(&mut self, index: usize) {
+		if let Some(slot) = self.slots.get_mut(index) {
+			*slot = None;
+		}
+	}
+	/// Clears every slot whose item doesn't pass the given test
+	pub fn retain(&mut self, mut keep: impl FnMut(Entity) -> bool) {
+		for slot in self.slots.iter_mut() {
+			if slot.map_or(false, |x| !keep(x)) {
+				*slot = None;
+			}
+		}
+	}
+	/// Produces the HUD line that lists the current bindings, given a way to look up each item's name; returns None if
+	/// nothing is bound
+	pub fn hint_text(&self, name_of: impl Fn(Entity) -> Option<String>) -> Option<String> {
+		let bindings: Vec<String> = self.slots.iter().enumerate()
+			.filter_map(|(index, slot)| slot.and_then(&name_of).map(|name| format!("{}:{}", index + 1, name)))
+			.collect();
+		if bindings.is_empty() { return None; }
+		Some(format!(" {} ", bindings.join(" ")))
+	}
+}
+
+// ###: UTILITIES
+/// Works out which action a quick-slot performs on the given item, or None if it doesn't have an obvious one
+pub fn quick_action(world: &World, item: Entity) -> Option<ActionType> {
+	if world.get::<Device>(item).is_some() { return Some(ActionType::UseItem); }
+	None
+}
+/// Works out what pressing a quick-slot's key does: either the event for the bound item's usual action, or what to
+/// tell the player instead if there's nothing to be done
+pub fn quick_slot_event(world: &World, index: usize, player: Entity) -> Result<GameEvent, String> {
+	let item = world.get_resource::<QuickSlots>().and_then(|x| x.get(index));
+	let i_enty = if let Some(value) = item { value } else {
+		return Err(format!("There's nothing on quick-slot {}.", index + 1));
+	};
+	if let Some(action) = quick_action(world, i_enty) {
+		return Ok(GameEvent::new(PlayerAction(action), Some(player), Some(i_enty)));
+	}
+	let i_name = world.get::<Description>(i_enty).map_or("item".to_string(), |x| x.name.clone());
+	Err(format!("There's nothing to do with the {} at a moment's notice.", i_name))
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use super::*;
+	/// Sets up a headless App with the quickslot_system, and a player who's carrying a flashlight
+	fn slot_app() -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<InventoryChanged>()
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(QuickSlots::new())
+		.add_systems(Update, quickslot_system);
+		let player = app.world.spawn(Player { }).id();
+		let item = app.world.spawn((Description::new().name("flashlight"), Portable::new(player), Device::new(1))).id();
+		(app, player, item)
+	}
+	//  ##: Auto-clear
+	#[test]
+	fn an_item_that_leaves_the_inventory_loses_its_slot() {
+		let (mut app, player, item) = slot_app();
+		app.world.resource_mut::<QuickSlots>().assign(item);
+		app.world.get_mut::<Portable>(item).unwrap().carrier = Entity::PLACEHOLDER;
+		app.world.send_event(InventoryChanged { carrier: player, item });
+		app.update();
+		assert_eq!(app.world.resource::<QuickSlots>().get(0), None);
+	}
+	#[test]
+	fn an_item_still_in_hand_keeps_its_slot() {
+		let (mut app, player, item) = slot_app();
+		app.world.resource_mut::<QuickSlots>().assign(item);
+		app.world.send_event(InventoryChanged { carrier: player, item });
+		app.update();
+		assert_eq!(app.world.resource::<QuickSlots>().get(0), Some(item));
+	}
+	//  ##: Pressing a slot
+	#[test]
+	fn an_empty_slot_only_gets_a_message() {
+		let (app, player, _) = slot_app();
+		assert_eq!(quick_slot_event(&app.world, 2, player).err(), Some("There's nothing on quick-slot 3.".to_string()));
+	}
+	#[test]
+	fn a_device_is_used_from_its_slot() {
+		let (mut app, player, item) = slot_app();
+		app.world.resource_mut::<QuickSlots>().assign(item);
+		let event = quick_slot_event(&app.world, 0, player).expect("the flashlight should be usable");
+		assert_eq!(event.etype, PlayerAction(ActionType::UseItem));
+		assert_eq!(event.context.map(|x| (x.subject, x.object)), Some((player, item)));
+	}
+	#[test]
+	fn an_item_without_a_usual_action_only_gets_a_message() {
+		let (mut app, player, _) = slot_app();
+		let rock = app.world.spawn((Description::new().name("rock"), Portable::new(player))).id();
+		app.world.resource_mut::<QuickSlots>().assign(rock);
+		assert_eq!(quick_slot_event(&app.world, 0, player).err(), Some("There's nothing to do with the rock at a moment's notice.".to_string()));
+	}
+}
+
+// EOF
@@ -0,0 +1,653 @@
+// robot.rs
+// Provides the maintenance task queue and the AI that lets the ship's robots work through it
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bracket_pathfinding::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::components::Direction;
+use crate::engine::event::*;
+use crate::engine::event::GameEventType::*;
+use crate::engine::messagelog::*;
+use crate::sys::posn_to_point;
+use crate::worldmap::*;
+
+// ###: CONSTANTS
+/// The number of turns that a robot will spend working on a job once it has arrived at the job site
+pub const WORK_DURATION: u32 = 3;
+
+// ###: BEVY SYSTEMS
+/// Inspects the state of the world and posts any maintenance jobs that need doing to the TaskQueue
+pub fn task_generation_system(model:     Res<WorldModel>,
+	                            mut queue: ResMut<TaskQueue>,
+	                            d_query:   Query<(Entity, &Body, &Device, Option<&Portable>)>,
+	                            o_query:   Query<(Entity, &Body, &Openable), Without<Portable>>,
+	                            i_query:   Query<(Entity, &Body, &Portable), Without<IsCarried>>,
+	                            w_query:   Query<&Maintainer>,
+) {
+	// Drop any jobs that don't need doing anymore, ie because someone else fixed the problem
+	// Tidy jobs that are in progress are kept, since the item will be picked up partway through the job, but only while
+	// the worker is still around to finish them
+	queue.retain(|task| match task.job {
+		MaintenanceJob::Repair(enty) => {
+			d_query.get(enty).map_or(false, |(_, _, device, _)| matches!(device.state, DeviceState::Error(_)))
+		}
+		MaintenanceJob::CloseDoor(enty) => { o_query.get(enty).map_or(false, |(_, _, door)| door.is_open) }
+		MaintenanceJob::Tidy(enty) => { task.assignee.map_or(false, |x| w_query.contains(x)) || i_query.get(enty).is_ok() }
+	});
+	// Broken devices, as long as nobody is carrying them around
+	for (d_enty, d_body, d_device, d_portable) in d_query.iter() {
+		if d_portable.map_or(false, |x| x.carrier != Entity::PLACEHOLDER) { continue; }
+		if let DeviceState::Error(_) = d_device.state {
+			queue.post(MaintenanceJob::Repair(d_enty), d_body.ref_posn);
+		}
+	}
+	// Doors that were left open next to hard vacuum
+	for (o_enty, o_body, o_open) in o_query.iter() {
+		let doorway = o_open.doorway(o_body.ref_posn); // An open sliding door will be off in its pocket
+		if o_open.is_open && is_next_to_vacuum(&model, doorway) {
+			queue.post(MaintenanceJob::CloseDoor(o_enty), doorway);
+		}
+	}
+	// Loose items lying around in the hallways
+	for (i_enty, i_body, i_portable) in i_query.iter() {
+		if i_portable.carrier != Entity::PLACEHOLDER { continue; }
+		if let Some(room_name) = model.layout.get_room_name(i_body.ref_posn) {
+			if room_name.contains("hallway") {
+				queue.post(MaintenanceJob::Tidy(i_enty), i_body.ref_posn);
+			}
+		}
+	}
+}
+/// Drives any entity with a Maintainer component: claims jobs, paths to them, and performs the work
+pub fn maintenance_ai_system(mut commands: Commands,
+	                           mut ewriter:  EventWriter<GameEvent>,
+	                           mut freader:  EventReader<ActionFailed>,
+	                           mut swriter:  EventWriter<ShipLogEvent>,
+	                           mut msglog:   ResMut<MessageLog>,
+	                           mut queue:    ResMut<TaskQueue>,
+	                           model:        Res<WorldModel>,
+	                           policy:       Res<PerceptionPolicy>,
+	                           mut w_query:  Query<(Entity, &Body, &Description, &mut Maintainer, Option<&NavCaps>), Without<Disabled>>,
+	                           p_query:      Query<(&Body, &Viewshed), With<Player>>,
+	                           mut t_query:  Query<(&Description, Option<&mut Device>, Option<&mut History>, Option<&Portable>), Without<Maintainer>>,
+	                           l_query:      Query<(Entity, &Body, &Description, Option<&Openable>), (With<Container>, Without<Mobile>, Without<Portable>)>,
+) {
+	let player_view = p_query.get_single().ok();
+	// Any moves that didn't go through last turn, and whether whatever stopped them should clear up by itself
+	let failures: Vec<(Entity, Direction, bool)> = freader.iter().filter_map(|x| match x.action {
+		ActionType::MoveTo(dir) => { Some((x.actor, dir, x.transient)) }
+		_ => { None }
+	}).collect();
+	for (w_enty, w_body, w_desc, mut w_job, w_caps) in w_query.iter_mut() {
+		// A move that was held up by something temporary gets tried once more, as is; if it fails again, or if the
+		// way was properly blocked, then the worker notes the tile that stopped it and plans a new route around it
+		let failure = failures.iter().find(|x| x.0 == w_enty).copied();
+		w_job.retry = match failure {
+			Some((_, dir, true)) if w_job.retry != Some(dir) => { Some(dir) }
+			_ => { None }
+		};
+		if let (Some((_, dir, _)), None) = (failure, w_job.retry) {
+			w_job.avoid.push(w_body.ref_posn + dir.to_offset());
+		}
+		// If the worker is idle, try to find it something to do
+		if w_job.task.is_none() {
+			w_job.task = queue.claim_nearest(w_enty, w_body.ref_posn);
+			w_job.stage = JobStage::Travel;
+		}
+		let task = if let Some(value) = w_job.task { value } else { continue };
+		// If the job was cancelled or resolved by someone else, go back to being idle
+		if !queue.jobs.iter().any(|x| x.job == task.job) {
+			w_job.finish();
+			continue;
+		}
+		// The player only hears about the work if they can perceive the worker doing it
+		let is_perceived = player_view.map_or(false, |(p_body, p_view)| {
+			policy.can_perceive(p_body.ref_posn, Some(p_view), w_body.ref_posn)
+		});
+		match w_job.stage {
+			JobStage::Stowing(locker) => {
+				// The item_collection_system has had its chance to put the item away, so see whether it worked
+				let item = task.job.target();
+				let locker_name = l_query.get(locker).map(|x| x.2.name.clone()).unwrap_or_default();
+				if let Ok((i_desc, _, i_history, i_portable)) = t_query.get_mut(item) {
+					let carrier = i_portable.map_or(Entity::PLACEHOLDER, |x| x.carrier);
+					if carrier == locker {
+						if is_perceived {
+							msglog.tell_player(format!("The {} stows the {} in the {}.", w_desc.name, i_desc.name, locker_name).as_str());
+						}
+						record_history(&mut commands, item, i_history, format!("Stowed in the {} by the {}.", locker_name, w_desc.name));
+						swriter.send(ShipLogEvent::new(&format!("{}: stowed {} in {}", w_desc.name, i_desc.name, locker_name)));
+					} else if carrier == w_enty {
+						// The locker wouldn't take it, ie because it's full, so leave the item here rather than hauling it around
+						ewriter.send(GameEvent::new(ActorAction(ActionType::DropItem), Some(w_enty), Some(item)));
+					}
+				}
+				queue.complete(task.job);
+				w_job.finish();
+			}
+			JobStage::Travel | JobStage::Stow(_) => {
+				let goal = if let JobStage::Stow(locker) = w_job.stage {
+					if let Ok((_, l_body, _, _)) = l_query.get(locker) { l_body.ref_posn } else { Position::INVALID }
+				} else {
+					task.site
+				};
+				if goal == Position::INVALID {
+					// The locker went missing, just keep whatever we were carrying
+					queue.complete(task.job);
+					w_job.finish();
+				} else if w_body.is_adjacent_to(&goal) {
+					if let JobStage::Stow(locker) = w_job.stage {
+						// The item goes into the locker the same way that anyone else would put it there
+						let is_closed = l_query.get(locker).map_or(false, |x| x.3.map_or(false, |y| !y.is_open));
+						if is_closed {
+							ewriter.send(GameEvent::new(ActorAction(ActionType::OpenItem), Some(w_enty), Some(locker)));
+						} else {
+							ewriter.send(GameEvent::give(w_enty, task.job.target(), locker));
+							w_job.stage = JobStage::Stowing(locker);
+						}
+					} else {
+						w_job.stage = JobStage::Work(WORK_DURATION);
+					}
+				} else if let Some(dir) = w_job.retry {
+					ewriter.send(GameEvent::new(ActorAction(ActionType::MoveTo(dir)), Some(w_enty), None));
+				} else if let Some(step) = next_step_around(&model, w_body.ref_posn, goal, w_caps.copied().unwrap_or_default(), &w_job.avoid) {
+					ewriter.send(GameEvent::new(ActorAction(ActionType::MoveTo(step)), Some(w_enty), None));
+				} else {
+					// Can't get there from here; give the job back so that someone else might try it
+					queue.release(task.job);
+					w_job.task = None;
+				}
+			}
+			JobStage::Work(0) => {
+				let target = task.job.target();
+				match task.job {
+					MaintenanceJob::Repair(_) => {
+						if let Ok((t_desc, Some(mut t_device), t_history, _)) = t_query.get_mut(target) {
+							t_device.repair();
+							if is_perceived {
+								msglog.tell_player(format!("The {} finishes repairing the {}.", w_desc.name, t_desc.name).as_str());
+							}
+							record_history(&mut commands, target, t_history, format!("Repaired by the {}.", w_desc.name));
+							swriter.send(ShipLogEvent::new(&format!("{}: repaired {}", w_desc.name, t_desc.name)));
+						}
+						queue.complete(task.job);
+						w_job.finish();
+					}
+					MaintenanceJob::CloseDoor(_) => {
+						// The openable_system will take care of the rest, including any messages
+						ewriter.send(GameEvent::new(ActorAction(ActionType::CloseItem), Some(w_enty), Some(target)));
+						if let Ok((t_desc, _, t_history, _)) = t_query.get_mut(target) {
+							swriter.send(ShipLogEvent::new(&format!("{}: closed {}", w_desc.name, t_desc.name)));
+							record_history(&mut commands, target, t_history, format!("Closed by the {}.", w_desc.name));
+						}
+						queue.complete(task.job);
+						w_job.finish();
+					}
+					MaintenanceJob::Tidy(_) => {
+						// Pick up the item, then go find a locker to put it in
+						ewriter.send(GameEvent::new(ActorAction(ActionType::MoveItem), Some(w_enty), Some(target)));
+						let nearest_locker = l_query.iter()
+							.filter(|(_, l_body, _, _)| l_body.ref_posn.z == w_body.ref_posn.z)
+							.min_by_key(|(_, l_body, _, _)| {
+								let diff = l_body.ref_posn.difference(&w_body.ref_posn);
+								diff.0.abs() + diff.1.abs()
+							})
+							.map(|(l_enty, _, _, _)| l_enty);
+						if let Some(locker) = nearest_locker {
+							w_job.stage = JobStage::Stow(locker);
+						} else {
+							queue.complete(task.job);
+							w_job.finish();
+						}
+					}
+				}
+			}
+			JobStage::Work(turns_left) => {
+				w_job.stage = JobStage::Work(turns_left - 1);
+			}
+		}
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: TaskQueue
+/// Contains the list of maintenance jobs that need doing around the ship; any entity with a Maintainer may claim them
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct TaskQueue {
+	pub jobs: Vec<Task>,
+}
+impl TaskQueue {
+	pub fn new() -> TaskQueue {
+		TaskQueue::default()
+	}
+	/// Adds a new job to the queue, unless that job is already listed
+	pub fn post(&mut self, job: MaintenanceJob, site: Position) {
+		if self.jobs.iter().any(|x| x.job == job) { return; }
+		self.jobs.push(Task::new(job, site));
+	}
+	/// Keeps only the jobs for which the predicate returns true
+	pub fn retain<F: FnMut(&Task) -> bool>(&mut self, predicate: F) {
+		self.jobs.retain(predicate);
+	}
+	/// Assigns the nearest unclaimed job on the same deck to the given worker, and returns a copy of it
+	pub fn claim_nearest(&mut self, worker: Entity, posn: Position) -> Option<Task> {
+		let mut nearest: Option<(usize, f32)> = None;
+		for (index, task) in self.jobs.iter().enumerate() {
+			if task.assignee.is_some() || task.site.z != posn.z { continue; }
+			let distance = DistanceAlg::Pythagoras.distance2d(posn_to_point(&posn), posn_to_point(&task.site));
+			if nearest.map_or(true, |x| distance < x.1) {
+				nearest = Some((index, distance));
+			}
+		}
+		let (index, _) = nearest?;
+		self.jobs[index].assignee = Some(worker);
+		Some(self.jobs[index])
+	}
+	/// Removes the given job from the queue, ie when it's been finished
+	pub fn complete(&mut self, job: MaintenanceJob) {
+		self.jobs.retain(|x| x.job != job);
+	}
+	/// Puts the given job back up for grabs
+	pub fn release(&mut self, job: MaintenanceJob) {
+		if let Some(task) = self.jobs.iter_mut().find(|x| x.job == job) {
+			task.assignee = None;
+		}
+	}
+}
+impl MapEntities for TaskQueue {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		for task in self.jobs.iter_mut() {
+			task.map_entities(entity_mapper);
+		}
+	}
+}
+//   #: Task
+/// A single entry in the TaskQueue: what needs doing, where, and who's doing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub struct Task {
+	pub job: MaintenanceJob,
+	pub site: Position,
+	pub assignee: Option<Entity>,
+}
+impl Task {
+	pub fn new(new_job: MaintenanceJob, new_site: Position) -> Task {
+		Task {
+			job: new_job,
+			site: new_site,
+			assignee: None,
+		}
+	}
+	/// Brings the job's target and the assignee up to date after their IDs have changed, ie after a load
+	pub fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		self.job = match self.job {
+			MaintenanceJob::Repair(enty) => { MaintenanceJob::Repair(map_entity(entity_mapper, enty)) }
+			MaintenanceJob::CloseDoor(enty) => { MaintenanceJob::CloseDoor(map_entity(entity_mapper, enty)) }
+			MaintenanceJob::Tidy(enty) => { MaintenanceJob::Tidy(map_entity(entity_mapper, enty)) }
+		};
+		self.assignee = self.assignee.map(|x| map_entity(entity_mapper, x));
+	}
+}
+//  ##: Maintainer
+/// Allows an entity to claim jobs from the TaskQueue and carry them out
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Maintainer {
+	pub task: Option<Task>,
+	pub stage: JobStage,
+	pub retry: Option<Direction>, // A move that was held up by something temporary, and is being given one more try
+	pub avoid: Vec<Position>, // Tiles where the current job's moves were blocked, which any new route has to go around
+}
+impl Maintainer {
+	/// Drops the current task once it's over with, along with the blocked tiles found on the way to it
+	/// A job that's only been given back keeps them, so that the worker doesn't try the same blocked route again
+	pub fn finish(&mut self) {
+		self.task = None;
+		self.avoid.clear();
+	}
+}
+impl MapEntities for Maintainer {
+	fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+		if let Some(task) = self.task.as_mut() {
+			task.map_entities(entity_mapper);
+		}
+		self.stage = match self.stage {
+			JobStage::Stow(enty) => { JobStage::Stow(map_entity(entity_mapper, enty)) }
+			JobStage::Stowing(enty) => { JobStage::Stowing(map_entity(entity_mapper, enty)) }
+			other => { other }
+		};
+	}
+}
+
+// ###: SIMPLE TYPES AND HELPERS
+//  ##: MaintenanceJob
+/// Describes the kinds of work that a maintenance robot knows how to do, and the entity to do it to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum MaintenanceJob {
+	Repair(Entity),    // A Device in an Error state
+	CloseDoor(Entity), // A door left open next to vacuum
+	Tidy(Entity),      // A loose item lying around in a hallway
+}
+impl MaintenanceJob {
+	/// Returns the entity that the job is about
+	pub fn target(&self) -> Entity {
+		match self {
+			MaintenanceJob::Repair(enty)
+			| MaintenanceJob::CloseDoor(enty)
+			| MaintenanceJob::Tidy(enty) => { *enty }
+		}
+	}
+}
+//  ##: JobStage
+/// Tracks a worker's progress through its current job
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum JobStage {
+	#[default]
+	Travel,        // Heading to the job site
+	Work(u32),     // At the job site, with this many turns of work left
+	Stow(Entity),  // Carrying an item to the given container
+	Stowing(Entity), // Waiting to see whether the container took the item
+}
+/// Calculates the direction of the first step on the shortest path from origin to somewhere next to the goal
+pub fn next_step(model: &WorldModel, origin: Position, goal: Position, caps: NavCaps) -> Option<Direction> {
+	next_step_around(model, origin, goal, caps, &[])
+}
+/// As for next_step, but the path will go around the given Positions, ie where the actor has already found its way blocked
+pub fn next_step_around(model: &WorldModel, origin: Position, goal: Position, caps: NavCaps, avoid: &[Position]) -> Option<Direction> {
+	if origin.z != goal.z { return None; } // NOTE: pathing between decks isn't supported yet
+	let map = &model.levels[origin.z as usize];
+	// The goal itself is often blocked (ie a closed door or a machine), so aim for an open tile beside it instead
+	let mut approach: Option<(Position, i32)> = None;
+	for x_diff in -1..=1 {
+		for y_diff in -1..=1 {
+			let candidate = goal + (x_diff, y_diff, 0);
+			if candidate.x < 0 || candidate.x >= map.width as i32 || candidate.y < 0 || candidate.y >= map.height as i32 {
+				continue;
+			}
+			if candidate != origin && (map.blocked_tiles[map.to_index(candidate.x, candidate.y)] || avoid.contains(&candidate)) { continue; }
+			let diff = candidate.difference(&origin);
+			let distance = diff.0.abs().max(diff.1.abs());
+			if approach.map_or(true, |x| distance < x.1) {
+				approach = Some((candidate, distance));
+			}
+		}
+	}
+	let (target, _) = approach?;
+	let next = *path_around(model, origin, target, caps, avoid)?.first()?;
+	Some(Direction::from_offset(next.x - origin.x, next.y - origin.y))
+}
+/// Calculates the direction of the first step on the shortest path from origin to the target itself
+/// The path will steer clear of any hazards that the given NavCaps can't handle
+pub fn step_toward(model: &WorldModel, origin: Position, target: Position, caps: NavCaps) -> Option<Direction> {
+	let next = *path_to(model, origin, target, caps)?.first()?;
+	Some(Direction::from_offset(next.x - origin.x, next.y - origin.y))
+}
+/// Calculates the shortest path from origin to the target, under the same rules as step_toward
+/// Returns every Position along the way in order, not including the origin but including the target
+pub fn path_to(model: &WorldModel, origin: Position, target: Position, caps: NavCaps) -> Option<Vec<Position>> {
+	path_around(model, origin, target, caps, &[])
+}
+/// As for path_to, but the path will not pass through any of the given Positions
+pub fn path_around(model: &WorldModel, origin: Position, target: Position, caps: NavCaps, avoid: &[Position]) -> Option<Vec<Position>> {
+	if origin.z != target.z || origin == target { return None; }
+	let map = &model.levels[origin.z as usize];
+	let path = a_star_search(map.to_index(origin.x, origin.y), map.to_index(target.x, target.y), &NavMap::new(map, caps).avoiding(avoid));
+	if !path.success || path.steps.len() < 2 { return None; }
+	Some(path.steps[1..].iter().map(|x| Position::new((x % map.width) as i32, (x / map.width) as i32, origin.z)).collect())
+}
+/// Returns true if any of the tiles surrounding the given Position are open to space
+pub fn is_next_to_vacuum(model: &WorldModel, target: Position) -> bool {
+	let map = &model.levels[target.z as usize];
+	for x_diff in -1..=1 {
+		for y_diff in -1..=1 {
+			let (x, y) = (target.x + x_diff, target.y + y_diff);
+			if x < 0 || x >= map.width as i32 || y < 0 || y >= map.height as i32 { continue; }
+			if map.tiles[map.to_index(x, y)].ttype == TileType::Vacuum {
+				return true;
+			}
+		}
+	}
+	false
+}
+/// Adds an entry to the target's History, creating the History if the target doesn't have one yet
+fn record_history(commands: &mut Commands, target: Entity, history: Option<Mut<History>>, entry: String) {
+	if let Some(mut record) = history {
+		record.add(&entry);
+	} else {
+		let mut record = History::new();
+		record.add(&entry);
+		commands.entity(target).insert(record);
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use bevy::ecs::event::ManualEventReader;
+	use crate::camera::ScreenCell;
+	use crate::engine::RunState;
+	use crate::engine::messagelog::{MessageLog, PerceptionPolicy};
+	use crate::sys::{item_collection_system, openable_system};
+	use crate::testmap::TestMap;
+	use super::*;
+	const HALLWAY: &str = "\
+#######
+#.....#
+#.....#
+#######";
+	/// Sets up a hallway with a robot in it, a loose item next to the robot, and a locker just below the robot
+	fn tidy_setup(locker: Container, is_open: bool) -> (App, Entity, Entity) {
+		let testmap = TestMap::new(HALLWAY).with_room("main hallway", Position::new(1, 1, 0), Position::new(5, 2, 0));
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ActionFailed>()
+		.add_event::<ShipLogEvent>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.add_event::<ContainerOpened>()
+		.insert_resource(testmap.model)
+		.insert_resource(TaskQueue::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(RunState::new())
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (task_generation_system, maintenance_ai_system, openable_system, item_collection_system).chain());
+		app.world.spawn((
+			Description::new().name("LMR"),
+			Body::small(Position::new(2, 1, 0), ScreenCell::default()),
+			Container::default(),
+			Mobile::default(),
+			Maintainer::default(),
+		));
+		let item = app.world.spawn((
+			Description::new().name("wrench"),
+			Body::small(Position::new(3, 1, 0), ScreenCell::default()),
+			Portable::empty(),
+		)).id();
+		let locker = app.world.spawn((
+			Description::new().name("locker"),
+			Body::small(Position::new(2, 2, 0), ScreenCell::default()),
+			locker,
+			Openable::new(is_open, "▯", "▮"),
+		)).id();
+		(app, item, locker)
+	}
+	//  ##: Tidying
+	#[test]
+	fn tidied_items_end_up_in_the_locker() {
+		let (mut app, item, locker) = tidy_setup(Container::default(), true);
+		for _ in 0..10 { app.update(); }
+		assert_eq!(app.world.get::<Portable>(item).map(|x| x.carrier), Some(locker));
+		assert!(app.world.get::<History>(item).is_some());
+		assert!(app.world.resource::<TaskQueue>().jobs.is_empty());
+	}
+	#[test]
+	fn a_closed_locker_is_opened_first() {
+		let (mut app, item, locker) = tidy_setup(Container::default(), false);
+		for _ in 0..12 { app.update(); }
+		assert!(app.world.get::<Openable>(locker).map_or(false, |x| x.is_open));
+		assert_eq!(app.world.get::<Portable>(item).map(|x| x.carrier), Some(locker));
+	}
+	#[test]
+	fn a_full_locker_gets_the_item_left_beside_it() {
+		// Five turns to pick the item up, one to hand it over, and one to see that the locker wouldn't take it
+		let (mut app, item, locker) = tidy_setup(Container { max_slots: Some(0) }, true);
+		for _ in 0..7 { app.update(); }
+		assert_eq!(app.world.get::<Portable>(item).map(|x| x.carrier), Some(Entity::PLACEHOLDER));
+		assert!(app.world.get::<IsCarried>(item).is_none());
+		assert!(app.world.resource::<TaskQueue>().jobs.is_empty());
+	}
+	#[test]
+	fn disabled_robots_do_not_take_jobs() {
+		let (mut app, item, _) = tidy_setup(Container::default(), true);
+		let robot = app.world.query_filtered::<Entity, With<Maintainer>>().single(&app.world);
+		app.world.entity_mut(robot).insert(Disabled::default());
+		for _ in 0..10 { app.update(); }
+		assert_eq!(app.world.get::<Portable>(item).map(|x| x.carrier), Some(Entity::PLACEHOLDER));
+		let queue = app.world.resource::<TaskQueue>();
+		assert_eq!(queue.jobs.len(), 1);
+		assert_eq!(queue.jobs[0].assignee, None);
+	}
+	//  ##: Repairs
+	#[test]
+	fn broken_devices_get_repaired() {
+		let (mut app, _, _) = tidy_setup(Container::default(), true);
+		let mut device = Device::new(0);
+		device.state = DeviceState::Error(1);
+		let broken = app.world.spawn((
+			Description::new().name("pump"),
+			Body::small(Position::new(1, 2, 0), ScreenCell::default()),
+			device,
+		)).id();
+		for _ in 0..20 { app.update(); }
+		assert!(!matches!(app.world.get::<Device>(broken).map(|x| x.state), Some(DeviceState::Error(_))));
+	}
+	//  ##: Blocked moves
+	/// Sets up a robot at the west end of the layout with a broken pump to go and fix; there's no movement_system, so
+	/// each test decides for itself how the robot's moves turn out
+	fn travel_setup(layout: &str, pump_posn: Position) -> (App, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ActionFailed>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(TestMap::new(layout).model)
+		.insert_resource(TaskQueue::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (task_generation_system, maintenance_ai_system).chain());
+		let robot = app.world.spawn((
+			Description::new().name("LMR"),
+			Body::small(Position::new(1, 1, 0), ScreenCell::default()),
+			Maintainer::default(),
+		)).id();
+		let mut device = Device::new(0);
+		device.state = DeviceState::Error(1);
+		app.world.spawn((Description::new().name("pump"), Body::small(pump_posn, ScreenCell::default()), device));
+		(app, robot)
+	}
+	/// Runs one update, and returns the moves that were asked for during it
+	fn next_moves(app: &mut App, reader: &mut ManualEventReader<GameEvent>) -> Vec<Direction> {
+		app.update();
+		reader.iter(app.world.resource::<Events<GameEvent>>()).filter_map(|x| match x.etype {
+			ActorAction(ActionType::MoveTo(dir)) => { Some(dir) }
+			_ => { None }
+		}).collect()
+	}
+	/// Tells the robot that its last move didn't go through
+	fn fail_move(app: &mut App, robot: Entity, dir: Direction, transient: bool) {
+		app.world.send_event(ActionFailed { actor: robot, action: ActionType::MoveTo(dir), transient });
+	}
+	#[test]
+	fn a_transient_block_is_retried_as_is() {
+		let (mut app, robot) = travel_setup(HALLWAY, Position::new(5, 1, 0));
+		let mut reader = ManualEventReader::default();
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		// Something that ought to clear up, ie a door that's still opening, gets the same move one more time
+		fail_move(&mut app, robot, Direction::E, true);
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		assert_eq!(app.world.get::<Maintainer>(robot).unwrap().retry, Some(Direction::E));
+		// Once the retry goes through, the robot goes back to planning its own route
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		assert_eq!(app.world.get::<Maintainer>(robot).unwrap().retry, None);
+		assert!(app.world.get::<Maintainer>(robot).unwrap().avoid.is_empty());
+	}
+	#[test]
+	fn a_genuine_block_is_routed_around() {
+		let (mut app, robot) = travel_setup(HALLWAY, Position::new(5, 1, 0));
+		let mut reader = ManualEventReader::default();
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		fail_move(&mut app, robot, Direction::E, false);
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::SE]);
+		assert_eq!(app.world.get::<Maintainer>(robot).unwrap().avoid, vec![Position::new(2, 1, 0)]);
+	}
+	#[test]
+	fn a_transient_block_that_fails_again_is_routed_around() {
+		let (mut app, robot) = travel_setup(HALLWAY, Position::new(5, 1, 0));
+		let mut reader = ManualEventReader::default();
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		fail_move(&mut app, robot, Direction::E, true);
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		fail_move(&mut app, robot, Direction::E, true);
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::SE]);
+	}
+	#[test]
+	fn a_block_with_no_way_around_gives_the_job_back() {
+		let (mut app, robot) = travel_setup("#####\n#...#\n#####", Position::new(3, 1, 0));
+		let mut reader = ManualEventReader::default();
+		assert_eq!(next_moves(&mut app, &mut reader), vec![Direction::E]);
+		fail_move(&mut app, robot, Direction::E, false);
+		// The only way there is the blocked tile, so the robot stops trying instead of walking into it over and over
+		for _ in 0..5 {
+			assert!(next_moves(&mut app, &mut reader).is_empty());
+		}
+		assert_eq!(app.world.get::<Maintainer>(robot).unwrap().task, None);
+		assert_eq!(app.world.resource::<TaskQueue>().jobs[0].assignee, None);
+	}
+	//  ##: Hazards
+	/// A hallway with a hull breach across the top row, and a longer way around through the rooms below it
+	const BREACHED_HALLWAY: &str = "\
+#######
+#.   .#
+#.###.#
+#.....#
+#######";
+	#[test]
+	fn a_breather_takes_the_long_way_around_the_breach() {
+		let model = TestMap::new(BREACHED_HALLWAY).model;
+		let (start, goal) = (Position::new(1, 1, 0), Position::new(5, 1, 0));
+		let route = path_to(&model, start, goal, NavCaps::default()).expect("there should be a safe way around");
+		assert!(route.iter().all(|x| model.get_tiletype_at(*x) != TileType::Vacuum), "{:?}", route);
+		// The straight run across the breach would only have been four steps
+		assert!(route.len() > 4, "{:?}", route);
+		assert_eq!(route.last(), Some(&goal));
+		// With the way around bricked up, there's no route at all rather than a deadly one
+		let mut sealed = model.clone();
+		let index = sealed.levels[0].to_index(1, 2);
+		sealed.levels[0].tiles[index] = Tile::new(TileType::Wall);
+		sealed.levels[0].update_tilemaps();
+		assert_eq!(path_to(&sealed, start, goal, NavCaps::default()), None);
+	}
+	#[test]
+	fn the_lmr_goes_straight_across_the_breach() {
+		let model = TestMap::new(BREACHED_HALLWAY).model;
+		let route = path_to(&model, Position::new(1, 1, 0), Position::new(5, 1, 0), NavCaps { breathes: false });
+		assert_eq!(route, Some((2..=5).map(|x| Position::new(x, 1, 0)).collect()));
+	}
+	#[test]
+	fn a_robot_on_a_job_heads_out_over_the_breach() {
+		// Anything without NavCaps is assumed to need air
+		let (mut app, _) = travel_setup(BREACHED_HALLWAY, Position::new(5, 1, 0));
+		assert_eq!(next_moves(&mut app, &mut ManualEventReader::default()), vec![Direction::S]);
+		let (mut app, robot) = travel_setup(BREACHED_HALLWAY, Position::new(5, 1, 0));
+		app.world.entity_mut(robot).insert(NavCaps { breathes: false });
+		assert_eq!(next_moves(&mut app, &mut ManualEventReader::default()), vec![Direction::E]);
+	}
+}
+
+// EOF
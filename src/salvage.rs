@@ -0,0 +1,232 @@
+// salvage.rs
+// Provides the salvage bin, where the player can turn whatever they've scavenged from the ship into a score
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::ecs::event::ManualEventReader;
+
+// ###: INTERNAL LIBS
+use crate::artisan::RawItem;
+use crate::components::*;
+use crate::engine::RunState;
+use crate::engine::event::*;
+use crate::engine::event::ActionType::DropItem;
+use crate::engine::event::GameEventType::{ActorAction, PlayerAction};
+use crate::engine::messagelog::MessageLog;
+use crate::planq::Planq;
+use crate::worldmap::WorldModel;
+
+// ###: CONSTANTS
+/// The name of the salvage bin as it's known to the ItemBuilder
+pub const SALVAGE_BIN_ITEM: &str = "salvage bin";
+/// The salvage bin goes in the first room whose name has one of these in it, in order of preference
+pub const SALVAGE_BIN_ROOMS: [&str; 2] = ["shuttle", "airlock"];
+/// The amounts of salvage that an escape needs to reach to be ranked any higher, with the rank that each one earns
+pub const SALVAGE_RANKS: [(u64, &str); 3] = [(0, "EMPTY-HANDED"), (50, "WITH SALVAGE"), (200, "WITH A HAUL")];
+/// What anything that can be carried off is worth as scrap, if its template doesn't say otherwise
+pub const SALVAGE_SCRAP: u32 = 5;
+/// What a working Device adds on top of the scrap value
+pub const SALVAGE_DEVICE: u32 = 20;
+/// What a LightSource adds on top of the scrap value
+pub const SALVAGE_LIGHT: u32 = 10;
+
+// ###: BEVY SYSTEMS
+/// Vets anything being deposited into a salvage bin, and credits the score once the items have actually landed in it
+/// A DropItem that names a destination is a deposit, which the item_collection_system leaves for this system; once it
+/// passes muster here it gets passed back as an ItemGive, so that the bin's slots and lid are respected like any other
+pub fn salvage_system(mut cmd:       Commands,
+	                    mut events:    ResMut<Events<GameEvent>>,
+	                    mut ereader:   Local<ManualEventReader<GameEvent>>,
+	                    mut ireader:   EventReader<InventoryChanged>,
+	                    mut msglog:    ResMut<MessageLog>,
+	                    mut run_state: ResMut<RunState>,
+	                    p_posn:        Res<Position>,
+	                    a_query:       Query<(&Body, Option<&Player>)>,
+	                    b_query:       Query<(&Description, &Body), (With<SalvageBin>, With<Container>)>,
+	                    i_query:       Query<(Entity, &Description, &Portable, Option<&Salvage>, Option<&Planq>)>,
+) {
+	// Pass along any deposits that are allowed
+	let deposits: Vec<GameEvent> = ereader.iter(&events).copied().collect();
+	for event in deposits.iter() {
+		if !matches!(event.etype, PlayerAction(DropItem) | ActorAction(DropItem)) { continue; }
+		let econtext = if let Some(value) = event.context { value } else { continue };
+		let bin = if let Some(value) = econtext.destination { value } else { continue };
+		let (s_body, s_player) = if let Ok(value) = a_query.get(econtext.subject) { value } else { continue };
+		let is_player_action = s_player.is_some();
+		let (b_desc, b_body) = if let Ok(value) = b_query.get(bin) { value } else {
+			if is_player_action { msglog.tell_player("You can't put things in there."); }
+			continue;
+		};
+		if !b_body.in_range_of(&s_body.ref_posn, DEFAULT_REACH) {
+			if is_player_action { msglog.tell_player(format!("The {} is out of reach.", b_desc.name).as_str()); }
+			continue;
+		}
+		let (_, i_desc, _, i_salvage, i_planq) = if let Ok(value) = i_query.get(econtext.object) { value } else { continue };
+		if i_planq.is_some() {
+			if is_player_action {
+				msglog.tell_player("The PLANQ's screen flashes: [[fg:red]]RECYCLING REQUEST DENIED.[[end]] It's not sure what it did to deserve that.");
+			}
+			continue;
+		}
+		if is_player_action && i_salvage.map_or(true, |x| x.value == 0) {
+			msglog.tell_player(format!("The {} won't give you anything for the {}.", b_desc.name, i_desc.name).as_str());
+		}
+		events.send(GameEvent::give(econtext.subject, econtext.object, bin));
+	}
+	// Credit whatever has made it into a bin since last time
+	let bins: Vec<Entity> = ireader.iter().map(|x| x.carrier).filter(|x| b_query.contains(*x)).collect();
+	for bin in bins.iter() {
+		let (b_desc, b_body) = if let Ok(value) = b_query.get(*bin) { value } else { continue };
+		for (i_enty, i_desc, i_portable, i_salvage, _) in i_query.iter() {
+			if i_portable.carrier != *bin { continue; }
+			let value = if let Some(value) = i_salvage { value.value } else { continue };
+			// The bin keeps whatever goes into it, but each item only counts toward the score the first time around
+			cmd.entity(i_enty).remove::<Salvage>();
+			run_state.salvage += value as u64;
+			if b_body.in_range_of(&p_posn, DEFAULT_REACH) {
+				msglog.tell_player(format!("The {} chimes as it takes the {}: +{} salvage, {} in all.", b_desc.name, i_desc.name, value, run_state.salvage).as_str());
+			}
+		}
+	}
+}
+
+// ###: METHODS
+/// Picks out the room that the salvage bin should be put in, if the ship has anywhere suitable
+pub fn salvage_bin_room(model: &WorldModel) -> Option<String> {
+	SALVAGE_BIN_ROOMS.iter().find_map(|tag| {
+		model.layout.rooms.iter().find(|x| x.name.to_lowercase().contains(tag)).map(|x| x.name.clone())
+	})
+}
+/// Puts a value on an item whose template doesn't give it one, going by what it's made of: anything that can be carried
+/// off is worth something as scrap, and the working parts are worth more; fixtures aren't worth anything
+pub fn appraise(is_portable: bool, has_device: bool, has_light: bool) -> u32 {
+	if !is_portable { return 0; }
+	let mut value = SALVAGE_SCRAP;
+	if has_device { value += SALVAGE_DEVICE; }
+	if has_light { value += SALVAGE_LIGHT; }
+	value
+}
+/// Provides the salvage bin's definition, for when the furniture file doesn't have one of its own
+pub fn salvage_bin_template() -> RawItem {
+	RawItem {
+		name: SALVAGE_BIN_ITEM.to_string(),
+		desc: "A squat hopper with a hungry-looking intake slot; anything dropped in is weighed, scanned, and credited to your salvage claim.".to_string(),
+		body: vec!["0,0 ▣ yellow black".to_string()],
+		shapes: vec![vec!["A".to_string()]],
+		extra: vec!["actionset".to_string(), "container".to_string(), "obstructs".to_string(), "salvagebin".to_string()],
+		constraints: None,
+	}
+}
+/// Returns the rank that an escape earns for the given amount of salvage
+pub fn salvage_rank(salvage: u64) -> &'static str {
+	SALVAGE_RANKS.iter().rev().find(|x| salvage >= x.0).map_or("", |x| x.1)
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::artisan::ItemBuilder;
+	use crate::camera::ScreenCell;
+	use crate::engine::legacy::RunSummary;
+	use crate::engine::messagelog::PerceptionPolicy;
+	use crate::sys::item_collection_system;
+	use super::*;
+	/// Sets up a headless App with the player standing next to a salvage bin with the given Container, and carrying a
+	/// single item made out of the given components
+	fn bin_setup(bin: Container, components: impl Bundle) -> (App, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.insert_resource(WorldModel::default())
+		.insert_resource(RunState::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(Position::new(1, 1, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (item_collection_system, salvage_system.after(item_collection_system)));
+		let player = app.world.spawn((
+			Player { },
+			Description::new().name("Pleyeur"),
+			Body::small(Position::new(1, 1, 0), ScreenCell::new()),
+			Container::default(),
+		)).id();
+		let bin = app.world.spawn((
+			Description::new().name(SALVAGE_BIN_ITEM),
+			Body::small(Position::new(2, 1, 0), ScreenCell::new()),
+			bin,
+			SalvageBin::default(),
+		)).id();
+		let item = app.world.spawn((Body::small(Position::new(1, 1, 0), ScreenCell::new()), Portable::new(player), IsCarried::default(), components)).id();
+		(app, player, bin, item)
+	}
+	/// Has the player deposit the item into the bin, and gives the systems long enough to deal with it
+	fn deposit(app: &mut App, player: Entity, bin: Entity, item: Entity) {
+		app.world.send_event(GameEvent::new(PlayerAction(DropItem), Some(player), Some(item)).into_container(bin));
+		for _ in 0..3 { app.update(); }
+	}
+	//  ##: Deposits
+	#[test]
+	fn deposits_add_to_the_score() {
+		let (mut app, player, bin, item) = bin_setup(Container::default(), (Description::new().name("circuit board"), Salvage { value: 30 }));
+		deposit(&mut app, player, bin, item);
+		assert_eq!(app.world.get::<Portable>(item).unwrap().carrier, bin);
+		assert_eq!(app.world.resource::<RunState>().salvage, 30);
+		assert!(app.world.get::<Salvage>(item).is_none());
+	}
+	#[test]
+	fn items_only_count_the_first_time() {
+		let (mut app, player, bin, item) = bin_setup(Container::default(), (Description::new().name("circuit board"), Salvage { value: 30 }));
+		deposit(&mut app, player, bin, item);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::MoveItem), Some(player), Some(item)));
+		app.update();
+		assert_eq!(app.world.get::<Portable>(item).unwrap().carrier, player);
+		deposit(&mut app, player, bin, item);
+		assert_eq!(app.world.get::<Portable>(item).unwrap().carrier, bin);
+		assert_eq!(app.world.resource::<RunState>().salvage, 30);
+	}
+	#[test]
+	fn the_planq_refuses_to_be_deposited() {
+		let (mut app, player, bin, planq) = bin_setup(Container::default(), (Description::new().name("PLANQ"), Planq::new(), Salvage { value: 25 }));
+		deposit(&mut app, player, bin, planq);
+		assert_eq!(app.world.get::<Portable>(planq).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<RunState>().salvage, 0);
+		let log = app.world.resource::<MessageLog>().get_log_as_messages("world", 0);
+		assert!(log.iter().any(|x| x.text.contains("RECYCLING REQUEST DENIED")));
+	}
+	#[test]
+	fn a_full_bin_takes_nothing() {
+		let (mut app, player, bin, item) = bin_setup(Container::with_slots(0), (Description::new().name("circuit board"), Salvage { value: 30 }));
+		deposit(&mut app, player, bin, item);
+		assert_eq!(app.world.get::<Portable>(item).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<RunState>().salvage, 0);
+	}
+	//  ##: Values and ranks
+	#[test]
+	fn working_parts_are_worth_more_than_scrap() {
+		assert_eq!(appraise(false, true, true), 0);
+		assert_eq!(appraise(true, false, false), SALVAGE_SCRAP);
+		assert_eq!(appraise(true, true, false), SALVAGE_SCRAP + SALVAGE_DEVICE);
+		assert_eq!(appraise(true, true, true), SALVAGE_SCRAP + SALVAGE_DEVICE + SALVAGE_LIGHT);
+	}
+	#[test]
+	fn the_bin_has_a_builtin_template() {
+		let mut world = World::new();
+		let mut artisan = ItemBuilder::new();
+		let bin = artisan.create(SALVAGE_BIN_ITEM).at(Position::new(3, 3, 0)).build(&mut world)[0].0.id();
+		assert!(world.get::<SalvageBin>(bin).is_some());
+		assert!(world.get::<Container>(bin).is_some());
+		assert!(world.get::<Salvage>(bin).is_none());
+		assert_eq!(world.get::<Body>(bin).unwrap().ref_posn, Position::new(3, 3, 0));
+	}
+	#[test]
+	fn escapes_are_ranked_on_the_end_screen() {
+		let summary = |escaped, salvage| RunSummary { escaped, salvage, cause: "vacuum".to_string(), ..RunSummary::default() };
+		assert_eq!(summary(true, 10).report()[0], "ESCAPED EMPTY-HANDED");
+		assert_eq!(summary(true, 60).report()[0], "ESCAPED WITH SALVAGE");
+		assert_eq!(summary(true, 250).report()[0], "ESCAPED WITH A HAUL");
+		assert_eq!(summary(false, 250).report()[0], "Cause of death: vacuum");
+	}
+}
+
+// EOF
@@ -0,0 +1,108 @@
+// savediff.rs
+// Provides a comparison between two saved games, for tracking down the things that don't survive a save and load
+
+// ###: EXTERNAL LIBS
+use std::collections::BTreeMap;
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::worldmap::WorldModel;
+
+// ###: METHODS
+/// Takes down where everything in the given World is, who's holding what, and which tiles have been revealed
+/// Entity IDs are not kept the same across a save and a load, so everything is recorded by name instead, and by its
+/// SpawnIndex if it has one, so that several of the same thing still get paired up correctly
+pub fn take_snapshot(world: &mut World) -> SaveSnapshot {
+	let mut snapshot = SaveSnapshot::default();
+	let mut t_query = world.query::<(&Description, &Body, Option<&Portable>, Option<&SpawnIndex>)>();
+	let found: Vec<(String, Position, Entity, Option<u64>)> = t_query.iter(world)
+		.map(|(t_desc, t_body, t_portable, t_index)| {
+			(t_desc.name.clone(), t_body.ref_posn, t_portable.map_or(Entity::PLACEHOLDER, |x| x.carrier), t_index.map(|x| x.index))
+		})
+		.collect();
+	for (name, posn, carrier, index) in found {
+		let carrier = if carrier == Entity::PLACEHOLDER {
+			None
+		} else {
+			Some(world.get::<Description>(carrier).map_or(format!("{:?}", carrier), |x| x.name.clone()))
+		};
+		snapshot.things.entry(name).or_default().push(SnapshotEntry { index, posn, carrier });
+	}
+	for entries in snapshot.things.values_mut() {
+		entries.sort();
+	}
+	if let Some(model) = world.get_resource::<WorldModel>() {
+		for (deck, level) in model.levels.iter().enumerate() {
+			for (index, _) in level.revealed_tiles.iter().enumerate().filter(|x| *x.1) {
+				let (x, y) = ((index % level.width) as i32, (index / level.width) as i32);
+				snapshot.revealed.push(Position::new(x, y, deck as i32));
+			}
+		}
+	}
+	snapshot
+}
+/// Lists the differences between two snapshots, as lines of text for display; reports "No differences." if they match
+pub fn diff_snapshots(before: &SaveSnapshot, after: &SaveSnapshot) -> Vec<String> {
+	let mut lines = Vec::new();
+	let no_entries = Vec::new();
+	let mut names: Vec<&String> = before.things.keys().chain(after.things.keys()).collect();
+	names.sort();
+	names.dedup();
+	for name in names {
+		let old_list = before.things.get(name).unwrap_or(&no_entries);
+		let new_list = after.things.get(name).unwrap_or(&no_entries);
+		// Anything that hasn't changed at all is left out, and what's left over is paired up in order
+		let old_rest: Vec<&SnapshotEntry> = old_list.iter().filter(|x| !new_list.contains(x)).collect();
+		let new_rest: Vec<&SnapshotEntry> = new_list.iter().filter(|x| !old_list.contains(x)).collect();
+		for (old, new) in old_rest.iter().zip(new_rest.iter()) {
+			if old.carrier != new.carrier {
+				lines.push(format!("{}: {} -> {}", name, old.holder(), new.holder()));
+			} else {
+				lines.push(format!("{}: {} -> {}", name, old.posn, new.posn));
+			}
+		}
+		for old in old_rest.iter().skip(new_rest.len()) {
+			lines.push(format!("{}: gone from {}", name, old.holder()));
+		}
+		for new in new_rest.iter().skip(old_rest.len()) {
+			lines.push(format!("{}: new at {}", name, new.holder()));
+		}
+	}
+	let gained = after.revealed.iter().filter(|x| !before.revealed.contains(x)).count();
+	let lost = before.revealed.iter().filter(|x| !after.revealed.contains(x)).count();
+	if gained > 0 { lines.push(format!("Revealed tiles: {} more", gained)); }
+	if lost > 0 { lines.push(format!("Revealed tiles: {} fewer", lost)); }
+	if lines.is_empty() {
+		lines.push("No differences.".to_string());
+	}
+	lines
+}
+
+// ###: COMPLEX TYPES
+//  ##: SaveSnapshot
+/// Everything that the save comparison looks at, as of when take_snapshot() was called
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveSnapshot {
+	pub things: BTreeMap<String, Vec<SnapshotEntry>>, // Keyed by name, since there may be several of the same thing
+	pub revealed: Vec<Position>,
+}
+//  ##: SnapshotEntry
+/// A single named thing in a snapshot: where it is, and who's holding it, if anyone
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SnapshotEntry {
+	pub index: Option<u64>, // The thing's SpawnIndex, which comes first so that the entries sort by it
+	pub posn: Position,
+	pub carrier: Option<String>,
+}
+impl SnapshotEntry {
+	/// Describes where the thing is, ie "4, 14, 1" or "held by the player"
+	pub fn holder(&self) -> String {
+		match self.carrier {
+			Some(ref carrier) => { format!("held by {}", carrier) }
+			None => { format!("{}", self.posn) }
+		}
+	}
+}
+
+// EOF
@@ -0,0 +1,37 @@
+// scenarios/escape.rs
+// Provides the default mission, where the player has to get their PLANQ back and then get off the ship
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::scenarios::*;
+use crate::worldmap::WorldModel;
+
+// ###: CONSTANTS
+/// Any room with this in its name counts as a way off the ship
+pub const EXIT_ROOM_TAG: &str = "airlock";
+
+// ###: COMPLEX TYPES
+//  ##: EscapeScenario
+/// Find the PLANQ, which the intro locks away near the spawnpoint, and carry it out through one of the airlocks
+#[derive(Clone, Debug, Default)]
+pub struct EscapeScenario { }
+impl Scenario for EscapeScenario {
+	fn name(&self) -> &'static str {
+		"Escape"
+	}
+	/// Nothing extra is needed: the intro that every new game gets already puts the PLANQ in its locker
+	fn setup(&self, _world: &mut World) { }
+	fn victory(&self, world: &mut World) -> Option<Outcome> {
+		let (player, p_posn) = find_player(world)?;
+		let mut q_query = world.query_filtered::<&Portable, With<Planq>>();
+		if !q_query.iter(world).any(|x| x.carrier == player) { return None; }
+		let room = world.get_resource::<WorldModel>()?.layout.get_room_name(p_posn)?;
+		if !room.to_lowercase().contains(EXIT_ROOM_TAG) { return None; }
+		Some(Outcome::win("escaped the ship with the PLANQ"))
+	}
+}
+
+// EOF
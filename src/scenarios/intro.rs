@@ -0,0 +1,60 @@
+// scenarios/intro.rs
+// Provides the opening scenario, where the player has to get their PLANQ back out of a locked locker
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::camera::ScreenCell;
+use crate::components::*;
+use crate::components::Color;
+use crate::sys::new_planq_spawn;
+use crate::worldmap::*;
+
+// ###: CONSTANTS
+/// The key_id that matches the intro locker and its key; chosen to stay clear of any key_ids in the item definitions
+pub const INTRO_KEY_ID: i32 = 1701;
+
+// ###: METHODS
+/// Sets up the intro around the player's spawnpoint: a locked locker with the PLANQ inside it, and its key nearby
+/// Returns the PLANQ's Entity, or None if there wasn't enough open floor around the spawnpoint to do so
+pub fn setup(commands: &mut Commands, model: &mut WorldModel, rng: &mut GlobalRng, spawnpoint: Position) -> Option<Entity> {
+	let locker_posn = find_open_spot_near(model, spawnpoint, &[])?;
+	let key_posn = find_open_spot_near(model, spawnpoint, &[locker_posn])?;
+	let locker_body = Body::small(locker_posn, ScreenCell::new().glyph("▯").fg(Color::White).bg(Color::Black));
+	let locker = commands.spawn((
+		Description::new().name("locker").desc("A narrow personal locker with a keyed lock."),
+		locker_body,
+		ActionSet::new(),
+		Container::default(),
+		Openable::new(false, "▭", "▯"),
+		Lockable { is_locked: true, key_id: INTRO_KEY_ID, ..Lockable::default() },
+		Obstructive::default(),
+	)).id();
+	model.add_contents(&vec![locker_posn], 0, locker);
+	let key = commands.spawn((
+		Description::new().name("locker key").desc("A small brass key on a numbered tag."),
+		Body::small(key_posn, ScreenCell::new().glyph("⚷").fg(Color::Yellow).bg(Color::Black)),
+		ActionSet::new(),
+		Portable::empty(),
+		Key { key_id: INTRO_KEY_ID },
+	)).id();
+	model.add_contents(&vec![key_posn], 0, key);
+	// The PLANQ stays dormant in the locker: no sidebar until it's been picked up, and no boot until it's powered on
+	let planq = new_planq_spawn(commands, rng, locker, locker_posn);
+	commands.entity(planq).insert(IsCarried::default());
+	Some(planq)
+}
+/// Finds the closest walkable, empty floor tile next to the origin, skipping any of the excluded Positions
+fn find_open_spot_near(model: &WorldModel, origin: Position, exclude: &[Position]) -> Option<Position> {
+	let offsets = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+	offsets.iter()
+		.map(|(x, y)| origin + (*x, *y, 0))
+		.find(|posn| model.contains(*posn)
+			&& !exclude.contains(posn)
+			&& model.get_tiletype_at(*posn) == TileType::Floor
+			&& model.get_contents_at(*posn).is_empty())
+}
+
+// EOF
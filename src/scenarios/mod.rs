@@ -0,0 +1,89 @@
+// scenarios/mod.rs
+// Provides the scripted setups that put the game world into a particular starting state, and the missions that decide
+// when a game has been won
+
+// ###: EXTERNAL LIBS
+use std::fmt;
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::EngineMode;
+use crate::engine::event::GameEvent;
+
+// Provides the default mission: get the PLANQ back and get off the ship
+pub mod escape;
+// Sets up the opening beat: the player wakes up next to the locker that their PLANQ is locked inside of
+pub mod intro;
+// Provides the mission where the ship's power has to be brought back up to get the elevator running again
+pub mod restore_power;
+
+// ###: COMPLEX TYPES
+//  ##: Scenario
+/// Describes a mission that can be played out on top of the usual game world; the GameEngine holds on to the one that
+/// was picked for the current game, and calls into it once every tick
+/// Anything that a Scenario needs to remember should be kept in the World, so that it survives a save and load
+pub trait Scenario {
+	/// The name of the scenario, as shown on the New Game menu
+	fn name(&self) -> &'static str;
+	/// Puts the world into the scenario's starting state; called once, after the player has been spawned
+	fn setup(&self, world: &mut World);
+	/// Checks whether the game has been won or lost; called after every tick, and ends the game when it returns Some
+	fn victory(&self, world: &mut World) -> Option<Outcome>;
+	/// Reacts to one of the GameEvents that were sent during the last tick
+	fn on_event(&mut self, _world: &mut World, _event: &GameEvent) { }
+}
+//  ##: Outcome
+/// How a Scenario ended the game, and why
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+	pub mode: EngineMode, // Either GoodEnd or BadEnd
+	pub cause: String,
+}
+impl Outcome {
+	pub fn win(cause: &str) -> Outcome {
+		Outcome { mode: EngineMode::GoodEnd, cause: cause.to_string() }
+	}
+	pub fn loss(cause: &str) -> Outcome {
+		Outcome { mode: EngineMode::BadEnd, cause: cause.to_string() }
+	}
+}
+//  ##: ScenarioKind
+/// The set of shipped scenarios that can be picked from the New Game menu; saved with the game so that a loaded game
+/// carries on with the same one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum ScenarioKind {
+	#[default]
+	Escape,
+	RestorePower,
+}
+impl ScenarioKind {
+	/// Returns the next scenario in the list, for cycling through them from the menu
+	pub fn next(&self) -> ScenarioKind {
+		match self {
+			ScenarioKind::Escape => ScenarioKind::RestorePower,
+			ScenarioKind::RestorePower => ScenarioKind::Escape,
+		}
+	}
+	/// Creates a fresh instance of the scenario
+	pub fn build(&self) -> Box<dyn Scenario> {
+		match self {
+			ScenarioKind::Escape => Box::new(escape::EscapeScenario::default()),
+			ScenarioKind::RestorePower => Box::new(restore_power::RestorePowerScenario::default()),
+		}
+	}
+}
+impl fmt::Display for ScenarioKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.build().name())
+	}
+}
+
+// ###: UTILITIES
+/// Returns the player's Entity and Position, if there's exactly one player in the world
+pub fn find_player(world: &mut World) -> Option<(Entity, Position)> {
+	let mut p_query = world.query_filtered::<(Entity, &Body), With<Player>>();
+	p_query.get_single(world).ok().map(|(p_enty, p_body)| (p_enty, p_body.ref_posn))
+}
+
+// EOF
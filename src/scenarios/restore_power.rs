@@ -0,0 +1,93 @@
+// scenarios/restore_power.rs
+// Provides the mission where the ship has lost power, and the player has to get it back up to reach the elevator
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::camera::ScreenCell;
+use crate::components::*;
+use crate::components::Color;
+use crate::engine::event::*;
+use crate::engine::messagelog::MessageLog;
+use crate::power::PowerGrid;
+use crate::scenarios::*;
+use crate::worldmap::*;
+
+// ###: CONSTANTS
+/// Any room with this in its name counts as the elevator
+pub const ELEVATOR_ROOM_TAG: &str = "elevator";
+/// The name of the breaker panel that brings the generators back online
+pub const BREAKER_NAME: &str = "breaker panel";
+
+// ###: COMPLEX TYPES
+//  ##: RestorePowerScenario
+/// Every generator starts out offline; the player has to find the breaker panel, throw it, and then ride the elevator
+/// out once it has power again
+#[derive(Clone, Debug, Default)]
+pub struct RestorePowerScenario { }
+impl Scenario for RestorePowerScenario {
+	fn name(&self) -> &'static str {
+		"Restore Power"
+	}
+	/// Takes every generator offline, and puts the breaker panel on the floor near the player
+	fn setup(&self, world: &mut World) {
+		if let Some(mut grid) = world.get_resource_mut::<PowerGrid>() {
+			let names: Vec<String> = grid.generators.iter().map(|x| x.name.clone()).collect();
+			for name in names {
+				grid.set_online(&name, false);
+			}
+		}
+		let (_, p_posn) = if let Some(value) = find_player(world) { value } else { return };
+		let spot = world.get_resource::<WorldModel>().and_then(|model| {
+			[(0, 1), (1, 0), (0, -1), (-1, 0), (1, 1), (-1, -1), (1, -1), (-1, 1)].iter()
+				.map(|(x, y)| p_posn + (*x, *y, 0))
+				.find(|posn| model.contains(*posn)
+					&& model.get_tiletype_at(*posn) == TileType::Floor
+					&& model.get_contents_at(*posn).is_empty())
+		});
+		let posn = if let Some(value) = spot { value } else {
+			warn!("* no room for the breaker panel near {}", p_posn); // DEBUG: report a missing breaker panel
+			return;
+		};
+		let breaker = world.spawn((
+			Description::new().name(BREAKER_NAME).desc("A heavy switch marked MAIN BUS. It's been thrown to OFF."),
+			Body::small(posn, ScreenCell::new().glyph("⌸").fg(Color::Yellow).bg(Color::Black)),
+			ActionSet::new(),
+			Device::new(0),
+			Obstructive::default(),
+		)).id();
+		if let Some(mut model) = world.get_resource_mut::<WorldModel>() {
+			model.add_contents(&vec![posn], 0, breaker);
+		}
+		if let Some(mut msglog) = world.get_resource_mut::<MessageLog>() {
+			msglog.tell_player("The ship is dark and silent. Somebody's thrown the main breaker.");
+		}
+	}
+	fn victory(&self, world: &mut World) -> Option<Outcome> {
+		let (_, p_posn) = find_player(world)?;
+		let grid = world.get_resource::<PowerGrid>()?;
+		if grid.generators.iter().any(|x| !x.is_online) { return None; }
+		let room = world.get_resource::<WorldModel>()?.layout.get_room_name(p_posn)?;
+		if !room.to_lowercase().contains(ELEVATOR_ROOM_TAG) || !grid.is_powered(&room) { return None; }
+		Some(Outcome::win("restored the power and rode the elevator out"))
+	}
+	/// Throwing the breaker brings every generator back online
+	fn on_event(&mut self, world: &mut World, event: &GameEvent) {
+		if !matches!(event.etype, GameEventType::PlayerAction(ActionType::UseItem)) { return; }
+		let object = if let Some(context) = event.context { context.object } else { return };
+		if world.get::<Description>(object).map_or(true, |x| x.name != BREAKER_NAME) { return; }
+		if let Some(mut grid) = world.get_resource_mut::<PowerGrid>() {
+			if grid.generators.iter().all(|x| x.is_online) { return; }
+			let names: Vec<String> = grid.generators.iter().map(|x| x.name.clone()).collect();
+			for name in names {
+				grid.set_online(&name, true);
+			}
+		}
+		if let Some(mut msglog) = world.get_resource_mut::<MessageLog>() {
+			msglog.tell_player("You heave the breaker over to ON, and the ship shudders back to life.");
+		}
+	}
+}
+
+// EOF
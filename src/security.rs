@@ -0,0 +1,259 @@
+// security.rs
+// Provides the authorization check for locks, so that every way of getting through a lock follows the same rules
+
+// ###: EXTERNAL LIBS
+use std::fmt;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::system::Query;
+use bevy_turborand::DelegatedRng;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+
+// ###: CONSTANTS
+/// The amount of battery charge that a failed hacking attempt costs the PLANQ
+pub const HACK_FAIL_DRAIN: i32 = 5;
+
+// ###: METHODS
+/// Decides whether the actor may open the given lock using the given method
+/// Lock attempts are not checked here: anyone can lock something, it's only the unlocking that needs permission
+pub fn authorize(world: &impl SecurityAccess, actor: Entity, lock: &Lockable, method: &AccessMethod) -> Result<(), FailReason> {
+	match method {
+		AccessMethod::Key => {
+			let keys = world.carried_keys(actor);
+			if keys.is_empty() { return Err(FailReason::NoKeys); }
+			// Any one matching key is enough, regardless of the order that the keys were found in
+			if keys.contains(&lock.key_id) { Ok(()) } else { Err(FailReason::WrongKey) }
+		}
+		AccessMethod::Code(input) => {
+			if lock.code == 0 { return Err(FailReason::NoKeypad); }
+			match input.trim().parse::<i32>() {
+				Ok(value) if value == lock.code => { Ok(()) }
+				_ => { Err(FailReason::WrongCode) }
+			}
+		}
+		AccessMethod::Remote(port) => {
+			if !lock.remote { return Err(FailReason::NotNetworked); }
+			if world.can_reach(actor, *port) { Ok(()) } else { Err(FailReason::Unreachable) }
+		}
+		AccessMethod::Force => { Err(FailReason::TooSturdy) } // TODO: needs tools, and a lock that isn't built to spec
+	}
+}
+
+/// Returns the percent chance that a hacking attempt on a lock of the given difficulty will succeed
+/// The bonus comes from any equipment that helps out, and may be negative if something is getting in the way
+pub fn hack_chance(difficulty: i32, bonus: i32) -> i32 {
+	(100 - difficulty + bonus).clamp(0, 100)
+}
+/// Rolls against the given percent chance using the given RNG, returns true if the roll succeeded
+/// A chance of 100 always succeeds and a chance of 0 always fails; any other result only depends on the RNG's seed
+pub fn skill_check(rng: &mut impl DelegatedRng, chance: i32) -> bool {
+	rng.i32(0..100) < chance
+}
+
+/// Decides whether something with the given lock on it can be opened right now; unlocking it is a separate step
+pub fn check_open(lock: Option<&Lockable>) -> Result<(), FailReason> {
+	if lock.map_or(false, |x| x.is_locked) { Err(FailReason::Locked) } else { Ok(()) }
+}
+
+// ###: COMPLEX TYPES
+//  ##: SecurityAccess
+/// Describes the parts of the game world that authorize() needs to look at
+pub trait SecurityAccess {
+	/// Returns the key_id of every Key that the actor is carrying
+	fn carried_keys(&self, actor: Entity) -> Vec<i32>;
+	/// Returns true if the actor has a live shipnet connection by way of the given access port
+	fn can_reach(&self, actor: Entity, port: Entity) -> bool;
+}
+//  ##: SecurityQuery
+/// Provides the SecurityAccess view of the game world from inside a Bevy system
+pub struct SecurityQuery<'a, 'w, 's> {
+	pub key_query: &'a Query<'w, 's, (&'static Portable, &'static Key), With<IsCarried>>,
+	/// The (actor, port) pair of the only shipnet connection there is so far, which is the player's PLANQ jack
+	pub cnxn: Option<(Entity, Entity)>,
+}
+impl<'a, 'w, 's> SecurityAccess for SecurityQuery<'a, 'w, 's> {
+	fn carried_keys(&self, actor: Entity) -> Vec<i32> {
+		self.key_query.iter().filter(|(k_portable, _)| k_portable.carrier == actor).map(|(_, k_key)| k_key.key_id).collect()
+	}
+	fn can_reach(&self, actor: Entity, port: Entity) -> bool {
+		port != Entity::PLACEHOLDER && self.cnxn == Some((actor, port))
+	}
+}
+
+// ###: SIMPLE TYPES AND HELPERS
+//  ##: AccessMethod
+/// The ways that an actor can try to get through a lock
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessMethod {
+	/// Try each of the keys that the actor is carrying
+	Key,
+	/// Punch the given code into the lock's keypad
+	Code(String),
+	/// Send an unlock command over the shipnet, by way of the given access port
+	Remote(Entity),
+	/// Break the lock open
+	Force,
+}
+//  ##: FailReason
+/// The reasons that authorize() can turn down an attempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailReason {
+	NoKeys,
+	WrongKey,
+	NoKeypad,
+	WrongCode,
+	NotNetworked,
+	Unreachable,
+	TooSturdy,
+	Locked,
+}
+impl fmt::Display for FailReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let output = match self {
+			FailReason::NoKeys => "You don't have any keys to try.",
+			FailReason::WrongKey => "You don't seem to have the right key.",
+			FailReason::NoKeypad => "There's no keypad to enter a code on.",
+			FailReason::WrongCode => "The keypad buzzes: wrong code.",
+			FailReason::NotNetworked => "That lock isn't connected to the shipnet.",
+			FailReason::Unreachable => "You can't reach that lock from the network you're on.",
+			FailReason::TooSturdy => "The lock is far too sturdy to break open.",
+			FailReason::Locked => "It won't budge: it's locked.",
+		};
+		write!(f, "{}", output)
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::ecs::system::SystemState;
+	use bevy::ecs::world::World;
+	use bevy_turborand::GlobalRng;
+	use super::*;
+	//  ##: Setup
+	/// A stand-in for the game world, holding just the keys the actor has on them and their shipnet connections
+	#[derive(Default)]
+	struct FakeWorld {
+		keys: Vec<i32>,
+		links: Vec<(Entity, Entity)>,
+	}
+	impl SecurityAccess for FakeWorld {
+		fn carried_keys(&self, _actor: Entity) -> Vec<i32> {
+			self.keys.clone()
+		}
+		fn can_reach(&self, actor: Entity, port: Entity) -> bool {
+			self.links.contains(&(actor, port))
+		}
+	}
+	/// The actor that makes every attempt, and the access port that's wired up to the locks
+	const ACTOR: Entity = Entity::from_raw(1);
+	const PORT: Entity = Entity::from_raw(2);
+	/// Makes a locked lock that takes key 7, has the keypad code 1234, and may or may not be on the shipnet
+	fn lock(remote: bool) -> Lockable {
+		Lockable { is_locked: true, key_id: 7, code: 1234, remote, difficulty: 50 }
+	}
+	//  ##: Keys
+	#[test]
+	fn a_key_is_needed_to_use_the_keyhole() {
+		assert_eq!(authorize(&FakeWorld::default(), ACTOR, &lock(false), &AccessMethod::Key), Err(FailReason::NoKeys));
+		let world = FakeWorld { keys: vec![3], ..FakeWorld::default() };
+		assert_eq!(authorize(&world, ACTOR, &lock(false), &AccessMethod::Key), Err(FailReason::WrongKey));
+		let world = FakeWorld { keys: vec![7], ..FakeWorld::default() };
+		assert_eq!(authorize(&world, ACTOR, &lock(false), &AccessMethod::Key), Ok(()));
+	}
+	#[test]
+	fn the_right_key_works_wherever_it_is_on_the_ring() {
+		// Only the first key used to get tried, so a wrong key picked up earlier would hide the right one
+		for keys in [vec![7, 3, 5], vec![3, 7, 5], vec![3, 5, 7]] {
+			let world = FakeWorld { keys: keys.clone(), ..FakeWorld::default() };
+			assert_eq!(authorize(&world, ACTOR, &lock(false), &AccessMethod::Key), Ok(()), "{:?}", keys);
+		}
+	}
+	#[test]
+	fn only_the_actors_own_keys_are_tried() {
+		let mut world = World::new();
+		let (actor, other) = (world.spawn_empty().id(), world.spawn_empty().id());
+		world.spawn((Portable::new(other), IsCarried::default(), Key { key_id: 7 }));
+		world.spawn((Portable::new(actor), IsCarried::default(), Key { key_id: 3 }));
+		// A key lying on the floor isn't carried by anyone, whatever its Portable says
+		world.spawn((Portable::new(actor), Key { key_id: 7 }));
+		let mut state: SystemState<Query<(&Portable, &Key), With<IsCarried>>> = SystemState::new(&mut world);
+		let key_query = state.get(&world);
+		let access = SecurityQuery { key_query: &key_query, cnxn: None };
+		assert_eq!(access.carried_keys(actor), vec![3]);
+		assert_eq!(authorize(&access, actor, &lock(false), &AccessMethod::Key), Err(FailReason::WrongKey));
+		assert_eq!(authorize(&access, other, &lock(false), &AccessMethod::Key), Ok(()));
+	}
+	//  ##: Keypads
+	#[test]
+	fn the_keypad_takes_only_its_own_code() {
+		let world = FakeWorld::default();
+		let attempt = |input: &str| authorize(&world, ACTOR, &lock(false), &AccessMethod::Code(input.to_string()));
+		assert_eq!(attempt("1234"), Ok(()));
+		assert_eq!(attempt(" 1234 "), Ok(()));
+		assert_eq!(attempt("4321"), Err(FailReason::WrongCode));
+		assert_eq!(attempt("12a4"), Err(FailReason::WrongCode));
+		assert_eq!(attempt(""), Err(FailReason::WrongCode));
+	}
+	#[test]
+	fn a_lock_without_a_keypad_takes_no_code() {
+		let keyless = Lockable { code: 0, ..lock(false) };
+		// Not even the 'code' that it has on file
+		let attempt = authorize(&FakeWorld::default(), ACTOR, &keyless, &AccessMethod::Code("0".to_string()));
+		assert_eq!(attempt, Err(FailReason::NoKeypad));
+	}
+	//  ##: Remote access
+	#[test]
+	fn remote_access_needs_a_networked_lock_and_a_live_connection() {
+		let linked = FakeWorld { links: vec![(ACTOR, PORT)], ..FakeWorld::default() };
+		assert_eq!(authorize(&linked, ACTOR, &lock(true), &AccessMethod::Remote(PORT)), Ok(()));
+		assert_eq!(authorize(&linked, ACTOR, &lock(false), &AccessMethod::Remote(PORT)), Err(FailReason::NotNetworked));
+		// Being jacked in somewhere else doesn't help
+		let elsewhere = Entity::from_raw(3);
+		assert_eq!(authorize(&linked, ACTOR, &lock(true), &AccessMethod::Remote(elsewhere)), Err(FailReason::Unreachable));
+		assert_eq!(authorize(&FakeWorld::default(), ACTOR, &lock(true), &AccessMethod::Remote(PORT)), Err(FailReason::Unreachable));
+	}
+	#[test]
+	fn the_placeholder_port_is_never_reachable() {
+		let mut world = World::new();
+		let mut state: SystemState<Query<(&Portable, &Key), With<IsCarried>>> = SystemState::new(&mut world);
+		let key_query = state.get(&world);
+		let jacked_in = SecurityQuery { key_query: &key_query, cnxn: Some((ACTOR, PORT)) };
+		assert!(jacked_in.can_reach(ACTOR, PORT));
+		assert!(!jacked_in.can_reach(Entity::from_raw(3), PORT));
+		let unplugged = SecurityQuery { key_query: &key_query, cnxn: Some((ACTOR, Entity::PLACEHOLDER)) };
+		assert!(!unplugged.can_reach(ACTOR, Entity::PLACEHOLDER));
+	}
+	//  ##: Hacking
+	#[test]
+	fn the_hack_chance_stays_within_bounds() {
+		assert_eq!(hack_chance(50, 0), 50);
+		assert_eq!(hack_chance(50, 10), 60);
+		assert_eq!(hack_chance(0, 20), 100);
+		assert_eq!(hack_chance(100, -20), 0);
+	}
+	#[test]
+	fn a_certain_roll_never_goes_the_other_way() {
+		let mut rng = GlobalRng::with_seed(1);
+		for _ in 0..1000 {
+			assert!(skill_check(&mut rng, hack_chance(0, 0)));
+			assert!(!skill_check(&mut rng, hack_chance(100, 0)));
+		}
+	}
+	//  ##: Force and opening
+	#[test]
+	fn no_lock_can_be_forced_yet() {
+		let world = FakeWorld { keys: vec![7], links: vec![(ACTOR, PORT)] };
+		assert_eq!(authorize(&world, ACTOR, &lock(true), &AccessMethod::Force), Err(FailReason::TooSturdy));
+	}
+	#[test]
+	fn only_a_locked_lock_stops_anything_opening() {
+		assert_eq!(check_open(None), Ok(()));
+		assert_eq!(check_open(Some(&Lockable { is_locked: false, ..lock(false) })), Ok(()));
+		assert_eq!(check_open(Some(&lock(false))), Err(FailReason::Locked));
+	}
+}
+
+// EOF
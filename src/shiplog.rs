@@ -0,0 +1,148 @@
+// shiplog.rs
+// Provides the ship's own record of what's been happening aboard, which is kept whether or not the player was around
+// to see any of it, and which can be read out at any of the AccessPorts
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+
+// ###: INTERNAL LIBS
+use crate::engine::event::ShipLogEvent;
+use crate::sys::{ship_clock, DurationFmtExt};
+
+// ###: CONSTANTS
+/// The number of entries that the ship log keeps before it starts archiving the oldest ones
+pub const SHIP_LOG_LIMIT: usize = 200;
+/// The number of entries that are shown on each page of the PLANQ's readout
+pub const SHIP_LOG_PAGE: usize = 10;
+
+// ###: BEVY SYSTEMS
+/// Stamps any newly-reported happenings with the ship time and adds them to the ship log
+pub fn ship_log_system(mut sreader: EventReader<ShipLogEvent>,
+	                     mut log:     ResMut<ShipLog>,
+	                     time:        Res<Time>,
+) {
+	if sreader.is_empty() { return; }
+	let stamp = ship_clock(&time).get_as_string();
+	for event in sreader.iter() {
+		log.record(&stamp, &event.text);
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: ShipLog
+/// The ship's log, oldest entry first; only the most recent SHIP_LOG_LIMIT entries are kept, and the rest are archived,
+/// which for now only means that they're counted
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct ShipLog {
+	pub entries: Vec<ShipLogEntry>,
+	pub archived: usize, // The number of entries that have been rolled off the end of the log
+}
+impl ShipLog {
+	/// Adds a new entry to the end of the log, archiving the oldest one if the log is full
+	pub fn record(&mut self, stamp: &str, text: &str) {
+		self.entries.push(ShipLogEntry { stamp: stamp.to_string(), text: text.to_string() });
+		if self.entries.len() > SHIP_LOG_LIMIT {
+			let overflow = self.entries.len() - SHIP_LOG_LIMIT;
+			self.entries.drain(..overflow);
+			self.archived += overflow;
+		}
+	}
+	/// Returns the number of pages that the log fills, which is always at least one
+	pub fn page_count(&self) -> usize {
+		((self.entries.len() + SHIP_LOG_PAGE - 1) / SHIP_LOG_PAGE).max(1)
+	}
+	/// Produces one page of the log for the PLANQ to show, newest entries first; page 1 is the most recent
+	pub fn page(&self, page: usize) -> Vec<String> {
+		if self.entries.is_empty() { return vec!["The ship log is empty.".to_string()]; }
+		let pages = self.page_count();
+		if page == 0 || page > pages { return vec![format!("No such page: {} (the log has {})", page, pages)]; }
+		let mut report = vec![format!("SHIP LOG, page {} of {}", page, pages)];
+		report.extend(self.entries.iter().rev()
+			.skip((page - 1) * SHIP_LOG_PAGE)
+			.take(SHIP_LOG_PAGE)
+			.map(|x| format!("  {} {}", x.stamp, x.text)));
+		if page == pages && self.archived > 0 {
+			report.push(format!("  ({} older entries archived)", self.archived));
+		}
+		report
+	}
+}
+//  ##: ShipLogEntry
+/// A single entry in the ship log
+#[derive(Clone, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct ShipLogEntry {
+	pub stamp: String, // The ship time that the entry was made at
+	pub text: String,
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use crate::engine::RunState;
+	use crate::engine::event::{DevicePowerChanged, PowerOutage};
+	use crate::engine::messagelog::MessageLog;
+	use crate::power::{power_grid_system, PowerGrid};
+	use crate::worldmap::WorldModel;
+	use super::*;
+	fn filled_log(count: usize) -> ShipLog {
+		let mut log = ShipLog::default();
+		for index in 0..count {
+			log.record("00:00", &format!("entry {}", index));
+		}
+		log
+	}
+	//  ##: Recording
+	#[test]
+	fn events_are_logged_with_nobody_around_to_see_them() {
+		let mut app = App::new();
+		app.add_event::<PowerOutage>()
+		.add_event::<DevicePowerChanged>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(Time::default())
+		.insert_resource(RunState::new())
+		.insert_resource(WorldModel::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.insert_resource(PowerGrid::new(vec!["reactor".to_string()]))
+		.insert_resource(ShipLog::default())
+		.add_systems(Update, (power_grid_system, ship_log_system.after(power_grid_system)));
+		app.world.send_event(PowerOutage { generator: None, duration: 10 });
+		app.update();
+		let log = app.world.resource::<ShipLog>();
+		assert_eq!(log.entries.iter().map(|x| x.text.as_str()).collect::<Vec<&str>>(), vec!["ALERT: reactor offline"]);
+	}
+	#[test]
+	fn the_oldest_entries_are_archived() {
+		let log = filled_log(SHIP_LOG_LIMIT + 5);
+		assert_eq!(log.entries.len(), SHIP_LOG_LIMIT);
+		assert_eq!(log.archived, 5);
+		assert_eq!(log.entries[0].text, "entry 5");
+	}
+	//  ##: Pagination
+	#[test]
+	fn the_first_page_has_the_newest_entries() {
+		let log = filled_log(SHIP_LOG_PAGE + 3);
+		assert_eq!(log.page_count(), 2);
+		let page = log.page(1);
+		assert_eq!(page[0], "SHIP LOG, page 1 of 2");
+		assert_eq!(page.len(), SHIP_LOG_PAGE + 1);
+		assert_eq!(page[1], format!("  00:00 entry {}", SHIP_LOG_PAGE + 2));
+	}
+	#[test]
+	fn the_last_page_has_the_rest() {
+		let log = filled_log(SHIP_LOG_PAGE + 3);
+		let page = log.page(2);
+		assert_eq!(page.len(), 4);
+		assert_eq!(page[3], "  00:00 entry 0");
+	}
+	#[test]
+	fn pages_out_of_range_are_refused() {
+		let log = filled_log(3);
+		assert_eq!(log.page(0), vec!["No such page: 0 (the log has 1)".to_string()]);
+		assert_eq!(log.page(2), vec!["No such page: 2 (the log has 1)".to_string()]);
+		assert_eq!(ShipLog::default().page(1), vec!["The ship log is empty.".to_string()]);
+	}
+}
+
+// EOF
@@ -9,7 +9,7 @@
 // ###: EXTERNAL LIBS
 use bevy::ecs::archetype::Archetypes;
 use bevy::ecs::component::{ComponentId, Components};
-use bevy::ecs::entity::Entity;
+use bevy::ecs::entity::{Entities, Entity};
 use bevy::ecs::event::{EventReader, EventWriter};
 use bevy::ecs::query::{
 	Changed,
@@ -18,10 +18,12 @@ use bevy::ecs::query::{
 };
 use bevy::ecs::system::{
 	Commands,
+	Local,
 	Query,
 	Res,
 	ResMut
 };
+use bevy::time::Time;
 use bevy::utils::{Duration, HashSet};
 use bevy_turborand::*;
 use bracket_pathfinding::prelude::*;
@@ -37,14 +39,33 @@ use crate::components::{
 	Player,
 	Position,
 };
+use crate::engine::{EngineMode, RunState};
+use crate::engine::preferences::Preferences;
 use crate::engine::event::*;
 use crate::engine::event::GameEventType::*;
 use crate::engine::event::ActionType::*;
 use crate::engine::messagelog::*;
+use crate::fov::*;
 use crate::planq::*;
+use crate::planq::comms::*;
 use crate::planq::monitor::*;
+use crate::power::PowerGrid;
+use crate::quickslots::QuickSlots;
+use crate::robot::Maintainer;
+use crate::scenarios::intro;
+use crate::security::*;
 use crate::worldmap::*;
 
+// ###: CONSTANTS
+/// The number of separate items that the player can carry, on top of the limit set by their CarryCapacity
+pub const PLAYER_SLOTS: usize = 12;
+/// The wear that a Device takes every time it's used
+pub const USE_WEAR: u32 = 2;
+/// The wear that a Device takes for every action the player takes while it's left lying out in a hazard, ie in vacuum
+pub const HAZARD_WEAR: u32 = 1;
+/// How far out from where an item was meant to be dropped that it may land instead, if that spot isn't clear
+pub const DROP_SEARCH_RADIUS: i32 = 2;
+
 // ###: CONTINUOUS SYSTEMS
 /// Handles connections between maintenance devices like the PLANQ and access ports on external entities
 pub fn access_port_system(mut ereader:      EventReader<GameEvent>,
@@ -111,6 +132,7 @@ pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually
 							"Lockable"    => {
 								new_set.insert(ActionType::UnlockItem);
 								new_set.insert(ActionType::LockItem);
+								new_set.insert(ActionType::HackItem);
 							}
 							"Key"         => {
 								new_set.insert(ActionType::UnlockItem);
@@ -132,7 +154,8 @@ pub fn action_referee_system(_cmd:       Commands, // gonna need this eventually
 /// Handles requests for descriptions of entities by the player
 pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 	                        mut msglog:   ResMut<MessageLog>,
-	                        e_query:      Query<(Entity, &Description)>,
+	                        e_query:      Query<(Entity, &Description, Option<&History>, Option<&Lockable>)>,
+	                        model:        Res<WorldModel>,
 ) {
 	// Bail out if there's no events in the queue
 	// For every event in the queue,
@@ -144,13 +167,81 @@ pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 		if event.etype != PlayerAction(ActionType::Examine) { continue; }
 		if let Some(econtext) = event.context.as_ref() {
 			if econtext.object == Entity::PLACEHOLDER {
+				// Examining a map tile rather than an Entity gets the engineering scan of the terrain
+				if let Some(target) = econtext.location {
+					for line in model.describe_tile(target) {
+						msglog.tell_player(&line);
+					}
+					continue;
+				}
 				warn!("* Attempted to Examine the Entity::PLACEHOLDER"); // DEBUG: warn if this case occurs
 				continue;
 			}
-			if let Ok((_enty, e_desc)) = e_query.get(econtext.object) {
+			if let Ok((_enty, e_desc, e_history, e_lock)) = e_query.get(econtext.object) {
 				//let output = e_desc.desc.clone();
 				let output = &e_desc.desc;
 				msglog.tell_player(output);
+				if let Some(lock) = e_lock {
+					let state = if lock.is_locked { "locked" } else { "unlocked" };
+					let keypad = if lock.code != 0 { ", and has a keypad" } else { "" };
+					msglog.tell_player(format!("It's fitted with a lock, which is currently {}{}.", state, keypad).as_str());
+				}
+				// If something notable happened to it recently, the player can tell
+				if let Some(latest) = e_history.and_then(|x| x.latest()) {
+					msglog.tell_player(latest);
+				}
+			}
+		}
+	}
+}
+/// Reveals the true nature of Unidentified items, whether by using them, examining them at a Workbench, or by way of
+/// a PLANQ analysis
+pub fn identification_system(mut commands: Commands,
+	                           mut ereader:  EventReader<GameEvent>,
+	                           mut ireader:  EventReader<IdentifyItem>,
+	                           mut msglog:   ResMut<MessageLog>,
+	                           i_query:      Query<(&Description, &Unidentified)>,
+	                           a_query:      Query<&Body>,
+	                           w_query:      Query<&Body, With<Workbench>>,
+	                           p_query:      Query<Entity, With<Player>>,
+) {
+	let mut requests: Vec<(Entity, Entity)> = ireader.iter().map(|x| (x.item, x.identifier)).collect();
+	for event in ereader.iter() {
+		let econtext = if let Some(value) = event.context { value } else { continue };
+		if let PlayerAction(action) | ActorAction(action) = event.etype {
+			match action {
+				UseItem => { requests.push((econtext.object, econtext.subject)); }
+				Examine => {
+					// Examining only helps if there's a Workbench at hand to take a proper look with
+					let at_bench = a_query.get(econtext.subject)
+						.map_or(false, |a_body| w_query.iter().any(|w_body| w_body.is_adjacent_to(&a_body.ref_posn)));
+					if at_bench { requests.push((econtext.object, econtext.subject)); }
+				}
+				_ => { }
+			}
+		}
+	}
+	requests.sort();
+	requests.dedup_by_key(|x| x.0);
+	let player = p_query.get_single().ok();
+	for (item, identifier) in requests {
+		if let Ok((i_desc, i_unknown)) = i_query.get(item) {
+			if Some(identifier) == player {
+				msglog.tell_player(format!("You've worked it out: the {} is a {}!", i_desc.name, i_unknown.true_desc.name).as_str());
+			}
+			commands.entity(item).insert(i_unknown.true_desc.clone()).remove::<Unidentified>();
+		}
+	}
+}
+/// Alerts anything that Hears to any Noise that was loud enough to reach it
+pub fn hearing_system(mut nreader: EventReader<Noise>,
+	                    mut h_query: Query<(Entity, &Body, &mut Hears)>,
+) {
+	for noise in nreader.iter() {
+		for (h_enty, h_body, mut h_ears) in h_query.iter_mut() {
+			if h_enty == noise.maker { continue; } // Nobody startles at their own footsteps
+			if h_body.in_range_of(&noise.source, noise.volume) {
+				h_ears.last_heard = Some(noise.source);
 			}
 		}
 	}
@@ -158,11 +249,27 @@ pub fn examination_system(mut ereader:  EventReader<GameEvent>,
 /// Handles pickup/drop/destroy requests for Items
 pub fn item_collection_system(mut cmd:      Commands,
 	                            mut ereader:  EventReader<GameEvent>,
+	                            mut iwriter:  EventWriter<InventoryChanged>,
+	                            mut kwriter:  EventWriter<EntityKilled>,
 	                            mut msglog:   ResMut<MessageLog>,
 	                            // The list of Entities that also have Containers
 	                            e_query:      Query<(Entity, &Description, &Body, &Container, Option<&Player>)>,
-	                            // The list of every Item that may or may not be in a container
-	                            mut i_query:      Query<(Entity, &Description, &mut Body, &Portable), Without<Container>>,
+	                            // The list of every Item that may or may not be in a container, including any bags and boxes
+	                            // Their Bodies are shared with the Containers above, so any moves go through the Commands
+	                            i_query:      Query<(Entity, &Description, &Body, &Portable)>,
+	                            // The list of Containers that can be opened and closed, ie crates and lockers
+	                            c_query:      Query<(&Description, &Openable), With<Container>>,
+	                            // The stationary Containers that can be emptied out, ie everything but the actors
+	                            b_query:      Query<(&Description, Option<&Openable>), (With<Container>, Without<Mobile>)>,
+	                            // The Containers that are being carried around, ie a toolbox in the player's inventory
+	                            d_query:      Query<(&Description, &Container, &Portable)>,
+	                            // The things that nothing else can share a tile with, ie actors and closed doors
+	                            o_query:      Query<Entity, With<Obstructive>>,
+	                            p_view_query: Query<&Viewshed, With<Player>>,
+	                            p_posn:       Res<Position>,
+	                            policy:       Res<PerceptionPolicy>,
+	                            model:        Res<WorldModel>,
+	                            mut run_state: ResMut<RunState>,
 ) {
 	// Don't even bother trying if there's no events to worry about
 	if ereader.is_empty() { return; }
@@ -174,51 +281,231 @@ pub fn item_collection_system(mut cmd:      Commands,
 				match action {
 					ActionType::MoveItem
 					| ActionType::DropItem
+					| ActionType::EmptyItem
 					| ActionType::KillItem => { atype = action; }
 					_ => { continue; }
 				}
 			}
+			GameEventType::ItemGive { from, item, to } => {
+				// A give moves the item straight from one carrier to the other, without it ever touching the floor
+				let (_, f_desc, f_body, _, f_player) = if let Ok(value) = e_query.get(from) { value } else { continue };
+				let is_player_action = f_player.is_some();
+				let (_, t_desc, t_body, t_container, t_player) = if let Ok(value) = e_query.get(to) { value } else {
+					if is_player_action { msglog.tell_player("You can't hand things to that."); }
+					continue;
+				};
+				if !t_body.in_range_of(&f_body.ref_posn, DEFAULT_REACH) {
+					if is_player_action { msglog.tell_player(format!("The {} is out of reach.", t_desc.name).as_str()); }
+					continue;
+				}
+				let (_, i_desc, _, i_portable) = if let Ok(value) = i_query.get(item) { value } else { continue };
+				if i_portable.carrier != from {
+					if is_player_action { msglog.tell_player("You aren't carrying that anymore."); }
+					continue;
+				}
+				if is_within(to, item, |x| i_query.get(x).ok().map(|y| y.3.carrier)) {
+					if is_player_action { msglog.tell_player(format!("The {} won't fit inside of itself.", i_desc.name).as_str()); }
+					continue;
+				}
+				if let Ok((c_desc, c_open)) = c_query.get(to) {
+					if !c_open.is_open {
+						if is_player_action { msglog.tell_player(format!("The {} is closed.", c_desc.name).as_str()); }
+						continue;
+					}
+				}
+				if let Some(max_slots) = t_container.max_slots {
+					if i_query.iter().filter(|x| x.3.carrier == to).count() >= max_slots {
+						if is_player_action { msglog.tell_player(format!("The {} can't carry any more.", t_desc.name).as_str()); }
+						continue;
+					}
+				}
+				let item_name = i_desc.name.clone();
+				cmd.entity(item)
+				.insert(Portable::new(to))
+				.insert(IsCarried::default());
+				iwriter.send(InventoryChanged { carrier: from, item });
+				iwriter.send(InventoryChanged { carrier: to, item });
+				// Nobody hands things to a locker, they just put them in it
+				let is_fixture = b_query.contains(to);
+				if is_player_action {
+					if is_fixture {
+						msglog.add(&format!("You put the {} in the {}.", item_name, t_desc.name), "world", 0, 0);
+					} else {
+						msglog.add(&format!("You hand the {} to the {}.", item_name, t_desc.name), "world", 0, 0);
+					}
+				} else if t_player.is_some() {
+					msglog.add(&format!("The {} hands you a {}.", f_desc.name, item_name), "world", 0, 0);
+				} else if policy.can_perceive(*p_posn, p_view_query.get_single().ok(), f_body.ref_posn) {
+					if is_fixture {
+						msglog.add(&format!("The {} puts a {} in the {}.", f_desc.name, item_name, t_desc.name), "world", 0, 0);
+					} else {
+						msglog.add(&format!("The {} hands a {} to the {}.", f_desc.name, item_name, t_desc.name), "world", 0, 0);
+					}
+				}
+				continue;
+			}
 			_ => { continue; }
 		};
 		// All of the item events require an event context, so if there isn't any then don't try to handle the event
-		if event.context.is_none() { continue; }
-		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
-		// We know that it is safe to unwrap these because calling is_invalid() checked that they are not placeholders
-		//let subject = e_query.get(econtext.subject).expect("econtext.subject should be Some(n)");
-		let (s_enty, s_desc, s_body, _container, s_player) = e_query.get(econtext.subject).expect("econtext.subject should be Some(n)");
+		let econtext = if let Some(value) = event.context.as_ref() { value } else { continue };
+		// The subject may have gone away since the event was sent, ie if it was destroyed on the same turn
+		let (s_enty, s_desc, s_body, s_container, s_player) = if let Ok(value) = e_query.get(econtext.subject) { value } else {
+			warn!("? item_collection_system: the subject {:?} of the {} is gone", econtext.subject, atype); // DEBUG: report a stale subject
+			continue;
+		};
 		let subject_name = s_desc.name.clone();
 		let is_player_action = s_player.is_some();
-		let (o_enty, o_desc, mut o_body, _) = i_query.get_mut(econtext.object).expect("econtext.object should be Some(n)");
+		let is_perceived = is_player_action || policy.can_perceive(*p_posn, p_view_query.get_single().ok(), s_body.ref_posn);
+		// Emptying a container drops everything in it at once, so it gets handled separately from the single items
+		if atype == ActionType::EmptyItem {
+			let (b_desc, b_open) = if let Ok(value) = b_query.get(econtext.object) { value } else { continue };
+			if b_open.map_or(false, |x| !x.is_open) {
+				if is_player_action { msglog.tell_player(format!("The {} is closed.", b_desc.name).as_str()); }
+				continue;
+			}
+			let contents: Vec<Entity> = i_query.iter().filter(|x| x.3.carrier == econtext.object).map(|x| x.0).collect();
+			if contents.is_empty() {
+				if is_player_action { msglog.tell_player(format!("The {} is already empty.", b_desc.name).as_str()); }
+				continue;
+			}
+			// Spread the items out around the actor's feet, doubling up only once every free tile has something on it
+			let mut taken: Vec<Position> = i_query.iter().filter(|x| x.3.carrier == Entity::PLACEHOLDER).map(|x| x.2.ref_posn).collect();
+			let origin = s_body.ref_posn;
+			let spots: Vec<Position> = [(0, 0), (0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)].iter()
+				.map(|(x, y)| origin + (*x, *y, 0))
+				.filter(|posn| model.contains(*posn) && model.get_tiletype_at(*posn).properties().passable && !model.is_blocked_at(*posn))
+				.collect();
+			for item in contents.iter() {
+				let spot = spots.iter().find(|x| !taken.contains(*x)).copied().unwrap_or(origin);
+				taken.push(spot);
+				if let Ok((i_enty, _, i_body, _)) = i_query.get(*item) {
+					let mut new_body = i_body.clone();
+					new_body.move_to(spot);
+					cmd.entity(i_enty)
+					.insert(Portable{carrier: Entity::PLACEHOLDER})
+					.insert(new_body)
+					.remove::<IsCarried>();
+				}
+			}
+			iwriter.send(InventoryChanged { carrier: econtext.object, item: Entity::PLACEHOLDER });
+			let count = contents.len();
+			let plural = if count == 1 { "item spills" } else { "items spill" };
+			if is_player_action {
+				msglog.add(&format!("You empty out the {}: {} {} onto the floor.", b_desc.name, count, plural), "world", 0, 0);
+			} else if is_perceived {
+				msglog.add(&format!("The {} empties out the {}.", subject_name, b_desc.name), "world", 0, 0);
+			}
+			continue;
+		}
+		// A DropItem with a destination is a deposit into a salvage bin, which the salvage_system vets and hands back as an ItemGive
+		if atype == ActionType::DropItem && econtext.destination.is_some() { continue; }
+		// A MoveItem goes into the subject's own inventory, unless it names one of the Containers that the subject carries
+		let mut dest = s_enty;
+		let mut dest_name = None;
+		let mut dest_slots = s_container.max_slots;
+		if let Some(target) = econtext.destination.filter(|x| *x != s_enty) {
+			match d_query.get(target) {
+				Ok((d_desc, d_container, d_portable)) if d_portable.carrier == s_enty => {
+					dest = target;
+					dest_name = Some(d_desc.name.clone());
+					dest_slots = d_container.max_slots;
+				}
+				_ => {
+					if is_player_action { msglog.tell_player("You aren't carrying that anymore."); }
+					continue;
+				}
+			}
+		}
+		// A Container with a slot limit refuses anything past it, no matter how light the item is
+		if atype == ActionType::MoveItem {
+			if let Some(max_slots) = dest_slots {
+				let held = i_query.iter().filter(|x| x.3.carrier == dest).count();
+				if held >= max_slots {
+					if is_player_action {
+						match dest_name {
+							Some(ref name) => { msglog.tell_player(format!("The {} is full: all {} of its slots are taken.", name, max_slots).as_str()); }
+							None => { msglog.tell_player(format!("You can't carry any more things: all {} of your slots are full.", max_slots).as_str()); }
+						}
+					}
+					continue;
+				}
+			}
+		}
+		let (o_enty, o_desc, o_body, o_portable) = if let Ok(value) = i_query.get(econtext.object) { value } else {
+			warn!("? item_collection_system: the object {:?} of the {} is gone", econtext.object, atype); // DEBUG: report a stale object
+			continue;
+		};
 		let item_name = o_desc.name.clone();
+		// A bag can't be put into itself, or into anything that's already inside of it
+		if atype == ActionType::MoveItem && is_within(dest, o_enty, |x| i_query.get(x).ok().map(|y| y.3.carrier)) {
+			if is_player_action { msglog.tell_player(format!("The {} won't fit inside of itself.", item_name).as_str()); }
+			continue;
+		}
+		// Items inside of a closed container can't be reached until it's opened
+		if atype == ActionType::MoveItem {
+			if let Ok((c_desc, c_open)) = c_query.get(o_portable.carrier) {
+				if !c_open.is_open {
+					if is_player_action {
+						msglog.tell_player(format!("The {} is closed.", c_desc.name).as_str());
+					}
+					continue;
+				}
+			}
+		}
 		// We have all of our context values now, so proceed to actually doing the requested action
 		let mut message: String = "".to_string();
 		match atype {
 			ActionType::MoveItem => { // Move an Item into an Entity's possession
 				// NOTE: the insert(Portable) call below will overwrite any previous instance of that component
 				cmd.entity(o_enty)
-				.insert(Portable{carrier: s_enty}) // put the container's ID to the target's Portable component
+				.insert(Portable{carrier: dest}) // put the container's ID to the target's Portable component
 				.insert(IsCarried::default()); // add the IsCarried tag to the component
+				iwriter.send(InventoryChanged { carrier: dest, item: o_enty });
+				if o_portable.carrier != Entity::PLACEHOLDER {
+					iwriter.send(InventoryChanged { carrier: o_portable.carrier, item: o_enty });
+				}
 				if is_player_action {
-					message = format!("Obtained a {}.", item_name);
-				} else {
+					message = match dest_name {
+						Some(ref name) => { format!("Obtained a {}, and put it in the {}.", item_name, name) }
+						None => { format!("Obtained a {}.", item_name) }
+					};
+					run_state.items_collected += 1;
+				} else if is_perceived {
 					message = format!("The {} takes a {}.", subject_name, item_name);
 				}
 			}
 			ActionType::DropItem => { // Remove an Item and place it into the World
 				//debug!("* Dropping item..."); // DEBUG: announce item drop
+				// The item goes where it was aimed if that's within reach, else at the subject's feet, or failing
+				// that onto the nearest clear tile; if there isn't one, it stays where it is
+				let preferred = econtext.location
+					.filter(|x| s_body.in_range_of(x, DEFAULT_REACH) && has_line_of_sight(&model, s_body.ref_posn, *x))
+					.unwrap_or(s_body.ref_posn);
+				let spot = if let Some(value) = find_drop_spot(&model, preferred, s_enty, &o_query) { value } else {
+					if is_player_action {
+						msglog.tell_player(format!("There's nowhere clear to put the {} down.", item_name).as_str());
+					}
+					continue;
+				};
+				let mut new_body = o_body.clone();
+				new_body.move_to(spot);
 				cmd.entity(o_enty)
 				.insert(Portable{carrier: Entity::PLACEHOLDER}) // still portable but not carried
+				.insert(new_body)
 				.remove::<IsCarried>(); // remove the tag from the component
-				o_body.move_to(s_body.ref_posn);
+				iwriter.send(InventoryChanged { carrier: s_enty, item: o_enty });
 				if is_player_action {
 					message = format!("Dropped a {}.", item_name);
-				} else {
+				} else if is_perceived {
 					message = format!("The {} drops a {}.", subject_name, item_name);
 				}
 			}
 			ActionType::KillItem => { // DESTROY an Item entirely, ie remove it from the game
 				//debug!("* KILLing item..."); // DEBUG: announce item destruction
-				cmd.entity(o_enty).despawn();
+				if o_portable.carrier != Entity::PLACEHOLDER {
+					iwriter.send(InventoryChanged { carrier: o_portable.carrier, item: o_enty });
+				}
+				despawn_gameplay_entity(&mut cmd, &mut kwriter, o_enty, o_body.posns(), DespawnPolicy::DestroyContents);
 			}
 			action => {
 				error!("* item_collection_system unhandled action: {}", action); // DEBUG: announce unhandled action for this item
@@ -229,20 +516,256 @@ pub fn item_collection_system(mut cmd:      Commands,
 		}
 	}
 }
+/// Cleans up after any gameplay entity that was despawned: deals with whatever it was carrying according to the
+/// despawn policy, and scrubs it out of everything else that might still be holding on to it
+pub fn entity_reaper_system(mut commands: Commands,
+	                          mut kreader:  EventReader<EntityKilled>,
+	                          mut iwriter:  EventWriter<InventoryChanged>,
+	                          mut model:    ResMut<WorldModel>,
+	                          mut planq:    ResMut<PlanqData>,
+	                          mut slots:    ResMut<QuickSlots>,
+	                          mut m_query:  Query<&mut Memory>,
+	                          mut i_query:  Query<(Entity, &Portable, &mut Body), Without<Vitals>>,
+) {
+	if kreader.is_empty() { return; }
+	// Destroying something's contents can turn up more contents in turn, so those get added to the pile as they're found
+	let mut worklist: Vec<EntityKilled> = kreader.iter().cloned().collect();
+	while let Some(killed) = worklist.pop() {
+		let count = match killed.policy {
+			DespawnPolicy::DropContents => {
+				let posn = killed.posns.first().copied().unwrap_or(Position::INVALID);
+				drop_all_carried(&mut commands, killed.entity, posn, &mut i_query)
+			}
+			DespawnPolicy::DestroyContents => {
+				let contents: Vec<(Entity, Vec<Position>)> = i_query.iter().filter(|x| x.1.carrier == killed.entity).map(|x| (x.0, x.2.posns())).collect();
+				for (i_enty, i_posns) in contents.iter() {
+					commands.entity(*i_enty).despawn();
+					worklist.push(EntityKilled { entity: *i_enty, posns: i_posns.clone(), policy: killed.policy });
+				}
+				contents.len()
+			}
+		};
+		if count > 0 { iwriter.send(InventoryChanged { carrier: killed.entity, item: Entity::PLACEHOLDER }); }
+		model.remove_contents(&killed.posns, killed.entity);
+		for mut memory in m_query.iter_mut() {
+			for entys in memory.visual.values_mut() {
+				entys.retain(|x| *x != killed.entity);
+			}
+		}
+		if planq.jack_cnxn == killed.entity {
+			planq.jack_cnxn = Entity::PLACEHOLDER;
+		}
+		slots.retain(|x| x != killed.entity);
+		debug!("* reaped {:?}, {} items {:?}", killed.entity, count, killed.policy); // DEBUG: report the cleanup
+	}
+}
+/// DEBUG: Reports any reference to an entity that has been despawned out from under it, ie an item whose container got
+/// KILLed without going through despawn_gameplay_entity(); covers item carriers, the PLANQ's jack, and the quick-slots
+/// Only runs in debug builds; each dangling reference is only reported once, so that the log doesn't fill up
+pub fn carrier_check_system(entities:     &Entities,
+	                          mut reported: Local<HashSet<Entity>>,
+	                          planq:        Res<PlanqData>,
+	                          slots:        Res<QuickSlots>,
+	                          i_query:      Query<(Entity, &Portable, Option<&Description>)>,
+) {
+	for (enty, report) in find_dangling_references(entities, &planq, &slots, &i_query) {
+		if reported.insert(enty) {
+			error!("! carrier_check_system: {}", report); // DEBUG: report a dangling reference
+		}
+	}
+}
+/// Lists every reference that carrier_check_system looks after which points at an entity that no longer exists, along
+/// with a description of what was left dangling; each one is keyed on the entity that the report is about
+pub fn find_dangling_references(entities: &Entities,
+	                              planq:    &PlanqData,
+	                              slots:    &QuickSlots,
+	                              i_query:  &Query<(Entity, &Portable, Option<&Description>)>,
+) -> Vec<(Entity, String)> {
+	let mut dangling = Vec::new();
+	for (i_enty, i_portable, i_desc) in i_query.iter() {
+		if i_portable.carrier == Entity::PLACEHOLDER || entities.contains(i_portable.carrier) { continue; }
+		let i_name = i_desc.map_or("(unnamed)".to_string(), |x| x.name.clone());
+		dangling.push((i_enty, format!("item {:?} '{}' is carried by {:?}, which no longer exists", i_enty, i_name, i_portable.carrier)));
+	}
+	if planq.jack_cnxn != Entity::PLACEHOLDER && !entities.contains(planq.jack_cnxn) {
+		dangling.push((planq.jack_cnxn, format!("the PLANQ is jacked into {:?}, which no longer exists", planq.jack_cnxn)));
+	}
+	for (index, item) in slots.slots.iter().enumerate() {
+		let item = if let Some(value) = item { *value } else { continue };
+		if !entities.contains(item) {
+			dangling.push((item, format!("quick-slot {} holds {:?}, which no longer exists", index + 1, item)));
+		}
+	}
+	dangling
+}
+/// Handles any actor whose Vitals have run out: the player's game ends, and anything else is reduced to a wreck
+/// Breathing actors that are standing in vacuum lose a hitpoint every turn until they get out of it
+pub fn vitals_system(mut commands:  Commands,
+	                   mut ewriter:   EventWriter<GameEvent>,
+	                   mut msglog:    ResMut<MessageLog>,
+	                   mut run_state: ResMut<RunState>,
+	                   model:         Res<WorldModel>,
+	                   policy:        Res<PerceptionPolicy>,
+	                   mut v_query:   Query<(Entity, &mut Vitals, &mut Body, &mut Description, Option<&NavCaps>, Option<&Player>), (Without<Disabled>, Without<Portable>)>,
+	                   i_query:       Query<(Entity, &Portable), Without<Vitals>>,
+	                   p_view_query:  Query<&Viewshed, With<Player>>,
+	                   p_posn:        Res<Position>,
+) {
+	if run_state.mode != EngineMode::Running { return; }
+	for (v_enty, mut v_vitals, mut v_body, mut v_desc, v_caps, v_player) in v_query.iter_mut() {
+		let posn = v_body.ref_posn;
+		if model.contains(posn)
+		&& model.get_tiletype_at(posn).properties().hazard == Hazard::Vacuum
+		&& v_caps.map_or(true, |x| x.breathes) {
+			v_vitals.hp -= 1;
+			if v_player.is_some() && !v_vitals.is_down() {
+				msglog.tell_player("[[fg:red]]You can't breathe![[end]]");
+			}
+		}
+		if !v_vitals.is_down() { continue; }
+		let count = drop_everything(&mut ewriter, v_enty, posn, &i_query);
+		if v_player.is_some() {
+			msglog.tell_player("[[fg:red]]You collapse.[[end]]");
+			run_state.end_game(EngineMode::BadEnd, "incapacitated");
+			continue;
+		}
+		// Anything else stays behind as an inert, examinable wreck
+		if policy.can_perceive(*p_posn, p_view_query.get_single().ok(), posn) {
+			msglog.add(&format!("The {} collapses in a heap.", v_desc.name), "world", 0, 0);
+		}
+		for glyph in v_body.extent.iter_mut() {
+			glyph.cell.set_glyph("%");
+			glyph.cell.fg = Color::LtBlack as u8;
+		}
+		v_desc.desc = format!("The wreck of a {}; it isn't going anywhere.", v_desc.name);
+		v_desc.name = format!("wrecked {}", v_desc.name);
+		commands.entity(v_enty)
+		.insert(Disabled::default())
+		.remove::<Mobile>()
+		.remove::<Obstructive>();
+		if count > 0 { debug!("* {:?} was disabled and dropped {} items", v_enty, count); } // DEBUG: report the dropped inventory
+	}
+}
+/// Removes a gameplay entity from the world; anything that takes a thing out of the game should come through here, so
+/// that the entity_reaper_system can deal with its contents and clear out any references to it
+pub fn despawn_gameplay_entity(commands: &mut Commands,
+	                             kwriter:  &mut EventWriter<EntityKilled>,
+	                             entity:   Entity,
+	                             posns:    Vec<Position>,
+	                             policy:   DespawnPolicy,
+) {
+	commands.entity(entity).despawn();
+	kwriter.send(EntityKilled { entity, posns, policy });
+}
+/// Has the carrier drop everything that it's holding onto the given Position, by sending a DropItem for each item so
+/// that the item_collection_system can find each one a clear spot; returns the number of items that were dropped
+pub fn drop_everything(ewriter: &mut EventWriter<GameEvent>,
+	                     carrier: Entity,
+	                     posn:    Position,
+	                     i_query: &Query<(Entity, &Portable), Without<Vitals>>,
+) -> usize {
+	let mut count = 0;
+	for (i_enty, i_portable) in i_query.iter() {
+		if i_portable.carrier != carrier { continue; }
+		ewriter.send(GameEvent::new(ActorAction(ActionType::DropItem), Some(carrier), Some(i_enty)).at(posn));
+		count += 1;
+	}
+	count
+}
+/// Puts everything that a despawned carrier was holding onto the given Position
+/// The carrier is already gone by the time this runs, so there's nobody left to send a DropItem for; anything that's
+/// still around should use drop_everything() instead
+/// Returns the number of items that were dropped
+pub fn drop_all_carried(commands: &mut Commands,
+	                      carrier:  Entity,
+	                      posn:     Position,
+	                      i_query:  &mut Query<(Entity, &Portable, &mut Body), Without<Vitals>>,
+) -> usize {
+	let mut count = 0;
+	for (i_enty, i_portable, mut i_body) in i_query.iter_mut() {
+		if i_portable.carrier != carrier { continue; }
+		commands.entity(i_enty)
+		.insert(Portable{carrier: Entity::PLACEHOLDER})
+		.remove::<IsCarried>();
+		i_body.move_to(posn);
+		count += 1;
+	}
+	count
+}
+/// Queues up the contents of any LazyLoot container that was just opened, for the GameEngine to spawn
+pub fn lazy_loot_system(mut commands:  Commands,
+	                      mut creader:   EventReader<ContainerOpened>,
+	                      mut loot:      ResMut<LootRequests>,
+	                      l_query:       Query<&LazyLoot>,
+) {
+	for event in creader.iter() {
+		if let Ok(l_loot) = l_query.get(event.container) {
+			for item in l_loot.items.iter() {
+				loot.pending.push((item.clone(), event.container));
+			}
+			// The loot only gets rolled the first time, after that it's just a normal container
+			commands.entity(event.container).remove::<LazyLoot>();
+		}
+	}
+}
+/// Works out which tiles are lit, from each deck's ambient light plus every LightSource that's switched on
+pub fn lighting_system(mut model:   ResMut<WorldModel>,
+	                     prefs:       Res<Preferences>,
+	                     grid:        Res<PowerGrid>,
+	                     l_query:     Query<(&Body, &LightSource)>,
+	                     mut s_query: Query<&mut Viewshed>,
+) {
+	let old_lighting: Vec<Vec<bool>> = model.levels.iter().map(|x| x.lit_tiles.clone()).collect();
+	for level in model.levels.iter_mut() {
+		level.lit_tiles = vec![level.ambient_light; level.tiles.len()];
+	}
+	// The zones that have lost their mains power go dark, though anything with its own batteries can still light them
+	let dark_posns: Vec<Position> = model.layout.rooms.iter()
+		.filter(|x| !grid.is_powered(&x.name))
+		.flat_map(|x| x.new_interior.keys().copied())
+		.collect();
+	for posn in dark_posns {
+		if !model.contains(posn) { continue; }
+		let map = &mut model.levels[posn.z as usize];
+		let index = map.to_index(posn.x, posn.y);
+		map.lit_tiles[index] = false;
+	}
+	for (l_body, l_light) in l_query.iter() {
+		if !l_light.is_on || !model.contains(l_body.ref_posn) { continue; }
+		let map = &mut model.levels[l_body.ref_posn.z as usize];
+		// Light doesn't go through walls any more than sight does, so it gets the same FOV treatment
+		for point in visible_points(prefs.fov_algorithm, posn_to_point(&l_body.ref_posn), l_light.radius, map) {
+			let index = map.to_index(point.x, point.y);
+			map.lit_tiles[index] = true;
+		}
+	}
+	// If the lighting changed, then everyone's view of the world has to be redone
+	if model.levels.iter().zip(old_lighting.iter()).any(|(level, old)| level.lit_tiles != *old) {
+		for mut s_viewshed in s_query.iter_mut() {
+			s_viewshed.dirty = true;
+		}
+	}
+}
 /// Handles ActorLock/Unlock events
 pub fn lockable_system(mut _commands:    Commands,
 	                     mut ereader:      EventReader<GameEvent>,
 	                     mut msglog:       ResMut<MessageLog>,
+	                     mut swriter:      EventWriter<ShipLogEvent>,
 	                     mut lock_query:   Query<(Entity, &Body, &Description, &mut Lockable)>,
 	                     mut e_query:      Query<(Entity, &Body, &Description, Option<&Player>)>,
-	                     key_query:        Query<(Entity, &Portable, &Description, &Key), With<IsCarried>>,
+	                     key_query:        Query<(&Portable, &Key), With<IsCarried>>,
+	                     p_view_query:     Query<&Viewshed, With<Player>>,
+	                     p_posn:           Res<Position>,
+	                     policy:           Res<PerceptionPolicy>,
+	                     mut q_query:      Query<(&Portable, &mut Device, &mut RngComponent), With<Planq>>,
+	                     planq:            Res<PlanqData>,
 ) {
 	// Bail out if there's no events or the wrong type
 	if ereader.is_empty() { return; }
-	for event in ereader.iter() {
+	for event in resolve_conflicts(ereader.iter(), &[LockItem, UnlockItem, HackItem]).iter() {
 		let mut atype = ActionType::NoAction;
 		if let PlayerAction(action) | ActorAction(action) = event.etype {
-			if action != LockItem && action != UnlockItem {
+			if action != LockItem && action != UnlockItem && action != HackItem {
 				continue;
 			} else {
 				atype = action;
@@ -250,8 +773,9 @@ pub fn lockable_system(mut _commands:    Commands,
 		}
 		if event.context.is_none() { continue; }
 		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
-		let (e_enty, _body, e_desc, e_player) = e_query.get_mut(econtext.subject).expect("econtext.subject should be found in e_query");
+		let (e_enty, e_body, e_desc, e_player) = e_query.get_mut(econtext.subject).expect("econtext.subject should be found in e_query");
 		let player_action = e_player.is_some();
+		let is_perceived = player_action || policy.can_perceive(*p_posn, p_view_query.get_single().ok(), e_body.ref_posn);
 		let (_enty, _portable, l_desc, mut l_lock) = lock_query.get_mut(econtext.object).expect("econtext.object should be found in lock_query");
 		let mut message: String = "".to_string();
 		// If they have the right key then they can unlock it
@@ -261,35 +785,57 @@ pub fn lockable_system(mut _commands:    Commands,
 				l_lock.is_locked = true;
 				if player_action {
 					message = format!("You tap the LOCK button on the {}.", l_desc.name.clone());
-				} else {
+				} else if is_perceived {
 					message = format!("The {} locks the {}.", e_desc.name.clone(), l_desc.name.clone());
 				}
 			}
 			ActionType::UnlockItem => {
-				// Obtain the set of keys that the actor is carrying
-				let mut carried_keys: Vec<(Entity, i32, String)> = Vec::new();
-				for (k_enty, k_portable, k_desc, k_key) in key_query.iter() {
-					if k_portable.carrier == e_enty { carried_keys.push((k_enty, k_key.key_id, k_desc.name.clone())); }
-				}
-				if carried_keys.is_empty() { continue; } // no keys to try!
-				// The actor has at least one key to try in the lock
-				for (_enty, try_key_id, try_key_name) in carried_keys.iter() {
-					if *try_key_id == l_lock.key_id {
-						// the subject has the right key, unlock the lock
+				let world = SecurityQuery {
+					key_query: &key_query,
+					cnxn: if player_action { Some((e_enty, planq.jack_cnxn)) } else { None },
+				};
+				match authorize(&world, e_enty, &l_lock, &AccessMethod::Key) {
+					Ok(()) => {
 						l_lock.is_locked = false;
 						if player_action {
-							message = format!("Your {} unlocks the {}.", try_key_name, l_desc.name.clone());
-						} else {
+							message = format!("You unlock the {}.", l_desc.name.clone());
+						} else if is_perceived {
 							message = format!("The {} unlocks the {}.", e_desc.name.clone(), l_desc.name.clone());
 						}
-					} else {
-						// none of the keys worked, report a failure
+					}
+					Err(reason) => {
 						if player_action {
-							message = "You don't seem to have the right key.".to_string();
+							message = reason.to_string();
 						}
 					}
 				}
 			}
+			ActionType::HackItem => {
+				// Hacking is done with the PLANQ, so the actor needs to be carrying one
+				let (_, mut q_device, mut q_rng) = if let Some(value) = q_query.iter_mut().find(|(q_portable, _, _)| q_portable.carrier == e_enty) {
+					value
+				} else {
+					if player_action { msglog.tell_player("You'll need your PLANQ to try that."); }
+					continue;
+				};
+				if !l_lock.is_locked {
+					if player_action { message = format!("The {} isn't locked.", l_desc.name.clone()); }
+				} else if skill_check(q_rng.as_mut(), hack_chance(l_lock.difficulty, 0)) {
+					l_lock.is_locked = false;
+					swriter.send(ShipLogEvent::new(&format!("SECURITY: {} lock bypassed", l_desc.name)));
+					if player_action {
+						message = format!("Your PLANQ chirps as the {} unlocks.", l_desc.name.clone());
+					} else if is_perceived {
+						message = format!("The {} fiddles with the {}, and it unlocks.", e_desc.name.clone(), l_desc.name.clone());
+					}
+				} else {
+					// A failed attempt still costs the PLANQ some of its battery
+					q_device.discharge(HACK_FAIL_DRAIN);
+					if player_action {
+						message = format!("The {} resists your PLANQ's intrusion attempt.", l_desc.name.clone());
+					}
+				}
+			}
 			_ => { }
 		}
 		if !message.is_empty() {
@@ -297,6 +843,28 @@ pub fn lockable_system(mut _commands:    Commands,
 		}
 	}
 }
+/// Gives a SpawnIndex to every entity with a Body that doesn't have one yet
+/// Everything that turned up since the last update is numbered in order of where it is, then which template it was
+/// made from, then when the ItemBuilder made it, and never by its Entity ID; that way the same seed always produces the
+/// same numbering no matter which order Bevy happened to run the spawning systems in, or which IDs it handed out
+/// Anything that didn't come from the ItemBuilder goes by its name instead, after everything that did
+pub fn spawn_index_system(mut commands: Commands,
+	                        mut order:    ResMut<SpawnOrder>,
+	                        n_query:      Query<(Entity, &Body, Option<&Description>, Option<&SpawnTicket>), Without<SpawnIndex>>,
+) {
+	if n_query.is_empty() { return; }
+	let mut newcomers: Vec<(Position, String, u64, Entity)> = n_query.iter()
+		.map(|(n_enty, n_body, n_desc, n_ticket)| match n_ticket {
+			Some(ticket) => { (n_body.ref_posn, ticket.template.clone(), ticket.serial, n_enty) }
+			None => { (n_body.ref_posn, n_desc.map_or(String::new(), |x| x.name.clone()), u64::MAX, n_enty) }
+		})
+		.collect();
+	newcomers.sort_by(|a, b| (a.0, &a.1, a.2).cmp(&(b.0, &b.1, b.2)));
+	for (_, _, _, n_enty) in newcomers {
+		commands.entity(n_enty).insert(SpawnIndex { index: order.next }).remove::<SpawnTicket>();
+		order.next += 1;
+	}
+}
 /// Handles updates to the 'meta' worldmaps, ie the blocked and opaque tilemaps
 pub fn map_indexing_system(mut model:         ResMut<WorldModel>,
 	                         blocker_query: Query<&Body, With<Obstructive>>,
@@ -324,7 +892,14 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 	                     mut msglog:      ResMut<MessageLog>,
 	                     mut p_posn_res:  ResMut<Position>,
 	                     mut model:       ResMut<WorldModel>,
-	                     mut e_query:     Query<(Entity, &mut Description, &mut Body, Option<&mut Viewshed>, Option<&Player>)>
+	                     run_state:       Res<RunState>,
+	                     prefs:           Res<Preferences>,
+	                     mut nwriter:     EventWriter<Noise>,
+	                     mut mwriter:     EventWriter<PlayerMoved>,
+	                     mut fwriter:     EventWriter<ActionFailed>,
+	                     mut e_query:     Query<(Entity, &mut Description, &mut Body, Option<&mut Viewshed>, Option<&Player>, Option<&mut MoveStyle>, Option<&CarryCapacity>)>,
+	                     w_query:         Query<(&Portable, Option<&Weight>), With<IsCarried>>,
+	                     o_query:         Query<(Option<&Openable>, Option<&Mobile>)>,
 ) {
 	if ereader.is_empty() { return; } // Don't even bother trying if there's no events to worry about
 	for event in ereader.iter() {
@@ -338,7 +913,7 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 				}
 				let econtext = event.context.expect("event.context should be Some(n)");
 				let origin = e_query.get_mut(econtext.subject);
-				let (actor_enty, mut actor_desc, mut actor_body, actor_viewshed, _) = origin.expect("econtext.subject should be in e_query");
+				let (actor_enty, mut actor_desc, mut actor_body, actor_viewshed, _, actor_style, actor_capacity) = origin.expect("econtext.subject should be in e_query");
 				// TODO: this is now overkill, just use the match case to make an implicit PosnOffset applied to the old position
 				let mut xdiff = 0;
 				let mut ydiff = 0;
@@ -357,19 +932,36 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 					Direction::DOWN =>      { zdiff -= 1 }
 				}
 				let mut new_location = Position::new(actor_body.ref_posn.x + xdiff, actor_body.ref_posn.y + ydiff, actor_body.ref_posn.z + zdiff);
+				// A careful step takes more than one turn, so the actor can't take another until it's done
+				if actor_style.as_ref().map_or(false, |x| x.is_stepping(run_state.turn_count)) {
+					if is_player_action {
+						msglog.tell_player("You're still picking your way forward.");
+					} else {
+						fwriter.send(ActionFailed { actor: actor_enty, action: atype, transient: true });
+					}
+					continue;
+				}
+				// Any move that doesn't go through gets reported back to the AI, so that it can re-plan instead of retrying
+				let mut refuse = |msglog: &mut MessageLog, reason: &str| {
+					if is_player_action {
+						msglog.tell_player(reason);
+					} else {
+						fwriter.send(ActionFailed { actor: actor_enty, action: atype, transient: false });
+					}
+				};
 				// If the actor is moving between z-levels, we have some extra logic to handle
 				if dir == Direction::UP || dir == Direction::DOWN { // Is the actor moving between z-levels?
 					// Prevent movement if an invalid z-level was calculated, or if they are not standing on stairs
 					//debug!("* Attempting ladder traverse to target posn {}", new_location);
 					// CASE 1: The target location is beyond the Model's height
 					if new_location.z < 0 || new_location.z as usize >= model.levels.len() {
-						msglog.tell_player(format!("You're already on the {}-most deck.", dir).as_str());
+						refuse(&mut msglog, format!("You're already on the {}-most deck.", dir).as_str());
 						continue;
 					}
 					// CASE 2: The actor is not standing on a ladder Tile
 					let actor_index = model.levels[actor_body.ref_posn.z as usize].to_index(actor_body.ref_posn.x, actor_body.ref_posn.y);
-					if model.levels[actor_body.ref_posn.z as usize].tiles[actor_index].ttype != TileType::Stairway {
-						msglog.tell_player(format!("You can't go {} without a ladder.", dir).as_str());
+					if !model.levels[actor_body.ref_posn.z as usize].tiles[actor_index].ttype.properties().is_portal {
+						refuse(&mut msglog, format!("You can't go {} without a ladder.", dir).as_str());
 						continue;
 					}
 					// CASE 3: Attempt to retrieve a Portal (aka ladder) from the list for this Position
@@ -377,26 +969,55 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 					if let Some(portal) = possible {
 						new_location = portal;
 					} else {
-						msglog.tell_player("Couldn't find a ladder to traverse (possible bug?)");
+						refuse(&mut msglog, "Couldn't find a ladder to traverse (possible bug?)");
 						continue;
 					}
 					// CASE 4: The actor is trying to climb higher than the ladder allows
 					if dir == Direction::UP && (actor_body.ref_posn.z > new_location.z) {
-						msglog.tell_player("You're already at the top of the ladder.");
+						refuse(&mut msglog, "You're already at the top of the ladder.");
 						continue;
 					}
 					// CASE 5: The actor is trying to climb lower than the ladder allows
 					if dir == Direction::DOWN && (actor_body.ref_posn.z < new_location.z) {
-						msglog.tell_player("You're already at the bottom of the ladder.");
+						refuse(&mut msglog, "You're already at the bottom of the ladder.");
 						continue;
 					}
 				}
-				let _locn_index = model.levels[new_location.z as usize].to_index(new_location.x, new_location.y);
 				// Get a picture of where the actor wants to move to so we can check it for collisions
-				let target_extent = actor_body.project_to(new_location);
+				let mut target_extent = actor_body.project_to(new_location);
+				// A multi-tile Body moving diagonally has to swing through both of the orthogonal steps on its way,
+				// so check those as well to keep it from cutting across a corner
+				if actor_body.extent.len() > 1 && xdiff != 0 && ydiff != 0 {
+					target_extent.append(&mut actor_body.project_to(actor_body.ref_posn + (xdiff, 0, 0)));
+					target_extent.append(&mut actor_body.project_to(actor_body.ref_posn + (0, ydiff, 0)));
+				}
+				// The actor's own tiles can't block it from moving
+				let current_extent = actor_body.posns();
+				target_extent.retain(|x| !current_extent.contains(x));
+				target_extent.sort();
+				target_extent.dedup();
 				//debug!("* target_extent: {:?}", target_extent);
+				// Don't let anyone wander off the edge of the map
+				if target_extent.iter().any(|x| !model.contains(*x)) {
+					error!("* ! movement_system: {:?} tried to move off the map to {}", actor_enty, new_location); // DEBUG: report an off-map move
+					refuse(&mut msglog, "You can't go that way.");
+					continue;
+				}
 				if let Some(mut blocked_tiles) = model.get_obstructions_at(target_extent, Some(actor_enty)) {
 					blocked_tiles.retain(|x| x.1 != Obstructor::Actor(actor_enty));
+					// Anyone else gets told why, so that their AI can decide whether to wait it out or find another way:
+					// another actor might step aside, and a door that was opened this turn stays blocked until the
+					// command that takes away its Obstructive has been applied
+					if !is_player_action {
+						let transient = blocked_tiles.iter().all(|x| match x.1 {
+							Obstructor::Actor(enty) => {
+								o_query.get(enty).map_or(false, |(b_open, b_mobile)| b_mobile.is_some() || b_open.map_or(false, |y| y.is_open))
+							}
+							Obstructor::Object(_) => { false }
+						});
+						fwriter.send(ActionFailed { actor: actor_enty, action: atype, transient });
+						continue;
+					}
 					// We have a list of positions that are definitely blocked, but we don't know why
 					// Get the first one off the list, find out why it's blocked, and report it
 					//debug!("blocked tiles: {:?}, {:?}", dir, blocked_tiles);
@@ -411,7 +1032,7 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 							format!("a {}", ttype)
 						}
 					};
-					msglog.tell_player(format!("The way {} is blocked by {}", dir, reply_msg).as_str());
+					msglog.tell_player(format!("The way {} is blocked by {}", dir.named(prefs.dir_names).to_string().to_lowercase(), reply_msg).as_str());
 					return;
 				}
 				// -> POINT OF NO RETURN
@@ -424,6 +1045,16 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 				if let Some(mut viewshed) = actor_viewshed {
 					viewshed.dirty = true;
 				}
+				// Footsteps get louder the more the actor is lugging around, unless they're being careful about it
+				if let Some(mut style) = actor_style {
+					let load = w_query.iter().filter(|(w_portable, _)| w_portable.carrier == actor_enty)
+						.map(|(_, w_weight)| w_weight.copied().unwrap_or_default().mass).sum();
+					let load_ratio = actor_capacity.map_or(0.0, |x| x.load_ratio(load));
+					style.last_volume = style.volume(load_ratio);
+					nwriter.send(Noise { source: new_location, volume: style.last_volume, maker: actor_enty });
+					// A step that takes longer than a turn holds the actor up for the turns after the one it was taken on
+					style.next_step = if style.time_cost() > 1 { run_state.turn_count + style.time_cost() } else { 0 };
+				}
 				// If the entity changed rooms, update their description to reflect that
 				if let Some(new_name) = model.layout.get_room_name(new_location) {
 					if new_name != actor_desc.locn {
@@ -433,6 +1064,7 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 				// If it was the player specifically moving around, we need to do a few more things
 				if is_player_action {
 					*p_posn_res = new_location; // Update the system-wide resource containing the player's location
+					mwriter.send(PlayerMoved { posn: new_location });
 					// Is there anything on the ground at the new location?
 					// If so, tell the player about it, but don't mention the player entity itself
 					let mut contents_list = model.get_contents_at(new_location);
@@ -476,12 +1108,18 @@ pub fn movement_system(mut ereader:     EventReader<GameEvent>,
 pub fn openable_system(mut commands:    Commands,
 	                     mut ereader:     EventReader<GameEvent>,
 	                     mut msglog:      ResMut<MessageLog>,
-	                     mut door_query:  Query<(Entity, &mut Body, &Description, &mut Openable, Option<&mut Opaque>, Option<&Obstructive>)>,
+	                     mut cwriter:     EventWriter<ContainerOpened>,
+	                     mut door_query:  Query<(Entity, &mut Body, &Description, &mut Openable, Option<&mut Opaque>, Option<&Obstructive>, Option<&Container>, Option<&Lockable>)>,
 	                     mut e_query:     Query<(Entity, &Body, &Description, Option<&Player>, Option<&mut Viewshed>), Without<Openable>>,
+	                     mut model:       ResMut<WorldModel>,
+	                     p_posn:          Res<Position>,
+	                     policy:          Res<PerceptionPolicy>,
 ) {
 	// Bail out if no events or wrong type
 	if ereader.is_empty() { return; }
-	for event in ereader.iter() {
+	// The player's view is also in the e_query, so it has to be copied out before the actors are borrowed below
+	let p_view = e_query.iter().find(|x| x.3.is_some()).and_then(|x| x.4.cloned());
+	for event in resolve_conflicts(ereader.iter(), &[OpenItem, CloseItem]).iter() {
 		let mut atype = ActionType::NoAction;
 		if let PlayerAction(action) | ActorAction(action) = event.etype {
 			if action != OpenItem && action != CloseItem {
@@ -493,16 +1131,34 @@ pub fn openable_system(mut commands:    Commands,
 		if event.context.is_none() { continue; }
 		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
 		// If they can see it, add it to the list of doors they can choose
-		let (_enty, _body, a_desc, a_player, a_viewshed) = e_query.get_mut(econtext.subject).expect("actor should be listed in e_query");
+		let (_enty, a_body, a_desc, a_player, a_viewshed) = e_query.get_mut(econtext.subject).expect("actor should be listed in e_query");
 		let is_player_action = a_player.is_some();
+		let is_perceived = is_player_action || policy.can_perceive(*p_posn, p_view.as_ref(), a_body.ref_posn);
 		let mut message: String = "".to_string();
 		match atype {
 			ActionType::OpenItem => {
 				//debug!("Trying to open a door"); // DEBUG: announce opening a door
 				let mut door_name = "".to_string();
-				for (d_enty, mut d_body, d_desc, mut d_open, d_opaque, _obstruct) in door_query.iter_mut() {
+				let mut refusal = None;
+				for (d_enty, mut d_body, d_desc, mut d_open, d_opaque, _obstruct, d_container, d_lock) in door_query.iter_mut() {
 					if d_enty == econtext.object {
+						// Nothing gets opened while it's still locked: that takes an Unlock action first
+						if let Err(reason) = check_open(d_lock) {
+							door_name = d_desc.name.clone();
+							refusal = Some(reason);
+							break;
+						}
+						// Let anything that's waiting on this container know that it's been opened
+						if d_container.is_some() && !d_open.is_open {
+							cwriter.send(ContainerOpened { container: d_enty, opener: econtext.subject });
+						}
 						d_open.is_open = true;
+						// Sliding doors get tucked away into their wall pocket
+						if let Some(pocket) = d_open.slide_into {
+							model.remove_contents(&d_body.posns(), d_enty);
+							d_body.move_to(pocket);
+							model.add_contents(&d_body.posns(), 0, d_enty);
+						}
 						let ref_posn = d_body.ref_posn; // Get the map posn of the openable
 						d_body.set_glyph_at(ref_posn, &d_open.open_glyph); // Change the openable's glyph to the open state
 						door_name = d_desc.name.clone();
@@ -512,9 +1168,13 @@ pub fn openable_system(mut commands:    Commands,
 						commands.entity(d_enty).remove::<Obstructive>(); // Things that are open are not obstructive
 					}
 				}
-				if is_player_action {
+				if let Some(reason) = refusal {
+					if is_player_action {
+						message = format!("You try to open the {}. {}", door_name, reason);
+					}
+				} else if is_player_action {
 					message = format!("You open the {}.", door_name);
-				} else {
+				} else if is_perceived {
 					message = format!("The {} opens a {}.", a_desc.name.clone(), door_name);
 				}
 				if let Some(mut view) = a_viewshed { view.dirty = true; } // Force a view update ASAP
@@ -522,8 +1182,20 @@ pub fn openable_system(mut commands:    Commands,
 			ActionType::CloseItem => {
 				//debug!("Trying to close a door"); // DEBUG: announce closing door
 				let mut door_name = "".to_string();
-				for (d_enty, mut d_body, d_desc, mut d_open, d_opaque, _obstruct) in door_query.iter_mut() {
+				let mut is_jammed = false;
+				for (d_enty, mut d_body, d_desc, mut d_open, d_opaque, _obstruct, _container, _lock) in door_query.iter_mut() {
 					if d_enty == econtext.object {
+						// A sliding door can't close on anything that's sitting in its doorway
+						if d_open.slide_into.is_some() {
+							if !model.get_contents_at(d_open.home).is_empty() {
+								door_name = d_desc.name.clone();
+								is_jammed = true;
+								break;
+							}
+							model.remove_contents(&d_body.posns(), d_enty);
+							d_body.move_to(d_open.home);
+							model.add_contents(&d_body.posns(), 0, d_enty);
+						}
 						d_open.is_open = false;
 						let ref_posn = d_body.ref_posn;
 						d_body.set_glyph_at(ref_posn, &d_open.closed_glyph); // Set the openable's glyph to the closed state
@@ -534,9 +1206,13 @@ pub fn openable_system(mut commands:    Commands,
 						commands.entity(d_enty).insert(Obstructive {}); // Closed things cannot be moved through
 					}
 				}
-				if is_player_action {
+				if is_jammed {
+					if is_player_action {
+						message = format!("Something in the doorway is stopping the {} from closing.", door_name);
+					}
+				} else if is_player_action {
 					message = format!("You close the {}.", door_name);
-				} else {
+				} else if is_perceived {
 					message = format!("The {} closes a {}.", a_desc.name.clone(), door_name);
 				}
 				if let Some(mut view) = a_viewshed { view.dirty = true; }
@@ -550,8 +1226,11 @@ pub fn openable_system(mut commands:    Commands,
 }
 /// Handles anything related to the CanOperate component: ActorUse, ToggleSwitch, &c
 pub fn operable_system(mut ereader: EventReader<GameEvent>,
+                       mut pwriter: EventWriter<DevicePowerChanged>,
+                       mut swriter: EventWriter<ShipLogEvent>,
+                       mut msglog:  ResMut<MessageLog>,
                        //mut o_query: Query<(Entity, &Position, &Name), With<CanOperate>>,
-                       mut d_query: Query<(Entity, &Description, &mut Device)>,
+                       mut d_query: Query<(Entity, &Description, &mut Device, Option<&Planq>, Option<&Airlock>)>,
 ) {
 	if ereader.is_empty() { return; }
 	for event in ereader.iter() {
@@ -563,28 +1242,145 @@ pub fn operable_system(mut ereader: EventReader<GameEvent>,
 		let econtext = event.context.as_ref().expect("event.context should be Some(n)");
 		if econtext.is_blank() { continue; }
 		let mut device = d_query.get_mut(econtext.object).expect("econtext.object should be in d_query");
+		let player_action = matches!(event.etype, PlayerAction(_));
+		// A broken device won't do anything until it's been repaired
+		if let DeviceState::Error(code) = device.2.state {
+			if player_action { msglog.tell_player(format!("The {} blinks an error code at you: {}.", device.1.name, code).as_str()); }
+			continue;
+		}
+		// An airlock's panel runs off the mains and only ever starts a cycle, which the airlock_system sees to
+		if device.4.is_some() { continue; }
 		if !device.2.pw_switch { // If it's not powered on, assume that function first
 			device.2.power_toggle();
+			pwriter.send(DevicePowerChanged { device: device.0, is_on: device.2.pw_switch });
+		}
+		// The PLANQ is the player's lifeline and nothing aboard can fix it, so it doesn't wear out from use
+		if device.3.is_none() && device.2.wear_down(USE_WEAR) {
+			swriter.send(ShipLogEvent::new(&format!("{}: ERROR {}", device.1.name, Device::WORN_OUT)));
+			if player_action { msglog.tell_player(format!("The {} sputters and goes dead in your hands.", device.1.name).as_str()); }
 		}
 		// TODO: there's definitely going to be more stuff to implement here depending on the actual Device
 	}
 }
+/// Wears down any Device that's been left lying out in a hazard; anything that's being carried is sheltered from it
+/// The wear is counted in the player's actions rather than in ticks, so that standing around doesn't cost anything
+pub fn device_wear_system(mut ereader: EventReader<GameEvent>,
+	                        mut swriter: EventWriter<ShipLogEvent>,
+	                        model:       Res<WorldModel>,
+	                        run_state:   Res<RunState>,
+	                        mut d_query: Query<(&Body, &Description, &mut Device, Option<&Portable>)>,
+) {
+	let player_acted = ereader.iter().any(|x| matches!(x.etype, PlayerAction(_)));
+	if run_state.mode != EngineMode::Running || !player_acted { return; }
+	for (d_body, d_desc, mut d_device, d_portable) in d_query.iter_mut() {
+		if d_portable.map_or(false, |x| x.carrier != Entity::PLACEHOLDER) { continue; }
+		let posn = d_body.ref_posn;
+		if model.contains(posn) && model.get_tiletype_at(posn).properties().hazard == Hazard::Vacuum
+		&& d_device.wear_down(HAZARD_WEAR) {
+			swriter.send(ShipLogEvent::new(&format!("{}: ERROR {}", d_desc.name, Device::WORN_OUT)));
+		}
+	}
+}
+/// Watches the player's movements for the moments that deserve some atmosphere, ie arriving on another deck or
+/// stepping out into vacuum, and sends an AmbientCue for each of them
+pub fn ambient_cue_system(mut preader:  EventReader<PlayerMoved>,
+	                        mut cwriter:  EventWriter<AmbientCue>,
+	                        model:        Res<WorldModel>,
+	                        mut last_posn: Local<Option<Position>>,
+) {
+	let in_vacuum = |x: Position| model.contains(x) && model.get_tiletype_at(x).properties().hazard == Hazard::Vacuum;
+	for event in preader.iter() {
+		let posn = event.posn;
+		if !model.contains(posn) { continue; }
+		if last_posn.map_or(true, |x| x.z != posn.z) {
+			cwriter.send(AmbientCue { cue: CueKind::EnterDeck(model.levels[posn.z as usize].environment) });
+		}
+		if in_vacuum(posn) && !last_posn.map_or(false, in_vacuum) {
+			cwriter.send(AmbientCue { cue: CueKind::HullBreach });
+		}
+		*last_posn = Some(posn);
+	}
+}
+/// Redoes the player's deck indicator whenever they move, for the HUD and the PLANQ's status bar to show
+pub fn deck_indicator_system(mut preader:   EventReader<PlayerMoved>,
+	                           model:         Res<WorldModel>,
+	                           mut indicator: ResMut<DeckIndicator>,
+) {
+	let moved = if let Some(value) = preader.iter().last() { value } else { return };
+	indicator.text = model.depth_indicator(moved.posn);
+}
+/// Turns the AmbientCues into atmospheric lines in the message log, until there's an audio backend to play them
+pub fn ambience_system(mut creader: EventReader<AmbientCue>,
+	                     mut msglog:  ResMut<MessageLog>,
+) {
+	for event in creader.iter() {
+		let message = format!("[[fg:gray]]{}[[end]]", event.cue.line());
+		// Hopping up and down a ladder shouldn't repeat the same line over and over
+		if msglog.last_text("world") != Some(message.as_str()) {
+			msglog.tell_player(&message);
+		}
+	}
+}
+/// Tells the player about any actor that has just come into view, if the player's Preferences allow it
+pub fn sighting_system(mut msglog:  ResMut<MessageLog>,
+	                     prefs:       Res<Preferences>,
+	                     mut in_view: Local<HashSet<Entity>>,
+	                     p_query:     Query<(&Body, &Viewshed), With<Player>>,
+	                     e_query:     Query<(Entity, &Body, &Description), (With<Mobile>, Without<Player>)>,
+) {
+	let (p_body, p_view) = if let Ok(value) = p_query.get_single() { value } else { return };
+	let p_posn = p_body.ref_posn;
+	let mut now_in_view = HashSet::new();
+	for (e_enty, e_body, e_desc) in e_query.iter() {
+		let is_seen = e_body.posns().iter().any(|x| x.z == p_posn.z && p_view.visible_points.contains(&posn_to_point(x)));
+		if !is_seen { continue; }
+		now_in_view.insert(e_enty);
+		if in_view.contains(&e_enty) || !prefs.announce_sightings { continue; }
+		let message = format!("You see a {} {}.", e_desc.name, relative_phrase(p_posn, e_body.ref_posn, prefs.dir_names));
+		// Anything that pops in and out of view at the edge would otherwise repeat itself over and over
+		if msglog.last_text("world") != Some(message.as_str()) {
+			msglog.tell_player(&message);
+		}
+	}
+	*in_view = now_in_view;
+}
+/// Marks every Viewshed as dirty when a resync has been asked for (ie after a load), so that the visibility_system
+/// recalculates all of them on the same pass
+/// NOTE: this system must run before the visibility_system, or else the resync will wait a turn
+pub fn viewshed_resync_system(mut resync:  EventReader<ResyncViewsheds>,
+	                            mut v_query: Query<&mut Viewshed>,
+) {
+	if resync.is_empty() { return; }
+	resync.clear();
+	for mut viewshed in v_query.iter_mut() {
+		viewshed.dirty = true;
+	}
+}
 /// Handles entities that can see physical light
+/// NOTE: this system must run before the camera_update_system, or else the camera will draw from stale Viewsheds
 pub fn visibility_system(mut model:  ResMut<WorldModel>,
+	                       prefs:      Res<Preferences>,
 	                       mut seers:  Query<(&mut Viewshed, &Body, Option<&Player>, Option<&mut Memory>), Changed<Viewshed>>,
 	                       //observable: Query<(Entity, &Body)>,
 ) {
 	for (mut s_viewshed, s_body, player, s_memory) in &mut seers {
 		if s_viewshed.dirty {
-			assert!(s_body.ref_posn.z != -1, "! ERROR: Encountered negative z-level index!");
+			// If the seer somehow ended up off the map, then they can't see anything, but that's no reason to crash
+			if !model.contains(s_body.ref_posn) {
+				error!("! visibility_system: seer is outside the map at {}, skipping FOV", s_body.ref_posn); // DEBUG: report an off-map seer
+				s_viewshed.visible_points.clear();
+				s_viewshed.dirty = false;
+				continue;
+			}
 			let map = &mut model.levels[s_body.ref_posn.z as usize];
 			s_viewshed.visible_points.clear();
 			// An interesting thought: should an Entity be able to 'see' from every part of its body?
 			// Right now it is calculated just from the Entity's reference point, the 'head'
-			s_viewshed.visible_points = field_of_view(posn_to_point(&s_body.ref_posn), s_viewshed.range, map);
-			s_viewshed.visible_points.retain(|p| p.x >= 0 && p.x < map.width as i32
-				                             && p.y >= 0 && p.y < map.height as i32
-			);
+			// The symmetric algorithm makes sure that nobody can see someone without being seen back
+			s_viewshed.visible_points = visible_points(prefs.fov_algorithm, posn_to_point(&s_body.ref_posn), s_viewshed.range, map);
+			// Only the lit tiles can actually be seen, although the seer always knows where they're standing
+			let seer_point = posn_to_point(&s_body.ref_posn);
+			s_viewshed.visible_points.retain(|p| *p == seer_point || map.lit_tiles[map.to_index(p.x, p.y)]);
 			if let Some(_player) = player { // if this is the player...
 				for s_posn in &s_viewshed.visible_points { // For all the player's visible tiles...
 					// ... set the corresponding tile in the map.revealed_tiles to TRUE
@@ -615,14 +1411,20 @@ pub fn visibility_system(mut model:  ResMut<WorldModel>,
 /// Adds a new player entity to a new game world
 pub fn new_player_spawn(mut commands: Commands,
 	                      spawnpoint:   Res<Position>,
+	                      run_state:    Res<RunState>,
+	                      mut mwriter:  EventWriter<PlayerMoved>,
+	                      mut iwriter:  EventWriter<InventoryChanged>,
 	                      mut model:    ResMut<WorldModel>,
 	                      mut p_query:  Query<(Entity, &Player)>,
 	                      mut msglog:   ResMut<MessageLog>,
+	                      mut comms:    ResMut<PlanqComms>,
 	                      mut global_rng: ResMut<GlobalRng>,
 ) {
 	if !p_query.is_empty() {
 		info!("* Existing player found, treating as a loaded game"); // DEBUG: announce possible game load
-		let player = p_query.get_single_mut().expect("A loaded game should have a valid player object already");
+		// A save with more than one player is broken, but the engine will report that itself once it's running
+		if p_query.iter().count() > 1 { error!("! new_player_spawn: the loaded game has more than one player"); } // DEBUG: report the extra players
+		let player = p_query.iter_mut().next().expect("A loaded game should have a valid player object already");
 		commands.entity(player.0).insert(Viewshed::new(8));
 		return;
 	}
@@ -638,50 +1440,79 @@ pub fn new_player_spawn(mut commands: Commands,
 	let player = commands.spawn((
 		Player { },
 		ActionSet::new(),
-		Description::new().name("Pleyeur").desc("Still your old self."),
+		Description::new().name(&run_state.player_name).desc("Still your old self."),
 		*spawnpoint,
-		Body::small(*spawnpoint, ScreenCell::new().glyph("@").fg(Color::LtBlue).bg(Color::Black)),
+		Body::small(*spawnpoint, ScreenCell::new().glyph("@").fg(Color::LtBlue).bg(Color::Black).bold()),
 		Viewshed::new(8),
 		Mobile::default(),
 		Obstructive::default(),
-		Container::default(),
+		Container::with_slots(PLAYER_SLOTS),
 		Memory::new(),
+		CarryCapacity::new(20),
+		MoveStyle::default(),
+		Vitals::new(10),
 	)).id();
 	model.add_contents(&vec![*spawnpoint], 0, player);
+	mwriter.send(PlayerMoved { posn: *spawnpoint });
 	//debug!("* new_player_spawn spawned @{spawnpoint:?}"); // DEBUG: print spawn location of new player
-	let planq = commands.spawn((
-		Planq::new(),
-		Description::new().name("PLANQ").desc("It's your PLANQ."),
-		Body::small(*spawnpoint, ScreenCell::new().glyph("¶").fg(Color::Pink).bg(Color::Black)),
-		ActionSet::new(),
-		Portable::new(player),
-		Device::new(-1),
-		RngComponent::from(&mut global_rng),
-	)).id();
-	debug!("* new planq spawned into player inventory: {:?}", planq); // DEBUG: announce creation of player's planq
+	// The PLANQ starts out locked away as part of the intro; if the intro can't fit, just hand it to the player
+	if intro::setup(&mut commands, &mut model, &mut global_rng, *spawnpoint).is_none() {
+		warn!("* no room for the intro scenario at {}, giving the PLANQ to the player instead", *spawnpoint); // DEBUG: report a skipped intro
+		let planq = new_planq_spawn(&mut commands, &mut global_rng, player, *spawnpoint);
+		debug!("* new planq spawned into player inventory: {:?}", planq); // DEBUG: announce creation of player's planq
+		iwriter.send(InventoryChanged { carrier: player, item: planq });
+	}
 	commands.spawn(DataSampleTimer::new().source("player_location"));
+	commands.spawn(DataSampleTimer::new().source("deck_indicator"));
 	commands.spawn(DataSampleTimer::new().source("current_time"));
 	commands.spawn(DataSampleTimer::new().source("planq_battery"));
 	commands.spawn(DataSampleTimer::new().source("planq_mode"));
+	commands.spawn(DataSampleTimer::new().source("comms_unread"));
+	commands.spawn(DataSampleTimer::new().source("noise_meter"));
+	comms.send("Ship's Steward", "Welcome back aboard. Please report to the bridge at your earliest convenience.", 10);
 	msglog.tell_player("[[fg:green]]WELCOME[[end]] TO [[fg:blue,mod:+italic]]SPACEGAME[[end]]");
 }
+/// Spawns the player's PLANQ into the given carrier's inventory, and returns its Entity
+pub fn new_planq_spawn(commands: &mut Commands, global_rng: &mut GlobalRng, carrier: Entity, posn: Position) -> Entity {
+	commands.spawn((
+		Planq::new(),
+		Description::new().name("PLANQ").desc("It's your PLANQ."),
+		Body::small(posn, ScreenCell::new().glyph("¶").fg(Color::Pink).bg(Color::Black)),
+		ActionSet::new(),
+		Portable::new(carrier),
+		Device::new(-1),
+		RngComponent::from(global_rng),
+	)).id()
+}
 /// Spawns a new LMR at the specified Position, using default values
 pub fn new_lmr_spawn(mut commands:  Commands,
 	                   mut msglog:    ResMut<MessageLog>,
+	                   mut model:     ResMut<WorldModel>,
+	                   l_query:       Query<Entity, With<LMR>>,
 ) {
+	// A loaded game brings its own LMR along, with whatever job it was in the middle of
+	if !l_query.is_empty() { return; }
 	let lmr_spawnpoint = (12, 12, 0).into();
-	commands.spawn((
+	let lmr_body = Body::small(lmr_spawnpoint, ScreenCell::new().glyph("l").fg(Color::Cyan).bg(Color::Black));
+	let lmr_posns = lmr_body.posns();
+	let lmr_enty = commands.spawn((
 		LMR         { },
 		ActionSet::new(),
 		Description::new().name("LMR").desc("The Light Maintenance Robot is awaiting instructions."),
 		lmr_spawnpoint, // TODO: remove magic numbers
-		Body::small(lmr_spawnpoint, ScreenCell::new().glyph("l").fg(Color::Cyan).bg(Color::Black)),
+		lmr_body,
 		Viewshed::new(5),
 		Mobile::default(),
 		Obstructive::default(),
 		Container::default(),
 		Opaque::new(true),
-	));
+		Maintainer::default(),
+		NavCaps { breathes: false }, // Robots don't need air
+		Hears::default(),
+		Vitals::new(20),
+	)).id();
+	// Register every tile of the LMR's Body with the model, otherwise it can't be found until it moves
+	model.add_contents(&lmr_posns, 0, lmr_enty);
 	msglog.add(format!("LMR spawned at {}, {}, {}", 12, 12, 0).as_str(), "debug", 1, 1);
 }
 /// Adds a demo NPC to the game world
@@ -711,6 +1542,52 @@ pub fn test_npc_spawn(mut commands: Commands,
 }
 
 // ###: UTILITIES
+/// Provides the current ship time, ie the elapsed game time plus the clock's starting offset
+pub fn ship_clock(time: &Time) -> Duration {
+	let start_time_offset = Duration::new(2096, 789); // 12:34:56.789
+	time.elapsed() + start_time_offset
+}
+/// Finds the nearest tile to the preferred one where an item can be put down, searching outward from it
+/// The tile has to be walkable, and can't hold anything obstructive besides whoever is doing the dropping; it also has
+/// to be reachable on foot from the preferred tile without leaving the search area, so that an item never ends up on the
+/// far side of a wall or a closed door
+pub fn find_drop_spot(model: &WorldModel, preferred: Position, dropper: Entity, o_query: &Query<Entity, With<Obstructive>>) -> Option<Position> {
+	let is_walkable = |posn: Position| model.contains(posn) && model.get_tiletype_at(posn).properties().passable;
+	let is_clear = |posn: Position| {
+		is_walkable(posn) && !model.get_contents_at(posn).iter().any(|x| *x != dropper && o_query.contains(*x))
+	};
+	if !is_walkable(preferred) { return None; }
+	// Flood outward along the floor; the preferred tile itself may be taken up, but nothing past it can be
+	let mut reachable = vec![preferred];
+	let mut index = 0;
+	while index < reachable.len() {
+		let origin = reachable[index];
+		index += 1;
+		for (x_diff, y_diff) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+			let posn = origin + (x_diff, y_diff, 0);
+			let diff = posn.difference(&preferred);
+			if diff.0.abs().max(diff.1.abs()) > DROP_SEARCH_RADIUS || reachable.contains(&posn) || !is_clear(posn) { continue; }
+			reachable.push(posn);
+		}
+	}
+	reachable.into_iter()
+		.filter(|x| is_clear(*x))
+		.min_by_key(|x| { let diff = x.difference(&preferred); diff.0.abs().max(diff.1.abs()) })
+}
+/// Returns true if the target is the container itself, or is being carried by it at any depth, ie a box in a bag that
+/// the container is holding; the carrier_of closure gives the carrier of a thing, if it has one
+pub fn is_within(target: Entity, container: Entity, carrier_of: impl Fn(Entity) -> Option<Entity>) -> bool {
+	let mut current = target;
+	// A chain of carriers that runs on this long must have gone around in a circle somewhere
+	for _ in 0..64 {
+		if current == container { return true; }
+		match carrier_of(current) {
+			Some(carrier) if carrier != Entity::PLACEHOLDER => { current = carrier; }
+			_ => { return false; }
+		}
+	}
+	false
+}
 /// Converts my Position type into a bracket_pathfinding::Point
 pub fn posn_to_point(input: &Position) -> Point { Point { x: input.x, y: input.y } }
 /// If the Entity exists, will return an Iterator that contains info on all the Components that belong to that Entity
@@ -756,4 +1633,1254 @@ impl DurationFmtExt for Duration {
 	}
 }
 
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use bevy::app::{App, Update};
+	use bevy::ecs::event::Events;
+	use bevy::ecs::system::SystemState;
+	use bevy::ecs::world::World;
+	use super::*;
+	//  ##: Device wear
+	/// Sets up a headless App with just the systems that use and wear out Devices
+	fn device_app() -> App {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<DevicePowerChanged>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, operable_system);
+		app
+	}
+	/// Has the given actor use the given Device the given number of times, one update apiece
+	fn use_device(app: &mut App, actor: Entity, device: Entity, times: u32) {
+		for _ in 0..times {
+			app.world.send_event(GameEvent::new(ActorAction(UseItem), Some(actor), Some(device)));
+			app.update();
+		}
+	}
+	#[test]
+	fn repeated_use_wears_a_device_out() {
+		let mut app = device_app();
+		let actor = app.world.spawn_empty().id();
+		let device = app.world.spawn((Description::new().name("scanner"), Device::new(0))).id();
+		use_device(&mut app, actor, device, 5);
+		let wear = app.world.get::<Device>(device).unwrap().wear;
+		assert_eq!(wear, 5 * USE_WEAR);
+		use_device(&mut app, actor, device, Device::WEAR_LIMIT / USE_WEAR);
+		assert_eq!(app.world.get::<Device>(device).unwrap().state, DeviceState::Error(Device::WORN_OUT));
+	}
+	#[test]
+	fn the_planq_does_not_wear_out_from_use() {
+		let mut app = device_app();
+		let actor = app.world.spawn_empty().id();
+		let planq = app.world.spawn((Description::new().name("PLANQ"), Device::new(0), Planq::new())).id();
+		use_device(&mut app, actor, planq, Device::WEAR_LIMIT);
+		let device = app.world.get::<Device>(planq).unwrap();
+		assert_eq!(device.wear, 0);
+		assert!(!matches!(device.state, DeviceState::Error(_)));
+	}
+	#[test]
+	fn hazard_wear_only_builds_up_when_the_player_acts() {
+		let mut app = App::new();
+		let mut model = WorldModel::default();
+		let mut level = WorldMap::new(3, 3);
+		level.tiles[0] = Tile::new_vacuum();
+		model.levels.push(level);
+		let mut run_state = RunState::new();
+		run_state.mode = EngineMode::Running;
+		app.add_event::<GameEvent>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(model)
+		.insert_resource(run_state)
+		.add_systems(Update, device_wear_system);
+		let posn = Position::new(0, 0, 0);
+		let device = app.world.spawn((Body::small(posn, ScreenCell::new()), Description::new().name("scanner"), Device::new(0))).id();
+		for _ in 0..10 { app.update(); }
+		assert_eq!(app.world.get::<Device>(device).unwrap().wear, 0);
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::NoAction), None, None));
+		app.update();
+		assert_eq!(app.world.get::<Device>(device).unwrap().wear, HAZARD_WEAR);
+	}
+	//  ##: Identification
+	/// Sets up a headless App with the identification_system, a player, and two of the same unlabeled data chip
+	fn identify_app() -> (App, Entity, [Entity; 2]) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<IdentifyItem>()
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, identification_system);
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(2, 2, 0), ScreenCell::new()))).id();
+		let chips = [0, 1].map(|_| app.world.spawn((
+			Description::new().name("unlabeled data chip"),
+			Unidentified::new("security chip", Description::new().name("security chip")),
+			Portable::new(player),
+		)).id());
+		(app, player, chips)
+	}
+	fn is_identified(app: &App, item: Entity) -> bool {
+		app.world.get::<Unidentified>(item).is_none() && app.world.get::<Description>(item).unwrap().name == "security chip"
+	}
+	#[test]
+	fn using_an_item_identifies_it() {
+		let (mut app, player, chips) = identify_app();
+		app.world.send_event(GameEvent::new(PlayerAction(UseItem), Some(player), Some(chips[0])));
+		app.update();
+		assert!(is_identified(&app, chips[0]));
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You've worked it out: the unlabeled data chip is a security chip!"));
+		// Identification goes item by item, so the other chip is still a mystery
+		assert!(!is_identified(&app, chips[1]));
+	}
+	#[test]
+	fn examining_only_identifies_at_a_workbench() {
+		let (mut app, player, chips) = identify_app();
+		app.world.send_event(GameEvent::new(PlayerAction(Examine), Some(player), Some(chips[0])));
+		app.update();
+		assert!(!is_identified(&app, chips[0]));
+		app.world.spawn((Body::small(Position::new(3, 2, 0), ScreenCell::new()), Workbench::default()));
+		app.world.send_event(GameEvent::new(PlayerAction(Examine), Some(player), Some(chips[0])));
+		app.update();
+		assert!(is_identified(&app, chips[0]));
+	}
+	#[test]
+	fn a_finished_analysis_identifies_its_item() {
+		let (mut app, player, chips) = identify_app();
+		app.world.send_event(IdentifyItem { item: chips[1], identifier: player });
+		app.update();
+		assert!(is_identified(&app, chips[1]));
+		assert!(!is_identified(&app, chips[0]));
+	}
+	//  ##: Incapacitation
+	/// Sets up a headless App with a small room, the systems that deal with actors going down, and an NPC carrying two
+	/// items; the NPC is in the middle of the room, and gets the given number of hitpoints
+	fn vitals_app(hp: i32) -> (App, Entity, Vec<Entity>) {
+		let mut app = App::new();
+		let mut run_state = RunState::new();
+		run_state.mode = EngineMode::Running;
+		app.add_event::<GameEvent>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.insert_resource(crate::testmap::TestMap::new("#####\n#...#\n#####").model)
+		.insert_resource(run_state)
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (map_indexing_system, vitals_system, item_collection_system).chain());
+		let mut vitals = Vitals::new(5);
+		vitals.hp = hp;
+		let npc = app.world.spawn((
+			Description::new().name("crewman"),
+			Body::small(Position::new(2, 1, 0), ScreenCell::new()),
+			Container::default(),
+			Mobile::default(),
+			Obstructive::default(),
+			vitals,
+		)).id();
+		let items = ["wrench", "flashlight"].iter().map(|name| app.world.spawn((
+			Description::new().name(name),
+			Body::small(Position::new(1, 1, 0), ScreenCell::new()),
+			Portable::new(npc),
+			IsCarried::default(),
+		)).id()).collect();
+		(app, npc, items)
+	}
+	#[test]
+	fn a_downed_npc_drops_everything_it_carried() {
+		let (mut app, npc, items) = vitals_app(0);
+		app.update();
+		app.update();
+		for item in items.iter() {
+			assert_eq!(app.world.get::<Portable>(*item).unwrap().carrier, Entity::PLACEHOLDER);
+			assert!(app.world.get::<IsCarried>(*item).is_none());
+			assert_eq!(app.world.get::<Body>(*item).unwrap().ref_posn, Position::new(2, 1, 0));
+		}
+		assert!(app.world.get::<Disabled>(npc).is_some());
+		assert!(app.world.get::<Mobile>(npc).is_none());
+		assert_eq!(app.world.get::<Description>(npc).unwrap().name, "wrecked crewman");
+	}
+	#[test]
+	fn a_wreck_does_not_block_movement() {
+		let (mut app, npc, _) = vitals_app(1);
+		app.update();
+		assert!(app.world.resource::<WorldModel>().is_blocked_at(Position::new(2, 1, 0)));
+		app.world.get_mut::<Vitals>(npc).unwrap().hp = 0;
+		app.update();
+		app.update();
+		assert!(app.world.get::<Obstructive>(npc).is_none());
+		assert!(!app.world.resource::<WorldModel>().is_blocked_at(Position::new(2, 1, 0)));
+	}
+	#[test]
+	fn a_healthy_npc_keeps_its_inventory() {
+		let (mut app, npc, items) = vitals_app(5);
+		app.update();
+		app.update();
+		for item in items.iter() {
+			assert_eq!(app.world.get::<Portable>(*item).unwrap().carrier, npc);
+		}
+		assert!(app.world.get::<Disabled>(npc).is_none());
+	}
+	//  ##: Movement
+	/// Sets up a headless App with a single row of floor and the movement_system, and an NPC at its west end
+	fn movement_app() -> (App, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ActionFailed>()
+		.add_event::<Noise>()
+		.add_event::<PlayerMoved>()
+		.insert_resource(crate::testmap::TestMap::new("...").model)
+		.insert_resource(RunState::new())
+		.insert_resource(Preferences::default())
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, movement_system);
+		let npc = app.world.spawn((Description::new().name("crewman"), Body::small(Position::new(0, 0, 0), ScreenCell::new()))).id();
+		(app, npc)
+	}
+	#[test]
+	fn every_failed_npc_move_is_reported_to_the_ai() {
+		let (mut app, npc) = movement_app();
+		let mut reader = app.world.resource::<Events<ActionFailed>>().get_reader();
+		// One move off the edge of the map, and one up a ladder that isn't there
+		for dir in [Direction::W, Direction::UP] {
+			app.world.send_event(GameEvent::new(ActorAction(MoveTo(dir)), Some(npc), None));
+			app.update();
+			let failures: Vec<ActionFailed> = reader.iter(app.world.resource::<Events<ActionFailed>>()).copied().collect();
+			assert_eq!(failures.len(), 1, "{:?}", dir);
+			assert_eq!(failures[0].actor, npc);
+			assert!(matches!(failures[0].action, MoveTo(x) if x == dir));
+			assert!(!failures[0].transient);
+		}
+		assert_eq!(app.world.get::<Body>(npc).unwrap().ref_posn, Position::new(0, 0, 0));
+	}
+	#[test]
+	fn npcs_walk_into_vacuum_without_being_asked() {
+		let (mut app, npc) = movement_app();
+		app.world.resource_mut::<WorldModel>().levels[0].tiles[1] = Tile::new_vacuum();
+		let mut reader = app.world.resource::<Events<ActionFailed>>().get_reader();
+		app.world.send_event(GameEvent::new(ActorAction(MoveTo(Direction::E)), Some(npc), None));
+		app.update();
+		// The confirmation is only ever asked for at the keyboard, so nothing holds the NPC back
+		assert_eq!(reader.iter(app.world.resource::<Events<ActionFailed>>()).count(), 0);
+		assert_eq!(app.world.get::<Body>(npc).unwrap().ref_posn, Position::new(1, 0, 0));
+	}
+	/// Loads the movement_app's NPC right up to its carrying capacity and gives it the given MoveStyle
+	fn loaded_mover(app: &mut App, npc: Entity, careful: bool) {
+		app.world.entity_mut(npc).insert((MoveStyle { careful, ..MoveStyle::default() }, CarryCapacity::new(10)));
+		app.world.spawn((Portable::new(npc), IsCarried::default(), Weight { mass: 10 }));
+	}
+	/// Steps the movement_app's NPC east on the given turn, returning the volume of any Noise and whether it was held up
+	fn step_east(app: &mut App, npc: Entity, turn: u64) -> (Option<i32>, bool) {
+		app.world.resource_mut::<RunState>().turn_count = turn;
+		app.world.send_event(GameEvent::new(ActorAction(MoveTo(Direction::E)), Some(npc), None));
+		app.update();
+		let volume = app.world.resource::<Events<Noise>>().get_reader().iter(app.world.resource::<Events<Noise>>()).last().map(|x| x.volume);
+		let held_up = app.world.resource::<Events<ActionFailed>>().get_reader().iter(app.world.resource::<Events<ActionFailed>>()).any(|x| x.transient);
+		app.world.resource_mut::<Events<Noise>>().clear();
+		app.world.resource_mut::<Events<ActionFailed>>().clear();
+		(volume, held_up)
+	}
+	#[test]
+	fn a_heavy_load_makes_loud_footsteps() {
+		let (mut app, npc) = movement_app();
+		loaded_mover(&mut app, npc, false);
+		assert_eq!(step_east(&mut app, npc, 0), (Some(8), false));
+		// An ordinary step only takes the one turn, so the next can follow straight after
+		assert_eq!(step_east(&mut app, npc, 0), (Some(8), false));
+		assert_eq!(app.world.get::<Body>(npc).unwrap().ref_posn, Position::new(2, 0, 0));
+		assert_eq!(app.world.get::<MoveStyle>(npc).unwrap().meter_percent(), 80);
+	}
+	#[test]
+	fn a_careful_step_is_quieter_but_takes_two_turns() {
+		let (mut app, npc) = movement_app();
+		loaded_mover(&mut app, npc, true);
+		assert_eq!(step_east(&mut app, npc, 5), (Some(4), false));
+		// The step is still under way for the rest of this turn and all of the next
+		assert_eq!(step_east(&mut app, npc, 5), (None, true));
+		assert_eq!(step_east(&mut app, npc, 6), (None, true));
+		assert_eq!(app.world.get::<Body>(npc).unwrap().ref_posn, Position::new(1, 0, 0));
+		assert_eq!(step_east(&mut app, npc, 7), (Some(4), false));
+		assert_eq!(app.world.get::<Body>(npc).unwrap().ref_posn, Position::new(2, 0, 0));
+	}
+	/// A room with a corner cut out of its lower-left, for a long Body to try to swing around
+	const NOTCHED_ROOM: &str = "\
+######
+#....#
+##...#
+######";
+	/// Swaps the movement_app's map for the NOTCHED_ROOM, and stretches its NPC out into a two-tile Body across (1,1)
+	/// and (2,1), standing on its own tiles the way that the map_indexing_system would leave it
+	fn long_mover(app: &mut App, npc: Entity) {
+		let mut model = crate::testmap::TestMap::new(NOTCHED_ROOM).model;
+		let body = Body::large(vec![Position::new(1, 1, 0), Position::new(2, 1, 0)], vec![ScreenCell::new()]);
+		for posn in body.posns() {
+			model.set_blocked_state(posn, true);
+		}
+		model.add_contents(&body.posns(), 0, npc);
+		app.insert_resource(model);
+		app.world.entity_mut(npc).insert(body);
+	}
+	/// Sends the NPC a move in the given direction, returning whatever the AI was told about it failing; afterward the
+	/// blocking map gets moved along with the Body, as the map_indexing_system would do
+	fn long_step(app: &mut App, npc: Entity, dir: Direction) -> Vec<ActionFailed> {
+		let old_posns = app.world.get::<Body>(npc).unwrap().posns();
+		app.world.send_event(GameEvent::new(ActorAction(MoveTo(dir)), Some(npc), None));
+		app.update();
+		let new_posns = app.world.get::<Body>(npc).unwrap().posns();
+		let mut model = app.world.resource_mut::<WorldModel>();
+		for posn in old_posns {
+			model.set_blocked_state(posn, false);
+		}
+		for posn in new_posns {
+			model.set_blocked_state(posn, true);
+		}
+		let failures = app.world.resource::<Events<ActionFailed>>().get_reader().iter(app.world.resource::<Events<ActionFailed>>()).copied().collect();
+		app.world.resource_mut::<Events<ActionFailed>>().clear();
+		failures
+	}
+	#[test]
+	fn a_long_body_cant_swing_through_a_corner() {
+		let (mut app, npc) = movement_app();
+		long_mover(&mut app, npc);
+		// Both of the tiles at the far end are open, but the tail would have to pass through the wall at (1,2)
+		let failures = long_step(&mut app, npc, Direction::SE);
+		assert_eq!(failures.len(), 1);
+		assert!(!failures[0].transient);
+		assert_eq!(app.world.get::<Body>(npc).unwrap().posns(), vec![Position::new(1, 1, 0), Position::new(2, 1, 0)]);
+		// One step further along, the swing is clear of the corner and the same move goes through
+		assert!(long_step(&mut app, npc, Direction::E).is_empty());
+		assert!(long_step(&mut app, npc, Direction::SE).is_empty());
+		assert_eq!(app.world.get::<Body>(npc).unwrap().posns(), vec![Position::new(3, 2, 0), Position::new(4, 2, 0)]);
+	}
+	#[test]
+	fn a_long_body_isnt_blocked_by_its_own_tiles() {
+		let (mut app, npc) = movement_app();
+		long_mover(&mut app, npc);
+		// Each of these moves lands at least one of the Body's parts on a tile that it's already standing on
+		assert!(long_step(&mut app, npc, Direction::E).is_empty());
+		assert_eq!(app.world.get::<Body>(npc).unwrap().posns(), vec![Position::new(2, 1, 0), Position::new(3, 1, 0)]);
+		assert!(long_step(&mut app, npc, Direction::W).is_empty());
+		assert_eq!(app.world.get::<Body>(npc).unwrap().posns(), vec![Position::new(1, 1, 0), Position::new(2, 1, 0)]);
+		let model = app.world.resource::<WorldModel>();
+		assert_eq!(model.get_contents_at(Position::new(3, 1, 0)), Vec::<Entity>::new());
+		assert!(model.get_contents_at(Position::new(1, 1, 0)).contains(&npc));
+	}
+	//  ##: Giving
+	/// Sets up a headless App with the item_collection_system, and a player holding a wrench next to the LMR, which has
+	/// room for one thing; returns the player, the LMR, and the wrench
+	fn give_app() -> (App, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.insert_resource(crate::testmap::TestMap::new("....").model)
+		.insert_resource(RunState::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(Position::new(1, 0, 0))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, item_collection_system);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Container::default())).id();
+		let lmr = app.world.spawn((Description::new().name("LMR"), Body::small(Position::new(2, 0, 0), ScreenCell::new()), Container::with_slots(1), Mobile::default())).id();
+		let wrench = app.world.spawn((Description::new().name("wrench"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Portable::new(player), IsCarried::default())).id();
+		(app, player, lmr, wrench)
+	}
+	/// Has the player give the wrench to the LMR, and returns the carriers that were told that their inventory changed
+	fn give(app: &mut App, player: Entity, lmr: Entity, wrench: Entity) -> Vec<Entity> {
+		app.world.send_event(GameEvent::give(player, wrench, lmr));
+		app.update();
+		let events = app.world.resource::<Events<InventoryChanged>>();
+		events.get_reader().iter(events).map(|x| x.carrier).collect()
+	}
+	#[test]
+	fn giving_hands_the_item_over() {
+		let (mut app, player, lmr, wrench) = give_app();
+		let changed = give(&mut app, player, lmr, wrench);
+		assert_eq!(app.world.get::<Portable>(wrench).unwrap().carrier, lmr);
+		assert!(app.world.get::<IsCarried>(wrench).is_some());
+		// Both sides hear about it, so that each of their contents lists gets brought up to date
+		assert_eq!(changed, vec![player, lmr]);
+		// The wrench never touched the floor on its way over
+		assert!(app.world.resource::<WorldModel>().get_contents_at(Position::new(1, 0, 0)).is_empty());
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You hand the wrench to the LMR."));
+	}
+	#[test]
+	fn giving_needs_the_recipient_in_reach() {
+		let (mut app, player, lmr, wrench) = give_app();
+		app.world.get_mut::<Body>(lmr).unwrap().move_to(Position::new(3, 0, 0));
+		assert!(give(&mut app, player, lmr, wrench).is_empty());
+		assert_eq!(app.world.get::<Portable>(wrench).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The LMR is out of reach."));
+	}
+	#[test]
+	fn giving_needs_room_in_the_recipient() {
+		let (mut app, player, lmr, wrench) = give_app();
+		app.world.spawn((Description::new().name("spanner"), Body::small(Position::new(2, 0, 0), ScreenCell::new()), Portable::new(lmr), IsCarried::default()));
+		assert!(give(&mut app, player, lmr, wrench).is_empty());
+		assert_eq!(app.world.get::<Portable>(wrench).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The LMR can't carry any more."));
+	}
+	#[test]
+	fn only_the_holder_can_give_an_item() {
+		let (mut app, player, lmr, wrench) = give_app();
+		app.world.get_mut::<Portable>(wrench).unwrap().carrier = Entity::PLACEHOLDER;
+		assert!(give(&mut app, player, lmr, wrench).is_empty());
+		assert_eq!(app.world.get::<Portable>(wrench).unwrap().carrier, Entity::PLACEHOLDER);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You aren't carrying that anymore."));
+	}
+	//  ##: Bags
+	/// As for give_app, but the player is also carrying a bag, and there's a spanner lying at their feet; returns the
+	/// player, the LMR, the bag, and the spanner
+	fn bag_app() -> (App, Entity, Entity, Entity, Entity) {
+		let (mut app, player, lmr, _) = give_app();
+		let bag = app.world.spawn((Description::new().name("bag"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Container::default(), Portable::new(player), IsCarried::default())).id();
+		let spanner = app.world.spawn((Description::new().name("spanner"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Portable::empty())).id();
+		(app, player, lmr, bag, spanner)
+	}
+	/// Has the player pick up the given thing and put it into the given container
+	fn pick_into(app: &mut App, player: Entity, item: Entity, container: Entity) {
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::MoveItem), Some(player), Some(item)).into_container(container));
+		app.update();
+	}
+	#[test]
+	fn an_item_can_be_picked_up_into_a_bag() {
+		let (mut app, player, _, bag, spanner) = bag_app();
+		pick_into(&mut app, player, spanner, bag);
+		assert_eq!(app.world.get::<Portable>(spanner).unwrap().carrier, bag);
+		assert!(app.world.get::<IsCarried>(spanner).is_some());
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("Obtained a spanner, and put it in the bag."));
+	}
+	#[test]
+	fn a_bag_can_be_handed_over() {
+		let (mut app, player, lmr, bag, _) = bag_app();
+		give(&mut app, player, lmr, bag);
+		assert_eq!(app.world.get::<Portable>(bag).unwrap().carrier, lmr);
+	}
+	#[test]
+	fn a_bag_wont_go_inside_of_itself() {
+		let (mut app, player, _, bag, _) = bag_app();
+		pick_into(&mut app, player, bag, bag);
+		assert_eq!(app.world.get::<Portable>(bag).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The bag won't fit inside of itself."));
+		// Nor into anything that's already inside of it
+		let tin = app.world.spawn((Description::new().name("tin"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Container::default(), Portable::new(bag), IsCarried::default())).id();
+		give(&mut app, player, tin, bag);
+		assert_eq!(app.world.get::<Portable>(bag).unwrap().carrier, player);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The bag won't fit inside of itself."));
+	}
+	#[test]
+	fn a_vanished_item_is_skipped() {
+		let (mut app, player, _, bag, spanner) = bag_app();
+		app.world.despawn(spanner);
+		pick_into(&mut app, player, spanner, bag);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), None);
+		// The same goes for a subject that's gone away
+		let wrench = app.world.spawn((Description::new().name("wrench"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Portable::empty())).id();
+		app.world.despawn(player);
+		pick_into(&mut app, player, wrench, bag);
+		assert_eq!(app.world.get::<Portable>(wrench).unwrap().carrier, Entity::PLACEHOLDER);
+	}
+	//  ##: Emptying
+	/// Adds a locker with three slots beside the player in the give_app, with each of its slots filled up
+	/// Returns the App, followed by the player, the locker, and the three things in it
+	fn full_locker_app() -> (App, Entity, Entity, Vec<Entity>) {
+		let (mut app, player, _, _) = give_app();
+		let locker = app.world.spawn((Description::new().name("locker"), Body::small(Position::new(2, 0, 0), ScreenCell::new()), Container::with_slots(3), Openable::new(true, "▯", "▮"))).id();
+		let contents = ["fuse", "rag", "tin"].iter().map(|name| {
+			app.world.spawn((Description::new().name(name), Body::small(Position::new(2, 0, 0), ScreenCell::new()), Portable::new(locker), IsCarried::default())).id()
+		}).collect();
+		(app, player, locker, contents)
+	}
+	/// Has the player empty out the given container
+	fn empty_out(app: &mut App, player: Entity, container: Entity) {
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::EmptyItem), Some(player), Some(container)));
+		app.update();
+	}
+	#[test]
+	fn emptying_a_full_locker_spreads_its_things_around() {
+		let (mut app, player, locker, contents) = full_locker_app();
+		empty_out(&mut app, player, locker);
+		let mut spots: Vec<Position> = Vec::new();
+		for item in contents.iter() {
+			assert_eq!(app.world.get::<Portable>(*item).unwrap().carrier, Entity::PLACEHOLDER);
+			assert!(app.world.get::<IsCarried>(*item).is_none());
+			spots.push(app.world.get::<Body>(*item).unwrap().ref_posn);
+		}
+		// The player's own tile is filled first, then the ones around it, so that nothing lands on top of anything else
+		assert_eq!(spots, vec![Position::new(1, 0, 0), Position::new(2, 0, 0), Position::new(0, 0, 0)]);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You empty out the locker: 3 items spill onto the floor."));
+	}
+	#[test]
+	fn an_empty_or_closed_locker_has_nothing_to_give() {
+		let (mut app, player, locker, contents) = full_locker_app();
+		app.world.get_mut::<Openable>(locker).unwrap().is_open = false;
+		empty_out(&mut app, player, locker);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The locker is closed."));
+		assert_eq!(app.world.get::<Portable>(contents[0]).unwrap().carrier, locker);
+		app.world.get_mut::<Openable>(locker).unwrap().is_open = true;
+		empty_out(&mut app, player, locker);
+		empty_out(&mut app, player, locker);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The locker is already empty."));
+	}
+	//  ##: Dangling references
+	/// Runs the same check as the carrier_check_system over the given World, and returns the entities that it reported on
+	fn dangling(world: &mut World) -> Vec<Entity> {
+		let mut state: SystemState<(&Entities, Res<PlanqData>, Res<QuickSlots>, Query<(Entity, &Portable, Option<&Description>)>)> = SystemState::new(world);
+		let (entities, planq, slots, i_query) = state.get(world);
+		find_dangling_references(entities, &planq, &slots, &i_query).into_iter().map(|x| x.0).collect()
+	}
+	#[test]
+	fn an_item_left_behind_by_its_carrier_is_found() {
+		let mut world = World::new();
+		world.insert_resource(PlanqData::default());
+		world.insert_resource(QuickSlots::default());
+		let bag = world.spawn(Description::new().name("bag")).id();
+		let spanner = world.spawn((Description::new().name("spanner"), Portable::new(bag))).id();
+		world.spawn((Description::new().name("tin"), Portable::empty()));
+		assert!(dangling(&mut world).is_empty());
+		// Despawning the bag directly, instead of through despawn_gameplay_entity(), strands whatever was inside of it
+		world.despawn(bag);
+		assert_eq!(dangling(&mut world), vec![spanner]);
+		world.resource_mut::<PlanqData>().jack_cnxn = bag;
+		world.resource_mut::<QuickSlots>().slots[0] = Some(bag);
+		assert_eq!(dangling(&mut world), vec![spanner, bag, bag]);
+	}
+	//  ##: Reaping
+	/// Sets up a headless App with the entity_reaper_system, and a crate lying at (2, 0) that holds a rag and a box with a
+	/// fuse in it; the player remembers seeing the crate, the PLANQ is jacked into it, and it sits in the first quick-slot
+	/// Returns the App, followed by the player, the crate, the rag, the box, and the fuse
+	fn reaper_app() -> (App, Entity, Entity, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<EntityKilled>()
+		.add_event::<InventoryChanged>()
+		.insert_resource(crate::testmap::TestMap::new("....").model)
+		.insert_resource(PlanqData::default())
+		.insert_resource(QuickSlots::default())
+		.add_systems(Update, entity_reaper_system);
+		let posn = Position::new(2, 0, 0);
+		let lockbox = app.world.spawn((Description::new().name("crate"), Body::small(posn, ScreenCell::new()), Container::default())).id();
+		let rag = app.world.spawn((Description::new().name("rag"), Body::small(posn, ScreenCell::new()), Portable::new(lockbox), IsCarried::default())).id();
+		let inner = app.world.spawn((Description::new().name("box"), Body::small(posn, ScreenCell::new()), Container::default(), Portable::new(lockbox), IsCarried::default())).id();
+		let fuse = app.world.spawn((Description::new().name("fuse"), Body::small(posn, ScreenCell::new()), Portable::new(inner), IsCarried::default())).id();
+		let mut memory = Memory::new();
+		memory.visual.insert(posn, vec![lockbox]);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), memory)).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![posn], 0, lockbox);
+		app.world.resource_mut::<PlanqData>().jack_cnxn = lockbox;
+		app.world.resource_mut::<QuickSlots>().slots[0] = Some(lockbox);
+		(app, player, lockbox, rag, inner, fuse)
+	}
+	/// Takes the given entity out of the game through despawn_gameplay_entity(), then lets the reaper clean up after it
+	fn kill(app: &mut App, entity: Entity, policy: DespawnPolicy) {
+		let posns = app.world.get::<Body>(entity).unwrap().posns();
+		let mut state: SystemState<(Commands, EventWriter<EntityKilled>)> = SystemState::new(&mut app.world);
+		let (mut commands, mut kwriter) = state.get_mut(&mut app.world);
+		despawn_gameplay_entity(&mut commands, &mut kwriter, entity, posns, policy);
+		state.apply(&mut app.world);
+		app.update();
+	}
+	/// Checks that nothing is holding on to the given entity after it was reaped
+	fn assert_forgotten(app: &mut App, player: Entity, entity: Entity) {
+		assert!(!app.world.resource::<WorldModel>().get_contents_at(Position::new(2, 0, 0)).contains(&entity));
+		assert!(app.world.get::<Memory>(player).unwrap().visual.values().all(|x| !x.contains(&entity)));
+		assert_eq!(app.world.resource::<PlanqData>().jack_cnxn, Entity::PLACEHOLDER);
+		assert_eq!(app.world.resource::<QuickSlots>().slots[0], None);
+		assert!(dangling(&mut app.world).is_empty());
+	}
+	#[test]
+	fn a_destroyed_container_spills_what_it_held() {
+		let (mut app, player, lockbox, rag, inner, fuse) = reaper_app();
+		kill(&mut app, lockbox, DespawnPolicy::DropContents);
+		assert!(app.world.get_entity(lockbox).is_none());
+		for item in [rag, inner] {
+			assert_eq!(app.world.get::<Portable>(item).unwrap().carrier, Entity::PLACEHOLDER);
+			assert!(app.world.get::<IsCarried>(item).is_none());
+			assert_eq!(app.world.get::<Body>(item).unwrap().ref_posn, Position::new(2, 0, 0));
+		}
+		// Only the crate's own contents fall out; the fuse stays put inside of the box
+		assert_eq!(app.world.get::<Portable>(fuse).unwrap().carrier, inner);
+		let events = app.world.resource::<Events<InventoryChanged>>();
+		let changed: Vec<Entity> = events.get_reader().iter(events).map(|x| x.carrier).collect();
+		assert_eq!(changed, vec![lockbox]);
+		assert_forgotten(&mut app, player, lockbox);
+	}
+	#[test]
+	fn destroying_the_contents_goes_all_the_way_down() {
+		let (mut app, player, lockbox, rag, inner, fuse) = reaper_app();
+		kill(&mut app, lockbox, DespawnPolicy::DestroyContents);
+		for enty in [lockbox, rag, inner, fuse] {
+			assert!(app.world.get_entity(enty).is_none());
+		}
+		assert_forgotten(&mut app, player, lockbox);
+	}
+	//  ##: Locked doors
+	/// Sets up a headless App with the openable_system, and a player standing next to a locked door in the given state
+	fn locked_door_app(is_open: bool) -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.insert_resource(crate::testmap::TestMap::new("...").model)
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, openable_system);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(0, 0, 0), ScreenCell::new()))).id();
+		let door = app.world.spawn((
+			Description::new().name("door"),
+			Body::small(Position::new(1, 0, 0), ScreenCell::new().glyph("+")),
+			Openable::new(is_open, "'", "+"),
+			Lockable { is_locked: true, ..Lockable::default() },
+		)).id();
+		if !is_open { app.world.entity_mut(door).insert(Obstructive::default()); }
+		(app, player, door)
+	}
+	#[test]
+	fn a_locked_door_refuses_to_open() {
+		let (mut app, player, door) = locked_door_app(false);
+		app.world.send_event(GameEvent::new(PlayerAction(OpenItem), Some(player), Some(door)));
+		app.update();
+		assert!(!app.world.get::<Openable>(door).unwrap().is_open);
+		assert!(app.world.get::<Obstructive>(door).is_some());
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You try to open the door. It won't budge: it's locked."));
+	}
+	#[test]
+	fn a_door_locked_open_can_still_be_closed() {
+		let (mut app, player, door) = locked_door_app(true);
+		app.world.send_event(GameEvent::new(PlayerAction(CloseItem), Some(player), Some(door)));
+		app.update();
+		assert!(!app.world.get::<Openable>(door).unwrap().is_open);
+		assert!(app.world.get::<Obstructive>(door).is_some());
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You close the door."));
+	}
+	//  ##: Locked lockers
+	/// Sets up a headless App with the systems that a locker goes through, and a player beside a locked locker with a
+	/// fuse inside of it; the player is already carrying the locker's key
+	/// Returns the App, followed by the player, the locker, and the fuse
+	fn locked_locker_app() -> (App, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(crate::testmap::TestMap::new("...").model)
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(RunState::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(PlanqData::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (examination_system, lockable_system, openable_system, item_collection_system).chain());
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(0, 0, 0), ScreenCell::new()), Container::default())).id();
+		app.world.spawn((Description::new().name("key"), Body::small(Position::new(0, 0, 0), ScreenCell::new()), Portable::new(player), IsCarried::default(), Key { key_id: 7 }));
+		let locker = app.world.spawn((
+			Description::new().name("locker").desc("A narrow locker."),
+			Body::small(Position::new(1, 0, 0), ScreenCell::new()),
+			Container::default(),
+			Openable::new(false, "▯", "▮"),
+			Lockable { is_locked: true, key_id: 7, ..Lockable::default() },
+		)).id();
+		let fuse = app.world.spawn((Description::new().name("fuse"), Body::small(Position::new(1, 0, 0), ScreenCell::new()), Portable::new(locker), IsCarried::default())).id();
+		(app, player, locker, fuse)
+	}
+	/// Has the player do the given thing to the given target, and returns everything that they were told about it
+	fn locker_step(app: &mut App, player: Entity, action: ActionType, target: Entity) -> Vec<String> {
+		let before = app.world.resource::<MessageLog>().channel_len("world");
+		app.world.send_event(GameEvent::new(PlayerAction(action), Some(player), Some(target)));
+		app.update();
+		app.world.resource::<MessageLog>().get_log_as_messages("world", 0).iter().skip(before).map(|x| x.text.clone()).collect()
+	}
+	#[test]
+	fn a_locked_locker_is_unlocked_then_opened_then_emptied() {
+		let (mut app, player, locker, fuse) = locked_locker_app();
+		assert_eq!(locker_step(&mut app, player, Examine, locker), vec!["A narrow locker.", "It's fitted with a lock, which is currently locked."]);
+		// Nothing comes out of it, and it doesn't open, until it's been unlocked
+		assert_eq!(locker_step(&mut app, player, MoveItem, fuse), vec!["The locker is closed."]);
+		assert_eq!(locker_step(&mut app, player, OpenItem, locker), vec!["You try to open the locker. It won't budge: it's locked."]);
+		assert!(!app.world.get::<Openable>(locker).unwrap().is_open);
+		assert_eq!(locker_step(&mut app, player, UnlockItem, locker), vec!["You unlock the locker."]);
+		assert_eq!(locker_step(&mut app, player, Examine, locker)[1], "It's fitted with a lock, which is currently unlocked.");
+		// Unlocking it doesn't open it as well
+		assert_eq!(locker_step(&mut app, player, MoveItem, fuse), vec!["The locker is closed."]);
+		assert_eq!(locker_step(&mut app, player, OpenItem, locker), vec!["You open the locker."]);
+		assert_eq!(locker_step(&mut app, player, MoveItem, fuse), vec!["Obtained a fuse."]);
+		assert_eq!(app.world.get::<Portable>(fuse).unwrap().carrier, player);
+	}
+	//  ##: Hacking
+	/// Sets up a headless App with the lockable_system, and a player carrying a charged PLANQ beside a locked door
+	/// The door's lock has the given difficulty; returns the App, followed by the player, the PLANQ, and the door
+	fn hacking_app(difficulty: i32) -> (App, Entity, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(PlanqData::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, lockable_system);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(0, 0, 0), ScreenCell::new()))).id();
+		let mut device = Device::new(1);
+		device.batt_voltage = 100;
+		let planq = app.world.spawn((
+			Portable::new(player),
+			device,
+			RngComponent::from(&mut GlobalRng::with_seed(1)),
+			Planq::new(),
+		)).id();
+		let door = app.world.spawn((
+			Description::new().name("door"),
+			Body::small(Position::new(1, 0, 0), ScreenCell::new().glyph("+")),
+			Lockable { is_locked: true, difficulty, ..Lockable::default() },
+		)).id();
+		(app, player, planq, door)
+	}
+	#[test]
+	fn a_trivial_lock_always_gives_way_to_the_planq() {
+		let (mut app, player, planq, door) = hacking_app(0);
+		app.world.send_event(GameEvent::new(PlayerAction(HackItem), Some(player), Some(door)));
+		app.update();
+		assert!(!app.world.get::<Lockable>(door).unwrap().is_locked);
+		assert_eq!(app.world.get::<Device>(planq).unwrap().batt_voltage, 100);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("Your PLANQ chirps as the door unlocks."));
+		assert_eq!(app.world.resource::<Events<ShipLogEvent>>().len(), 1);
+	}
+	#[test]
+	fn an_impossible_lock_always_holds_and_drains_the_planq() {
+		let (mut app, player, planq, door) = hacking_app(100);
+		for attempt in 1..=3 {
+			app.world.send_event(GameEvent::new(PlayerAction(HackItem), Some(player), Some(door)));
+			app.update();
+			assert!(app.world.get::<Lockable>(door).unwrap().is_locked);
+			assert_eq!(app.world.get::<Device>(planq).unwrap().batt_voltage, 100 - attempt * HACK_FAIL_DRAIN);
+		}
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The door resists your PLANQ's intrusion attempt."));
+		assert!(app.world.resource::<Events<ShipLogEvent>>().is_empty());
+	}
+	//  ##: Sliding doors
+	/// Sets up a headless App with the openable_system on the corridor map, with the player beside a closed sliding
+	/// door that tucks into the wall just north of its doorway
+	/// Returns the App, followed by the player and the door
+	fn sliding_door_app() -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.insert_resource(crate::testmap::TestMap::new(crate::testmap::CORRIDOR_WITH_DOOR).model)
+		.insert_resource(Position::new(3, 1, 0))
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, openable_system);
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(3, 1, 0), ScreenCell::new()))).id();
+		let mut openable = Openable::new(false, "'", "+");
+		openable.slides(Position::new(4, 1, 0), Position::new(4, 0, 0));
+		let door = app.world.spawn((
+			Description::new().name("door"),
+			Body::small(Position::new(4, 1, 0), ScreenCell::new().glyph("+")),
+			openable,
+			Obstructive::default(),
+		)).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![Position::new(4, 1, 0)], 0, door);
+		(app, player, door)
+	}
+	/// Sends the given open or close action from the player to the door, and runs an update
+	fn work_door(app: &mut App, player: Entity, door: Entity, action: ActionType) {
+		app.world.send_event(GameEvent::new(PlayerAction(action), Some(player), Some(door)));
+		app.update();
+	}
+	#[test]
+	fn a_sliding_door_swaps_between_its_doorway_and_its_pocket() {
+		let (mut app, player, door) = sliding_door_app();
+		let (doorway, pocket) = (Position::new(4, 1, 0), Position::new(4, 0, 0));
+		work_door(&mut app, player, door, OpenItem);
+		assert_eq!(app.world.get::<Body>(door).unwrap().ref_posn, pocket);
+		assert!(app.world.get::<Obstructive>(door).is_none());
+		let model = app.world.resource::<WorldModel>();
+		assert!(model.get_contents_at(doorway).is_empty());
+		assert_eq!(model.get_contents_at(pocket), vec![door]);
+		// The door tells anything that asks about it where its doorway is, not where it's been tucked away to
+		assert_eq!(app.world.get::<Openable>(door).unwrap().doorway(pocket), doorway);
+		work_door(&mut app, player, door, CloseItem);
+		assert_eq!(app.world.get::<Body>(door).unwrap().ref_posn, doorway);
+		assert!(app.world.get::<Obstructive>(door).is_some());
+		let model = app.world.resource::<WorldModel>();
+		assert!(model.get_contents_at(pocket).is_empty());
+		assert_eq!(model.get_contents_at(doorway), vec![door]);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You close the door."));
+	}
+	#[test]
+	fn a_sliding_door_wont_close_on_its_occupied_doorway() {
+		let (mut app, player, door) = sliding_door_app();
+		let (doorway, pocket) = (Position::new(4, 1, 0), Position::new(4, 0, 0));
+		work_door(&mut app, player, door, OpenItem);
+		// Something gets left lying in the doorway once the player's through it
+		let crate_enty = app.world.spawn((Description::new().name("crate"), Body::small(doorway, ScreenCell::new()))).id();
+		app.world.resource_mut::<WorldModel>().add_contents(&vec![doorway], 0, crate_enty);
+		work_door(&mut app, player, door, CloseItem);
+		assert!(app.world.get::<Openable>(door).unwrap().is_open);
+		assert_eq!(app.world.get::<Body>(door).unwrap().ref_posn, pocket);
+		assert!(app.world.get::<Obstructive>(door).is_none());
+		assert_eq!(app.world.resource::<WorldModel>().get_contents_at(doorway), vec![crate_enty]);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"),
+		           Some("Something in the doorway is stopping the door from closing."));
+		// Once it's cleared away the door closes as usual
+		app.world.resource_mut::<WorldModel>().remove_contents(&vec![doorway], crate_enty);
+		work_door(&mut app, player, door, CloseItem);
+		assert!(!app.world.get::<Openable>(door).unwrap().is_open);
+		assert_eq!(app.world.get::<Body>(door).unwrap().ref_posn, doorway);
+	}
+	#[test]
+	fn a_door_fought_over_ends_up_the_same_either_way() {
+		for flipped in [false, true] {
+			let (mut app, player, door) = sliding_door_app();
+			let first = app.world.spawn((Description::new().name("crewman"), Body::small(Position::new(5, 1, 0), ScreenCell::new()))).id();
+			let second = app.world.spawn((Description::new().name("LMR"), Body::small(Position::new(5, 1, 0), ScreenCell::new()))).id();
+			let mut events = vec![
+				GameEvent::new(ActorAction(OpenItem), Some(second), Some(door)),
+				GameEvent::new(ActorAction(CloseItem), Some(first), Some(door)),
+			];
+			if flipped { events.reverse(); }
+			for event in events { app.world.send_event(event); }
+			app.update();
+			// The crewman has the lower index, so the door stays shut no matter who spoke first
+			assert!(!app.world.get::<Openable>(door).unwrap().is_open, "flipped: {}", flipped);
+			// Though the player's word beats either of theirs
+			let mut events = vec![
+				GameEvent::new(PlayerAction(OpenItem), Some(player), Some(door)),
+				GameEvent::new(ActorAction(CloseItem), Some(first), Some(door)),
+			];
+			if flipped { events.reverse(); }
+			for event in events { app.world.send_event(event); }
+			app.update();
+			assert!(app.world.get::<Openable>(door).unwrap().is_open, "flipped: {}", flipped);
+		}
+	}
+	//  ##: Lazy loot
+	/// Sets up a headless App with the openable_system feeding the lazy_loot_system, and a player standing next to a
+	/// closed locker that has a wrench and a battery waiting to be stocked in it
+	/// Returns the App, followed by the player and the locker
+	fn lazy_loot_app() -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.insert_resource(crate::testmap::TestMap::new("...").model)
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(LootRequests::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (openable_system, lazy_loot_system).chain());
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(0, 0, 0), ScreenCell::new()))).id();
+		let locker = app.world.spawn((
+			Description::new().name("locker"),
+			Body::small(Position::new(1, 0, 0), ScreenCell::new()),
+			Container::default(),
+			Openable::new(false, "▯", "▮"),
+			LazyLoot { items: vec!["wrench".to_string(), "battery".to_string()] },
+		)).id();
+		(app, player, locker)
+	}
+	/// Sends the given open or close action from the player to the locker, runs an update, and returns every
+	/// ContainerOpened event that came out of it
+	fn work_locker(app: &mut App, player: Entity, locker: Entity, action: ActionType) -> Vec<(Entity, Entity)> {
+		app.world.send_event(GameEvent::new(PlayerAction(action), Some(player), Some(locker)));
+		app.update();
+		let events = app.world.resource::<Events<ContainerOpened>>();
+		let opened = events.get_reader().iter(events).map(|x| (x.container, x.opener)).collect();
+		app.world.resource_mut::<Events<ContainerOpened>>().clear();
+		opened
+	}
+	#[test]
+	fn opening_a_container_announces_it_once() {
+		let (mut app, player, locker) = lazy_loot_app();
+		assert_eq!(work_locker(&mut app, player, locker, OpenItem), vec![(locker, player)]);
+		// Trying to open it again while it's still open doesn't count as opening it
+		assert_eq!(work_locker(&mut app, player, locker, OpenItem), vec![]);
+		// Nor does opening something that isn't a container
+		app.world.entity_mut(locker).remove::<Container>();
+		work_locker(&mut app, player, locker, CloseItem);
+		assert_eq!(work_locker(&mut app, player, locker, OpenItem), vec![]);
+	}
+	#[test]
+	fn lazy_loot_is_only_asked_for_on_the_first_opening() {
+		let (mut app, player, locker) = lazy_loot_app();
+		assert!(app.world.resource::<LootRequests>().pending.is_empty());
+		work_locker(&mut app, player, locker, OpenItem);
+		assert_eq!(app.world.resource::<LootRequests>().pending, vec![("wrench".to_string(), locker), ("battery".to_string(), locker)]);
+		assert!(app.world.get::<LazyLoot>(locker).is_none());
+		// Once it's been stocked, it's just a locker like any other
+		app.world.resource_mut::<LootRequests>().pending.clear();
+		work_locker(&mut app, player, locker, CloseItem);
+		assert_eq!(work_locker(&mut app, player, locker, OpenItem), vec![(locker, player)]);
+		assert!(app.world.resource::<LootRequests>().pending.is_empty());
+	}
+	//  ##: Intro
+	/// Sets up a headless App with the systems that the intro leans on, and runs the intro's setup around a player
+	/// standing in the middle of a short row of floor; returns the App, followed by the player
+	fn intro_app() -> (App, Entity) {
+		let mut app = App::new();
+		let spawnpoint = Position::new(2, 0, 0);
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.add_event::<InventoryChanged>()
+		.add_event::<EntityKilled>()
+		.add_event::<ShipLogEvent>()
+		.insert_resource(crate::testmap::TestMap::new(".....").model)
+		.insert_resource(spawnpoint)
+		.insert_resource(RunState::new())
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(PlanqData::default())
+		.insert_resource(GlobalRng::with_seed(1))
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(bevy::app::Startup, move |mut commands: Commands, mut model: ResMut<WorldModel>, mut rng: ResMut<GlobalRng>| {
+			intro::setup(&mut commands, &mut model, &mut rng, spawnpoint).expect("the intro should fit around the player");
+		})
+		.add_systems(Update, (lockable_system, openable_system, item_collection_system).chain());
+		let player = app.world.spawn((Player::default(), Description::new().name("player"), Body::small(spawnpoint, ScreenCell::new()), Container::default())).id();
+		app.update();
+		(app, player)
+	}
+	/// Finds the one thing in the World with the given name
+	fn named(app: &mut App, name: &str) -> Entity {
+		let mut d_query = app.world.query::<(Entity, &Description)>();
+		d_query.iter(&app.world).find(|x| x.1.name == name).map(|x| x.0).expect("the intro should have made it")
+	}
+	/// Has the player try the given action on the given thing, and returns whatever the player was told about it
+	fn try_action(app: &mut App, player: Entity, action: ActionType, target: Entity) -> Option<String> {
+		app.world.send_event(GameEvent::new(PlayerAction(action), Some(player), Some(target)));
+		app.update();
+		app.world.resource::<MessageLog>().last_text("world").map(|x| x.to_string())
+	}
+	#[test]
+	fn the_intro_can_be_played_through() {
+		let (mut app, player) = intro_app();
+		let (locker, key, planq) = (named(&mut app, "locker"), named(&mut app, "locker key"), named(&mut app, "PLANQ"));
+		assert_eq!(app.world.get::<Portable>(planq).unwrap().carrier, locker);
+		// The PLANQ is out of reach until the locker has been unlocked and opened
+		assert_eq!(try_action(&mut app, player, MoveItem, planq).as_deref(), Some("The locker is closed."));
+		assert_eq!(try_action(&mut app, player, OpenItem, locker).as_deref(), Some("You try to open the locker. It won't budge: it's locked."));
+		assert_eq!(try_action(&mut app, player, MoveItem, key).as_deref(), Some("Obtained a locker key."));
+		assert_eq!(try_action(&mut app, player, UnlockItem, locker).as_deref(), Some("You unlock the locker."));
+		assert_eq!(try_action(&mut app, player, OpenItem, locker).as_deref(), Some("You open the locker."));
+		assert_eq!(try_action(&mut app, player, MoveItem, planq).as_deref(), Some("Obtained a PLANQ."));
+		assert_eq!(app.world.get::<Portable>(planq).unwrap().carrier, player);
+		assert!(app.world.get::<IsCarried>(planq).is_some());
+	}
+	//  ##: Perception
+	/// Sets up a headless App with the openable_system along a long corridor, with the player at the west end; there's
+	/// a crewman standing beside a closed door at the far edge of the player's earshot, and another well beyond it
+	/// Returns the App, followed by each crewman with their door, the nearer one first
+	fn earshot_app() -> (App, [(Entity, Entity); 2]) {
+		let mut app = App::new();
+		app.add_event::<GameEvent>()
+		.add_event::<ContainerOpened>()
+		.insert_resource(crate::testmap::TestMap::new("....................").model)
+		.insert_resource(Position::new(0, 0, 0))
+		.insert_resource(PerceptionPolicy::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, openable_system);
+		app.world.spawn((Player::default(), Description::new().name("player"), Body::small(Position::new(0, 0, 0), ScreenCell::new())));
+		let earshot = app.world.resource::<PerceptionPolicy>().earshot;
+		let pairs = [earshot, earshot + 10].map(|x_posn| {
+			let npc = app.world.spawn((Description::new().name("crewman"), Body::small(Position::new(x_posn, 0, 0), ScreenCell::new()))).id();
+			let door = app.world.spawn((
+				Description::new().name("door"),
+				Body::small(Position::new(x_posn + 1, 0, 0), ScreenCell::new().glyph("+")),
+				Openable::new(false, "'", "+"),
+			)).id();
+			(npc, door)
+		});
+		(app, pairs)
+	}
+	#[test]
+	fn a_door_opened_out_of_earshot_goes_unmentioned() {
+		let (mut app, [_, (npc, door)]) = earshot_app();
+		app.world.send_event(GameEvent::new(ActorAction(OpenItem), Some(npc), Some(door)));
+		app.update();
+		// The door still opens, the player just doesn't hear about it
+		assert!(app.world.get::<Openable>(door).unwrap().is_open);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), None);
+	}
+	#[test]
+	fn a_door_opened_within_earshot_is_heard() {
+		let (mut app, [(npc, door), _]) = earshot_app();
+		app.world.send_event(GameEvent::new(ActorAction(OpenItem), Some(npc), Some(door)));
+		app.update();
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The crewman opens a door."));
+		// Unless the policy only counts what the player can see, and the player has no view of it
+		app.world.resource_mut::<PerceptionPolicy>().mode = PerceptionMode::SightOnly;
+		app.world.send_event(GameEvent::new(ActorAction(CloseItem), Some(npc), Some(door)));
+		app.update();
+		assert!(!app.world.get::<Openable>(door).unwrap().is_open);
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("The crewman opens a door."));
+	}
+	//  ##: Sightings
+	/// Sets up a headless App with the sighting_system, a player who can see the whole row, and a robot three steps east
+	/// Returns the App, followed by the player and the robot
+	fn sighting_app() -> (App, Entity, Entity) {
+		let mut app = App::new();
+		app.insert_resource(Preferences::default())
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, sighting_system);
+		let mut viewshed = Viewshed::new(8);
+		viewshed.visible_points = (0..9).map(|x| Point::new(x, 0)).collect();
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(0, 0, 0), ScreenCell::new()), viewshed)).id();
+		let robot = app.world.spawn((Description::new().name("robot"), Body::small(Position::new(3, 0, 0), ScreenCell::new()), Mobile::default())).id();
+		(app, player, robot)
+	}
+	#[test]
+	fn an_actor_in_view_is_announced_only_once() {
+		let (mut app, _, robot) = sighting_app();
+		for _ in 0..3 { app.update(); }
+		// Moving around while it stays in view doesn't count as a new sighting either
+		app.world.get_mut::<Body>(robot).unwrap().move_to(Position::new(4, 0, 0));
+		app.update();
+		let log = app.world.resource::<MessageLog>();
+		assert_eq!(log.channel_len("world"), 1);
+		assert_eq!(log.last_text("world"), Some("You see a robot nearby to the east."));
+	}
+	#[test]
+	fn an_actor_that_comes_back_into_view_is_announced_again() {
+		let (mut app, player, robot) = sighting_app();
+		app.update();
+		app.world.get_mut::<Viewshed>(player).unwrap().visible_points.clear();
+		app.update();
+		app.world.get_mut::<Body>(robot).unwrap().move_to(Position::new(1, 0, 0));
+		app.world.get_mut::<Viewshed>(player).unwrap().visible_points = (0..9).map(|x| Point::new(x, 0)).collect();
+		app.update();
+		let log = app.world.resource::<MessageLog>();
+		assert_eq!(log.channel_len("world"), 2);
+		assert_eq!(log.last_text("world"), Some("You see a robot right next to you, to the east."));
+	}
+	#[test]
+	fn a_sighting_can_be_given_in_the_ships_frame() {
+		let (mut app, _, _) = sighting_app();
+		app.world.resource_mut::<Preferences>().dir_names = DirNames::Ship;
+		app.update();
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("You see a robot nearby to the starboard."));
+	}
+	#[test]
+	fn sightings_can_be_kept_quiet() {
+		let (mut app, _, _) = sighting_app();
+		app.world.resource_mut::<Preferences>().announce_sightings = false;
+		app.update();
+		assert_eq!(app.world.resource::<MessageLog>().channel_len("world"), 0);
+	}
+	//  ##: Dropping things
+	/// Finds a drop spot on the given map for a dropper standing at the preferred tile, next to an obstructive crate that
+	/// takes up the preferred tile itself
+	fn drop_spot_on(layout: &str, preferred: Position) -> Option<Position> {
+		let mut world = World::new();
+		let mut model = crate::testmap::TestMap::new(layout).model;
+		let crate_enty = world.spawn((Body::small(preferred, ScreenCell::new()), Obstructive::default())).id();
+		model.add_contents(&vec![preferred], 0, crate_enty);
+		let dropper = world.spawn_empty().id();
+		let mut state: SystemState<Query<Entity, With<Obstructive>>> = SystemState::new(&mut world);
+		let o_query = state.get(&world);
+		find_drop_spot(&model, preferred, dropper, &o_query)
+	}
+	#[test]
+	fn a_drop_lands_on_the_nearest_clear_tile() {
+		assert_eq!(drop_spot_on("#####\n#...#\n#####", Position::new(1, 1, 0)), Some(Position::new(2, 1, 0)));
+	}
+	#[test]
+	fn a_drop_never_lands_beyond_a_wall() {
+		// The tile on the far side of the wall is within the search radius, but can't be reached from this side
+		assert_eq!(drop_spot_on("#####\n#.#.#\n#####", Position::new(1, 1, 0)), None);
+	}
+	#[test]
+	fn a_drop_goes_around_a_wall_when_it_can() {
+		let layout = "\
+#####
+#.#.#
+#...#
+#####";
+		// Straight across is two tiles away but behind the wall, so the item goes down the way that's open instead
+		assert_eq!(drop_spot_on(layout, Position::new(1, 1, 0)), Some(Position::new(1, 2, 0)));
+	}
+	//  ##: Spawn order
+	/// Builds three salvage bins with a fresh ItemBuilder, the first and last at the same spot, and returns the SpawnIndex
+	/// that each one got in the order that they were built; the given number of throwaway entities get spawned and
+	/// despawned first, and since Bevy hands out freed IDs newest-first, the bins' Entity IDs end up in reverse order
+	fn spawn_run(churn: usize) -> Vec<u64> {
+		let mut app = App::new();
+		app.insert_resource(SpawnOrder::default())
+		.add_systems(Update, spawn_index_system);
+		let spares: Vec<Entity> = (0..churn).map(|_| app.world.spawn_empty().id()).collect();
+		for spare in spares {
+			app.world.despawn(spare);
+		}
+		let mut artisan = crate::artisan::ItemBuilder::new();
+		let mut built = Vec::new();
+		for posn in [Position::new(3, 1, 0), Position::new(1, 1, 0), Position::new(3, 1, 0)] {
+			let made = artisan.create(crate::salvage::SALVAGE_BIN_ITEM).at(posn).build(&mut app.world);
+			built.push(made[0].0.id());
+		}
+		app.update();
+		built.iter().map(|x| app.world.get::<SpawnIndex>(*x).unwrap().index).collect()
+	}
+	#[test]
+	fn the_same_spawns_always_get_the_same_numbers() {
+		let first = spawn_run(0);
+		assert_eq!(first, vec![1, 0, 2]);
+		assert_eq!(spawn_run(3), first);
+	}
+	#[test]
+	fn spawn_tickets_are_taken_off_once_used() {
+		let mut app = App::new();
+		app.insert_resource(SpawnOrder::default())
+		.add_systems(Update, spawn_index_system);
+		let item = app.world.spawn((
+			Body::small(Position::new(1, 1, 0), ScreenCell::new()),
+			SpawnTicket { template: "wrench".to_string(), serial: 1 },
+		)).id();
+		app.update();
+		assert_eq!(app.world.get::<SpawnIndex>(item), Some(&SpawnIndex { index: 0 }));
+		assert!(app.world.get::<SpawnTicket>(item).is_none());
+		assert_eq!(app.world.resource::<SpawnOrder>().next, 1);
+	}
+	//  ##: Deck indicator
+	#[test]
+	fn the_deck_indicator_only_changes_when_the_player_moves() {
+		let mut app = App::new();
+		app.add_event::<PlayerMoved>()
+		.insert_resource(crate::testmap::TestMap::from_decks(&crate::testmap::TWO_DECKS_WITH_STAIRS).model)
+		.insert_resource(DeckIndicator::default())
+		.add_systems(Update, deck_indicator_system);
+		app.update();
+		assert_eq!(app.world.resource::<DeckIndicator>().text, "");
+		app.world.send_event(PlayerMoved { posn: Position::new(4, 1, 0) });
+		app.update();
+		assert_eq!(app.world.resource::<DeckIndicator>().text, "D0 ▲");
+		app.world.send_event(PlayerMoved { posn: Position::new(2, 1, 1) });
+		app.update();
+		assert_eq!(app.world.resource::<DeckIndicator>().text, "D1");
+	}
+	//  ##: Ambience
+	/// Sets up a headless App with two decks joined by stairs, where the upper deck has lost its air, and the systems
+	/// that turn the player's movements into ambient cues
+	fn ambience_app() -> App {
+		let mut app = App::new();
+		let mut model = crate::testmap::TestMap::from_decks(&crate::testmap::TWO_DECKS_WITH_STAIRS).model;
+		model.levels[1].environment = LevelEnvironment::NoAtmosphere;
+		app.add_event::<PlayerMoved>()
+		.add_event::<AmbientCue>()
+		.insert_resource(model)
+		.insert_resource(MessageLog::new(vec!["world".to_string(), "planq".to_string()]))
+		.add_systems(Update, (ambient_cue_system, ambience_system).chain());
+		app
+	}
+	/// Moves the player to the given Position and returns whatever cues that sent
+	fn move_player(app: &mut App, posn: Position) -> Vec<CueKind> {
+		app.world.send_event(PlayerMoved { posn });
+		app.update();
+		let mut events = app.world.resource_mut::<Events<AmbientCue>>();
+		let cues = events.get_reader().iter(&events).map(|x| x.cue).collect();
+		events.clear();
+		cues
+	}
+	#[test]
+	fn entering_an_airless_deck_sends_its_cue() {
+		let mut app = ambience_app();
+		assert_eq!(move_player(&mut app, Position::new(1, 1, 0)), vec![CueKind::EnterDeck(LevelEnvironment::Nominal)]);
+		assert_eq!(move_player(&mut app, Position::new(2, 1, 0)), Vec::new());
+		assert_eq!(move_player(&mut app, Position::new(5, 1, 1)), vec![CueKind::EnterDeck(LevelEnvironment::NoAtmosphere)]);
+		let expected = format!("[[fg:gray]]{}[[end]]", LevelEnvironment::NoAtmosphere.line());
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some(expected.as_str()));
+	}
+	#[test]
+	fn stepping_into_vacuum_sends_a_hull_breach() {
+		let mut app = ambience_app();
+		let index = app.world.resource::<WorldModel>().levels[0].to_index(3, 1);
+		app.world.resource_mut::<WorldModel>().levels[0].tiles[index] = Tile::new_vacuum();
+		move_player(&mut app, Position::new(2, 1, 0));
+		assert_eq!(move_player(&mut app, Position::new(3, 1, 0)), vec![CueKind::HullBreach]);
+		// Staying out in the vacuum doesn't keep sounding the alarm
+		let index = app.world.resource::<WorldModel>().levels[0].to_index(4, 1);
+		app.world.resource_mut::<WorldModel>().levels[0].tiles[index] = Tile::new_vacuum();
+		assert_eq!(move_player(&mut app, Position::new(4, 1, 0)), Vec::new());
+	}
+	#[test]
+	fn deck_conditions_are_read_from_the_plans() {
+		assert_eq!(LevelEnvironment::from_name("no_atmosphere"), LevelEnvironment::NoAtmosphere);
+		assert_eq!(LevelEnvironment::from_name("Unpowered"), LevelEnvironment::Unpowered);
+		assert_eq!(LevelEnvironment::from_name(""), LevelEnvironment::Nominal);
+		assert_eq!(LevelEnvironment::from_name("haunted"), LevelEnvironment::Nominal);
+	}
+	//  ##: Field of view
+	/// Sets up a headless App with the visibility_system, and a player who can see a long way standing at the given
+	/// spot on the CORRIDOR_WITH_DOOR, which is a lot smaller than their sight range
+	fn sight_app(p_posn: Position) -> (App, Entity) {
+		let mut app = App::new();
+		app.insert_resource(crate::testmap::TestMap::new(crate::testmap::CORRIDOR_WITH_DOOR).model)
+		.insert_resource(Preferences::default())
+		.add_systems(Update, visibility_system);
+		let player = app.world.spawn((Player::default(), Body::small(p_posn, ScreenCell::new()), Viewshed::new(20), Memory::new())).id();
+		(app, player)
+	}
+	#[test]
+	fn a_view_that_runs_off_the_edge_of_the_map_is_cut_short() {
+		for algorithm in [FovAlgorithm::Symmetric, FovAlgorithm::Bracket] {
+			let (mut app, player) = sight_app(Position::new(1, 1, 0));
+			app.world.resource_mut::<Preferences>().fov_algorithm = algorithm;
+			app.update();
+			let view = app.world.get::<Viewshed>(player).unwrap();
+			assert!(!view.dirty, "{:?}", algorithm);
+			assert!(view.visible_points.contains(&Point::new(8, 1)), "{:?}", algorithm);
+			let level = &app.world.resource::<WorldModel>().levels[0];
+			assert!(view.visible_points.iter().all(|p| level.in_bounds(p.x, p.y)), "{:?}: {:?}", algorithm, view.visible_points);
+		}
+	}
+	#[test]
+	fn a_seer_off_the_map_sees_nothing() {
+		for p_posn in [Position::new(-3, 1, 0), Position::new(40, 1, 0), Position::new(1, -1, 0), Position::new(1, 1, 5)] {
+			let (mut app, player) = sight_app(p_posn);
+			app.update();
+			let view = app.world.get::<Viewshed>(player).unwrap();
+			assert!(view.visible_points.is_empty(), "{}", p_posn);
+			assert!(!view.dirty, "{}", p_posn);
+			assert!(!app.world.resource::<WorldModel>().levels[0].revealed_tiles.contains(&true), "{}", p_posn);
+		}
+	}
+	#[test]
+	fn a_distant_console_lights_up_what_the_player_can_see() {
+		let mut app = App::new();
+		let mut model = crate::testmap::TestMap::new("\
+####################
+#..................#
+####################").model;
+		model.levels[0].ambient_light = false; // The ship's lights are out
+		app.insert_resource(model)
+		.insert_resource(Preferences::default())
+		.insert_resource(PowerGrid::default())
+		.add_systems(Update, (lighting_system, visibility_system).chain());
+		let player = app.world.spawn((Player::default(), Body::small(Position::new(1, 1, 0), ScreenCell::new()), Viewshed::new(20))).id();
+		let console = app.world.spawn((Body::small(Position::new(15, 1, 0), ScreenCell::new()), LightSource::new(2))).id();
+		app.update();
+		let view = app.world.get::<Viewshed>(player).unwrap();
+		// The player has no light of their own, but they can see the console and the floor around it from across the room
+		for x_posn in 13..=17 {
+			assert!(view.visible_points.contains(&Point::new(x_posn, 1)), "{}: {:?}", x_posn, view.visible_points);
+		}
+		assert!(!view.visible_points.contains(&Point::new(8, 1)), "{:?}", view.visible_points);
+		let level = &app.world.resource::<WorldModel>().levels[0];
+		assert!(level.revealed_tiles[level.to_index(15, 1)]);
+		assert!(!level.revealed_tiles[level.to_index(8, 1)]);
+		// Once the console is switched off, there's nothing left to see by
+		app.world.get_mut::<LightSource>(console).unwrap().is_on = false;
+		app.update();
+		let view = app.world.get::<Viewshed>(player).unwrap();
+		assert!(view.visible_points.iter().all(|p| *p == Point::new(1, 1)), "{:?}", view.visible_points);
+	}
+	//  ##: Examining terrain
+	#[test]
+	fn examining_an_empty_stairway_names_it_and_its_room() {
+		let mut app = App::new();
+		let testmap = crate::testmap::TestMap::from_decks(&crate::testmap::TWO_DECKS_WITH_STAIRS)
+			.with_room("engine room", Position::new(1, 1, 0), Position::new(5, 1, 0));
+		app.add_event::<GameEvent>()
+		.insert_resource(MessageLog::new(vec!["world".to_string()]))
+		.insert_resource(testmap.model)
+		.add_systems(Update, examination_system);
+		let player = app.world.spawn(Player::default()).id();
+		app.world.send_event(GameEvent::new(PlayerAction(ActionType::Examine), Some(player), None).at(Position::new(5, 1, 0)));
+		app.update();
+		assert_eq!(app.world.resource::<MessageLog>().last_text("world"), Some("SCAN: stairway in engine room, deck 0; passable, transparent."));
+	}
+}
+
 // EOF
@@ -0,0 +1,247 @@
+// testmap.rs
+// Provides a quick way to lay out a small map from text art, for trying out movement, doors, and sight lines without
+// having to generate a whole ship; only built for tests and with the testkit feature
+/* The glyphs that the layouts understand:
+ *   '#' - wall
+ *   '.' - floor
+ *   ' ' - vacuum, which is also what any short row is padded out with
+ *   '+' - a door, spawned on a floor tile by the ItemBuilder
+ *   '<' - a stairway up, linked to the matching '>' on the deck above
+ *   '>' - a stairway down, linked to the matching '<' on the deck below
+ *   '@' - floor, and where the player starts
+ * Anything else becomes floor, and is noted in the TestMap's warnings
+ */
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+
+// ###: INTERNAL LIBS
+use crate::artisan::ItemBuilder;
+use crate::components::*;
+use crate::mason::logical_map::{CellType, GraphRoom};
+use crate::worldmap::*;
+
+// ###: CONSTANTS
+/// The name of the door as it's known to the ItemBuilder
+pub const TESTMAP_DOOR_ITEM: &str = "door";
+/// A straight corridor with a door partway along it
+pub const CORRIDOR_WITH_DOOR: &str = "\
+#########
+#@..+...#
+#########";
+/// Two rooms joined by a door, with a pillar in the second one to hide behind
+pub const TWO_ROOMS_WITH_DOOR: &str = "\
+###########
+#...#.....#
+#.@.+..#..#
+#...#.....#
+###########";
+/// Two decks joined by a stairway; the stairs sit at the same spot on both decks
+pub const TWO_DECKS_WITH_STAIRS: [&str; 2] = ["\
+#######
+#@...<#
+#######", "\
+#######
+#....>#
+#######"];
+/// An airlock chamber with a door on either side, opening onto vacuum at the east end; the short rows around the
+/// middle one get padded out with more vacuum, and the chamber itself still needs marking out with with_room()
+pub const AIRLOCK_CHAMBER: &str = "\
+########
+#@..+..+   
+########";
+
+// ###: COMPLEX TYPES
+//  ##: TestMap
+/// A small WorldModel built from text art, along with the things that the art marked out for spawning
+#[derive(Clone, Debug, Default)]
+pub struct TestMap {
+	pub model: WorldModel,
+	pub player_start: Option<Position>,
+	pub doors: Vec<Position>,
+	pub stairs_up: Vec<Position>,
+	pub stairs_down: Vec<Position>,
+	pub warnings: Vec<String>, // Anything in the art that couldn't be understood, with where it was found
+}
+impl TestMap {
+	/// Builds a single-deck map from the given layout
+	pub fn new(layout: &str) -> TestMap {
+		TestMap::from_decks(&[layout])
+	}
+	/// Builds a map with a deck for each of the given layouts, from deck 0 upward, and links the stairways between
+	/// each pair of decks in the order that they appear, reading left to right and top to bottom
+	pub fn from_decks(layouts: &[&str]) -> TestMap {
+		let mut testmap = TestMap::default();
+		for (deck, layout) in layouts.iter().enumerate() {
+			let rows: Vec<Vec<char>> = layout.lines().map(|x| x.chars().collect()).collect();
+			let width = rows.iter().map(|x| x.len()).max().unwrap_or(0);
+			let mut level = WorldMap::new(width, rows.len());
+			for (y_posn, row) in rows.iter().enumerate() {
+				for x_posn in 0..width {
+					let posn = Position::new(x_posn as i32, y_posn as i32, deck as i32);
+					let glyph = row.get(x_posn).copied().unwrap_or(' ');
+					let tile = match glyph {
+						'#' => { Tile::new_wall() }
+						' ' => { Tile::new_vacuum() }
+						'.' => { Tile::new_floor() }
+						'+' => { testmap.doors.push(posn); Tile::new_floor() }
+						'<' => { testmap.stairs_up.push(posn); Tile::new_stairway() }
+						'>' => { testmap.stairs_down.push(posn); Tile::new_stairway() }
+						'@' => {
+							if testmap.player_start.is_some() {
+								testmap.warnings.push(format!("extra player start at {}", posn));
+							} else {
+								testmap.player_start = Some(posn);
+							}
+							Tile::new_floor()
+						}
+						other => {
+							testmap.warnings.push(format!("unknown glyph '{}' at {}", other, posn));
+							Tile::new_floor()
+						}
+					};
+					let index = level.to_index(posn.x, posn.y);
+					level.tiles[index] = tile;
+				}
+			}
+			level.update_tilemaps();
+			testmap.model.levels.push(level);
+		}
+		// Each deck's stairs up meet the stairs down on the deck above, pairing them off in reading order
+		for deck in 0..layouts.len() as i32 {
+			let ups: Vec<Position> = testmap.stairs_up.iter().filter(|x| x.z == deck).copied().collect();
+			let downs: Vec<Position> = testmap.stairs_down.iter().filter(|x| x.z == deck + 1).copied().collect();
+			if ups.len() != downs.len() {
+				testmap.warnings.push(format!("deck {} has {} stairs up but the deck above has {} down", deck, ups.len(), downs.len()));
+			}
+			for (up, down) in ups.iter().zip(downs.iter()) {
+				testmap.model.add_portal(*up, *down, true);
+			}
+		}
+		testmap
+	}
+	/// Marks out a named room on the map, covering every passable tile in the rectangle between the two corners
+	pub fn with_room(mut self, name: &str, ul_corner: Position, dr_corner: Position) -> TestMap {
+		let mut interior = HashMap::new();
+		for y_posn in ul_corner.y..=dr_corner.y {
+			for x_posn in ul_corner.x..=dr_corner.x {
+				let posn = Position::new(x_posn, y_posn, ul_corner.z);
+				if self.model.contains(posn) && self.model.get_tiletype_at(posn).properties().passable {
+					interior.insert(posn, CellType::Open);
+				}
+			}
+		}
+		self.model.layout.add_room(GraphRoom {
+			name: name.to_string(),
+			new_interior: interior,
+			centerpoint: Position::new((ul_corner.x + dr_corner.x) / 2, (ul_corner.y + dr_corner.y) / 2, ul_corner.z),
+			ul_corner,
+			dr_corner,
+			..GraphRoom::default()
+		});
+		self
+	}
+	/// Spawns the doors with the ItemBuilder, gives them their wall pockets the same way that a new game does, and puts
+	/// the finished model into the World, along with the player's starting Position if the art had one
+	/// Returns the doors that were spawned, in the same order as the doors list
+	pub fn spawn(mut self, world: &mut World, artisan: &mut ItemBuilder) -> Vec<Entity> {
+		let mut spawned = Vec::new();
+		for posn in self.doors.iter() {
+			for (d_enty, d_shape) in artisan.create(TESTMAP_DOOR_ITEM).at(*posn).build(world).iter() {
+				self.model.add_contents(d_shape, 0, d_enty.id());
+				spawned.push(d_enty.id());
+			}
+		}
+		// Any door that sits in a straight wall gets a pocket in that wall to slide into when it opens
+		for d_enty in spawned.iter() {
+			let doorway = if let Some(d_body) = world.get::<Body>(*d_enty) {
+				if d_body.extent.len() > 1 { continue; }
+				d_body.ref_posn
+			} else { continue };
+			let pocket = if let Some(value) = self.model.find_door_pocket(doorway) { value } else { continue };
+			let is_open = if let Some(mut d_open) = world.get_mut::<Openable>(*d_enty) {
+				d_open.slides(doorway, pocket);
+				d_open.is_open
+			} else { continue };
+			if is_open { // Doors that start out open need to be put away first
+				if let Some(mut d_body) = world.get_mut::<Body>(*d_enty) {
+					self.model.remove_contents(&d_body.posns(), *d_enty);
+					d_body.move_to(pocket);
+					self.model.add_contents(&d_body.posns(), 0, *d_enty);
+				}
+			}
+		}
+		if let Some(start) = self.player_start {
+			world.insert_resource(start);
+		}
+		world.insert_resource(self.model);
+		spawned
+	}
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use super::*;
+	//  ##: parsing
+	#[test]
+	fn short_rows_are_padded_with_vacuum() {
+		let testmap = TestMap::new(AIRLOCK_CHAMBER);
+		let level = &testmap.model.levels[0];
+		assert_eq!(level.width, 11);
+		// The end of the top row is past its last glyph, so it's vacuum rather than wall
+		assert_eq!(testmap.model.get_tiletype_at(Position::new(10, 0, 0)), TileType::Vacuum);
+		assert_eq!(testmap.model.get_tiletype_at(Position::new(7, 0, 0)), TileType::Wall);
+		assert_eq!(testmap.model.get_tiletype_at(Position::new(9, 1, 0)), TileType::Vacuum);
+		assert!(testmap.warnings.is_empty());
+	}
+	#[test]
+	fn doors_and_the_player_start_are_picked_out() {
+		let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR);
+		assert_eq!(testmap.player_start, Some(Position::new(2, 2, 0)));
+		assert_eq!(testmap.doors, vec![Position::new(4, 2, 0)]);
+		// The doors themselves come from the ItemBuilder, so the tile underneath is plain floor
+		assert_eq!(testmap.model.get_tiletype_at(Position::new(4, 2, 0)), TileType::Floor);
+	}
+	#[test]
+	fn unknown_glyphs_become_floor_with_a_warning() {
+		let testmap = TestMap::new("###\n#X#\n###");
+		assert_eq!(testmap.model.get_tiletype_at(Position::new(1, 1, 0)), TileType::Floor);
+		assert_eq!(testmap.warnings, vec!["unknown glyph 'X' at 1, 1, 0".to_string()]);
+	}
+	#[test]
+	fn only_the_first_player_start_counts() {
+		let testmap = TestMap::new("#@.@#");
+		assert_eq!(testmap.player_start, Some(Position::new(1, 0, 0)));
+		assert_eq!(testmap.warnings, vec!["extra player start at 3, 0, 0".to_string()]);
+	}
+	//  ##: stairways
+	#[test]
+	fn stairways_are_linked_between_decks() {
+		let mut testmap = TestMap::from_decks(&TWO_DECKS_WITH_STAIRS);
+		let up = Position::new(5, 1, 0);
+		let down = Position::new(5, 1, 1);
+		assert_eq!(testmap.stairs_up, vec![up]);
+		assert_eq!(testmap.stairs_down, vec![down]);
+		assert_eq!(testmap.model.get_exit(up), Some(down));
+		assert_eq!(testmap.model.get_exit(down), Some(up));
+		assert!(testmap.warnings.is_empty());
+	}
+	#[test]
+	fn unmatched_stairways_are_reported() {
+		let testmap = TestMap::from_decks(&["#<<#", "#>.#"]);
+		assert_eq!(testmap.warnings, vec!["deck 0 has 2 stairs up but the deck above has 1 down".to_string()]);
+	}
+	//  ##: rooms
+	#[test]
+	fn rooms_cover_only_the_passable_tiles() {
+		let testmap = TestMap::new(TWO_ROOMS_WITH_DOOR)
+			.with_room("west room", Position::new(0, 0, 0), Position::new(3, 4, 0));
+		assert_eq!(testmap.model.layout.get_room_name(Position::new(2, 2, 0)), Some("west room".to_string()));
+		assert_eq!(testmap.model.layout.get_room_name(Position::new(0, 0, 0)), None);
+		assert_eq!(testmap.model.layout.get_room_name(Position::new(6, 2, 0)), None);
+	}
+}
+
+// EOF
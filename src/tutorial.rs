@@ -0,0 +1,156 @@
+// tutorial.rs
+// Provides the one-time hints that point new players towards the controls they're going to need
+
+// ###: EXTERNAL LIBS
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+// ###: INTERNAL LIBS
+use crate::components::*;
+use crate::engine::RunState;
+use crate::engine::preferences::Preferences;
+use crate::engine::messagelog::*;
+use crate::planq::PlanqData;
+
+// ###: CONSTANTS
+/// The tutorial's hints are sent to the message log at this priority, so that they stand out from the usual messages
+pub const HINT_PRIORITY: i32 = 2;
+
+// ###: BEVY SYSTEMS
+/// Checks the tutorial's rules once per turn, and shows the first hint that applies and hasn't been shown before
+pub fn tutorial_system(mut tutorial: ResMut<TutorialState>,
+	                     mut msglog:   ResMut<MessageLog>,
+	                     run_state:    Res<RunState>,
+	                     prefs:        Res<Preferences>,
+	                     planq:        Res<PlanqData>,
+	                     p_posn:       Res<Position>,
+	                     d_query:      Query<(&Body, &Openable), (Without<Portable>, Without<Container>)>,
+	                     i_query:      Query<(&Body, &Portable)>,
+) {
+	if !prefs.show_hints || tutorial.last_turn == Some(run_state.turn_count) { return; }
+	tutorial.last_turn = Some(run_state.turn_count);
+	let situation = Situation {
+		door_adjacent: door_is_adjacent(*p_posn, d_query.iter()),
+		item_underfoot: i_query.iter().any(|(i_body, i_portable)| i_portable.carrier == Entity::PLACEHOLDER && i_body.contains(&p_posn)),
+		planq_unpowered: planq.is_carried && !planq.power_is_on,
+	};
+	if let Some(hint) = tutorial.next_hint(&situation) {
+		msglog.add(format!("[[fg:ltyellow]]HINT:[[end]] {}", hint.text()).as_str(), "world", HINT_PRIORITY, 0);
+	}
+}
+
+// ###: COMPLEX TYPES
+//  ##: TutorialState
+/// Remembers which hints have already been shown in this game, so that none of them are shown twice
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct TutorialState {
+	pub fired: Vec<TutorialHint>,
+	pub last_turn: Option<u64>, // The turn that the rules were last checked on
+}
+impl TutorialState {
+	pub fn new() -> TutorialState {
+		TutorialState::default()
+	}
+	/// Returns the first hint that applies to the given situation and hasn't been shown yet, and marks it as shown
+	pub fn next_hint(&mut self, situation: &Situation) -> Option<TutorialHint> {
+		let hint = TutorialHint::iter().find(|x| !self.fired.contains(x) && x.applies(situation))?;
+		self.fired.push(hint);
+		Some(hint)
+	}
+}
+//  ##: TutorialHint
+/// The hints that the tutorial can show, in the order that they're checked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, EnumIter)]
+pub enum TutorialHint {
+	#[default]
+	OpenDoor,
+	GetItem,
+	PowerPlanq,
+}
+impl TutorialHint {
+	/// The rule that decides whether this hint is relevant to what's going on around the player
+	pub fn applies(&self, situation: &Situation) -> bool {
+		match self {
+			TutorialHint::OpenDoor   => { situation.door_adjacent }
+			TutorialHint::GetItem    => { situation.item_underfoot }
+			TutorialHint::PowerPlanq => { situation.planq_unpowered }
+		}
+	}
+	pub fn text(&self) -> &'static str {
+		match self {
+			TutorialHint::OpenDoor   => { "Press o to open doors." }
+			TutorialHint::GetItem    => { "Press g to pick up the things at your feet." }
+			TutorialHint::PowerPlanq => { "Your PLANQ is switched off; press a to turn it on." }
+		}
+	}
+}
+//  ##: Situation
+/// A summary of the player's surroundings, worked out once per turn for the hint rules to check against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Situation {
+	pub door_adjacent: bool, // There's a closed door right next to the player
+	pub item_underfoot: bool, // There's something on the ground where the player is standing
+	pub planq_unpowered: bool, // The player is carrying the PLANQ but it's switched off
+}
+
+// ###: UTILITIES
+/// Returns true if any of the given doors is closed and right next to the given Position
+pub fn door_is_adjacent<'a>(posn: Position, mut doors: impl Iterator<Item = (&'a Body, &'a Openable)>) -> bool {
+	doors.any(|(d_body, d_open)| !d_open.is_open && d_body.is_adjacent_to(&posn))
+}
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use crate::camera::ScreenCell;
+	use super::*;
+	//  ##: Fire-once
+	#[test]
+	fn each_hint_is_only_shown_once() {
+		let mut tutorial = TutorialState::new();
+		let situation = Situation { door_adjacent: true, ..Situation::default() };
+		assert_eq!(tutorial.next_hint(&situation), Some(TutorialHint::OpenDoor));
+		assert_eq!(tutorial.next_hint(&situation), None);
+	}
+	#[test]
+	fn hints_are_shown_one_at_a_time_in_order() {
+		let mut tutorial = TutorialState::new();
+		let situation = Situation { door_adjacent: true, item_underfoot: true, planq_unpowered: true };
+		assert_eq!(tutorial.next_hint(&situation), Some(TutorialHint::OpenDoor));
+		assert_eq!(tutorial.next_hint(&situation), Some(TutorialHint::GetItem));
+		assert_eq!(tutorial.next_hint(&situation), Some(TutorialHint::PowerPlanq));
+		assert_eq!(tutorial.next_hint(&situation), None);
+	}
+	#[test]
+	fn nothing_is_shown_when_nothing_applies() {
+		let mut tutorial = TutorialState::new();
+		assert_eq!(tutorial.next_hint(&Situation::default()), None);
+		assert!(tutorial.fired.is_empty());
+	}
+	//  ##: door_is_adjacent
+	fn door(posn: Position, is_open: bool) -> (Body, Openable) {
+		let mut d_open = Openable::new(false, "/", "+");
+		d_open.is_open = is_open;
+		(Body::small(posn, ScreenCell::default()), d_open)
+	}
+	#[test]
+	fn a_closed_door_next_to_the_player_counts() {
+		let doors = [door(Position::new(5, 4, 0), false)];
+		assert!(door_is_adjacent(Position::new(4, 4, 0), doors.iter().map(|(x, y)| (x, y))));
+		assert!(door_is_adjacent(Position::new(4, 3, 0), doors.iter().map(|(x, y)| (x, y))));
+	}
+	#[test]
+	fn an_open_or_distant_door_does_not_count() {
+		let doors = [door(Position::new(5, 4, 0), true), door(Position::new(9, 4, 0), false)];
+		assert!(!door_is_adjacent(Position::new(4, 4, 0), doors.iter().map(|(x, y)| (x, y))));
+	}
+	#[test]
+	fn a_door_on_another_deck_does_not_count() {
+		let doors = [door(Position::new(5, 4, 1), false)];
+		assert!(!door_is_adjacent(Position::new(4, 4, 0), doors.iter().map(|(x, y)| (x, y))));
+	}
+}
+
+// EOF
@@ -4,7 +4,7 @@
 // ###: EXTERNAL LIBS
 use std::fmt;
 use std::fmt::Display;
-use bracket_algorithm_traits::prelude::{Algorithm2D, BaseMap};
+use bracket_algorithm_traits::prelude::{Algorithm2D, BaseMap, SmallVec};
 use bracket_geometry::prelude::*;
 use bevy::prelude::{
 	Entity,
@@ -12,6 +12,7 @@ use bevy::prelude::{
 	ReflectResource,
 	Resource,
 };
+use serde::{Deserialize, Serialize};
 use simplelog::*;
 use bevy_turborand::*;
 
@@ -31,6 +32,20 @@ pub const MAPSIZE: i32 = MAPWIDTH * MAPHEIGHT;
 pub fn xy_to_index(x: usize, y: usize, w: usize) -> usize {
 	(y * w) + x
 }
+/// Returns true if nothing opaque stands on the straight line between the two Positions; the endpoints themselves are
+/// never counted, so that a closed door can still be seen from the far side of the room
+/// A line that only grazes the corner of a wall is let through if the line drawn back the other way gets past it
+pub fn has_line_of_sight(model: &WorldModel, from: Position, to: Position) -> bool {
+	if from.z != to.z || !model.contains(from) || !model.contains(to) { return false; }
+	let level = &model.levels[from.z as usize];
+	let is_clear = |start: Position, end: Position| {
+		// The Bresenham iterator includes its start point but not its end point
+		Bresenham::new(Point::new(start.x, start.y), Point::new(end.x, end.y))
+			.skip(1)
+			.all(|point| !level.opaque_tiles[level.to_index(point.x, point.y)])
+	};
+	is_clear(from, to) || is_clear(to, from)
+}
 
 // ###: STRUCTS
 //  ##: WorldModel
@@ -73,11 +88,61 @@ impl WorldModel {
 			portal
 		}
 	}
+	/// Reports whether there's a ladder going up and/or down that can be reached from the given Position, ie by standing
+	/// on it or right next to it; returns (up, down)
+	pub fn ladder_access(&self, target: Position) -> (bool, bool) {
+		let mut access = (false, false);
+		for portal in self.portals.iter() {
+			for (entry, exit) in [(portal.left, portal.right), (portal.right, portal.left)] {
+				if entry == portal.right && !portal.bidir { continue; }
+				if !entry.in_range_of(&target, 1) { continue; }
+				if exit.z > target.z { access.0 = true; }
+				if exit.z < target.z { access.1 = true; }
+			}
+		}
+		access
+	}
+	/// Produces the deck indicator for the given Position, ie "D2 ENG ▲▼": the deck number, a short code for the zone,
+	/// and an arrow for each way that a nearby ladder goes
+	pub fn depth_indicator(&self, target: Position) -> String {
+		let zone = self.layout.get_room_name(target).unwrap_or_default()
+			.chars().filter(|x| x.is_alphanumeric()).take(3).collect::<String>().to_uppercase();
+		let (up, down) = self.ladder_access(target);
+		let mut output = format!("D{}", target.z);
+		if !zone.is_empty() { output = format!("{} {}", output, zone); }
+		if up || down { output.push(' '); }
+		if up { output.push('▲'); }
+		if down { output.push('▼'); }
+		output
+	}
+	/// Returns true if the given Position lies inside one of the maps in this model, ie is safe to use as an index
+	pub fn contains(&self, target: Position) -> bool {
+		if target.z < 0 || target.z as usize >= self.levels.len() { return false; }
+		self.levels[target.z as usize].in_bounds(target.x, target.y)
+	}
 	/// Retrieve the tiletype of the given Position
 	pub fn get_tiletype_at(&self, target: Position) -> TileType {
 		let index = self.levels[target.z as usize].to_index(target.x, target.y);
 		self.levels[target.z as usize].tiles[index].ttype
 	}
+	/// Produces the engineering scan of the given map tile, for when the player examines the terrain itself
+	pub fn describe_tile(&self, target: Position) -> Vec<String> {
+		if !self.contains(target) { return vec!["Your scan returns nothing but static.".to_string()]; }
+		let props = self.get_tiletype_at(target).properties();
+		// TODO: report the light level, atmosphere, and any hidden features once the map keeps track of them
+		vec![
+			props.desc.to_string(),
+			format!("SCAN: {}, deck {}; {}, {}.", self.describe_terrain(target), target.z,
+			        if props.passable { "passable" } else { "impassable" },
+			        if props.opaque { "opaque" } else { "transparent" }),
+		]
+	}
+	/// Names the kind of terrain at the given Position and the room that it's in, ie "stairway in Engineering"
+	pub fn describe_terrain(&self, target: Position) -> String {
+		if !self.contains(target) { return "nothing".to_string(); }
+		let zone = self.layout.get_room_name(target).unwrap_or("an unmapped area".to_string());
+		format!("{} in {}", self.get_tiletype_at(target), zone)
+	}
 	/// Adds the given Entity as an occupant at the specified positions, with the given priority
 	pub fn add_contents(&mut self, posns: &Vec<Position>, priority: i32, enty: Entity) {
 		trace!("add_contents: {:?} for enty {:?} at priority {}", posns, enty, priority); // DEBUG: log the call to add_contents
@@ -139,6 +204,21 @@ impl WorldModel {
 		}
 		None
 	}
+	/// Looks for a wall tile next to the given doorway that a sliding door could be tucked into; the doorway must be
+	/// part of a straight wall, so that the door slides along the wall instead of into it
+	pub fn find_door_pocket(&self, doorway: Position) -> Option<Position> {
+		for (x_diff, y_diff) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+			let pocket = doorway + (x_diff, y_diff, 0);
+			let opposite = doorway + (-x_diff, -y_diff, 0);
+			if !self.contains(pocket) || !self.contains(opposite) { continue; }
+			if self.get_tiletype_at(pocket) == TileType::Wall
+			&& self.get_tiletype_at(opposite) == TileType::Wall
+			&& self.get_contents_at(pocket).is_empty() {
+				return Some(pocket);
+			}
+		}
+		None
+	}
 	/// Returns a list of Room names in the topology of the ship
 	pub fn get_room_name_list(&self) -> Vec<String> {
 		self.layout.get_room_list()
@@ -151,6 +231,23 @@ impl WorldModel {
 	pub fn set_opaque_state(&mut self, target: Position, state: bool) {
 		self.levels[target.z as usize].set_opaque(target, state);
 	}
+	/// Marks every tile on the given deck as having been seen, ie from a downloaded map; returns false if there's no such deck
+	pub fn reveal_deck(&mut self, deck: i32) -> bool {
+		if deck < 0 { return false; }
+		if let Some(level) = self.levels.get_mut(deck as usize) {
+			level.revealed_tiles.fill(true);
+			return true;
+		}
+		false
+	}
+}
+//   ##: DeckIndicator
+/// Holds the player's deck indicator as made by WorldModel::depth_indicator(), so that it only gets redone when the
+/// player moves instead of on every frame
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct DeckIndicator {
+	pub text: String,
 }
 //   ##: WorldMap
 /// Represents a single layer of physical space in the game world
@@ -164,6 +261,9 @@ pub struct WorldMap {
 	pub visible_tiles: Vec<bool>,
 	pub blocked_tiles: Vec<bool>,
 	pub opaque_tiles: Vec<bool>,
+	pub lit_tiles: Vec<bool>, // Tiles that have enough light on them to be seen by, see the lighting_system
+	pub ambient_light: bool, // If true, the whole deck is lit, ie the ship's lights are on
+	pub environment: LevelEnvironment, // The general state of the deck, for setting the mood when the player arrives
 }
 impl WorldMap {
 	/// Generates a map from the default settings
@@ -177,6 +277,9 @@ impl WorldMap {
 			visible_tiles: vec![false; map_size],
 			blocked_tiles: vec![false; map_size],
 			opaque_tiles: vec![false; map_size],
+			lit_tiles: vec![true; map_size],
+			ambient_light: true,
+			environment: LevelEnvironment::default(),
 		}
 	}
 	/// Converts an x, y pair into a tilemap index using the given map's width
@@ -186,17 +289,21 @@ impl WorldMap {
 		// ... yes, it DEFINITELY will ( TT n TT)
 		((y * self.width as i32) + x) as usize
 	}
-	/// Returns true if the tiletype is Wall
+	/// Returns true if the given x, y coordinates fall inside this map's dimensions
+	pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+		x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32
+	}
+	/// Returns true if the tiletype is not passable, ie a Wall
 	pub fn is_occupied(&self, target: Position) -> bool {
 		let index = self.to_index(target.x, target.y);
-		if self.tiles[index].ttype == TileType::Wall { return true }
-		false
+		!self.tiles[index].ttype.properties().passable
 	}
 	/// Walks through the map and populates the blocked_tiles and opaque_tiles maps according to the TileTypes
 	pub fn update_tilemaps(&mut self) {
 		for (index, tile) in self.tiles.iter_mut().enumerate() {
-			self.blocked_tiles[index] = tile.ttype == TileType::Wall;
-			self.opaque_tiles[index] = tile.ttype == TileType::Wall;
+			let props = tile.ttype.properties();
+			self.blocked_tiles[index] = !props.passable;
+			self.opaque_tiles[index] = props.opaque;
 		}
 	}
 	/// Obtains the Tile data from the given position and creates a ScreenCell to display it
@@ -250,13 +357,70 @@ impl BaseMap for WorldMap {
 	fn is_opaque(&self, index: usize) -> bool {
 		self.opaque_tiles[index]
 	}
-	//fn get_available_exits(&self, index: usize) -> SmallVec<[(usize, f32); 10]> {
-		// "Returns a vector of tile indices to which one can path from the index"
-		// "Does not need to be contiguous (teleports OK); do NOT return current tile as an exit"
-	//}
-	//fn get_pathing_distance(&self, indexStart: usize, indexFinish: usize) _> f32 {
-		// "Return the distance you would like to use for path-finding"
-	//}
+	/// Returns a vector of tile indices to which one can path from the index; does NOT include the current tile
+	fn get_available_exits(&self, index: usize) -> SmallVec<[(usize, f32); 10]> {
+		let mut exits = SmallVec::new();
+		let x = (index % self.width) as i32;
+		let y = (index / self.width) as i32;
+		for (x_diff, y_diff, cost) in [(-1, 0, 1.0), (1, 0, 1.0), (0, -1, 1.0), (0, 1, 1.0),
+		                               (-1, -1, 1.45), (1, -1, 1.45), (-1, 1, 1.45), (1, 1, 1.45)] {
+			let (new_x, new_y) = (x + x_diff, y + y_diff);
+			if new_x < 0 || new_x >= self.width as i32 || new_y < 0 || new_y >= self.height as i32 { continue; }
+			let exit = self.to_index(new_x, new_y);
+			if !self.blocked_tiles[exit] {
+				exits.push((exit, cost));
+			}
+		}
+		exits
+	}
+	/// Returns the distance to use for the pathfinding heuristic
+	fn get_pathing_distance(&self, index_start: usize, index_finish: usize) -> f32 {
+		let start = Point::new(index_start % self.width, index_start / self.width);
+		let finish = Point::new(index_finish % self.width, index_finish / self.width);
+		DistanceAlg::Pythagoras.distance2d(start, finish)
+	}
+}
+//   ##: NavMap
+/// Wraps a WorldMap for pathfinding on behalf of a particular actor, so that the tile costs can account for the
+/// hazards that the actor can or can't tolerate
+pub struct NavMap<'a> {
+	pub map: &'a WorldMap,
+	pub caps: NavCaps,
+	pub avoid: Vec<usize>, // Tiles that the actor found blocked in person, which the map itself doesn't know about
+}
+impl<'a> NavMap<'a> {
+	pub fn new(map: &'a WorldMap, caps: NavCaps) -> NavMap<'a> {
+		NavMap { map, caps, avoid: Vec::new() }
+	}
+	/// Leaves the given Positions out of any path, as if they were walls
+	pub fn avoiding(mut self, posns: &[Position]) -> NavMap<'a> {
+		self.avoid = posns.iter().map(|x| self.map.to_index(x.x, x.y)).collect();
+		self
+	}
+}
+impl Algorithm2D for NavMap<'_> {
+	fn dimensions(&self) -> Point {
+		self.map.dimensions()
+	}
+}
+impl BaseMap for NavMap<'_> {
+	fn is_opaque(&self, index: usize) -> bool {
+		self.map.is_opaque(index)
+	}
+	/// As for the WorldMap, but leaves out any tiles that would be lethal to the actor and charges extra for the risky ones
+	fn get_available_exits(&self, index: usize) -> SmallVec<[(usize, f32); 10]> {
+		let mut exits = SmallVec::new();
+		for (exit, cost) in self.map.get_available_exits(index) {
+			if self.avoid.contains(&exit) { continue; }
+			if let Some(penalty) = self.map.tiles[exit].ttype.properties().hazard.penalty(&self.caps) {
+				exits.push((exit, cost + penalty));
+			}
+		}
+		exits
+	}
+	fn get_pathing_distance(&self, index_start: usize, index_finish: usize) -> f32 {
+		self.map.get_pathing_distance(index_start, index_finish)
+	}
 }
 //    #: Tile
 /// Represents a single position within the game world
@@ -273,7 +437,7 @@ impl Tile {
 		self
 	}
 	pub fn glyph(mut self, new_glyph: &str) -> Self {
-		self.cell.glyph = new_glyph.to_string();
+		self.cell.glyph = checked_glyph(new_glyph);
 		self
 	}
 	pub fn colors(mut self, new_fg: Color, new_bg: Color) -> Self {
@@ -330,37 +494,29 @@ impl Tile {
 			index += 1;
 		}
 	}
-	/// Produces an 'empty space' tile
-	pub fn new_vacuum() -> Tile {
+	/// Produces a Tile of the given type, using that type's default appearance
+	pub fn new(new_type: TileType) -> Tile {
 		Tile {
-			ttype: TileType::Vacuum,
+			ttype: new_type,
 			contents: Vec::new(),
-			cell: ScreenCell::new_from_str("★ grey black none"),
+			cell: ScreenCell::new_from_str(new_type.properties().cell),
 		}
 	}
+	/// Produces an 'empty space' tile
+	pub fn new_vacuum() -> Tile {
+		Tile::new(TileType::Vacuum)
+	}
 	/// Produces a default 'floor' tile
 	pub fn new_floor() -> Tile {
-		Tile {
-			ttype: TileType::Floor,
-			contents: Vec::new(),
-			cell: ScreenCell::new_from_str(". grey black none"),
-		}
+		Tile::new(TileType::Floor)
 	}
 	/// Produces a default 'wall' tile
 	pub fn new_wall() -> Tile {
-		Tile {
-			ttype: TileType::Wall,
-			contents: Vec::new(),
-			cell: ScreenCell::new_from_str("╳ white black none"),
-		}
+		Tile::new(TileType::Wall)
 	}
 	/// Produces a default 'stairway' tile
 	pub fn new_stairway() -> Tile {
-		Tile {
-			ttype: TileType::Stairway,
-			contents: Vec::new(),
-			cell: ScreenCell::new_from_str("∑ white black none"),
-		}
+		Tile::new(TileType::Stairway)
 	}
 }
 impl Default for Tile {
@@ -431,6 +587,26 @@ pub enum TileType {
 	Wall,
 	Stairway,
 }
+impl TileType {
+	/// Looks up the shared properties of this TileType; anything that needs to know whether a tile blocks movement
+	/// or sight, or what it looks like by default, should derive it from here
+	pub fn properties(&self) -> TileProperties {
+		match self {
+			TileType::Vacuum   => TileProperties { passable: true,  opaque: false, is_portal: false, hazard: Hazard::Vacuum,
+			                                        cell: "★ grey black none",
+			                                        desc: "The open vacuum of space, with no hull between you and the stars." },
+			TileType::Floor    => TileProperties { passable: true,  opaque: false, is_portal: false, hazard: Hazard::None,
+			                                        cell: ". grey black none",
+			                                        desc: "A scuffed deck plate, bolted down over the ship's frame." },
+			TileType::Wall     => TileProperties { passable: false, opaque: true,  is_portal: false, hazard: Hazard::None,
+			                                        cell: "╳ white black none",
+			                                        desc: "A bulkhead, lined with conduit and the occasional access panel." },
+			TileType::Stairway => TileProperties { passable: true,  opaque: false, is_portal: true,  hazard: Hazard::None,
+			                                        cell: "∑ white black none",
+			                                        desc: "A ladder set into a narrow well, running between decks." },
+		}
+	}
+}
 impl Display for TileType {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let output = match self {
@@ -442,6 +618,92 @@ impl Display for TileType {
 		write!(f, "{}", output)
 	}
 }
+//   ##: TileProperties
+/// Describes the physical and visual properties shared by every Tile of a particular TileType
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileProperties {
+	pub passable: bool,
+	pub opaque: bool,
+	pub is_portal: bool, // ie a ladder that can be used to move between decks
+	pub hazard: Hazard, // Anything about the tile that could hurt an actor that passes through it
+	pub cell: &'static str, // The default appearance, in the format used by ScreenCell::new_from_str
+	pub desc: &'static str, // What the player sees when they examine a tile of this type
+}
+//   ##: Hazard
+/// Describes the dangers that a tile can pose to the actors moving through it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hazard {
+	#[default]
+	None,
+	Vacuum, // Lethal to anything that needs to breathe
+}
+impl Hazard {
+	/// Returns the extra pathing cost for an actor with the given NavCaps to cross this hazard, or None if it would be
+	/// lethal to them; damaging-but-survivable hazards should return a high cost, so that a safe detour wins out
+	pub fn penalty(&self, caps: &NavCaps) -> Option<f32> {
+		match self {
+			Hazard::None => { Some(0.0) }
+			Hazard::Vacuum => { if caps.breathes { None } else { Some(0.0) } }
+		}
+	}
+	/// The question that the player is asked before stepping into this hazard, or None if it's safe
+	pub fn warning(&self) -> Option<&'static str> {
+		match self {
+			Hazard::None => { None }
+			Hazard::Vacuum => { Some("Really step out into the vacuum?") }
+		}
+	}
+}
+//   ##: HazardPrompt
+/// Decides when the player is asked to confirm a step into a hazard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum HazardPrompt {
+	Always,
+	#[default]
+	FirstTime, // Only the first time for each kind of hazard at each spot
+	Never,
+}
+impl HazardPrompt {
+	/// Returns the next setting in the list, for the menu toggle
+	pub fn next(&self) -> HazardPrompt {
+		match self {
+			HazardPrompt::Always    => { HazardPrompt::FirstTime }
+			HazardPrompt::FirstTime => { HazardPrompt::Never }
+			HazardPrompt::Never     => { HazardPrompt::Always }
+		}
+	}
+}
+//   ##: LevelEnvironment
+/// Describes the overall condition of a deck, as the player would notice it on arrival
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum LevelEnvironment {
+	#[default]
+	Nominal,
+	FailingLifeSupport,
+	NoAtmosphere,
+	Unpowered,
+}
+impl LevelEnvironment {
+	/// The line of ambiance that sets the mood for a deck in this condition
+	pub fn line(&self) -> &'static str {
+		match self {
+			LevelEnvironment::Nominal            => { "The deck hums with the steady drone of the air recyclers." }
+			LevelEnvironment::FailingLifeSupport => { "The deck hums with failing life support." }
+			LevelEnvironment::NoAtmosphere       => { "There is no sound here at all; the air is gone." }
+			LevelEnvironment::Unpowered          => { "The deck is dark and still; nothing here has any power." }
+		}
+	}
+	/// Reads a deck's condition from the name that the ship's plans give it; anything unrecognized, including no name
+	/// at all, is taken to be Nominal
+	pub fn from_name(name: &str) -> LevelEnvironment {
+		match name.to_lowercase().as_str() {
+			"failing_life_support" => { LevelEnvironment::FailingLifeSupport }
+			"no_atmosphere"        => { LevelEnvironment::NoAtmosphere }
+			"unpowered"            => { LevelEnvironment::Unpowered }
+			_                      => { LevelEnvironment::Nominal }
+		}
+	}
+}
 //   ##: Obstructor
 /// Represents a 'thing' that is blocking movement by an Entity into a particular Tile;
 /// could be an Entity or just a particular TileType
@@ -450,4 +712,148 @@ pub enum Obstructor {
 	Actor(Entity),
 	Object(TileType),
 }
+
+// ###: TESTS
+#[cfg(test)]
+mod tests {
+	use crate::testmap::*;
+	use super::*;
+	//  ##: Line of sight
+	#[test]
+	fn an_open_room_is_all_in_sight() {
+		let model = TestMap::new(TWO_ROOMS_WITH_DOOR).model;
+		assert!(has_line_of_sight(&model, Position::new(1, 1, 0), Position::new(3, 3, 0)));
+		assert!(has_line_of_sight(&model, Position::new(2, 2, 0), Position::new(2, 2, 0)));
+		// The doorway lets the line through to the next room
+		assert!(has_line_of_sight(&model, Position::new(3, 2, 0), Position::new(6, 2, 0)));
+	}
+	#[test]
+	fn walls_block_the_line() {
+		let model = TestMap::new(TWO_ROOMS_WITH_DOOR).model;
+		assert!(!has_line_of_sight(&model, Position::new(3, 1, 0), Position::new(5, 1, 0)));
+		// The pillar in the east room hides what's behind it
+		assert!(!has_line_of_sight(&model, Position::new(6, 2, 0), Position::new(8, 2, 0)));
+	}
+	#[test]
+	fn the_endpoints_are_never_in_the_way() {
+		let model = TestMap::new(TWO_ROOMS_WITH_DOOR).model;
+		// A wall can itself be seen, and a wall can see out
+		assert!(has_line_of_sight(&model, Position::new(1, 1, 0), Position::new(4, 1, 0)));
+		assert!(has_line_of_sight(&model, Position::new(4, 1, 0), Position::new(1, 1, 0)));
+	}
+	#[test]
+	fn grazing_a_corner_is_let_through_both_ways() {
+		let model = TestMap::new(TWO_ROOMS_WITH_DOOR).model;
+		// The line between these passes halfway between the wall at 4, 1 and the doorway at 4, 2
+		let (west, east) = (Position::new(3, 1, 0), Position::new(5, 2, 0));
+		assert!(has_line_of_sight(&model, west, east));
+		assert!(has_line_of_sight(&model, east, west));
+	}
+	#[test]
+	fn a_diagonal_squeezes_between_two_corners() {
+		let model = TestMap::new("\
+#####
+#.#.#
+##..#
+#...#
+#####").model;
+		assert!(has_line_of_sight(&model, Position::new(1, 1, 0), Position::new(3, 3, 0)));
+		assert!(has_line_of_sight(&model, Position::new(3, 3, 0), Position::new(1, 1, 0)));
+	}
+	//  ##: Terrain
+	#[test]
+	fn terrain_is_named_with_its_room() {
+		let model = TestMap::from_decks(&TWO_DECKS_WITH_STAIRS)
+			.with_room("engine room", Position::new(1, 1, 0), Position::new(5, 1, 0))
+			.model;
+		assert_eq!(model.describe_terrain(Position::new(5, 1, 0)), "stairway in engine room");
+		assert_eq!(model.describe_terrain(Position::new(2, 1, 0)), "floor in engine room");
+		// The deck above was never marked out into rooms
+		assert_eq!(model.describe_terrain(Position::new(5, 1, 1)), "stairway in an unmapped area");
+		assert_eq!(model.describe_terrain(Position::new(5, 1, 7)), "nothing");
+	}
+	#[test]
+	fn there_is_no_line_between_decks_or_off_the_map() {
+		let model = TestMap::from_decks(&TWO_DECKS_WITH_STAIRS).model;
+		assert!(!has_line_of_sight(&model, Position::new(1, 1, 0), Position::new(1, 1, 1)));
+		assert!(!has_line_of_sight(&model, Position::new(1, 1, 0), Position::new(40, 1, 0)));
+	}
+	//  ##: Deck indicator
+	#[test]
+	fn the_deck_indicator_names_the_deck_zone_and_ladders() {
+		let model = TestMap::from_decks(&TWO_DECKS_WITH_STAIRS)
+			.with_room("engine room", Position::new(1, 1, 0), Position::new(5, 1, 0))
+			.model;
+		assert_eq!(model.depth_indicator(Position::new(1, 1, 0)), "D0 ENG");
+		// Standing right beside the stairs is close enough to use them
+		assert_eq!(model.depth_indicator(Position::new(4, 1, 0)), "D0 ENG ▲");
+		assert_eq!(model.depth_indicator(Position::new(5, 1, 0)), "D0 ENG ▲");
+		// The deck above has no rooms, so it only gets its number
+		assert_eq!(model.depth_indicator(Position::new(5, 1, 1)), "D1 ▼");
+		assert_eq!(model.depth_indicator(Position::new(2, 1, 1)), "D1");
+	}
+	#[test]
+	fn a_middle_deck_can_show_both_ways() {
+		let model = TestMap::from_decks(&["\
+#####
+#..<#
+#####", "\
+#####
+#.<>#
+#####", "\
+#####
+#.>.#
+#####"]).model;
+		assert_eq!(model.ladder_access(Position::new(2, 1, 1)), (true, true));
+		assert_eq!(model.depth_indicator(Position::new(2, 1, 1)), "D1 ▲▼");
+		assert_eq!(model.ladder_access(Position::new(1, 1, 0)), (false, false));
+	}
+	//  ##: Tile properties
+	/// Every TileType, in the order they're declared
+	const TILETYPES: [TileType; 4] = [TileType::Vacuum, TileType::Floor, TileType::Wall, TileType::Stairway];
+	#[test]
+	fn every_tiletype_blocks_and_hides_as_its_properties_say() {
+		let mut level = WorldMap::new(TILETYPES.len(), 1);
+		for (index, ttype) in TILETYPES.iter().enumerate() {
+			level.tiles[index] = Tile::new(*ttype);
+		}
+		level.update_tilemaps();
+		for (index, ttype) in TILETYPES.iter().enumerate() {
+			let props = ttype.properties();
+			assert_eq!(level.blocked_tiles[index], !props.passable, "{} blocking", ttype);
+			assert_eq!(level.opaque_tiles[index], props.opaque, "{} opacity", ttype);
+			assert_eq!(level.is_occupied(Position::new(index as i32, 0, 0)), !props.passable, "{} occupancy", ttype);
+			assert_eq!(level.tiles[index].cell, ScreenCell::new_from_str(props.cell), "{} appearance", ttype);
+		}
+	}
+	#[test]
+	fn every_tiletype_gets_its_own_scan() {
+		let mut model = TestMap::new(" .#.").model;
+		model.levels[0].tiles[3] = Tile::new(TileType::Stairway);
+		for (x_posn, ttype) in TILETYPES.iter().enumerate() {
+			let props = ttype.properties();
+			let scan = model.describe_tile(Position::new(x_posn as i32, 0, 0));
+			assert_eq!(scan[0], props.desc);
+			assert_eq!(scan[1], format!("SCAN: {} in an unmapped area, deck 0; {}, {}.", ttype,
+			                            if props.passable { "passable" } else { "impassable" },
+			                            if props.opaque { "opaque" } else { "transparent" }));
+		}
+		// Nothing useful comes back from off the edge of the map
+		assert_eq!(model.describe_tile(Position::new(9, 0, 0)), vec!["Your scan returns nothing but static.".to_string()]);
+	}
+	#[test]
+	fn only_walls_block_and_only_stairs_lead_anywhere() {
+		let table: Vec<(bool, bool, bool, Hazard)> = TILETYPES.iter()
+			.map(|x| x.properties())
+			.map(|x| (x.passable, x.opaque, x.is_portal, x.hazard))
+			.collect();
+		assert_eq!(table, vec![
+			(true,  false, false, Hazard::Vacuum), // Vacuum
+			(true,  false, false, Hazard::None),   // Floor
+			(false, true,  false, Hazard::None),   // Wall
+			(true,  false, true,  Hazard::None),   // Stairway
+		]);
+	}
+}
+
 // EOF